@@ -0,0 +1,122 @@
+"""DOMGuard for Python agent frameworks.
+
+Drives a local Chrome over the DevTools protocol through DOMGuard's Rust
+core, without spawning the CLI or parsing its JSON output::
+
+    import asyncio
+    import domguard
+
+    async def main():
+        browser = await domguard.connect()
+        await browser.navigate("https://example.com")
+        await browser.click("a")
+        shot = await browser.screenshot()
+        shot.save("page.png")
+
+    asyncio.run(main())
+
+Actions follow the project's ``.domguard`` settings as the CLI does: blocked
+sites, site rules and ``@aliases``, sensitive-action checks, action budgets
+and CAPTCHA takeovers. Failures, refusals included, raise ``DomGuardError``
+with DOMGuard's error message.
+"""
+
+from __future__ import annotations
+
+import json
+from dataclasses import dataclass
+from os import PathLike
+from typing import Any, Optional, Union
+
+from . import _domguard
+
+__version__ = _domguard.__version__
+
+__all__ = [
+    "Browser",
+    "ClickResult",
+    "DomGuardError",
+    "Screenshot",
+    "connect",
+]
+
+
+class DomGuardError(RuntimeError):
+    """A browser operation failed."""
+
+
+@dataclass(frozen=True)
+class ClickResult:
+    selector: str
+    #: How the click got past a covering element ("scroll", "corner", ...), if it had to
+    fallback: Optional[str] = None
+
+
+@dataclass(frozen=True)
+class Screenshot:
+    data: bytes
+    format: str = "png"
+
+    def save(self, path: Union[str, PathLike]) -> None:
+        with open(path, "wb") as f:
+            f.write(self.data)
+
+
+async def _call(awaitable: Any) -> Any:
+    try:
+        return await awaitable
+    except RuntimeError as e:
+        raise DomGuardError(str(e)) from e
+
+
+class Browser:
+    """A Chrome tab; get one from :func:`connect`."""
+
+    def __init__(self, native: Any) -> None:
+        self._native = native
+
+    async def navigate(self, url: str, confirm_sensitive: bool = False) -> str:
+        """Navigate and wait for load. Returns the final URL.
+
+        ``confirm_sensitive`` lets a sensitive action through under
+        ``on_sensitive = "confirm"``, like the CLI's ``--confirm-sensitive``.
+        """
+        return await _call(self._native.navigate(url, confirm_sensitive))
+
+    async def click(
+        self, selector: str, nth: int = 0, confirm_sensitive: bool = False
+    ) -> ClickResult:
+        """Click the nth element matching ``selector`` (-1 for the last)."""
+        fallback = await _call(self._native.click(selector, nth, confirm_sensitive))
+        return ClickResult(selector=selector, fallback=fallback)
+
+    async def type(self, selector: str, text: str, confirm_sensitive: bool = False) -> None:
+        """Type ``text`` into the element matching ``selector``."""
+        await _call(self._native.type(selector, text, confirm_sensitive))
+
+    async def screenshot(self, full_page: bool = False) -> Screenshot:
+        """PNG of the viewport, or of the whole page."""
+        return Screenshot(data=await _call(self._native.screenshot(full_page)))
+
+    async def eval(self, expression: str) -> Any:
+        """Evaluate JavaScript and return its JSON-compatible result.
+
+        Like ``domguard debug eval``, the script runs outside the action guards.
+        """
+        return json.loads(await _call(self._native.eval(expression)))
+
+    async def wait(self, selector: str, timeout_ms: int = 5000) -> None:
+        """Wait until an element matches ``selector``."""
+        await _call(self._native.wait(selector, timeout_ms))
+
+    async def url(self) -> str:
+        return await _call(self._native.url())
+
+
+async def connect(host: Optional[str] = None, port: Optional[int] = None) -> Browser:
+    """Connect to Chrome started with ``--remote-debugging-port``.
+
+    Settings come from the project's ``.domguard/config.toml`` (found from
+    the working directory); ``host`` and ``port`` override its ``[chrome]``.
+    """
+    return Browser(await _call(_domguard.connect(host, port)))
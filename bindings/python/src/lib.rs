@@ -0,0 +1,239 @@
+//! Python bindings for DOMGuard
+//!
+//! `_domguard` is the native half of the `domguard` Python package: an async
+//! `connect()` and a `Browser` whose methods return awaitables.
+//! `python/domguard/__init__.py` wraps them in typed results. Evaluation
+//! results cross over as JSON text, so Python gets plain dicts and lists.
+//!
+//! Actions go through `run_interact`, so the project's `.domguard` settings
+//! apply as they do to the CLI: blocked sites, site rules and aliases,
+//! sensitive-action checks, action budgets and CAPTCHA takeovers.
+
+use std::sync::Arc;
+
+use domguard::backend::{CaptureOptions, ImageFormat};
+use domguard::cdp::WaitUntil;
+use domguard::interact::{screen, CoordSpace, GuardOverrides};
+use domguard::{run_interact, CdpConnection, Config, Formatter, InteractCommand};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use pyo3_async_runtimes::tokio::future_into_py;
+
+fn py_err(e: &anyhow::Error) -> PyErr {
+    PyRuntimeError::new_err(e.to_string())
+}
+
+/// A Chrome tab controlled over CDP
+#[pyclass(module = "domguard._domguard")]
+struct Browser {
+    cdp: Arc<CdpConnection>,
+    config: Arc<Config>,
+}
+
+/// Run an interact command past the project's guards; resolves to its
+/// result's `data`
+async fn interact(
+    cdp: &CdpConnection,
+    config: &Config,
+    command: InteractCommand,
+    confirm_sensitive: bool,
+) -> PyResult<serde_json::Value> {
+    let overrides = GuardOverrides {
+        confirm_sensitive,
+        ..GuardOverrides::default()
+    };
+    let command = screen(config, command, overrides).map_err(|e| py_err(&e))?;
+    let formatter = Formatter::new(true).capturing();
+    run_interact(cdp, config, command, None, &formatter)
+        .await
+        .map_err(|e| py_err(&e))?;
+    Ok(formatter
+        .take_captured()
+        .pop()
+        .and_then(|document| serde_json::from_str::<serde_json::Value>(&document).ok())
+        .map(|mut document| document["data"].take())
+        .unwrap_or_default())
+}
+
+#[pymethods]
+impl Browser {
+    /// Navigate and wait for load; resolves to the final URL
+    #[pyo3(signature = (url, confirm_sensitive = false))]
+    fn navigate<'py>(
+        &self,
+        py: Python<'py>,
+        url: String,
+        confirm_sensitive: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let (cdp, config) = (Arc::clone(&self.cdp), Arc::clone(&self.config));
+        future_into_py(py, async move {
+            let command = InteractCommand::Navigate {
+                url,
+                credential: None,
+                wait_until: WaitUntil::Load,
+                referrer: None,
+                timeout_ms: config.defaults.timeout_ms,
+            };
+            interact(&cdp, &config, command, confirm_sensitive).await?;
+            cdp.current_url().await.map_err(|e| py_err(&e))
+        })
+    }
+
+    /// Click the nth match of a selector; resolves to the fallback used when
+    /// the centre was covered, or None
+    #[pyo3(signature = (selector, nth = 0, confirm_sensitive = false))]
+    fn click<'py>(
+        &self,
+        py: Python<'py>,
+        selector: String,
+        nth: i32,
+        confirm_sensitive: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let (cdp, config) = (Arc::clone(&self.cdp), Arc::clone(&self.config));
+        future_into_py(py, async move {
+            let command = InteractCommand::Click {
+                selector: Some(selector),
+                coords: None,
+                space: CoordSpace::default(),
+                nth,
+                text: None,
+                then: None,
+            };
+            let data = interact(&cdp, &config, command, confirm_sensitive).await?;
+            Ok(data["details"].as_str().and_then(|details| {
+                details
+                    .split(", ")
+                    .find_map(|detail| detail.strip_prefix("fallback: "))
+                    .map(str::to_string)
+            }))
+        })
+    }
+
+    /// Type text into an element
+    #[pyo3(name = "type", signature = (selector, text, confirm_sensitive = false))]
+    fn type_into<'py>(
+        &self,
+        py: Python<'py>,
+        selector: String,
+        text: String,
+        confirm_sensitive: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let (cdp, config) = (Arc::clone(&self.cdp), Arc::clone(&self.config));
+        future_into_py(py, async move {
+            let command = InteractCommand::Type {
+                selector: Some(selector),
+                text: Some(text),
+                focused: false,
+                keystroke_delay: None,
+                framework: None,
+            };
+            interact(&cdp, &config, command, confirm_sensitive)
+                .await
+                .map(|_| ())
+        })
+    }
+
+    /// PNG screenshot of the viewport, or of the whole page
+    #[pyo3(signature = (full_page = false))]
+    fn screenshot<'py>(&self, py: Python<'py>, full_page: bool) -> PyResult<Bound<'py, PyAny>> {
+        let cdp = Arc::clone(&self.cdp);
+        future_into_py(py, async move {
+            let data = cdp
+                .capture(&CaptureOptions {
+                    format: ImageFormat::Png,
+                    quality: None,
+                    full_page,
+                    clip: None,
+                })
+                .await
+                .map_err(|e| py_err(&e))?;
+            Ok(Python::with_gil(|py| {
+                PyBytes::new_bound(py, &data).unbind()
+            }))
+        })
+    }
+
+    /// Evaluate JavaScript; resolves to the result as JSON text
+    ///
+    /// Like the CLI's `debug eval`, this runs as given, outside the action guards.
+    fn eval<'py>(&self, py: Python<'py>, expression: String) -> PyResult<Bound<'py, PyAny>> {
+        let cdp = Arc::clone(&self.cdp);
+        future_into_py(py, async move {
+            let value = cdp.evaluate(&expression).await.map_err(|e| py_err(&e))?;
+            Ok(value.to_string())
+        })
+    }
+
+    /// Wait for an element matching a selector
+    #[pyo3(signature = (selector, timeout_ms = 5000))]
+    fn wait<'py>(
+        &self,
+        py: Python<'py>,
+        selector: String,
+        timeout_ms: u64,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let (cdp, config) = (Arc::clone(&self.cdp), Arc::clone(&self.config));
+        future_into_py(py, async move {
+            let command = InteractCommand::Wait {
+                selector,
+                visible: false,
+                gone: false,
+                timeout_ms,
+                text: None,
+                text_gone: None,
+                page: None,
+                state: None,
+            };
+            interact(&cdp, &config, command, false).await.map(|_| ())
+        })
+    }
+
+    /// URL of the page
+    fn url<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let cdp = Arc::clone(&self.cdp);
+        future_into_py(py, async move {
+            cdp.current_url().await.map_err(|e| py_err(&e))
+        })
+    }
+}
+
+/// Connect to Chrome's remote debugging port
+///
+/// Settings come from the project's `.domguard/config.toml`, found from the
+/// working directory as the CLI finds it; `host` and `port` override its
+/// `[chrome]` section. Remote hosts need `allow_remote` there.
+#[pyfunction]
+#[pyo3(signature = (host = None, port = None))]
+fn connect(py: Python<'_>, host: Option<String>, port: Option<u16>) -> PyResult<Bound<'_, PyAny>> {
+    future_into_py(py, async move {
+        let mut config = Config::load().map_err(|e| py_err(&e))?;
+        if let Some(host) = host {
+            config.chrome.host = host;
+        }
+        if let Some(port) = port {
+            config.chrome.port = port;
+        }
+        let mut cdp = CdpConnection::new(config.clone());
+        cdp.connect().await.map_err(|e| py_err(&e))?;
+        // Network settings and init scripts apply after each connection, as in the CLI
+        domguard::network::apply_settings(&cdp)
+            .await
+            .map_err(|e| py_err(&e))?;
+        domguard::debug::InitScripts::apply(&cdp)
+            .await
+            .map_err(|e| py_err(&e))?;
+        Ok(Browser {
+            cdp: Arc::new(cdp),
+            config: Arc::new(config),
+        })
+    })
+}
+
+#[pymodule]
+fn _domguard(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add_class::<Browser>()?;
+    m.add_function(wrap_pyfunction!(connect, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,535 @@
+//! DOMGuard gRPC server
+//!
+//! Serves `proto/domguard.proto`; started by `domguard serve --grpc <addr>`.
+//! Every call runs the `domguard` binary with `--json`, so calls behave
+//! exactly like the CLI (config, site policies, sessions, budgets) and one
+//! failing call can't take the server down. Streams run `debug console
+//! --follow` or `debug xhr --follow` with `--ndjson` and forward each line;
+//! a client that reads slowly leaves the pipe full, which holds that
+//! `domguard` back instead of queueing events here.
+
+use clap::Parser;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+mod proto {
+    tonic::include_proto!("domguard.v1");
+}
+
+use proto::dom_guard_server::{DomGuard, DomGuardServer};
+use proto::{
+    CommandRequest, CommandResult, ConsoleEvent, NetworkEvent, StreamRequest, WorkflowRequest,
+};
+
+/// Events a stream holds for a slow client before `domguard` is held back
+const STREAM_BUFFER: usize = 64;
+
+/// Global flags the server sets itself, so requests can't change them
+const RESERVED_OPTIONS: &[&str] = &[
+    "json",
+    "ndjson",
+    "format",
+    "output-file",
+    "quiet",
+    "profile",
+    "host",
+    "port",
+    "allow-remote",
+];
+
+#[derive(Parser)]
+#[command(name = "domguard-grpc", version, about = "gRPC server for DOMGuard")]
+struct Args {
+    /// Address to listen on
+    #[arg(long, value_name = "ADDR")]
+    listen: SocketAddr,
+
+    /// The domguard binary to run (default: domguard in PATH)
+    #[arg(long, default_value = "domguard")]
+    domguard: PathBuf,
+
+    /// Config profile for every call
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Chrome DevTools host for every call
+    #[arg(long)]
+    host: Option<String>,
+
+    /// Chrome DevTools port for every call
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// Allow a non-localhost Chrome host
+    #[arg(long)]
+    allow_remote: bool,
+}
+
+/// Runs `domguard` with the server's connection flags
+struct Runner {
+    domguard: PathBuf,
+    globals: Vec<String>,
+}
+
+impl Runner {
+    fn new(args: &Args) -> Self {
+        let mut globals = Vec::new();
+        if let Some(profile) = &args.profile {
+            globals.extend(["--profile".to_string(), profile.clone()]);
+        }
+        if let Some(host) = &args.host {
+            globals.extend(["--host".to_string(), host.clone()]);
+        }
+        if let Some(port) = args.port {
+            globals.extend(["--port".to_string(), port.to_string()]);
+        }
+        if args.allow_remote {
+            globals.push("--allow-remote".to_string());
+        }
+        Self {
+            domguard: args.domguard.clone(),
+            globals,
+        }
+    }
+
+    fn command(&self, args: &[String]) -> Command {
+        let mut command = Command::new(&self.domguard);
+        command
+            .args(&self.globals)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // A cancelled call stops its domguard
+            .kill_on_drop(true);
+        command
+    }
+
+    fn spawn_error(&self, error: &std::io::Error) -> Status {
+        Status::internal(format!(
+            "Failed to run {}: {}",
+            self.domguard.display(),
+            error
+        ))
+    }
+
+    /// Run one command to completion
+    async fn run(&self, args: &[String]) -> Result<CommandResult, Status> {
+        let mut full = vec!["--json".to_string()];
+        full.extend_from_slice(args);
+        let started = Instant::now();
+        let output = self
+            .command(&full)
+            .output()
+            .await
+            .map_err(|e| self.spawn_error(&e))?;
+        Ok(command_result(
+            output.status.success(),
+            &String::from_utf8_lossy(&output.stdout),
+            &String::from_utf8_lossy(&output.stderr),
+            started.elapsed(),
+        ))
+    }
+
+    /// Run a following command, sending each event `parse` accepts until
+    /// it exits or the client goes away
+    fn stream<T: Send + 'static>(
+        &self,
+        args: &[String],
+        parse: fn(&Value) -> Option<T>,
+    ) -> Result<ReceiverStream<Result<T, Status>>, Status> {
+        let mut full = vec!["--json".to_string(), "--ndjson".to_string()];
+        full.extend_from_slice(args);
+        let mut child = self
+            .command(&full)
+            .spawn()
+            .map_err(|e| self.spawn_error(&e))?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        // Read alongside stdout so warnings can't fill their pipe
+        let stderr = tokio::spawn(async move {
+            let mut text = String::new();
+            let _ = stderr.read_to_string(&mut text).await;
+            text
+        });
+
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            loop {
+                let line = tokio::select! {
+                    line = lines.next_line() => line,
+                    // Dropping the child stops domguard
+                    () = tx.closed() => return,
+                };
+                let line = match line {
+                    Ok(Some(line)) => line,
+                    Ok(None) => break,
+                    Err(e) => {
+                        let _ = tx.send(Err(Status::internal(e.to_string()))).await;
+                        return;
+                    }
+                };
+                let Ok(value) = serde_json::from_str::<Value>(&line) else {
+                    continue;
+                };
+                // The command failed (e.g. no browser) and printed its error
+                if value.get("success") == Some(&Value::Bool(false)) {
+                    let error = value["error"].as_str().unwrap_or("domguard failed");
+                    let _ = tx.send(Err(Status::unavailable(error))).await;
+                    return;
+                }
+                // Anything else, such as the closing summary, isn't an event
+                let Some(event) = parse(&value) else {
+                    continue;
+                };
+                if tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+
+            match child.wait().await {
+                Ok(status) if status.success() => {}
+                Ok(status) => {
+                    let stderr = stderr.await.unwrap_or_default();
+                    let _ = tx.send(Err(failure(status, &stderr))).await;
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(Status::internal(e.to_string()))).await;
+                }
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+}
+
+fn failure(status: std::process::ExitStatus, stderr: &str) -> Status {
+    match stderr.trim() {
+        "" => Status::unavailable(format!("domguard exited with {}", status)),
+        stderr => Status::unavailable(stderr),
+    }
+}
+
+/// Map a command's JSON output to a result
+///
+/// Most commands print a `{success, data, error, timing_ms}` document; the
+/// rest print their data alone, which becomes `data_json` as is (or as a
+/// JSON string when it isn't JSON).
+fn command_result(success: bool, stdout: &str, stderr: &str, elapsed: Duration) -> CommandResult {
+    let elapsed_ms = elapsed.as_millis() as u64;
+    let parsed = serde_json::from_str::<Value>(stdout.trim()).ok();
+    if let Some(Value::Object(result)) = &parsed {
+        if let Some(ok) = result.get("success").and_then(Value::as_bool) {
+            return CommandResult {
+                success: ok && success,
+                data_json: result.get("data").map(Value::to_string).unwrap_or_default(),
+                error: match result.get("error").and_then(Value::as_str) {
+                    Some(error) => error.to_string(),
+                    None if !success => stderr.trim().to_string(),
+                    None => String::new(),
+                },
+                timing_ms: result
+                    .get("timing_ms")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(elapsed_ms),
+            };
+        }
+    }
+    let error = match stderr.trim() {
+        _ if success => String::new(),
+        "" => "domguard failed".to_string(),
+        stderr => stderr.to_string(),
+    };
+    CommandResult {
+        success,
+        data_json: match parsed {
+            Some(value) => value.to_string(),
+            None if stdout.trim().is_empty() => String::new(),
+            None => Value::String(stdout.trim().to_string()).to_string(),
+        },
+        error,
+        timing_ms: elapsed_ms,
+    }
+}
+
+/// `<group> <command> <args>` followed by the request's options as flags
+///
+/// An option `"timeout": "5000"` becomes `--timeout 5000`, `"quiet": "true"`
+/// (or an empty value) becomes `--quiet`, and `"false"` leaves it out.
+/// Flags only come from options, so the reserved ones can't be slipped in
+/// through `args`.
+fn command_args(group: &str, request: CommandRequest) -> Result<Vec<String>, Status> {
+    if request.command.is_empty() || request.command.starts_with('-') {
+        return Err(Status::invalid_argument(format!(
+            "command must be a {} subcommand, e.g. \"click\"",
+            group
+        )));
+    }
+    if let Some(flag) = request.args.iter().find(|arg| arg.starts_with('-')) {
+        return Err(Status::invalid_argument(format!(
+            "args are positional; pass {} through options",
+            flag
+        )));
+    }
+    let mut args = vec![group.to_string(), request.command];
+    args.extend(request.args);
+    args.extend(option_flags(&request.options)?);
+    Ok(args)
+}
+
+fn option_flags(options: &HashMap<String, String>) -> Result<Vec<String>, Status> {
+    let mut names: Vec<_> = options.keys().collect();
+    names.sort();
+    let mut flags = Vec::new();
+    for name in names {
+        let flag = name.replace('_', "-");
+        let valid = flag.starts_with(|c: char| c.is_ascii_lowercase())
+            && flag
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !valid {
+            return Err(Status::invalid_argument(format!(
+                "Invalid option name: {}",
+                name
+            )));
+        }
+        if RESERVED_OPTIONS.contains(&flag.as_str()) {
+            return Err(Status::invalid_argument(format!(
+                "Option {} is set by the server",
+                name
+            )));
+        }
+        match options[name].as_str() {
+            "false" => {}
+            "" | "true" => flags.push(format!("--{}", flag)),
+            value => flags.extend([format!("--{}", flag), value.to_string()]),
+        }
+    }
+    Ok(flags)
+}
+
+fn console_event(value: &Value) -> Option<ConsoleEvent> {
+    Some(ConsoleEvent {
+        level: value.get("level")?.as_str()?.to_string(),
+        text: value.get("text")?.as_str()?.to_string(),
+        url: value["url"].as_str().unwrap_or_default().to_string(),
+        line: value["line"].as_u64().unwrap_or_default() as u32,
+        // Console messages carry no time of their own on the CLI
+        timestamp_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as i64),
+    })
+}
+
+fn network_event(value: &Value) -> Option<NetworkEvent> {
+    Some(NetworkEvent {
+        method: value.get("method")?.as_str()?.to_string(),
+        url: value.get("url")?.as_str()?.to_string(),
+        status: value["status"].as_i64().unwrap_or_default() as i32,
+        resource_type: value["kind"].as_str().unwrap_or_default().to_string(),
+        size_bytes: value["response_bytes"].as_u64().unwrap_or_default(),
+        duration_ms: value["duration_ms"].as_f64().unwrap_or_default(),
+    })
+}
+
+struct Service {
+    runner: Runner,
+}
+
+#[tonic::async_trait]
+impl DomGuard for Service {
+    async fn interact(
+        &self,
+        request: Request<CommandRequest>,
+    ) -> Result<Response<CommandResult>, Status> {
+        let args = command_args("interact", request.into_inner())?;
+        self.runner.run(&args).await.map(Response::new)
+    }
+
+    async fn debug(
+        &self,
+        request: Request<CommandRequest>,
+    ) -> Result<Response<CommandResult>, Status> {
+        let args = command_args("debug", request.into_inner())?;
+        self.runner.run(&args).await.map(Response::new)
+    }
+
+    async fn session(
+        &self,
+        request: Request<CommandRequest>,
+    ) -> Result<Response<CommandResult>, Status> {
+        let args = command_args("session", request.into_inner())?;
+        self.runner.run(&args).await.map(Response::new)
+    }
+
+    async fn run_workflow(
+        &self,
+        request: Request<WorkflowRequest>,
+    ) -> Result<Response<CommandResult>, Status> {
+        let request = request.into_inner();
+        if request.workflow.is_empty() {
+            return Err(Status::invalid_argument("workflow is required"));
+        }
+        let mut args = vec!["workflow".to_string(), "run".to_string(), request.workflow];
+        let mut params: Vec<_> = request.params.into_iter().collect();
+        params.sort();
+        for (name, value) in params {
+            args.extend(["--param".to_string(), format!("{}={}", name, value)]);
+        }
+        self.runner.run(&args).await.map(Response::new)
+    }
+
+    type StreamConsoleStream = ReceiverStream<Result<ConsoleEvent, Status>>;
+
+    async fn stream_console(
+        &self,
+        request: Request<StreamRequest>,
+    ) -> Result<Response<Self::StreamConsoleStream>, Status> {
+        let mut args = vec![
+            "debug".to_string(),
+            "console".to_string(),
+            "--follow".to_string(),
+        ];
+        let filter = request.into_inner().filter;
+        if !filter.is_empty() {
+            args.extend(["--filter".to_string(), filter]);
+        }
+        self.runner.stream(&args, console_event).map(Response::new)
+    }
+
+    type StreamNetworkStream = ReceiverStream<Result<NetworkEvent, Status>>;
+
+    async fn stream_network(
+        &self,
+        request: Request<StreamRequest>,
+    ) -> Result<Response<Self::StreamNetworkStream>, Status> {
+        let mut args = vec![
+            "debug".to_string(),
+            "xhr".to_string(),
+            "--follow".to_string(),
+        ];
+        let filter = request.into_inner().filter;
+        if !filter.is_empty() {
+            args.extend(["--url-filter".to_string(), filter]);
+        }
+        self.runner.stream(&args, network_event).map(Response::new)
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let service = Service {
+        runner: Runner::new(&args),
+    };
+    tonic::transport::Server::builder()
+        .add_service(DomGuardServer::new(service))
+        .serve_with_shutdown(args.listen, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_args() {
+        let request = CommandRequest {
+            command: "click".to_string(),
+            args: vec!["#submit".to_string()],
+            options: HashMap::from([
+                ("timeout".to_string(), "5000".to_string()),
+                ("confirm_sensitive".to_string(), "true".to_string()),
+                ("record".to_string(), "false".to_string()),
+            ]),
+        };
+        assert_eq!(
+            command_args("interact", request).unwrap(),
+            [
+                "interact",
+                "click",
+                "#submit",
+                "--confirm-sensitive",
+                "--timeout",
+                "5000"
+            ]
+        );
+
+        let reserved = CommandRequest {
+            command: "click".to_string(),
+            options: HashMap::from([("host".to_string(), "10.0.0.5".to_string())]),
+            ..CommandRequest::default()
+        };
+        assert!(command_args("interact", reserved).is_err());
+        let smuggled = CommandRequest {
+            command: "click".to_string(),
+            args: vec!["#submit".to_string(), "--port=9333".to_string()],
+            ..CommandRequest::default()
+        };
+        assert!(command_args("interact", smuggled).is_err());
+        let flag = CommandRequest {
+            command: "--help".to_string(),
+            ..CommandRequest::default()
+        };
+        assert!(command_args("interact", flag).is_err());
+    }
+
+    #[test]
+    fn test_command_result() {
+        let elapsed = Duration::from_millis(40);
+        let wrapped = command_result(
+            true,
+            r##"{"success": true, "data": {"selector": "#submit"}, "timing_ms": 12}"##,
+            "",
+            elapsed,
+        );
+        assert!(wrapped.success);
+        assert_eq!(wrapped.data_json, r##"{"selector":"#submit"}"##);
+        assert_eq!(wrapped.timing_ms, 12);
+
+        let failed = command_result(
+            false,
+            r#"{"success": false, "error": "Element not found: #nope"}"#,
+            "",
+            elapsed,
+        );
+        assert!(!failed.success);
+        assert_eq!(failed.error, "Element not found: #nope");
+
+        let bare = command_result(true, "[1, 2]", "", elapsed);
+        assert_eq!((bare.data_json.as_str(), bare.timing_ms), ("[1,2]", 40));
+        let crashed = command_result(false, "", "error: unexpected argument\n", elapsed);
+        assert_eq!(crashed.error, "error: unexpected argument");
+    }
+
+    #[test]
+    fn test_stream_events() {
+        let console: Value =
+            serde_json::from_str(r#"{"level": "error", "text": "boom", "line": 3}"#).unwrap();
+        let event = console_event(&console).unwrap();
+        assert_eq!((event.level.as_str(), event.line), ("error", 3));
+        let summary: Value = serde_json::from_str(r#"{"summary": {"total": 1}}"#).unwrap();
+        assert!(console_event(&summary).is_none());
+
+        let call: Value = serde_json::from_str(
+            r#"{"kind": "fetch", "method": "GET", "url": "https://shop.test/api", "status": 200, "response_bytes": 512, "duration_ms": 8}"#,
+        )
+        .unwrap();
+        let event = network_event(&call).unwrap();
+        assert_eq!((event.status, event.size_bytes), (200, 512));
+        assert_eq!(event.resource_type, "fetch");
+    }
+}
@@ -0,0 +1,275 @@
+//! Saved logins for `domguard auth`
+//!
+//! `auth login` runs a site's login workflow and keeps the resulting cookies
+//! and storage in `.domguard/auth/<domain>.json`; `auth restore` puts them back
+//! so later runs start signed in. The files hold live session tokens, so they
+//! are readable only by the user.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::cdp::{CdpConnection, SavedCookie};
+use crate::config::Config;
+use crate::runner::{RunOptions, StepProgress};
+use crate::site_instructions::{LoginInstructions, SiteInstructionsManager};
+use crate::workflow::Workflow;
+
+/// Cookies and storage captured after a login
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthState {
+    pub domain: String,
+    pub saved_at: chrono::DateTime<chrono::Utc>,
+    /// Page the login ended on; storage is restored on its origin
+    pub url: String,
+    pub cookies: Vec<SavedCookie>,
+    #[serde(default)]
+    pub local_storage: Vec<(String, String)>,
+    #[serde(default)]
+    pub session_storage: Vec<(String, String)>,
+}
+
+impl AuthState {
+    /// File for `domain` under `dir` (`.domguard/auth`)
+    pub fn path(dir: &Path, domain: &str) -> PathBuf {
+        dir.join(format!("{}.json", domain.replace(['*', '/', ':'], "_")))
+    }
+
+    /// The saved login for `domain`
+    pub fn load(dir: &Path, domain: &str) -> Result<Self> {
+        let path = Self::path(dir, domain);
+        if !path.exists() {
+            return Err(anyhow!(
+                "No saved login for {} (run 'domguard auth login {}')",
+                domain,
+                domain
+            ));
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    /// Write the login to `dir`, readable only by the user
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = Self::path(dir, &self.domain);
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+
+        Ok(path)
+    }
+}
+
+/// The project's `.domguard/auth`
+pub fn auth_dir() -> PathBuf {
+    Config::find_domguard_dir()
+        .unwrap_or_else(Config::domguard_dir)
+        .join("auth")
+}
+
+/// The `[login]` instructions for `domain` and the workflow they name
+pub fn login_workflow(domain: &str) -> Result<(LoginInstructions, Workflow)> {
+    let login = SiteInstructionsManager::open()?
+        .get_for_domain(domain)
+        .and_then(|site| site.login.clone())
+        .ok_or_else(|| {
+            anyhow!(
+                "No [login] section in the site instructions for {} (see 'domguard sites edit {}')",
+                domain,
+                domain
+            )
+        })?;
+    let workflow_id = login.workflow.as_deref().ok_or_else(|| {
+        anyhow!(
+            "No login workflow for {}; set workflow = \"<id>\" under [login] in its site instructions",
+            domain
+        )
+    })?;
+    let workflow = crate::runner::WorkflowStore::open()?
+        .find(workflow_id)
+        .ok_or_else(|| anyhow!("Login workflow not found: {}", workflow_id))?
+        .clone();
+    Ok((login, workflow))
+}
+
+/// What `auth login` saved
+#[derive(Debug)]
+pub struct SavedLogin {
+    pub state: AuthState,
+    pub path: PathBuf,
+    /// False without a `success_indicator` to confirm the login worked
+    pub verified: bool,
+}
+
+/// Run the login workflow, check the page shows the login worked, and save
+/// the cookies and storage for `domain`
+pub async fn login(
+    cdp: &mut CdpConnection,
+    domain: &str,
+    login: &LoginInstructions,
+    workflow: &Workflow,
+    params: &HashMap<String, String>,
+    session: Option<&str>,
+    on_step: &mut dyn FnMut(&StepProgress),
+) -> Result<SavedLogin> {
+    cdp.connect().await?;
+    crate::network::apply_settings(cdp).await?;
+    crate::debug::InitScripts::apply(cdp).await?;
+    crate::interact::DialogPolicy::apply(cdp, session).await?;
+
+    let result =
+        crate::runner::execute_workflow(cdp, workflow, params, &RunOptions::default(), on_step)
+            .await?;
+    if !result.success {
+        anyhow::bail!("Login workflow {} failed", workflow.name);
+    }
+
+    // Verify the login with the checks from the site instructions
+    if let Some(failure) = &login.failure_indicator {
+        let failed = cdp
+            .evaluate(&format!(
+                "!!document.querySelector({})",
+                serde_json::to_string(failure)?
+            ))
+            .await?;
+        if failed.as_bool() == Some(true) {
+            anyhow::bail!("Login failed: the page shows \"{}\"", failure);
+        }
+    }
+    if let Some(success) = &login.success_indicator {
+        cdp.wait_for(success, cdp.config().defaults.timeout_ms)
+            .await
+            .map_err(|e| anyhow!("Login not confirmed: {}", e))?;
+    }
+
+    let cookies: Vec<_> = cdp
+        .all_cookies()
+        .await?
+        .into_iter()
+        .filter(|cookie| cookie_for_domain(&cookie.domain, domain))
+        .collect();
+    let state = AuthState {
+        domain: domain.to_string(),
+        saved_at: chrono::Utc::now(),
+        url: cdp.current_url().await?,
+        cookies,
+        local_storage: parse_storage_entries(&cdp.get_local_storage().await?),
+        session_storage: parse_storage_entries(&cdp.get_session_storage().await?),
+    };
+    let path = state.save(&auth_dir())?;
+    Ok(SavedLogin {
+        state,
+        path,
+        verified: login.success_indicator.is_some(),
+    })
+}
+
+/// Put the saved login for `domain` back: its cookies, then its page and storage
+pub async fn restore(cdp: &mut CdpConnection, domain: &str) -> Result<AuthState> {
+    let state = AuthState::load(&auth_dir(), domain)?;
+
+    cdp.connect().await?;
+    crate::network::apply_settings(cdp).await?;
+    crate::security::enforce_site_policy(cdp.config(), "auth restore", &state.url, false)?;
+
+    cdp.set_saved_cookies(&state.cookies).await?;
+    cdp.navigate(&state.url).await?;
+    // Storage belongs to the origin, so it goes in once the page is there
+    if !state.local_storage.is_empty() || !state.session_storage.is_empty() {
+        cdp.evaluate(&restore_storage_script(
+            &state.local_storage,
+            &state.session_storage,
+        ))
+        .await?;
+        cdp.refresh().await?;
+    }
+    Ok(state)
+}
+
+/// Whether a cookie set for `cookie_domain` belongs to a login on `domain`
+///
+/// Keeps the domain's own and its subdomains' cookies, plus parent-domain
+/// cookies it would be sent (`.example.com` for `app.example.com`).
+pub fn cookie_for_domain(cookie_domain: &str, domain: &str) -> bool {
+    let cookie_domain = cookie_domain.trim_start_matches('.').to_lowercase();
+    let domain = domain.trim_start_matches("*.").to_lowercase();
+    cookie_domain == domain
+        || cookie_domain.ends_with(&format!(".{}", domain))
+        || domain.ends_with(&format!(".{}", cookie_domain))
+}
+
+/// Script that writes saved entries into localStorage and sessionStorage
+pub fn restore_storage_script(local: &[(String, String)], session: &[(String, String)]) -> String {
+    format!(
+        r"(function() {{
+            for (const [key, value] of {}) localStorage.setItem(key, value);
+            for (const [key, value] of {}) sessionStorage.setItem(key, value);
+            return true;
+        }})()",
+        serde_json::json!(local),
+        serde_json::json!(session)
+    )
+}
+
+/// Parse the `[[key, value], ...]` JSON string storage reads return
+pub fn parse_storage_entries(value: &serde_json::Value) -> Vec<(String, String)> {
+    value
+        .as_str()
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cookie_for_domain() {
+        assert!(cookie_for_domain(".example.com", "example.com"));
+        assert!(cookie_for_domain("app.example.com", "example.com"));
+        assert!(cookie_for_domain(".example.com", "app.example.com"));
+        assert!(!cookie_for_domain("other.com", "example.com"));
+        assert!(!cookie_for_domain("notexample.com", "example.com"));
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AuthState {
+            domain: "shop.example".to_string(),
+            saved_at: chrono::Utc::now(),
+            url: "https://shop.example/account".to_string(),
+            cookies: vec![SavedCookie {
+                name: "sid".to_string(),
+                value: "abc".to_string(),
+                domain: ".shop.example".to_string(),
+                path: "/".to_string(),
+                secure: true,
+                http_only: true,
+                same_site: Some("Lax".to_string()),
+                expires: None,
+            }],
+            local_storage: vec![("token".to_string(), "xyz".to_string())],
+            session_storage: Vec::new(),
+        };
+        let path = state.save(dir.path()).unwrap();
+        assert!(path.ends_with("shop.example.json"));
+
+        let loaded = AuthState::load(dir.path(), "shop.example").unwrap();
+        assert_eq!(loaded.cookies, state.cookies);
+        assert_eq!(loaded.local_storage, state.local_storage);
+        assert!(AuthState::load(dir.path(), "other.example").is_err());
+        assert_eq!(
+            parse_storage_entries(&serde_json::json!(r#"[["a","1"]]"#)),
+            vec![("a".to_string(), "1".to_string())]
+        );
+    }
+}
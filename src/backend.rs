@@ -0,0 +1,298 @@
+//! Browser backends for DOMGuard
+//!
+//! The core commands (navigate, click, type, screenshot, eval) go through
+//! `BrowserBackend`, so they run the same way against Chrome over CDP and,
+//! experimentally, Firefox over WebDriver BiDi (see `bidi.rs`). Everything
+//! else still needs Chrome.
+
+use anyhow::{anyhow, Result};
+use futures::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::cdp::CdpConnection;
+
+/// Which browser protocol to speak
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// Chrome/Chromium over the DevTools Protocol
+    #[default]
+    Chrome,
+    /// Firefox over WebDriver BiDi (experimental)
+    Firefox,
+}
+
+impl std::fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Chrome => write!(f, "chrome"),
+            Self::Firefox => write!(f, "firefox"),
+        }
+    }
+}
+
+impl std::str::FromStr for BackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chrome" | "chromium" => Ok(Self::Chrome),
+            "firefox" => Ok(Self::Firefox),
+            other => Err(format!(
+                "Unknown browser \"{}\" (expected chrome or firefox)",
+                other
+            )),
+        }
+    }
+}
+
+/// Screenshot image format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl std::fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Png => write!(f, "png"),
+            Self::Jpeg => write!(f, "jpeg"),
+            Self::Webp => write!(f, "webp"),
+        }
+    }
+}
+
+impl std::str::FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "png" => Ok(Self::Png),
+            "jpeg" | "jpg" => Ok(Self::Jpeg),
+            "webp" => Ok(Self::Webp),
+            other => Err(format!(
+                "Unknown image format \"{}\" (expected png, jpeg or webp)",
+                other
+            )),
+        }
+    }
+}
+
+impl ImageFormat {
+    /// File extension for this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+
+    /// MIME type for this format
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+        }
+    }
+}
+
+/// A rectangle in page (document) CSS pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// What a screenshot covers and how it is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CaptureOptions {
+    pub format: ImageFormat,
+    /// 0-100, for jpeg and webp
+    pub quality: Option<u8>,
+    /// Beyond the viewport (ignored with a clip)
+    pub full_page: bool,
+    pub clip: Option<ClipRect>,
+}
+
+/// The browser operations behind the core commands
+///
+/// `click` and `type_into` have script-based defaults, so a backend only has
+/// to provide navigation, evaluation and screenshots.
+pub trait BrowserBackend {
+    /// Backend name for messages
+    fn name(&self) -> &'static str;
+
+    /// Navigate and wait for the page to load
+    fn navigate<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<()>>;
+
+    /// Evaluate a JavaScript expression (promises are awaited) and return its value
+    fn evaluate<'a>(&'a self, expression: &'a str)
+        -> LocalBoxFuture<'a, Result<serde_json::Value>>;
+
+    /// Capture a PNG of the viewport, or of the whole page
+    fn screenshot(&self, full_page: bool) -> LocalBoxFuture<'_, Result<Vec<u8>>>;
+
+    /// Capture a screenshot with a format, quality or clip
+    fn capture<'a>(&'a self, options: &'a CaptureOptions) -> LocalBoxFuture<'a, Result<Vec<u8>>> {
+        Box::pin(async move {
+            if options.format != ImageFormat::Png || options.clip.is_some() {
+                return Err(anyhow!(
+                    "The {} backend only captures plain PNG screenshots",
+                    self.name()
+                ));
+            }
+            self.screenshot(options.full_page).await
+        })
+    }
+
+    /// Click the first element matching `selector`
+    fn click<'a>(&'a self, selector: &'a str) -> LocalBoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let clicked = self.evaluate(&click_script(selector)).await?;
+            if clicked.as_bool() != Some(true) {
+                return Err(anyhow!("No element matches selector \"{}\"", selector));
+            }
+            Ok(())
+        })
+    }
+
+    /// Set the value of the first element matching `selector`, firing input/change events
+    fn type_into<'a>(&'a self, selector: &'a str, text: &'a str) -> LocalBoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let typed = self.evaluate(&type_script(selector, text)).await?;
+            if typed.as_bool() != Some(true) {
+                return Err(anyhow!("No element matches selector \"{}\"", selector));
+            }
+            Ok(())
+        })
+    }
+
+    /// URL of the current page
+    fn current_url(&self) -> LocalBoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            self.evaluate("window.location.href")
+                .await?
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("Failed to get current URL"))
+        })
+    }
+}
+
+impl BrowserBackend for CdpConnection {
+    fn name(&self) -> &'static str {
+        "chrome"
+    }
+
+    fn navigate<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<()>> {
+        Box::pin(CdpConnection::navigate(self, url))
+    }
+
+    fn evaluate<'a>(
+        &'a self,
+        expression: &'a str,
+    ) -> LocalBoxFuture<'a, Result<serde_json::Value>> {
+        Box::pin(CdpConnection::evaluate(self, expression))
+    }
+
+    fn screenshot(&self, full_page: bool) -> LocalBoxFuture<'_, Result<Vec<u8>>> {
+        Box::pin(CdpConnection::screenshot(self, full_page))
+    }
+
+    fn capture<'a>(&'a self, options: &'a CaptureOptions) -> LocalBoxFuture<'a, Result<Vec<u8>>> {
+        Box::pin(CdpConnection::capture(self, options))
+    }
+
+    fn click<'a>(&'a self, selector: &'a str) -> LocalBoxFuture<'a, Result<()>> {
+        Box::pin(CdpConnection::click(self, selector, 0))
+    }
+
+    fn type_into<'a>(&'a self, selector: &'a str, text: &'a str) -> LocalBoxFuture<'a, Result<()>> {
+        Box::pin(CdpConnection::type_into(self, selector, text))
+    }
+
+    fn current_url(&self) -> LocalBoxFuture<'_, Result<String>> {
+        Box::pin(CdpConnection::current_url(self))
+    }
+}
+
+/// Quote a string as a JavaScript literal
+fn js_string(value: &str) -> String {
+    serde_json::Value::String(value.to_string()).to_string()
+}
+
+/// Script that scrolls to and clicks an element, returning whether it was found
+fn click_script(selector: &str) -> String {
+    format!(
+        r"(function() {{
+            const el = document.querySelector({});
+            if (!el) return false;
+            el.scrollIntoView({{ block: 'center' }});
+            el.click();
+            return true;
+        }})()",
+        js_string(selector)
+    )
+}
+
+/// Script that sets an input's value through the native setter (so frameworks see it)
+fn type_script(selector: &str, text: &str) -> String {
+    format!(
+        r"(function() {{
+            const el = document.querySelector({});
+            if (!el) return false;
+            el.focus();
+            const text = {};
+            if (el.tagName === 'INPUT' || el.tagName === 'TEXTAREA') {{
+                const proto = el.tagName === 'INPUT'
+                    ? HTMLInputElement.prototype
+                    : HTMLTextAreaElement.prototype;
+                Object.getOwnPropertyDescriptor(proto, 'value').set.call(el, text);
+                el.dispatchEvent(new Event('input', {{ bubbles: true }}));
+                el.dispatchEvent(new Event('change', {{ bubbles: true }}));
+            }} else if (el.isContentEditable) {{
+                el.textContent = text;
+            }}
+            return true;
+        }})()",
+        js_string(selector),
+        js_string(text)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_kind_parse() {
+        assert_eq!("firefox".parse::<BackendKind>(), Ok(BackendKind::Firefox));
+        assert_eq!("Chromium".parse::<BackendKind>(), Ok(BackendKind::Chrome));
+        assert!("safari".parse::<BackendKind>().is_err());
+        assert_eq!(BackendKind::Firefox.to_string(), "firefox");
+    }
+
+    #[test]
+    fn test_image_format_parse() {
+        assert_eq!("JPG".parse::<ImageFormat>(), Ok(ImageFormat::Jpeg));
+        assert_eq!("webp".parse::<ImageFormat>(), Ok(ImageFormat::Webp));
+        assert!("gif".parse::<ImageFormat>().is_err());
+        assert_eq!(ImageFormat::Jpeg.extension(), "jpg");
+        assert_eq!(ImageFormat::default().mime_type(), "image/png");
+    }
+
+    #[test]
+    fn test_scripts_quote_arguments() {
+        let script = type_script("input[name='q']", "it's \"quoted\"\n");
+        assert!(script.contains(r#"document.querySelector("input[name='q']")"#));
+        assert!(script.contains(r#"const text = "it's \"quoted\"\n";"#));
+        assert!(click_script("#go").contains(r##"querySelector("#go")"##));
+    }
+}
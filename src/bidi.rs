@@ -0,0 +1,522 @@
+//! Experimental Firefox backend over WebDriver BiDi
+//!
+//! Firefox started with `--remote-debugging-port` serves BiDi at
+//! `ws://host:port/session`. This module speaks just enough WebSocket (RFC 6455,
+//! text frames over a plain TCP stream) and BiDi (`session.new`,
+//! `browsingContext.*`, `script.evaluate`) for the core commands. The socket
+//! is blocking, so its reads run on tokio's blocking pool and are cut short at
+//! the command's `--timeout`.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use futures::future::LocalBoxFuture;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::backend::{BrowserBackend, CaptureOptions};
+use crate::config::Config;
+
+/// How long to wait for one BiDi command (navigation included)
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long to wait for a BiDi command, at most until `deadline`
+fn read_limit(deadline: Option<Instant>) -> Result<Duration> {
+    let Some(deadline) = deadline else {
+        return Ok(COMMAND_TIMEOUT);
+    };
+    match deadline.checked_duration_since(Instant::now()) {
+        Some(left) if !left.is_zero() => Ok(left.min(COMMAND_TIMEOUT)),
+        _ => Err(anyhow!("Timed out waiting for Firefox (--timeout)")),
+    }
+}
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// A decoded WebSocket frame
+#[derive(Debug, PartialEq)]
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+/// Encode a client frame (clients must mask their payload)
+fn encode_frame(opcode: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..=125 => frame.push(0x80 | len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(0x80 | 126);
+            frame.extend((len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(0x80 | 127);
+            frame.extend((len as u64).to_be_bytes());
+        }
+    }
+    frame.extend(mask);
+    frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+    frame
+}
+
+/// Decode one frame from the start of `buf`, returning it and the bytes used
+///
+/// Returns `None` until `buf` holds a whole frame.
+fn decode_frame(buf: &[u8]) -> Option<(Frame, usize)> {
+    let (first, second) = (*buf.first()?, *buf.get(1)?);
+    let masked = second & 0x80 != 0;
+    let (len, mut offset) = match second & 0x7F {
+        126 => (
+            u64::from(u16::from_be_bytes([*buf.get(2)?, *buf.get(3)?])),
+            4,
+        ),
+        127 => (u64::from_be_bytes(buf.get(2..10)?.try_into().ok()?), 10),
+        len => (u64::from(len), 2),
+    };
+    let mask = if masked {
+        let mask: [u8; 4] = buf.get(offset..offset + 4)?.try_into().ok()?;
+        offset += 4;
+        Some(mask)
+    } else {
+        None
+    };
+
+    let end = offset.checked_add(usize::try_from(len).ok()?)?;
+    let mut payload = buf.get(offset..end)?.to_vec();
+    if let Some(mask) = mask {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+
+    Some((
+        Frame {
+            fin: first & 0x80 != 0,
+            opcode: first & 0x0F,
+            payload,
+        },
+        end,
+    ))
+}
+
+/// Four bytes that differ between calls (masking keys needn't be secret)
+fn frame_mask(counter: u64) -> [u8; 4] {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u64(counter);
+    let word = hasher.finish().to_le_bytes();
+    [word[0], word[1], word[2], word[3]]
+}
+
+/// A blocking WebSocket client for text messages
+struct WebSocket {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+    sent: u64,
+}
+
+impl WebSocket {
+    fn connect(host: &str, port: u16, path: &str, limit: Duration) -> Result<Self> {
+        let addr = (host, port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow!("Could not resolve {}", host))?;
+        let mut stream = TcpStream::connect_timeout(&addr, limit.min(Duration::from_secs(5)))
+            .with_context(|| format!("Failed to connect to Firefox at {}:{}", host, port))?;
+        stream.set_read_timeout(Some(limit))?;
+
+        let key: Vec<u8> = (0..4u64).flat_map(frame_mask).collect();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: {}:{}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            path,
+            host,
+            port,
+            base64::engine::general_purpose::STANDARD.encode(key)
+        )?;
+
+        // Read the upgrade response; anything after it is already frame data
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 1024];
+        let head_end = loop {
+            if let Some(i) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                break i + 4;
+            }
+            let n = stream.read(&mut chunk)?;
+            if n == 0 {
+                return Err(anyhow!(
+                    "Firefox closed the connection during the handshake"
+                ));
+            }
+            buffer.extend_from_slice(&chunk[..n]);
+        };
+        let head = String::from_utf8_lossy(&buffer[..head_end]).to_string();
+        let status = head.lines().next().unwrap_or_default();
+        if status.split_whitespace().nth(1) != Some("101") {
+            return Err(anyhow!(
+                "WebDriver BiDi handshake failed: {} (is Firefox running with --remote-debugging-port?)",
+                status
+            ));
+        }
+        buffer.drain(..head_end);
+
+        Ok(Self {
+            stream,
+            buffer,
+            sent: 0,
+        })
+    }
+
+    fn send_frame(&mut self, opcode: u8, payload: &[u8]) -> Result<()> {
+        self.sent += 1;
+        let frame = encode_frame(opcode, payload, frame_mask(self.sent));
+        self.stream.write_all(&frame)?;
+        Ok(())
+    }
+
+    fn send_text(&mut self, text: &str) -> Result<()> {
+        self.send_frame(OPCODE_TEXT, text.as_bytes())
+    }
+
+    /// Read the next complete text message, answering pings on the way
+    fn read_text(&mut self) -> Result<String> {
+        let mut message = Vec::new();
+        loop {
+            let (frame, used) = loop {
+                if let Some(decoded) = decode_frame(&self.buffer) {
+                    break decoded;
+                }
+                let mut chunk = [0u8; 16 * 1024];
+                let n = self
+                    .stream
+                    .read(&mut chunk)
+                    .context("Timed out waiting for Firefox")?;
+                if n == 0 {
+                    return Err(anyhow!("Firefox closed the connection"));
+                }
+                self.buffer.extend_from_slice(&chunk[..n]);
+            };
+            self.buffer.drain(..used);
+
+            match frame.opcode {
+                OPCODE_TEXT | OPCODE_CONTINUATION => {
+                    message.extend(frame.payload);
+                    if frame.fin {
+                        return Ok(String::from_utf8_lossy(&message).to_string());
+                    }
+                }
+                OPCODE_PING => self.send_frame(OPCODE_PONG, &frame.payload)?,
+                OPCODE_CLOSE => return Err(anyhow!("Firefox closed the connection")),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for WebSocket {
+    fn drop(&mut self) {
+        let _ = self.send_frame(OPCODE_CLOSE, &[]);
+    }
+}
+
+/// BiDi session state: the socket and the next command ID
+struct BidiSession {
+    socket: WebSocket,
+    next_id: u64,
+}
+
+impl BidiSession {
+    /// Send a command and wait for its response (events in between are skipped)
+    fn command(&mut self, method: &str, params: Value) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.socket
+            .send_text(&json!({ "id": id, "method": method, "params": params }).to_string())?;
+
+        loop {
+            let message: Value = serde_json::from_str(&self.socket.read_text()?)
+                .context("Invalid WebDriver BiDi message")?;
+            if message.get("id").and_then(Value::as_u64) != Some(id) {
+                continue;
+            }
+            if message.get("type").and_then(Value::as_str) == Some("error") {
+                return Err(anyhow!(
+                    "{} failed: {} ({})",
+                    method,
+                    message["message"].as_str().unwrap_or_default(),
+                    message["error"].as_str().unwrap_or("unknown error")
+                ));
+            }
+            return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+}
+
+/// Firefox driven over WebDriver BiDi
+pub struct FirefoxBackend {
+    session: Arc<Mutex<BidiSession>>,
+    /// Browsing context (tab) the commands act on
+    context: String,
+    /// When the command's `--timeout` runs out
+    deadline: Option<Instant>,
+}
+
+impl FirefoxBackend {
+    /// Connect to Firefox, start a BiDi session and pick the first tab
+    pub async fn connect(config: &Config, deadline: Option<Instant>) -> Result<Self> {
+        if !config.is_localhost() && !config.chrome.allow_remote {
+            return Err(anyhow!(
+                "Security: Non-localhost connections are blocked by default (see SECURITY.md, or use --allow-remote)"
+            ));
+        }
+        if config.chrome.tls {
+            return Err(anyhow!(
+                "The Firefox backend doesn't support TLS connections yet"
+            ));
+        }
+
+        let (host, port) = (config.chrome.host.clone(), config.chrome.port);
+        let limit = read_limit(deadline)?;
+        let (session, context) = tokio::task::spawn_blocking(move || {
+            let socket = WebSocket::connect(&host, port, "/session", limit)?;
+            let mut session = BidiSession { socket, next_id: 0 };
+            session.command("session.new", json!({ "capabilities": {} }))?;
+
+            let tree = session.command("browsingContext.getTree", json!({ "maxDepth": 0 }))?;
+            let context = match tree["contexts"][0]["context"].as_str() {
+                Some(context) => context.to_string(),
+                None => session.command("browsingContext.create", json!({ "type": "tab" }))?
+                    ["context"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("Firefox did not return a browsing context"))?
+                    .to_string(),
+            };
+            Ok::<_, anyhow::Error>((session, context))
+        })
+        .await
+        .context("Firefox connection task failed")??;
+
+        Ok(Self {
+            session: Arc::new(Mutex::new(session)),
+            context,
+            deadline,
+        })
+    }
+
+    /// Send a command on the blocking pool, so a slow Firefox doesn't stall the runtime
+    async fn command(&self, method: &str, params: Value) -> Result<Value> {
+        let limit = read_limit(self.deadline)?;
+        let session = Arc::clone(&self.session);
+        let method = method.to_string();
+        tokio::task::spawn_blocking(move || {
+            let mut session = session
+                .lock()
+                .map_err(|_| anyhow!("Firefox session lock poisoned"))?;
+            session.socket.stream.set_read_timeout(Some(limit))?;
+            session.command(&method, params)
+        })
+        .await
+        .context("Firefox command task failed")?
+    }
+}
+
+impl BrowserBackend for FirefoxBackend {
+    fn name(&self) -> &'static str {
+        "firefox"
+    }
+
+    fn navigate<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.command(
+                "browsingContext.navigate",
+                json!({ "context": self.context, "url": url, "wait": "complete" }),
+            )
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn evaluate<'a>(&'a self, expression: &'a str) -> LocalBoxFuture<'a, Result<Value>> {
+        Box::pin(async move {
+            let result = self
+                .command(
+                    "script.evaluate",
+                    json!({
+                        "expression": expression,
+                        "target": { "context": self.context },
+                        "awaitPromise": true,
+                        "resultOwnership": "none"
+                    }),
+                )
+                .await?;
+            if result["type"] == "exception" {
+                return Err(anyhow!(
+                    "JavaScript error: {}",
+                    result["exceptionDetails"]["text"]
+                        .as_str()
+                        .unwrap_or("unknown error")
+                ));
+            }
+            Ok(remote_value_to_json(&result["result"]))
+        })
+    }
+
+    fn screenshot(&self, full_page: bool) -> LocalBoxFuture<'_, Result<Vec<u8>>> {
+        Box::pin(async move {
+            let options = CaptureOptions {
+                full_page,
+                ..CaptureOptions::default()
+            };
+            self.capture(&options).await
+        })
+    }
+
+    fn capture<'a>(&'a self, options: &'a CaptureOptions) -> LocalBoxFuture<'a, Result<Vec<u8>>> {
+        Box::pin(async move {
+            let mut params = json!({
+                "context": self.context,
+                "origin": if options.full_page || options.clip.is_some() { "document" } else { "viewport" },
+                "format": { "type": options.format.mime_type() }
+            });
+            if let Some(quality) = options.quality {
+                params["format"]["quality"] = json!(f64::from(quality) / 100.0);
+            }
+            if let Some(clip) = options.clip {
+                params["clip"] = json!({
+                    "type": "box",
+                    "x": clip.x,
+                    "y": clip.y,
+                    "width": clip.width,
+                    "height": clip.height
+                });
+            }
+            let result = self
+                .command("browsingContext.captureScreenshot", params)
+                .await?;
+            let data = result["data"]
+                .as_str()
+                .ok_or_else(|| anyhow!("Firefox returned no screenshot data"))?;
+            base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .context("Failed to decode screenshot data")
+        })
+    }
+}
+
+/// Convert a BiDi `RemoteValue` into plain JSON
+///
+/// Objects and maps arrive as `[key, value]` pairs; values with no JSON form
+/// (functions, nodes, ...) become their type name.
+fn remote_value_to_json(value: &Value) -> Value {
+    let inner = &value["value"];
+    match value["type"].as_str().unwrap_or_default() {
+        "undefined" | "null" => Value::Null,
+        "string" | "boolean" => inner.clone(),
+        "number" => match inner {
+            // NaN, Infinity and -0 are sent as strings
+            Value::String(s) if s == "-0" => json!(0),
+            Value::String(_) => Value::Null,
+            other => other.clone(),
+        },
+        "bigint" | "date" => inner.clone(),
+        "regexp" => json!(format!(
+            "/{}/{}",
+            inner["pattern"].as_str().unwrap_or_default(),
+            inner["flags"].as_str().unwrap_or_default()
+        )),
+        "array" | "set" => Value::Array(
+            inner
+                .as_array()
+                .map(|items| items.iter().map(remote_value_to_json).collect())
+                .unwrap_or_default(),
+        ),
+        "object" | "map" => Value::Object(
+            inner
+                .as_array()
+                .map(|pairs| {
+                    pairs
+                        .iter()
+                        .filter_map(|pair| {
+                            let key = match &pair[0] {
+                                Value::String(key) => key.clone(),
+                                other => remote_value_to_json(other).to_string(),
+                            };
+                            Some((key, remote_value_to_json(pair.get(1)?)))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ),
+        other => json!(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_roundtrip() {
+        for len in [0, 5, 125, 126, 70_000] {
+            let payload = vec![b'x'; len];
+            let encoded = encode_frame(OPCODE_TEXT, &payload, [1, 2, 3, 4]);
+            let (frame, used) = decode_frame(&encoded).unwrap();
+            assert_eq!(used, encoded.len());
+            assert!(frame.fin);
+            assert_eq!(frame.opcode, OPCODE_TEXT);
+            assert_eq!(frame.payload, payload);
+
+            // Partial frames wait for more data
+            assert!(decode_frame(&encoded[..encoded.len() - 1]).is_none());
+        }
+    }
+
+    #[test]
+    fn test_decode_unmasked_server_frame() {
+        let (frame, used) = decode_frame(b"\x81\x02hi\x89\x00").unwrap();
+        assert_eq!(frame.payload, b"hi");
+        assert_eq!(used, 4);
+        let (ping, _) = decode_frame(&b"\x81\x02hi\x89\x00"[used..]).unwrap();
+        assert_eq!(ping.opcode, OPCODE_PING);
+    }
+
+    #[test]
+    fn test_read_limit_stops_at_deadline() {
+        assert_eq!(read_limit(None).unwrap(), COMMAND_TIMEOUT);
+        let later = Instant::now() + Duration::from_secs(120);
+        assert_eq!(read_limit(Some(later)).unwrap(), COMMAND_TIMEOUT);
+        let soon = read_limit(Some(Instant::now() + Duration::from_secs(2))).unwrap();
+        assert!(soon > Duration::from_secs(1) && soon <= Duration::from_secs(2));
+        assert!(read_limit(Some(Instant::now())).is_err());
+    }
+
+    #[test]
+    fn test_remote_value_to_json() {
+        let value = json!({
+            "type": "object",
+            "value": [
+                ["title", { "type": "string", "value": "Example" }],
+                ["count", { "type": "number", "value": 3 }],
+                ["tags", { "type": "array", "value": [{ "type": "string", "value": "a" }] }],
+                ["missing", { "type": "undefined" }],
+                ["ratio", { "type": "number", "value": "NaN" }]
+            ]
+        });
+        assert_eq!(
+            remote_value_to_json(&value),
+            json!({ "title": "Example", "count": 3, "tags": ["a"], "missing": null, "ratio": null })
+        );
+        assert_eq!(
+            remote_value_to_json(&json!({ "type": "function" })),
+            json!("function")
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! Action budgets for DOMGuard
+//!
+//! Per-domain rate limits for interact commands, so a runaway agent can't hammer
+//! a production site. Counts survive between CLI invocations in
+//! `.domguard/budget.json`, which is changed under its lock file so parallel
+//! agents can't lose each other's counts.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::cdp::CdpConnection;
+use crate::config::Config;
+use crate::takeover::{TakeoverManager, TakeoverReason, TakeoverSession};
+
+/// Idle time after which navigations outside a recorded session start a new count
+const IDLE_RESET_MS: i64 = 30 * 60 * 1000;
+
+/// Limits for one domain (unset means unlimited)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DomainBudget {
+    /// Maximum interact commands per minute
+    #[serde(default)]
+    pub max_actions_per_minute: Option<u32>,
+    /// Maximum navigations per session
+    #[serde(default)]
+    pub max_navigations_per_session: Option<u32>,
+}
+
+/// Budget configuration (`[budget]` in .domguard/config.toml)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    /// Limits applied to every domain without its own entry
+    #[serde(flatten)]
+    pub default: DomainBudget,
+    /// Request a takeover when a budget is exceeded (default: false)
+    #[serde(default)]
+    pub takeover_on_exceeded: bool,
+    /// Per-domain limits (e.g., `[budget.domains."shop.example.com"]`)
+    #[serde(default)]
+    pub domains: BTreeMap<String, DomainBudget>,
+}
+
+impl BudgetConfig {
+    /// Limits for a domain: its own entry (or its closest parent domain's), else the defaults
+    pub fn for_domain(&self, domain: &str) -> &DomainBudget {
+        let mut candidate = domain;
+        loop {
+            if let Some(budget) = self.domains.get(candidate) {
+                return budget;
+            }
+            match candidate.split_once('.') {
+                Some((_, parent)) if parent.contains('.') => candidate = parent,
+                _ => return &self.default,
+            }
+        }
+    }
+}
+
+/// Which limit was hit
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetLimit {
+    ActionsPerMinute,
+    NavigationsPerSession,
+}
+
+/// An interact command refused because a budget is used up
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetExceeded {
+    pub domain: String,
+    pub limit: BudgetLimit,
+    pub max: u32,
+    /// Milliseconds until another action is allowed (actions per minute only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<i64>,
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.limit {
+            BudgetLimit::ActionsPerMinute => write!(
+                f,
+                "Budget exceeded: {} allows {} actions per minute",
+                self.domain, self.max
+            )?,
+            BudgetLimit::NavigationsPerSession => write!(
+                f,
+                "Budget exceeded: {} allows {} navigations per session",
+                self.domain, self.max
+            )?,
+        }
+        if let Some(ms) = self.retry_after_ms {
+            write!(f, " (retry in {}s)", (ms + 999) / 1000)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// Navigation count for one session on one domain
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NavigationCount {
+    pub session: String,
+    pub count: u32,
+    pub last_ms: i64,
+}
+
+/// Recent usage per domain
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetLedger {
+    /// Timestamps (ms) of actions in the last minute
+    #[serde(default)]
+    pub actions: BTreeMap<String, Vec<i64>>,
+    #[serde(default)]
+    pub navigations: BTreeMap<String, NavigationCount>,
+}
+
+impl BudgetLedger {
+    /// Load the ledger, or an empty one if it doesn't exist
+    ///
+    /// A ledger that can't be parsed is an error rather than a fresh start,
+    /// which would quietly lift every limit.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| {
+            format!(
+                "Failed to parse {} (delete it to reset the counts)",
+                path.display()
+            )
+        })
+    }
+
+    /// Save the ledger
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        crate::storage::write_atomic(path, &serde_json::to_string_pretty(self)?)
+    }
+
+    /// Load, change and save the ledger at `path` under its lock
+    pub fn update<T>(path: &Path, change: impl FnOnce(&mut Self) -> T) -> Result<T> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let _lock = crate::storage::FileLock::acquire(path)?;
+        let mut ledger = Self::load(path)?;
+        let changed = change(&mut ledger);
+        ledger.save(path)?;
+        Ok(changed)
+    }
+
+    /// Count an action (and a navigation, if it is one) against the domain's budget
+    ///
+    /// Nothing is counted when the budget is already used up. `session` is the
+    /// recorded session ID; without one, navigations reset after 30 idle minutes.
+    pub fn charge(
+        &mut self,
+        budget: &DomainBudget,
+        domain: &str,
+        session: Option<&str>,
+        navigation: bool,
+        now_ms: i64,
+    ) -> std::result::Result<(), BudgetExceeded> {
+        let recent = self.actions.entry(domain.to_string()).or_default();
+        recent.retain(|t| now_ms - t < 60_000);
+        if let Some(max) = budget.max_actions_per_minute {
+            if recent.len() >= max as usize {
+                let oldest = recent.iter().min().copied().unwrap_or(now_ms);
+                return Err(BudgetExceeded {
+                    domain: domain.to_string(),
+                    limit: BudgetLimit::ActionsPerMinute,
+                    max,
+                    retry_after_ms: Some(60_000 - (now_ms - oldest)),
+                });
+            }
+        }
+
+        if navigation {
+            let session = session.unwrap_or_default();
+            let entry = self.navigations.entry(domain.to_string()).or_default();
+            if entry.session != session
+                || (session.is_empty() && now_ms - entry.last_ms > IDLE_RESET_MS)
+            {
+                *entry = NavigationCount {
+                    session: session.to_string(),
+                    ..NavigationCount::default()
+                };
+            }
+            if let Some(max) = budget.max_navigations_per_session {
+                if entry.count >= max {
+                    return Err(BudgetExceeded {
+                        domain: domain.to_string(),
+                        limit: BudgetLimit::NavigationsPerSession,
+                        max,
+                        retry_after_ms: None,
+                    });
+                }
+            }
+            entry.count += 1;
+            entry.last_ms = now_ms;
+        }
+
+        recent.push(now_ms);
+        Ok(())
+    }
+}
+
+/// An interact command refused by its domain's budget
+#[derive(Debug)]
+pub struct Refusal {
+    pub exceeded: BudgetExceeded,
+    /// Requested with `takeover_on_exceeded` unless a takeover was already active
+    pub takeover: Option<TakeoverSession>,
+}
+
+/// Charge an interact command to its domain's action budget
+///
+/// `navigate_to` is the URL a navigation goes to; anything else is charged
+/// to the current page's domain. Returns the refusal if the budget is used up.
+pub async fn enforce(
+    cdp: &CdpConnection,
+    config: &Config,
+    navigate_to: Option<&str>,
+    session: Option<&str>,
+) -> Result<Option<Refusal>> {
+    if config.budget.default == DomainBudget::default() && config.budget.domains.is_empty() {
+        return Ok(None);
+    }
+
+    let url = match navigate_to {
+        Some(url) => Some(url.to_string()),
+        None => cdp.current_url().await.ok(),
+    };
+    let Some(domain) = url
+        .as_deref()
+        .and_then(crate::site_instructions::extract_domain)
+    else {
+        return Ok(None);
+    };
+
+    let domguard_dir = Config::find_domguard_dir().unwrap_or_else(Config::domguard_dir);
+    let ledger_path = domguard_dir.join("budget.json");
+    let charged = BudgetLedger::update(&ledger_path, |ledger| {
+        ledger.charge(
+            config.budget.for_domain(&domain),
+            &domain,
+            session,
+            navigate_to.is_some(),
+            chrono::Utc::now().timestamp_millis(),
+        )
+    })?;
+
+    let Err(exceeded) = charged else {
+        return Ok(None);
+    };
+    let mut takeover = None;
+    if config.budget.takeover_on_exceeded {
+        let manager = TakeoverManager::new(&domguard_dir);
+        if !manager.is_active() {
+            let mut session = TakeoverSession::new(
+                TakeoverReason::Custom("budget_exceeded".to_string()),
+                &exceeded.to_string(),
+            )
+            .with_instructions("Check what the agent is doing before letting it continue");
+            if let Some(url) = &url {
+                session = session.with_url(url);
+            }
+            manager.start(&session)?;
+            takeover = Some(session);
+        }
+    }
+    Ok(Some(Refusal { exceeded, takeover }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_budget_config() {
+        let config: BudgetConfig = toml::from_str(
+            "max_actions_per_minute = 30\n\n[domains.\"example.com\"]\nmax_navigations_per_session = 5\n",
+        )
+        .unwrap();
+        assert_eq!(config.default.max_actions_per_minute, Some(30));
+        assert_eq!(
+            config
+                .for_domain("shop.example.com")
+                .max_navigations_per_session,
+            Some(5)
+        );
+        assert_eq!(
+            config.for_domain("other.org").max_actions_per_minute,
+            Some(30)
+        );
+    }
+
+    #[test]
+    fn test_actions_per_minute() {
+        let budget = DomainBudget {
+            max_actions_per_minute: Some(2),
+            ..DomainBudget::default()
+        };
+        let mut ledger = BudgetLedger::default();
+        assert!(ledger.charge(&budget, "a.com", None, false, 0).is_ok());
+        assert!(ledger.charge(&budget, "a.com", None, false, 10_000).is_ok());
+
+        let err = ledger
+            .charge(&budget, "a.com", None, false, 20_000)
+            .unwrap_err();
+        assert_eq!(err.limit, BudgetLimit::ActionsPerMinute);
+        assert_eq!(err.retry_after_ms, Some(40_000));
+
+        // Other domains and later minutes are unaffected
+        assert!(ledger.charge(&budget, "b.com", None, false, 20_000).is_ok());
+        assert!(ledger.charge(&budget, "a.com", None, false, 61_000).is_ok());
+    }
+
+    #[test]
+    fn test_navigations_per_session() {
+        let budget = DomainBudget {
+            max_navigations_per_session: Some(1),
+            ..DomainBudget::default()
+        };
+        let mut ledger = BudgetLedger::default();
+        assert!(ledger.charge(&budget, "a.com", Some("s1"), true, 0).is_ok());
+        assert!(ledger
+            .charge(&budget, "a.com", Some("s1"), false, 1)
+            .is_ok());
+        assert_eq!(
+            ledger
+                .charge(&budget, "a.com", Some("s1"), true, 2)
+                .unwrap_err()
+                .limit,
+            BudgetLimit::NavigationsPerSession
+        );
+
+        // A new session, or a long idle gap without one, starts over
+        assert!(ledger.charge(&budget, "a.com", Some("s2"), true, 3).is_ok());
+        assert!(ledger.charge(&budget, "a.com", None, true, 4).is_ok());
+        assert!(ledger.charge(&budget, "a.com", None, true, 5).is_err());
+        assert!(ledger
+            .charge(&budget, "a.com", None, true, 5 + IDLE_RESET_MS + 1)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_ledger_updates_are_locked_and_corruption_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("budget.json");
+        let budget = DomainBudget {
+            max_actions_per_minute: Some(1),
+            ..DomainBudget::default()
+        };
+        let charge = |ledger: &mut BudgetLedger| ledger.charge(&budget, "a.com", None, false, 0);
+        assert!(BudgetLedger::update(&path, charge).unwrap().is_ok());
+        assert!(BudgetLedger::update(&path, charge).unwrap().is_err());
+        assert!(!dir.path().join("budget.json.lock").exists());
+
+        std::fs::write(&path, "{not json").unwrap();
+        let err = BudgetLedger::update(&path, charge).unwrap_err();
+        assert!(format!("{:#}", err).contains("delete it to reset"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{not json");
+    }
+}
@@ -1,8 +1,11 @@
 //! CAPTCHA detection for DOMGuard
 //!
 //! Detects common CAPTCHA implementations and pauses for human intervention.
-//! Supports reCAPTCHA, hCaptcha, Cloudflare Turnstile, and other common patterns.
+//! Supports reCAPTCHA, hCaptcha, Cloudflare Turnstile, Arkose Labs, GeeTest, and other
+//! common patterns, including widgets loaded in cross-origin iframes.
 
+use crate::cdp::CdpConnection;
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::fmt::Write as _;
 
@@ -30,6 +33,11 @@ pub enum CaptchaType {
     TextCaptcha,
     /// Slider/puzzle CAPTCHA
     SliderCaptcha,
+    /// GeeTest (slide, click or icon challenge)
+    #[serde(rename = "geetest")]
+    GeeTest,
+    /// Image-grid challenge popup (reCAPTCHA or hCaptcha "select all squares")
+    ImageGrid,
     /// Unknown but detected CAPTCHA
     Unknown,
 }
@@ -52,6 +60,24 @@ pub struct CaptchaDetection {
     pub appears_solved: bool,
     /// Recommended action
     pub recommendation: CaptchaRecommendation,
+    /// How sure the detection is, from 0.0 to 1.0
+    #[serde(default)]
+    pub confidence: f64,
+    /// Where the widget is on the page, for cropping screenshots
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bounding_box: Option<CaptchaBox>,
+    /// URL of the iframe the widget was found in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_url: Option<String>,
+}
+
+/// Bounding box of a CAPTCHA widget in page (document) coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptchaBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
 }
 
 /// Recommended action for CAPTCHA
@@ -77,6 +103,9 @@ impl Default for CaptchaDetection {
             description: None,
             appears_solved: false,
             recommendation: CaptchaRecommendation::Continue,
+            confidence: 0.0,
+            bounding_box: None,
+            frame_url: None,
         }
     }
 }
@@ -90,111 +119,126 @@ pub fn captcha_detection_script() -> &'static str {
             captcha_type: null,
             selector: null,
             description: null,
-            appears_solved: false
+            appears_solved: false,
+            confidence: 0,
+            bounding_box: null,
+            frame_url: null
+        };
+
+        // Widget box in page coordinates, or null when it isn't rendered
+        const boxOf = (el) => {
+            if (!el || !el.getBoundingClientRect) return null;
+            const r = el.getBoundingClientRect();
+            if (r.width === 0 || r.height === 0) return null;
+            return { x: r.left + window.scrollX, y: r.top + window.scrollY, width: r.width, height: r.height };
+        };
+
+        const found = (type, selector, description, confidence, el, solved) => {
+            result.detected = true;
+            result.captcha_type = type;
+            result.selector = selector;
+            result.description = description;
+            result.confidence = confidence;
+            result.bounding_box = boxOf(el);
+            result.appears_solved = !!solved;
+            return result;
+        };
+
+        // Cross-origin widgets can't be inspected, but their iframe src names the provider.
+        // Challenge popups (image grids) come first: they are what actually blocks the page.
+        // Invisible widgets are left to the checks below (e.g., reCAPTCHA v3).
+        const iframePatterns = [
+            [/google\.com\/recaptcha\/(api2|enterprise)\/bframe/, 'image_grid', 'reCAPTCHA image challenge', true],
+            [/hcaptcha\.com\/.*frame=challenge/, 'image_grid', 'hCaptcha image challenge', true],
+            [/challenges\.cloudflare\.com/, 'cloudflare_turnstile', 'Cloudflare Turnstile', true],
+            [/(arkoselabs\.com|funcaptcha\.com)/, 'fun_captcha', 'Arkose Labs FunCaptcha', false],
+            [/(geetest\.com|geevisit\.com)/, 'geetest', 'GeeTest', false],
+            [/google\.com\/recaptcha\/(api2|enterprise)\/anchor/, 'recaptcha_v2', 'Google reCAPTCHA v2', true],
+            [/hcaptcha\.com\/.*frame=checkbox/, 'hcaptcha', 'hCaptcha', true]
+        ];
+        const responseFields = {
+            recaptcha_v2: 'g-recaptcha-response',
+            hcaptcha: 'h-captcha-response',
+            cloudflare_turnstile: 'cf-turnstile-response'
         };
+        const iframes = Array.from(document.querySelectorAll('iframe[src]'));
+        for (const [pattern, type, name, mustBeVisible] of iframePatterns) {
+            const frame = iframes.find(f => pattern.test(f.src) && (!mustBeVisible || boxOf(f)));
+            if (frame) {
+                const responseName = responseFields[type];
+                const response = responseName && document.querySelector('[name="' + responseName + '"]');
+                found(type, 'iframe[src^="' + frame.src.split(/[?#]/)[0] + '"]', name + ' detected in iframe', 0.95, frame, response && response.value);
+                result.frame_url = frame.src;
+                return result;
+            }
+        }
 
         // Check for reCAPTCHA v2
         const recaptchaV2 = document.querySelector('.g-recaptcha, [data-sitekey], iframe[src*="recaptcha"]');
         if (recaptchaV2) {
-            result.detected = true;
-            result.captcha_type = 'recaptcha_v2';
-            result.selector = '.g-recaptcha';
-            result.description = 'Google reCAPTCHA v2 detected';
             // Check if checkbox is checked
             const checkbox = document.querySelector('.recaptcha-checkbox-checked, .recaptcha-checkbox-checkmark[style*="opacity: 1"]');
-            result.appears_solved = !!checkbox;
-            return result;
+            return found('recaptcha_v2', '.g-recaptcha', 'Google reCAPTCHA v2 detected', 0.9, recaptchaV2, checkbox);
         }
 
         // Check for reCAPTCHA v3 (invisible)
         const recaptchaV3 = document.querySelector('.grecaptcha-badge, script[src*="recaptcha/api.js?render="]');
         if (recaptchaV3) {
-            result.detected = true;
-            result.captcha_type = 'recaptcha_v3';
-            result.selector = '.grecaptcha-badge';
-            result.description = 'Google reCAPTCHA v3 (invisible) detected';
-            result.appears_solved = true; // v3 is automatic
-            return result;
+            // v3 is automatic
+            return found('recaptcha_v3', '.grecaptcha-badge', 'Google reCAPTCHA v3 (invisible) detected', 0.8, recaptchaV3, true);
         }
 
         // Check for hCaptcha
         const hcaptcha = document.querySelector('.h-captcha, iframe[src*="hcaptcha.com"], [data-hcaptcha-widget-id]');
         if (hcaptcha) {
-            result.detected = true;
-            result.captcha_type = 'hcaptcha';
-            result.selector = '.h-captcha';
-            result.description = 'hCaptcha detected';
             // Check for success indicator
             const solved = document.querySelector('[data-hcaptcha-response]:not([data-hcaptcha-response=""])');
-            result.appears_solved = !!solved;
-            return result;
+            return found('hcaptcha', '.h-captcha', 'hCaptcha detected', 0.9, hcaptcha, solved);
         }
 
         // Check for Cloudflare Turnstile
-        const turnstile = document.querySelector('.cf-turnstile, iframe[src*="challenges.cloudflare.com/turnstile"]');
+        const turnstile = document.querySelector('.cf-turnstile, iframe[src*="challenges.cloudflare.com/turnstile"], script[src*="challenges.cloudflare.com/turnstile"]');
         if (turnstile) {
-            result.detected = true;
-            result.captcha_type = 'cloudflare_turnstile';
-            result.selector = '.cf-turnstile';
-            result.description = 'Cloudflare Turnstile detected';
             const solved = document.querySelector('[name="cf-turnstile-response"]:not([value=""])');
-            result.appears_solved = !!solved;
-            return result;
+            return found('cloudflare_turnstile', '.cf-turnstile', 'Cloudflare Turnstile detected', 0.9, turnstile, solved);
         }
 
         // Check for Cloudflare challenge page
         const cfChallenge = document.querySelector('#cf-challenge-running, .cf-browser-verification, #challenge-form, #challenge-error-text');
         if (cfChallenge || document.title.includes('Just a moment') || document.body.innerHTML.includes('Checking if the site connection is secure')) {
-            result.detected = true;
-            result.captcha_type = 'cloudflare_challenge';
-            result.selector = '#challenge-form';
-            result.description = 'Cloudflare challenge page detected';
-            result.appears_solved = false;
-            return result;
+            return found('cloudflare_challenge', '#challenge-form', 'Cloudflare challenge page detected', cfChallenge ? 0.9 : 0.7, cfChallenge, false);
         }
 
         // Check for FunCaptcha (Arkose Labs)
-        const funcaptcha = document.querySelector('#FunCaptcha, iframe[src*="arkoselabs.com"], [data-callback*="funcaptcha"]');
+        const funcaptcha = document.querySelector('#FunCaptcha, #arkose, [data-callback*="funcaptcha"], script[src*="arkoselabs.com"]');
         if (funcaptcha) {
-            result.detected = true;
-            result.captcha_type = 'fun_captcha';
-            result.selector = '#FunCaptcha';
-            result.description = 'Arkose Labs FunCaptcha detected';
-            result.appears_solved = false;
-            return result;
+            return found('fun_captcha', '#FunCaptcha', 'Arkose Labs FunCaptcha detected', 0.85, funcaptcha, false);
+        }
+
+        // Check for GeeTest
+        const geetest = document.querySelector('.geetest_holder, .geetest_panel, .geetest_radar_tip, .geetest_box, script[src*="geetest"]');
+        if (geetest) {
+            const solved = document.querySelector('.geetest_success, .geetest_lock_success');
+            const widget = document.querySelector('.geetest_holder, .geetest_box, .geetest_panel') || geetest;
+            return found('geetest', '.' + (widget.className || 'geetest_holder').split(' ')[0], 'GeeTest CAPTCHA detected', 0.85, widget, solved);
         }
 
         // Check for slider CAPTCHA patterns
-        const slider = document.querySelector('.slider-captcha, .slide-verify, .geetest_slider, [class*="captcha-slider"]');
+        const slider = document.querySelector('.slider-captcha, .slide-verify, [class*="captcha-slider"]');
         if (slider) {
-            result.detected = true;
-            result.captcha_type = 'slider_captcha';
-            result.selector = slider.className.split(' ')[0];
-            result.description = 'Slider/puzzle CAPTCHA detected';
-            result.appears_solved = false;
-            return result;
+            return found('slider_captcha', slider.className.split(' ')[0], 'Slider/puzzle CAPTCHA detected', 0.7, slider, false);
         }
 
         // Check for image CAPTCHA
         const imageCaptcha = document.querySelector('img[src*="captcha"], img[alt*="captcha" i], input[name*="captcha" i]');
         if (imageCaptcha) {
-            result.detected = true;
-            result.captcha_type = 'image_captcha';
-            result.selector = imageCaptcha.tagName.toLowerCase();
-            result.description = 'Image-based CAPTCHA detected';
-            result.appears_solved = false;
-            return result;
+            return found('image_captcha', imageCaptcha.tagName.toLowerCase(), 'Image-based CAPTCHA detected', 0.6, imageCaptcha, false);
         }
 
         // Check for generic CAPTCHA indicators
         const genericCaptcha = document.querySelector('[class*="captcha" i], [id*="captcha" i], [aria-label*="captcha" i]');
-        if (genericCaptcha && !result.detected) {
-            result.detected = true;
-            result.captcha_type = 'unknown';
-            result.selector = genericCaptcha.id || genericCaptcha.className.split(' ')[0];
-            result.description = 'Unknown CAPTCHA type detected';
-            result.appears_solved = false;
-            return result;
+        if (genericCaptcha) {
+            return found('unknown', genericCaptcha.id || genericCaptcha.className.split(' ')[0], 'Unknown CAPTCHA type detected', 0.4, genericCaptcha, false);
         }
 
         return result;
@@ -202,6 +246,86 @@ pub fn captcha_detection_script() -> &'static str {
     "#
 }
 
+/// Providers recognised by frame URL: (URL fragments that must all match, type, name)
+///
+/// The frame tree doesn't say which frames are visible, and reCAPTCHA and hCaptcha
+/// load their (hidden) challenge frame next to the checkbox, so widgets are matched
+/// before challenge popups.
+const FRAME_PATTERNS: [(&[&str], CaptchaType, &str); 8] = [
+    (
+        &["challenges.cloudflare.com"],
+        CaptchaType::CloudflareTurnstile,
+        "Cloudflare Turnstile",
+    ),
+    (
+        &["arkoselabs.com"],
+        CaptchaType::FunCaptcha,
+        "Arkose Labs FunCaptcha",
+    ),
+    (
+        &["funcaptcha.com"],
+        CaptchaType::FunCaptcha,
+        "Arkose Labs FunCaptcha",
+    ),
+    (&["geetest.com"], CaptchaType::GeeTest, "GeeTest"),
+    (
+        &["/recaptcha/", "/anchor"],
+        CaptchaType::RecaptchaV2,
+        "Google reCAPTCHA v2",
+    ),
+    (
+        &["hcaptcha.com", "frame=checkbox"],
+        CaptchaType::Hcaptcha,
+        "hCaptcha",
+    ),
+    (
+        &["/recaptcha/", "/bframe"],
+        CaptchaType::ImageGrid,
+        "reCAPTCHA image challenge",
+    ),
+    (
+        &["hcaptcha.com", "frame=challenge"],
+        CaptchaType::ImageGrid,
+        "hCaptcha image challenge",
+    ),
+];
+
+/// Detect a CAPTCHA from the URLs of the page's frames
+///
+/// Catches widgets nested inside other cross-origin iframes, where the page
+/// script can't see them.
+pub fn detect_in_frames(frame_urls: &[String]) -> Option<CaptchaDetection> {
+    FRAME_PATTERNS
+        .iter()
+        .find_map(|(fragments, captcha_type, name)| {
+            let url = frame_urls
+                .iter()
+                .find(|url| fragments.iter().all(|fragment| url.contains(fragment)))?;
+            Some(CaptchaDetection {
+                detected: true,
+                captcha_type: Some(captcha_type.clone()),
+                selector: None,
+                description: Some(format!("{} detected in frame tree", name)),
+                appears_solved: false,
+                recommendation: CaptchaRecommendation::PauseForHuman,
+                confidence: 0.85,
+                bounding_box: None,
+                frame_url: Some(url.clone()),
+            })
+        })
+}
+
+/// Run DOM detection, falling back to the CDP frame tree for nested widgets
+pub async fn detect_captcha(cdp: &CdpConnection) -> Result<CaptchaDetection> {
+    let detection = parse_captcha_detection(&cdp.evaluate(captcha_detection_script()).await?);
+    if detection.detected {
+        return Ok(detection);
+    }
+
+    let frame_urls = cdp.frame_urls().await.unwrap_or_default();
+    Ok(detect_in_frames(&frame_urls).unwrap_or(detection))
+}
+
 /// Parse the detection result from JavaScript
 pub fn parse_captcha_detection(js_result: &serde_json::Value) -> CaptchaDetection {
     if js_result.is_null() {
@@ -231,6 +355,8 @@ pub fn parse_captcha_detection(js_result: &serde_json::Value) -> CaptchaDetectio
             "audio_captcha" => CaptchaType::AudioCaptcha,
             "text_captcha" => CaptchaType::TextCaptcha,
             "slider_captcha" => CaptchaType::SliderCaptcha,
+            "geetest" => CaptchaType::GeeTest,
+            "image_grid" => CaptchaType::ImageGrid,
             _ => CaptchaType::Unknown,
         });
 
@@ -249,6 +375,20 @@ pub fn parse_captcha_detection(js_result: &serde_json::Value) -> CaptchaDetectio
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
 
+    let confidence = js_result
+        .get("confidence")
+        .and_then(serde_json::Value::as_f64)
+        .unwrap_or(0.5);
+
+    let bounding_box = js_result
+        .get("bounding_box")
+        .and_then(|v| serde_json::from_value(v.clone()).ok());
+
+    let frame_url = js_result
+        .get("frame_url")
+        .and_then(|v| v.as_str())
+        .map(String::from);
+
     // Determine recommendation based on type and solved state
     let recommendation = if appears_solved {
         CaptchaRecommendation::Continue
@@ -268,6 +408,9 @@ pub fn parse_captcha_detection(js_result: &serde_json::Value) -> CaptchaDetectio
         description,
         appears_solved,
         recommendation,
+        confidence,
+        bounding_box,
+        frame_url,
     }
 }
 
@@ -293,6 +436,24 @@ pub fn format_captcha_detection(detection: &CaptchaDetection) -> String {
         let _ = writeln!(output, "   Element: {}", selector);
     }
 
+    if let Some(frame_url) = &detection.frame_url {
+        let _ = writeln!(output, "   Frame: {}", frame_url);
+    }
+
+    if let Some(b) = &detection.bounding_box {
+        let _ = writeln!(
+            output,
+            "   Box: {:.0}x{:.0} at ({:.0}, {:.0})",
+            b.width, b.height, b.x, b.y
+        );
+    }
+
+    let _ = writeln!(
+        output,
+        "   Confidence: {:.0}%",
+        detection.confidence * 100.0
+    );
+
     let _ = writeln!(
         output,
         "   Solved: {}",
@@ -388,4 +549,59 @@ mod tests {
         let detection = parse_captcha_detection(&result);
         assert!(!detection.detected);
     }
+
+    #[test]
+    fn test_parse_confidence_and_box() {
+        let result = serde_json::json!({
+            "detected": true,
+            "captcha_type": "geetest",
+            "selector": ".geetest_holder",
+            "appears_solved": false,
+            "confidence": 0.85,
+            "bounding_box": { "x": 10.0, "y": 20.0, "width": 300.0, "height": 150.0 }
+        });
+
+        let detection = parse_captcha_detection(&result);
+        assert_eq!(detection.captcha_type, Some(CaptchaType::GeeTest));
+        assert!((detection.confidence - 0.85).abs() < f64::EPSILON);
+        assert_eq!(
+            detection.bounding_box,
+            Some(CaptchaBox {
+                x: 10.0,
+                y: 20.0,
+                width: 300.0,
+                height: 150.0
+            })
+        );
+        assert!(format_captcha_detection(&detection).contains("Box: 300x150 at (10, 20)"));
+    }
+
+    #[test]
+    fn test_detect_in_frames() {
+        let urls = vec![
+            "https://shop.example.com/checkout".to_string(),
+            "https://www.google.com/recaptcha/api2/bframe?k=abc".to_string(),
+            "https://www.google.com/recaptcha/api2/anchor?k=abc".to_string(),
+        ];
+        let detection = detect_in_frames(&urls).unwrap();
+        assert_eq!(detection.captcha_type, Some(CaptchaType::RecaptchaV2));
+        assert_eq!(detection.frame_url.as_deref(), Some(urls[2].as_str()));
+
+        let arkose = vec!["https://client-api.arkoselabs.com/fc/gc/?token=1".to_string()];
+        assert_eq!(
+            detect_in_frames(&arkose).unwrap().captcha_type,
+            Some(CaptchaType::FunCaptcha)
+        );
+
+        let grid = vec![
+            "https://newassets.hcaptcha.com/captcha/v1/x/static/hcaptcha.html#frame=challenge"
+                .to_string(),
+        ];
+        assert_eq!(
+            detect_in_frames(&grid).unwrap().captcha_type,
+            Some(CaptchaType::ImageGrid)
+        );
+
+        assert!(detect_in_frames(&["https://example.com/".to_string()]).is_none());
+    }
 }
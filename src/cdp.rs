@@ -11,12 +11,20 @@ use chromiumoxide::cdp::browser_protocol::page::{CaptureScreenshotParams, PrintT
 use chromiumoxide::cdp::js_protocol::runtime::{self, EventConsoleApiCalled, EventExceptionThrown};
 use chromiumoxide::page::Page;
 use futures::StreamExt;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
 
+use crate::backend::{CaptureOptions, ClipRect, ImageFormat};
 use crate::config::Config;
+use crate::events::{EventKind, PageEvent};
+use crate::framework::{
+    input_events_script, DetectedFramework, Framework, ANGULAR_STABLE, DETECT_FRAMEWORK,
+};
+use crate::keyboard::{char_key, human_delay, parse_key_sequence, KeyStroke};
+use crate::network::{find_credential, Credential, MockRule};
 
 /// Tab information for listing browser tabs
 #[derive(Debug, Clone, Serialize)]
@@ -24,12 +32,1002 @@ pub struct TabInfo {
     pub id: String,
     pub url: String,
     pub title: String,
+    /// The tab DOMGuard commands run against
+    pub active: bool,
 }
 
+/// A separate browser context (its own cookie jar and storage) and its tabs
+#[derive(Debug, Clone, Serialize)]
+pub struct BrowserContextInfo {
+    pub id: String,
+    pub tabs: Vec<TabInfo>,
+}
+
+/// File remembering the tab chosen with `tabs switch` / `tabs new`
+fn active_tab_path() -> std::path::PathBuf {
+    Config::find_domguard_dir()
+        .unwrap_or_else(Config::domguard_dir)
+        .join("active_tab")
+}
+
+/// The remembered tab, if any
+fn read_active_tab() -> Option<String> {
+    let id = std::fs::read_to_string(active_tab_path()).ok()?;
+    let id = id.trim();
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+/// Remember (or forget) the tab DOMGuard commands run against
+fn write_active_tab(id: Option<&str>) -> Result<()> {
+    let path = active_tab_path();
+    match id {
+        Some(id) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, id).context("Failed to remember the active tab")?;
+        }
+        None if path.exists() => std::fs::remove_file(&path)?,
+        None => {}
+    }
+    Ok(())
+}
+
+/// Index of the tab commands should use
+///
+/// The remembered tab wins while it is still open; otherwise the first tab
+/// with a real URL, then the first `about:blank`.
+fn choose_tab(tabs: &[TabInfo], remembered: Option<&str>) -> Option<usize> {
+    remembered
+        .and_then(|id| tabs.iter().position(|tab| tab.id == id))
+        .or_else(|| {
+            tabs.iter()
+                .position(|tab| tab.url != "about:blank" && !tab.url.is_empty())
+        })
+        .or_else(|| (!tabs.is_empty()).then_some(0))
+}
+
+/// Page tabs among the browser targets, skipping internal Chrome pages
+fn page_tabs(targets: &[chromiumoxide::cdp::browser_protocol::target::TargetInfo]) -> Vec<TabInfo> {
+    targets
+        .iter()
+        .filter(|target| {
+            target.r#type == "page"
+                && !target.url.starts_with("chrome://")
+                && !target.url.starts_with("chrome-extension://")
+                && !target.url.starts_with("devtools://")
+        })
+        .map(|target| TabInfo {
+            id: target.target_id.as_ref().to_string(),
+            url: target.url.clone(),
+            title: target.title.clone(),
+            active: false,
+        })
+        .collect()
+}
+
+/// Entry in a tab's navigation history
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    /// Position in the history list
+    pub index: usize,
+    /// CDP entry ID used by Page.navigateToHistoryEntry
+    pub id: i64,
+    pub url: String,
+    pub title: String,
+    /// Whether this is the page currently shown
+    pub current: bool,
+}
+
+/// A condition on the page URL or title, for waits that survive redirects
+#[derive(Debug, Clone)]
+pub enum PageCondition {
+    UrlContains(String),
+    UrlMatches(regex::Regex),
+    TitleContains(String),
+}
+
+impl PageCondition {
+    /// Whether a page with this URL and title meets the condition
+    pub fn matches(&self, url: &str, title: &str) -> bool {
+        match self {
+            Self::UrlContains(text) => url.contains(text.as_str()),
+            Self::UrlMatches(pattern) => pattern.is_match(url),
+            Self::TitleContains(text) => title.contains(text.as_str()),
+        }
+    }
+}
+
+impl std::fmt::Display for PageCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UrlContains(text) => write!(f, "URL containing \"{}\"", text),
+            Self::UrlMatches(pattern) => write!(f, "URL matching /{}/", pattern),
+            Self::TitleContains(text) => write!(f, "title containing \"{}\"", text),
+        }
+    }
+}
+
+/// A readiness condition on an element that already exists
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ElementCondition {
+    /// Not disabled, not in a disabled fieldset, not aria-disabled
+    Enabled,
+    /// Bounding box unchanged for this many milliseconds
+    Stable(u64),
+    /// Attribute has exactly this value
+    Attribute(String, String),
+}
+
+impl ElementCondition {
+    /// Script whose result is compared between polls: a bool, or the box for `Stable`
+    ///
+    /// Evaluates to null while the element doesn't exist.
+    fn script(&self, selector: &str) -> String {
+        let check = match self {
+            Self::Enabled => "!el.matches(':disabled') && el.getAttribute('aria-disabled') !== 'true'"
+                .to_string(),
+            Self::Stable(_) => {
+                "(({ x, y, width, height }) => [x, y, width, height].join(','))(el.getBoundingClientRect())"
+                    .to_string()
+            }
+            Self::Attribute(name, value) => format!(
+                "el.getAttribute({}) === {}",
+                serde_json::Value::String(name.clone()),
+                serde_json::Value::String(value.clone())
+            ),
+        };
+        format!(
+            "(() => {{ const el = document.querySelector({}); return el ? {} : null; }})()",
+            serde_json::Value::String(selector.to_string()),
+            check
+        )
+    }
+}
+
+impl std::fmt::Display for ElementCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Enabled => write!(f, "enabled"),
+            Self::Stable(ms) => write!(f, "stable for {}ms", ms),
+            Self::Attribute(name, value) => write!(f, "{}=\"{}\"", name, value),
+        }
+    }
+}
+
+/// Lifecycle event that ends a navigation wait
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaitUntil {
+    /// The load event fired
+    #[default]
+    Load,
+    /// The DOMContentLoaded event fired
+    DomContentLoaded,
+    /// No network activity for 500ms
+    NetworkIdle,
+}
+
+impl WaitUntil {
+    /// Name of the matching Page.lifecycleEvent
+    fn event_name(self) -> &'static str {
+        match self {
+            Self::Load => "load",
+            Self::DomContentLoaded => "DOMContentLoaded",
+            Self::NetworkIdle => "networkIdle",
+        }
+    }
+}
+
+impl std::fmt::Display for WaitUntil {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Load => write!(f, "load"),
+            Self::DomContentLoaded => write!(f, "domcontentloaded"),
+            Self::NetworkIdle => write!(f, "networkidle"),
+        }
+    }
+}
+
+impl std::str::FromStr for WaitUntil {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "load" => Ok(Self::Load),
+            "domcontentloaded" => Ok(Self::DomContentLoaded),
+            "networkidle" => Ok(Self::NetworkIdle),
+            other => Err(format!(
+                "Unknown wait condition \"{}\" (expected load, domcontentloaded or networkidle)",
+                other
+            )),
+        }
+    }
+}
+
+/// A length for PDF layout, parsed into inches (bare numbers are inches)
+fn parse_length(value: &str) -> Result<f64, String> {
+    let value = value.trim().to_lowercase();
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid length \"{}\"", value))?;
+    let per_inch = match unit {
+        "" | "in" => 1.0,
+        "cm" => 2.54,
+        "mm" => 25.4,
+        "px" => 96.0,
+        other => {
+            return Err(format!(
+                "Unknown unit \"{}\" (expected in, cm, mm or px)",
+                other
+            ))
+        }
+    };
+    if number < 0.0 {
+        return Err(format!("Length \"{}\" is negative", value));
+    }
+    Ok(number / per_inch)
+}
+
+/// PDF paper size in inches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaperSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl std::str::FromStr for PaperSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = match s.to_lowercase().as_str() {
+            "letter" => (8.5, 11.0),
+            "legal" => (8.5, 14.0),
+            "tabloid" => (11.0, 17.0),
+            "a3" => (11.69, 16.54),
+            "a4" => (8.27, 11.69),
+            "a5" => (5.83, 8.27),
+            other => {
+                let (width, height) = other.split_once('x').ok_or_else(|| {
+                    format!(
+                        "Unknown paper \"{}\" (expected a3, a4, a5, letter, legal, tabloid or WxH like 210x297mm)",
+                        s
+                    )
+                })?;
+                // "210x297mm": the unit on the height applies to both
+                let unit: String = height
+                    .chars()
+                    .skip_while(|c| !c.is_ascii_alphabetic())
+                    .collect();
+                let width = if width.chars().any(|c| c.is_ascii_alphabetic()) {
+                    width.to_string()
+                } else {
+                    format!("{}{}", width, unit)
+                };
+                (parse_length(&width)?, parse_length(height)?)
+            }
+        };
+        if width <= 0.0 || height <= 0.0 {
+            return Err(format!("Paper size \"{}\" must be positive", s));
+        }
+        Ok(Self { width, height })
+    }
+}
+
+/// PDF page margins in inches, given like CSS: 1, 2 or 4 comma-separated lengths
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Margins {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+impl std::str::FromStr for Margins {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(parse_length)
+            .collect::<Result<Vec<_>, _>>()?;
+        match values[..] {
+            [all] => Ok(Self {
+                top: all,
+                right: all,
+                bottom: all,
+                left: all,
+            }),
+            [vertical, horizontal] => Ok(Self {
+                top: vertical,
+                right: horizontal,
+                bottom: vertical,
+                left: horizontal,
+            }),
+            [top, right, bottom, left] => Ok(Self {
+                top,
+                right,
+                bottom,
+                left,
+            }),
+            _ => Err(format!(
+                "Margins \"{}\" need 1, 2 or 4 values (top,right,bottom,left)",
+                s
+            )),
+        }
+    }
+}
+
+/// Page.printToPDF layout options
+#[derive(Debug, Clone, Default)]
+pub struct PdfOptions {
+    pub landscape: bool,
+    pub paper: Option<PaperSize>,
+    pub margins: Option<Margins>,
+    /// Rendering scale, 0.1 to 2
+    pub scale: Option<f64>,
+    /// HTML templates; either one turns on the header and footer
+    pub header_template: Option<String>,
+    pub footer_template: Option<String>,
+    /// Pages to print, such as "1-5, 8"
+    pub page_ranges: Option<String>,
+    /// Let CSS `@page` sizes win over `paper`
+    pub prefer_css_page_size: bool,
+}
+
+impl PdfOptions {
+    /// Check the options Chrome would reject
+    pub fn validate(&self) -> Result<()> {
+        if let Some(scale) = self.scale {
+            if !(0.1..=2.0).contains(&scale) {
+                return Err(anyhow!(
+                    "PDF scale must be between 0.1 and 2 (got {})",
+                    scale
+                ));
+            }
+        }
+        if let Some(ranges) = &self.page_ranges {
+            if !ranges
+                .chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, '-' | ',' | ' '))
+            {
+                return Err(anyhow!(
+                    "Invalid page ranges \"{}\" (expected e.g. 1-5, 8)",
+                    ranges
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn params(&self) -> Result<PrintToPdfParams> {
+        self.validate()?;
+        let mut params = PrintToPdfParams::builder()
+            .landscape(self.landscape)
+            .print_background(true)
+            .prefer_css_page_size(self.prefer_css_page_size);
+        if let Some(paper) = self.paper {
+            params = params.paper_width(paper.width).paper_height(paper.height);
+        }
+        if let Some(margins) = self.margins {
+            params = params
+                .margin_top(margins.top)
+                .margin_right(margins.right)
+                .margin_bottom(margins.bottom)
+                .margin_left(margins.left);
+        }
+        if let Some(scale) = self.scale {
+            params = params.scale(scale);
+        }
+        if self.header_template.is_some() || self.footer_template.is_some() {
+            // Chrome fills in its default for whichever template is missing
+            params = params.display_header_footer(true);
+            if let Some(header) = &self.header_template {
+                params = params.header_template(header.clone());
+            }
+            if let Some(footer) = &self.footer_template {
+                params = params.footer_template(footer.clone());
+            }
+        }
+        if let Some(ranges) = &self.page_ranges {
+            params = params.page_ranges(ranges.clone());
+        }
+        Ok(params.build())
+    }
+}
+
+/// A tab opened by the page DOMGuard was driving (popup or `target=_blank`)
+#[derive(Debug, Clone, Serialize)]
+pub struct PopupInfo {
+    pub tab_id: String,
+    pub url: String,
+}
+
+/// Popups seen by `watch_popups`, filled in the background
+pub type PopupLog = Arc<Mutex<Vec<String>>>;
+
+/// How long to wait after an action for a popup to be created
+const POPUP_GRACE_MS: u64 = 500;
+
+/// How long to wait for a followed popup to leave about:blank
+const POPUP_LOAD_MS: u64 = 3000;
+
+/// Page-side tracker behind `page_readiness` and `wait_for_route_change`
+///
+/// Counts in-flight fetch/XHR requests and client-side route changes
+/// (history.pushState/replaceState, popstate, hashchange) from the first
+/// time it is installed on a page; later calls reuse it.
+const READINESS_TRACKER: &str = r"(function() {
+    const key = Symbol.for('domguard.readiness');
+    if (window[key]) return window[key];
+    const state = {
+        installedAt: Date.now(),
+        pending: 0,
+        url: location.href,
+        routeChanges: 0,
+        // Route changes already reported by wait_for_route_change
+        seenRouteChanges: 0,
+        lastRouteChange: null,
+    };
+    window[key] = state;
+    const routeChanged = () => {
+        if (location.href === state.url) return;
+        state.url = location.href;
+        state.routeChanges++;
+        state.lastRouteChange = Date.now();
+    };
+    for (const name of ['pushState', 'replaceState']) {
+        const original = history[name];
+        history[name] = function(...args) {
+            const result = original.apply(this, args);
+            routeChanged();
+            return result;
+        };
+    }
+    window.addEventListener('popstate', routeChanged);
+    window.addEventListener('hashchange', routeChanged);
+    const fetch = window.fetch;
+    if (fetch) {
+        window.fetch = function(...args) {
+            state.pending++;
+            return fetch.apply(this, args).finally(() => state.pending--);
+        };
+    }
+    const send = XMLHttpRequest.prototype.send;
+    XMLHttpRequest.prototype.send = function(...args) {
+        state.pending++;
+        this.addEventListener('loadend', () => state.pending--, { once: true });
+        return send.apply(this, args);
+    };
+    return state;
+})()";
+
+/// Route changes count as settled this long after the last one
+const ROUTE_QUIET_MS: u64 = 500;
+
+/// Composite signal for whether a page is ready to automate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageReadiness {
+    /// document.readyState: loading, interactive or complete
+    pub ready_state: String,
+    /// fetch/XHR requests still running (counted since `tracking_ms` ago)
+    pub pending_requests: u32,
+    /// Framework seen on the page: react, next, vue or nuxt
+    pub framework: Option<String>,
+    /// Whether the framework has mounted/hydrated; none without a framework
+    pub hydrated: Option<bool>,
+    /// Client-side route changes since tracking started
+    pub route_changes: u32,
+    /// Time since the last client-side route change
+    pub ms_since_route_change: Option<u64>,
+    /// How long requests and route changes have been tracked on this page
+    pub tracking_ms: u64,
+    /// 0-100, see `PageReadiness::score`
+    #[serde(default)]
+    pub score: u8,
+    #[serde(default)]
+    pub ready: bool,
+}
+
+impl PageReadiness {
+    /// Weighted score: load state 40, no pending requests 30, hydration 20,
+    /// no route change in the last 500ms 10
+    pub fn score(&self) -> u8 {
+        let load = match self.ready_state.as_str() {
+            "complete" => 40,
+            "interactive" => 20,
+            _ => 0,
+        };
+        let network = if self.pending_requests == 0 { 30 } else { 0 };
+        let hydration = if self.hydrated == Some(false) { 0 } else { 20 };
+        let routes = match self.ms_since_route_change {
+            Some(ms) if ms < ROUTE_QUIET_MS => 0,
+            _ => 10,
+        };
+        load + network + hydration + routes
+    }
+}
+
+/// Page-side model of the table at (or inside) a selector
+///
+/// Understands `<table>` (header row from thead, or a leading all-`th` row)
+/// and ARIA grids (`role=row`, `columnheader`, `cell`/`gridcell`). Returns
+/// null without a match, `{ root: null }` without a table, and otherwise the
+/// header and body row elements plus `text` and `column` helpers; columns are
+/// 1-based numbers or header text.
+const TABLE_MODEL: &str = r"(function(selector) {
+    const found = document.querySelector(selector);
+    if (!found) return null;
+    const kinds = 'table, [role=grid], [role=table], [role=treegrid]';
+    const root = found.matches(kinds) ? found : found.querySelector(kinds);
+    if (!root) return { root: null };
+    const text = el => (el.innerText || el.textContent || '').replace(/\s+/g, ' ').trim();
+    const own = el => el.closest(kinds) === root;
+    let headers = [];
+    let rows = [];
+    if (root.tagName === 'TABLE') {
+        rows = [...root.rows].filter(r => own(r)
+            && !['THEAD', 'TFOOT'].includes(r.parentElement.tagName));
+        if (root.tHead && root.tHead.rows.length) {
+            headers = [...root.tHead.rows[root.tHead.rows.length - 1].cells];
+        } else if (rows.length && [...rows[0].cells].every(c => c.tagName === 'TH')) {
+            headers = [...rows.shift().cells];
+        }
+        rows = rows.map(r => ({ el: r, cells: [...r.cells] }));
+    } else {
+        headers = [...root.querySelectorAll('[role=columnheader]')].filter(own);
+        rows = [...root.querySelectorAll('[role=row]')]
+            .filter(r => own(r) && !r.querySelector('[role=columnheader]'))
+            .map(r => ({
+                el: r,
+                cells: [...r.querySelectorAll('[role=cell], [role=gridcell], [role=rowheader]')],
+            }));
+    }
+    const column = ref => {
+        if (/^\d+$/.test(ref)) return Number(ref) - 1;
+        const want = ref.toLowerCase();
+        const exact = headers.findIndex(h => text(h).toLowerCase() === want);
+        return exact >= 0 ? exact : headers.findIndex(h => text(h).toLowerCase().includes(want));
+    };
+    return { root, headers, rows, text, column };
+})";
+
+/// How long a table must stay unchanged after a re-render to count as settled
+const TABLE_SETTLE_MS: u64 = 200;
+
+/// Shape of a table as `interact table` sees it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableSnapshot {
+    pub headers: Vec<String>,
+    /// Body rows on the current page
+    pub rows: usize,
+    /// Sorted column and direction from aria-sort, e.g. "Name ascending"
+    pub sort: Option<String>,
+    /// Row count and leading rows' text, to notice re-renders
+    #[serde(skip_serializing)]
+    pub signature: String,
+}
+
+/// A body row found by `find_table_row`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableRow {
+    /// 1-based position among the body rows
+    pub index: usize,
+    pub headers: Vec<String>,
+    pub cells: Vec<String>,
+}
+
+/// Something in a table to click
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableTarget {
+    /// A body cell (1-based row; column number or header text)
+    Cell { row: usize, column: String },
+    /// A column header, to sort by it
+    Header(String),
+    /// The pagination control that moves to the next page
+    NextPage,
+}
+
+/// JS function `(iso, year, month, day, locale)` taking one step through an
+/// open calendar widget toward a date (`month` 0-based)
+///
+/// Finds the day by `data-date`-style attributes or an aria-label naming it,
+/// else reads the displayed month from the calendar heading and points at the
+/// next/previous month button. Month names are matched in the page locale and
+/// English.
+const CALENDAR_STEP: &str = r"(function(iso, year, month, day, locale) {
+    const visible = el => {
+        const r = el.getBoundingClientRect();
+        return r.width > 0 && r.height > 0;
+    };
+    const center = el => {
+        el.scrollIntoView({ block: 'nearest' });
+        const r = el.getBoundingClientRect();
+        return { x: r.x + r.width / 2, y: r.y + r.height / 2 };
+    };
+    const isDisabled = el => el.disabled || el.getAttribute('aria-disabled') === 'true'
+        || (typeof el.className === 'string' && /\bdisabled\b/.test(el.className));
+    const names = [];
+    for (const loc of [...new Set([locale, 'en'])]) {
+        for (const style of ['long', 'short']) {
+            try {
+                const f = new Intl.DateTimeFormat(loc, { month: style, timeZone: 'UTC' });
+                names.push([...Array(12).keys()].map(m =>
+                    f.format(new Date(Date.UTC(2000, m, 1))).toLowerCase().replace('.', '')));
+            } catch (e) {}
+        }
+    }
+    const monthIn = text => {
+        const words = text.toLowerCase().split(/[\s,.\/-]+/);
+        for (const list of names) {
+            const i = list.findIndex(n => words.includes(n));
+            if (i >= 0) return i;
+        }
+        return -1;
+    };
+
+    const attrs = ['data-date', 'data-day', 'data-value', 'data-iso', 'data-full-date', 'datetime'];
+    const dayPattern = new RegExp('(^|\\D)0?' + day + '(st|nd|rd|th)?(\\D|$)');
+    const candidates = document.querySelectorAll(
+        '[role=gridcell], td, button, [data-date], [data-day], [data-value], [data-iso], time');
+    for (const el of candidates) {
+        if (!visible(el)) continue;
+        const attr = attrs.map(a => el.getAttribute(a)).find(v => v && v.startsWith(iso));
+        const label = el.getAttribute('aria-label') || el.getAttribute('title') || '';
+        const labelled = label.includes(String(year)) && monthIn(label) === month
+            && dayPattern.test(label.replace(String(year), ''));
+        if (!attr && !labelled) continue;
+        const target = el.querySelector('button') || el;
+        if (isDisabled(target) || isDisabled(el)) {
+            return { kind: 'stuck', reason: 'the day is disabled in the calendar' };
+        }
+        return { kind: 'day', ...center(target) };
+    }
+
+    let shown = null;
+    const headings = document.querySelectorAll('[role=grid][aria-label], [aria-live], '
+        + '[role=heading], h1, h2, h3, h4, h5, h6, caption, th, '
+        + '[class*=month], [class*=caption], [class*=title], [class*=header]');
+    for (const el of headings) {
+        if (!visible(el)) continue;
+        const text = el.getAttribute('role') === 'grid'
+            ? el.getAttribute('aria-label') : (el.innerText || '');
+        if (!text || text.length > 40) continue;
+        const y = text.match(/\b(1[89]|2\d)\d\d\b/);
+        const m = monthIn(text);
+        if (y && m >= 0) {
+            shown = Number(y[0]) * 12 + m;
+            break;
+        }
+    }
+    if (shown === null) return { kind: 'stuck', reason: 'no open calendar found' };
+    const diff = year * 12 + month - shown;
+    if (diff === 0) return { kind: 'stuck', reason: 'the day is not in the displayed month' };
+    const forward = diff > 0;
+    const want = forward ? /next|›|»|→|>/i : /prev|‹|«|←|</i;
+    const nav = [...document.querySelectorAll('button, a, [role=button]')].find(el => {
+        if (!visible(el) || isDisabled(el)) return false;
+        const label = [el.getAttribute('aria-label'), el.getAttribute('title'), el.innerText]
+            .filter(Boolean).join(' ').trim();
+        return label.length < 30 && want.test(label) && !/year/i.test(label);
+    });
+    if (!nav) {
+        return { kind: 'stuck', reason: 'no ' + (forward ? 'next' : 'previous') + '-month button' };
+    }
+    return { kind: 'nav', forward, ...center(nav) };
+})";
+
+/// The input `interact date` is filling
+#[derive(Debug, Clone, Deserialize)]
+pub struct DateInputInfo {
+    /// Lowercase `type` attribute (`text` without one)
+    pub input_type: String,
+    pub value: String,
+    pub placeholder: String,
+    pub read_only: bool,
+    /// Page locale: `<html lang>`, else the browser's
+    pub locale: String,
+    /// The locale's numeric date order, e.g. "MM/DD/YYYY"
+    pub locale_pattern: String,
+}
+
+/// One step of `calendar_step`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CalendarStep {
+    /// The day to click
+    Day { x: f64, y: f64 },
+    /// The month button to click to get closer
+    Nav { x: f64, y: f64, forward: bool },
+    /// Nothing to click
+    Stuck { reason: String },
+}
+
+/// JS function `(selector)` returning the text input of a combobox: the
+/// element itself, or the input inside a wrapper (react-select, MUI, downshift)
+const COMBOBOX_INPUT: &str = r"(function(selector) {
+    const el = document.querySelector(selector);
+    if (!el) return null;
+    if (el.matches('input, textarea, [contenteditable=true]')) return el;
+    return el.querySelector('input:not([type=hidden]), [role=combobox], [contenteditable=true]') || el;
+})";
+
+/// An entry in an open combobox list
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ComboOption {
+    pub text: String,
+    pub x: f64,
+    pub y: f64,
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+/// The option list a combobox shows
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ComboOptions {
+    pub options: Vec<ComboOption>,
+    /// The list says it is still loading (aria-busy or a "Loading" notice)
+    pub loading: bool,
+}
+
+/// JS function called on the focused element (`this`), returning what
+/// `FocusStop` reads from the page
+const FOCUS_STOP: &str = r"function() {
+    const el = this;
+    const selectorFor = node => {
+        const unique = id => node.getRootNode().querySelectorAll('#' + CSS.escape(id)).length === 1;
+        if (node.id && unique(node.id)) return '#' + CSS.escape(node.id);
+        const parts = [];
+        for (let current = node; current && current.nodeType === 1; current = current.parentElement) {
+            if (current !== node && current.id) {
+                parts.unshift('#' + CSS.escape(current.id));
+                break;
+            }
+            let part = current.tagName.toLowerCase();
+            const parent = current.parentElement;
+            if (parent) {
+                const same = [...parent.children].filter(c => c.tagName === current.tagName);
+                if (same.length > 1) part += ':nth-of-type(' + (same.indexOf(current) + 1) + ')';
+            }
+            parts.unshift(part);
+            if (part === 'html') break;
+        }
+        return parts.join(' > ');
+    };
+    const style = getComputedStyle(el);
+    let indicator = null;
+    if (style.outlineStyle !== 'none' && parseFloat(style.outlineWidth) > 0) {
+        indicator = 'outline ' + style.outlineWidth + ' ' + style.outlineStyle + ' ' + style.outlineColor;
+    } else if (style.boxShadow && style.boxShadow !== 'none') {
+        indicator = 'box-shadow ' + style.boxShadow;
+    }
+    const text = (el.innerText || el.value || '').trim().replace(/\s+/g, ' ').slice(0, 80);
+    return {
+        selector: selectorFor(el),
+        tag: el.tagName.toLowerCase(),
+        role: el.getAttribute('role'),
+        name: el.getAttribute('aria-label') || el.getAttribute('title') || text || null,
+        focus_visible: el.matches(':focus-visible'),
+        indicator,
+    };
+}";
+
+/// JS function `(selector, nth)` that scrolls the nth match into view and finds
+/// a point where a click reaches it
+///
+/// Returns `{ found, strategy, x, y }`: strategy "center", "scroll-offset (...)",
+/// "corner" or "unoccluded-point"; "script" once it has clicked a box-less
+/// element itself; none (with `blocker`) when every point is covered.
+const CLICK_TARGET: &str = r"function(selector, nth) {
+    const els = document.querySelectorAll(selector);
+    if (els.length === 0) return { found: false, count: 0 };
+    const index = nth < 0 ? els.length + nth : nth;
+    if (index < 0 || index >= els.length) return { found: false, count: els.length, index };
+    const el = els[index];
+
+    el.scrollIntoView({ block: 'center', inline: 'center' });
+    let rect = el.getBoundingClientRect();
+    if (rect.width === 0 || rect.height === 0) {
+        el.click();
+        return { found: true, strategy: 'script' };
+    }
+
+    const reaches = (x, y) => {
+        if (x < 0 || y < 0 || x >= window.innerWidth || y >= window.innerHeight) return false;
+        const hit = document.elementFromPoint(x, y);
+        return !!hit && (hit === el || el.contains(hit));
+    };
+    const center = () => {
+        rect = el.getBoundingClientRect();
+        return [rect.left + rect.width / 2, rect.top + rect.height / 2];
+    };
+    const describe = node => node
+        ? node.tagName.toLowerCase() + (node.id ? '#' + node.id : '')
+            + [...node.classList].slice(0, 2).map(c => '.' + c).join('')
+        : null;
+
+    let [x, y] = center();
+    if (reaches(x, y)) return { found: true, strategy: 'center', x, y };
+    const blocker = describe(document.elementFromPoint(x, y));
+
+    // Fixed and sticky bars along the top and bottom edges
+    let header = 0;
+    let footer = 0;
+    for (const bar of document.body.querySelectorAll('*')) {
+        const position = getComputedStyle(bar).position;
+        if (position !== 'fixed' && position !== 'sticky') continue;
+        const box = bar.getBoundingClientRect();
+        if (box.height === 0 || box.height > window.innerHeight / 2) continue;
+        if (box.top <= 1) header = Math.max(header, box.bottom);
+        if (box.bottom >= window.innerHeight - 1) footer = Math.max(footer, window.innerHeight - box.top);
+    }
+    const scrolls = [
+        ['header', () => { el.scrollIntoView({ block: 'start' }); window.scrollBy(0, -(header + 8)); }],
+        ['footer', () => { el.scrollIntoView({ block: 'end' }); window.scrollBy(0, footer + 8); }],
+    ];
+    for (const [edge, scroll] of scrolls) {
+        if ((edge === 'header' ? header : footer) === 0) continue;
+        scroll();
+        [x, y] = center();
+        if (reaches(x, y)) {
+            const offset = Math.round(edge === 'header' ? header : footer);
+            return { found: true, strategy: 'scroll-offset (' + edge + ' ' + offset + 'px)', x, y };
+        }
+    }
+
+    el.scrollIntoView({ block: 'center', inline: 'center' });
+    center();
+    const inset = Math.min(3, rect.width / 4, rect.height / 4);
+    const corners = [
+        [rect.left + inset, rect.top + inset], [rect.right - inset, rect.top + inset],
+        [rect.left + inset, rect.bottom - inset], [rect.right - inset, rect.bottom - inset],
+    ];
+    for (const [cx, cy] of corners) {
+        if (reaches(cx, cy)) return { found: true, strategy: 'corner', x: cx, y: cy };
+    }
+    for (let i = 1; i < 6; i++) {
+        for (let j = 1; j < 6; j++) {
+            const px = rect.left + rect.width * i / 6;
+            const py = rect.top + rect.height * j / 6;
+            if (reaches(px, py)) return { found: true, strategy: 'unoccluded-point', x: px, y: py };
+        }
+    }
+    return { found: true, strategy: null, blocker };
+}";
+
+/// What converting between coordinate spaces needs: the scroll offset, the
+/// viewport size and an element's top-left, all in CSS pixels
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct CoordinateOrigin {
+    pub scroll_x: f64,
+    pub scroll_y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Viewport position of the element's border box
+    pub element: Option<(f64, f64)>,
+}
+
+/// The focused element at one stop of `interact tab-walk`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FocusStop {
+    pub selector: String,
+    pub tag: String,
+    /// Computed accessible role and name (the page's own attributes if the
+    /// accessibility tree can't be read)
+    pub role: Option<String>,
+    pub name: Option<String>,
+    /// Matches `:focus-visible`
+    pub focus_visible: bool,
+    /// Outline or box-shadow drawn around it, if any
+    pub indicator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screenshot: Option<String>,
+}
+
+/// A canvas's bitmap as `debug canvas` reads it
+#[derive(Debug, Clone)]
+pub struct CanvasBitmap {
+    /// Bitmap size (not the CSS size)
+    pub width: u32,
+    pub height: u32,
+    /// The encoded image, when the canvas could be read directly
+    pub data: Option<Vec<u8>>,
+    /// Why it couldn't be (tainted, blank WebGL buffer, offscreen)
+    pub reason: Option<String>,
+    /// Where the canvas is on the page, to screenshot instead
+    pub clip: ClipRect,
+}
+
+/// A browser cookie as `auth login` saves it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedCookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    pub secure: bool,
+    pub http_only: bool,
+    /// "Strict", "Lax" or "None"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<String>,
+    /// Seconds since the epoch; none for session cookies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<f64>,
+}
+
+/// Requests in flight since `watch_network` started, filled in the background
+#[derive(Debug)]
+pub struct NetworkActivity {
+    pending: HashSet<String>,
+    changed: std::time::Instant,
+}
+
+/// Shared handle on the activity `watch_network` records
+pub type NetworkWatch = Arc<Mutex<NetworkActivity>>;
+
+/// How long the network has to stay quiet to count as idle
+const NETWORK_IDLE_MS: u64 = 500;
+
+/// JavaScript dialog reported by Page.javascriptDialogOpening
+#[derive(Debug, Clone, Serialize)]
+pub struct DialogInfo {
+    /// Dialog type: alert, confirm, prompt or beforeunload
+    pub dialog_type: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_prompt: Option<String>,
+    pub url: String,
+}
+
+/// Dialogs answered in the background by `auto_handle_dialogs`
+pub type DialogLog = Arc<Mutex<Vec<DialogInfo>>>;
+
+impl DialogInfo {
+    fn from_event(
+        event: &chromiumoxide::cdp::browser_protocol::page::EventJavascriptDialogOpening,
+    ) -> Self {
+        Self {
+            dialog_type: event.r#type.as_ref().to_string(),
+            message: event.message.clone(),
+            default_prompt: event.default_prompt.clone().filter(|p| !p.is_empty()),
+            url: event.url.clone(),
+        }
+    }
+}
+
+/// How long `navigate` waits for the load event
+const NAVIGATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The command ran past the global `--timeout`
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandTimeout {
+    pub timeout_ms: u64,
+}
+
+impl std::fmt::Display for CommandTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Command timed out after {}ms (--timeout)",
+            self.timeout_ms
+        )
+    }
+}
+
+impl std::error::Error for CommandTimeout {}
+
 /// CDP connection manager
 pub struct CdpConnection {
     config: Config,
     browser: Option<Arc<Mutex<Browser>>>,
+    /// How long the last `connect` took
+    connect_ms: Option<u64>,
+    /// When the command's `--timeout` runs out; waits never go past it
+    deadline: Option<std::time::Instant>,
 }
 
 impl CdpConnection {
@@ -38,16 +1036,44 @@ impl CdpConnection {
         Self {
             config,
             browser: None,
+            connect_ms: None,
+            deadline: None,
+        }
+    }
+
+    /// Stop every wait at `deadline`, so a command fits its `--timeout`
+    pub fn set_deadline(&mut self, deadline: Option<std::time::Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// When the command's `--timeout` runs out, if it has one
+    pub fn deadline(&self) -> Option<std::time::Instant> {
+        self.deadline
+    }
+
+    /// `timeout_ms`, cut short to what is left before the deadline
+    pub fn bounded(&self, timeout_ms: u64) -> u64 {
+        match self.deadline {
+            Some(deadline) => {
+                let left = deadline.saturating_duration_since(std::time::Instant::now());
+                timeout_ms.min(left.as_millis() as u64)
+            }
+            None => timeout_ms,
         }
     }
 
+    /// The configuration this connection was created with
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
     /// Check if connection is to localhost (security check)
     pub fn validate_security(&self) -> Result<()> {
-        if !self.config.is_localhost() {
+        if !self.config.is_localhost() && !self.config.chrome.allow_remote {
             return Err(anyhow!(
                 "Security: Non-localhost connections are blocked by default.\n\
                  Remote Chrome connections have security risks (no encryption, no auth).\n\
-                 See SECURITY.md for safe remote connection setup.\n\
+                 See SECURITY.md for safe remote connection setup (or use 'domguard tunnel').\n\
                  Use --allow-remote flag to proceed anyway."
             ));
         }
@@ -56,9 +1082,31 @@ impl CdpConnection {
 
     /// Connect to Chrome DevTools, launching Chrome if needed
     pub async fn connect(&mut self) -> Result<()> {
+        let started = std::time::Instant::now();
+        self.open_browser(true).await?;
+        self.connect_ms = Some(started.elapsed().as_millis() as u64);
+        Ok(())
+    }
+
+    /// Connect to a Chrome that is already running, never launching one
+    pub async fn attach(&mut self) -> Result<()> {
+        self.open_browser(false).await
+    }
+
+    /// How long connecting took, including discovery and any Chrome launch
+    pub fn connect_ms(&self) -> Option<u64> {
+        self.connect_ms
+    }
+
+    async fn open_browser(&mut self, launch: bool) -> Result<()> {
         self.validate_security()?;
 
-        let ws_url = self.config.ws_url();
+        // Ask Chrome where its WebSocket endpoint is, else use the configured address
+        let limit = Duration::from_millis(self.bounded(self.config.defaults.timeout_ms));
+        let ws_url =
+            crate::discovery::off_runtime(&self.config, limit, crate::discovery::discover_ws_url)
+                .await
+                .unwrap_or_else(|_| self.config.ws_url());
 
         // Try to connect to existing Chrome first
         match Browser::connect(&ws_url).await {
@@ -72,6 +1120,18 @@ impl CdpConnection {
                 self.browser = Some(Arc::new(Mutex::new(browser)));
                 return Ok(());
             }
+            Err(e) if !launch => {
+                return Err(anyhow!("Failed to connect to Chrome: {}", e));
+            }
+            Err(e) if !self.config.is_localhost() => {
+                // A remote Chrome can't be launched from here
+                return Err(anyhow!(
+                    "Failed to connect to remote Chrome at {}:{}: {}",
+                    self.config.chrome.host,
+                    self.config.chrome.port,
+                    e
+                ));
+            }
             Err(_) => {
                 // Chrome not running, launch it
                 self.launch_chrome().await?;
@@ -122,7 +1182,7 @@ impl CdpConnection {
     }
 
     /// Find Chrome executable on the system
-    fn find_chrome() -> Result<std::path::PathBuf> {
+    pub fn find_chrome() -> Result<std::path::PathBuf> {
         let candidates = [
             "google-chrome-stable",
             "google-chrome",
@@ -169,36 +1229,12 @@ impl CdpConnection {
         // Small delay as docs recommend - pages may not be immediately ready
         tokio::time::sleep(std::time::Duration::from_millis(50)).await;
 
-        // Find the best target: prefer real URLs over about:blank, skip chrome:// pages
-        let mut best_target = None;
-        let mut fallback_target = None;
-
-        for target in &targets {
-            if target.r#type != "page" {
-                continue;
-            }
-            // Skip internal Chrome pages and extensions
-            if target.url.starts_with("chrome://")
-                || target.url.starts_with("chrome-extension://")
-                || target.url.starts_with("devtools://")
-            {
-                continue;
-            }
-            // Prefer pages with real URLs over about:blank
-            if target.url != "about:blank" && !target.url.is_empty() {
-                best_target = Some(target.target_id.clone());
-                break;
-            }
-            // Keep about:blank as fallback
-            if fallback_target.is_none() {
-                fallback_target = Some(target.target_id.clone());
-            }
-        }
-
-        // Use best target or fallback
-        if let Some(target_id) = best_target.or(fallback_target) {
+        // The tab picked with `tabs switch`, else prefer real URLs over about:blank
+        let tabs = page_tabs(&targets);
+        if let Some(index) = choose_tab(&tabs, read_active_tab().as_deref()) {
+            use chromiumoxide::cdp::browser_protocol::target::TargetId;
             return browser
-                .get_page(target_id)
+                .get_page(TargetId::from(tabs[index].id.clone()))
                 .await
                 .context("Failed to attach to page");
         }
@@ -225,62 +1261,155 @@ impl CdpConnection {
 
         let targets = browser.fetch_targets().await?;
 
-        let mut tabs = Vec::new();
-        for target in &targets {
-            if target.r#type != "page" {
-                continue;
-            }
-            // Skip internal Chrome pages
-            if target.url.starts_with("chrome://")
-                || target.url.starts_with("chrome-extension://")
-                || target.url.starts_with("devtools://")
-            {
-                continue;
-            }
-            tabs.push(TabInfo {
-                id: target.target_id.as_ref().to_string(),
-                url: target.url.clone(),
-                title: target.title.clone(),
-            });
+        let mut tabs = page_tabs(&targets);
+        if let Some(index) = choose_tab(&tabs, read_active_tab().as_deref()) {
+            tabs[index].active = true;
         }
         Ok(tabs)
     }
 
-    /// Create a new tab
+    /// Create a new tab and make it the active one
     pub async fn new_tab(&self, url: Option<&str>) -> Result<String> {
+        Ok(self.new_tab_in(url, false, false).await?.0)
+    }
+
+    /// Create a new tab, optionally in a fresh browser context and/or its own window
+    ///
+    /// Returns the tab id and, with `incognito`, the new context's id. The
+    /// context outlives this connection until `contexts dispose`.
+    pub async fn new_tab_in(
+        &self,
+        url: Option<&str>,
+        incognito: bool,
+        new_window: bool,
+    ) -> Result<(String, Option<String>)> {
+        use chromiumoxide::cdp::browser_protocol::target::{
+            CreateBrowserContextParams, CreateTargetParams,
+        };
+
         let browser = self
             .browser
             .as_ref()
             .ok_or_else(|| anyhow!("Not connected to Chrome"))?;
         let browser = browser.lock().await;
 
+        let mut params = CreateTargetParams::builder().url(url.unwrap_or("about:blank"));
+        let context_id = if incognito {
+            let context = browser
+                .execute(
+                    CreateBrowserContextParams::builder()
+                        .dispose_on_detach(false)
+                        .build(),
+                )
+                .await
+                .context("Failed to create browser context")?
+                .result
+                .browser_context_id;
+            params = params.browser_context_id(context.clone());
+            Some(context.as_ref().to_string())
+        } else {
+            None
+        };
+        if new_window {
+            params = params.new_window(true);
+        }
+
         let page = browser
-            .new_page(url.unwrap_or("about:blank"))
+            .new_page(params.build().map_err(|e| anyhow!(e))?)
             .await
             .context("Failed to create new tab")?;
 
         let target_id = page.target_id().as_ref().to_string();
-        Ok(target_id)
+        write_active_tab(Some(&target_id))?;
+        Ok((target_id, context_id))
     }
 
-    /// Switch to a specific tab by ID
-    pub async fn switch_tab(&self, target_id: &str) -> Result<()> {
+    /// Browser contexts created besides the default one, with their tabs
+    pub async fn list_browser_contexts(&self) -> Result<Vec<BrowserContextInfo>> {
+        use chromiumoxide::cdp::browser_protocol::target::GetBrowserContextsParams;
+
         let browser = self
             .browser
             .as_ref()
             .ok_or_else(|| anyhow!("Not connected to Chrome"))?;
-        let browser = browser.lock().await;
-
-        use chromiumoxide::cdp::browser_protocol::target::{ActivateTargetParams, TargetId};
-        let tid = TargetId::from(target_id.to_string());
+        let mut browser = browser.lock().await;
 
-        // Use CDP command directly to activate the target
-        browser
-            .execute(ActivateTargetParams::new(tid))
+        let ids = browser
+            .execute(GetBrowserContextsParams::default())
             .await
-            .context("Failed to switch to tab")?;
+            .context("Failed to list browser contexts")?
+            .result
+            .browser_context_ids;
+        let targets = browser.fetch_targets().await?;
 
-        Ok(())
+        Ok(ids
+            .into_iter()
+            .map(|id| {
+                let owned: Vec<_> = targets
+                    .iter()
+                    .filter(|target| target.browser_context_id.as_ref() == Some(&id))
+                    .cloned()
+                    .collect();
+                BrowserContextInfo {
+                    id: id.as_ref().to_string(),
+                    tabs: page_tabs(&owned),
+                }
+            })
+            .collect())
+    }
+
+    /// Close a browser context and every tab in it
+    pub async fn dispose_browser_context(&self, context_id: &str) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::browser::BrowserContextId;
+        use chromiumoxide::cdp::browser_protocol::target::DisposeBrowserContextParams;
+
+        let browser = self
+            .browser
+            .as_ref()
+            .ok_or_else(|| anyhow!("Not connected to Chrome"))?;
+        let browser = browser.lock().await;
+
+        browser
+            .execute(DisposeBrowserContextParams::new(BrowserContextId::from(
+                context_id.to_string(),
+            )))
+            .await
+            .with_context(|| format!("Failed to dispose browser context {}", context_id))?;
+
+        Ok(())
+    }
+
+    /// Open a tab's URL in a new tab (history and page state aren't copied)
+    pub async fn duplicate_tab(&self, target_id: &str) -> Result<String> {
+        let url = self
+            .list_tabs()
+            .await?
+            .into_iter()
+            .find(|tab| tab.id == target_id)
+            .map(|tab| tab.url)
+            .ok_or_else(|| anyhow!("No tab with id {}", target_id))?;
+        self.new_tab(Some(&url)).await
+    }
+
+    /// Bring a tab to the front and run later commands against it
+    pub async fn switch_tab(&self, target_id: &str) -> Result<()> {
+        let browser = self
+            .browser
+            .as_ref()
+            .ok_or_else(|| anyhow!("Not connected to Chrome"))?;
+        let browser = browser.lock().await;
+
+        use chromiumoxide::cdp::browser_protocol::target::{ActivateTargetParams, TargetId};
+        let tid = TargetId::from(target_id.to_string());
+
+        // Use CDP command directly to activate the target
+        browser
+            .execute(ActivateTargetParams::new(tid))
+            .await
+            .context("Failed to switch to tab")?;
+        write_active_tab(Some(target_id))?;
+
+        Ok(())
     }
 
     /// Close a tab by ID
@@ -299,6 +1428,9 @@ impl CdpConnection {
             .execute(CloseTargetParams::new(tid))
             .await
             .context("Failed to close tab")?;
+        if read_active_tab().as_deref() == Some(target_id) {
+            write_active_tab(None)?;
+        }
 
         Ok(())
     }
@@ -338,6 +1470,78 @@ impl CdpConnection {
             .context("Failed to decode screenshot data")
     }
 
+    /// Capture a screenshot with a format, quality or clip
+    pub async fn capture(&self, options: &CaptureOptions) -> Result<Vec<u8>> {
+        use chromiumoxide::cdp::browser_protocol::page::{CaptureScreenshotFormat, Viewport};
+
+        let page = self.get_page().await?;
+
+        let mut params = CaptureScreenshotParams::builder().format(match options.format {
+            ImageFormat::Png => CaptureScreenshotFormat::Png,
+            ImageFormat::Jpeg => CaptureScreenshotFormat::Jpeg,
+            ImageFormat::Webp => CaptureScreenshotFormat::Webp,
+        });
+        if let Some(quality) = options.quality {
+            params = params.quality(i64::from(quality));
+        }
+        if let Some(clip) = options.clip {
+            // Beyond the viewport, so clips taller than the window come out whole
+            params = params.capture_beyond_viewport(true).clip(Viewport {
+                x: clip.x,
+                y: clip.y,
+                width: clip.width,
+                height: clip.height,
+                scale: 1.0,
+            });
+        } else if options.full_page {
+            params = params.capture_beyond_viewport(true);
+        }
+
+        let data = page
+            .execute(params.build())
+            .await
+            .context("Failed to capture screenshot")?;
+
+        base64::engine::general_purpose::STANDARD
+            .decode(&data.data)
+            .context("Failed to decode screenshot data")
+    }
+
+    /// Capture a small JPEG of the viewport, scaled down to `max_width` pixels wide
+    pub async fn thumbnail(&self, max_width: u32) -> Result<Vec<u8>> {
+        use chromiumoxide::cdp::browser_protocol::page::{CaptureScreenshotFormat, Viewport};
+
+        let page = self.get_page().await?;
+
+        let size = self
+            .evaluate("({ width: window.innerWidth, height: window.innerHeight })")
+            .await?;
+        let width = size["width"].as_f64().unwrap_or(1280.0).max(1.0);
+        let height = size["height"].as_f64().unwrap_or(720.0).max(1.0);
+
+        let clip = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+            scale: (f64::from(max_width) / width).min(1.0),
+        };
+        let params = CaptureScreenshotParams::builder()
+            .format(CaptureScreenshotFormat::Jpeg)
+            .quality(60)
+            .clip(clip)
+            .build();
+
+        let data = page
+            .execute(params)
+            .await
+            .context("Failed to capture thumbnail")?;
+
+        base64::engine::general_purpose::STANDARD
+            .decode(&data.data)
+            .context("Failed to decode screenshot data")
+    }
+
     /// Execute JavaScript and return result
     pub async fn evaluate(&self, expression: &str) -> Result<serde_json::Value> {
         let page = self.get_page().await?;
@@ -350,43 +1554,147 @@ impl CdpConnection {
         Ok(result.value().cloned().unwrap_or(serde_json::Value::Null))
     }
 
-    /// Navigate to URL with extended timeout
+    /// Evaluate JavaScript, optionally in an isolated world or with top-level await
+    ///
+    /// The isolated world shares the DOM but not globals, so page scripts
+    /// can't observe or tamper with the evaluation. `top_level_await` runs in
+    /// REPL mode, as the DevTools console does. Returned promises are awaited
+    /// either way. Results JSON can't carry (nodes, maps, class instances)
+    /// are expanded through `Runtime.getProperties` down to `depth` levels.
+    pub async fn evaluate_in(
+        &self,
+        expression: &str,
+        options: &EvalOptions,
+    ) -> Result<serde_json::Value> {
+        use chromiumoxide::cdp::browser_protocol::page::CreateIsolatedWorldParams;
+
+        let page = self.get_page().await?;
+
+        let mut params = runtime::EvaluateParams::builder()
+            .expression(expression)
+            .return_by_value(false)
+            .object_group(EVAL_OBJECT_GROUP)
+            .await_promise(true)
+            .repl_mode(options.top_level_await);
+        if options.isolated {
+            let frame_id = page
+                .mainframe()
+                .await?
+                .ok_or_else(|| anyhow!("Page has no main frame"))?;
+            let world = page
+                .execute(
+                    CreateIsolatedWorldParams::builder()
+                        .frame_id(frame_id)
+                        .world_name("domguard")
+                        .build()
+                        .map_err(|e| anyhow!(e))?,
+                )
+                .await
+                .context("Failed to create isolated world")?;
+            params = params.context_id(world.result.execution_context_id);
+        }
+
+        let response = page
+            .execute(params.build().map_err(|e| anyhow!(e))?)
+            .await
+            .context("Failed to evaluate JavaScript")?;
+        if let Some(details) = &response.result.exception_details {
+            let message = details
+                .exception
+                .as_ref()
+                .and_then(|e| e.description.clone())
+                .unwrap_or_else(|| details.text.clone());
+            return Err(anyhow!("JavaScript error: {}", message));
+        }
+
+        let value = remote_to_json(&page, &response.result.result, options.depth).await;
+        let _ = page
+            .execute(runtime::ReleaseObjectGroupParams::new(EVAL_OBJECT_GROUP))
+            .await;
+        value
+    }
+
+    /// Navigate to URL, waiting for the page's load event
     pub async fn navigate(&self, url: &str) -> Result<()> {
-        // Ensure we have a page to work with
-        let _ = self.get_or_create_page().await?;
+        self.navigate_with(url, WaitUntil::Load, None, NAVIGATION_TIMEOUT)
+            .await
+    }
+
+    /// Navigate to URL with CDP Page.navigate and wait for a lifecycle event
+    ///
+    /// Lifecycle events are tied to the navigation's loader, so redirects and
+    /// SPA pages that keep the old document around don't end the wait early.
+    pub async fn navigate_with(
+        &self,
+        url: &str,
+        wait_until: WaitUntil,
+        referrer: Option<&str>,
+        timeout: Duration,
+    ) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::page::{
+            EnableParams, EventLifecycleEvent, NavigateParams, SetLifecycleEventsEnabledParams,
+        };
 
-        // Use JavaScript navigation with our own timeout handling
-        // This avoids chromiumoxide's default timeout which can be too short
-        let escaped_url = url.replace('\\', "\\\\").replace('\'', "\\'");
+        let timeout = Duration::from_millis(self.bounded(timeout.as_millis() as u64));
+        let page = self.get_or_create_page().await?;
 
-        // Start navigation
-        self.evaluate(&format!("window.location.href = '{}'", escaped_url))
-            .await?;
+        // Listen before navigating so early lifecycle events are not missed
+        let mut lifecycle = page.event_listener::<EventLifecycleEvent>().await?;
+        page.execute(EnableParams::default())
+            .await
+            .context("Failed to enable page domain")?;
+        page.execute(SetLifecycleEventsEnabledParams::new(true))
+            .await
+            .context("Failed to enable lifecycle events")?;
+
+        let mut builder = NavigateParams::builder().url(url);
+        if let Some(referrer) = referrer {
+            builder = builder.referrer(referrer);
+        }
+        let params = builder
+            .build()
+            .map_err(|e| anyhow!("Failed to build navigate params: {}", e))?;
+
+        let timeout_error = || {
+            anyhow!(
+                "Navigation timeout after {}ms waiting for {}",
+                timeout.as_millis(),
+                wait_until
+            )
+        };
 
-        // Wait for navigation to complete (check document.readyState)
-        let timeout = Duration::from_secs(30);
         let start = std::time::Instant::now();
+        let navigation = tokio::time::timeout(timeout, page.execute(params))
+            .await
+            .map_err(|_| timeout_error())?
+            .context("Failed to navigate")?;
 
-        loop {
-            if start.elapsed() > timeout {
-                return Err(anyhow!("Navigation timeout after 30s"));
-            }
+        if let Some(error) = &navigation.result.error_text {
+            return Err(anyhow!("Navigation to {} failed: {}", url, error));
+        }
 
-            // Check if we've navigated and page is ready
-            let ready_state = self.evaluate("document.readyState").await?;
-            let current_url = self.evaluate("window.location.href").await?;
+        // Same-document navigations (hash changes) have no loader to wait for
+        let Some(loader_id) = navigation.result.loader_id.clone() else {
+            return Ok(());
+        };
+        let frame_id = navigation.result.frame_id.clone();
 
-            if let (Some(state), Some(cur)) = (ready_state.as_str(), current_url.as_str()) {
-                // Check if we're on the target URL (or redirected) and page is loaded
-                if (cur.starts_with(url) || cur != "about:blank")
-                    && (state == "complete" || state == "interactive")
+        let wait = async {
+            while let Some(event) = lifecycle.next().await {
+                if event.frame_id == frame_id
+                    && event.loader_id == loader_id
+                    && event.name == wait_until.event_name()
                 {
                     return Ok(());
                 }
             }
+            Err(anyhow!("Page closed during navigation"))
+        };
 
-            tokio::time::sleep(Duration::from_millis(100)).await;
-        }
+        let remaining = timeout.saturating_sub(start.elapsed());
+        tokio::time::timeout(remaining, wait)
+            .await
+            .map_err(|_| timeout_error())?
     }
 
     /// Get current URL
@@ -457,6 +1765,71 @@ impl CdpConnection {
         Err(anyhow!("No element matches selector \"{}\"", selector))
     }
 
+    /// Click an element with a trusted click at a point that reaches it
+    ///
+    /// Hit-tests the element's center first. When something else is on top
+    /// (sticky headers, banners), tries scrolling it clear of fixed headers
+    /// and footers, then its corners, then any unoccluded point of its rect.
+    /// Returns the fallback that worked, `None` when the center was clear.
+    /// Elements without a box are clicked from script as before.
+    pub async fn click_unoccluded(&self, selector: &str, nth: i32) -> Result<Option<String>> {
+        let found = self
+            .evaluate(&format!(
+                "({})({}, {})",
+                CLICK_TARGET,
+                serde_json::json!(selector),
+                nth
+            ))
+            .await?;
+
+        if found.get("found").and_then(serde_json::Value::as_bool) != Some(true) {
+            let count = found
+                .get("count")
+                .and_then(serde_json::Value::as_i64)
+                .unwrap_or(0);
+            if count == 0 {
+                return Err(anyhow!("No element matches selector \"{}\"", selector));
+            }
+            let index = found
+                .get("index")
+                .and_then(serde_json::Value::as_i64)
+                .unwrap_or(i64::from(nth));
+            return Err(anyhow!(
+                "Index {} out of bounds, found {} element(s) matching \"{}\"",
+                index,
+                count,
+                selector
+            ));
+        }
+
+        let strategy = found
+            .get("strategy")
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string);
+        let point = found
+            .get("x")
+            .and_then(serde_json::Value::as_f64)
+            .zip(found.get("y").and_then(serde_json::Value::as_f64));
+        match (strategy, point) {
+            (Some(strategy), _) if strategy == "script" => Ok(Some(strategy)),
+            (Some(strategy), Some((x, y))) => {
+                self.dispatch_click_at(x, y, "left", 1).await?;
+                Ok((strategy != "center").then_some(strategy))
+            }
+            _ => {
+                let blocker = found
+                    .get("blocker")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("another element");
+                Err(anyhow!(
+                    "Click on \"{}\" intercepted: covered by {} at every point, even after scrolling",
+                    selector,
+                    blocker
+                ))
+            }
+        }
+    }
+
     /// Click element by visible text content
     /// Useful for dynamic dropdowns and elements without stable CSS selectors
     pub async fn click_by_text(&self, text: &str, nth: i32) -> Result<()> {
@@ -553,6 +1926,17 @@ impl CdpConnection {
     /// Type text into element using JavaScript
     /// Uses native value setter to work with React controlled inputs
     pub async fn type_into(&self, selector: &str, text: &str) -> Result<()> {
+        self.type_into_as(selector, text, None).await.map(|_| ())
+    }
+
+    /// Type text into element, firing the events `framework` listens for
+    /// (detected when `None`); returns the framework the events were shaped for
+    pub async fn type_into_as(
+        &self,
+        selector: &str,
+        text: &str,
+        framework: Option<Framework>,
+    ) -> Result<Option<String>> {
         let escaped_sel = selector.replace('\\', "\\\\").replace('\'', "\\'");
         let escaped_text = text
             .replace('\\', "\\\\")
@@ -572,32 +1956,50 @@ impl CdpConnection {
                         : HTMLTextAreaElement.prototype;
                     const nativeSetter = Object.getOwnPropertyDescriptor(proto, 'value').set;
                     nativeSetter.call(el, '{}');
-                    el.dispatchEvent(new Event('input', {{ bubbles: true }}));
-                    el.dispatchEvent(new Event('change', {{ bubbles: true }}));
+                    return {{ framework: ({})(el, '{}') }};
                 }} else if (el.contentEditable === 'true') {{
                     el.textContent = '{}';
                 }}
-                return true;
+                return {{ framework: null }};
             }})()
             "#,
-                escaped_sel, escaped_text, escaped_text
+                escaped_sel,
+                escaped_text,
+                input_events_script(framework),
+                escaped_text,
+                escaped_text
             ))
             .await?;
 
-        if result.as_bool() != Some(true) {
+        let Some(used) = result.get("framework") else {
             return Err(anyhow!("No element matches selector \"{}\"", selector));
+        };
+        let used = used.as_str().map(str::to_string);
+        self.settle_framework(used.as_deref()).await;
+        Ok(used)
+    }
+
+    /// Framework and version the current page runs on
+    pub async fn detect_framework(&self) -> Result<DetectedFramework> {
+        let detected = self.evaluate(&format!("{}()", DETECT_FRAMEWORK)).await?;
+        serde_json::from_value(detected).context("Unexpected framework detection result")
+    }
+
+    /// Give Angular's zone a chance to run change detection after input events
+    async fn settle_framework(&self, framework: Option<&str>) {
+        if framework == Some("angular") {
+            let _ = self.evaluate(ANGULAR_STABLE).await;
         }
-        Ok(())
     }
 
     /// Type text into currently focused element using JavaScript
     /// Uses native value setter to work with React controlled inputs
-    pub async fn type_focused(&self, text: &str) -> Result<()> {
+    pub async fn type_focused(&self, text: &str, framework: Option<Framework>) -> Result<()> {
         let escaped = text
             .replace('\\', "\\\\")
             .replace('\'', "\\'")
             .replace('\n', "\\n");
-        self.evaluate(&format!(
+        let used = self.evaluate(&format!(
             r#"
             (function() {{
                 const el = document.activeElement;
@@ -610,34 +2012,107 @@ impl CdpConnection {
                         const nativeSetter = Object.getOwnPropertyDescriptor(proto, 'value').set;
                         const currentValue = el.value || '';
                         nativeSetter.call(el, currentValue + '{}');
-                        el.dispatchEvent(new Event('input', {{ bubbles: true }}));
+                        return ({})(el, '{}');
                     }} else {{
                         document.execCommand('insertText', false, '{}');
                     }}
                 }}
+                return null;
             }})()
-            "#, escaped, escaped
+            "#, escaped, input_events_script(framework), escaped, escaped
         )).await?;
+        self.settle_framework(used.as_str()).await;
         Ok(())
     }
 
-    /// Press a key using JavaScript
-    pub async fn press_key(&self, key: &str) -> Result<()> {
-        self.evaluate(&format!(
-            r#"
+    /// Type text as individual key events using CDP Input.dispatchKeyEvent
+    /// Autocomplete widgets and input masks only react to real keystrokes
+    pub async fn type_keystrokes(&self, text: &str, delay_ms: u64) -> Result<()> {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+
+        for (i, c) in text.chars().enumerate() {
+            self.dispatch_key_stroke(&char_key(c)).await?;
+
+            let delay = human_delay(delay_ms, seed.wrapping_add(i as u64));
+            if delay > 0 {
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Focus element using JavaScript
+    pub async fn focus(&self, selector: &str) -> Result<()> {
+        let escaped = selector.replace('\\', "\\\\").replace('\'', "\\'");
+        let result = self
+            .evaluate(&format!(
+                r#"
             (function() {{
-                const el = document.activeElement || document.body;
-                const event = new KeyboardEvent('keydown', {{
-                    key: '{}',
-                    bubbles: true,
-                    cancelable: true
-                }});
-                el.dispatchEvent(event);
+                const el = document.querySelector('{}');
+                if (!el) return false;
+                el.scrollIntoView({{ block: 'center' }});
+                el.focus();
+                return true;
             }})()
             "#,
-            key
-        ))
-        .await?;
+                escaped
+            ))
+            .await?;
+
+        if result.as_bool() != Some(true) {
+            return Err(anyhow!("No element matches selector \"{}\"", selector));
+        }
+        Ok(())
+    }
+
+    /// Press a key sequence using CDP Input.dispatchKeyEvent
+    /// Supports modifier combinations ("ctrl+shift+p") and sequences ("Tab Tab Enter")
+    pub async fn press_key(&self, keys: &str) -> Result<()> {
+        let strokes = parse_key_sequence(keys)?;
+        let count = strokes.len();
+
+        for stroke in strokes {
+            self.dispatch_key_stroke(&stroke).await?;
+
+            // Small delay between keys
+            if count > 1 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Dispatch one keystroke: modifiers down, key down/up, modifiers up
+    pub async fn dispatch_key_stroke(&self, stroke: &KeyStroke) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::input::DispatchKeyEventType;
+
+        let page = self.get_page().await?;
+        let modifier_keys = stroke.modifier_keys();
+
+        // Hold modifiers, accumulating the bitmask as each goes down
+        let mut held = 0;
+        for (bit, modifier) in &modifier_keys {
+            held |= bit;
+            dispatch_key_event(&page, DispatchKeyEventType::RawKeyDown, modifier, held).await?;
+        }
+
+        // A key with text goes down as keyDown so Chrome emits keypress/input
+        let down_type = if stroke.text.is_some() {
+            DispatchKeyEventType::KeyDown
+        } else {
+            DispatchKeyEventType::RawKeyDown
+        };
+        dispatch_key_event(&page, down_type, stroke, stroke.modifiers).await?;
+        dispatch_key_event(&page, DispatchKeyEventType::KeyUp, stroke, stroke.modifiers).await?;
+
+        for (bit, modifier) in modifier_keys.iter().rev() {
+            held &= !bit;
+            dispatch_key_event(&page, DispatchKeyEventType::KeyUp, modifier, held).await?;
+        }
+
         Ok(())
     }
 
@@ -696,8 +2171,135 @@ impl CdpConnection {
         Ok(())
     }
 
+    /// Scroll an overflow container by pixels
+    pub async fn scroll_within(&self, selector: &str, x: i64, y: i64) -> Result<()> {
+        let escaped = selector.replace('\\', "\\\\").replace('\'', "\\'");
+        let result = self
+            .evaluate(&format!(
+                r#"
+            (function() {{
+                const el = document.querySelector('{}');
+                if (!el) return false;
+                el.scrollBy({}, {});
+                return true;
+            }})()
+            "#,
+                escaped, x, y
+            ))
+            .await?;
+
+        if result.as_bool() != Some(true) {
+            return Err(anyhow!("No element matches selector \"{}\"", selector));
+        }
+        Ok(())
+    }
+
+    /// Scroll until an element and/or text appears, waiting for lazy-loaded content
+    /// Scrolls the window (or `within` container) by `step` pixels, or 90% of its height
+    /// when `step` is 0. Returns the number of scrolls it took.
+    pub async fn scroll_until(
+        &self,
+        selector: Option<&str>,
+        text: Option<&str>,
+        within: Option<&str>,
+        step: i64,
+        max_scrolls: u32,
+    ) -> Result<u32> {
+        let escape = |s: &str| {
+            s.replace('\\', "\\\\")
+                .replace('\'', "\\'")
+                .replace('\n', "\\n")
+        };
+        let selector_js =
+            selector.map_or_else(|| "null".to_string(), |s| format!("'{}'", escape(s)));
+        let text_js = text.map_or_else(|| "null".to_string(), |t| format!("'{}'", escape(t)));
+        let within_js = within.map_or_else(|| "null".to_string(), |w| format!("'{}'", escape(w)));
+        let target = selector.or(text).unwrap_or_default();
+
+        let check_js = format!(
+            r#"
+            (function() {{
+                const sel = {}, text = {};
+                const el = sel ? document.querySelector(sel) : null;
+                if (sel && !el) return false;
+                if (text && !document.body.innerText.includes(text)) return false;
+                if (el) el.scrollIntoView({{ block: 'center' }});
+                return true;
+            }})()
+            "#,
+            selector_js, text_js
+        );
+
+        let scroll_js = format!(
+            r#"
+            (function() {{
+                const within = {};
+                const box = within ? document.querySelector(within) : document.scrollingElement;
+                if (!box) return null;
+                const step = {} || Math.round((within ? box.clientHeight : window.innerHeight) * 0.9);
+                if (within) box.scrollBy(0, step); else window.scrollBy(0, step);
+                return box.scrollHeight;
+            }})()
+            "#,
+            within_js, step
+        );
+
+        let height_js = format!(
+            "(function() {{ const w = {}; const b = w ? document.querySelector(w) : document.scrollingElement; return b ? b.scrollHeight : 0; }})()",
+            within_js
+        );
+
+        let mut stalled = 0;
+        for scrolls in 0..=max_scrolls {
+            if self.evaluate(&check_js).await?.as_bool() == Some(true) {
+                return Ok(scrolls);
+            }
+            if scrolls == max_scrolls {
+                break;
+            }
+
+            let before = self.evaluate(&scroll_js).await?;
+            let Some(before) = before.as_i64() else {
+                return Err(anyhow!(
+                    "No element matches selector \"{}\"",
+                    within.unwrap_or_default()
+                ));
+            };
+
+            // Give lazy loaders time to append content (up to 2s)
+            let wait_start = std::time::Instant::now();
+            let mut grew = false;
+            while wait_start.elapsed() < Duration::from_secs(2) {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                if self.evaluate(&height_js).await?.as_i64().unwrap_or(0) > before {
+                    grew = true;
+                    break;
+                }
+                if self.evaluate(&check_js).await?.as_bool() == Some(true) {
+                    return Ok(scrolls + 1);
+                }
+            }
+
+            stalled = if grew { 0 } else { stalled + 1 };
+            if stalled >= 3 {
+                return Err(anyhow!(
+                    "Reached end of content after {} scroll(s) without finding \"{}\"",
+                    scrolls + 1,
+                    target
+                ));
+            }
+        }
+
+        Err(anyhow!(
+            "\"{}\" not found after {} scroll(s)",
+            target,
+            max_scrolls
+        ))
+    }
+
     /// Wait for element to appear using JavaScript polling
     pub async fn wait_for(&self, selector: &str, timeout_ms: u64) -> Result<()> {
+        let timeout_ms = self.bounded(timeout_ms);
         let escaped = selector.replace('\\', "\\\\").replace('\'', "\\'");
         let start = std::time::Instant::now();
         let timeout = Duration::from_millis(timeout_ms);
@@ -725,6 +2327,7 @@ impl CdpConnection {
 
     /// Wait for element to be gone
     pub async fn wait_for_gone(&self, selector: &str, timeout_ms: u64) -> Result<()> {
+        let timeout_ms = self.bounded(timeout_ms);
         let start = std::time::Instant::now();
         let timeout = std::time::Duration::from_millis(timeout_ms);
 
@@ -746,10 +2349,91 @@ impl CdpConnection {
         }
     }
 
-    /// Go back in history
-    pub async fn go_back(&self) -> Result<()> {
-        self.evaluate("window.history.back()").await?;
-        Ok(())
+    /// Get the navigation history of the active tab via Page.getNavigationHistory
+    pub async fn navigation_history(&self) -> Result<Vec<HistoryEntry>> {
+        use chromiumoxide::cdp::browser_protocol::page::GetNavigationHistoryParams;
+
+        let page = self.get_page().await?;
+        let history = page
+            .execute(GetNavigationHistoryParams::default())
+            .await
+            .context("Failed to get navigation history")?;
+
+        let current = usize::try_from(history.result.current_index).ok();
+        Ok(history
+            .result
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| HistoryEntry {
+                index: i,
+                id: entry.id,
+                url: entry.url.clone(),
+                title: entry.title.clone(),
+                current: Some(i) == current,
+            })
+            .collect())
+    }
+
+    /// URLs of every frame in the active tab, including nested cross-origin iframes,
+    /// from Page.getFrameTree
+    pub async fn frame_urls(&self) -> Result<Vec<String>> {
+        use chromiumoxide::cdp::browser_protocol::page::{FrameTree, GetFrameTreeParams};
+
+        fn collect(tree: &FrameTree, urls: &mut Vec<String>) {
+            let fragment = tree.frame.url_fragment.as_deref().unwrap_or_default();
+            urls.push(format!("{}{}", tree.frame.url, fragment));
+            for child in tree.child_frames.iter().flatten() {
+                collect(child, urls);
+            }
+        }
+
+        let page = self.get_page().await?;
+        let tree = page
+            .execute(GetFrameTreeParams::default())
+            .await
+            .context("Failed to get frame tree")?;
+
+        let mut urls = Vec::new();
+        collect(&tree.result.frame_tree, &mut urls);
+        Ok(urls)
+    }
+
+    /// Move through history by `delta` entries (negative goes back)
+    /// Returns the entry navigated to
+    pub async fn go_history(&self, delta: i64) -> Result<HistoryEntry> {
+        use chromiumoxide::cdp::browser_protocol::page::NavigateToHistoryEntryParams;
+
+        let entries = self.navigation_history().await?;
+        let current = entries
+            .iter()
+            .position(|e| e.current)
+            .ok_or_else(|| anyhow!("Navigation history has no current entry"))?;
+
+        let target = i64::try_from(current)? + delta;
+        let entry = usize::try_from(target)
+            .ok()
+            .and_then(|i| entries.get(i))
+            .ok_or_else(|| {
+                let available = if delta < 0 {
+                    current
+                } else {
+                    entries.len() - current - 1
+                };
+                anyhow!(
+                    "Cannot go {} {} step(s): only {} available",
+                    if delta < 0 { "back" } else { "forward" },
+                    delta.unsigned_abs(),
+                    available
+                )
+            })?;
+
+        let page = self.get_page().await?;
+        page.execute(NavigateToHistoryEntryParams::new(entry.id))
+            .await
+            .context("Failed to navigate to history entry")?;
+
+        Ok(entry.clone())
     }
 
     /// Refresh page
@@ -778,6 +2462,71 @@ impl CdpConnection {
         .await
     }
 
+    /// Every cookie in the browser, HttpOnly ones included (Network.getAllCookies)
+    pub async fn all_cookies(&self) -> Result<Vec<SavedCookie>> {
+        use chromiumoxide::cdp::browser_protocol::network::GetAllCookiesParams;
+
+        let cookies = self
+            .get_page()
+            .await?
+            .execute(GetAllCookiesParams::default())
+            .await
+            .context("Failed to read cookies")?
+            .result
+            .cookies;
+        Ok(cookies
+            .into_iter()
+            .map(|cookie| SavedCookie {
+                expires: (!cookie.session).then_some(cookie.expires),
+                same_site: cookie.same_site.map(|s| s.as_ref().to_string()),
+                name: cookie.name,
+                value: cookie.value,
+                domain: cookie.domain,
+                path: cookie.path,
+                secure: cookie.secure,
+                http_only: cookie.http_only,
+            })
+            .collect())
+    }
+
+    /// Set cookies saved by `all_cookies`
+    pub async fn set_saved_cookies(&self, cookies: &[SavedCookie]) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::network::{
+            CookieParam, CookieSameSite, SetCookiesParams, TimeSinceEpoch,
+        };
+
+        let params = cookies
+            .iter()
+            .map(|cookie| {
+                let mut builder = CookieParam::builder()
+                    .name(cookie.name.clone())
+                    .value(cookie.value.clone())
+                    .domain(cookie.domain.clone())
+                    .path(cookie.path.clone())
+                    .secure(cookie.secure)
+                    .http_only(cookie.http_only);
+                if let Some(same_site) = cookie.same_site.as_deref() {
+                    builder = builder.same_site(
+                        same_site
+                            .parse::<CookieSameSite>()
+                            .map_err(|e| anyhow!("Invalid sameSite for {}: {}", cookie.name, e))?,
+                    );
+                }
+                if let Some(expires) = cookie.expires {
+                    builder = builder.expires(TimeSinceEpoch::new(expires));
+                }
+                builder.build().map_err(|e| anyhow!(e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.get_page()
+            .await?
+            .execute(SetCookiesParams::new(params))
+            .await
+            .context("Failed to set cookies")?;
+        Ok(())
+    }
+
     /// Get localStorage
     pub async fn get_local_storage(&self) -> Result<serde_json::Value> {
         self.evaluate("JSON.stringify(Object.entries(localStorage))")
@@ -792,7 +2541,14 @@ impl CdpConnection {
 
     /// Capture console messages via CDP event listeners
     /// This captures Log entries, Console API calls, and Runtime exceptions
-    pub async fn capture_console_messages(&self, timeout_ms: u64) -> Result<Vec<ConsoleEntry>> {
+    ///
+    /// `on_entry` sees each entry as it arrives, for streaming output.
+    /// Without a timeout it listens until the process is interrupted.
+    pub async fn capture_console_messages(
+        &self,
+        timeout_ms: Option<u64>,
+        on_entry: &mut dyn FnMut(&ConsoleEntry),
+    ) -> Result<Vec<ConsoleEntry>> {
         let page = self.get_page().await?;
         let mut entries = Vec::new();
 
@@ -813,19 +2569,21 @@ impl CdpConnection {
             .context("Failed to enable runtime domain")?;
 
         // Collect events for the specified timeout
-        let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout_ms);
-
-        loop {
-            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
-            if remaining.is_zero() {
-                break;
+        let deadline = timeout_ms.map(|ms| tokio::time::Instant::now() + Duration::from_millis(ms));
+        let timeout = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
             }
+        };
+        tokio::pin!(timeout);
 
-            tokio::select! {
-                _ = tokio::time::sleep(remaining) => break,
+        loop {
+            let entry = tokio::select! {
+                () = &mut timeout => break,
 
                 Some(event) = log_events.next() => {
-                    entries.push(ConsoleEntry {
+                    ConsoleEntry {
                         level: format!("{:?}", event.entry.level).to_lowercase(),
                         source: format!("{:?}", event.entry.source).to_lowercase(),
                         text: event.entry.text.clone(),
@@ -841,7 +2599,7 @@ impl CdpConnection {
                                 .join("\n")
                         }),
                         timestamp: *event.entry.timestamp.inner(),
-                    });
+                    }
                 }
 
                 Some(event) = console_events.next() => {
@@ -850,7 +2608,7 @@ impl CdpConnection {
                         .collect::<Vec<_>>()
                         .join(" ");
 
-                    entries.push(ConsoleEntry {
+                    ConsoleEntry {
                         level: format!("{:?}", event.r#type).to_lowercase(),
                         source: "console".to_string(),
                         text,
@@ -866,12 +2624,12 @@ impl CdpConnection {
                                 .join("\n")
                         }),
                         timestamp: *event.timestamp.inner(),
-                    });
+                    }
                 }
 
                 Some(event) = exception_events.next() => {
                     let details = &event.exception_details;
-                    entries.push(ConsoleEntry {
+                    ConsoleEntry {
                         level: "error".to_string(),
                         source: "exception".to_string(),
                         text: details.exception.as_ref()
@@ -889,14 +2647,473 @@ impl CdpConnection {
                                 .join("\n")
                         }),
                         timestamp: *event.timestamp.inner(),
-                    });
+                    }
                 }
-            }
+            };
+
+            on_entry(&entry);
+            entries.push(entry);
         }
 
         Ok(entries)
     }
 
+    /// Report navigations, console errors, dialogs and downloads as they happen,
+    /// until the page goes away
+    ///
+    /// Dialogs are only reported: whoever drives the page still answers them.
+    pub async fn watch_page_events(&self, on_event: &mut dyn FnMut(PageEvent)) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::page::{
+            self, EventDownloadWillBegin, EventFrameNavigated, EventJavascriptDialogOpening,
+        };
+
+        let page = self.get_page().await?;
+        let mut current_url = page.url().await.ok().flatten();
+
+        let mut navigations = page.event_listener::<EventFrameNavigated>().await?;
+        let mut console_events = page.event_listener::<EventConsoleApiCalled>().await?;
+        let mut exception_events = page.event_listener::<EventExceptionThrown>().await?;
+        let mut dialogs = page
+            .event_listener::<EventJavascriptDialogOpening>()
+            .await?;
+        let mut downloads = page.event_listener::<EventDownloadWillBegin>().await?;
+
+        page.execute(page::EnableParams::default())
+            .await
+            .context("Failed to enable page domain")?;
+        page.execute(runtime::EnableParams::default())
+            .await
+            .context("Failed to enable runtime domain")?;
+
+        loop {
+            let (event, data) = tokio::select! {
+                Some(event) = navigations.next() => {
+                    // Subframe navigations aren't the page moving
+                    if event.frame.parent_id.is_some() {
+                        continue;
+                    }
+                    current_url = Some(event.frame.url.clone());
+                    (EventKind::Navigation, serde_json::Value::Null)
+                }
+
+                Some(event) = console_events.next() => {
+                    if event.r#type != runtime::ConsoleApiCalledType::Error {
+                        continue;
+                    }
+                    let text = event.args.iter()
+                        .filter_map(|arg| arg.value.as_ref().map(|v| {
+                            v.as_str().map_or_else(|| v.to_string(), str::to_string)
+                        }))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    (EventKind::ConsoleError, serde_json::json!({ "source": "console", "text": text }))
+                }
+
+                Some(event) = exception_events.next() => {
+                    let details = &event.exception_details;
+                    let text = details.exception.as_ref()
+                        .and_then(|e| e.description.clone())
+                        .unwrap_or_else(|| details.text.clone());
+                    (EventKind::ConsoleError, serde_json::json!({
+                        "source": "exception",
+                        "text": text,
+                        "script": details.url,
+                        "line": details.line_number,
+                    }))
+                }
+
+                Some(event) = dialogs.next() => {
+                    (EventKind::Dialog, serde_json::json!(DialogInfo::from_event(&event)))
+                }
+
+                Some(event) = downloads.next() => {
+                    (EventKind::Download, serde_json::json!({
+                        "download_url": event.url,
+                        "filename": event.suggested_filename,
+                    }))
+                }
+
+                else => break,
+            };
+
+            on_event(PageEvent {
+                event,
+                timestamp: chrono::Utc::now(),
+                url: current_url.clone(),
+                data,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Report fetch/XHR calls with their bodies as they complete, until `duration_ms` runs out
+    ///
+    /// Response bodies are read with `Network.getResponseBody` once a call
+    /// finishes loading. Server-Sent Events streams stay open, so each message
+    /// is reported on its own as a call of kind `sse`.
+    pub async fn watch_xhr(
+        &self,
+        watch: &XhrWatch,
+        on_call: &mut dyn FnMut(XhrCall),
+    ) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::network::{
+            self, EventEventSourceMessageReceived, EventLoadingFailed, EventLoadingFinished,
+            EventRequestWillBeSent, EventResponseReceived, GetResponseBodyParams, ResourceType,
+        };
+
+        let page = self.get_page().await?;
+        let mut sent = page.event_listener::<EventRequestWillBeSent>().await?;
+        let mut responses = page.event_listener::<EventResponseReceived>().await?;
+        let mut finished = page.event_listener::<EventLoadingFinished>().await?;
+        let mut failed = page.event_listener::<EventLoadingFailed>().await?;
+        let mut messages = page
+            .event_listener::<EventEventSourceMessageReceived>()
+            .await?;
+        page.execute(network::EnableParams::default())
+            .await
+            .context("Failed to enable network domain")?;
+
+        // request id -> call so far, and when it started
+        let mut pending: HashMap<String, (XhrCall, std::time::Instant)> = HashMap::new();
+
+        let deadline = watch
+            .duration_ms
+            .map(|ms| tokio::time::Instant::now() + Duration::from_millis(ms));
+        let timeout = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(timeout);
+
+        loop {
+            let (mut call, started) = tokio::select! {
+                () = &mut timeout => break,
+
+                Some(event) = sent.next() => {
+                    let kind = match event.r#type {
+                        Some(ResourceType::Fetch) => "fetch",
+                        Some(ResourceType::Xhr) => "xhr",
+                        Some(ResourceType::EventSource) => "sse",
+                        _ => continue,
+                    };
+                    if !watch.matches(&event.request.method, &event.request.url) {
+                        continue;
+                    }
+                    let mut call = XhrCall {
+                        kind: kind.to_string(),
+                        method: event.request.method.clone(),
+                        url: event.request.url.clone(),
+                        request_headers: event.request.headers.inner().clone(),
+                        ..XhrCall::default()
+                    };
+                    if let Some(body) = &event.request.post_data {
+                        let (body, truncated) = body_value(body, watch.max_body_bytes);
+                        call.request_body = Some(body);
+                        call.truncated = truncated;
+                    }
+                    pending.insert(
+                        event.request_id.inner().clone(),
+                        (call, std::time::Instant::now()),
+                    );
+                    continue;
+                }
+
+                Some(event) = responses.next() => {
+                    if let Some((call, _)) = pending.get_mut(event.request_id.inner()) {
+                        call.status = Some(event.response.status);
+                        call.mime_type = Some(event.response.mime_type.clone());
+                        call.response_headers = event.response.headers.inner().clone();
+                    }
+                    continue;
+                }
+
+                Some(event) = messages.next() => {
+                    let Some((stream, started)) = pending.get(event.request_id.inner()) else {
+                        continue;
+                    };
+                    let (body, truncated) = body_value(&event.data, watch.max_body_bytes);
+                    let message = XhrCall {
+                        event: Some(event.event_name.clone()).filter(|name| !name.is_empty()),
+                        response_bytes: Some(event.data.len()),
+                        response_body: Some(body),
+                        truncated,
+                        ..stream.clone()
+                    };
+                    (message, *started)
+                }
+
+                Some(event) = finished.next() => {
+                    let Some((mut call, started)) = pending.remove(event.request_id.inner()) else {
+                        continue;
+                    };
+                    // A closed event stream's messages were already reported
+                    if call.kind == "sse" {
+                        continue;
+                    }
+                    match page.execute(GetResponseBodyParams::new(event.request_id.clone())).await {
+                        Ok(response) if response.result.base64_encoded => {
+                            // Binary: only its (decoded) size is useful
+                            call.response_bytes = Some(response.result.body.len() / 4 * 3);
+                        }
+                        Ok(response) => {
+                            let (body, truncated) =
+                                body_value(&response.result.body, watch.max_body_bytes);
+                            call.response_bytes = Some(response.result.body.len());
+                            call.response_body = Some(body);
+                            call.truncated |= truncated;
+                        }
+                        Err(e) => call.error = Some(format!("Response body unavailable: {}", e)),
+                    }
+                    (call, started)
+                }
+
+                Some(event) = failed.next() => {
+                    let Some((mut call, started)) = pending.remove(event.request_id.inner()) else {
+                        continue;
+                    };
+                    call.error = Some(event.error_text.clone());
+                    (call, started)
+                }
+
+                else => break,
+            };
+            call.duration_ms = started.elapsed().as_millis() as u64;
+            on_call(call);
+        }
+
+        Ok(())
+    }
+
+    /// Report JavaScript exceptions as they are thrown, until `duration_ms` runs out
+    ///
+    /// With `pause`, the debugger stops on each exception so the page can be
+    /// captured before any handler runs, then resumes. Stack locations are
+    /// mapped through the scripts' source maps where they can be fetched.
+    pub async fn watch_exceptions(
+        &self,
+        watch: &ExceptionWatch,
+        on_exception: &mut dyn FnMut(ExceptionReport),
+    ) -> Result<()> {
+        use chromiumoxide::cdp::js_protocol::debugger::{
+            self, EvaluateOnCallFrameParams, EventPaused, EventScriptParsed, PausedReason,
+            ResumeParams, SetPauseOnExceptionsParams, SetPauseOnExceptionsState,
+        };
+
+        let page = self.get_page().await?;
+
+        // Listen before enabling: enabling replays scriptParsed for loaded scripts
+        let mut parsed_events = page.event_listener::<EventScriptParsed>().await?;
+        let mut paused_events = page.event_listener::<EventPaused>().await?;
+        let mut exception_events = page.event_listener::<EventExceptionThrown>().await?;
+
+        page.execute(debugger::EnableParams::default())
+            .await
+            .context("Failed to enable debugger domain")?;
+        page.execute(runtime::EnableParams::default())
+            .await
+            .context("Failed to enable runtime domain")?;
+        if watch.pause {
+            let state = if watch.caught {
+                SetPauseOnExceptionsState::All
+            } else {
+                SetPauseOnExceptionsState::Uncaught
+            };
+            page.execute(SetPauseOnExceptionsParams::new(state))
+                .await
+                .context("Failed to pause on exceptions")?;
+        }
+
+        // script id -> (url, sourceMappingURL)
+        let mut scripts: HashMap<String, (String, Option<String>)> = HashMap::new();
+        let mut source_maps: HashMap<String, Option<crate::sourcemap::SourceMap>> = HashMap::new();
+
+        let deadline = watch
+            .duration_ms
+            .map(|ms| tokio::time::Instant::now() + Duration::from_millis(ms));
+        let timeout = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(timeout);
+
+        loop {
+            let mut report = tokio::select! {
+                () = &mut timeout => break,
+
+                Some(event) = parsed_events.next() => {
+                    let map_url = event
+                        .source_map_url
+                        .clone()
+                        .filter(|url| !url.is_empty());
+                    scripts.insert(event.script_id.inner().clone(), (event.url.clone(), map_url));
+                    continue;
+                }
+
+                Some(event) = paused_events.next(), if watch.pause => {
+                    if !matches!(event.reason, PausedReason::Exception | PausedReason::PromiseRejection) {
+                        page.execute(ResumeParams::default()).await?;
+                        continue;
+                    }
+                    let message = event
+                        .data
+                        .as_ref()
+                        .and_then(|data| data.get("description").or_else(|| data.get("value")))
+                        .map(|value| value.as_str().map_or_else(|| value.to_string(), str::to_string))
+                        .unwrap_or_else(|| "Exception".to_string());
+                    let stack = event
+                        .call_frames
+                        .iter()
+                        .take(MAX_EXCEPTION_FRAMES)
+                        .map(|frame| {
+                            let script = frame.location.script_id.inner();
+                            StackLocation {
+                                function: frame.function_name.clone(),
+                                url: scripts
+                                    .get(script)
+                                    .map_or_else(|| frame.url.clone(), |(url, _)| url.clone()),
+                                script_id: script.clone(),
+                                line: frame.location.line_number as u32 + 1,
+                                column: frame.location.column_number.unwrap_or(0) as u32 + 1,
+                                original: None,
+                            }
+                        })
+                        .collect();
+
+                    let (mut dom, mut screenshot) = (None, None);
+                    if watch.capture {
+                        if let Some(frame) = event.call_frames.first() {
+                            // Page scripts are stopped, so evaluate in the paused frame
+                            let params = EvaluateOnCallFrameParams::builder()
+                                .call_frame_id(frame.call_frame_id.clone())
+                                .expression("document.documentElement.outerHTML")
+                                .return_by_value(true)
+                                .build()
+                                .map_err(|e| anyhow!(e))?;
+                            dom = page
+                                .execute(params)
+                                .await
+                                .ok()
+                                .and_then(|html| html.result.result.value.clone())
+                                .and_then(|html| html.as_str().map(str::to_string));
+                        }
+                        // The compositor may not produce a frame while paused
+                        screenshot = tokio::time::timeout(
+                            Duration::from_secs(5),
+                            self.capture(&CaptureOptions::default()),
+                        )
+                        .await
+                        .ok()
+                        .and_then(Result::ok);
+                    }
+                    page.execute(ResumeParams::default()).await?;
+
+                    ExceptionReport {
+                        message,
+                        paused: true,
+                        stack,
+                        dom,
+                        screenshot,
+                    }
+                }
+
+                Some(event) = exception_events.next(), if !watch.pause => {
+                    let details = &event.exception_details;
+                    let message = details
+                        .exception
+                        .as_ref()
+                        .and_then(|e| e.description.clone())
+                        .unwrap_or_else(|| details.text.clone());
+                    let mut stack: Vec<StackLocation> = details
+                        .stack_trace
+                        .as_ref()
+                        .map(|trace| {
+                            trace
+                                .call_frames
+                                .iter()
+                                .take(MAX_EXCEPTION_FRAMES)
+                                .map(|frame| StackLocation {
+                                    function: frame.function_name.clone(),
+                                    url: frame.url.clone(),
+                                    script_id: frame.script_id.inner().clone(),
+                                    line: frame.line_number as u32 + 1,
+                                    column: frame.column_number as u32 + 1,
+                                    original: None,
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    if stack.is_empty() {
+                        if let Some(script) = &details.script_id {
+                            stack.push(StackLocation {
+                                function: String::new(),
+                                url: details.url.clone().unwrap_or_default(),
+                                script_id: script.inner().clone(),
+                                line: details.line_number as u32 + 1,
+                                column: details.column_number as u32 + 1,
+                                original: None,
+                            });
+                        }
+                    }
+
+                    ExceptionReport {
+                        message,
+                        paused: false,
+                        stack,
+                        dom: None,
+                        screenshot: None,
+                    }
+                }
+            };
+
+            // The page is running again, so source maps can be fetched through it
+            for frame in &mut report.stack {
+                let Some((script_url, Some(map_url))) = scripts.get(&frame.script_id) else {
+                    continue;
+                };
+                let map_url = crate::sourcemap::resolve_url(script_url, map_url);
+                if !source_maps.contains_key(&map_url) {
+                    let map = self.fetch_source_map(&map_url).await;
+                    source_maps.insert(map_url.clone(), map);
+                }
+                if let Some(Some(map)) = source_maps.get(&map_url) {
+                    frame.original = map.lookup(frame.line - 1, frame.column - 1);
+                }
+            }
+
+            on_exception(report);
+        }
+
+        if watch.pause {
+            let _ = page
+                .execute(SetPauseOnExceptionsParams::new(
+                    SetPauseOnExceptionsState::None,
+                ))
+                .await;
+        }
+        let _ = page.execute(debugger::DisableParams::default()).await;
+        Ok(())
+    }
+
+    /// Load and parse a source map, inline or fetched by the page
+    async fn fetch_source_map(&self, url: &str) -> Option<crate::sourcemap::SourceMap> {
+        let text = match crate::sourcemap::decode_data_url(url) {
+            Some(text) => text,
+            None => {
+                let script = format!(
+                    "fetch({}).then(r => r.ok ? r.text() : null).catch(() => null)",
+                    serde_json::Value::String(url.to_string())
+                );
+                self.evaluate(&script).await.ok()?.as_str()?.to_string()
+            }
+        };
+        crate::sourcemap::SourceMap::parse(&text).ok()
+    }
+
     /// Get accessibility tree via JavaScript
     /// Uses JavaScript to traverse the accessibility tree since chromiumoxide
     /// doesn't expose the Accessibility domain directly
@@ -1118,6 +3335,7 @@ impl CdpConnection {
 
     /// Wait for text to appear on page
     pub async fn wait_for_text(&self, text: &str, timeout_ms: u64) -> Result<()> {
+        let timeout_ms = self.bounded(timeout_ms);
         let escaped_text = text.replace('\\', "\\\\").replace('\'', "\\'");
         let js = format!(
             r#"
@@ -1149,8 +3367,190 @@ impl CdpConnection {
         }
     }
 
+    /// Wait until the element matching `selector` meets `condition`
+    pub async fn wait_for_element(
+        &self,
+        selector: &str,
+        condition: &ElementCondition,
+        timeout_ms: u64,
+    ) -> Result<()> {
+        let timeout_ms = self.bounded(timeout_ms);
+        let js = condition.script(selector);
+        let timeout = Duration::from_millis(timeout_ms);
+        let start = std::time::Instant::now();
+        // Last box seen, and since when, for `Stable`
+        let mut unchanged: Option<(serde_json::Value, std::time::Instant)> = None;
+
+        loop {
+            let value = self.evaluate(&js).await?;
+            let met = match condition {
+                ElementCondition::Stable(ms) if !value.is_null() => match &unchanged {
+                    Some((last, since)) if *last == value => {
+                        since.elapsed() >= Duration::from_millis(*ms)
+                    }
+                    _ => {
+                        unchanged = Some((value.clone(), std::time::Instant::now()));
+                        *ms == 0
+                    }
+                },
+                _ => value.as_bool() == Some(true),
+            };
+            if met {
+                return Ok(());
+            }
+
+            if start.elapsed() > timeout {
+                let state = if value.is_null() {
+                    "not found"
+                } else {
+                    "not ready"
+                };
+                return Err(anyhow!(
+                    "Timeout waiting for \"{}\" to be {} ({}ms; element {})",
+                    selector,
+                    condition,
+                    timeout_ms,
+                    state
+                ));
+            }
+
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    /// Wait until the page URL or title meets `condition`, returning the URL and title
+    ///
+    /// Evaluation errors while a navigation is in flight count as "not yet".
+    pub async fn wait_for_page(
+        &self,
+        condition: &PageCondition,
+        timeout_ms: u64,
+    ) -> Result<(String, String)> {
+        let timeout_ms = self.bounded(timeout_ms);
+        let timeout = Duration::from_millis(timeout_ms);
+        let start = std::time::Instant::now();
+        let mut last = (String::new(), String::new());
+
+        loop {
+            if let Ok(value) = self.evaluate("[location.href, document.title]").await {
+                let field = |i: usize| value[i].as_str().unwrap_or_default().to_string();
+                last = (field(0), field(1));
+                if condition.matches(&last.0, &last.1) {
+                    return Ok(last);
+                }
+            }
+
+            if start.elapsed() > timeout {
+                return Err(anyhow!(
+                    "Timeout waiting for {} ({}ms); last URL {} with title \"{}\"",
+                    condition,
+                    timeout_ms,
+                    last.0,
+                    last.1
+                ));
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Readiness of the current page (installs the request/route tracker on first use)
+    pub async fn page_readiness(&self) -> Result<PageReadiness> {
+        let js = format!(
+            r"(function() {{
+                const state = {};
+                const now = Date.now();
+                const body = document.body;
+                const roots = body
+                    ? [body, ...body.children, document.getElementById('__next'),
+                       document.getElementById('root')].filter(Boolean)
+                    : [];
+                const hasKey = (el, prefix) => Object.keys(el).some(k => k.startsWith(prefix));
+                const react = roots.some(el => el._reactRootContainer
+                    || hasKey(el, '__reactContainer$') || hasKey(el, '__reactFiber$'));
+                const vue = roots.some(el => el.__vue_app__ || el.__vue__)
+                    || !!document.querySelector('[data-v-app]');
+                let framework = null;
+                let hydrated = null;
+                if (document.getElementById('__NEXT_DATA__') || window.__next_f) {{
+                    framework = 'next';
+                    hydrated = react;
+                }} else if (window.__NUXT__ || document.getElementById('__NUXT_DATA__')) {{
+                    framework = 'nuxt';
+                    hydrated = vue;
+                }} else if (react || document.querySelector('[data-reactroot]')) {{
+                    framework = 'react';
+                    hydrated = react;
+                }} else if (vue) {{
+                    framework = 'vue';
+                    hydrated = true;
+                }}
+                return {{
+                    ready_state: document.readyState,
+                    pending_requests: Math.max(0, state.pending),
+                    framework,
+                    hydrated,
+                    route_changes: state.routeChanges,
+                    ms_since_route_change: state.lastRouteChange === null
+                        ? null : now - state.lastRouteChange,
+                    tracking_ms: now - state.installedAt,
+                }};
+            }})()",
+            READINESS_TRACKER
+        );
+        let mut readiness: PageReadiness = serde_json::from_value(self.evaluate(&js).await?)
+            .context("Unexpected readiness result")?;
+        readiness.score = readiness.score();
+        readiness.ready = readiness.score == 100;
+        Ok(readiness)
+    }
+
+    /// Start tracking requests and route changes on the current page
+    pub async fn track_page_activity(&self) -> Result<()> {
+        self.evaluate(&format!("{} && undefined", READINESS_TRACKER))
+            .await?;
+        Ok(())
+    }
+
+    /// Wait for a client-side route change (pushState, replaceState, popstate or
+    /// hashchange to a new URL), for SPAs whose navigations fire no load event
+    ///
+    /// A change since `track_page_activity` that no earlier wait reported counts,
+    /// so a click in the previous command is caught. Returns the new URL.
+    pub async fn wait_for_route_change(&self, timeout_ms: u64) -> Result<String> {
+        let timeout_ms = self.bounded(timeout_ms);
+        let js = format!(
+            r"(function() {{
+                const state = {};
+                if (state.routeChanges <= state.seenRouteChanges) return null;
+                state.seenRouteChanges = state.routeChanges;
+                return location.href;
+            }})()",
+            READINESS_TRACKER
+        );
+        let timeout = Duration::from_millis(timeout_ms);
+        let start = std::time::Instant::now();
+
+        loop {
+            if let Ok(serde_json::Value::String(url)) = self.evaluate(&js).await {
+                return Ok(url);
+            }
+
+            if start.elapsed() > timeout {
+                return Err(anyhow!(
+                    "Timeout waiting for a client-side route change ({}ms); still at {}",
+                    timeout_ms,
+                    self.current_url().await.unwrap_or_default()
+                ));
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
     /// Wait for text to disappear from page
     pub async fn wait_for_text_gone(&self, text: &str, timeout_ms: u64) -> Result<()> {
+        let timeout_ms = self.bounded(timeout_ms);
         let escaped_text = text.replace('\\', "\\\\").replace('\'', "\\'");
         let js = format!(
             r#"
@@ -1182,8 +3582,56 @@ impl CdpConnection {
         }
     }
 
-    /// Get element center coordinates
-    pub async fn get_element_center(&self, selector: &str) -> Result<(f64, f64)> {
+    /// Scroll offset and viewport size, plus the top-left of `element` if given
+    pub async fn coordinate_origin(&self, element: Option<&str>) -> Result<CoordinateOrigin> {
+        let origin = self
+            .evaluate(&format!(
+                r"(function(selector) {{
+                    let element = null;
+                    if (selector !== null) {{
+                        const el = document.querySelector(selector);
+                        if (!el) return null;
+                        const rect = el.getBoundingClientRect();
+                        element = [rect.left, rect.top];
+                    }}
+                    return {{
+                        scroll_x: window.scrollX, scroll_y: window.scrollY,
+                        width: window.innerWidth, height: window.innerHeight, element
+                    }};
+                }})({})",
+                serde_json::json!(element)
+            ))
+            .await?;
+        if origin.is_null() {
+            return Err(anyhow!(
+                "No element matches selector \"{}\"",
+                element.unwrap_or_default()
+            ));
+        }
+        serde_json::from_value(origin).map_err(|e| anyhow!("Unexpected viewport info: {}", e))
+    }
+
+    /// Device pixels per CSS pixel (2 on most HiDPI screens)
+    ///
+    /// Screenshots are captured in device pixels; input events take CSS pixels.
+    pub async fn device_pixel_ratio(&self) -> Result<f64> {
+        let ratio = self.evaluate("window.devicePixelRatio").await?;
+        Ok(ratio.as_f64().filter(|r| *r > 0.0).unwrap_or(1.0))
+    }
+
+    /// Scroll the window to `(x, y)` in page coordinates, returning where it ended up
+    pub async fn scroll_window_to(&self, x: f64, y: f64) -> Result<(f64, f64)> {
+        let scrolled = self
+            .evaluate(&format!(
+                "(function() {{ window.scrollTo({}, {}); return [window.scrollX, window.scrollY]; }})()",
+                x, y
+            ))
+            .await?;
+        serde_json::from_value(scrolled).map_err(|e| anyhow!("Unexpected scroll position: {}", e))
+    }
+
+    /// Get element center coordinates
+    pub async fn get_element_center(&self, selector: &str) -> Result<(f64, f64)> {
         let escaped = selector.replace('\\', "\\\\").replace('\'', "\\'");
         let js = format!(
             r#"
@@ -1308,14 +3756,17 @@ impl CdpConnection {
         Ok(())
     }
 
-    /// Select option in dropdown
+    /// Select option in dropdown, firing the events `framework` listens for
+    /// (detected when `None`)
     pub async fn select_option(
         &self,
         selector: &str,
         value: &str,
         by_label: bool,
         by_index: bool,
-    ) -> Result<()> {
+        framework: Option<Framework>,
+    ) -> Result<Option<String>> {
+        let events = input_events_script(framework);
         let escaped_sel = selector.replace('\\', "\\\\").replace('\'', "\\'");
         let escaped_val = value.replace('\\', "\\\\").replace('\'', "\\'");
 
@@ -1328,13 +3779,12 @@ impl CdpConnection {
                     const idx = parseInt('{}');
                     if (idx >= 0 && idx < select.options.length) {{
                         select.selectedIndex = idx;
-                        select.dispatchEvent(new Event('change', {{ bubbles: true }}));
-                        return true;
+                        return {{ framework: ({})(select, null) }};
                     }}
                     return false;
                 }})()
             "#,
-                escaped_sel, escaped_val
+                escaped_sel, escaped_val, events
             )
         } else if by_label {
             format!(
@@ -1345,14 +3795,13 @@ impl CdpConnection {
                     for (let i = 0; i < select.options.length; i++) {{
                         if (select.options[i].text === '{}') {{
                             select.selectedIndex = i;
-                            select.dispatchEvent(new Event('change', {{ bubbles: true }}));
-                            return true;
+                            return {{ framework: ({})(select, null) }};
                         }}
                     }}
                     return false;
                 }})()
             "#,
-                escaped_sel, escaped_val
+                escaped_sel, escaped_val, events
             )
         } else {
             format!(
@@ -1361,21 +3810,536 @@ impl CdpConnection {
                     const select = document.querySelector('{}');
                     if (!select) return false;
                     select.value = '{}';
-                    select.dispatchEvent(new Event('change', {{ bubbles: true }}));
-                    return true;
+                    return {{ framework: ({})(select, null) }};
                 }})()
             "#,
-                escaped_sel, escaped_val
+                escaped_sel, escaped_val, events
             )
         };
 
         let result = self.evaluate(&js).await?;
-        if result.as_bool() != Some(true) {
+        let Some(used) = result.get("framework") else {
             return Err(anyhow!("Failed to select option in \"{}\"", selector));
+        };
+        let used = used.as_str().map(str::to_string);
+        self.settle_framework(used.as_deref()).await;
+        Ok(used)
+    }
+
+    /// Run `body` with `model` bound to the table at `selector`
+    ///
+    /// `body` returns an object; one with an `error` field fails.
+    async fn table_eval(&self, selector: &str, body: &str) -> Result<serde_json::Value> {
+        let result = self
+            .evaluate(&format!(
+                r"(function() {{
+                    const model = ({})({});
+                    if (!model) return {{ error: 'element' }};
+                    if (!model.root) return {{ error: 'table' }};
+                    {}
+                }})()",
+                TABLE_MODEL,
+                serde_json::json!(selector),
+                body
+            ))
+            .await?;
+        match result.get("error").and_then(|e| e.as_str()) {
+            Some("element") => Err(anyhow!("No element matches selector \"{}\"", selector)),
+            Some("table") => Err(anyhow!(
+                "No table or ARIA grid at \"{}\" (expected <table> or role=grid/table)",
+                selector
+            )),
+            Some(error) => Err(anyhow!("{}", error)),
+            None => Ok(result),
+        }
+    }
+
+    /// Headers, row count and sort state of the table at `selector`
+    pub async fn table_snapshot(&self, selector: &str) -> Result<TableSnapshot> {
+        let snapshot = self
+            .table_eval(
+                selector,
+                r"const sorted = model.headers.find(h =>
+                    ['ascending', 'descending'].includes(h.getAttribute('aria-sort')));
+                return {
+                    headers: model.headers.map(model.text),
+                    rows: model.rows.length,
+                    sort: sorted ? model.text(sorted) + ' ' + sorted.getAttribute('aria-sort') : null,
+                    signature: model.rows.length + ':'
+                        + model.rows.slice(0, 3).map(r => model.text(r.el)).join('|'),
+                };",
+            )
+            .await?;
+        serde_json::from_value(snapshot).context("Unexpected table snapshot")
+    }
+
+    /// First body row with a cell equal to `text`, else one containing it
+    /// (case-insensitive)
+    pub async fn find_table_row(&self, selector: &str, text: &str) -> Result<Option<TableRow>> {
+        let found = self
+            .table_eval(
+                selector,
+                &format!(
+                    r"const want = {}.toLowerCase();
+                    const cellsOf = r => r.cells.map(model.text);
+                    let index = model.rows.findIndex(r =>
+                        cellsOf(r).some(c => c.toLowerCase() === want));
+                    if (index < 0) index = model.rows.findIndex(r =>
+                        model.text(r.el).toLowerCase().includes(want));
+                    if (index < 0) return {{ row: null }};
+                    return {{ row: {{
+                        index: index + 1,
+                        headers: model.headers.map(model.text),
+                        cells: cellsOf(model.rows[index]),
+                    }} }};",
+                    serde_json::json!(text)
+                ),
+            )
+            .await?;
+        serde_json::from_value(found["row"].clone()).context("Unexpected table row")
+    }
+
+    /// Scroll a table cell, header or next-page control into view and return
+    /// its center for a real click (links and buttons inside cells are preferred)
+    pub async fn table_click_point(
+        &self,
+        selector: &str,
+        target: &TableTarget,
+    ) -> Result<(f64, f64)> {
+        let find = match target {
+            TableTarget::Cell { row, column } => format!(
+                r"const row = model.rows[{} - 1];
+                if (!row) return {{ error: 'Row {} is no longer in the table' }};
+                const index = model.column({});
+                const cell = row.cells[index];
+                if (index < 0 || !cell) return {{ error: 'No column ' + {} + ' in the table' }};
+                const el = cell.querySelector('a, button, input, select, [role=button], [role=link]')
+                    || cell;",
+                row,
+                row,
+                serde_json::json!(column),
+                serde_json::json!(format!("\"{}\"", column))
+            ),
+            TableTarget::Header(column) => format!(
+                r"const index = model.column({});
+                const header = model.headers[index];
+                if (index < 0 || !header) return {{ error: 'No column ' + {} + ' in the table' }};
+                const el = header.querySelector('button, a, [role=button]') || header;",
+                serde_json::json!(column),
+                serde_json::json!(format!("\"{}\"", column))
+            ),
+            TableTarget::NextPage => r"const isNext = el => {
+                    const label = ((el.getAttribute('aria-label') || '') + ' '
+                        + (el.getAttribute('title') || '')).toLowerCase();
+                    const shown = (el.innerText || el.textContent || '').trim().toLowerCase();
+                    return el.getAttribute('rel') === 'next' || /\bnext\b/.test(label)
+                        || ['next', 'next page', '›', '»', '>', 'next ›', 'next »', 'next >']
+                            .includes(shown);
+                };
+                let el = null;
+                for (let scope = model.root.parentElement; scope && !el; scope = scope.parentElement) {
+                    el = [...scope.querySelectorAll('a, button, [role=button], [role=link]')]
+                        .find(c => !model.root.contains(c) && isNext(c)) || null;
+                }
+                if (!el) return { error: 'No next-page control found near the table' };
+                if (el.disabled || el.closest('.disabled, [aria-disabled=true]')) {
+                    return { error: 'Already on the last page (the next-page control is disabled)' };
+                }"
+            .to_string(),
+        };
+        let point = self
+            .table_eval(
+                selector,
+                &format!(
+                    r"{}
+                    el.scrollIntoView({{ block: 'center' }});
+                    const rect = el.getBoundingClientRect();
+                    return {{ x: rect.x + rect.width / 2, y: rect.y + rect.height / 2 }};",
+                    find
+                ),
+            )
+            .await?;
+        match (point["x"].as_f64(), point["y"].as_f64()) {
+            (Some(x), Some(y)) => Ok((x, y)),
+            _ => Err(anyhow!("Unexpected table click point")),
+        }
+    }
+
+    /// Wait for the table to re-render (rows differ from `signature`) and settle
+    ///
+    /// Returns `None` if it is unchanged after `timeout_ms`.
+    pub async fn wait_for_table_change(
+        &self,
+        selector: &str,
+        signature: &str,
+        timeout_ms: u64,
+    ) -> Result<Option<TableSnapshot>> {
+        let timeout = Duration::from_millis(self.bounded(timeout_ms));
+        let start = std::time::Instant::now();
+        let mut changed: Option<(TableSnapshot, std::time::Instant)> = None;
+
+        loop {
+            // The table may be missing for a moment while the page re-renders it
+            if let Ok(snapshot) = self.table_snapshot(selector).await {
+                if snapshot.signature == signature {
+                    changed = None;
+                } else {
+                    match &changed {
+                        Some((last, since)) if last.signature == snapshot.signature => {
+                            if since.elapsed() >= Duration::from_millis(TABLE_SETTLE_MS) {
+                                return Ok(Some(snapshot));
+                            }
+                        }
+                        _ => changed = Some((snapshot, std::time::Instant::now())),
+                    }
+                }
+            }
+
+            if start.elapsed() > timeout {
+                // A change seen but still moving at the deadline counts
+                return Ok(changed.map(|(snapshot, _)| snapshot));
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Type, value, placeholder and page locale of a date input
+    pub async fn date_input_info(&self, selector: &str) -> Result<DateInputInfo> {
+        let info = self
+            .evaluate(&format!(
+                r"(function() {{
+                    const el = document.querySelector({});
+                    if (!el) return null;
+                    let locale = document.documentElement.lang || navigator.language || 'en-US';
+                    let parts;
+                    try {{
+                        parts = new Intl.DateTimeFormat(locale, {{
+                            year: 'numeric', month: '2-digit', day: '2-digit'
+                        }}).formatToParts(new Date(2025, 0, 31));
+                    }} catch (e) {{
+                        locale = 'en-US';
+                        parts = new Intl.DateTimeFormat(locale, {{
+                            year: 'numeric', month: '2-digit', day: '2-digit'
+                        }}).formatToParts(new Date(2025, 0, 31));
+                    }}
+                    const tokens = {{ year: 'YYYY', month: 'MM', day: 'DD' }};
+                    return {{
+                        input_type: (el.getAttribute('type') || 'text').toLowerCase(),
+                        value: el.value || '',
+                        placeholder: el.getAttribute('placeholder') || '',
+                        read_only: !!el.readOnly,
+                        locale,
+                        locale_pattern: parts.map(p => tokens[p.type] || p.value).join(''),
+                    }};
+                }})()",
+                serde_json::json!(selector)
+            ))
+            .await?;
+        if info.is_null() {
+            return Err(anyhow!("No element matches selector \"{}\"", selector));
+        }
+        serde_json::from_value(info).context("Unexpected date input info")
+    }
+
+    /// Current value of an input
+    pub async fn input_value(&self, selector: &str) -> Result<String> {
+        let value = self
+            .evaluate(&format!(
+                "(function() {{ const el = document.querySelector({}); return el ? el.value || '' : null; }})()",
+                serde_json::json!(selector)
+            ))
+            .await?;
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("No element matches selector \"{}\"", selector))
+    }
+
+    /// Focus an input and select its text, so typing replaces it
+    pub async fn select_input_text(&self, selector: &str) -> Result<()> {
+        let found = self
+            .evaluate(&format!(
+                "(function() {{ const el = document.querySelector({}); if (!el) return false; el.focus(); if (el.select) el.select(); return true; }})()",
+                serde_json::json!(selector)
+            ))
+            .await?;
+        if found.as_bool() != Some(true) {
+            return Err(anyhow!("No element matches selector \"{}\"", selector));
         }
         Ok(())
     }
 
+    /// Next click toward `date` in the open calendar widget
+    pub async fn calendar_step(
+        &self,
+        date: chrono::NaiveDate,
+        locale: &str,
+    ) -> Result<CalendarStep> {
+        use chrono::Datelike;
+
+        let step = self
+            .evaluate(&format!(
+                "{}({}, {}, {}, {}, {})",
+                CALENDAR_STEP,
+                serde_json::json!(date.format("%Y-%m-%d").to_string()),
+                date.year(),
+                date.month0(),
+                date.day(),
+                serde_json::json!(locale)
+            ))
+            .await?;
+        serde_json::from_value(step).context("Unexpected calendar step")
+    }
+
+    /// Click a combobox's input and select its text, so typing replaces it
+    pub async fn open_combobox(&self, selector: &str) -> Result<()> {
+        let point = self
+            .evaluate(&format!(
+                r"(function() {{
+                    const input = ({})({});
+                    if (!input) return null;
+                    input.scrollIntoView({{ block: 'center' }});
+                    const r = input.getBoundingClientRect();
+                    return {{ x: r.x + r.width / 2, y: r.y + r.height / 2 }};
+                }})()",
+                COMBOBOX_INPUT,
+                serde_json::json!(selector)
+            ))
+            .await?;
+        let (Some(x), Some(y)) = (point["x"].as_f64(), point["y"].as_f64()) else {
+            return Err(anyhow!("No element matches selector \"{}\"", selector));
+        };
+        // Real pointer events: react-select opens its menu on mousedown
+        self.dispatch_click_at(x, y, "left", 1).await?;
+        self.evaluate(&format!(
+            "(function() {{ const input = ({})({}); input.focus(); if (input.select) input.select(); }})()",
+            COMBOBOX_INPUT,
+            serde_json::json!(selector)
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Visible options of the combobox's list: the element its aria-controls
+    /// or aria-owns names, else any open listbox or library menu
+    pub async fn combobox_options(&self, selector: &str) -> Result<ComboOptions> {
+        let found = self
+            .evaluate(&format!(
+                r#"(function() {{
+                    const input = ({})({});
+                    if (!input) return null;
+                    const visible = el => {{
+                        const r = el.getBoundingClientRect();
+                        return r.width > 0 && r.height > 0;
+                    }};
+                    const ids = [input.getAttribute('aria-controls'), input.getAttribute('aria-owns')]
+                        .filter(Boolean).join(' ').split(/\s+/).filter(Boolean);
+                    let lists = ids.map(id => document.getElementById(id)).filter(el => el && visible(el));
+                    if (!lists.length) {{
+                        lists = [...document.querySelectorAll(
+                            '[role=listbox], [class*=menu], [class*=popper], [class*=dropdown]')].filter(visible);
+                    }}
+                    const marker = '[role=option], [id*="-option-"]';
+                    const options = [];
+                    const seen = new Set();
+                    for (const list of lists) {{
+                        for (const el of list.querySelectorAll(marker + ', [class*=option]')) {{
+                            if (seen.has(el) || !visible(el) || el.querySelector(marker)) continue;
+                            seen.add(el);
+                            const r = el.getBoundingClientRect();
+                            const cls = typeof el.className === 'string' ? el.className : '';
+                            options.push({{
+                                text: (el.innerText || el.textContent || '').replace(/\s+/g, ' ').trim(),
+                                x: r.x + r.width / 2,
+                                y: r.y + r.height / 2,
+                                disabled: el.getAttribute('aria-disabled') === 'true' || /disabled/.test(cls),
+                            }});
+                        }}
+                    }}
+                    const loading = input.getAttribute('aria-busy') === 'true' || lists.some(l =>
+                        l.getAttribute('aria-busy') === 'true' || /^loading/i.test((l.innerText || '').trim()));
+                    return {{ options, loading }};
+                }})()"#,
+                COMBOBOX_INPUT,
+                serde_json::json!(selector)
+            ))
+            .await?;
+        if found.is_null() {
+            return Err(anyhow!("No element matches selector \"{}\"", selector));
+        }
+        serde_json::from_value(found).context("Unexpected combobox options")
+    }
+
+    /// Whether the combobox shows `text` as its value (input value, or the
+    /// chips and value labels in the wrapper around it)
+    pub async fn combobox_shows(&self, selector: &str, text: &str) -> Result<bool> {
+        let shown = self
+            .evaluate(&format!(
+                r"(function() {{
+                    const input = ({})({});
+                    if (!input) return false;
+                    const want = {}.toLowerCase();
+                    if ((input.value || '').toLowerCase().includes(want)) return true;
+                    let el = document.querySelector({});
+                    for (let i = 0; el && i < 4; i++, el = el.parentElement) {{
+                        if ((el.innerText || '').toLowerCase().includes(want)) return true;
+                    }}
+                    return false;
+                }})()",
+                COMBOBOX_INPUT,
+                serde_json::json!(selector),
+                serde_json::json!(text),
+                serde_json::json!(selector)
+            ))
+            .await?;
+        Ok(shown.as_bool() == Some(true))
+    }
+
+    /// The focused element, looking into shadow roots, or `None` when focus
+    /// is on the body or outside the page
+    pub async fn focused_element(&self) -> Result<Option<FocusStop>> {
+        use chromiumoxide::cdp::browser_protocol::accessibility::GetPartialAxTreeParams;
+
+        let page = self.get_page().await?;
+        let active = page
+            .evaluate(
+                r"(function() {
+                    let el = document.activeElement;
+                    while (el && el.shadowRoot && el.shadowRoot.activeElement) {
+                        el = el.shadowRoot.activeElement;
+                    }
+                    return el && el !== document.body && el !== document.documentElement ? el : null;
+                })()",
+            )
+            .await
+            .context("Failed to read the focused element")?;
+        let Some(object_id) = active.object().object_id.clone() else {
+            return Ok(None);
+        };
+
+        let describe = runtime::CallFunctionOnParams::builder()
+            .function_declaration(FOCUS_STOP)
+            .object_id(object_id.clone())
+            .return_by_value(true)
+            .build()
+            .map_err(|e| anyhow!("Failed to build CallFunctionOn params: {}", e))?;
+        let described = page
+            .execute(describe)
+            .await
+            .context("Failed to describe the focused element")?;
+        let value = described.result.result.value.clone().unwrap_or_default();
+        let mut stop: FocusStop = serde_json::from_value(value)
+            .map_err(|e| anyhow!("Unexpected focused element description: {}", e))?;
+
+        let tree = page
+            .execute(
+                GetPartialAxTreeParams::builder()
+                    .object_id(object_id)
+                    .fetch_relatives(false)
+                    .build(),
+            )
+            .await;
+        if let Some(node) = tree
+            .ok()
+            .and_then(|tree| tree.result.nodes.first().cloned())
+        {
+            let text = |value: Option<&serde_json::Value>| {
+                value
+                    .and_then(|value| value.as_str())
+                    .filter(|text| !text.is_empty())
+                    .map(str::to_string)
+            };
+            if let Some(role) = text(node.role.as_ref().and_then(|role| role.value.as_ref())) {
+                stop.role = Some(role);
+            }
+            if let Some(name) = text(node.name.as_ref().and_then(|name| name.value.as_ref())) {
+                stop.name = Some(name);
+            }
+        }
+        Ok(Some(stop))
+    }
+
+    /// Set checkbox/radio (or ARIA checkbox) state, clicking only if it differs
+    /// Returns (previous, new) checked state
+    pub async fn set_checked(&self, selector: &str, checked: bool) -> Result<(bool, bool)> {
+        let escaped = selector.replace('\\', "\\\\").replace('\'', "\\'");
+        let result = self
+            .evaluate(&format!(
+                r#"
+            (function() {{
+                const el = document.querySelector('{}');
+                if (!el) return {{ error: 'not_found' }};
+
+                const isInput = el.tagName === 'INPUT' && (el.type === 'checkbox' || el.type === 'radio');
+                const role = el.getAttribute('role');
+                if (!isInput && role !== 'checkbox' && role !== 'radio' && role !== 'switch') {{
+                    return {{ error: 'not_checkable', tag: el.tagName.toLowerCase() }};
+                }}
+
+                const read = () => isInput ? el.checked : el.getAttribute('aria-checked') === 'true';
+                const previous = read();
+                const wanted = {};
+                if (previous === wanted) return {{ previous, current: previous }};
+
+                if (!wanted && (el.type === 'radio' || role === 'radio')) {{
+                    return {{ error: 'radio_uncheck', previous }};
+                }}
+                if (el.disabled || el.getAttribute('aria-disabled') === 'true') {{
+                    return {{ error: 'disabled', previous }};
+                }}
+
+                // A real click fires input/change and runs framework handlers
+                el.scrollIntoView({{ block: 'center' }});
+                el.click();
+                return {{ previous, current: read() }};
+            }})()
+            "#,
+                escaped, checked
+            ))
+            .await?;
+
+        match result.get("error").and_then(|v| v.as_str()) {
+            Some("not_found") => {
+                return Err(anyhow!("No element matches selector \"{}\"", selector));
+            }
+            Some("not_checkable") => {
+                return Err(anyhow!(
+                    "Element \"{}\" is not a checkbox or radio (found <{}>)",
+                    selector,
+                    result.get("tag").and_then(|v| v.as_str()).unwrap_or("?")
+                ));
+            }
+            Some("radio_uncheck") => {
+                return Err(anyhow!(
+                    "Cannot uncheck radio \"{}\"; check another option in its group instead",
+                    selector
+                ));
+            }
+            Some("disabled") => {
+                return Err(anyhow!("Element \"{}\" is disabled", selector));
+            }
+            _ => {}
+        }
+
+        let previous = result
+            .get("previous")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let current = result
+            .get("current")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(previous);
+
+        if current != checked {
+            return Err(anyhow!(
+                "Clicking \"{}\" did not change its state (still {})",
+                selector,
+                if current { "checked" } else { "unchecked" }
+            ));
+        }
+        Ok((previous, current))
+    }
+
     /// Upload files to file input using CDP DOM.setFileInputFiles
     pub async fn upload_files(&self, selector: &str, files: &[std::path::PathBuf]) -> Result<()> {
         let page = self.get_page().await?;
@@ -1439,34 +4403,244 @@ impl CdpConnection {
         Ok(())
     }
 
-    /// Handle JavaScript dialog (alert, confirm, prompt)
-    pub async fn handle_dialog(&self, accept: bool, text: Option<&str>) -> Result<()> {
-        // Override window methods to auto-handle dialogs
-        let text_value = text.map_or_else(
-            || "''".to_string(),
-            |t| format!("'{}'", t.replace('\'', "\\'")),
-        );
-        let js = format!(
-            r#"
-            (function() {{
-                // Store original methods
-                window.__origAlert = window.__origAlert || window.alert;
-                window.__origConfirm = window.__origConfirm || window.confirm;
-                window.__origPrompt = window.__origPrompt || window.prompt;
+    /// Upload files through the native file chooser opened by clicking a trigger element
+    /// Uses Page.setInterceptFileChooserDialog so no picker window is shown
+    pub async fn upload_via_chooser(
+        &self,
+        trigger: &str,
+        files: &[std::path::PathBuf],
+        timeout_ms: u64,
+    ) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::dom::BackendNodeId;
+        use chromiumoxide::cdp::browser_protocol::page::{
+            EventFileChooserOpened, FileChooserOpenedMode, SetInterceptFileChooserDialogParams,
+        };
 
-                // Override with auto-response
-                window.alert = function(msg) {{ window.__lastDialog = {{ type: 'alert', message: msg }}; }};
-                window.confirm = function(msg) {{ window.__lastDialog = {{ type: 'confirm', message: msg }}; return {}; }};
-                window.prompt = function(msg, def) {{ window.__lastDialog = {{ type: 'prompt', message: msg }}; return {} ? {} : null; }};
+        let page = self.get_page().await?;
 
-                return true;
-            }})()
-        "#,
-            accept, accept, text_value
-        );
+        let mut choosers = page.event_listener::<EventFileChooserOpened>().await?;
+        page.execute(SetInterceptFileChooserDialogParams::new(true))
+            .await
+            .context("Failed to intercept file chooser")?;
 
-        self.evaluate(&js).await?;
-        Ok(())
+        // A trusted mouse click counts as the user gesture the picker requires
+        let (x, y) = self.element_click_point(trigger).await?;
+        self.dispatch_click_at(x, y, "left", 1).await?;
+
+        let opened = tokio::time::timeout(Duration::from_millis(timeout_ms), choosers.next())
+            .await
+            .ok()
+            .flatten();
+
+        let result = match opened {
+            None => Err(anyhow!(
+                "Clicking \"{}\" did not open a file chooser within {}ms",
+                trigger,
+                timeout_ms
+            )),
+            Some(event)
+                if files.len() > 1 && matches!(event.mode, FileChooserOpenedMode::SelectSingle) =>
+            {
+                Err(anyhow!(
+                    "File chooser opened by \"{}\" accepts a single file, got {}",
+                    trigger,
+                    files.len()
+                ))
+            }
+            Some(event) => match &event.backend_node_id {
+                Some(node_id) => {
+                    let file_paths: Vec<String> = files
+                        .iter()
+                        .map(|f| f.to_string_lossy().to_string())
+                        .collect();
+                    let params = SetFileInputFilesParams::builder()
+                        .files(file_paths)
+                        .backend_node_id(BackendNodeId::new(*node_id.inner()))
+                        .build()
+                        .map_err(|e| anyhow!("Failed to build SetFileInputFiles params: {}", e))?;
+                    page.execute(params)
+                        .await
+                        .map(|_| ())
+                        .context("Failed to set file chooser files")
+                }
+                None => Err(anyhow!("File chooser has no associated file input")),
+            },
+        };
+
+        // Later pickers should open normally again
+        page.execute(SetInterceptFileChooserDialogParams::new(false))
+            .await
+            .context("Failed to stop intercepting file chooser")?;
+
+        result
+    }
+
+    /// Wait for a JavaScript dialog (alert, confirm, prompt) and answer it
+    /// A dialog that is already open is reported as soon as the Page domain is enabled
+    pub async fn handle_dialog(
+        &self,
+        accept: bool,
+        prompt_text: Option<&str>,
+        timeout_ms: u64,
+    ) -> Result<DialogInfo> {
+        use chromiumoxide::cdp::browser_protocol::page::{
+            EnableParams, EventJavascriptDialogOpening,
+        };
+
+        let timeout_ms = self.bounded(timeout_ms);
+        let page = self.get_page().await?;
+
+        // Listen before enabling so a pending dialog is not missed
+        let mut dialogs = page
+            .event_listener::<EventJavascriptDialogOpening>()
+            .await?;
+        page.execute(EnableParams::default())
+            .await
+            .context("Failed to enable page domain")?;
+
+        let event = tokio::time::timeout(Duration::from_millis(timeout_ms), dialogs.next())
+            .await
+            .map_err(|_| anyhow!("Timeout: no dialog appeared within {}ms", timeout_ms))?
+            .ok_or_else(|| anyhow!("Dialog event stream closed"))?;
+
+        answer_dialog(&page, accept, prompt_text).await?;
+        Ok(DialogInfo::from_event(&event))
+    }
+
+    /// Start counting the current page's in-flight requests
+    pub async fn watch_network(&self) -> Result<NetworkWatch> {
+        use chromiumoxide::cdp::browser_protocol::network::{
+            EnableParams, EventLoadingFailed, EventLoadingFinished, EventRequestWillBeSent,
+        };
+
+        let page = self.get_page().await?;
+        page.execute(EnableParams::default())
+            .await
+            .context("Failed to enable network domain")?;
+        let mut sent = page.event_listener::<EventRequestWillBeSent>().await?;
+        let mut finished = page.event_listener::<EventLoadingFinished>().await?;
+        let mut failed = page.event_listener::<EventLoadingFailed>().await?;
+
+        let watch: NetworkWatch = Arc::new(Mutex::new(NetworkActivity {
+            pending: HashSet::new(),
+            changed: std::time::Instant::now(),
+        }));
+        let activity = Arc::clone(&watch);
+        tokio::spawn(async move {
+            loop {
+                let (request_id, started) = tokio::select! {
+                    Some(event) = sent.next() => (event.request_id.as_ref().to_string(), true),
+                    Some(event) = finished.next() => (event.request_id.as_ref().to_string(), false),
+                    Some(event) = failed.next() => (event.request_id.as_ref().to_string(), false),
+                    else => break,
+                };
+                let mut activity = activity.lock().await;
+                if started {
+                    activity.pending.insert(request_id);
+                } else {
+                    activity.pending.remove(&request_id);
+                }
+                activity.changed = std::time::Instant::now();
+            }
+        });
+
+        Ok(watch)
+    }
+
+    /// Start noting tabs the current page opens (via Target.targetCreated)
+    pub async fn watch_popups(&self) -> Result<PopupLog> {
+        use chromiumoxide::cdp::browser_protocol::target::EventTargetCreated;
+
+        let opener = self.get_page().await?.target_id().clone();
+        let mut created = {
+            let browser = self
+                .browser
+                .as_ref()
+                .ok_or_else(|| anyhow!("Not connected to Chrome"))?;
+            browser
+                .lock()
+                .await
+                .event_listener::<EventTargetCreated>()
+                .await?
+        };
+
+        let popups: PopupLog = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&popups);
+        tokio::spawn(async move {
+            while let Some(event) = created.next().await {
+                let target = &event.target_info;
+                if target.r#type == "page" && target.opener_id.as_ref() == Some(&opener) {
+                    log.lock().await.push(target.target_id.as_ref().to_string());
+                }
+            }
+        });
+
+        Ok(popups)
+    }
+
+    /// Make the newest popup seen by `watch_popups` the active tab
+    ///
+    /// Waits briefly for one to appear, then for it to start loading its URL.
+    pub async fn follow_popup(&self, popups: &PopupLog) -> Result<Option<PopupInfo>> {
+        tokio::time::sleep(Duration::from_millis(POPUP_GRACE_MS)).await;
+        let Some(tab_id) = popups.lock().await.last().cloned() else {
+            return Ok(None);
+        };
+
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(POPUP_LOAD_MS);
+        let url = loop {
+            let url = self
+                .list_tabs()
+                .await?
+                .into_iter()
+                .find(|tab| tab.id == tab_id)
+                .map(|tab| tab.url);
+            match url {
+                // Closed again already (e.g. a popup that finished on its own)
+                None => return Ok(None),
+                Some(url) if url != "about:blank" && !url.is_empty() => break url,
+                Some(url) if tokio::time::Instant::now() >= deadline => break url,
+                Some(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+            }
+        };
+
+        write_active_tab(Some(&tab_id))?;
+        Ok(Some(PopupInfo { tab_id, url }))
+    }
+
+    /// Answer every dialog that opens on this connection with a fixed policy
+    /// Returns the list of dialogs handled so far, filled in the background
+    pub async fn auto_handle_dialogs(
+        &self,
+        accept: bool,
+        prompt_text: Option<String>,
+    ) -> Result<DialogLog> {
+        use chromiumoxide::cdp::browser_protocol::page::{
+            EnableParams, EventJavascriptDialogOpening,
+        };
+
+        let page = self.get_page().await?;
+        let mut dialogs = page
+            .event_listener::<EventJavascriptDialogOpening>()
+            .await?;
+        page.execute(EnableParams::default())
+            .await
+            .context("Failed to enable page domain")?;
+
+        let handled: DialogLog = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&handled);
+        tokio::spawn(async move {
+            while let Some(event) = dialogs.next().await {
+                if answer_dialog(&page, accept, prompt_text.as_deref())
+                    .await
+                    .is_ok()
+                {
+                    log.lock().await.push(DialogInfo::from_event(&event));
+                }
+            }
+        });
+
+        Ok(handled)
     }
 
     /// Resize viewport
@@ -1487,25 +4661,452 @@ impl CdpConnection {
         Ok(())
     }
 
-    /// Print page to PDF using CDP Page.printToPDF
-    /// Note: PDF generation only works in Chrome headless mode
-    pub async fn print_to_pdf(&self, landscape: bool) -> Result<Vec<u8>> {
+    /// Emulate a viewport size with Emulation.setDeviceMetricsOverride
+    pub async fn set_device_metrics(&self, width: u32, height: u32, mobile: bool) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::emulation::SetDeviceMetricsOverrideParams;
+
         let page = self.get_page().await?;
+        page.execute(SetDeviceMetricsOverrideParams::new(
+            i64::from(width),
+            i64::from(height),
+            1.0,
+            mobile,
+        ))
+        .await
+        .context("Failed to set device metrics")?;
+        Ok(())
+    }
 
-        // Build PDF params with landscape option
-        let params = PrintToPdfParams::builder()
-            .landscape(landscape)
-            .print_background(true)
-            .build();
+    /// Undo `set_device_metrics`
+    pub async fn clear_device_metrics(&self) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::emulation::ClearDeviceMetricsOverrideParams;
+
+        let page = self.get_page().await?;
+        page.execute(ClearDeviceMetricsOverrideParams::default())
+            .await
+            .context("Failed to clear device metrics")?;
+        Ok(())
+    }
+
+    /// Print page to PDF using CDP Page.printToPDF
+    /// Note: older headful Chrome can't print; see `print_to_pdf_headless`
+    pub async fn print_to_pdf(&self, options: &PdfOptions) -> Result<Vec<u8>> {
+        let page = self.get_page().await?;
+        let params = options.params()?;
 
         // Use page.pdf() which handles the CDP command and base64 decoding
-        let pdf_data = page.pdf(params).await.context(
-            "Failed to generate PDF. Note: PDF export only works in Chrome headless mode",
-        )?;
+        let pdf_data = page.pdf(params).await.context("Failed to generate PDF")?;
 
         Ok(pdf_data)
     }
 
+    /// Print the current page in a temporary headless Chrome
+    ///
+    /// For a headful browser that can't print: the sidecar gets the page's
+    /// cookies, loads its URL, prints, and is shut down again.
+    pub async fn print_to_pdf_headless(&self, options: &PdfOptions) -> Result<Vec<u8>> {
+        use chromiumoxide::cdp::browser_protocol::network::{
+            CookieParam, GetCookiesParams, SetCookiesParams,
+        };
+
+        let params = options.params()?;
+        let page = self.get_page().await?;
+        let url = page.url().await?.unwrap_or_default();
+        let cookies = page
+            .execute(GetCookiesParams::default())
+            .await
+            .context("Failed to read cookies")?
+            .result
+            .cookies;
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")?
+            .local_addr()?
+            .port();
+        let user_data_dir = std::env::temp_dir().join(format!("domguard-pdf-{}", port));
+        std::fs::create_dir_all(&user_data_dir)?;
+        let mut child = std::process::Command::new(Self::find_chrome()?)
+            .arg("--headless=new")
+            .arg(format!("--remote-debugging-port={}", port))
+            .arg(format!("--user-data-dir={}", user_data_dir.display()))
+            .arg("--no-first-run")
+            .arg("--no-default-browser-check")
+            .arg("about:blank")
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .context("Failed to launch headless Chrome")?;
+
+        let mut sidecar_config = self.config.clone();
+        sidecar_config.chrome.host = "127.0.0.1".to_string();
+        sidecar_config.chrome.port = port;
+        sidecar_config.chrome.token = None;
+        sidecar_config.chrome.tls = false;
+
+        let printed = async {
+            let mut browser = None;
+            for _ in 0..50 {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                let limit = Duration::from_millis(self.bounded(self.config.defaults.timeout_ms));
+                let discovered = crate::discovery::off_runtime(
+                    &sidecar_config,
+                    limit,
+                    crate::discovery::discover_ws_url,
+                )
+                .await;
+                if let Ok(ws_url) = discovered {
+                    if let Ok((connected, mut handler)) = Browser::connect(&ws_url).await {
+                        tokio::spawn(
+                            async move { while let Some(_event) = handler.next().await {} },
+                        );
+                        browser = Some(connected);
+                        break;
+                    }
+                }
+            }
+            let browser =
+                browser.ok_or_else(|| anyhow!("Headless Chrome did not start on port {}", port))?;
+
+            let sidecar = browser.new_page("about:blank").await?;
+            let cookies = cookies
+                .into_iter()
+                .filter_map(|cookie| {
+                    let mut builder = CookieParam::builder()
+                        .name(cookie.name)
+                        .value(cookie.value)
+                        .domain(cookie.domain)
+                        .path(cookie.path)
+                        .secure(cookie.secure)
+                        .http_only(cookie.http_only);
+                    if let Some(same_site) = cookie.same_site {
+                        builder = builder.same_site(same_site);
+                    }
+                    builder.build().ok()
+                })
+                .collect::<Vec<_>>();
+            if !cookies.is_empty() {
+                sidecar.execute(SetCookiesParams::new(cookies)).await?;
+            }
+            sidecar.goto(url.as_str()).await?;
+            sidecar
+                .pdf(params)
+                .await
+                .context("Failed to generate PDF in headless Chrome")
+        }
+        .await;
+
+        let _ = child.kill();
+        let _ = child.wait();
+        let _ = std::fs::remove_dir_all(&user_data_dir);
+        printed
+    }
+
+    /// CSS rules that apply to an element and its descendants, via CSS.getMatchedStylesForNode
+    ///
+    /// Rules come back as CSS text in first-seen order, wrapped in their
+    /// `@media` blocks, without user-agent rules. Rules matched on ancestors
+    /// are only kept when they define custom properties the subtree may use.
+    pub async fn matched_css_rules(&self, selector: &str) -> Result<Vec<String>> {
+        use chromiumoxide::cdp::browser_protocol::css::{
+            CssRule, EnableParams as CssEnableParams, GetMatchedStylesForNodeParams,
+            StyleSheetOrigin,
+        };
+        use chromiumoxide::cdp::browser_protocol::dom::{
+            EnableParams as DomEnableParams, GetDocumentParams, QuerySelectorAllParams,
+        };
+
+        /// Most elements whose styles are collected
+        const MAX_NODES: usize = 500;
+        const MARK: &str = "data-domguard-component";
+
+        fn rule_text(rule: &CssRule) -> Option<String> {
+            if matches!(rule.origin, StyleSheetOrigin::UserAgent) {
+                return None;
+            }
+            let body = rule.style.css_text.as_deref()?.trim();
+            if body.is_empty() {
+                return None;
+            }
+            let mut text = format!("{} {{ {} }}", rule.selector_list.text, body);
+            // Innermost media query first
+            for media in rule.media.iter().flatten() {
+                text = format!("@media {} {{\n  {}\n}}", media.text, text);
+            }
+            Some(text)
+        }
+
+        let page = self.get_page().await?;
+        page.execute(DomEnableParams::default())
+            .await
+            .context("Failed to enable DOM domain")?;
+        page.execute(CssEnableParams::default())
+            .await
+            .context("Failed to enable CSS domain")?;
+
+        // Mark the component so one query finds it and its descendants
+        let marked = self
+            .evaluate(&format!(
+                "(function() {{ const el = document.querySelector({}); if (!el) return false; el.setAttribute('{}', ''); return true; }})()",
+                serde_json::to_string(selector)?,
+                MARK
+            ))
+            .await?;
+        if marked.as_bool() != Some(true) {
+            return Err(anyhow!("No element matches selector \"{}\"", selector));
+        }
+
+        let document = page
+            .execute(GetDocumentParams::default())
+            .await
+            .context("Failed to get document")?;
+        let nodes = page
+            .execute(QuerySelectorAllParams::new(
+                document.result.root.node_id,
+                format!("[{0}], [{0}] *", MARK),
+            ))
+            .await
+            .context("Failed to query component nodes");
+        self.evaluate(&format!(
+            "document.querySelectorAll('[{0}]').forEach(el => el.removeAttribute('{0}'))",
+            MARK
+        ))
+        .await?;
+
+        let mut rules: Vec<String> = Vec::new();
+        let mut add = |text: Option<String>| {
+            if let Some(text) = text {
+                if !rules.contains(&text) {
+                    rules.push(text);
+                }
+            }
+        };
+        for (i, node_id) in nodes?.result.node_ids.into_iter().enumerate() {
+            if i >= MAX_NODES {
+                break;
+            }
+            let Ok(styles) = page
+                .execute(GetMatchedStylesForNodeParams::new(node_id))
+                .await
+            else {
+                continue;
+            };
+            let styles = styles.result;
+
+            for matched in styles.matched_css_rules.iter().flatten() {
+                add(rule_text(&matched.rule));
+            }
+            for pseudo in styles.pseudo_elements.iter().flatten() {
+                for matched in &pseudo.matches {
+                    add(rule_text(&matched.rule));
+                }
+            }
+            // Only the first node's ancestors are outside the component
+            if i == 0 {
+                for entry in styles.inherited.iter().flatten() {
+                    for matched in &entry.matched_css_rules {
+                        let defines_variables = matched
+                            .rule
+                            .style
+                            .css_properties
+                            .iter()
+                            .any(|p| p.name.starts_with("--"));
+                        if defines_variables {
+                            add(rule_text(&matched.rule));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(rules)
+    }
+
+    /// The CSS rules applied to one element, in cascade order (lowest first)
+    ///
+    /// Each rule carries its matching selectors, source location and
+    /// specificity; a declaration is marked overridden when a later rule or
+    /// the inline style sets the same property with at least its importance.
+    pub async fn matched_styles(&self, selector: &str) -> Result<Vec<MatchedRule>> {
+        use chromiumoxide::cdp::browser_protocol::css::{
+            CssStyle, EnableParams as CssEnableParams, EventStyleSheetAdded,
+            GetMatchedStylesForNodeParams, StyleSheetOrigin,
+        };
+
+        let page = self.get_page().await?;
+        let node_id = self.query_node(&page, selector).await?;
+
+        // Enabling CSS announces every existing sheet; keep their URLs
+        let mut sheet_events = page.event_listener::<EventStyleSheetAdded>().await?;
+        page.execute(CssEnableParams::default())
+            .await
+            .context("Failed to enable CSS domain")?;
+        let mut sheets: HashMap<String, (String, u32)> = HashMap::new();
+        while let Ok(Some(event)) =
+            tokio::time::timeout(Duration::from_millis(50), sheet_events.next()).await
+        {
+            let header = &event.header;
+            sheets.insert(
+                header.style_sheet_id.inner().clone(),
+                (header.source_url.clone(), header.start_line as u32),
+            );
+        }
+
+        let styles = page
+            .execute(GetMatchedStylesForNodeParams::new(node_id))
+            .await
+            .context("Failed to get matched styles")?
+            .result;
+
+        let declarations = |style: &CssStyle| -> Vec<MatchedProperty> {
+            style
+                .css_properties
+                .iter()
+                .filter(|p| {
+                    p.implicit != Some(true)
+                        && p.disabled != Some(true)
+                        && p.parsed_ok != Some(false)
+                })
+                .map(|p| MatchedProperty {
+                    name: p.name.clone(),
+                    value: p.value.clone(),
+                    important: p.important == Some(true),
+                    overridden: false,
+                })
+                .collect()
+        };
+
+        let mut rules: Vec<MatchedRule> = Vec::new();
+        for matched in styles.matched_css_rules.iter().flatten() {
+            let rule = &matched.rule;
+            let selectors: Vec<&str> = matched
+                .matching_selectors
+                .iter()
+                .filter_map(|&i| rule.selector_list.selectors.get(usize::try_from(i).ok()?))
+                .map(|value| value.text.as_str())
+                .collect();
+            let first = matched
+                .matching_selectors
+                .first()
+                .and_then(|&i| rule.selector_list.selectors.get(usize::try_from(i).ok()?));
+            let sheet = rule
+                .style_sheet_id
+                .as_ref()
+                .and_then(|id| sheets.get(id.inner()));
+            rules.push(MatchedRule {
+                selector: selectors.join(", "),
+                origin: match rule.origin {
+                    StyleSheetOrigin::UserAgent => "user-agent",
+                    StyleSheetOrigin::Injected => "injected",
+                    StyleSheetOrigin::Inspector => "inspector",
+                    StyleSheetOrigin::Regular => "author",
+                }
+                .to_string(),
+                source: sheet
+                    .map(|(url, _)| url.clone())
+                    .filter(|url| !url.is_empty()),
+                line: first.and_then(|value| value.range.as_ref()).map(|range| {
+                    sheet.map_or(0, |(_, start)| *start) + range.start_line as u32 + 1
+                }),
+                specificity: selectors
+                    .iter()
+                    .map(|text| selector_specificity(text))
+                    .max()
+                    .unwrap_or_default(),
+                media: rule
+                    .media
+                    .iter()
+                    .flatten()
+                    .map(|media| media.text.clone())
+                    .collect(),
+                properties: declarations(&rule.style),
+            });
+        }
+        if let Some(inline) = &styles.inline_style {
+            let properties = declarations(inline);
+            if !properties.is_empty() {
+                rules.push(MatchedRule {
+                    selector: "element.style".to_string(),
+                    origin: "inline".to_string(),
+                    source: None,
+                    line: None,
+                    specificity: (1, 0, 0, 0),
+                    media: Vec::new(),
+                    properties,
+                });
+            }
+        }
+
+        mark_overridden(&mut rules);
+        Ok(rules)
+    }
+
+    /// Content, padding, border and margin boxes of an element (DOM.getBoxModel)
+    pub async fn box_model(&self, selector: &str) -> Result<ElementBoxes> {
+        use chromiumoxide::cdp::browser_protocol::dom::GetBoxModelParams;
+
+        let page = self.get_page().await?;
+        let node_id = self.query_node(&page, selector).await?;
+        let model = page
+            .execute(GetBoxModelParams::builder().node_id(node_id).build())
+            .await
+            .context("Failed to get box model (is the element rendered?)")?
+            .result
+            .model;
+
+        let rect = |quad: &chromiumoxide::cdp::browser_protocol::dom::Quad| {
+            let points = quad.inner();
+            let xs = points.iter().step_by(2);
+            let ys = points.iter().skip(1).step_by(2);
+            let (left, right) =
+                xs.fold((f64::MAX, f64::MIN), |(lo, hi), &x| (lo.min(x), hi.max(x)));
+            let (top, bottom) =
+                ys.fold((f64::MAX, f64::MIN), |(lo, hi), &y| (lo.min(y), hi.max(y)));
+            BoxRect {
+                x: left,
+                y: top,
+                width: right - left,
+                height: bottom - top,
+            }
+        };
+        Ok(ElementBoxes {
+            content: rect(&model.content),
+            padding: rect(&model.padding),
+            border: rect(&model.border),
+            margin: rect(&model.margin),
+        })
+    }
+
+    /// DOM node id of the first element matching `selector`
+    async fn query_node(
+        &self,
+        page: &Page,
+        selector: &str,
+    ) -> Result<chromiumoxide::cdp::browser_protocol::dom::NodeId> {
+        use chromiumoxide::cdp::browser_protocol::dom::{
+            EnableParams as DomEnableParams, GetDocumentParams, QuerySelectorParams,
+        };
+
+        page.execute(DomEnableParams::default())
+            .await
+            .context("Failed to enable DOM domain")?;
+        let document = page
+            .execute(GetDocumentParams::default())
+            .await
+            .context("Failed to get document")?;
+        let node_id = page
+            .execute(QuerySelectorParams::new(
+                document.result.root.node_id,
+                selector,
+            ))
+            .await
+            .with_context(|| format!("Invalid selector \"{}\"", selector))?
+            .result
+            .node_id;
+        if *node_id.inner() == 0 {
+            return Err(anyhow!("No element matches selector \"{}\"", selector));
+        }
+        Ok(node_id)
+    }
+
     // =========================================================================
     // Anthropic Computer Use CDP methods
     // =========================================================================
@@ -1630,63 +5231,460 @@ impl CdpConnection {
                     }}));
                 }}
 
-                // Also select text if it's a text container
-                const selection = window.getSelection();
-                const range = document.createRange();
-                range.selectNodeContents(el);
-                selection.removeAllRanges();
-                selection.addRange(range);
+                // Also select text if it's a text container
+                const selection = window.getSelection();
+                const range = document.createRange();
+                range.selectNodeContents(el);
+                selection.removeAllRanges();
+                selection.addRange(range);
+
+                return true;
+            }})()
+        "#,
+                escaped
+            ))
+            .await?;
+
+        if result.as_bool() != Some(true) {
+            return Err(anyhow!("No element matches selector \"{}\"", selector));
+        }
+        Ok(())
+    }
+
+    /// Triple-click at coordinates
+    pub async fn triple_click_at(&self, x: f64, y: f64) -> Result<()> {
+        self.evaluate(&format!(
+            r#"
+            (function() {{
+                const el = document.elementFromPoint({}, {});
+                if (!el) return false;
+
+                // Dispatch 3 click events with increasing detail
+                for (let i = 1; i <= 3; i++) {{
+                    el.dispatchEvent(new MouseEvent('click', {{
+                        bubbles: true,
+                        cancelable: true,
+                        clientX: {},
+                        clientY: {},
+                        detail: i,
+                        view: window
+                    }}));
+                }}
+
+                // Try to select the text at that point
+                const selection = window.getSelection();
+                if (selection.rangeCount > 0) {{
+                    selection.modify('extend', 'backward', 'paragraphboundary');
+                    selection.modify('extend', 'forward', 'paragraphboundary');
+                }}
+
+                return true;
+            }})()
+        "#,
+            x, y, x, y
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Click at coordinates with real mouse events using CDP Input.dispatchMouseEvent
+    /// `click_count` > 1 sends press/release pairs with increasing detail (double-click)
+    pub async fn dispatch_click_at(
+        &self,
+        x: f64,
+        y: f64,
+        button: &str,
+        click_count: i64,
+    ) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::input::{
+            DispatchMouseEventParams, DispatchMouseEventType, MouseButton,
+        };
+
+        let button = button.to_lowercase();
+        let mouse_button = || match button.as_str() {
+            "middle" | "auxiliary" => MouseButton::Middle,
+            "right" | "secondary" => MouseButton::Right,
+            _ => MouseButton::Left,
+        };
+        // `buttons` bitmask: left = 1, right = 2, middle = 4
+        let buttons = match button.as_str() {
+            "middle" | "auxiliary" => 4,
+            "right" | "secondary" => 2,
+            _ => 1,
+        };
+
+        let page = self.get_page().await?;
+
+        let moved = DispatchMouseEventParams::builder()
+            .r#type(DispatchMouseEventType::MouseMoved)
+            .x(x)
+            .y(y)
+            .build()
+            .map_err(|e| anyhow!("Failed to build DispatchMouseEvent params: {}", e))?;
+        page.execute(moved).await.context("Failed to move mouse")?;
+
+        for count in 1..=click_count {
+            for (event_type, pressed) in [
+                (DispatchMouseEventType::MousePressed, buttons),
+                (DispatchMouseEventType::MouseReleased, 0),
+            ] {
+                let params = DispatchMouseEventParams::builder()
+                    .r#type(event_type)
+                    .x(x)
+                    .y(y)
+                    .button(mouse_button())
+                    .buttons(pressed)
+                    .click_count(count)
+                    .build()
+                    .map_err(|e| anyhow!("Failed to build DispatchMouseEvent params: {}", e))?;
+                page.execute(params)
+                    .await
+                    .context("Failed to dispatch mouse event")?;
+            }
+        }
+
+        // Keep cursor tracking in sync with mouse_move
+        self.evaluate(&format!(
+            "window.__domguardCursorX = {}; window.__domguardCursorY = {};",
+            x, y
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Hover with the real mouse pointer, so CSS `:hover` applies too
+    ///
+    /// The pointer stays over the element until the next mouse event.
+    pub async fn hover_pointer(&self, selector: &str) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::input::{
+            DispatchMouseEventParams, DispatchMouseEventType,
+        };
+
+        let (x, y) = self.element_click_point(selector).await?;
+        let moved = DispatchMouseEventParams::builder()
+            .r#type(DispatchMouseEventType::MouseMoved)
+            .x(x)
+            .y(y)
+            .build()
+            .map_err(|e| anyhow!("Failed to build DispatchMouseEvent params: {}", e))?;
+        self.get_page()
+            .await?
+            .execute(moved)
+            .await
+            .context("Failed to move mouse")?;
+
+        self.evaluate(&format!(
+            "window.__domguardCursorX = {}; window.__domguardCursorY = {};",
+            x, y
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Press at the first point, move through the rest `pause` apart and
+    /// release at the last, as trusted mouse (left button) or touch input
+    pub async fn dispatch_gesture(
+        &self,
+        points: &[(f64, f64)],
+        pause: Duration,
+        touch: bool,
+    ) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::input::{
+            DispatchMouseEventParams, DispatchMouseEventType, MouseButton,
+        };
+
+        let (Some(&first), Some(&last)) = (points.first(), points.last()) else {
+            return Err(anyhow!("A gesture needs at least one point"));
+        };
+        if touch {
+            let frames: Vec<Vec<(f64, f64)>> = points.iter().map(|&point| vec![point]).collect();
+            return self.dispatch_touch(&frames, pause).await;
+        }
+        let page = self.get_page().await?;
+
+        let mouse_event = |kind, (x, y): (f64, f64), buttons: i64| {
+            DispatchMouseEventParams::builder()
+                .r#type(kind)
+                .x(x)
+                .y(y)
+                .button(MouseButton::Left)
+                .buttons(buttons)
+                .click_count(1)
+                .build()
+                .map_err(|e| anyhow!("Failed to build DispatchMouseEvent params: {}", e))
+        };
+
+        page.execute(mouse_event(DispatchMouseEventType::MouseMoved, first, 0)?)
+            .await
+            .context("Failed to move mouse")?;
+        page.execute(mouse_event(DispatchMouseEventType::MousePressed, first, 1)?)
+            .await
+            .context("Failed to dispatch mouse event")?;
+        for &point in &points[1..] {
+            tokio::time::sleep(pause).await;
+            page.execute(mouse_event(DispatchMouseEventType::MouseMoved, point, 1)?)
+                .await
+                .context("Failed to move mouse")?;
+        }
+        page.execute(mouse_event(DispatchMouseEventType::MouseReleased, last, 0)?)
+            .await
+            .context("Failed to dispatch mouse event")?;
+
+        // Keep cursor tracking in sync with mouse_move
+        self.evaluate(&format!(
+            "window.__domguardCursorX = {}; window.__domguardCursorY = {};",
+            last.0, last.1
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Make the page see a touch screen (`ontouchstart`, `maxTouchPoints`)
+    ///
+    /// Lasts for this connection; pages that check for touch support on load
+    /// only notice it after a reload in the same session.
+    pub async fn enable_touch_emulation(&self) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::emulation::SetTouchEmulationEnabledParams;
+
+        let page = self.get_page().await?;
+        let params = SetTouchEmulationEnabledParams::builder()
+            .enabled(true)
+            .max_touch_points(5)
+            .build()
+            .map_err(|e| anyhow!("Failed to build SetTouchEmulationEnabled params: {}", e))?;
+        page.execute(params)
+            .await
+            .context("Failed to enable touch emulation")?;
+        Ok(())
+    }
+
+    /// Trusted touch input: `frames[0]` touches down, each later frame moves
+    /// the same fingers `pause` apart, then every finger lifts
+    ///
+    /// Each frame lists one point per finger, in the same order throughout.
+    pub async fn dispatch_touch(&self, frames: &[Vec<(f64, f64)>], pause: Duration) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::input::{
+            DispatchTouchEventParams, DispatchTouchEventType, TouchPoint,
+        };
+
+        if frames.is_empty() || frames[0].is_empty() {
+            return Err(anyhow!("A touch needs at least one point"));
+        }
+        self.enable_touch_emulation().await?;
+        let page = self.get_page().await?;
+
+        let touch_event = |kind, fingers: &[(f64, f64)]| {
+            let points = fingers
+                .iter()
+                .enumerate()
+                .map(|(id, &(x, y))| {
+                    TouchPoint::builder()
+                        .x(x)
+                        .y(y)
+                        .id(id as f64)
+                        .build()
+                        .map_err(|e| anyhow!("Failed to build TouchPoint: {}", e))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            DispatchTouchEventParams::builder()
+                .r#type(kind)
+                .touch_points(points)
+                .build()
+                .map_err(|e| anyhow!("Failed to build DispatchTouchEvent params: {}", e))
+        };
+
+        page.execute(touch_event(DispatchTouchEventType::TouchStart, &frames[0])?)
+            .await
+            .context("Failed to dispatch touch event")?;
+        for fingers in &frames[1..] {
+            tokio::time::sleep(pause).await;
+            page.execute(touch_event(DispatchTouchEventType::TouchMove, fingers)?)
+                .await
+                .context("Failed to dispatch touch event")?;
+        }
+        page.execute(touch_event(DispatchTouchEventType::TouchEnd, &[])?)
+            .await
+            .context("Failed to dispatch touch event")?;
+        Ok(())
+    }
+
+    /// Read the bitmap of the canvas at (or inside) `selector`
+    ///
+    /// Canvases that can't be read back (cross-origin taint, WebGL without
+    /// `preserveDrawingBuffer`, which reads as blank) come back without data,
+    /// with the reason and the page area to screenshot instead.
+    pub async fn canvas_bitmap(&self, selector: &str, format: ImageFormat) -> Result<CanvasBitmap> {
+        let found = self
+            .evaluate(&format!(
+                r"(async function() {{
+                    const found = document.querySelector({});
+                    if (!found) return null;
+                    const canvas = found.tagName === 'CANVAS' ? found : found.querySelector('canvas');
+                    if (!canvas) return {{ error: 'no canvas' }};
+                    canvas.scrollIntoView({{ block: 'center' }});
+                    await new Promise(r => requestAnimationFrame(() => r()));
+                    const rect = canvas.getBoundingClientRect();
+                    let data = null;
+                    let reason = null;
+                    try {{
+                        data = canvas.toDataURL({});
+                        const blank = document.createElement('canvas');
+                        blank.width = canvas.width;
+                        blank.height = canvas.height;
+                        if (data === blank.toDataURL({})) {{
+                            data = null;
+                            reason = 'it reads back blank (WebGL without preserveDrawingBuffer?)';
+                        }}
+                    }} catch (e) {{
+                        reason = e.name === 'SecurityError'
+                            ? 'it is tainted by cross-origin content'
+                            : 'it cannot be read (' + e.name + ')';
+                    }}
+                    return {{
+                        width: canvas.width,
+                        height: canvas.height,
+                        data,
+                        reason,
+                        clip: {{
+                            x: rect.x + window.scrollX,
+                            y: rect.y + window.scrollY,
+                            width: rect.width,
+                            height: rect.height,
+                        }},
+                    }};
+                }})()",
+                serde_json::json!(selector),
+                serde_json::json!(format.mime_type()),
+                serde_json::json!(format.mime_type())
+            ))
+            .await?;
+        if found.is_null() {
+            return Err(anyhow!("No element matches selector \"{}\"", selector));
+        }
+        if found.get("error").is_some() {
+            return Err(anyhow!("No canvas at \"{}\"", selector));
+        }
+
+        let data = match found["data"].as_str() {
+            Some(url) => {
+                let encoded = url.split_once(',').map_or("", |(_, data)| data);
+                Some(
+                    base64::engine::general_purpose::STANDARD
+                        .decode(encoded)
+                        .context("Failed to decode canvas data")?,
+                )
+            }
+            None => None,
+        };
+        let size = |key: &str| u32::try_from(found[key].as_u64().unwrap_or(0)).unwrap_or(u32::MAX);
+        let clip = |key: &str| found["clip"][key].as_f64().unwrap_or(0.0);
+        Ok(CanvasBitmap {
+            width: size("width"),
+            height: size("height"),
+            data,
+            reason: found["reason"].as_str().map(str::to_string),
+            clip: ClipRect {
+                x: clip("x"),
+                y: clip("y"),
+                width: clip("width"),
+                height: clip("height"),
+            },
+        })
+    }
 
+    /// Scroll element into view and return its center point
+    pub async fn element_click_point(&self, selector: &str) -> Result<(f64, f64)> {
+        let escaped = selector.replace('\\', "\\\\").replace('\'', "\\'");
+        let found = self
+            .evaluate(&format!(
+                r#"
+            (function() {{
+                const el = document.querySelector('{}');
+                if (!el) return false;
+                el.scrollIntoView({{ block: 'center' }});
                 return true;
             }})()
-        "#,
+            "#,
                 escaped
             ))
             .await?;
 
-        if result.as_bool() != Some(true) {
+        if found.as_bool() != Some(true) {
             return Err(anyhow!("No element matches selector \"{}\"", selector));
         }
-        Ok(())
+        self.get_element_center(selector).await
     }
 
-    /// Triple-click at coordinates
-    pub async fn triple_click_at(&self, x: f64, y: f64) -> Result<()> {
-        self.evaluate(&format!(
+    /// Start recording elements added or toggled on the page (see `take_ui_changes`)
+    pub async fn watch_ui_changes(&self) -> Result<()> {
+        self.evaluate(
             r#"
-            (function() {{
-                const el = document.elementFromPoint({}, {});
-                if (!el) return false;
-
-                // Dispatch 3 click events with increasing detail
-                for (let i = 1; i <= 3; i++) {{
-                    el.dispatchEvent(new MouseEvent('click', {{
-                        bubbles: true,
-                        cancelable: true,
-                        clientX: {},
-                        clientY: {},
-                        detail: i,
-                        view: window
-                    }}));
-                }}
-
-                // Try to select the text at that point
-                const selection = window.getSelection();
-                if (selection.rangeCount > 0) {{
-                    selection.modify('extend', 'backward', 'paragraphboundary');
-                    selection.modify('extend', 'forward', 'paragraphboundary');
-                }}
-
+            (function() {
+                if (window.__domguardUiObserver) window.__domguardUiObserver.disconnect();
+                window.__domguardUiChanges = new Set();
+                window.__domguardUiObserver = new MutationObserver(mutations => {
+                    for (const m of mutations) {
+                        if (m.type === 'childList') {
+                            m.addedNodes.forEach(n => {
+                                if (n.nodeType === 1) window.__domguardUiChanges.add(n);
+                            });
+                        } else if (m.target.nodeType === 1) {
+                            window.__domguardUiChanges.add(m.target);
+                        }
+                    }
+                });
+                window.__domguardUiObserver.observe(document.body, {
+                    childList: true,
+                    subtree: true,
+                    attributes: true,
+                    attributeFilter: ['class', 'style', 'hidden', 'open', 'aria-expanded', 'aria-hidden']
+                });
                 return true;
-            }})()
+            })()
         "#,
-            x, y, x, y
-        ))
+        )
         .await?;
-
         Ok(())
     }
 
+    /// Describe visible elements added or toggled since `watch_ui_changes`
+    /// Also reports selected text and the focused element (dblclick usually selects a word)
+    pub async fn take_ui_changes(&self) -> Result<serde_json::Value> {
+        self.evaluate(
+            r#"
+            (function() {
+                const changed = Array.from(window.__domguardUiChanges || []);
+                if (window.__domguardUiObserver) window.__domguardUiObserver.disconnect();
+                window.__domguardUiObserver = null;
+                window.__domguardUiChanges = null;
+
+                const visible = changed.filter(el =>
+                    el.isConnected && el.getClientRects().length > 0 &&
+                    getComputedStyle(el).visibility !== 'hidden');
+                // Keep outermost elements only
+                const roots = visible.filter(el => !visible.some(o => o !== el && o.contains(el)));
+
+                const active = document.activeElement;
+                return {
+                    elements: roots.slice(0, 10).map(el => ({
+                        tag: el.tagName.toLowerCase(),
+                        id: el.id || null,
+                        classes: Array.from(el.classList).slice(0, 5),
+                        role: el.getAttribute('role'),
+                        text: (el.innerText || '').trim().replace(/\s+/g, ' ').slice(0, 100)
+                    })),
+                    total_changed: roots.length,
+                    selection: window.getSelection().toString() || null,
+                    focused: active && active !== document.body ? active.tagName.toLowerCase() : null
+                };
+            })()
+        "#,
+        )
+        .await
+    }
+
     /// Press mouse button down (without releasing)
     pub async fn mouse_down(&self, button: &str) -> Result<()> {
         let button_num = match button.to_lowercase().as_str() {
@@ -1794,6 +5792,48 @@ impl CdpConnection {
     // Chrome DevTools MCP CDP methods
     // =========================================================================
 
+    /// Take a V8 heap snapshot of the page, returned as the raw `.heapsnapshot` JSON
+    ///
+    /// With `gc`, garbage is collected first so only live objects remain.
+    pub async fn heap_snapshot(&self, gc: bool) -> Result<String> {
+        use chromiumoxide::cdp::js_protocol::heap_profiler::{
+            CollectGarbageParams, EnableParams, EventAddHeapSnapshotChunk, TakeHeapSnapshotParams,
+        };
+        use futures::FutureExt;
+
+        let page = self.get_page().await?;
+        page.execute(EnableParams::default())
+            .await
+            .context("Failed to enable HeapProfiler domain")?;
+        if gc {
+            page.execute(CollectGarbageParams::default())
+                .await
+                .context("Failed to collect garbage")?;
+        }
+
+        // The snapshot arrives as chunk events before the command returns
+        let mut chunks = page.event_listener::<EventAddHeapSnapshotChunk>().await?;
+        let take = page.execute(TakeHeapSnapshotParams::default());
+        tokio::pin!(take);
+        let mut snapshot = String::new();
+        loop {
+            tokio::select! {
+                result = &mut take => {
+                    result.context("Failed to take heap snapshot")?;
+                    break;
+                }
+                Some(event) = chunks.next() => snapshot.push_str(&event.chunk),
+            }
+        }
+        while let Some(Some(event)) = chunks.next().now_or_never() {
+            snapshot.push_str(&event.chunk);
+        }
+        if snapshot.is_empty() {
+            return Err(anyhow!("Chrome sent an empty heap snapshot"));
+        }
+        Ok(snapshot)
+    }
+
     /// Get performance metrics including Core Web Vitals
     pub async fn get_performance_metrics(&self) -> Result<serde_json::Value> {
         use chromiumoxide::cdp::browser_protocol::performance::{EnableParams, GetMetricsParams};
@@ -1908,85 +5948,450 @@ impl CdpConnection {
         let download_bps = (download_throughput_kbps * 1024.0 / 8.0) as i64;
         let upload_bps = (upload_throughput_kbps * 1024.0 / 8.0) as i64;
 
-        let params = EmulateNetworkConditionsParams::builder()
-            .offline(offline)
-            .latency(latency_ms)
-            .download_throughput(download_bps as f64)
-            .upload_throughput(upload_bps as f64)
-            .build()
-            .map_err(|e| anyhow!("Failed to build network throttling params: {}", e))?;
+        let params = EmulateNetworkConditionsParams::builder()
+            .offline(offline)
+            .latency(latency_ms)
+            .download_throughput(download_bps as f64)
+            .upload_throughput(upload_bps as f64)
+            .build()
+            .map_err(|e| anyhow!("Failed to build network throttling params: {}", e))?;
+
+        page.execute(params)
+            .await
+            .context("Failed to set network throttling")?;
+
+        Ok(())
+    }
+
+    /// Disable network throttling
+    pub async fn disable_network_throttling(&self) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::network::EmulateNetworkConditionsParams;
+
+        let page = self.get_page().await?;
+
+        let params = EmulateNetworkConditionsParams::builder()
+            .offline(false)
+            .latency(0.0)
+            .download_throughput(-1.0) // -1 disables throttling
+            .upload_throughput(-1.0)
+            .build()
+            .map_err(|e| anyhow!("Failed to build network params: {}", e))?;
+
+        page.execute(params)
+            .await
+            .context("Failed to disable network throttling")?;
+
+        Ok(())
+    }
+
+    /// Get detailed network request info using Resource Timing API
+    pub async fn get_network_details(&self, filter: Option<&str>) -> Result<serde_json::Value> {
+        let filter_str = filter.map(|f| f.replace('\'', "\\'")).unwrap_or_default();
+
+        let js = format!(
+            r#"
+            (function() {{
+                const entries = performance.getEntriesByType('resource');
+                const filter = '{}';
+
+                return entries
+                    .filter(e => !filter || e.name.includes(filter))
+                    .map(e => {{
+                        // Calculate timing breakdown
+                        const timing = {{
+                            dns: e.domainLookupEnd - e.domainLookupStart,
+                            connect: e.connectEnd - e.connectStart,
+                            ssl: e.secureConnectionStart > 0 ? e.connectEnd - e.secureConnectionStart : 0,
+                            ttfb: e.responseStart - e.requestStart,
+                            download: e.responseEnd - e.responseStart,
+                            total: e.duration
+                        }};
+
+                        return {{
+                            url: e.name,
+                            method: 'GET', // Resource Timing doesn't expose method
+                            type: e.initiatorType,
+                            status: null, // Not available in Resource Timing
+                            size_bytes: e.transferSize || null,
+                            encoded_size: e.encodedBodySize || null,
+                            decoded_size: e.decodedBodySize || null,
+                            timing: timing,
+                            cache_hit: e.transferSize === 0 && e.decodedBodySize > 0,
+                            next_hop_protocol: e.nextHopProtocol || null
+                        }};
+                    }});
+            }})()
+        "#,
+            filter_str
+        );
+
+        self.evaluate(&js).await
+    }
+
+    // =========================================================================
+    // Network control CDP methods
+    // =========================================================================
+
+    /// Start answering requests from mock rules using CDP Fetch domain
+    /// Credentials (keyed by domain pattern) answer auth challenges and add bearer tokens
+    /// Interception lives as long as the returned task (and this process) runs
+    pub async fn start_request_interception(
+        &self,
+        rules: Vec<MockRule>,
+        credentials: HashMap<String, Credential>,
+    ) -> Result<tokio::task::JoinHandle<()>> {
+        use chromiumoxide::cdp::browser_protocol::fetch::{
+            EnableParams, EventAuthRequired, EventRequestPaused, RequestPattern,
+        };
+
+        // Stored credentials may reference `${secret:...}` or `${env:...}`
+        let credentials = crate::network::resolve_credentials(credentials)?;
+        let page = self.get_page().await?;
+
+        // Listen before enabling so no paused request is missed
+        let mut paused_events = page.event_listener::<EventRequestPaused>().await?;
+        let mut auth_events = page.event_listener::<EventAuthRequired>().await?;
+
+        let mut patterns: Vec<RequestPattern> = rules
+            .iter()
+            .map(|r| {
+                RequestPattern::builder()
+                    .url_pattern(r.pattern.clone())
+                    .build()
+            })
+            .collect();
+        if !credentials.is_empty() {
+            // Auth challenges are only reported for intercepted requests
+            patterns.push(RequestPattern::builder().url_pattern("*").build());
+        }
+
+        let params = EnableParams::builder()
+            .patterns(patterns)
+            .handle_auth_requests(!credentials.is_empty())
+            .build();
+
+        page.execute(params)
+            .await
+            .context("Failed to enable request interception")?;
+
+        let rules = Arc::new(rules);
+        let credentials = Arc::new(credentials);
+        let handle = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    Some(event) = paused_events.next() => {
+                        let page = page.clone();
+                        let rules = Arc::clone(&rules);
+                        let credentials = Arc::clone(&credentials);
+                        // Handle each request separately so delayed mocks don't stall others
+                        tokio::spawn(async move {
+                            let rule = rules.iter().find(|r| r.matches(&event.request.url));
+                            let credential = find_credential(&credentials, &event.request.url);
+                            let _ = respond_to_paused_request(&page, &event, rule, credential).await;
+                        });
+                    }
+                    Some(event) = auth_events.next() => {
+                        let credential = find_credential(&credentials, &event.auth_challenge.origin);
+                        let _ = respond_to_auth_challenge(&page, &event, credential).await;
+                    }
+                    else => break,
+                }
+            }
+        });
+
+        Ok(handle)
+    }
+
+    /// Block requests matching URL patterns using CDP Network.setBlockedURLs
+    /// An empty list clears any blocking for this connection
+    pub async fn set_blocked_urls(&self, patterns: Vec<String>) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::network::{EnableParams, SetBlockedUrLsParams};
+
+        let page = self.get_page().await?;
+
+        // Blocking only takes effect while the Network domain is enabled
+        page.execute(EnableParams::default())
+            .await
+            .context("Failed to enable network domain")?;
+
+        page.execute(SetBlockedUrLsParams::new(patterns))
+            .await
+            .context("Failed to set blocked URLs")?;
+
+        Ok(())
+    }
+
+    /// Send extra HTTP headers with every request using CDP Network.setExtraHTTPHeaders
+    /// An empty map clears previously set headers for this connection
+    pub async fn set_extra_headers(&self, headers: &HashMap<String, String>) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::network::{
+            EnableParams, Headers, SetExtraHttpHeadersParams,
+        };
+
+        let page = self.get_page().await?;
+
+        page.execute(EnableParams::default())
+            .await
+            .context("Failed to enable network domain")?;
+
+        let headers = Headers::new(serde_json::to_value(headers)?);
+        page.execute(SetExtraHttpHeadersParams::new(headers))
+            .await
+            .context("Failed to set extra HTTP headers")?;
+
+        Ok(())
+    }
+
+    /// Set the DevTools playback rate for all animations (1 = normal)
+    ///
+    /// Chrome resets it to 1 once this connection closes.
+    pub async fn set_animation_playback_rate(&self, rate: f64) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::animation::SetPlaybackRateParams;
+
+        let page = self.get_page().await?;
+        page.execute(SetPlaybackRateParams::new(rate))
+            .await
+            .context("Failed to set animation playback rate")?;
+
+        Ok(())
+    }
+
+    /// Run `source` before page scripts in every new document of this page
+    ///
+    /// Chrome drops the registration when this connection closes, so
+    /// persisted scripts are registered again on each connection.
+    pub async fn add_init_script(&self, source: &str) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::page::AddScriptToEvaluateOnNewDocumentParams;
+
+        let page = self.get_page().await?;
+        page.execute(AddScriptToEvaluateOnNewDocumentParams::new(source))
+            .await
+            .context("Failed to add init script")?;
+
+        Ok(())
+    }
+
+    /// Override the browser user agent using CDP Emulation.setUserAgentOverride
+    pub async fn set_user_agent(&self, user_agent: &str) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::emulation::SetUserAgentOverrideParams;
+
+        let page = self.get_page().await?;
+        page.execute(SetUserAgentOverrideParams::new(user_agent))
+            .await
+            .context("Failed to override user agent")?;
+
+        Ok(())
+    }
+
+    /// Stop request interception (disables CDP Fetch domain)
+    pub async fn stop_request_interception(&self) -> Result<()> {
+        use chromiumoxide::cdp::browser_protocol::fetch::DisableParams;
+
+        let page = self.get_page().await?;
+        page.execute(DisableParams::default())
+            .await
+            .context("Failed to disable request interception")?;
+
+        Ok(())
+    }
+}
+
+/// Wait until nothing seen by `watch_network` has been in flight for 500ms
+pub async fn wait_for_network_idle(watch: &NetworkWatch, timeout_ms: u64) -> Result<()> {
+    let timeout = Duration::from_millis(timeout_ms);
+    let idle = Duration::from_millis(NETWORK_IDLE_MS);
+    let start = std::time::Instant::now();
+
+    loop {
+        let pending = {
+            let activity = watch.lock().await;
+            if activity.pending.is_empty() && activity.changed.elapsed() >= idle {
+                return Ok(());
+            }
+            activity.pending.len()
+        };
+
+        if start.elapsed() > timeout {
+            return Err(anyhow!(
+                "Timeout waiting for network idle ({}ms); {} request(s) still in flight",
+                timeout_ms,
+                pending
+            ));
+        }
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// Accept or dismiss the open JavaScript dialog
+async fn answer_dialog(page: &Page, accept: bool, prompt_text: Option<&str>) -> Result<()> {
+    use chromiumoxide::cdp::browser_protocol::page::HandleJavaScriptDialogParams;
 
-        page.execute(params)
-            .await
-            .context("Failed to set network throttling")?;
+    let mut builder = HandleJavaScriptDialogParams::builder().accept(accept);
+    if let Some(text) = prompt_text {
+        builder = builder.prompt_text(text);
+    }
+    let params = builder
+        .build()
+        .map_err(|e| anyhow!("Failed to build dialog params: {}", e))?;
 
-        Ok(())
+    page.execute(params)
+        .await
+        .context("Failed to handle dialog")?;
+    Ok(())
+}
+
+/// Send a single Input.dispatchKeyEvent
+async fn dispatch_key_event(
+    page: &Page,
+    event_type: chromiumoxide::cdp::browser_protocol::input::DispatchKeyEventType,
+    stroke: &KeyStroke,
+    modifiers: i64,
+) -> Result<()> {
+    use chromiumoxide::cdp::browser_protocol::input::{
+        DispatchKeyEventParams, DispatchKeyEventType,
+    };
+
+    let is_key_down = matches!(event_type, DispatchKeyEventType::KeyDown);
+    let mut builder = DispatchKeyEventParams::builder()
+        .r#type(event_type)
+        .modifiers(modifiers)
+        .key(stroke.key.clone())
+        .code(stroke.code.clone())
+        .windows_virtual_key_code(stroke.key_code)
+        .native_virtual_key_code(stroke.key_code);
+
+    if is_key_down {
+        if let Some(text) = &stroke.text {
+            builder = builder.text(text.clone()).unmodified_text(text.clone());
+        }
     }
 
-    /// Disable network throttling
-    pub async fn disable_network_throttling(&self) -> Result<()> {
-        use chromiumoxide::cdp::browser_protocol::network::EmulateNetworkConditionsParams;
+    let params = builder
+        .build()
+        .map_err(|e| anyhow!("Failed to build DispatchKeyEvent params: {}", e))?;
 
-        let page = self.get_page().await?;
+    page.execute(params)
+        .await
+        .with_context(|| format!("Failed to dispatch key \"{}\"", stroke.key))?;
 
-        let params = EmulateNetworkConditionsParams::builder()
-            .offline(false)
-            .latency(0.0)
-            .download_throughput(-1.0) // -1 disables throttling
-            .upload_throughput(-1.0)
-            .build()
-            .map_err(|e| anyhow!("Failed to build network params: {}", e))?;
+    Ok(())
+}
+
+/// Answer a paused request: fulfill or fail it from a mock rule, or let it through
+async fn respond_to_paused_request(
+    page: &Page,
+    event: &chromiumoxide::cdp::browser_protocol::fetch::EventRequestPaused,
+    rule: Option<&MockRule>,
+    credential: Option<&Credential>,
+) -> Result<()> {
+    use chromiumoxide::cdp::browser_protocol::fetch::{
+        ContinueRequestParams, FailRequestParams, FulfillRequestParams, HeaderEntry,
+    };
+    use chromiumoxide::cdp::browser_protocol::network::ErrorReason;
+
+    let Some(rule) = rule else {
+        let params = if let Some(Credential::Bearer { token }) = credential {
+            // Keep the original headers; Fetch.continueRequest replaces them wholesale
+            let mut headers: Vec<HeaderEntry> = event
+                .request
+                .headers
+                .inner()
+                .as_object()
+                .map(|map| {
+                    map.iter()
+                        .filter(|(name, _)| !name.eq_ignore_ascii_case("authorization"))
+                        .map(|(name, value)| {
+                            HeaderEntry::new(name.clone(), value.as_str().unwrap_or_default())
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            headers.push(HeaderEntry::new(
+                "Authorization",
+                format!("Bearer {}", token),
+            ));
+
+            ContinueRequestParams::builder()
+                .request_id(event.request_id.clone())
+                .headers(headers)
+                .build()
+                .map_err(|e| anyhow!("Failed to build ContinueRequest params: {}", e))?
+        } else {
+            ContinueRequestParams::new(event.request_id.clone())
+        };
 
         page.execute(params)
             .await
-            .context("Failed to disable network throttling")?;
+            .context("Failed to continue request")?;
+        return Ok(());
+    };
 
-        Ok(())
+    if rule.delay_ms > 0 {
+        tokio::time::sleep(Duration::from_millis(rule.delay_ms)).await;
     }
 
-    /// Get detailed network request info using Resource Timing API
-    pub async fn get_network_details(&self, filter: Option<&str>) -> Result<serde_json::Value> {
-        let filter_str = filter.map(|f| f.replace('\'', "\\'")).unwrap_or_default();
+    if rule.abort {
+        page.execute(FailRequestParams::new(
+            event.request_id.clone(),
+            ErrorReason::Failed,
+        ))
+        .await
+        .context("Failed to abort request")?;
+        return Ok(());
+    }
 
-        let js = format!(
-            r#"
-            (function() {{
-                const entries = performance.getEntriesByType('resource');
-                const filter = '{}';
+    let body = base64::engine::general_purpose::STANDARD.encode(rule.body.as_deref().unwrap_or(""));
+    let params = FulfillRequestParams::builder()
+        .request_id(event.request_id.clone())
+        .response_code(i64::from(rule.status))
+        .response_headers(
+            rule.headers
+                .iter()
+                .map(|(name, value)| HeaderEntry::new(name.clone(), value.clone())),
+        )
+        .body(body)
+        .build()
+        .map_err(|e| anyhow!("Failed to build FulfillRequest params: {}", e))?;
 
-                return entries
-                    .filter(e => !filter || e.name.includes(filter))
-                    .map(e => {{
-                        // Calculate timing breakdown
-                        const timing = {{
-                            dns: e.domainLookupEnd - e.domainLookupStart,
-                            connect: e.connectEnd - e.connectStart,
-                            ssl: e.secureConnectionStart > 0 ? e.connectEnd - e.secureConnectionStart : 0,
-                            ttfb: e.responseStart - e.requestStart,
-                            download: e.responseEnd - e.responseStart,
-                            total: e.duration
-                        }};
+    page.execute(params)
+        .await
+        .context("Failed to fulfill mocked request")?;
 
-                        return {{
-                            url: e.name,
-                            method: 'GET', // Resource Timing doesn't expose method
-                            type: e.initiatorType,
-                            status: null, // Not available in Resource Timing
-                            size_bytes: e.transferSize || null,
-                            encoded_size: e.encodedBodySize || null,
-                            decoded_size: e.decodedBodySize || null,
-                            timing: timing,
-                            cache_hit: e.transferSize === 0 && e.decodedBodySize > 0,
-                            next_hop_protocol: e.nextHopProtocol || null
-                        }};
-                    }});
-            }})()
-        "#,
-            filter_str
-        );
+    Ok(())
+}
 
-        self.evaluate(&js).await
+/// Answer an auth challenge with stored basic credentials, or cancel it
+async fn respond_to_auth_challenge(
+    page: &Page,
+    event: &chromiumoxide::cdp::browser_protocol::fetch::EventAuthRequired,
+    credential: Option<&Credential>,
+) -> Result<()> {
+    use chromiumoxide::cdp::browser_protocol::fetch::{
+        AuthChallengeResponse, AuthChallengeResponseResponse, ContinueWithAuthParams,
+    };
+
+    let response = match credential {
+        Some(Credential::Basic { username, password }) => AuthChallengeResponse::builder()
+            .response(AuthChallengeResponseResponse::ProvideCredentials)
+            .username(username.clone())
+            .password(password.clone())
+            .build(),
+        // No basic credentials for this origin; a headless browser can't prompt
+        _ => AuthChallengeResponse::builder()
+            .response(AuthChallengeResponseResponse::CancelAuth)
+            .build(),
     }
+    .map_err(|e| anyhow!("Failed to build AuthChallengeResponse: {}", e))?;
+
+    page.execute(ContinueWithAuthParams::new(
+        event.request_id.clone(),
+        response,
+    ))
+    .await
+    .context("Failed to answer auth challenge")?;
+
+    Ok(())
 }
 
 impl CdpConnection {
@@ -2246,6 +6651,503 @@ fn parse_color(color: &str) -> Option<(u8, u8, u8, u8)> {
     None
 }
 
+/// A CSS rule matched by an element, from `matched_styles`
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchedRule {
+    /// The rule's selectors that match the element
+    pub selector: String,
+    /// author, user-agent, injected, inspector or inline
+    pub origin: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// 1-based line in `source`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    /// (inline, ids, classes, types)
+    pub specificity: (u32, u32, u32, u32),
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub media: Vec<String>,
+    pub properties: Vec<MatchedProperty>,
+}
+
+/// A declaration in a matched rule
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchedProperty {
+    pub name: String,
+    pub value: String,
+    pub important: bool,
+    /// A later rule wins for this property
+    pub overridden: bool,
+}
+
+/// A rectangle in viewport CSS pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BoxRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// The CSS boxes of an element, from the inside out
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ElementBoxes {
+    pub content: BoxRect,
+    pub padding: BoxRect,
+    pub border: BoxRect,
+    pub margin: BoxRect,
+}
+
+/// Selector specificity as (inline, ids, classes, types), with inline always 0
+///
+/// `:is()`, `:not()` and `:has()` count their most specific argument and
+/// `:where()` counts nothing. Nesting beyond that is approximated.
+pub fn selector_specificity(selector: &str) -> (u32, u32, u32, u32) {
+    fn add(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32)) -> (u32, u32, u32, u32) {
+        (a.0 + b.0, a.1 + b.1, a.2 + b.2, a.3 + b.3)
+    }
+
+    let chars: Vec<char> = selector.chars().collect();
+    let mut total = (0, 0, 0, 0);
+    let mut i = 0;
+    let ident_end = |mut j: usize| {
+        while j < chars.len()
+            && (chars[j].is_alphanumeric() || matches!(chars[j], '-' | '_' | '\\'))
+        {
+            j += if chars[j] == '\\' { 2 } else { 1 };
+        }
+        j.min(chars.len())
+    };
+    while i < chars.len() {
+        match chars[i] {
+            '#' => {
+                total.1 += 1;
+                i = ident_end(i + 1);
+            }
+            '.' => {
+                total.2 += 1;
+                i = ident_end(i + 1);
+            }
+            '[' => {
+                total.2 += 1;
+                while i < chars.len() && chars[i] != ']' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            ':' => {
+                let element = chars.get(i + 1) == Some(&':');
+                let start = if element { i + 2 } else { i + 1 };
+                let end = ident_end(start);
+                let name: String = chars[start..end].iter().collect::<String>().to_lowercase();
+                i = end;
+                // Functional pseudo-class arguments
+                let mut argument = None;
+                if chars.get(i) == Some(&'(') {
+                    let mut depth = 0;
+                    let open = i;
+                    while i < chars.len() {
+                        match chars[i] {
+                            '(' => depth += 1,
+                            ')' => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    break;
+                                }
+                            }
+                            _ => {}
+                        }
+                        i += 1;
+                    }
+                    argument = Some(
+                        chars[open + 1..i.min(chars.len())]
+                            .iter()
+                            .collect::<String>(),
+                    );
+                    i += 1;
+                }
+                let legacy_element = matches!(
+                    name.as_str(),
+                    "before" | "after" | "first-line" | "first-letter"
+                );
+                if element || legacy_element {
+                    total.3 += 1;
+                } else {
+                    match (name.as_str(), argument) {
+                        ("where", _) => {}
+                        ("is" | "not" | "has" | "matches", Some(argument)) => {
+                            let most = argument
+                                .split(',')
+                                .map(selector_specificity)
+                                .max()
+                                .unwrap_or_default();
+                            total = add(total, most);
+                        }
+                        _ => total.2 += 1,
+                    }
+                }
+            }
+            c if c.is_alphabetic() => {
+                total.3 += 1;
+                i = ident_end(i);
+            }
+            _ => i += 1,
+        }
+    }
+    total
+}
+
+/// Mark declarations beaten by a later (or more important) one for the same property
+fn mark_overridden(rules: &mut [MatchedRule]) {
+    use std::collections::hash_map::Entry;
+
+    // Walk from the highest precedence down; the first seen wins unless an
+    // earlier declaration is !important and the winner isn't
+    let mut winners: HashMap<String, (usize, usize, bool)> = HashMap::new();
+    for (r, rule) in rules.iter().enumerate().rev() {
+        for (p, property) in rule.properties.iter().enumerate().rev() {
+            let name = if property.name.starts_with("--") {
+                property.name.clone()
+            } else {
+                property.name.to_lowercase()
+            };
+            match winners.entry(name) {
+                Entry::Vacant(entry) => {
+                    entry.insert((r, p, property.important));
+                }
+                Entry::Occupied(mut entry) => {
+                    if property.important && !entry.get().2 {
+                        entry.insert((r, p, true));
+                    }
+                }
+            }
+        }
+    }
+    for (r, rule) in rules.iter_mut().enumerate() {
+        for (p, property) in rule.properties.iter_mut().enumerate() {
+            let name = if property.name.starts_with("--") {
+                property.name.clone()
+            } else {
+                property.name.to_lowercase()
+            };
+            property.overridden = winners
+                .get(&name)
+                .is_some_and(|&(wr, wp, _)| (wr, wp) != (r, p));
+        }
+    }
+}
+
+/// Object group for remote objects fetched while expanding eval results
+const EVAL_OBJECT_GROUP: &str = "domguard-eval";
+
+/// Returns `this` when it is plain JSON data (by value it would lose nothing)
+const PLAIN_COPY_FUNCTION: &str = r"function() {
+    const seen = new Set();
+    const plain = (v) => v === null || typeof v !== 'object'
+        ? !['function', 'symbol', 'bigint'].includes(typeof v)
+        : (Array.isArray(v) || Object.getPrototypeOf(v) === Object.prototype)
+            && !seen.has(v) && seen.add(v) && Object.values(v).every(plain);
+    return plain(this) ? this : undefined;
+}";
+
+/// Properties or elements kept per expanded object
+const MAX_REMOTE_PROPERTIES: usize = 100;
+
+/// How `evaluate_in` runs and returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalOptions {
+    /// Run in an isolated world instead of the page's own
+    pub isolated: bool,
+    /// Allow `await` at the top level (REPL mode)
+    pub top_level_await: bool,
+    /// Levels of non-JSON objects to expand
+    pub depth: u32,
+}
+
+impl Default for EvalOptions {
+    fn default() -> Self {
+        Self {
+            isolated: false,
+            top_level_await: false,
+            depth: 3,
+        }
+    }
+}
+
+/// Convert a remote object to JSON, expanding what `returnByValue` can't carry
+///
+/// Plain objects and arrays are copied by value when they can be; nodes
+/// become their description, maps and sets list their entries, and class
+/// instances keep their own properties under a `$class` marker.
+fn remote_to_json<'a>(
+    page: &'a Page,
+    object: &'a runtime::RemoteObject,
+    depth: u32,
+) -> futures::future::BoxFuture<'a, Result<serde_json::Value>> {
+    use runtime::{RemoteObjectSubtype as Subtype, RemoteObjectType as Type};
+    use serde_json::Value;
+
+    Box::pin(async move {
+        let description = || Value::String(object.description.clone().unwrap_or_default());
+        let unserializable = object
+            .unserializable_value
+            .as_ref()
+            .map(|value| Value::String(value.inner().clone()));
+        match object.r#type {
+            Type::Undefined => return Ok(Value::Null),
+            Type::Function | Type::Symbol => return Ok(description()),
+            Type::Bigint | Type::Number | Type::String | Type::Boolean => {
+                return Ok(unserializable
+                    .or_else(|| object.value.clone())
+                    .unwrap_or(Value::Null))
+            }
+            Type::Object => {}
+        }
+        let Some(object_id) = object.object_id.clone() else {
+            return Ok(object.value.clone().unwrap_or(Value::Null));
+        };
+        let subtype = object.subtype.as_ref();
+        match subtype {
+            Some(Subtype::Null) => return Ok(Value::Null),
+            Some(Subtype::Node) => {
+                return Ok(serde_json::json!({ "$node": object.description.clone() }))
+            }
+            Some(Subtype::Date | Subtype::Regexp | Subtype::Error | Subtype::Promise) => {
+                return Ok(description())
+            }
+            _ => {}
+        }
+
+        let plain = subtype.is_none_or(|subtype| *subtype == Subtype::Array)
+            && object
+                .class_name
+                .as_deref()
+                .is_none_or(|class| class == "Object" || class == "Array");
+        if plain {
+            // One round trip for JSON-like data; anything else comes back undefined
+            let copy = runtime::CallFunctionOnParams::builder()
+                .function_declaration(PLAIN_COPY_FUNCTION)
+                .object_id(object_id.clone())
+                .return_by_value(true)
+                .build()
+                .map_err(|e| anyhow!(e))?;
+            if let Ok(copy) = page.execute(copy).await {
+                if copy.result.exception_details.is_none() {
+                    if let Some(value) = copy.result.result.value.clone() {
+                        return Ok(value);
+                    }
+                }
+            }
+        }
+        if depth == 0 {
+            return Ok(description());
+        }
+
+        let properties = page
+            .execute(
+                runtime::GetPropertiesParams::builder()
+                    .object_id(object_id)
+                    .own_properties(true)
+                    .generate_preview(false)
+                    .build()
+                    .map_err(|e| anyhow!(e))?,
+            )
+            .await
+            .context("Failed to read object properties")?
+            .result;
+
+        if matches!(subtype, Some(Subtype::Map | Subtype::Set)) {
+            let entries = properties
+                .internal_properties
+                .iter()
+                .flatten()
+                .find(|property| property.name == "[[Entries]]")
+                .and_then(|property| property.value.as_ref());
+            let mut items = Vec::new();
+            if let Some(entries) = entries {
+                // Each entry is a { key, value } object (just { value } for sets)
+                let Value::Array(list) = remote_to_json(page, entries, depth).await? else {
+                    return Ok(description());
+                };
+                for entry in list.into_iter().take(MAX_REMOTE_PROPERTIES) {
+                    items.push(match (entry.get("key"), entry.get("value")) {
+                        (Some(key), Some(value)) => Value::Array(vec![key.clone(), value.clone()]),
+                        (None, Some(value)) => value.clone(),
+                        _ => entry,
+                    });
+                }
+            }
+            let (class, key) = if matches!(subtype, Some(Subtype::Map)) {
+                ("Map", "entries")
+            } else {
+                ("Set", "values")
+            };
+            let mut fields = serde_json::Map::new();
+            fields.insert("$class".to_string(), Value::String(class.to_string()));
+            fields.insert(key.to_string(), Value::Array(items));
+            return Ok(Value::Object(fields));
+        }
+
+        let array_like = matches!(subtype, Some(Subtype::Array | Subtype::Typedarray));
+        let mut fields = serde_json::Map::new();
+        let mut elements = Vec::new();
+        for property in properties
+            .result
+            .iter()
+            .filter(|property| property.enumerable)
+            .take(MAX_REMOTE_PROPERTIES)
+        {
+            let Some(value) = &property.value else {
+                // Accessors are skipped; reading them could run page code
+                continue;
+            };
+            let value = remote_to_json(page, value, depth - 1).await?;
+            if !array_like {
+                fields.insert(property.name.clone(), value);
+            } else if property.name.parse::<usize>().is_ok() {
+                elements.push(value);
+            }
+        }
+        if array_like {
+            return Ok(Value::Array(elements));
+        }
+        if let Some(class) = object
+            .class_name
+            .as_deref()
+            .filter(|class| *class != "Object")
+        {
+            fields.insert("$class".to_string(), Value::String(class.to_string()));
+        }
+        Ok(Value::Object(fields))
+    })
+}
+
+/// Stack frames kept per exception report
+const MAX_EXCEPTION_FRAMES: usize = 10;
+
+/// What `watch_exceptions` listens for
+#[derive(Debug, Clone, Default)]
+pub struct ExceptionWatch {
+    /// Stop in the debugger on each exception
+    pub pause: bool,
+    /// Also pause on caught exceptions (with `pause`)
+    pub caught: bool,
+    /// Take a screenshot and DOM snapshot while paused (with `pause`)
+    pub capture: bool,
+    /// `None` listens until interrupted
+    pub duration_ms: Option<u64>,
+}
+
+/// Bytes of each body `watch_xhr` keeps unless told otherwise
+pub const DEFAULT_XHR_BODY_BYTES: usize = 64 * 1024;
+
+/// What `watch_xhr` reports
+#[derive(Debug, Clone)]
+pub struct XhrWatch {
+    /// Only calls whose URL contains this
+    pub url_filter: Option<String>,
+    /// Only calls with this method (case-insensitive)
+    pub method: Option<String>,
+    /// Longer bodies are cut to this many bytes
+    pub max_body_bytes: usize,
+    /// `None` listens until interrupted
+    pub duration_ms: Option<u64>,
+}
+
+impl XhrWatch {
+    pub fn matches(&self, method: &str, url: &str) -> bool {
+        self.url_filter
+            .as_ref()
+            .is_none_or(|filter| url.contains(filter.as_str()))
+            && self
+                .method
+                .as_ref()
+                .is_none_or(|m| m.eq_ignore_ascii_case(method))
+    }
+}
+
+/// A fetch/XHR call, or one Server-Sent Events message, seen by `watch_xhr`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct XhrCall {
+    /// "fetch", "xhr" or "sse"
+    pub kind: String,
+    pub method: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// SSE event name, when not the default "message"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    /// Header names and values, as an object
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub request_headers: serde_json::Value,
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub response_headers: serde_json::Value,
+    /// Parsed JSON when the body is JSON, else the text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_body: Option<serde_json::Value>,
+    /// Full response size
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_bytes: Option<usize>,
+    /// A body was cut to the size limit
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// A body as JSON when it parses, else as text, cut to `max_bytes`
+///
+/// A cut body is never valid JSON, so it is kept as text.
+pub fn body_value(body: &str, max_bytes: usize) -> (serde_json::Value, bool) {
+    if body.len() > max_bytes {
+        let mut end = max_bytes;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        return (serde_json::Value::String(body[..end].to_string()), true);
+    }
+    let value = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .filter(|value| value.is_object() || value.is_array())
+        .unwrap_or_else(|| serde_json::Value::String(body.to_string()));
+    (value, false)
+}
+
+/// A stack frame in the generated script, 1-based
+#[derive(Debug, Clone, Serialize)]
+pub struct StackLocation {
+    pub function: String,
+    pub url: String,
+    #[serde(skip)]
+    pub script_id: String,
+    pub line: u32,
+    pub column: u32,
+    /// Where the source map points
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original: Option<crate::sourcemap::OriginalLocation>,
+}
+
+/// A JavaScript exception reported by `watch_exceptions`
+#[derive(Debug, Clone, Serialize)]
+pub struct ExceptionReport {
+    pub message: String,
+    /// Whether the debugger paused on it
+    pub paused: bool,
+    pub stack: Vec<StackLocation>,
+    /// Page HTML at the moment of the exception
+    #[serde(skip)]
+    pub dom: Option<String>,
+    /// Viewport PNG at the moment of the exception
+    #[serde(skip)]
+    pub screenshot: Option<Vec<u8>>,
+}
+
 /// Console entry captured via CDP
 #[derive(Debug, Clone, Serialize)]
 pub struct ConsoleEntry {
@@ -2265,6 +7167,189 @@ pub struct ConsoleEntry {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_xhr_bodies_and_filters() {
+        let (body, truncated) = body_value(r#"{"id": 7, "name": "Ada"}"#, 1024);
+        assert_eq!(body["id"], 7);
+        assert!(!truncated);
+        // Only objects and arrays count as JSON; a bare number stays text
+        assert_eq!(body_value("42", 1024).0, serde_json::json!("42"));
+        let (cut, truncated) = body_value("{\"name\": \"Zoë\"}", 13);
+        assert_eq!(cut, serde_json::json!("{\"name\": \"Zo"));
+        assert!(truncated);
+
+        let watch = XhrWatch {
+            url_filter: Some("/api/".to_string()),
+            method: Some("post".to_string()),
+            max_body_bytes: DEFAULT_XHR_BODY_BYTES,
+            duration_ms: None,
+        };
+        assert!(watch.matches("POST", "https://shop.test/api/cart"));
+        assert!(!watch.matches("GET", "https://shop.test/api/cart"));
+        assert!(!watch.matches("POST", "https://shop.test/cart"));
+    }
+
+    #[test]
+    fn test_deadline_bounds_waits() {
+        let mut cdp = CdpConnection::new(Config::default());
+        assert_eq!(cdp.bounded(30_000), 30_000);
+        cdp.set_deadline(Some(std::time::Instant::now() + Duration::from_secs(1)));
+        assert!(cdp.bounded(30_000) <= 1000);
+        assert_eq!(cdp.bounded(10), 10);
+        cdp.set_deadline(Some(std::time::Instant::now()));
+        assert_eq!(cdp.bounded(5000), 0);
+
+        let error = anyhow::Error::from(CommandTimeout { timeout_ms: 2000 });
+        assert_eq!(
+            crate::correction::describe_error(&error, "click").code,
+            crate::output::ErrorCode::Timeout
+        );
+    }
+
+    #[test]
+    fn test_selector_specificity() {
+        assert_eq!(selector_specificity("div"), (0, 0, 0, 1));
+        assert_eq!(selector_specificity("#main .card > a:hover"), (0, 1, 2, 1));
+        assert_eq!(selector_specificity("a[href^='http']::after"), (0, 0, 1, 2));
+        assert_eq!(selector_specificity("li:not(.done, #x)"), (0, 1, 0, 1));
+        assert_eq!(selector_specificity(":where(#a, .b) p"), (0, 0, 0, 1));
+        assert_eq!(selector_specificity("*"), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_mark_overridden() {
+        let rule = |selector: &str, properties: &[(&str, bool)]| MatchedRule {
+            selector: selector.to_string(),
+            origin: "author".to_string(),
+            source: None,
+            line: None,
+            specificity: selector_specificity(selector),
+            media: Vec::new(),
+            properties: properties
+                .iter()
+                .map(|&(name, important)| MatchedProperty {
+                    name: name.to_string(),
+                    value: String::new(),
+                    important,
+                    overridden: false,
+                })
+                .collect(),
+        };
+        let mut rules = vec![
+            rule("p", &[("color", true), ("margin", false)]),
+            rule(".note", &[("color", false), ("Margin", false)]),
+            rule("#intro", &[("padding", false)]),
+        ];
+        mark_overridden(&mut rules);
+
+        let overridden = |r: usize, p: usize| rules[r].properties[p].overridden;
+        // !important beats the later plain declaration
+        assert!(!overridden(0, 0) && overridden(1, 0));
+        assert!(overridden(0, 1) && !overridden(1, 1));
+        assert!(!overridden(2, 0));
+    }
+
+    #[test]
+    fn test_element_condition_script() {
+        let script =
+            ElementCondition::Attribute("aria-busy".into(), "false".into()).script("#save");
+        assert!(script.contains(r#"document.querySelector("#save")"#));
+        assert!(script.contains(r#"el.getAttribute("aria-busy") === "false""#));
+        assert!(ElementCondition::Enabled
+            .script("button")
+            .contains(":disabled"));
+        assert_eq!(
+            ElementCondition::Stable(300).to_string(),
+            "stable for 300ms"
+        );
+    }
+
+    #[test]
+    fn test_readiness_score() {
+        let mut readiness: PageReadiness = serde_json::from_value(serde_json::json!({
+            "ready_state": "complete",
+            "pending_requests": 0,
+            "framework": "next",
+            "hydrated": true,
+            "route_changes": 1,
+            "ms_since_route_change": 2000,
+            "tracking_ms": 5000
+        }))
+        .unwrap();
+        assert_eq!(readiness.score(), 100);
+
+        readiness.pending_requests = 2;
+        readiness.ms_since_route_change = Some(100);
+        assert_eq!(readiness.score(), 60);
+        readiness.ready_state = "interactive".to_string();
+        readiness.hydrated = Some(false);
+        assert_eq!(readiness.score(), 20);
+    }
+
+    #[test]
+    fn test_page_condition() {
+        let url = "https://shop.test/orders/42?ref=mail";
+        assert!(PageCondition::UrlContains("/orders/".into()).matches(url, ""));
+        let pattern = PageCondition::UrlMatches(regex::Regex::new(r"/orders/\d+").unwrap());
+        assert!(pattern.matches(url, ""));
+        assert!(!pattern.matches("https://shop.test/orders/new", ""));
+        assert!(PageCondition::TitleContains("Order".into()).matches(url, "Order #42"));
+        assert_eq!(pattern.to_string(), r"URL matching //orders/\d+/");
+    }
+
+    #[test]
+    fn test_choose_tab() {
+        let tab = |id: &str, url: &str| TabInfo {
+            id: id.to_string(),
+            url: url.to_string(),
+            title: String::new(),
+            active: false,
+        };
+        let tabs = [
+            tab("a", "about:blank"),
+            tab("b", "https://a.test"),
+            tab("c", "https://b.test"),
+        ];
+        assert_eq!(choose_tab(&tabs, Some("c")), Some(2));
+        assert_eq!(choose_tab(&tabs, Some("closed")), Some(1));
+        assert_eq!(choose_tab(&tabs[..1], None), Some(0));
+        assert_eq!(choose_tab(&[], Some("a")), None);
+    }
+
+    #[test]
+    fn test_pdf_sizes() {
+        assert_eq!(
+            "A4".parse::<PaperSize>(),
+            Ok(PaperSize {
+                width: 8.27,
+                height: 11.69
+            })
+        );
+        let custom: PaperSize = "254x127mm".parse().unwrap();
+        assert!((custom.width - 10.0).abs() < 1e-9 && (custom.height - 5.0).abs() < 1e-9);
+        let mixed: PaperSize = "8inx960px".parse().unwrap();
+        assert_eq!((mixed.width, mixed.height), (8.0, 10.0));
+        assert!("b5".parse::<PaperSize>().is_err());
+        assert!("10x5pt".parse::<PaperSize>().is_err());
+
+        let margins: Margins = "1cm,0.5".parse().unwrap();
+        assert!((margins.top - 1.0 / 2.54).abs() < 1e-9);
+        assert_eq!((margins.right, margins.left), (0.5, 0.5));
+        assert!("1,2,3".parse::<Margins>().is_err());
+
+        let options = PdfOptions {
+            scale: Some(3.0),
+            ..PdfOptions::default()
+        };
+        assert!(options.validate().is_err());
+        let options = PdfOptions {
+            page_ranges: Some("1-3; drop".to_string()),
+            ..PdfOptions::default()
+        };
+        assert!(options.validate().is_err());
+        assert!(PdfOptions::default().params().is_ok());
+    }
+
     #[test]
     fn test_security_validation_localhost() {
         let config = Config::default();
@@ -2279,4 +7364,53 @@ mod tests {
         let conn = CdpConnection::new(config);
         assert!(conn.validate_security().is_err());
     }
+
+    #[test]
+    fn test_security_validation_allow_remote() {
+        let mut config = Config::default();
+        config.chrome.host = "192.168.1.100".to_string();
+        config.chrome.allow_remote = true;
+        let conn = CdpConnection::new(config);
+        assert!(conn.validate_security().is_ok());
+    }
+
+    #[test]
+    fn test_wait_until_parse() {
+        assert_eq!("load".parse::<WaitUntil>().unwrap(), WaitUntil::Load);
+        assert_eq!(
+            "DOMContentLoaded".parse::<WaitUntil>().unwrap(),
+            WaitUntil::DomContentLoaded
+        );
+        assert_eq!(
+            "networkidle".parse::<WaitUntil>().unwrap(),
+            WaitUntil::NetworkIdle
+        );
+        assert!("idle".parse::<WaitUntil>().is_err());
+        assert_eq!(WaitUntil::NetworkIdle.event_name(), "networkIdle");
+    }
+
+    #[test]
+    fn test_wss_has_a_tls_backend() {
+        use async_tungstenite::tungstenite::{error::UrlError, Error};
+
+        // The server hangs up, so the handshake fails, but not for lack of TLS
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let _ = listener.accept();
+        });
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let error = runtime
+            .block_on(async_tungstenite::tokio::connect_async(format!(
+                "wss://127.0.0.1:{}/",
+                port
+            )))
+            .err()
+            .unwrap();
+        assert!(
+            !matches!(error, Error::Url(UrlError::TlsFeatureNotEnabled)),
+            "{}",
+            error
+        );
+    }
 }
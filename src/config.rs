@@ -2,8 +2,9 @@
 //!
 //! Loads configuration from .domguard/config.toml (project-local, like TaskGuard)
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 /// The embedded AI guide content
@@ -18,6 +19,20 @@ pub struct ChromeConfig {
     /// Chrome host (default: 127.0.0.1)
     #[serde(default = "default_host")]
     pub host: String,
+    /// Allow connecting to a non-localhost host (default: false)
+    #[serde(default)]
+    pub allow_remote: bool,
+    /// Connect over TLS (https/wss), e.g. to a TLS-terminating proxy in front of
+    /// Chrome or a hosted browser; certificates are checked against the
+    /// webpki roots (default: false)
+    #[serde(default)]
+    pub tls: bool,
+    /// Access token sent as `?token=` (browserless-style endpoints); supports `${env:VAR}`
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Browser backend: "chrome" (default) or "firefox" (experimental, WebDriver BiDi)
+    #[serde(default)]
+    pub browser: crate::backend::BackendKind,
 }
 
 impl Default for ChromeConfig {
@@ -25,6 +40,10 @@ impl Default for ChromeConfig {
         Self {
             port: default_port(),
             host: default_host(),
+            allow_remote: false,
+            tls: false,
+            token: None,
+            browser: crate::backend::BackendKind::default(),
         }
     }
 }
@@ -49,6 +68,15 @@ pub struct DefaultsConfig {
     /// Automatically cleanup screenshots on session stop (default: false)
     #[serde(default)]
     pub auto_cleanup_screenshots: bool,
+    /// Make tabs opened by an interact action the active tab (default: false)
+    #[serde(default)]
+    pub follow_popups: bool,
+    /// Turn off CPU/network throttling on session stop (default: false)
+    #[serde(default)]
+    pub reset_throttle_on_stop: bool,
+    /// Accept cookie-consent banners after navigate, back, forward and refresh (default: false)
+    #[serde(default)]
+    pub auto_dismiss_consent: bool,
 }
 
 impl Default for DefaultsConfig {
@@ -57,6 +85,9 @@ impl Default for DefaultsConfig {
             timeout_ms: default_timeout(),
             screenshot_format: default_screenshot_format(),
             auto_cleanup_screenshots: false,
+            follow_popups: false,
+            reset_throttle_on_stop: false,
+            auto_dismiss_consent: false,
         }
     }
 }
@@ -75,12 +106,16 @@ pub struct InspireConfig {
     /// Directory to save inspirations (relative to .domguard/)
     #[serde(default = "default_save_dir")]
     pub save_dir: PathBuf,
+    /// Viewport widths for `inspire --responsive` (default: 375, 768, 1280)
+    #[serde(default = "default_responsive_widths")]
+    pub responsive_widths: Vec<u32>,
 }
 
 impl Default for InspireConfig {
     fn default() -> Self {
         Self {
             save_dir: default_save_dir(),
+            responsive_widths: default_responsive_widths(),
         }
     }
 }
@@ -89,6 +124,47 @@ fn default_save_dir() -> PathBuf {
     PathBuf::from("inspirations")
 }
 
+fn default_responsive_widths() -> Vec<u32> {
+    vec![375, 768, 1280]
+}
+
+/// Session recording configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionConfig {
+    /// Start a session on the first interact command when none is active
+    /// (default: false). The session stays open until `session stop`.
+    #[serde(default)]
+    pub auto_record: bool,
+}
+
+/// Takeover notification configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TakeoverConfig {
+    /// Show a desktop notification when a takeover is requested (default: false)
+    #[serde(default)]
+    pub notify_desktop: bool,
+    /// Webhook to POST takeover requests to (Slack-compatible payload)
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Request a takeover when a CAPTCHA blocks an interact command (default: false)
+    #[serde(default)]
+    pub takeover_on_captcha: bool,
+}
+
+/// Sensitive action and blocked site handling
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecurityConfig {
+    /// What to do when an interact command looks sensitive (default: warn)
+    #[serde(default)]
+    pub on_sensitive: crate::security::SecurityEnforcement,
+    /// Let `--override-block` open sites on the blocked list (default: false)
+    #[serde(default)]
+    pub allow_block_override: bool,
+    /// Site patterns blocked on top of `blocked_sites.toml` (handy per profile)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_sites: Vec<String>,
+}
+
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
@@ -98,6 +174,25 @@ pub struct Config {
     pub defaults: DefaultsConfig,
     #[serde(default)]
     pub inspire: InspireConfig,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub correction: crate::correction::CorrectionConfig,
+    #[serde(default)]
+    pub takeover: TakeoverConfig,
+    #[serde(default)]
+    pub security: SecurityConfig,
+    #[serde(default)]
+    pub masking: crate::output::MaskingConfig,
+    #[serde(default)]
+    pub budget: crate::budget::BudgetConfig,
+    #[serde(default)]
+    pub consent: crate::consent::ConsentConfig,
+    #[serde(default)]
+    pub storage: crate::storage::StorageConfig,
+    /// Named overrides (`[profiles.staging]`, ...) selected with `--profile`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, toml::Table>,
 }
 
 impl Config {
@@ -152,6 +247,129 @@ impl Config {
         }
     }
 
+    /// Load configuration, with `[profiles.<name>]` merged over it if a profile is given
+    pub fn load_profile(profile: Option<&str>) -> Result<Self> {
+        let config = Self::load()?;
+        match profile {
+            Some(name) => config.with_profile(name),
+            None => Ok(config),
+        }
+    }
+
+    /// This configuration with a profile's settings merged over it
+    ///
+    /// Tables merge key by key, anything else (including lists) is replaced.
+    pub fn with_profile(&self, name: &str) -> Result<Self> {
+        let overrides = self.profiles.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            anyhow!(
+                "Unknown profile \"{}\" (defined: {})",
+                name,
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            )
+        })?;
+
+        let mut merged =
+            toml::Table::try_from(self).with_context(|| "Failed to serialize config")?;
+        merge_tables(&mut merged, overrides);
+        merged
+            .try_into()
+            .with_context(|| format!("Invalid [profiles.{}] in config file", name))
+    }
+
+    /// Value of a dotted key such as `defaults.timeout_ms`, if it is set
+    pub fn get_key(&self, key: &str) -> Result<Option<toml::Value>> {
+        let table = toml::Table::try_from(self).with_context(|| "Failed to serialize config")?;
+        let mut value = None;
+        let mut current = Some(&table);
+        for part in key.split('.') {
+            value = current.and_then(|table| table.get(part));
+            current = value.and_then(toml::Value::as_table);
+        }
+        Ok(value.cloned())
+    }
+
+    /// Set a dotted key from its command-line text, returning the stored value
+    ///
+    /// The text is coerced to the key's current type (`10000` to an integer,
+    /// `yes` to a boolean, `a,b` to a list), and the result must still be a
+    /// valid config: unknown keys and values of the wrong type are errors.
+    pub fn set_key(&mut self, key: &str, raw: &str) -> Result<toml::Value> {
+        let current = self.get_key(key)?;
+        let value = coerce_value(key, raw, current.as_ref())?;
+        let updated = match self.with_key(key, value) {
+            // Unset keys have no type to go by, and a list is the other likely reading
+            Err(e) if current.is_none() => self.with_key(key, comma_list(raw)).map_err(|_| e)?,
+            result => result?,
+        };
+
+        let stored = updated
+            .get_key(key)?
+            .unwrap_or(toml::Value::String(raw.to_string()));
+        *self = updated;
+        Ok(stored)
+    }
+
+    /// A copy of this configuration with `key` set to `value`, if that's still a valid config
+    fn with_key(&self, key: &str, value: toml::Value) -> Result<Self> {
+        let mut table =
+            toml::Table::try_from(self).with_context(|| "Failed to serialize config")?;
+        let (parents, leaf) = match key.rsplit_once('.') {
+            Some((parents, leaf)) => (parents.split('.').collect(), leaf),
+            None => (Vec::new(), key),
+        };
+
+        let mut current = &mut table;
+        for part in parents {
+            if !current.contains_key(part) {
+                current.insert(part.to_string(), toml::Value::Table(toml::Table::new()));
+            }
+            current = match current.get_mut(part) {
+                Some(toml::Value::Table(table)) => table,
+                _ => return Err(anyhow!("{} is not a table in {}", part, key)),
+            };
+        }
+        current.insert(leaf.to_string(), value.clone());
+
+        let updated: Config = table
+            .try_into()
+            .with_context(|| format!("Invalid value for {}", key))?;
+        // Keys the schema doesn't know are dropped on the way through
+        if updated.get_key(key)?.as_ref() != Some(&value) {
+            return Err(anyhow!("Unknown config key \"{}\"", key));
+        }
+        for name in updated.profiles.keys() {
+            updated.with_profile(name)?;
+        }
+        Ok(updated)
+    }
+
+    /// Every set key as a dotted path, in file order
+    pub fn list_keys(&self) -> Result<Vec<(String, toml::Value)>> {
+        fn flatten(prefix: &str, table: &toml::Table, out: &mut Vec<(String, toml::Value)>) {
+            for (key, value) in table {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                match value {
+                    toml::Value::Table(table) => flatten(&path, table, out),
+                    _ => out.push((path, value.clone())),
+                }
+            }
+        }
+
+        let table = toml::Table::try_from(self).with_context(|| "Failed to serialize config")?;
+        let mut keys = Vec::new();
+        flatten("", &table, &mut keys);
+        Ok(keys)
+    }
+
     /// Save configuration to .domguard/config.toml
     pub fn save(&self) -> Result<()> {
         let config_path = Self::config_path();
@@ -171,7 +389,22 @@ impl Config {
 
     /// Get the WebSocket URL for Chrome DevTools
     pub fn ws_url(&self) -> String {
-        format!("http://{}:{}", self.chrome.host, self.chrome.port)
+        // Token-protected endpoints take the WebSocket connection directly
+        match &self.chrome.token {
+            Some(token) => format!(
+                "{}://{}:{}/?token={}",
+                if self.chrome.tls { "wss" } else { "ws" },
+                self.chrome.host,
+                self.chrome.port,
+                token
+            ),
+            None => format!(
+                "{}://{}:{}",
+                if self.chrome.tls { "https" } else { "http" },
+                self.chrome.host,
+                self.chrome.port
+            ),
+        }
     }
 
     /// Check if host is localhost (security check)
@@ -185,6 +418,70 @@ impl Config {
     }
 }
 
+/// Parse command-line text as a value of the same type as `current`
+fn coerce_value(key: &str, raw: &str, current: Option<&toml::Value>) -> Result<toml::Value> {
+    use toml::Value;
+
+    // A TOML literal: 10, true, "x", ["a", "b"]
+    let literal = || {
+        toml::from_str::<toml::Table>(&format!("value = {}", raw))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+    };
+
+    Ok(match current {
+        Some(Value::String(_)) => Value::String(raw.to_string()),
+        Some(Value::Integer(_)) => Value::Integer(
+            raw.trim()
+                .parse()
+                .with_context(|| format!("{} expects an integer, got \"{}\"", key, raw))?,
+        ),
+        Some(Value::Float(_)) => Value::Float(
+            raw.trim()
+                .parse()
+                .with_context(|| format!("{} expects a number, got \"{}\"", key, raw))?,
+        ),
+        Some(Value::Boolean(_)) => Value::Boolean(match raw.trim().to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => true,
+            "false" | "no" | "off" | "0" => false,
+            _ => return Err(anyhow!("{} expects true or false, got \"{}\"", key, raw)),
+        }),
+        Some(Value::Array(_)) => literal()
+            .filter(Value::is_array)
+            .unwrap_or_else(|| comma_list(raw)),
+        Some(Value::Table(_)) => {
+            return Err(anyhow!("{} is a table; set one of its keys instead", key))
+        }
+        // Unset optional keys take whatever the text looks like
+        _ => literal().unwrap_or_else(|| Value::String(raw.to_string())),
+    })
+}
+
+/// `a, b` as a list of strings
+fn comma_list(raw: &str) -> toml::Value {
+    toml::Value::Array(
+        raw.split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| toml::Value::String(item.to_string()))
+            .collect(),
+    )
+}
+
+/// Merge `overlay` into `base`, recursing into tables
+fn merge_tables(base: &mut toml::Table, overlay: &toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table);
+            }
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
 /// Initialize DOMGuard in the current directory
 pub fn init_domguard() -> Result<InitResult> {
     let domguard_dir = Config::domguard_dir();
@@ -243,6 +540,8 @@ mod tests {
         assert_eq!(config.chrome.host, "127.0.0.1");
         assert_eq!(config.defaults.timeout_ms, 5000);
         assert_eq!(config.defaults.screenshot_format, "png");
+        assert!(!config.defaults.follow_popups);
+        assert!(!config.defaults.auto_dismiss_consent);
     }
 
     #[test]
@@ -251,6 +550,21 @@ mod tests {
         assert_eq!(config.ws_url(), "http://127.0.0.1:9222");
     }
 
+    #[test]
+    fn test_ws_url_tls_and_token() {
+        let mut config = Config::default();
+        config.chrome.host = "chrome.example.com".to_string();
+        config.chrome.port = 443;
+        config.chrome.tls = true;
+        assert_eq!(config.ws_url(), "https://chrome.example.com:443");
+
+        config.chrome.token = Some("abc123".to_string());
+        assert_eq!(
+            config.ws_url(),
+            "wss://chrome.example.com:443/?token=abc123"
+        );
+    }
+
     #[test]
     fn test_is_localhost() {
         let mut config = Config::default();
@@ -285,5 +599,129 @@ save_dir = "my-inspirations"
         assert_eq!(config.defaults.timeout_ms, 10000);
         assert_eq!(config.defaults.screenshot_format, "jpeg");
         assert_eq!(config.inspire.save_dir, PathBuf::from("my-inspirations"));
+        assert!(!config.session.auto_record);
+    }
+
+    #[test]
+    fn test_profiles() {
+        let toml_str = r#"
+[chrome]
+port = 9333
+
+[defaults]
+timeout_ms = 10000
+
+[profiles.staging.chrome]
+host = "staging-chrome.internal"
+allow_remote = true
+
+[profiles.prod.security]
+on_sensitive = "block"
+blocked_sites = ["admin.example.com"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.profiles.len(), 2);
+
+        let staging = config.with_profile("staging").unwrap();
+        assert_eq!(staging.chrome.host, "staging-chrome.internal");
+        assert!(staging.chrome.allow_remote);
+        // Untouched keys come from the base config
+        assert_eq!(staging.chrome.port, 9333);
+        assert_eq!(staging.defaults.timeout_ms, 10000);
+
+        let prod = config.with_profile("prod").unwrap();
+        assert_eq!(prod.security.blocked_sites, vec!["admin.example.com"]);
+        assert_eq!(prod.chrome.host, "127.0.0.1");
+
+        let err = config.with_profile("dev").unwrap_err().to_string();
+        assert!(err.contains("prod, staging"));
+    }
+
+    #[test]
+    fn test_get_and_set_keys() {
+        let mut config = Config::default();
+        assert_eq!(
+            config.get_key("chrome.port").unwrap(),
+            Some(toml::Value::Integer(9222))
+        );
+        assert_eq!(config.get_key("chrome.token").unwrap(), None);
+
+        config.set_key("defaults.timeout_ms", "10000").unwrap();
+        assert_eq!(config.defaults.timeout_ms, 10000);
+        config.set_key("session.auto_record", "yes").unwrap();
+        assert!(config.session.auto_record);
+        config
+            .set_key("security.blocked_sites", "a.example.com, b.example.com")
+            .unwrap();
+        assert_eq!(config.security.blocked_sites.len(), 2);
+        config.set_key("security.on_sensitive", "block").unwrap();
+        config.set_key("chrome.token", "s3cret").unwrap();
+        assert_eq!(config.chrome.token.as_deref(), Some("s3cret"));
+
+        assert!(config.set_key("defaults.timeout_ms", "soon").is_err());
+        assert!(config.set_key("security.on_sensitive", "maybe").is_err());
+        assert!(config.set_key("defaults.timeout", "1").is_err());
+        assert!(config.set_key("chrome", "1").is_err());
+        // Failed sets leave the config alone
+        assert_eq!(config.defaults.timeout_ms, 10000);
+
+        let keys = config.list_keys().unwrap();
+        assert!(keys
+            .iter()
+            .any(|(key, value)| key == "chrome.host" && value.as_str() == Some("127.0.0.1")));
+    }
+
+    #[test]
+    fn test_parse_session_config() {
+        let config: Config = toml::from_str("[session]\nauto_record = true\n").unwrap();
+        assert!(config.session.auto_record);
+        assert_eq!(config.chrome.port, 9222);
+    }
+
+    #[test]
+    fn test_parse_correction_config() {
+        let config: Config =
+            toml::from_str("[correction]\nenabled = false\nmax_retries = 1\n").unwrap();
+        assert!(!config.correction.enabled);
+        assert_eq!(config.correction.max_retries, 1);
+        assert_eq!(config.correction.base_delay_ms, 500);
+    }
+
+    #[test]
+    fn test_parse_takeover_config() {
+        let config: Config = toml::from_str(
+            "[takeover]\nnotify_desktop = true\nwebhook_url = \"https://hooks.example.com/x\"\n",
+        )
+        .unwrap();
+        assert!(config.takeover.notify_desktop);
+        assert_eq!(
+            config.takeover.webhook_url.as_deref(),
+            Some("https://hooks.example.com/x")
+        );
+        assert!(!config.takeover.takeover_on_captcha);
+        assert!(Config::default().takeover.webhook_url.is_none());
+    }
+
+    #[test]
+    fn test_parse_security_config() {
+        use crate::security::SecurityEnforcement;
+
+        let config: Config =
+            toml::from_str("[security]\nallow_remote = false\non_sensitive = \"confirm\"\n")
+                .unwrap();
+        assert_eq!(config.security.on_sensitive, SecurityEnforcement::Confirm);
+        assert!(!config.security.allow_block_override);
+        assert_eq!(
+            Config::default().security.on_sensitive,
+            SecurityEnforcement::Warn
+        );
+    }
+
+    #[test]
+    fn test_parse_masking_config() {
+        let config: Config = toml::from_str("[masking]\npatterns = [\"EMP-\\\\d{6}\"]\n").unwrap();
+        assert!(config.masking.enabled);
+        assert_eq!(config.masking.patterns, vec![r"EMP-\d{6}".to_string()]);
+        assert_eq!(Config::default().masking.patterns.len(), 2);
     }
 }
@@ -0,0 +1,183 @@
+//! Cookie-consent banners for `interact dismiss-consent`
+//!
+//! Built-in rules know the accept buttons of common consent managers
+//! (OneTrust, Cookiebot, Didomi, ...). `[[consent.rules]]` in the config adds
+//! site-specific ones, tried first. When no rule matches, a generic pass looks
+//! for an "Accept all"-style button inside something that talks about cookies.
+//! Managers that render into cross-origin iframes can't be reached.
+
+use serde::{Deserialize, Serialize};
+
+/// Accept buttons of one consent manager
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsentRule {
+    pub name: String,
+    /// CSS selectors of the accept button, in order of preference
+    pub accept: Vec<String>,
+    /// Element whose shadow root holds the banner (Usercentrics)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow_host: Option<String>,
+}
+
+/// Consent handling configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsentConfig {
+    /// Rules tried before the built-in ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ConsentRule>,
+}
+
+impl ConsentConfig {
+    /// Configured rules followed by the built-in ones
+    pub fn all_rules(&self) -> Vec<ConsentRule> {
+        self.rules.iter().cloned().chain(builtin_rules()).collect()
+    }
+}
+
+/// What was clicked to accept the banner
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsentDismissed {
+    /// Rule name, or "generic" for the text match
+    pub rule: String,
+    /// Selector or button text that matched
+    pub target: String,
+}
+
+/// Button texts the generic pass accepts, most specific first
+pub const GENERIC_ACCEPT_TEXTS: &[&str] = &[
+    "accept all cookies",
+    "accept all",
+    "allow all cookies",
+    "allow all",
+    "accept cookies",
+    "alle akzeptieren",
+    "tout accepter",
+    "aceptar todo",
+    "accetta tutto",
+    "accept",
+    "i agree",
+    "agree",
+    "i accept",
+    "got it",
+    "ok",
+];
+
+/// Rules for common consent managers
+pub fn builtin_rules() -> Vec<ConsentRule> {
+    let rule = |name: &str, accept: &[&str]| ConsentRule {
+        name: name.to_string(),
+        accept: accept.iter().map(|s| (*s).to_string()).collect(),
+        shadow_host: None,
+    };
+    vec![
+        rule(
+            "onetrust",
+            &[
+                "#onetrust-accept-btn-handler",
+                "#accept-recommended-btn-handler",
+            ],
+        ),
+        rule(
+            "cookiebot",
+            &[
+                "#CybotCookiebotDialogBodyLevelButtonLevelOptinAllowAll",
+                "#CybotCookiebotDialogBodyButtonAccept",
+            ],
+        ),
+        rule("didomi", &["#didomi-notice-agree-button"]),
+        rule(
+            "quantcast",
+            &[".qc-cmp2-summary-buttons button[mode=\"primary\"]"],
+        ),
+        rule("trustarc", &["#truste-consent-button"]),
+        ConsentRule {
+            name: "usercentrics".to_string(),
+            accept: vec!["[data-testid=\"uc-accept-all-button\"]".to_string()],
+            shadow_host: Some("#usercentrics-root".to_string()),
+        },
+        rule("cookieyes", &[".cky-btn-accept"]),
+        rule("osano", &[".osano-cm-accept-all"]),
+        rule("complianz", &[".cmplz-btn.cmplz-accept"]),
+        rule("klaro", &[".cm-btn-accept-all", ".cm-btn-success"]),
+        rule("iubenda", &[".iubenda-cs-accept-btn"]),
+    ]
+}
+
+/// Script that clicks the first visible accept button and returns a
+/// `ConsentDismissed`, or null when no banner is showing
+pub fn dismiss_script(rules: &[ConsentRule]) -> String {
+    format!(
+        r#"(function(rules, texts) {{
+            const visible = el => el.getClientRects().length > 0
+                && getComputedStyle(el).visibility !== 'hidden' && !el.disabled;
+            for (const rule of rules) {{
+                let root = document;
+                if (rule.shadow_host) {{
+                    const host = document.querySelector(rule.shadow_host);
+                    root = host && host.shadowRoot;
+                    if (!root) continue;
+                }}
+                for (const selector of rule.accept) {{
+                    let el = null;
+                    try {{ el = root.querySelector(selector); }} catch (e) {{ continue; }}
+                    if (el && visible(el)) {{
+                        el.click();
+                        return {{ rule: rule.name, target: selector }};
+                    }}
+                }}
+            }}
+
+            const containers = [...document.querySelectorAll(
+                '[id*="cookie" i], [class*="cookie" i], [id*="consent" i], [class*="consent" i], '
+                + '[id*="gdpr" i], [class*="gdpr" i], [aria-label*="cookie" i], [aria-label*="consent" i], '
+                + '[role="dialog"], [role="alertdialog"], [aria-modal="true"]'
+            )].filter(el => visible(el)
+                && /cookie|consent|gdpr|privacy|datenschutz/i.test(el.innerText || ''));
+            const normalize = text => (text || '').trim().replace(/\s+/g, ' ').toLowerCase();
+            for (const text of texts) {{
+                for (const container of containers) {{
+                    const button = [...container.querySelectorAll(
+                        'button, a, [role="button"], input[type="button"], input[type="submit"]'
+                    )].find(b => visible(b) && normalize(b.innerText || b.value) === text);
+                    if (button) {{
+                        button.click();
+                        return {{ rule: 'generic', target: text }};
+                    }}
+                }}
+            }}
+            return null;
+        }})({}, {})"#,
+        serde_json::json!(rules),
+        serde_json::json!(GENERIC_ACCEPT_TEXTS)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_rules_come_first() {
+        let config: ConsentConfig = toml::from_str(
+            r##"
+[[rules]]
+name = "shop"
+accept = ["#shop-cookies .yes"]
+"##,
+        )
+        .unwrap();
+        let rules = config.all_rules();
+        assert_eq!(rules[0].name, "shop");
+        assert_eq!(rules.len(), builtin_rules().len() + 1);
+        assert!(rules.iter().any(|rule| rule.name == "onetrust"));
+        assert_eq!(ConsentConfig::default().all_rules()[0].name, "onetrust");
+    }
+
+    #[test]
+    fn test_dismiss_script() {
+        let script = dismiss_script(&builtin_rules());
+        assert!(script.contains(r##""#didomi-notice-agree-button""##));
+        assert!(script.contains(r##""shadow_host":"#usercentrics-root""##));
+        assert!(script.contains(r#""accept all cookies""#));
+    }
+}
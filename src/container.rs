@@ -0,0 +1,204 @@
+//! Containerized Chrome for DOMGuard
+//!
+//! `domguard chrome up` runs Chrome in Docker (or Podman) with the debugging
+//! port published on localhost, for machines without a local Chrome such as
+//! CI runners. `domguard chrome down` removes the container again.
+
+use anyhow::{anyhow, Context, Result};
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::config::Config;
+
+/// Default image: Chromium that takes Chrome flags as its arguments
+pub const DEFAULT_IMAGE: &str = "zenika/alpine-chrome";
+
+/// Default container name
+pub const DEFAULT_NAME: &str = "domguard-chrome";
+
+/// Debugging port inside the container
+const CONTAINER_PORT: u16 = 9222;
+
+/// Find a container runtime (docker, then podman)
+pub fn find_runtime() -> Result<PathBuf> {
+    ["docker", "podman"]
+        .iter()
+        .find_map(|name| which::which(name).ok())
+        .ok_or_else(|| anyhow!("Neither docker nor podman found in PATH"))
+}
+
+/// Arguments for `docker run` starting Chrome with its debugging port on `127.0.0.1:port`
+pub fn run_args(name: &str, image: &str, port: u16, headless: bool) -> Vec<String> {
+    let mut args = vec![
+        "run".to_string(),
+        "-d".to_string(),
+        "--rm".to_string(),
+        "--name".to_string(),
+        name.to_string(),
+        // Published on loopback only, like a local Chrome
+        "-p".to_string(),
+        format!("127.0.0.1:{}:{}", port, CONTAINER_PORT),
+        // Chrome needs more shared memory than the container default
+        "--shm-size=1g".to_string(),
+        image.to_string(),
+        "--no-sandbox".to_string(),
+        "--no-first-run".to_string(),
+        "--remote-debugging-address=0.0.0.0".to_string(),
+        format!("--remote-debugging-port={}", CONTAINER_PORT),
+    ];
+    if headless {
+        args.push("--headless=new".to_string());
+    }
+    args.push("about:blank".to_string());
+    args
+}
+
+/// Start the container, returning its ID
+pub fn start(name: &str, image: &str, port: u16, headless: bool) -> Result<String> {
+    let output = Command::new(find_runtime()?)
+        .args(run_args(name, image, port, headless))
+        .output()
+        .context("Failed to run container runtime")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to start {}: {}",
+            image,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Stop and remove the container, returning whether it existed
+pub fn stop(name: &str) -> Result<bool> {
+    let output = Command::new(find_runtime()?)
+        .args(["rm", "-f", name])
+        .output()
+        .context("Failed to run container runtime")?;
+    if output.status.success() {
+        return Ok(true);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("No such container") || stderr.contains("no such container") {
+        return Ok(false);
+    }
+    Err(anyhow!("Failed to stop {}: {}", name, stderr.trim()))
+}
+
+/// Chrome answering in its container
+#[derive(Debug, Clone)]
+pub struct Running {
+    /// Container ID
+    pub id: String,
+    pub host: String,
+    pub port: u16,
+    /// e.g. "HeadlessChrome/120.0.6099.109"
+    pub browser: String,
+}
+
+/// Start Chrome in a container on `127.0.0.1:port`, wait up to `wait_secs`
+/// for it to answer, and point config.toml at it
+///
+/// A container that doesn't become ready is removed again, and the error
+/// carries the end of its log.
+pub async fn up(
+    config: &Config,
+    name: &str,
+    image: &str,
+    port: u16,
+    headless: bool,
+    wait_secs: u64,
+) -> Result<Running> {
+    let id = start(name, image, port, headless)?;
+
+    // The published port accepts connections before Chrome answers on it
+    let mut probe = config.clone();
+    probe.chrome.host = "127.0.0.1".to_string();
+    probe.chrome.port = port;
+    probe.chrome.tls = false;
+    probe.chrome.token = None;
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(wait_secs);
+    let version = loop {
+        let left = deadline.saturating_duration_since(std::time::Instant::now());
+        let fetched =
+            crate::discovery::off_runtime(&probe, left, crate::discovery::fetch_version).await;
+        match fetched {
+            Ok(version) => break version,
+            Err(_) if std::time::Instant::now() < deadline => {
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            }
+            Err(e) => {
+                let logs = logs(name);
+                let _ = stop(name);
+                anyhow::bail!(
+                    "Chrome in {} did not become ready within {}s: {}{}",
+                    image,
+                    wait_secs,
+                    e,
+                    if logs.is_empty() {
+                        String::new()
+                    } else {
+                        format!("\n{}", logs)
+                    }
+                );
+            }
+        }
+    };
+
+    // Save the file config, not this invocation's CLI overrides
+    let mut saved = Config::load()?;
+    saved.chrome.host = probe.chrome.host.clone();
+    saved.chrome.port = port;
+    saved.chrome.tls = false;
+    saved.chrome.token = None;
+    saved.save()?;
+
+    Ok(Running {
+        id,
+        host: probe.chrome.host,
+        port,
+        browser: version.browser,
+    })
+}
+
+/// Last lines of the container log, for explaining a failed start
+pub fn logs(name: &str) -> String {
+    find_runtime()
+        .ok()
+        .and_then(|runtime| {
+            Command::new(runtime)
+                .args(["logs", "--tail", "20", name])
+                .output()
+                .ok()
+        })
+        .map(|output| {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            text.trim().to_string()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_args() {
+        let args = run_args(DEFAULT_NAME, DEFAULT_IMAGE, 9333, true);
+        assert!(args.contains(&"127.0.0.1:9333:9222".to_string()));
+        assert!(args.contains(&"--headless=new".to_string()));
+
+        // Chrome flags follow the image
+        let image = args.iter().position(|a| a == DEFAULT_IMAGE).unwrap();
+        let flag = args
+            .iter()
+            .position(|a| a == "--remote-debugging-port=9222")
+            .unwrap();
+        assert!(flag > image);
+
+        let headed = run_args("c", "chromium", 9222, false);
+        assert!(!headed.iter().any(|a| a.starts_with("--headless")));
+        assert_eq!(headed.last().map(String::as_str), Some("about:blank"));
+    }
+}
@@ -4,8 +4,12 @@
 //! When an action fails, the system can attempt various recovery techniques
 //! before giving up or requesting user takeover.
 
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+use crate::cdp::CdpConnection;
+use crate::output::{ErrorCode, ErrorDetails};
+
 /// Types of errors that can occur during automation
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -58,6 +62,29 @@ impl std::fmt::Display for AutomationError {
     }
 }
 
+impl AutomationError {
+    /// The error type named on the command line (`not-found`, `captcha`, ...)
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().replace(['-', '_'], " ").as_str() {
+            "element not found" | "not found" => AutomationError::ElementNotFound,
+            "element not visible" | "not visible" => AutomationError::ElementNotVisible,
+            "element not interactable" | "not interactable" => {
+                AutomationError::ElementNotInteractable
+            }
+            "navigation timeout" | "timeout" => AutomationError::NavigationTimeout,
+            "network" | "network error" => AutomationError::NetworkError,
+            "javascript" | "js error" => AutomationError::JavaScriptError,
+            "captcha" => AutomationError::CaptchaDetected,
+            "auth" | "authentication" => AutomationError::AuthRequired,
+            "dialog" | "unexpected dialog" => AutomationError::UnexpectedDialog,
+            "stale" | "stale element" => AutomationError::StaleElement,
+            "click intercepted" | "intercepted" => AutomationError::ClickIntercepted,
+            "page change" | "unexpected page" => AutomationError::UnexpectedPageChange,
+            other => AutomationError::Unknown(other.to_string()),
+        }
+    }
+}
+
 /// Recovery strategy to attempt
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -114,8 +141,9 @@ impl std::fmt::Display for RecoveryStrategy {
     }
 }
 
-/// Self-correction configuration
+/// Self-correction configuration (`[correction]` in `.domguard/config.toml`)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CorrectionConfig {
     /// Whether self-correction is enabled
     pub enabled: bool,
@@ -133,6 +161,25 @@ pub struct CorrectionConfig {
     pub auto_scroll: bool,
     /// Whether to request takeover after exhausting strategies
     pub takeover_on_failure: bool,
+    /// Whether refreshing the page is allowed as a recovery step
+    pub auto_refresh: bool,
+}
+
+/// Per-site changes to the correction config (`[correction]` in a site instructions file)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CorrectionOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_delay_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_dismiss_dialogs: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_scroll: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_refresh: Option<bool>,
 }
 
 impl Default for CorrectionConfig {
@@ -146,10 +193,85 @@ impl Default for CorrectionConfig {
             auto_dismiss_dialogs: true,
             auto_scroll: true,
             takeover_on_failure: true,
+            auto_refresh: true,
         }
     }
 }
 
+impl CorrectionConfig {
+    /// Apply a site's overrides on top of this config
+    pub fn with_overrides(&self, overrides: &CorrectionOverrides) -> Self {
+        Self {
+            enabled: overrides.enabled.unwrap_or(self.enabled),
+            max_retries: overrides.max_retries.unwrap_or(self.max_retries),
+            base_delay_ms: overrides.base_delay_ms.unwrap_or(self.base_delay_ms),
+            auto_dismiss_dialogs: overrides
+                .auto_dismiss_dialogs
+                .unwrap_or(self.auto_dismiss_dialogs),
+            auto_scroll: overrides.auto_scroll.unwrap_or(self.auto_scroll),
+            auto_refresh: overrides.auto_refresh.unwrap_or(self.auto_refresh),
+            ..self.clone()
+        }
+    }
+
+    /// Delay before retrying after recovery attempt `attempt` (0-based)
+    pub fn retry_delay_ms(&self, attempt: u32) -> u64 {
+        if self.exponential_backoff {
+            self.base_delay_ms.saturating_mul(1 << attempt.min(10))
+        } else {
+            self.base_delay_ms
+        }
+    }
+
+    /// Whether a strategy can be applied automatically under this config
+    pub fn allows(&self, strategy: &RecoveryStrategy, has_selector: bool) -> bool {
+        match strategy {
+            RecoveryStrategy::WaitAndRetry { .. }
+            | RecoveryStrategy::DismissOverlay
+            | RecoveryStrategy::WaitForStable => true,
+            RecoveryStrategy::RefreshPage => self.auto_refresh,
+            RecoveryStrategy::ScrollIntoView => has_selector && self.auto_scroll,
+            RecoveryStrategy::FocusThenType => has_selector,
+            RecoveryStrategy::ScrollAndSearch => self.auto_scroll,
+            // These change history, cookies or the action itself, or need a human
+            RecoveryStrategy::NavigateBackForward
+            | RecoveryStrategy::ClearCookies
+            | RecoveryStrategy::CloseDialog
+            | RecoveryStrategy::TryAlternateSelector { .. }
+            | RecoveryStrategy::ClickViaJs
+            | RecoveryStrategy::RequestTakeover { .. } => false,
+        }
+    }
+}
+
+/// One recovery strategy applied after a failed action
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryAttempt {
+    /// Error that triggered the attempt
+    pub error: AutomationError,
+    /// Strategy applied before retrying
+    pub strategy: RecoveryStrategy,
+    /// Whether the strategy itself ran without error
+    pub applied: bool,
+}
+
+/// Strategies to try automatically for a failed action, capped at `max_retries`
+pub fn plan_recovery(
+    config: &CorrectionConfig,
+    error: &AutomationError,
+    action: &str,
+    has_selector: bool,
+) -> Vec<RecoveryStrategy> {
+    if !config.enabled {
+        return Vec::new();
+    }
+    get_recovery_strategies(error, action)
+        .into_iter()
+        .filter(|s| config.allows(s, has_selector))
+        .take(config.max_retries as usize)
+        .collect()
+}
+
 /// Get recovery strategies for an error type
 pub fn get_recovery_strategies(error: &AutomationError, action: &str) -> Vec<RecoveryStrategy> {
     match error {
@@ -299,6 +421,116 @@ pub fn classify_error(error_message: &str) -> AutomationError {
     }
 }
 
+/// Error code, selector and recovery suggestions for a failed command's JSON output
+///
+/// `action` is the interact action that failed ("click", "type", ...), if any.
+pub fn describe_error(error: &anyhow::Error, action: &str) -> ErrorDetails {
+    let message = error.to_string();
+    let lower = message.to_lowercase();
+
+    let selector = regex::Regex::new(r#"(?:selector|waiting for) "([^"]*)""#)
+        .ok()
+        .and_then(|re| re.captures(&message))
+        .map(|caps| caps[1].to_string());
+    let details = |code: ErrorCode, suggestions: Vec<String>| ErrorDetails {
+        code,
+        retryable: code.retryable(),
+        selector: selector.clone(),
+        suggestions,
+    };
+
+    if error
+        .downcast_ref::<crate::security::BlockedByPolicy>()
+        .is_some()
+    {
+        return details(
+            ErrorCode::NavBlocked,
+            vec![
+                "Re-run with --override-block if [security] allow_block_override is set"
+                    .to_string(),
+            ],
+        );
+    }
+    if let Some(forbidden) = error.downcast_ref::<crate::site_instructions::ForbiddenBySite>() {
+        return details(
+            ErrorCode::ActionForbidden,
+            vec![format!(
+                "Check forbidden_selectors in the site instructions for {} ('domguard sites show {}')",
+                forbidden.domain, forbidden.domain
+            )],
+        );
+    }
+    if let Some(exceeded) = error.downcast_ref::<crate::budget::BudgetExceeded>() {
+        return ErrorDetails {
+            retryable: exceeded.retry_after_ms.is_some(),
+            ..details(ErrorCode::BudgetExceeded, Vec::new())
+        };
+    }
+    if lower.contains("sensitive action") || lower.contains("blocked by security policy") {
+        return details(
+            ErrorCode::SensitiveAction,
+            vec!["Re-run with --confirm-sensitive after checking the action".to_string()],
+        );
+    }
+    if lower.contains("non-localhost connections are blocked") {
+        return details(
+            ErrorCode::RemoteNotAllowed,
+            vec!["Use --allow-remote, or forward the port with 'domguard tunnel'".to_string()],
+        );
+    }
+    if lower.contains("not connected to chrome")
+        || lower.contains("chrome not found")
+        || lower.contains("failed to connect")
+        || lower.contains("failed to launch chrome")
+        || lower.contains("connection refused")
+    {
+        return details(
+            ErrorCode::ChromeUnreachable,
+            vec![
+                "Start Chrome with --remote-debugging-port=9222 (or 'domguard chrome up')"
+                    .to_string(),
+                "Check the connection with 'domguard status --verbose'".to_string(),
+            ],
+        );
+    }
+
+    let kind = classify_error(&message);
+    let code = match kind {
+        _ if lower.contains("timeout") || lower.contains("timed out") => ErrorCode::Timeout,
+        AutomationError::ElementNotFound => ErrorCode::ElementNotFound,
+        AutomationError::ElementNotVisible => ErrorCode::ElementNotVisible,
+        AutomationError::ElementNotInteractable => ErrorCode::ElementNotInteractable,
+        AutomationError::NavigationTimeout => ErrorCode::Timeout,
+        AutomationError::NetworkError => ErrorCode::NetworkError,
+        AutomationError::JavaScriptError => ErrorCode::JavascriptError,
+        AutomationError::CaptchaDetected => ErrorCode::CaptchaDetected,
+        AutomationError::AuthRequired => ErrorCode::AuthRequired,
+        AutomationError::UnexpectedDialog => ErrorCode::UnexpectedDialog,
+        AutomationError::UnexpectedPageChange => ErrorCode::PageChanged,
+        AutomationError::StaleElement => ErrorCode::StaleElement,
+        AutomationError::ClickIntercepted => ErrorCode::ClickIntercepted,
+        AutomationError::Unknown(_)
+            if lower.contains("required")
+                || lower.contains("requires")
+                || lower.contains("invalid")
+                || lower.contains("unknown") =>
+        {
+            ErrorCode::InvalidArgument
+        }
+        AutomationError::Unknown(_) => ErrorCode::Unknown,
+    };
+
+    let suggestions = if matches!(code, ErrorCode::InvalidArgument | ErrorCode::Unknown) {
+        Vec::new()
+    } else {
+        get_recovery_strategies(&kind, action)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    };
+    details(code, suggestions)
+}
+
 /// JavaScript for dismissing common overlays
 pub fn dismiss_overlay_script() -> &'static str {
     r#"
@@ -367,6 +599,93 @@ pub fn dismiss_overlay_script() -> &'static str {
     "#
 }
 
+/// An element covering part of the viewport, as `debug overlays` reports it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Overlay {
+    pub selector: String,
+    pub role: Option<String>,
+    /// Computed z-index (`None` for auto)
+    pub z_index: Option<i64>,
+    pub position: String,
+    /// Share of the viewport it covers, 0-1
+    pub coverage: f64,
+    /// Clicks in the area it covers land on it rather than the page beneath
+    pub intercepts_pointer: bool,
+    pub text: Option<String>,
+    /// Close, dismiss and accept buttons inside it
+    pub dismiss_candidates: Vec<DismissCandidate>,
+}
+
+/// A button that probably closes an overlay
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DismissCandidate {
+    pub selector: String,
+    pub text: Option<String>,
+}
+
+/// JavaScript listing the outermost fixed, sticky or absolute elements
+/// covering at least `min_coverage` (0-1) of the viewport, topmost first
+///
+/// Uses the close-button selectors of `dismiss_overlay_script`, plus
+/// buttons whose text or label reads like close, dismiss or accept.
+pub fn overlay_report_script(min_coverage: f64) -> String {
+    format!(
+        r#"(function(minCoverage) {{
+            const viewport = window.innerWidth * window.innerHeight;
+            const closeSelectors = ['.close', '.close-btn', '.close-button', '[aria-label="Close"]',
+                '[aria-label="close"]', 'button[class*="close"]', '.modal-close', '.dismiss', '.cancel'];
+            const closeText = /^(×|✕|x|close|dismiss|no,? thanks|not now|maybe later|skip|cancel|accept( all)?|got it|ok)$/i;
+            const describe = el => {{
+                if (el.id) return '#' + CSS.escape(el.id);
+                const classes = [...el.classList].slice(0, 2).map(c => '.' + CSS.escape(c)).join('');
+                return el.tagName.toLowerCase() + classes;
+            }};
+            const label = el => (el.getAttribute('aria-label') || el.innerText || el.value || '')
+                .trim().replace(/\s+/g, ' ');
+
+            const covering = [];
+            for (const el of document.body ? document.body.querySelectorAll('*') : []) {{
+                const style = getComputedStyle(el);
+                if (!['fixed', 'sticky', 'absolute'].includes(style.position)) continue;
+                if (style.display === 'none' || style.visibility === 'hidden' || parseFloat(style.opacity) === 0) continue;
+                const rect = el.getBoundingClientRect();
+                const width = Math.max(0, Math.min(rect.right, window.innerWidth) - Math.max(rect.left, 0));
+                const height = Math.max(0, Math.min(rect.bottom, window.innerHeight) - Math.max(rect.top, 0));
+                const coverage = viewport ? (width * height) / viewport : 0;
+                if (coverage < minCoverage) continue;
+                covering.push({{ el, style, rect, coverage }});
+            }}
+            const outermost = covering.filter(c => !covering.some(o => o.el !== c.el && o.el.contains(c.el)));
+
+            return outermost.map(({{ el, style, rect, coverage }}) => {{
+                const x = Math.min(Math.max((Math.max(rect.left, 0) + Math.min(rect.right, window.innerWidth)) / 2, 0), window.innerWidth - 1);
+                const y = Math.min(Math.max((Math.max(rect.top, 0) + Math.min(rect.bottom, window.innerHeight)) / 2, 0), window.innerHeight - 1);
+                const hit = document.elementFromPoint(x, y);
+                const candidates = new Map();
+                for (const button of el.querySelectorAll(closeSelectors.join(', ') + ', button, [role="button"], a')) {{
+                    if (button.getClientRects().length === 0) continue;
+                    const text = label(button);
+                    if (!button.matches(closeSelectors.join(', ')) && !closeText.test(text)) continue;
+                    const selector = describe(button);
+                    if (!candidates.has(selector)) candidates.set(selector, {{ selector, text: text.slice(0, 40) || null }});
+                }}
+                const z = parseInt(style.zIndex, 10);
+                return {{
+                    selector: describe(el),
+                    role: el.getAttribute('role') || (el.tagName === 'DIALOG' ? 'dialog' : null),
+                    z_index: Number.isNaN(z) ? null : z,
+                    position: style.position,
+                    coverage: Math.round(coverage * 1000) / 1000,
+                    intercepts_pointer: style.pointerEvents !== 'none' && !!hit && el.contains(hit),
+                    text: label(el).slice(0, 100) || null,
+                    dismiss_candidates: [...candidates.values()].slice(0, 5),
+                }};
+            }}).sort((a, b) => (b.z_index ?? 0) - (a.z_index ?? 0) || b.coverage - a.coverage);
+        }})({})"#,
+        min_coverage
+    )
+}
+
 /// JavaScript for waiting until page is stable
 pub fn wait_stable_script() -> &'static str {
     r#"
@@ -399,10 +718,120 @@ pub fn wait_stable_script() -> &'static str {
     "#
 }
 
+/// How long the page took to settle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stability {
+    /// False when it was still changing after 3s
+    pub stable: bool,
+    pub duration_ms: u64,
+}
+
+/// Dismiss common overlays, returning whether any were found
+pub async fn dismiss_overlays(cdp: &CdpConnection) -> Result<bool> {
+    let result = cdp.evaluate(dismiss_overlay_script()).await?;
+    Ok(result.as_bool().unwrap_or(false))
+}
+
+/// Wait until the DOM stops changing, for up to 3s
+pub async fn wait_stable(cdp: &CdpConnection) -> Result<Stability> {
+    let result = cdp.evaluate(wait_stable_script()).await?;
+    Ok(Stability {
+        stable: result
+            .get("stable")
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false),
+        duration_ms: result
+            .get("duration")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0),
+    })
+}
+
+/// Result of trying one recovery strategy by hand
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyTest {
+    pub success: bool,
+    pub message: String,
+}
+
+/// Try a recovery strategy (`scroll`, `dismiss-overlay`, `wait-stable` or
+/// `refresh`) on the current page
+///
+/// A strategy that fails is reported in the result rather than as an error.
+pub async fn test_strategy(
+    cdp: &CdpConnection,
+    strategy: &str,
+    target: Option<&str>,
+) -> StrategyTest {
+    let (success, message) = match strategy.to_lowercase().as_str() {
+        "scroll" | "scroll-into-view" => {
+            if let Some(sel) = target {
+                match cdp.scroll_to_element(sel).await {
+                    Ok(_) => (true, "Element scrolled into view".to_string()),
+                    Err(e) => (false, e.to_string()),
+                }
+            } else {
+                (
+                    false,
+                    "Target selector required for scroll strategy".to_string(),
+                )
+            }
+        }
+        "dismiss-overlay" | "overlay" => match dismiss_overlays(cdp).await {
+            Ok(true) => (true, "Overlays dismissed".to_string()),
+            Ok(false) => (false, "No overlays found".to_string()),
+            Err(e) => (false, e.to_string()),
+        },
+        "wait-stable" | "stable" => match wait_stable(cdp).await {
+            Ok(stability) => (
+                stability.stable,
+                format!(
+                    "Page {} after {}ms",
+                    if stability.stable {
+                        "stabilized"
+                    } else {
+                        "still changing"
+                    },
+                    stability.duration_ms
+                ),
+            ),
+            Err(e) => (false, e.to_string()),
+        },
+        "refresh" => match cdp.refresh().await {
+            Ok(_) => (true, "Page refreshed".to_string()),
+            Err(e) => (false, e.to_string()),
+        },
+        _ => (
+            false,
+            format!(
+                "Unknown strategy: {}. Use: scroll, dismiss-overlay, wait-stable, refresh",
+                strategy
+            ),
+        ),
+    };
+    StrategyTest { success, message }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_error_from_name() {
+        assert_eq!(
+            AutomationError::from_name("not-found"),
+            AutomationError::ElementNotFound
+        );
+        assert_eq!(
+            AutomationError::from_name("Click_Intercepted"),
+            AutomationError::ClickIntercepted
+        );
+        assert_eq!(
+            AutomationError::from_name("quota"),
+            AutomationError::Unknown("quota".to_string())
+        );
+    }
+
     #[test]
     fn test_classify_element_not_found() {
         assert_eq!(
@@ -438,6 +867,62 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_plan_recovery() {
+        let config = CorrectionConfig::default();
+        let plan = plan_recovery(&config, &AutomationError::ClickIntercepted, "click", true);
+        assert_eq!(
+            plan,
+            vec![
+                RecoveryStrategy::WaitAndRetry { delay_ms: 300 },
+                RecoveryStrategy::DismissOverlay,
+                RecoveryStrategy::ScrollIntoView,
+            ]
+        );
+
+        // Without a selector there is nothing to scroll to
+        let plan = plan_recovery(&config, &AutomationError::ElementNotVisible, "click", false);
+        assert!(!plan.contains(&RecoveryStrategy::ScrollIntoView));
+
+        let disabled = CorrectionConfig {
+            enabled: false,
+            ..CorrectionConfig::default()
+        };
+        assert!(
+            plan_recovery(&disabled, &AutomationError::ElementNotFound, "click", true).is_empty()
+        );
+        assert!(
+            plan_recovery(&config, &AutomationError::CaptchaDetected, "click", true).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_site_overrides() {
+        let base = CorrectionConfig::default();
+        let banking = base.with_overrides(&CorrectionOverrides {
+            auto_refresh: Some(false),
+            max_retries: Some(5),
+            ..CorrectionOverrides::default()
+        });
+        assert!(banking.enabled);
+        assert_eq!(banking.max_retries, 5);
+        assert!(!banking.allows(&RecoveryStrategy::RefreshPage, true));
+        assert!(base.allows(&RecoveryStrategy::RefreshPage, true));
+
+        let partial: CorrectionConfig = toml::from_str("max_retries = 1").unwrap();
+        assert_eq!(partial.max_retries, 1);
+        assert!(partial.auto_refresh);
+    }
+
+    #[test]
+    fn test_retry_delay_backoff() {
+        let mut config = CorrectionConfig::default();
+        assert_eq!(config.retry_delay_ms(0), 500);
+        assert_eq!(config.retry_delay_ms(2), 2000);
+        config.exponential_backoff = false;
+        assert_eq!(config.retry_delay_ms(2), 500);
+    }
+
     #[test]
     fn test_get_recovery_strategies_captcha() {
         let strategies = get_recovery_strategies(&AutomationError::CaptchaDetected, "click");
@@ -447,4 +932,64 @@ mod tests {
             RecoveryStrategy::RequestTakeover { .. }
         ));
     }
+
+    #[test]
+    fn test_describe_error() {
+        let not_found = describe_error(
+            &anyhow::anyhow!("No element matches selector \"#submit\""),
+            "click",
+        );
+        assert_eq!(not_found.code, ErrorCode::ElementNotFound);
+        assert!(not_found.retryable);
+        assert_eq!(not_found.selector.as_deref(), Some("#submit"));
+        assert!(!not_found.suggestions.is_empty());
+
+        let timeout = describe_error(
+            &anyhow::anyhow!("Timeout waiting for \".results\" (5000ms)"),
+            "wait",
+        );
+        assert_eq!(timeout.code, ErrorCode::Timeout);
+        assert_eq!(timeout.selector.as_deref(), Some(".results"));
+
+        let unreachable = describe_error(&anyhow::anyhow!("Not connected to Chrome"), "");
+        assert_eq!(unreachable.code, ErrorCode::ChromeUnreachable);
+
+        let blocked = describe_error(
+            &anyhow::Error::new(crate::security::BlockedByPolicy {
+                command: "interact navigate".to_string(),
+                url: "https://bad.example".to_string(),
+                rule: "bad.example".to_string(),
+                override_refused: false,
+            }),
+            "navigate",
+        );
+        assert_eq!(blocked.code, ErrorCode::NavBlocked);
+        assert!(!blocked.retryable);
+
+        let forbidden = describe_error(
+            &anyhow::Error::new(crate::site_instructions::ForbiddenBySite {
+                domain: "shop.example".to_string(),
+                action: "click".to_string(),
+                selector: "#delete".to_string(),
+                rule: "#delete".to_string(),
+            }),
+            "click",
+        );
+        assert_eq!(forbidden.code, ErrorCode::ActionForbidden);
+        assert!(!forbidden.retryable);
+    }
+
+    #[test]
+    fn test_overlay_report_script() {
+        let script = overlay_report_script(0.25);
+        assert!(script.ends_with("})(0.25)"));
+        let overlay: Overlay = serde_json::from_str(
+            r##"{"selector":"#modal","role":"dialog","z_index":1000,"position":"fixed",
+                "coverage":0.6,"intercepts_pointer":true,"text":"Subscribe",
+                "dismiss_candidates":[{"selector":"button.close","text":"×"}]}"##,
+        )
+        .unwrap();
+        assert_eq!(overlay.z_index, Some(1000));
+        assert_eq!(overlay.dismiss_candidates[0].selector, "button.close");
+    }
 }
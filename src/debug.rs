@@ -3,11 +3,20 @@
 //! DOM tree, console messages, network requests, storage, cookies
 
 use anyhow::Result;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fmt::Write as _;
 
-use crate::cdp::CdpConnection;
-use crate::output::{mask_sensitive, AriaNode, ConsoleMessage, DomNode, Formatter, NetworkRequest};
+use crate::backend::{BrowserBackend, CaptureOptions, ImageFormat};
+use crate::cdp::{
+    CdpConnection, ConsoleEntry, EvalOptions, ExceptionReport, ExceptionWatch, TabInfo, XhrCall,
+    XhrWatch,
+};
+use crate::config::Config;
+use crate::output::{
+    mask_sensitive, AriaNode, ConsoleMessage, DomNode, Formatter, Masker, NetworkRequest,
+};
+use crate::perf::PerfBudget;
+use crate::site_instructions::expand_page_aliases;
 
 /// Get current timestamp in seconds, with fallback to 0 if system clock is before UNIX epoch
 fn safe_timestamp() -> u64 {
@@ -21,9 +30,36 @@ fn safe_timestamp() -> u64 {
 #[derive(Debug, Clone)]
 pub enum TabCommand {
     List,
-    New { url: Option<String> },
-    Switch { id: String },
-    Close { id: String },
+    New {
+        url: Option<String>,
+        /// In a fresh browser context (separate cookies and storage)
+        incognito: bool,
+        /// In its own window
+        window: bool,
+    },
+    Switch {
+        id: String,
+    },
+    /// Switch to the only tab whose URL contains `pattern`
+    SwitchUrl {
+        pattern: String,
+    },
+    Duplicate {
+        id: String,
+    },
+    Close {
+        id: String,
+    },
+}
+
+/// Browser context actions
+#[derive(Debug, Clone)]
+pub enum ContextCommand {
+    List,
+    /// Dispose one context, or all of them with `None`
+    Dispose {
+        id: Option<String>,
+    },
 }
 
 /// Throttle mode for CPU/network emulation
@@ -47,6 +83,377 @@ pub enum ThrottleMode {
     },
 }
 
+/// Network conditions left applied by `debug throttle`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkThrottle {
+    /// "3g", "slow3g", "offline" or "custom"
+    pub preset: String,
+    pub download_kbps: f64,
+    pub upload_kbps: f64,
+    pub latency_ms: f64,
+}
+
+/// Throttling DOMGuard applied and hasn't turned off
+///
+/// Chrome keeps it on the page after the CLI exits, so it is remembered for
+/// `status` and `debug throttle status` to report.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThrottleState {
+    /// CPU slowdown factor, unless 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_rate: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkThrottle>,
+    /// Page it was applied on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ThrottleState {
+    pub fn is_off(&self) -> bool {
+        self.cpu_rate.is_none() && self.network.is_none()
+    }
+
+    /// Record `mode` as applied on `url`
+    pub fn apply(&mut self, mode: &ThrottleMode, url: Option<String>) {
+        let network = |preset: &str, download_kbps, upload_kbps, latency_ms| {
+            Some(NetworkThrottle {
+                preset: preset.to_string(),
+                download_kbps,
+                upload_kbps,
+                latency_ms,
+            })
+        };
+        match mode {
+            ThrottleMode::Off => {
+                *self = Self::default();
+                return;
+            }
+            ThrottleMode::Cpu { rate } => {
+                self.cpu_rate = ((rate - 1.0).abs() > f64::EPSILON).then_some(*rate);
+            }
+            ThrottleMode::Network3g => self.network = network("3g", 1600.0, 750.0, 300.0),
+            ThrottleMode::NetworkSlow3g => self.network = network("slow3g", 400.0, 400.0, 2000.0),
+            ThrottleMode::NetworkOffline => self.network = network("offline", 0.0, 0.0, 0.0),
+            ThrottleMode::NetworkCustom {
+                download_kbps,
+                upload_kbps,
+                latency_ms,
+            } => self.network = network("custom", *download_kbps, *upload_kbps, *latency_ms),
+        }
+        if self.is_off() {
+            *self = Self::default();
+        } else {
+            self.url = url;
+            self.updated_at = Some(chrono::Utc::now());
+        }
+    }
+
+    /// e.g. "CPU 4x slowdown, network 3g (1600 Kbps down, 750 Kbps up, 300ms latency)"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(rate) = self.cpu_rate {
+            parts.push(format!("CPU {}x slowdown", rate));
+        }
+        match &self.network {
+            Some(network) if network.preset == "offline" => parts.push("network offline".into()),
+            Some(network) => parts.push(format!(
+                "network {} ({} Kbps down, {} Kbps up, {}ms latency)",
+                network.preset, network.download_kbps, network.upload_kbps, network.latency_ms
+            )),
+            None => {}
+        }
+        if parts.is_empty() {
+            "off".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// Browser state DOMGuard leaves behind, stored in `.domguard/state.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageState {
+    #[serde(default)]
+    pub throttle: ThrottleState,
+}
+
+impl PageState {
+    /// Where the state is stored
+    pub fn path() -> std::path::PathBuf {
+        Config::find_domguard_dir()
+            .unwrap_or_else(Config::domguard_dir)
+            .join("state.json")
+    }
+
+    /// Load the stored state (nothing applied if the file doesn't exist)
+    pub fn load(path: &std::path::Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Save the state
+    pub fn save(&self, path: &std::path::Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        crate::storage::write_atomic(path, &serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Attribute/property action types
+#[derive(Debug, Clone)]
+pub enum AttrCommand {
+    /// Read one attribute/property, or all attributes and common properties
+    Get {
+        selector: String,
+        name: Option<String>,
+    },
+    /// Set an attribute (or a DOM property with `property`)
+    Set {
+        selector: String,
+        name: String,
+        value: String,
+        property: bool,
+    },
+}
+
+/// How page animations should run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimationMode {
+    /// Jump animations and transitions to their end state
+    Disable,
+    /// Run them this many times slower
+    Slow(f64),
+    /// Undo a previous disable or slow
+    Enable,
+}
+
+/// CSS injection actions
+#[derive(Debug, Clone)]
+pub enum CssCommand {
+    /// Inject a stylesheet under `name`, replacing one injected under the same name
+    Inject { name: String, css: String },
+    /// Override declarations for a selector
+    Set {
+        selector: String,
+        declarations: String,
+    },
+    /// Remove all injected styles
+    Clear,
+}
+
+/// A script run before page scripts on every page DOMGuard loads
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InitScript {
+    pub id: u32,
+    /// File the script was read from
+    pub name: String,
+    pub source: String,
+}
+
+/// Persisted init scripts, stored in `.domguard/inject.toml`
+///
+/// Registered with `Page.addScriptToEvaluateOnNewDocument` on every connection
+/// that loads pages, until removed with `debug inject remove`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InitScripts {
+    #[serde(default)]
+    pub scripts: Vec<InitScript>,
+}
+
+impl InitScripts {
+    /// Where the scripts are stored
+    pub fn path() -> std::path::PathBuf {
+        Config::find_domguard_dir()
+            .unwrap_or_else(Config::domguard_dir)
+            .join("inject.toml")
+    }
+
+    /// Register the stored scripts for this connection
+    pub async fn apply(cdp: &CdpConnection) -> Result<()> {
+        for script in Self::load(&Self::path())?.scripts {
+            cdp.add_init_script(&script.source).await?;
+        }
+        Ok(())
+    }
+
+    /// Load the stored scripts (none if the file doesn't exist)
+    pub fn load(path: &std::path::Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Save the scripts, removing the file once none are left
+    pub fn save(&self, path: &std::path::Path) -> Result<()> {
+        if self.scripts.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Add a script, replacing one injected from the same file, and return its id
+    pub fn add(&mut self, name: &str, source: &str) -> u32 {
+        if let Some(script) = self.scripts.iter_mut().find(|script| script.name == name) {
+            script.source = source.to_string();
+            return script.id;
+        }
+        let id = self
+            .scripts
+            .iter()
+            .map(|script| script.id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        self.scripts.push(InitScript {
+            id,
+            name: name.to_string(),
+            source: source.to_string(),
+        });
+        id
+    }
+
+    /// Remove one script (or all with `None`), returning how many were removed
+    pub fn remove(&mut self, id: Option<u32>) -> usize {
+        let before = self.scripts.len();
+        self.scripts
+            .retain(|script| id.is_some_and(|id| script.id != id));
+        before - self.scripts.len()
+    }
+}
+
+/// Which text of an element to read
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextMode {
+    /// Raw `textContent`
+    Content,
+    /// Rendered `innerText` (respects CSS visibility and line breaks)
+    Inner,
+    /// Form control `value`
+    Value,
+    /// `textContent` with whitespace collapsed and trimmed
+    Trimmed,
+}
+
+impl TextMode {
+    /// JavaScript expression reading the text of `el`
+    fn js_expression(self) -> &'static str {
+        match self {
+            Self::Content => "el.textContent",
+            Self::Inner => "el.innerText",
+            Self::Value => "('value' in el ? String(el.value) : null)",
+            Self::Trimmed => "el.textContent.replace(/\\s+/g, ' ').trim()",
+        }
+    }
+}
+
+/// Where `debug eval` runs its script
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvalWorld {
+    /// The page's own context, sharing globals with its scripts
+    #[default]
+    Main,
+    /// A separate world with its own globals over the same DOM
+    Isolated,
+}
+
+impl std::fmt::Display for EvalWorld {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Main => write!(f, "main"),
+            Self::Isolated => write!(f, "isolated"),
+        }
+    }
+}
+
+impl std::str::FromStr for EvalWorld {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "main" | "page" => Ok(Self::Main),
+            "isolated" => Ok(Self::Isolated),
+            other => Err(format!(
+                "Unknown world \"{}\" (expected main or isolated)",
+                other
+            )),
+        }
+    }
+}
+
+/// Wrap a script so `args` are visible to it as a `params` object
+///
+/// The block keeps `params` out of the page's globals while the last
+/// statement still becomes the result.
+pub fn eval_source(source: &str, args: &[(String, String)]) -> String {
+    if args.is_empty() {
+        return source.to_string();
+    }
+    let params: serde_json::Map<String, serde_json::Value> = args
+        .iter()
+        .map(|(key, value)| (key.clone(), serde_json::Value::String(value.clone())))
+        .collect();
+    format!(
+        "{{\nconst params = Object.freeze({});\n{}\n}}",
+        serde_json::Value::Object(params),
+        source
+    )
+}
+
+/// Page-side store for `--return-by-ref` results
+///
+/// Remote object ids die with the CDP session, so values are pinned in the
+/// page instead, under a symbol page scripts won't stumble on. They last until
+/// the document goes away.
+const REFS_JS: &str = "(globalThis[Symbol.for('domguard.refs')] ??= new Map())";
+
+/// Wrap a script to keep its result as a ref, or to run it with a ref as `this`
+pub fn ref_script(source: &str, return_by_ref: bool, this: Option<&str>) -> String {
+    if !return_by_ref && this.is_none() {
+        return source.to_string();
+    }
+    let target = match this {
+        Some(name) => {
+            let name = serde_json::Value::String(name.to_string());
+            format!(
+                "(() => {{ const refs = {refs}; if (!refs.has({name})) throw new Error('Unknown ref ' + {name} + '; refs last until the page navigates'); return refs.get({name}); }})()",
+                refs = REFS_JS,
+                name = name
+            )
+        }
+        None => "globalThis".to_string(),
+    };
+    // Direct eval keeps statement scripts working and sees the bound `this`
+    let run = format!(
+        "(function() {{ return eval({}); }}).call({})",
+        serde_json::Value::String(source.to_string()),
+        target
+    );
+    if !return_by_ref {
+        return run;
+    }
+    format!(
+        "(async () => {{ const value = await {run}; const refs = {refs}; const ref = 'ref' + (refs.size + 1); refs.set(ref, value); return {{ ref, type: value === null ? 'null' : typeof value, class: value?.constructor?.name ?? null }}; }})()",
+        run = run,
+        refs = REFS_JS
+    )
+}
+
 /// Debug subcommand types
 #[derive(Debug, Clone)]
 pub enum DebugCommand {
@@ -55,16 +462,48 @@ pub enum DebugCommand {
     },
     Styles {
         selector: String,
+        /// Also list the matched CSS rules
+        matched: bool,
+        /// Also show the box model
+        box_model: bool,
+    },
+    Css {
+        action: CssCommand,
+    },
+    Animations {
+        mode: AnimationMode,
+    },
+    Inject {
+        name: String,
+        source: String,
+        persist: bool,
     },
     Console {
         follow: bool,
         filter: Option<String>,
+        /// Ignored unless following; `None` listens until interrupted
+        duration_ms: Option<u64>,
+        /// Only these levels (e.g. error, warn); empty for all
+        levels: Vec<String>,
+    },
+    Exceptions {
+        watch: ExceptionWatch,
+        /// Directory for captures
+        output: Option<std::path::PathBuf>,
     },
     Network {
         filter: Option<String>,
     },
+    Xhr {
+        watch: XhrWatch,
+        /// Print each call as it completes instead of all of them at the end
+        follow: bool,
+        /// Show GraphQL operations instead of raw calls
+        decode_graphql: bool,
+    },
     Eval {
         expression: String,
+        options: EvalOptions,
     },
     Storage,
     Cookies,
@@ -74,8 +513,30 @@ pub enum DebugCommand {
     Tabs {
         action: TabCommand,
     },
+    Contexts {
+        action: ContextCommand,
+    },
+    History,
     // Chrome DevTools MCP features
     Performance,
+    PerfAssert {
+        budget: PerfBudget,
+    },
+    Heap {
+        /// Id or path of an earlier summary to compare against
+        compare: Option<String>,
+        /// Collect garbage before the snapshot
+        gc: bool,
+        /// Constructors to show
+        top: usize,
+    },
+    Readiness,
+    Framework,
+    Canvas {
+        selector: String,
+        format: ImageFormat,
+        output: Option<std::path::PathBuf>,
+    },
     Snapshot {
         output: Option<std::path::PathBuf>,
     },
@@ -95,28 +556,131 @@ pub enum DebugCommand {
     ClearHighlights,
     // CAPTCHA detection
     Captcha,
+    /// Elements covering at least `min_coverage` (0-1) of the viewport
+    Overlays {
+        min_coverage: f64,
+    },
+    // Element attributes
+    Attr {
+        action: AttrCommand,
+    },
+    Text {
+        selector: String,
+        all: bool,
+        mode: TextMode,
+    },
+    Count {
+        selector: String,
+        exists: bool,
+    },
+    Find {
+        query: FindQuery,
+    },
+    DomDiff {
+        baseline: DiffBaseline,
+    },
+}
+
+impl DebugCommand {
+    /// CSS selectors this command targets
+    fn selectors_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Self::Dom { selector } | Self::Aria { selector } => selector.iter_mut().collect(),
+            Self::Styles { selector, .. }
+            | Self::Highlight { selector, .. }
+            | Self::Text { selector, .. }
+            | Self::Count { selector, .. }
+            | Self::Canvas { selector, .. }
+            | Self::Css {
+                action: CssCommand::Set { selector, .. },
+            }
+            | Self::Attr {
+                action: AttrCommand::Get { selector, .. } | AttrCommand::Set { selector, .. },
+            } => vec![selector],
+            _ => Vec::new(),
+        }
+    }
 }
 
 /// Run debug command
 pub async fn run_debug(
     cdp: &CdpConnection,
-    command: DebugCommand,
+    config: &Config,
+    mut command: DebugCommand,
     formatter: &Formatter,
 ) -> Result<()> {
+    expand_page_aliases(cdp, command.selectors_mut()).await?;
     match command {
-        DebugCommand::Dom { selector } => debug_dom(cdp, selector.as_deref(), formatter).await,
-        DebugCommand::Styles { selector } => debug_styles(cdp, &selector, formatter).await,
-        DebugCommand::Console { follow, filter } => {
-            debug_console(cdp, follow, filter.as_deref(), formatter).await
+        DebugCommand::Dom { selector } => {
+            let masker = Masker::new(&config.masking)?;
+            debug_dom(cdp, selector.as_deref(), &masker, formatter).await
+        }
+        DebugCommand::Styles {
+            selector,
+            matched,
+            box_model,
+        } => debug_styles(cdp, &selector, matched, box_model, formatter).await,
+        DebugCommand::Css { action } => debug_css(cdp, action, formatter).await,
+        DebugCommand::Animations { mode } => debug_animations(cdp, mode, formatter).await,
+        DebugCommand::Inject {
+            name,
+            source,
+            persist,
+        } => debug_inject(cdp, &name, &source, persist, formatter).await,
+        DebugCommand::Console {
+            follow,
+            filter,
+            duration_ms,
+            levels,
+        } => {
+            if follow {
+                follow_console(cdp, filter.as_deref(), duration_ms, &levels, formatter).await
+            } else {
+                debug_console(cdp, filter.as_deref(), &levels, formatter).await
+            }
+        }
+        DebugCommand::Exceptions { watch, output } => {
+            debug_exceptions(cdp, &watch, output, formatter).await
         }
         DebugCommand::Network { filter } => debug_network(cdp, filter.as_deref(), formatter).await,
-        DebugCommand::Eval { expression } => debug_eval(cdp, &expression, formatter).await,
+        DebugCommand::Xhr {
+            mut watch,
+            follow,
+            decode_graphql,
+        } => {
+            if !follow {
+                watch.duration_ms = watch.duration_ms.or(Some(XHR_SAMPLE_MS));
+            }
+            let masker = Masker::new(&config.masking)?;
+            debug_xhr(cdp, &watch, follow, decode_graphql, &masker, formatter).await
+        }
+        DebugCommand::Eval {
+            expression,
+            options,
+        } => {
+            let masker = Masker::new(&config.masking)?;
+            let result = cdp.evaluate_in(&expression, &options).await?;
+            print_eval_result(result, &masker, formatter)
+        }
         DebugCommand::Storage => debug_storage(cdp, formatter).await,
         DebugCommand::Cookies => debug_cookies(cdp, formatter).await,
         DebugCommand::Aria { selector } => debug_aria(cdp, selector.as_deref(), formatter).await,
         DebugCommand::Tabs { action } => debug_tabs(cdp, action, formatter).await,
+        DebugCommand::Contexts { action } => debug_contexts(cdp, action, formatter).await,
+        DebugCommand::History => debug_history(cdp, formatter).await,
         // Chrome DevTools MCP features
         DebugCommand::Performance => debug_performance(cdp, formatter).await,
+        DebugCommand::PerfAssert { budget } => debug_perf_assert(cdp, &budget, formatter).await,
+        DebugCommand::Heap { compare, gc, top } => {
+            debug_heap(cdp, compare.as_deref(), gc, top, formatter).await
+        }
+        DebugCommand::Readiness => debug_readiness(cdp, formatter).await,
+        DebugCommand::Framework => debug_framework(cdp, formatter).await,
+        DebugCommand::Canvas {
+            selector,
+            format,
+            output,
+        } => debug_canvas(cdp, &selector, format, output, formatter).await,
         DebugCommand::Snapshot { output } => debug_snapshot(cdp, output, formatter).await,
         DebugCommand::Throttle { mode } => debug_throttle(cdp, mode, formatter).await,
         DebugCommand::NetworkDetails { filter } => {
@@ -132,6 +696,21 @@ pub async fn run_debug(
         DebugCommand::ClearHighlights => debug_clear_highlights(cdp, formatter).await,
         // CAPTCHA detection
         DebugCommand::Captcha => debug_captcha(cdp, formatter).await,
+        DebugCommand::Overlays { min_coverage } => {
+            debug_overlays(cdp, min_coverage, formatter).await
+        }
+        // Element attributes
+        DebugCommand::Attr { action } => debug_attr(cdp, action, formatter).await,
+        DebugCommand::Text {
+            selector,
+            all,
+            mode,
+        } => debug_text(cdp, &selector, all, mode, formatter).await,
+        DebugCommand::Count { selector, exists } => {
+            debug_count(cdp, &selector, exists, formatter).await
+        }
+        DebugCommand::Find { query } => debug_find(cdp, &query, formatter).await,
+        DebugCommand::DomDiff { baseline } => debug_dom_diff(cdp, &baseline, formatter).await,
     }
 }
 
@@ -139,9 +718,10 @@ pub async fn run_debug(
 async fn debug_dom(
     cdp: &CdpConnection,
     selector: Option<&str>,
+    masker: &Masker,
     formatter: &Formatter,
 ) -> Result<()> {
-    let dom_tree = if let Some(sel) = selector {
+    let mut dom_tree = if let Some(sel) = selector {
         // Get specific element(s)
         let js = format!(
             r#"
@@ -149,6 +729,11 @@ async fn debug_dom(
                 const elements = document.querySelectorAll('{}');
                 if (elements.length === 0) return null;
 
+                function formFieldAttributes(el) {{
+                    if (!['INPUT', 'TEXTAREA', 'SELECT'].includes(el.tagName)) return null;
+                    return {{ type: el.type || '', name: el.name || '', value: el.value || '' }};
+                }}
+
                 function nodeToJson(el, depth) {{
                     if (depth > 5) return null;
                     const result = {{
@@ -157,6 +742,7 @@ async fn debug_dom(
                         classes: el.className ? el.className.split(' ').filter(c => c) : null,
                         text: el.childNodes.length === 1 && el.childNodes[0].nodeType === 3
                             ? el.textContent.trim() : null,
+                        attributes: formFieldAttributes(el),
                         children: []
                     }};
                     for (const child of el.children) {{
@@ -181,6 +767,11 @@ async fn debug_dom(
         // Get full DOM tree (limited depth)
         let js = r#"
             (function() {
+                function formFieldAttributes(el) {
+                    if (!['INPUT', 'TEXTAREA', 'SELECT'].includes(el.tagName)) return null;
+                    return { type: el.type || '', name: el.name || '', value: el.value || '' };
+                }
+
                 function nodeToJson(el, depth) {
                     if (depth > 4) return null;
                     if (!el.tagName) return null;
@@ -192,6 +783,7 @@ async fn debug_dom(
                             ? el.className.split(' ').filter(c => c) : null,
                         text: el.childNodes.length === 1 && el.childNodes[0].nodeType === 3
                             ? el.textContent.trim().substring(0, 100) : null,
+                        attributes: formFieldAttributes(el),
                         children: []
                     };
 
@@ -209,6 +801,7 @@ async fn debug_dom(
 
         cdp.evaluate(js).await?
     };
+    masker.mask_value(&mut dom_tree);
 
     if formatter.is_json() {
         formatter.output_json(&dom_tree);
@@ -227,7 +820,16 @@ async fn debug_dom(
                         .filter_map(|c| c.as_str().map(|s| s.to_string()))
                         .collect()
                 }),
-                attributes: None,
+                attributes: obj
+                    .get("attributes")
+                    .and_then(|v| v.as_object())
+                    .map(|attrs| {
+                        attrs
+                            .iter()
+                            .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                            .filter(|(_, v)| !v.is_empty())
+                            .collect()
+                    }),
                 text: obj
                     .get("text")
                     .and_then(|v| v.as_str())
@@ -257,7 +859,13 @@ async fn debug_dom(
 }
 
 /// Debug computed styles
-async fn debug_styles(cdp: &CdpConnection, selector: &str, formatter: &Formatter) -> Result<()> {
+async fn debug_styles(
+    cdp: &CdpConnection,
+    selector: &str,
+    matched: bool,
+    box_model: bool,
+    formatter: &Formatter,
+) -> Result<()> {
     let js = format!(
         r#"
         (function() {{
@@ -296,8 +904,27 @@ async fn debug_styles(cdp: &CdpConnection, selector: &str, formatter: &Formatter
         ));
     }
 
+    let rules = if matched {
+        Some(cdp.matched_styles(selector).await?)
+    } else {
+        None
+    };
+    let boxes = if box_model {
+        Some(cdp.box_model(selector).await?)
+    } else {
+        None
+    };
+
     if formatter.is_json() {
-        formatter.output_json(&result);
+        if rules.is_none() && boxes.is_none() {
+            formatter.output_json(&result);
+        } else {
+            formatter.output_json(&serde_json::json!({
+                "computed": result,
+                "matched": rules,
+                "box_model": boxes
+            }));
+        }
     } else {
         formatter.header(&format!("Styles for \"{}\"", selector));
         if let Some(obj) = result.as_object() {
@@ -305,73 +932,887 @@ async fn debug_styles(cdp: &CdpConnection, selector: &str, formatter: &Formatter
                 formatter.kv(key, value.as_str().unwrap_or(""));
             }
         }
+        if let Some(rules) = &rules {
+            print_matched_rules(rules, formatter);
+        }
+        if let Some(boxes) = &boxes {
+            print_box_model(boxes, formatter);
+        }
     }
 
     Ok(())
 }
 
-/// Debug console messages using CDP event listeners
-async fn debug_console(
+/// Matched rules, winning rule first, with overridden declarations struck through
+fn print_matched_rules(rules: &[crate::cdp::MatchedRule], formatter: &Formatter) {
+    use colored::Colorize;
+
+    formatter.header("Matched Rules");
+    if rules.is_empty() {
+        println!("  (none)");
+    }
+    for rule in rules.iter().rev() {
+        let (inline, ids, classes, types) = rule.specificity;
+        let location = match (&rule.source, rule.line) {
+            (Some(source), Some(line)) => format!("{}:{}", source, line),
+            (Some(source), None) => source.clone(),
+            _ => rule.origin.clone(),
+        };
+        let specificity = if inline > 0 {
+            "inline".to_string()
+        } else {
+            format!("{},{},{}", ids, classes, types)
+        };
+        println!(
+            "\n  {} {}",
+            rule.selector.bold(),
+            format!("({}) {}", specificity, location).dimmed()
+        );
+        for media in &rule.media {
+            println!("    {}", format!("@media {}", media).dimmed());
+        }
+        for property in &rule.properties {
+            let important = if property.important {
+                " !important"
+            } else {
+                ""
+            };
+            let line = format!("{}: {}{};", property.name, property.value, important);
+            if property.overridden {
+                println!(
+                    "    {} {}",
+                    line.strikethrough().dimmed(),
+                    "(overridden)".dimmed()
+                );
+            } else {
+                println!("    {}", line);
+            }
+        }
+    }
+}
+
+/// Box sizes plus the thickness of each edge (top right bottom left)
+fn print_box_model(boxes: &crate::cdp::ElementBoxes, formatter: &Formatter) {
+    let edges = |outer: &crate::cdp::BoxRect, inner: &crate::cdp::BoxRect| {
+        format!(
+            "{} {} {} {}",
+            inner.y - outer.y,
+            (outer.x + outer.width) - (inner.x + inner.width),
+            (outer.y + outer.height) - (inner.y + inner.height),
+            inner.x - outer.x
+        )
+    };
+    let size = |rect: &crate::cdp::BoxRect| {
+        format!("{}x{} at {},{}", rect.width, rect.height, rect.x, rect.y)
+    };
+
+    formatter.header("Box Model");
+    formatter.kv("Content", &size(&boxes.content));
+    formatter.kv("Padding", &edges(&boxes.padding, &boxes.content));
+    formatter.kv("Border", &edges(&boxes.border, &boxes.padding));
+    formatter.kv("Margin", &edges(&boxes.margin, &boxes.border));
+    formatter.kv("Border box", &size(&boxes.border));
+}
+
+/// Run a script in the current page and optionally keep it for every later page load
+async fn debug_inject(
     cdp: &CdpConnection,
-    follow: bool,
-    filter: Option<&str>,
+    name: &str,
+    source: &str,
+    persist: bool,
     formatter: &Formatter,
 ) -> Result<()> {
-    // Use CDP to capture console messages, exceptions, and log entries
-    // Timeout determines how long to listen for events
-    let timeout_ms = if follow { 5000 } else { 500 };
-
-    let entries = cdp.capture_console_messages(timeout_ms).await?;
-
-    // Apply filter if specified
-    let filtered: Vec<_> = if let Some(f) = filter {
-        entries
-            .iter()
-            .filter(|e| e.text.contains(f) || e.source.contains(f))
-            .collect()
+    // The script's completion value isn't interesting and may not serialize
+    cdp.evaluate(&format!("{}\n;undefined", source)).await?;
+
+    let id = if persist {
+        let path = InitScripts::path();
+        let mut scripts = InitScripts::load(&path)?;
+        let id = scripts.add(name, source);
+        scripts.save(&path)?;
+        Some(id)
     } else {
-        entries.iter().collect()
+        None
     };
 
-    // Convert ConsoleEntry to ConsoleMessage for output
-    let messages: Vec<ConsoleMessage> = filtered
-        .iter()
-        .map(|e| ConsoleMessage {
-            level: e.level.clone(),
-            text: e.text.clone(),
-            url: e.url.clone(),
-            line: e.line,
-        })
-        .collect();
+    if formatter.is_json() {
+        formatter.output_json(&serde_json::json!({ "script": name, "persisted_id": id }));
+    } else {
+        formatter.success(&format!("Ran {} in the current page", name));
+        if let Some(id) = id {
+            formatter.kv(
+                "Persisted",
+                &format!("#{} (runs before page scripts on every load)", id),
+            );
+            formatter.hint("'domguard debug inject list' shows injected scripts; 'inject remove <ID>' stops one");
+        }
+    }
+    Ok(())
+}
 
+/// List the persisted init scripts
+pub fn list_init_scripts(formatter: &Formatter) -> Result<()> {
+    let scripts = InitScripts::load(&InitScripts::path())?;
     if formatter.is_json() {
-        formatter.output_json(&messages);
+        formatter.output_json(&scripts.scripts);
+        return Ok(());
+    }
+    if scripts.scripts.is_empty() {
+        println!("No injected scripts");
+        return Ok(());
+    }
+    formatter.header("Injected Scripts");
+    for script in &scripts.scripts {
+        let lines = script.source.lines().count();
+        formatter.kv(
+            &format!("#{}", script.id),
+            &format!(
+                "{} ({} line{})",
+                script.name,
+                lines,
+                if lines == 1 { "" } else { "s" }
+            ),
+        );
+    }
+    Ok(())
+}
+
+/// Stop injecting one persisted script, or all of them
+pub fn remove_init_scripts(id: Option<u32>, formatter: &Formatter) -> Result<()> {
+    let path = InitScripts::path();
+    let mut scripts = InitScripts::load(&path)?;
+    let removed = scripts.remove(id);
+    if let (Some(id), 0) = (id, removed) {
+        anyhow::bail!(
+            "No injected script #{} (see 'domguard debug inject list')",
+            id
+        );
+    }
+    scripts.save(&path)?;
+
+    if formatter.is_json() {
+        formatter.output_json(&serde_json::json!({ "removed": removed }));
     } else {
-        formatter.header("Console Messages (CDP)");
+        formatter.success(&format!("Removed {} injected script(s)", removed));
+        formatter.hint("Pages already loaded keep what the scripts did until reloaded");
+    }
+    Ok(())
+}
 
-        if messages.is_empty() {
-            if follow {
-                println!("  No new messages captured in {}ms", timeout_ms);
+/// A rule that forces `declarations` onto `selector`
+fn css_override_rule(selector: &str, declarations: &str) -> String {
+    let body = declarations
+        .split(';')
+        .map(str::trim)
+        .filter(|declaration| !declaration.is_empty())
+        .map(|declaration| {
+            if declaration.to_lowercase().ends_with("!important") {
+                format!("{};", declaration)
             } else {
-                println!("  No messages. Use --follow to wait for new events.");
-            }
-            formatter.hint("Refresh page with 'domguard interact refresh' then run console again.");
-        } else {
-            for msg in &messages {
-                // Use ConsoleMessage's Display implementation for colored output
-                println!("  {}", msg);
+                format!("{} !important;", declaration)
             }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("{} {{ {} }}", selector.trim(), body)
+}
 
-            println!("\n  Total: {} message(s)", messages.len());
-        }
+/// Script that adds (`Some`), replaces or removes (`None`) the injected sheet `id`
+///
+/// Sheets live in a page-side registry. A MutationObserver and the history
+/// API put them back when a client-side route re-renders `<head>`. With no
+/// id, everything is removed and the hooks go idle. Returns the sheet ids
+/// still injected.
+fn css_script(id: Option<&str>, css: Option<&str>) -> String {
+    format!(
+        r"(function() {{
+            const id = {}, css = {};
+            const state = globalThis[Symbol.for('domguard.css')] ??= (() => {{
+                const s = {{ sheets: new Map() }};
+                s.ensure = () => {{
+                    for (const el of document.querySelectorAll('style[data-domguard-css]')) {{
+                        if (!s.sheets.has(el.dataset.domguardCss)) el.remove();
+                    }}
+                    for (const [name, text] of s.sheets) {{
+                        let el = [...document.querySelectorAll('style[data-domguard-css]')]
+                            .find((e) => e.dataset.domguardCss === name);
+                        if (!el) {{
+                            el = document.createElement('style');
+                            el.dataset.domguardCss = name;
+                            (document.head || document.documentElement).appendChild(el);
+                        }}
+                        if (el.textContent !== text) el.textContent = text;
+                    }}
+                }};
+                let queued = false;
+                const later = () => {{
+                    if (queued || !s.sheets.size) return;
+                    queued = true;
+                    queueMicrotask(() => {{ queued = false; s.ensure(); }});
+                }};
+                new MutationObserver(later).observe(document, {{ childList: true, subtree: true }});
+                for (const method of ['pushState', 'replaceState']) {{
+                    const original = history[method];
+                    history[method] = function(...args) {{
+                        const result = original.apply(this, args);
+                        later();
+                        return result;
+                    }};
+                }}
+                addEventListener('popstate', later);
+                return s;
+            }})();
+            if (id === null) state.sheets.clear();
+            else if (css === null) state.sheets.delete(id);
+            else state.sheets.set(id, css);
+            state.ensure();
+            return [...state.sheets.keys()];
+        }})()",
+        serde_json::to_string(&id).unwrap_or_default(),
+        serde_json::to_string(&css).unwrap_or_default()
+    )
+}
 
-        if let Some(f) = filter {
-            println!("  Filter: {}", f);
+/// Inject, override or clear page CSS
+async fn debug_css(cdp: &CdpConnection, action: CssCommand, formatter: &Formatter) -> Result<()> {
+    let (script, message) = match &action {
+        CssCommand::Inject { name, css } => (
+            css_script(Some(&format!("file:{}", name)), Some(css)),
+            format!("Injected {}", name),
+        ),
+        CssCommand::Set {
+            selector,
+            declarations,
+        } => {
+            let rule = css_override_rule(selector, declarations);
+            let message = format!("Applied {}", rule);
+            (
+                css_script(Some(&format!("set:{}", selector.trim())), Some(&rule)),
+                message,
+            )
         }
-    }
+        CssCommand::Clear => (css_script(None, None), "Removed injected CSS".to_string()),
+    };
+    let sheets = cdp.evaluate(&script).await?;
 
-    Ok(())
-}
+    if formatter.is_json() {
+        formatter.output_json(&serde_json::json!({
+            "action": match action {
+                CssCommand::Inject { .. } => "inject",
+                CssCommand::Set { .. } => "set",
+                CssCommand::Clear => "clear",
+            },
+            "sheets": sheets
+        }));
+    } else {
+        formatter.success(&message);
+        let count = sheets.as_array().map_or(0, Vec::len);
+        if count > 0 {
+            formatter.kv("Injected", &format!("{} sheet(s)", count));
+            formatter.hint(
+                "Kept across client-side navigation; 'domguard debug css clear' removes them.",
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Stylesheet that collapses animations and transitions to zero length
+const DISABLE_ANIMATIONS_CSS: &str = "*, *::before, *::after { \
+    animation-duration: 0s !important; animation-delay: 0s !important; \
+    animation-iteration-count: 1 !important; transition-duration: 0s !important; \
+    transition-delay: 0s !important; scroll-behavior: auto !important; \
+    caret-color: transparent !important; }";
+
+/// Script that finishes, slows or restores the page's running animations
+///
+/// For `slow`, animations that start later are slowed as they start. Returns
+/// how many animations were running.
+fn animations_script(mode: AnimationMode) -> String {
+    let (name, rate) = match mode {
+        AnimationMode::Disable => ("disable", 1.0),
+        AnimationMode::Slow(factor) => ("slow", 1.0 / factor),
+        AnimationMode::Enable => ("enable", 1.0),
+    };
+    format!(
+        r"(function() {{
+            const mode = '{}', rate = {};
+            const state = globalThis[Symbol.for('domguard.animations')] ??= {{ listener: null }};
+            const events = ['animationstart', 'transitionrun'];
+            if (state.listener) {{
+                for (const type of events) document.removeEventListener(type, state.listener, true);
+                state.listener = null;
+            }}
+            const running = document.getAnimations();
+            for (const animation of running) {{
+                if (mode === 'disable') {{
+                    try {{ animation.finish(); }} catch {{ animation.pause(); }}
+                }} else {{
+                    animation.playbackRate = rate;
+                    if (animation.playState === 'paused') animation.play();
+                }}
+            }}
+            if (mode === 'slow') {{
+                state.listener = (event) => {{
+                    for (const animation of event.target.getAnimations()) animation.playbackRate = rate;
+                }};
+                for (const type of events) document.addEventListener(type, state.listener, true);
+            }}
+            return running.length;
+        }})()",
+        name, rate
+    )
+}
+
+/// Freeze, slow or restore page animations
+async fn debug_animations(
+    cdp: &CdpConnection,
+    mode: AnimationMode,
+    formatter: &Formatter,
+) -> Result<()> {
+    let css = (mode == AnimationMode::Disable).then_some(DISABLE_ANIMATIONS_CSS);
+    cdp.evaluate(&css_script(Some("animations"), css)).await?;
+    let running = cdp.evaluate(&animations_script(mode)).await?;
+    // The page-side rate outlives this connection; only clear DevTools' own
+    if mode == AnimationMode::Enable {
+        cdp.set_animation_playback_rate(1.0).await?;
+    }
+    let running = running.as_u64().unwrap_or(0);
+
+    if formatter.is_json() {
+        formatter.output_json(&serde_json::json!({
+            "mode": match mode {
+                AnimationMode::Disable => "disable".to_string(),
+                AnimationMode::Slow(factor) => format!("slow {}x", factor),
+                AnimationMode::Enable => "enable".to_string(),
+            },
+            "running": running
+        }));
+    } else {
+        formatter.success(&match mode {
+            AnimationMode::Disable => "Animations and transitions disabled".to_string(),
+            AnimationMode::Slow(factor) => format!("Animations slowed {}x", factor),
+            AnimationMode::Enable => "Animations restored".to_string(),
+        });
+        formatter.kv("Running", &running.to_string());
+        if mode != AnimationMode::Enable {
+            formatter.hint("Lasts until a reload; 'domguard debug animations --enable' undoes it");
+        }
+    }
+    Ok(())
+}
+
+/// Console level as reported by CDP, folding `warn` into `warning`
+fn console_level(level: &str) -> String {
+    match level.to_lowercase().as_str() {
+        "warn" => "warning".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Whether a console entry passes the text filter and level list
+fn console_matches(entry: &ConsoleEntry, filter: Option<&str>, levels: &[String]) -> bool {
+    filter.is_none_or(|f| entry.text.contains(f) || entry.source.contains(f))
+        && (levels.is_empty()
+            || levels
+                .iter()
+                .any(|level| console_level(level) == console_level(&entry.level)))
+}
+
+fn console_message(entry: &ConsoleEntry) -> ConsoleMessage {
+    ConsoleMessage {
+        level: entry.level.clone(),
+        text: entry.text.clone(),
+        url: entry.url.clone(),
+        line: entry.line,
+    }
+}
+
+/// Debug console messages using CDP event listeners
+async fn debug_console(
+    cdp: &CdpConnection,
+    filter: Option<&str>,
+    levels: &[String],
+    formatter: &Formatter,
+) -> Result<()> {
+    // Use CDP to capture console messages, exceptions, and log entries
+    // Messages logged before the domains are enabled arrive right away
+    let timeout_ms = 500;
+
+    // With --ndjson, each message is written as it arrives
+    let streaming = formatter.is_ndjson();
+    let entries = cdp
+        .capture_console_messages(Some(timeout_ms), &mut |entry| {
+            if streaming && console_matches(entry, filter, levels) {
+                formatter.stream_json(&console_message(entry));
+            }
+        })
+        .await?;
+    if streaming {
+        return Ok(());
+    }
+
+    // Convert ConsoleEntry to ConsoleMessage for output
+    let messages: Vec<ConsoleMessage> = entries
+        .iter()
+        .filter(|e| console_matches(e, filter, levels))
+        .map(console_message)
+        .collect();
+
+    if formatter.is_json() {
+        formatter.output_json(&messages);
+    } else {
+        formatter.header("Console Messages (CDP)");
+
+        if messages.is_empty() {
+            println!("  No messages. Use --follow to wait for new events.");
+            formatter.hint("Refresh page with 'domguard interact refresh' then run console again.");
+        } else {
+            for msg in &messages {
+                // Use ConsoleMessage's Display implementation for colored output
+                println!("  {}", msg);
+            }
+
+            println!("\n  Total: {} message(s)", messages.len());
+        }
+
+        if let Some(f) = filter {
+            println!("  Filter: {}", f);
+        }
+    }
+
+    Ok(())
+}
+
+/// Stream console messages as they arrive, until `duration_ms` or Ctrl-C
+///
+/// Each entry is written immediately (one JSON line each in JSON mode); the
+/// per-level summary follows when the duration runs out or Ctrl-C stops it.
+async fn follow_console(
+    cdp: &CdpConnection,
+    filter: Option<&str>,
+    duration_ms: Option<u64>,
+    levels: &[String],
+    formatter: &Formatter,
+) -> Result<()> {
+    let json = formatter.is_json();
+    if !json {
+        formatter.header("Console Messages (live)");
+        match duration_ms {
+            Some(ms) => println!("  Listening for {}ms", ms),
+            None => formatter.hint("Press Ctrl-C to stop"),
+        }
+    }
+
+    let started = std::time::Instant::now();
+    let mut counts: std::collections::BTreeMap<String, usize> = std::collections::BTreeMap::new();
+    let capture = cdp.capture_console_messages(duration_ms, &mut |entry| {
+        if !console_matches(entry, filter, levels) {
+            return;
+        }
+        *counts.entry(console_level(&entry.level)).or_default() += 1;
+        let message = console_message(entry);
+        if json {
+            formatter.stream_json(&message);
+        } else {
+            println!("  {}", message);
+        }
+    });
+    let interrupted = tokio::select! {
+        captured = capture => {
+            captured?;
+            false
+        }
+        _ = tokio::signal::ctrl_c() => true,
+    };
+
+    let total: usize = counts.values().sum();
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    if json {
+        formatter.stream_json(&serde_json::json!({
+            "summary": {
+                "total": total,
+                "levels": counts,
+                "duration_ms": elapsed_ms,
+                "interrupted": interrupted,
+            }
+        }));
+    } else {
+        let by_level = counts
+            .iter()
+            .map(|(level, count)| format!("{} {}", count, level))
+            .collect::<Vec<_>>();
+        if by_level.is_empty() {
+            println!("\n  Total: 0 messages in {}ms", elapsed_ms);
+        } else {
+            println!(
+                "\n  Total: {} message(s) in {}ms ({})",
+                total,
+                elapsed_ms,
+                by_level.join(", ")
+            );
+        }
+        if let Some(f) = filter {
+            println!("  Filter: {}", f);
+        }
+    }
+
+    Ok(())
+}
+
+/// Search traffic captured by `debug xhr` for `pattern`
+///
+/// With `since_navigation`, only calls made on the current page count.
+pub async fn grep_traffic(
+    cdp: &CdpConnection,
+    pattern: &str,
+    is_regex: bool,
+    since_navigation: bool,
+    formatter: &Formatter,
+) -> Result<()> {
+    let re = if is_regex {
+        regex::Regex::new(pattern)
+            .map_err(|e| anyhow::anyhow!("Invalid pattern \"{}\": {}", pattern, e))?
+    } else {
+        regex::Regex::new(&regex::escape(pattern))?
+    };
+    let since = if since_navigation {
+        let origin = cdp.evaluate("performance.timeOrigin").await?;
+        let millis = origin.as_f64().unwrap_or_default() as i64;
+        chrono::DateTime::from_timestamp_millis(millis)
+    } else {
+        None
+    };
+    let path = crate::traffic::log_path();
+    let entries = crate::traffic::load(&path)?;
+    let found = crate::traffic::grep(&entries, &re, since);
+
+    if formatter.is_json() {
+        formatter.output_json(&serde_json::json!({
+            "pattern": pattern,
+            "searched": entries.len(),
+            "matches": found
+        }));
+        return Ok(());
+    }
+    if entries.is_empty() {
+        println!("No captured traffic to search");
+        formatter
+            .hint("Capture calls with 'domguard debug xhr --follow' while the page makes them.");
+        return Ok(());
+    }
+    formatter.header(&format!("Traffic matching \"{}\"", pattern));
+    if found.is_empty() {
+        println!("  No matches in {} captured call(s)", entries.len());
+        return Ok(());
+    }
+    for call in &found {
+        let status = call
+            .status
+            .map_or_else(|| "-".to_string(), |s| s.to_string());
+        println!(
+            "\n  {} {} {} ({})",
+            call.method,
+            status,
+            call.url,
+            call.captured_at.format("%H:%M:%S")
+        );
+        for location in &call.matches {
+            println!("    {}: {}", location.part, location.snippet);
+        }
+    }
+    println!(
+        "\n  {} of {} captured call(s) match",
+        found.len(),
+        entries.len()
+    );
+    Ok(())
+}
+
+/// How long `debug xhr` listens without --follow or --duration
+const XHR_SAMPLE_MS: u64 = 5000;
+
+/// Capture fetch/XHR calls with their bodies
+async fn debug_xhr(
+    cdp: &CdpConnection,
+    watch: &XhrWatch,
+    follow: bool,
+    decode_graphql: bool,
+    masker: &Masker,
+    formatter: &Formatter,
+) -> Result<()> {
+    let json = formatter.is_json();
+    if !json {
+        formatter.header(match (decode_graphql, follow) {
+            (true, true) => "GraphQL Operations (live)",
+            (true, false) => "GraphQL Operations",
+            (false, true) => "Fetch/XHR Calls (live)",
+            (false, false) => "Fetch/XHR Calls",
+        });
+        match watch.duration_ms {
+            Some(ms) => println!("  Listening for {}ms", ms),
+            None => formatter.hint("Press Ctrl-C to stop"),
+        }
+    }
+
+    let log = crate::traffic::log_path();
+    let mut log_error = None;
+    let mut calls = Vec::new();
+    let mut operations = Vec::new();
+    cdp.watch_xhr(watch, &mut |mut call| {
+        for value in [&mut call.request_headers, &mut call.response_headers]
+            .into_iter()
+            .chain(
+                [&mut call.request_body, &mut call.response_body]
+                    .into_iter()
+                    .flatten(),
+            )
+        {
+            masker.mask_value(value);
+        }
+        // Kept for 'debug network grep'
+        if let Err(e) = crate::traffic::append(&log, &call) {
+            log_error = log_error.take().or(Some(e));
+        }
+        if decode_graphql {
+            let decoded = crate::graphql::decode(&call);
+            if !follow {
+                operations.extend(decoded);
+            } else if json {
+                decoded.iter().for_each(|op| formatter.stream_json(op));
+            } else {
+                decoded.iter().for_each(print_graphql_operation);
+            }
+        } else if !follow {
+            calls.push(call);
+        } else if json {
+            formatter.stream_json(&call);
+        } else {
+            print_xhr_call(&call);
+        }
+    })
+    .await?;
+    if let Some(e) = log_error {
+        formatter.warning(&format!(
+            "Could not log traffic to {}: {}",
+            log.display(),
+            e
+        ));
+    }
+
+    if follow {
+        return Ok(());
+    }
+    let hint = "Trigger the calls (e.g. with an interact action) while this runs, or use --follow.";
+    if decode_graphql {
+        if json {
+            formatter.output_json(&operations);
+        } else if operations.is_empty() {
+            println!("  No GraphQL operations captured.");
+            formatter.hint(hint);
+        } else {
+            operations.iter().for_each(print_graphql_operation);
+        }
+    } else if json {
+        formatter.output_json(&calls);
+    } else if calls.is_empty() {
+        println!("  No fetch/XHR calls captured.");
+        formatter.hint(hint);
+    } else {
+        calls.iter().for_each(print_xhr_call);
+    }
+
+    Ok(())
+}
+
+/// One line per operation, then its variables, returned fields and errors
+fn print_graphql_operation(op: &crate::graphql::GraphqlOperation) {
+    use colored::Colorize;
+
+    let status = op.status.map_or_else(|| "-".to_string(), |s| s.to_string());
+    println!(
+        "\n  {} {} {} ({}ms) {}",
+        op.kind,
+        op.name.as_deref().unwrap_or("(anonymous)").bold(),
+        status,
+        op.duration_ms,
+        op.url.dimmed()
+    );
+    if !op.variables.is_null() {
+        println!("    variables: {}", op.variables);
+    }
+    if !op.data.is_empty() {
+        println!("    data: {}", op.data.join(", "));
+    }
+    for error in &op.errors {
+        println!("    {} {}", "error:".red(), error);
+    }
+}
+
+fn print_xhr_call(call: &XhrCall) {
+    let status = match (&call.error, call.status) {
+        (Some(error), _) => error.clone(),
+        (None, Some(status)) => status.to_string(),
+        (None, None) => "-".to_string(),
+    };
+    let kind = match &call.event {
+        Some(event) => format!("sse: {}", event),
+        None => call.kind.clone(),
+    };
+    println!(
+        "\n  {} {} {} ({}, {}ms)",
+        call.method, status, call.url, kind, call.duration_ms
+    );
+    let body = |arrow: &str, value: &serde_json::Value| {
+        let text = match value {
+            serde_json::Value::String(text) => text.clone(),
+            value => serde_json::to_string_pretty(value).unwrap_or_default(),
+        };
+        let text = text.replace('\n', "\n      ");
+        println!("    {} {}", arrow, text);
+    };
+    if let Some(request) = &call.request_body {
+        body("→", request);
+    }
+    if let Some(response) = &call.response_body {
+        body("←", response);
+    } else if let Some(bytes) = call.response_bytes {
+        println!("    ← {} bytes of binary data", bytes);
+    }
+    if call.truncated {
+        println!("    (body truncated; raise --max-body to see more)");
+    }
+}
+
+/// Stream JavaScript exceptions, saving captures taken while paused
+async fn debug_exceptions(
+    cdp: &CdpConnection,
+    watch: &ExceptionWatch,
+    output: Option<std::path::PathBuf>,
+    formatter: &Formatter,
+) -> Result<()> {
+    let json = formatter.is_json();
+    let dir = output.unwrap_or_else(|| {
+        Config::find_domguard_dir()
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_default())
+            .join("exceptions")
+    });
+    if watch.capture {
+        std::fs::create_dir_all(&dir)?;
+    }
+    if !json {
+        formatter.header("JavaScript Exceptions (live)");
+        if watch.pause {
+            println!(
+                "  Pausing on {} exceptions",
+                if watch.caught { "all" } else { "uncaught" }
+            );
+        }
+        match watch.duration_ms {
+            Some(ms) => println!("  Listening for {}ms", ms),
+            None => formatter.hint("Press Ctrl-C to stop"),
+        }
+    }
+
+    let started = safe_timestamp();
+    let mut count = 0usize;
+    let mut write_error = None;
+    cdp.watch_exceptions(watch, &mut |report| {
+        count += 1;
+        let saved = save_exception_capture(&dir, started, count, &report);
+        let (screenshot, dom) = match saved {
+            Ok(paths) => paths,
+            Err(e) => {
+                write_error.get_or_insert(e);
+                (None, None)
+            }
+        };
+        if json {
+            let mut value = serde_json::to_value(&report).unwrap_or_default();
+            if let Some(object) = value.as_object_mut() {
+                if let Some(path) = screenshot {
+                    object.insert("screenshot".to_string(), path.display().to_string().into());
+                }
+                if let Some(path) = dom {
+                    object.insert("dom".to_string(), path.display().to_string().into());
+                }
+            }
+            formatter.stream_json(&value);
+        } else {
+            print_exception(&report);
+            for path in screenshot.iter().chain(dom.iter()) {
+                formatter.kv("    Saved", &path.display().to_string());
+            }
+        }
+    })
+    .await?;
+    if let Some(e) = write_error {
+        return Err(e.context("Failed to save exception capture"));
+    }
+
+    if json {
+        formatter.stream_json(&serde_json::json!({ "summary": { "total": count } }));
+    } else {
+        println!("\n  Total: {} exception(s)", count);
+    }
+    Ok(())
+}
+
+/// Write the screenshot and DOM taken at an exception, returning their paths
+fn save_exception_capture(
+    dir: &std::path::Path,
+    started: u64,
+    index: usize,
+    report: &ExceptionReport,
+) -> Result<(Option<std::path::PathBuf>, Option<std::path::PathBuf>)> {
+    let stem = format!("exception_{}_{}", started, index);
+    let screenshot = match &report.screenshot {
+        Some(png) => {
+            let path = dir.join(format!("{}.png", stem));
+            std::fs::write(&path, png)?;
+            Some(path)
+        }
+        None => None,
+    };
+    let dom = match &report.dom {
+        Some(html) => {
+            let path = dir.join(format!("{}.html", stem));
+            std::fs::write(&path, html)?;
+            Some(path)
+        }
+        None => None,
+    };
+    Ok((screenshot, dom))
+}
+
+/// Human-readable exception with its stack, original locations first
+fn print_exception(report: &ExceptionReport) {
+    use colored::Colorize;
+
+    let first_line = report.message.lines().next().unwrap_or_default();
+    println!("\n  {} {}", "[exception]".red(), first_line);
+    for frame in &report.stack {
+        let function = if frame.function.is_empty() {
+            "<anonymous>"
+        } else {
+            frame.function.as_str()
+        };
+        match &frame.original {
+            Some(original) => println!(
+                "    at {} ({}:{}:{}) {}",
+                original.name.as_deref().unwrap_or(function),
+                original.source,
+                original.line,
+                original.column,
+                format!("[{}:{}:{}]", frame.url, frame.line, frame.column).dimmed()
+            ),
+            None => println!(
+                "    at {} ({}:{}:{})",
+                function, frame.url, frame.line, frame.column
+            ),
+        }
+    }
+}
 
 /// Debug network requests
 async fn debug_network(
@@ -440,8 +1881,23 @@ async fn debug_network(
 }
 
 /// Debug eval - execute JavaScript
-async fn debug_eval(cdp: &CdpConnection, expression: &str, formatter: &Formatter) -> Result<()> {
+pub async fn debug_eval(
+    cdp: &dyn BrowserBackend,
+    expression: &str,
+    masker: &Masker,
+    formatter: &Formatter,
+) -> Result<()> {
     let result = cdp.evaluate(expression).await?;
+    print_eval_result(result, masker, formatter)
+}
+
+/// Print an evaluation result with sensitive values masked
+fn print_eval_result(
+    mut result: serde_json::Value,
+    masker: &Masker,
+    formatter: &Formatter,
+) -> Result<()> {
+    masker.mask_value(&mut result);
 
     if formatter.is_json() {
         formatter.output_json(&result);
@@ -602,8 +2058,56 @@ async fn debug_aria(
     Ok(())
 }
 
+/// List navigation history entries for the active tab
+async fn debug_history(cdp: &CdpConnection, formatter: &Formatter) -> Result<()> {
+    let entries = cdp.navigation_history().await?;
+
+    if formatter.is_json() {
+        formatter.output_json(&entries);
+    } else {
+        formatter.header("Navigation History");
+        for entry in &entries {
+            let marker = if entry.current { "→" } else { " " };
+            println!("  {} {}. {}", marker, entry.index, entry.title);
+            println!("       {}", entry.url);
+        }
+        let noun = if entries.len() == 1 {
+            "entry"
+        } else {
+            "entries"
+        };
+        println!("\n  Total: {} {}", entries.len(), noun);
+    }
+
+    Ok(())
+}
+
 /// Debug tabs - list, create, switch, close browser tabs
+/// The single tab whose URL contains `pattern`
+fn tab_with_url<'a>(tabs: &'a [TabInfo], pattern: &str) -> Result<&'a TabInfo> {
+    let matches: Vec<&TabInfo> = tabs
+        .iter()
+        .filter(|tab| tab.url.contains(pattern))
+        .collect();
+    match matches.as_slice() {
+        [tab] => Ok(tab),
+        [] => Err(anyhow::anyhow!("No tab URL contains \"{}\"", pattern)),
+        _ => Err(anyhow::anyhow!(
+            "{} tabs match \"{}\"; use a longer pattern or an id:\n{}",
+            matches.len(),
+            pattern,
+            matches
+                .iter()
+                .map(|tab| format!("  {}  {}", tab.id, tab.url))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )),
+    }
+}
+
 async fn debug_tabs(cdp: &CdpConnection, action: TabCommand, formatter: &Formatter) -> Result<()> {
+    use colored::Colorize;
+
     match action {
         TabCommand::List => {
             let tabs = cdp.list_tabs().await?;
@@ -616,27 +2120,44 @@ async fn debug_tabs(cdp: &CdpConnection, action: TabCommand, formatter: &Formatt
                     println!("  (no tabs found)");
                 } else {
                     for tab in &tabs {
-                        println!("  {} - {}", tab.id, tab.title);
+                        let marker = if tab.active {
+                            " (active)".green().to_string()
+                        } else {
+                            String::new()
+                        };
+                        println!("  {} - {}{}", tab.id, tab.title, marker);
                         println!("    {}", tab.url);
                     }
                     println!("\n  Total: {} tab(s)", tabs.len());
                 }
             }
         }
-        TabCommand::New { url } => {
-            let tab_id = cdp.new_tab(url.as_deref()).await?;
+        TabCommand::New {
+            url,
+            incognito,
+            window,
+        } => {
+            let (tab_id, context_id) = cdp.new_tab_in(url.as_deref(), incognito, window).await?;
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
                     "action": "new",
                     "tab_id": tab_id,
-                    "url": url.as_deref().unwrap_or("about:blank")
+                    "url": url.as_deref().unwrap_or("about:blank"),
+                    "context_id": context_id
                 }));
             } else {
                 formatter.success(&format!("Created new tab: {}", tab_id));
                 if let Some(u) = url {
                     println!("  URL: {}", u);
                 }
+                if let Some(context_id) = context_id {
+                    println!("  Context: {}", context_id);
+                    formatter.hint(&format!(
+                        "Run 'domguard debug contexts dispose {}' to discard it",
+                        context_id
+                    ));
+                }
             }
         }
         TabCommand::Switch { id } => {
@@ -651,6 +2172,35 @@ async fn debug_tabs(cdp: &CdpConnection, action: TabCommand, formatter: &Formatt
                 formatter.success(&format!("Switched to tab: {}", id));
             }
         }
+        TabCommand::SwitchUrl { pattern } => {
+            let tabs = cdp.list_tabs().await?;
+            let tab = tab_with_url(&tabs, &pattern)?;
+            cdp.switch_tab(&tab.id).await?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "action": "switch",
+                    "tab_id": tab.id,
+                    "url": tab.url
+                }));
+            } else {
+                formatter.success(&format!("Switched to tab: {}", tab.id));
+                println!("  URL: {}", tab.url);
+            }
+        }
+        TabCommand::Duplicate { id } => {
+            let tab_id = cdp.duplicate_tab(&id).await?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "action": "duplicate",
+                    "source_tab_id": id,
+                    "tab_id": tab_id
+                }));
+            } else {
+                formatter.success(&format!("Duplicated tab {} as {}", id, tab_id));
+            }
+        }
         TabCommand::Close { id } => {
             cdp.close_tab(&id).await?;
 
@@ -668,13 +2218,66 @@ async fn debug_tabs(cdp: &CdpConnection, action: TabCommand, formatter: &Formatt
     Ok(())
 }
 
-// ============================================================================
-// Chrome DevTools MCP features
-// ============================================================================
-
-/// Debug performance metrics (Core Web Vitals and runtime metrics)
-async fn debug_performance(cdp: &CdpConnection, formatter: &Formatter) -> Result<()> {
-    let metrics = cdp.get_performance_metrics().await?;
+/// List or dispose browser contexts
+async fn debug_contexts(
+    cdp: &CdpConnection,
+    action: ContextCommand,
+    formatter: &Formatter,
+) -> Result<()> {
+    match action {
+        ContextCommand::List => {
+            let contexts = cdp.list_browser_contexts().await?;
+
+            if formatter.is_json() {
+                formatter.output_json(&contexts);
+            } else {
+                formatter.header("Browser Contexts");
+                if contexts.is_empty() {
+                    println!("  (only the default context)");
+                }
+                for context in &contexts {
+                    println!("  {} ({} tab(s))", context.id, context.tabs.len());
+                    for tab in &context.tabs {
+                        println!("    {}  {}", tab.id, tab.url);
+                    }
+                }
+            }
+        }
+        ContextCommand::Dispose { id } => {
+            let ids = match id {
+                Some(id) => vec![id],
+                None => cdp
+                    .list_browser_contexts()
+                    .await?
+                    .into_iter()
+                    .map(|context| context.id)
+                    .collect(),
+            };
+            for id in &ids {
+                cdp.dispose_browser_context(id).await?;
+            }
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "action": "dispose",
+                    "context_ids": ids
+                }));
+            } else {
+                formatter.success(&format!("Disposed {} browser context(s)", ids.len()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Chrome DevTools MCP features
+// ============================================================================
+
+/// Debug performance metrics (Core Web Vitals and runtime metrics)
+async fn debug_performance(cdp: &CdpConnection, formatter: &Formatter) -> Result<()> {
+    let metrics = cdp.get_performance_metrics().await?;
 
     if formatter.is_json() {
         formatter.output_json(&metrics);
@@ -730,6 +2333,271 @@ async fn debug_performance(cdp: &CdpConnection, formatter: &Formatter) -> Result
     Ok(())
 }
 
+/// Check the page against a performance budget
+///
+/// Fails with [`BudgetFailed`](crate::perf::BudgetFailed) (exit status 1)
+/// when a limit is exceeded, so CI can gate on it
+async fn debug_perf_assert(
+    cdp: &CdpConnection,
+    budget: &PerfBudget,
+    formatter: &Formatter,
+) -> Result<()> {
+    use colored::Colorize;
+    let report = crate::perf::assert_budget(cdp, budget).await?;
+
+    if formatter.is_json() {
+        formatter.output_json(&report);
+    } else {
+        formatter.header(if report.passed {
+            "Performance Budget: passed"
+        } else {
+            "Performance Budget: failed"
+        });
+        for check in &report.checks {
+            let mark = if check.passed {
+                "✓".green()
+            } else {
+                "✗".red()
+            };
+            println!(
+                "  {} {}: {} (limit {})",
+                mark, check.metric, check.actual, check.limit
+            );
+        }
+    }
+
+    if !report.passed {
+        return Err(crate::perf::BudgetFailed { report }.into());
+    }
+    Ok(())
+}
+
+/// Take a heap snapshot, save its summary and show the largest constructors,
+/// or with `compare`, what grew since an earlier snapshot
+async fn debug_heap(
+    cdp: &CdpConnection,
+    compare: Option<&str>,
+    gc: bool,
+    top: usize,
+    formatter: &Formatter,
+) -> Result<()> {
+    use crate::heap::HeapSummary;
+
+    let dir = HeapSummary::dir();
+    // Fail on a wrong id before spending time on the snapshot
+    let baseline = compare.map(|id| HeapSummary::load(&dir, id)).transpose()?;
+    let snapshot = cdp.heap_snapshot(gc).await?;
+    let summary = HeapSummary::from_snapshot(&snapshot, cdp.current_url().await.ok())?;
+    let path = summary.save(&dir)?;
+
+    if let Some(baseline) = baseline {
+        let mut comparison = crate::heap::compare(&baseline, &summary);
+        comparison.growing.truncate(top);
+        if formatter.is_json() {
+            formatter.output_json(&comparison);
+            return Ok(());
+        }
+        formatter.header(&format!(
+            "Heap Growth: {} -> {}",
+            comparison.baseline, comparison.current
+        ));
+        formatter.kv(
+            "Total",
+            &format!(
+                "{} ({:+} objects)",
+                signed_bytes(comparison.total_delta),
+                comparison.objects_delta
+            ),
+        );
+        if comparison.growing.is_empty() {
+            formatter.success("No constructor grew");
+        }
+        for growth in &comparison.growing {
+            formatter.item(&format!(
+                "{}: {:+} objects ({} total), retained {}",
+                growth.name,
+                growth.count_delta,
+                growth.count,
+                signed_bytes(growth.retained_delta)
+            ));
+        }
+        formatter.hint(&format!("Saved as {}", summary.id));
+        return Ok(());
+    }
+
+    if formatter.is_json() {
+        let mut output = serde_json::to_value(&summary)?;
+        output["path"] = path.display().to_string().into();
+        if let Some(classes) = output["classes"].as_array_mut() {
+            classes.truncate(top);
+        }
+        formatter.output_json(&output);
+        return Ok(());
+    }
+    formatter.header(&format!("Heap Snapshot {}", summary.id));
+    formatter.kv(
+        "Reachable",
+        &format!(
+            "{:.1} MB in {} objects",
+            summary.total_bytes as f64 / 1_048_576.0,
+            summary.objects
+        ),
+    );
+    for class in summary.classes.iter().take(top) {
+        formatter.item(&format!(
+            "{}: {} objects, {:.1} KB self, {:.1} KB retained",
+            class.name,
+            class.count,
+            class.self_size as f64 / 1024.0,
+            class.retained_size as f64 / 1024.0
+        ));
+    }
+    formatter.hint(&format!(
+        "Repeat the flow, then: domguard debug heap --compare {}",
+        summary.id
+    ));
+    Ok(())
+}
+
+/// "+1.5 KB", "-300 B"
+fn signed_bytes(delta: i64) -> String {
+    let size = delta.unsigned_abs() as f64;
+    let sign = if delta < 0 { '-' } else { '+' };
+    if size >= 1_048_576.0 {
+        format!("{}{:.1} MB", sign, size / 1_048_576.0)
+    } else if size >= 1024.0 {
+        format!("{}{:.1} KB", sign, size / 1024.0)
+    } else {
+        format!("{}{} B", sign, size)
+    }
+}
+
+/// Report how ready the page is for automation
+async fn debug_readiness(cdp: &CdpConnection, formatter: &Formatter) -> Result<()> {
+    let readiness = cdp.page_readiness().await?;
+
+    if formatter.is_json() {
+        formatter.output_json(&readiness);
+        return Ok(());
+    }
+
+    formatter.header(&format!(
+        "Page Readiness: {}/100{}",
+        readiness.score,
+        if readiness.ready { " (ready)" } else { "" }
+    ));
+    formatter.kv("Document", &readiness.ready_state);
+    formatter.kv(
+        "Pending requests",
+        &format!(
+            "{} (tracked for {}ms)",
+            readiness.pending_requests, readiness.tracking_ms
+        ),
+    );
+    let hydration = match (&readiness.framework, readiness.hydrated) {
+        (Some(framework), Some(true)) => format!("{} (hydrated)", framework),
+        (Some(framework), _) => format!("{} (not hydrated yet)", framework),
+        (None, _) => "no framework detected".to_string(),
+    };
+    formatter.kv("Framework", &hydration);
+    let routes = match readiness.ms_since_route_change {
+        Some(ms) => format!("{} (last {}ms ago)", readiness.route_changes, ms),
+        None => "none seen".to_string(),
+    };
+    formatter.kv("Route changes", &routes);
+
+    Ok(())
+}
+
+/// Report the page's frontend framework
+async fn debug_framework(cdp: &CdpConnection, formatter: &Formatter) -> Result<()> {
+    let detected = cdp.detect_framework().await?;
+
+    if formatter.is_json() {
+        formatter.output_json(&serde_json::json!({
+            "framework": detected,
+            "input_events": detected.input_style().map(|f| f.to_string()),
+        }));
+        return Ok(());
+    }
+
+    formatter.header("Framework");
+    let Some(name) = &detected.name else {
+        formatter.kv("Detected", "none");
+        formatter.kv("Input events", "plain input/change");
+        return Ok(());
+    };
+    let version = detected.version.as_deref().unwrap_or("unknown version");
+    formatter.kv("Detected", &format!("{} {}", name, version));
+    if let Some(meta) = &detected.meta {
+        let meta_version = detected
+            .meta_version
+            .as_deref()
+            .unwrap_or("unknown version");
+        formatter.kv("Meta-framework", &format!("{} {}", meta, meta_version));
+    }
+    let events = detected
+        .input_style()
+        .map_or_else(|| "plain input/change".to_string(), |f| f.to_string());
+    formatter.kv("Input events", &events);
+
+    Ok(())
+}
+
+/// Export a canvas bitmap, falling back to a screenshot of its area
+async fn debug_canvas(
+    cdp: &CdpConnection,
+    selector: &str,
+    format: ImageFormat,
+    output: Option<std::path::PathBuf>,
+    formatter: &Formatter,
+) -> Result<()> {
+    let bitmap = cdp.canvas_bitmap(selector, format).await?;
+    let extension = match format {
+        ImageFormat::Jpeg => "jpg".to_string(),
+        other => other.to_string(),
+    };
+    let output_path = output.unwrap_or_else(|| {
+        std::path::PathBuf::from(format!("canvas_{}.{}", safe_timestamp(), extension))
+    });
+
+    let (data, source) = match bitmap.data {
+        Some(data) => (data, "bitmap"),
+        None => {
+            let data = cdp
+                .capture(&CaptureOptions {
+                    format,
+                    quality: None,
+                    full_page: false,
+                    clip: Some(bitmap.clip),
+                })
+                .await?;
+            (data, "screenshot")
+        }
+    };
+    std::fs::write(&output_path, &data)?;
+
+    if formatter.is_json() {
+        formatter.output_json(&serde_json::json!({
+            "action": "canvas",
+            "output": output_path.display().to_string(),
+            "width": bitmap.width,
+            "height": bitmap.height,
+            "source": source,
+            "reason": bitmap.reason,
+        }));
+    } else {
+        formatter.success(&format!("Canvas saved: {}", output_path.display()));
+        formatter.kv("Size", &format!("{}x{}", bitmap.width, bitmap.height));
+        match &bitmap.reason {
+            Some(reason) => formatter.kv("Source", &format!("screenshot ({})", reason)),
+            None => formatter.kv("Source", "canvas bitmap"),
+        }
+    }
+
+    Ok(())
+}
+
 /// Debug snapshot - export full DOM as HTML
 async fn debug_snapshot(
     cdp: &CdpConnection,
@@ -737,21 +2605,37 @@ async fn debug_snapshot(
     formatter: &Formatter,
 ) -> Result<()> {
     let html = cdp.get_full_dom_snapshot().await?;
+    let id = safe_timestamp().to_string();
 
-    let output_path = output
-        .unwrap_or_else(|| std::path::PathBuf::from(format!("snapshot_{}.html", safe_timestamp())));
+    let output_path =
+        output.unwrap_or_else(|| std::path::PathBuf::from(format!("snapshot_{}.html", id)));
 
     std::fs::write(&output_path, &html)?;
 
+    // Keep a copy in the project so `dom-diff --baseline-id` can find it later
+    let baseline_id = match Config::find_domguard_dir() {
+        Some(dir) => {
+            let snapshots = dir.join("snapshots");
+            std::fs::create_dir_all(&snapshots)?;
+            std::fs::write(snapshots.join(format!("{}.html", id)), &html)?;
+            Some(id)
+        }
+        None => None,
+    };
+
     if formatter.is_json() {
         formatter.output_json(&serde_json::json!({
             "action": "snapshot",
             "output": output_path.display().to_string(),
-            "size_bytes": html.len()
+            "size_bytes": html.len(),
+            "baseline_id": baseline_id
         }));
     } else {
         formatter.success(&format!("DOM snapshot saved: {}", output_path.display()));
         formatter.kv("Size", &format!("{} bytes", html.len()));
+        if let Some(id) = &baseline_id {
+            formatter.kv("Baseline id", id);
+        }
     }
 
     Ok(())
@@ -853,9 +2737,57 @@ async fn debug_throttle(
         }
     }
 
+    // Throttling outlives the command; remember it so later runs can say so
+    let url = cdp.current_url().await.ok();
+    let path = PageState::path();
+    let recorded = PageState::load(&path).and_then(|mut state| {
+        state.throttle.apply(&mode, url);
+        state.save(&path)
+    });
+    if let Err(e) = recorded {
+        formatter.warning(&format!("Could not record throttling state: {}", e));
+    }
+
+    Ok(())
+}
+
+/// Show the throttling DOMGuard left applied
+pub fn throttle_status(formatter: &Formatter) -> Result<()> {
+    let throttle = PageState::load(&PageState::path())?.throttle;
+    if formatter.is_json() {
+        formatter.output_json(&serde_json::json!({
+            "active": !throttle.is_off(),
+            "throttle": throttle
+        }));
+        return Ok(());
+    }
+    if throttle.is_off() {
+        println!("No throttling applied");
+        return Ok(());
+    }
+    formatter.header("Throttling");
+    formatter.kv("Applied", &throttle.describe());
+    if let Some(url) = &throttle.url {
+        formatter.kv("Page", url);
+    }
+    if let Some(at) = throttle.updated_at {
+        formatter.kv("Since", &at.format("%Y-%m-%d %H:%M:%S UTC").to_string());
+    }
+    formatter.hint("Turn it off with: domguard debug throttle off");
     Ok(())
 }
 
+/// Turn all throttling off, returning what was recorded as applied
+pub async fn reset_throttling(cdp: &CdpConnection) -> Result<ThrottleState> {
+    cdp.disable_cpu_throttling().await?;
+    cdp.disable_network_throttling().await?;
+    let path = PageState::path();
+    let mut state = PageState::load(&path)?;
+    let was = std::mem::take(&mut state.throttle);
+    state.save(&path)?;
+    Ok(was)
+}
+
 /// Debug network details - get detailed info including headers and timing
 async fn debug_network_details(
     cdp: &CdpConnection,
@@ -1038,13 +2970,9 @@ async fn debug_clear_highlights(cdp: &CdpConnection, formatter: &Formatter) -> R
 
 /// Detect CAPTCHAs on the current page
 async fn debug_captcha(cdp: &CdpConnection, formatter: &Formatter) -> Result<()> {
-    use crate::captcha::{
-        captcha_detection_script, format_captcha_detection, parse_captcha_detection,
-    };
+    use crate::captcha::{detect_captcha, format_captcha_detection};
 
-    let script = captcha_detection_script();
-    let result = cdp.evaluate(script).await?;
-    let detection = parse_captcha_detection(&result);
+    let detection = detect_captcha(cdp).await?;
 
     if formatter.is_json() {
         formatter.output_json(&detection);
@@ -1055,10 +2983,825 @@ async fn debug_captcha(cdp: &CdpConnection, formatter: &Formatter) -> Result<()>
     Ok(())
 }
 
+/// List overlays and modals covering the viewport, with their dismiss buttons
+async fn debug_overlays(
+    cdp: &CdpConnection,
+    min_coverage: f64,
+    formatter: &Formatter,
+) -> Result<()> {
+    use crate::correction::{overlay_report_script, Overlay};
+
+    let overlays: Vec<Overlay> =
+        serde_json::from_value(cdp.evaluate(&overlay_report_script(min_coverage)).await?)?;
+
+    if formatter.is_json() {
+        formatter.output_json(&serde_json::json!({ "overlays": overlays }));
+        return Ok(());
+    }
+
+    formatter.header(&format!("Overlays ({})", overlays.len()));
+    if overlays.is_empty() {
+        formatter.kv(
+            "None",
+            &format!(
+                "nothing covers {:.0}% of the viewport",
+                min_coverage * 100.0
+            ),
+        );
+        return Ok(());
+    }
+    for overlay in &overlays {
+        let z_index = overlay
+            .z_index
+            .map_or_else(|| "auto".to_string(), |z| z.to_string());
+        let role = overlay
+            .role
+            .as_ref()
+            .map(|role| format!(" [role={}]", role))
+            .unwrap_or_default();
+        println!(
+            "\n  {}{} - {:.0}% of viewport, z-index {}, {}",
+            overlay.selector,
+            role,
+            overlay.coverage * 100.0,
+            z_index,
+            overlay.position
+        );
+        formatter.kv(
+            "Intercepts clicks",
+            if overlay.intercepts_pointer {
+                "yes"
+            } else {
+                "no (pointer-events pass through)"
+            },
+        );
+        if let Some(text) = &overlay.text {
+            formatter.kv("Text", text);
+        }
+        if overlay.dismiss_candidates.is_empty() {
+            formatter.kv("Dismiss", "no close button found");
+        }
+        for candidate in &overlay.dismiss_candidates {
+            let text = candidate
+                .text
+                .as_ref()
+                .map(|text| format!(" \"{}\"", text))
+                .unwrap_or_default();
+            formatter.kv("Dismiss", &format!("{}{}", candidate.selector, text));
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a value for a DOM property: JSON literals (true, 42, null) keep their type,
+/// anything else is a string
+fn parse_property_value(value: &str) -> serde_json::Value {
+    match serde_json::from_str::<serde_json::Value>(value) {
+        Ok(v) if !v.is_object() && !v.is_array() => v,
+        _ => serde_json::Value::String(value.to_string()),
+    }
+}
+
+/// Read or modify element attributes and properties
+async fn debug_attr(cdp: &CdpConnection, action: AttrCommand, formatter: &Formatter) -> Result<()> {
+    match action {
+        AttrCommand::Get { selector, name } => {
+            // JSON string literals are valid JS string literals
+            let sel_js = serde_json::to_string(&selector)?;
+            let name_js = serde_json::to_string(&name)?;
+            let js = format!(
+                r#"
+                (function() {{
+                    const el = document.querySelector({});
+                    if (!el) return null;
+                    const name = {};
+
+                    const plain = v => (v === null || v === undefined || ['string', 'number', 'boolean'].includes(typeof v)) ? v : String(v);
+                    if (name === 'dataset') return {{ dataset: Object.assign({{}}, el.dataset) }};
+                    if (name !== null) {{
+                        return {{
+                            [name]: {{
+                                attribute: el.getAttribute(name),
+                                property: name in el ? plain(el[name]) : null
+                            }}
+                        }};
+                    }}
+
+                    const attributes = {{}};
+                    for (const attr of el.attributes) attributes[attr.name] = attr.value;
+                    const properties = {{}};
+                    for (const prop of ['value', 'checked', 'disabled', 'href', 'src', 'selected', 'hidden', 'readOnly']) {{
+                        if (prop in el) properties[prop] = plain(el[prop]);
+                    }}
+                    return {{
+                        tag: el.tagName.toLowerCase(),
+                        attributes,
+                        properties,
+                        dataset: Object.assign({{}}, el.dataset)
+                    }};
+                }})()
+            "#,
+                sel_js, name_js
+            );
+
+            let result = cdp.evaluate(&js).await?;
+            if result.is_null() {
+                return Err(anyhow::anyhow!(
+                    "No element matches selector \"{}\"",
+                    selector
+                ));
+            }
+
+            if formatter.is_json() {
+                formatter.output_json(&result);
+            } else {
+                formatter.header(&format!("Attributes for \"{}\"", selector));
+                print_attr_value(formatter, "", &result);
+            }
+        }
+
+        AttrCommand::Set {
+            selector,
+            name,
+            value,
+            property,
+        } => {
+            let sel_js = serde_json::to_string(&selector)?;
+            let name_js = serde_json::to_string(&name)?;
+            let value_js = if property {
+                parse_property_value(&value).to_string()
+            } else {
+                serde_json::to_string(&value)?
+            };
+            let js = format!(
+                r#"
+                (function() {{
+                    const el = document.querySelector({});
+                    if (!el) return null;
+                    const name = {}, value = {};
+                    if ({}) {{
+                        const previous = el[name];
+                        el[name] = value;
+                        // Let frameworks see value/checked changes
+                        if (name === 'value' || name === 'checked') {{
+                            el.dispatchEvent(new Event('input', {{ bubbles: true }}));
+                            el.dispatchEvent(new Event('change', {{ bubbles: true }}));
+                        }}
+                        return {{ previous: previous === undefined ? null : previous, current: el[name] }};
+                    }}
+                    const previous = el.getAttribute(name);
+                    el.setAttribute(name, value);
+                    return {{ previous, current: el.getAttribute(name) }};
+                }})()
+            "#,
+                sel_js, name_js, value_js, property
+            );
+
+            let result = cdp.evaluate(&js).await?;
+            if result.is_null() {
+                return Err(anyhow::anyhow!(
+                    "No element matches selector \"{}\"",
+                    selector
+                ));
+            }
+
+            let kind = if property { "property" } else { "attribute" };
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "success": true,
+                    "selector": selector,
+                    "kind": kind,
+                    "name": name,
+                    "previous": result.get("previous"),
+                    "current": result.get("current")
+                }));
+            } else {
+                formatter.success(&format!("Set {} \"{}\" on \"{}\"", kind, name, selector));
+                formatter.kv(
+                    "Previous",
+                    &result
+                        .get("previous")
+                        .map(ToString::to_string)
+                        .unwrap_or_default(),
+                );
+                formatter.kv(
+                    "Current",
+                    &result
+                        .get("current")
+                        .map(ToString::to_string)
+                        .unwrap_or_default(),
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Read text or value of the first (or every) matching element
+async fn debug_text(
+    cdp: &CdpConnection,
+    selector: &str,
+    all: bool,
+    mode: TextMode,
+    formatter: &Formatter,
+) -> Result<()> {
+    let js = format!(
+        r#"
+        (function() {{
+            const els = Array.from(document.querySelectorAll({}));
+            return els.slice(0, {}).map(el => {});
+        }})()
+    "#,
+        serde_json::to_string(selector)?,
+        if all { "els.length" } else { "1" },
+        mode.js_expression()
+    );
+
+    let result = cdp.evaluate(&js).await?;
+    let texts = result.as_array().cloned().unwrap_or_default();
+
+    if texts.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No element matches selector \"{}\"",
+            selector
+        ));
+    }
+    if mode == TextMode::Value && texts.iter().all(serde_json::Value::is_null) {
+        return Err(anyhow::anyhow!(
+            "Element \"{}\" has no value (not a form control)",
+            selector
+        ));
+    }
+
+    if formatter.is_json() {
+        if all {
+            formatter.output_json(&serde_json::json!({
+                "selector": selector,
+                "count": texts.len(),
+                "texts": texts
+            }));
+        } else {
+            formatter.output_json(&serde_json::json!({
+                "selector": selector,
+                "text": texts[0]
+            }));
+        }
+    } else if all {
+        formatter.header(&format!("{} match(es) for \"{}\"", texts.len(), selector));
+        for (i, text) in texts.iter().enumerate() {
+            println!("  {}. {}", i + 1, text.as_str().unwrap_or(""));
+        }
+    } else {
+        println!("{}", texts[0].as_str().unwrap_or(""));
+    }
+
+    Ok(())
+}
+
+/// Count matching elements (or report whether any exist)
+/// Fails with [`NotFound`] (exit status 1) when nothing matches
+async fn debug_count(
+    cdp: &CdpConnection,
+    selector: &str,
+    exists: bool,
+    formatter: &Formatter,
+) -> Result<()> {
+    let js = format!(
+        "document.querySelectorAll({}).length",
+        serde_json::to_string(selector)?
+    );
+    let count = cdp.evaluate(&js).await?.as_u64().unwrap_or(0);
+
+    if formatter.is_json() {
+        if exists {
+            formatter.output_json(&serde_json::json!({
+                "selector": selector,
+                "exists": count > 0
+            }));
+        } else {
+            formatter.output_json(&serde_json::json!({
+                "selector": selector,
+                "count": count
+            }));
+        }
+    } else if exists {
+        println!("{}", count > 0);
+    } else {
+        println!("{}", count);
+    }
+
+    if count == 0 {
+        return Err(NotFound {
+            query: format!("selector \"{}\"", selector),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Nothing on the page matched, after the result was printed
+///
+/// `main` exits with status 1 for it without printing anything more, so
+/// shell scripts can branch on it while `retry` can still retry it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NotFound {
+    /// e.g. `selector "#cart"` or `text "Buy", tag button`
+    pub query: String,
+}
+
+impl std::fmt::Display for NotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No element matches {}", self.query)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// What `debug find` looks for; every given criterion must match
+#[derive(Debug, Clone, Default)]
+pub struct FindQuery {
+    pub text: Option<String>,
+    /// `name` or `name=value`
+    pub attr: Option<String>,
+    pub tag: Option<String>,
+    pub visible_only: bool,
+    pub limit: usize,
+}
+
+impl FindQuery {
+    /// `text "Buy", tag button` for error messages
+    fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(text) = &self.text {
+            parts.push(format!("text \"{}\"", text));
+        }
+        if let Some(attr) = &self.attr {
+            parts.push(format!("attribute {}", attr));
+        }
+        if let Some(tag) = &self.tag {
+            parts.push(format!("tag {}", tag));
+        }
+        if self.visible_only {
+            parts.push("visible".to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+/// An element found by `debug find`
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct FoundElement {
+    /// CSS selector; ` >>> ` steps into a shadow root
+    pub selector: String,
+    pub tag: String,
+    pub text: String,
+    pub visible: bool,
+    /// Viewport CSS pixels
+    pub bounds: FoundBounds,
+}
+
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct FoundBounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Page-side `selectorIn(el, root)`: a short selector unique within `root`
+///
+/// Prefers ids and test attributes, falling back to a `tag:nth-of-type`
+/// path up to the nearest ancestor with a unique id.
+const SELECTOR_JS: &str = r"const unique = (root, sel) => {
+                try { return root.querySelectorAll(sel).length === 1; } catch (e) { return false; }
+            };
+            const selectorIn = (el, root) => {
+                for (const a of ['id', 'data-testid', 'data-test', 'data-cy', 'name']) {
+                    const v = el.getAttribute(a);
+                    if (!v) continue;
+                    const sel = a === 'id' ? '#' + CSS.escape(v)
+                        : el.localName + '[' + a + '=' + JSON.stringify(v) + ']';
+                    if (unique(root, sel)) return sel;
+                }
+                const parts = [];
+                for (let n = el; n && n.nodeType === 1; n = n.parentElement) {
+                    if (n.id && unique(root, '#' + CSS.escape(n.id))) {
+                        parts.unshift('#' + CSS.escape(n.id));
+                        break;
+                    }
+                    let part = n.localName;
+                    const same = n.parentElement
+                        ? [...n.parentElement.children].filter((c) => c.localName === n.localName)
+                        : [];
+                    if (same.length > 1) part += ':nth-of-type(' + (same.indexOf(n) + 1) + ')';
+                    parts.unshift(part);
+                }
+                return parts.join(' > ');
+            };";
+
+/// Script that walks the document and its shadow roots for `query`
+fn find_script(query: &FindQuery) -> String {
+    let (attr_name, attr_value) = match query.attr.as_deref().map(|attr| attr.split_once('=')) {
+        Some(Some((name, value))) => (Some(name.trim()), Some(value)),
+        Some(None) => (query.attr.as_deref().map(str::trim), None),
+        None => (None, None),
+    };
+    let options = serde_json::json!({
+        "text": query
+            .text
+            .as_deref()
+            .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()),
+        "attrName": attr_name,
+        "attrValue": attr_value,
+        "tag": query.tag.as_deref().map(str::to_lowercase),
+        "visibleOnly": query.visible_only,
+        "limit": query.limit,
+    });
+    format!(
+        r"(function() {{
+            const q = {};
+            const norm = (s) => (s || '').replace(/\s+/g, ' ').trim().toLowerCase();
+            const labels = (el) => ['aria-label', 'placeholder', 'alt', 'title', 'value']
+                .map((a) => norm(el.getAttribute(a))).join(' ');
+            const textMatches = (el) => {{
+                if (labels(el).includes(q.text)) return true;
+                // The innermost element holding the text, not all its ancestors
+                return norm(el.textContent).includes(q.text)
+                    && ![...el.children].some((c) => norm(c.textContent).includes(q.text));
+            }};
+            const visible = (el) => {{
+                const r = el.getBoundingClientRect();
+                const s = getComputedStyle(el);
+                return r.width > 0 && r.height > 0 && s.visibility !== 'hidden'
+                    && s.display !== 'none' && parseFloat(s.opacity) > 0;
+            }};
+            {}
+            const selectorFor = (el) => {{
+                const steps = [];
+                for (let n = el; n; ) {{
+                    const root = n.getRootNode();
+                    steps.unshift(selectorIn(n, root));
+                    n = root instanceof ShadowRoot ? root.host : null;
+                }}
+                return steps.join(' >>> ');
+            }};
+            const found = [];
+            const walk = (root) => {{
+                for (const el of root.querySelectorAll('*')) {{
+                    if (found.length >= q.limit) return;
+                    const matches = (!q.tag || el.localName === q.tag)
+                        && (!q.attrName || (el.hasAttribute(q.attrName)
+                            && (q.attrValue === null || el.getAttribute(q.attrName) === q.attrValue)))
+                        && (!q.text || textMatches(el));
+                    if (matches) {{
+                        const shown = visible(el);
+                        if (shown || !q.visibleOnly) {{
+                            const r = el.getBoundingClientRect();
+                            found.push({{
+                                selector: selectorFor(el),
+                                tag: el.localName,
+                                text: norm(el.innerText || el.textContent || labels(el)).slice(0, 80),
+                                visible: shown,
+                                bounds: {{ x: Math.round(r.x), y: Math.round(r.y),
+                                    width: Math.round(r.width), height: Math.round(r.height) }}
+                            }});
+                        }}
+                    }}
+                    if (el.shadowRoot) walk(el.shadowRoot);
+                }}
+            }};
+            walk(document);
+            return found;
+        }})()",
+        options, SELECTOR_JS
+    )
+}
+
+/// Search the DOM (shadow roots included) for elements an agent can target
+/// Fails with [`NotFound`] (exit status 1) when nothing matches
+async fn debug_find(cdp: &CdpConnection, query: &FindQuery, formatter: &Formatter) -> Result<()> {
+    let result = cdp.evaluate(&find_script(query)).await?;
+    let found: Vec<FoundElement> = serde_json::from_value(result)?;
+
+    if formatter.is_json() {
+        formatter.output_json(&found);
+    } else if found.is_empty() {
+        println!("  No matching elements");
+    } else {
+        formatter.header(&format!("Found {} element(s)", found.len()));
+        for (i, element) in found.iter().enumerate() {
+            let b = &element.bounds;
+            let hidden = if element.visible { "" } else { " (hidden)" };
+            println!(
+                "  {}. {}{}\n     \"{}\" at {},{} {}x{}",
+                i + 1,
+                element.selector,
+                hidden,
+                element.text,
+                b.x,
+                b.y,
+                b.width,
+                b.height
+            );
+        }
+        if found.len() == query.limit {
+            formatter.hint("More may match; raise --limit to see them.");
+        }
+    }
+
+    if found.is_empty() {
+        return Err(NotFound {
+            query: query.describe(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// The earlier DOM `debug dom-diff` compares against
+#[derive(Debug, Clone)]
+pub enum DiffBaseline {
+    /// An HTML file, e.g. from `debug snapshot -o`
+    File(std::path::PathBuf),
+    /// An id printed by `debug snapshot`, stored in `.domguard/snapshots`
+    Id(String),
+}
+
+impl DiffBaseline {
+    /// Read the baseline HTML
+    fn load(&self) -> Result<String> {
+        let path = match self {
+            Self::File(path) => path.clone(),
+            Self::Id(id) => {
+                if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                    anyhow::bail!("Invalid baseline id \"{}\"", id);
+                }
+                Config::find_domguard_dir()
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("No .domguard directory; run 'domguard init' first")
+                    })?
+                    .join("snapshots")
+                    .join(format!("{}.html", id))
+            }
+        };
+        std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read baseline {}: {}", path.display(), e))
+    }
+}
+
+/// An element only one side of a DOM diff has
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct DiffElement {
+    pub selector: String,
+    pub tag: String,
+    pub text: String,
+}
+
+/// An attribute that differs between the two DOMs
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct AttributeChange {
+    pub name: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct TextChange {
+    pub before: String,
+    pub after: String,
+}
+
+/// An element present in both DOMs whose attributes or own text changed
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct ChangedElement {
+    pub selector: String,
+    pub tag: String,
+    pub attributes: Vec<AttributeChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<TextChange>,
+}
+
+/// What changed between a baseline DOM and the current one
+///
+/// Added and removed subtrees are reported once, at their root. Selectors
+/// for removed elements refer to the baseline document.
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct DomDiff {
+    pub added: Vec<DiffElement>,
+    pub removed: Vec<DiffElement>,
+    pub changed: Vec<ChangedElement>,
+    /// More differences than were listed
+    pub truncated: bool,
+}
+
+/// Script that parses `before` and diffs it against the live document
+///
+/// Children are matched by tag and id with a longest common subsequence,
+/// so an insertion doesn't make every later sibling look changed.
+fn dom_diff_script(before: &str) -> String {
+    format!(
+        r"(function() {{
+            const before = new DOMParser().parseFromString({}, 'text/html');
+            {}
+            const SKIP = new Set(['script', 'style', 'noscript', 'template']);
+            const LIMIT = 200;
+            const norm = (s) => (s || '').replace(/\s+/g, ' ').trim();
+            const ownText = (el) => norm([...el.childNodes]
+                .filter((n) => n.nodeType === 3).map((n) => n.textContent).join(' '));
+            const key = (el) => el.localName + (el.id ? '#' + el.id : '');
+            const kids = (el) => [...el.children].filter((c) => !SKIP.has(c.localName));
+            const out = {{ added: [], removed: [], changed: [], truncated: false }};
+            const push = (list, item) => {{
+                if (list.length < LIMIT) list.push(item); else out.truncated = true;
+            }};
+            const describe = (el, root) => ({{
+                selector: selectorIn(el, root), tag: el.localName,
+                text: norm(el.textContent).slice(0, 80)
+            }});
+            const compare = (a, b) => {{
+                const attributes = [];
+                const names = new Set([...a.getAttributeNames(), ...b.getAttributeNames()]);
+                for (const name of [...names].sort()) {{
+                    const x = a.getAttribute(name), y = b.getAttribute(name);
+                    if (x !== y) attributes.push({{ name, before: x, after: y }});
+                }}
+                const ta = ownText(a), tb = ownText(b);
+                if (attributes.length || ta !== tb) {{
+                    push(out.changed, {{
+                        selector: selectorIn(b, document), tag: b.localName, attributes,
+                        text: ta !== tb ? {{ before: ta, after: tb }} : null
+                    }});
+                }}
+            }};
+            const match = (xs, ys) => {{
+                const n = xs.length, m = ys.length, pairs = [];
+                if (n * m > 40000) {{
+                    // Too many siblings for the table: pair in order by key
+                    let j = 0;
+                    for (let i = 0; i < n && j < m; i++) {{
+                        const k = ys.slice(j, j + 50).findIndex((y) => key(y) === key(xs[i]));
+                        if (k >= 0) {{ pairs.push([i, j + k]); j += k + 1; }}
+                    }}
+                    return pairs;
+                }}
+                const dp = new Uint16Array((n + 1) * (m + 1));
+                for (let i = n - 1; i >= 0; i--) for (let j = m - 1; j >= 0; j--) {{
+                    dp[i * (m + 1) + j] = key(xs[i]) === key(ys[j])
+                        ? dp[(i + 1) * (m + 1) + j + 1] + 1
+                        : Math.max(dp[(i + 1) * (m + 1) + j], dp[i * (m + 1) + j + 1]);
+                }}
+                for (let i = 0, j = 0; i < n && j < m; ) {{
+                    if (key(xs[i]) === key(ys[j])) {{ pairs.push([i, j]); i++; j++; }}
+                    else if (dp[(i + 1) * (m + 1) + j] >= dp[i * (m + 1) + j + 1]) i++;
+                    else j++;
+                }}
+                return pairs;
+            }};
+            const diff = (a, b) => {{
+                compare(a, b);
+                const xs = kids(a), ys = kids(b);
+                let i = 0, j = 0;
+                for (const [pi, pj] of match(xs, ys)) {{
+                    for (; i < pi; i++) push(out.removed, describe(xs[i], before));
+                    for (; j < pj; j++) push(out.added, describe(ys[j], document));
+                    diff(xs[pi], ys[pj]);
+                    i = pi + 1;
+                    j = pj + 1;
+                }}
+                for (; i < xs.length; i++) push(out.removed, describe(xs[i], before));
+                for (; j < ys.length; j++) push(out.added, describe(ys[j], document));
+            }};
+            diff(before.documentElement, document.documentElement);
+            return out;
+        }})()",
+        serde_json::Value::String(before.to_string()),
+        SELECTOR_JS
+    )
+}
+
+/// Compare the current DOM with an earlier snapshot
+async fn debug_dom_diff(
+    cdp: &CdpConnection,
+    baseline: &DiffBaseline,
+    formatter: &Formatter,
+) -> Result<()> {
+    use colored::Colorize;
+
+    let before = baseline.load()?;
+    let result = cdp.evaluate(&dom_diff_script(&before)).await?;
+    let diff: DomDiff = serde_json::from_value(result)?;
+
+    if formatter.is_json() {
+        formatter.output_json(&diff);
+        return Ok(());
+    }
+
+    formatter.header("DOM Diff");
+    if diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty() {
+        println!("  No changes");
+        return Ok(());
+    }
+    for element in &diff.added {
+        println!(
+            "  {} {}  \"{}\"",
+            "+".green(),
+            element.selector,
+            element.text
+        );
+    }
+    for element in &diff.removed {
+        println!("  {} {}  \"{}\"", "-".red(), element.selector, element.text);
+    }
+    for element in &diff.changed {
+        println!("  {} {}", "~".yellow(), element.selector);
+        for attribute in &element.attributes {
+            println!(
+                "      [{}] {} -> {}",
+                attribute.name,
+                attribute.before.as_deref().unwrap_or("(none)"),
+                attribute.after.as_deref().unwrap_or("(none)")
+            );
+        }
+        if let Some(text) = &element.text {
+            println!("      text: \"{}\" -> \"{}\"", text.before, text.after);
+        }
+    }
+    println!(
+        "\n  {} added, {} removed, {} changed",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len()
+    );
+    if diff.truncated {
+        formatter.hint("Only the first 200 of each kind are listed.");
+    }
+    Ok(())
+}
+
+/// Print nested attribute data as indented key/value lines
+fn print_attr_value(formatter: &Formatter, prefix: &str, value: &serde_json::Value) {
+    if let Some(obj) = value.as_object() {
+        for (key, val) in obj {
+            let key = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            if val.is_object() {
+                print_attr_value(formatter, &key, val);
+            } else {
+                let text = val
+                    .as_str()
+                    .map_or_else(|| val.to_string(), ToString::to_string);
+                formatter.kv(&key, &mask_sensitive(&text));
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_not_found_is_retryable() {
+        let error: anyhow::Error = NotFound {
+            query: "selector \"#cart\"".to_string(),
+        }
+        .into();
+        let details = crate::correction::describe_error(&error, "debug count");
+        assert_eq!(details.code, crate::output::ErrorCode::ElementNotFound);
+        assert!(details.retryable);
+        assert_eq!(details.selector.as_deref(), Some("#cart"));
+
+        let query = FindQuery {
+            text: Some("Buy".to_string()),
+            tag: Some("button".to_string()),
+            ..FindQuery::default()
+        };
+        assert_eq!(query.describe(), "text \"Buy\", tag button");
+    }
+
+    #[test]
+    fn test_parse_property_value() {
+        assert_eq!(parse_property_value("true"), serde_json::json!(true));
+        assert_eq!(parse_property_value("42"), serde_json::json!(42));
+        assert_eq!(parse_property_value("null"), serde_json::Value::Null);
+        assert_eq!(parse_property_value("hello"), serde_json::json!("hello"));
+        // Objects and arrays are kept as strings
+        assert_eq!(parse_property_value("[1,2]"), serde_json::json!("[1,2]"));
+    }
+
+    #[test]
+    fn test_text_mode_expressions() {
+        assert_eq!(TextMode::Content.js_expression(), "el.textContent");
+        assert_eq!(TextMode::Inner.js_expression(), "el.innerText");
+        assert!(TextMode::Value.js_expression().contains("el.value"));
+        assert!(TextMode::Trimmed.js_expression().ends_with(".trim()"));
+    }
+
     #[test]
     fn test_safe_timestamp() {
         let ts = safe_timestamp();
@@ -1071,6 +3814,8 @@ mod tests {
         let list = TabCommand::List;
         let new = TabCommand::New {
             url: Some("https://example.com".to_string()),
+            incognito: false,
+            window: false,
         };
         let switch = TabCommand::Switch {
             id: "abc123".to_string(),
@@ -1086,6 +3831,27 @@ mod tests {
         assert!(format!("{:?}", close).contains("def456"));
     }
 
+    #[test]
+    fn test_tab_with_url() {
+        let tab = |id: &str, url: &str| TabInfo {
+            id: id.to_string(),
+            url: url.to_string(),
+            title: String::new(),
+            active: false,
+        };
+        let tabs = [
+            tab("A1", "https://shop.test/cart"),
+            tab("B2", "https://shop.test/checkout"),
+        ];
+        assert_eq!(tab_with_url(&tabs, "checkout").unwrap().id, "B2");
+        assert!(tab_with_url(&tabs, "admin")
+            .unwrap_err()
+            .to_string()
+            .contains("No tab URL"));
+        let err = tab_with_url(&tabs, "shop.test").unwrap_err().to_string();
+        assert!(err.starts_with("2 tabs match") && err.contains("A1"));
+    }
+
     #[test]
     fn test_throttle_mode_variants() {
         let slow_3g = ThrottleMode::NetworkSlow3g;
@@ -1100,6 +3866,161 @@ mod tests {
         assert!(format!("{:?}", off).contains("Off"));
     }
 
+    #[test]
+    fn test_throttle_state() {
+        let mut throttle = ThrottleState::default();
+        throttle.apply(
+            &ThrottleMode::Cpu { rate: 4.0 },
+            Some("https://a.test/".into()),
+        );
+        throttle.apply(&ThrottleMode::Network3g, Some("https://a.test/".into()));
+        assert_eq!(
+            throttle.describe(),
+            "CPU 4x slowdown, network 3g (1600 Kbps down, 750 Kbps up, 300ms latency)"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        assert!(PageState::load(&path).unwrap().throttle.is_off());
+        let state = PageState { throttle };
+        state.save(&path).unwrap();
+        assert_eq!(PageState::load(&path).unwrap(), state);
+
+        let mut throttle = state.throttle;
+        throttle.apply(&ThrottleMode::Cpu { rate: 1.0 }, None);
+        assert_eq!(throttle.cpu_rate, None);
+        assert!(!throttle.is_off());
+        throttle.apply(&ThrottleMode::Off, None);
+        assert_eq!(throttle, ThrottleState::default());
+    }
+
+    #[test]
+    fn test_dom_diff_baseline() {
+        assert!(DiffBaseline::Id("../../etc/passwd".to_string())
+            .load()
+            .is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("before.html");
+        std::fs::write(&path, "<html><body><p id=\"a\">Hi</p></body></html>").unwrap();
+        let html = DiffBaseline::File(path).load().unwrap();
+        // The baseline is embedded as a JS string literal
+        assert!(dom_diff_script(&html).contains(
+            r#"parseFromString("<html><body><p id=\"a\">Hi</p></body></html>", 'text/html')"#
+        ));
+    }
+
+    #[test]
+    fn test_init_scripts_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("inject.toml");
+        let mut scripts = InitScripts::load(&path).unwrap();
+        assert_eq!(scripts.add("a.js", "window.a = 1;"), 1);
+        assert_eq!(scripts.add("b.js", "window.b = 1;"), 2);
+        assert_eq!(scripts.add("a.js", "window.a = 2;"), 1);
+        scripts.save(&path).unwrap();
+
+        let mut loaded = InitScripts::load(&path).unwrap();
+        assert_eq!(loaded, scripts);
+        assert_eq!(loaded.scripts[0].source, "window.a = 2;");
+        assert_eq!(loaded.remove(Some(3)), 0);
+        assert_eq!(loaded.remove(Some(1)), 1);
+        assert_eq!(loaded.add("c.js", ""), 3);
+        assert_eq!(loaded.remove(None), 2);
+        loaded.save(&path).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_animations_script() {
+        assert!(animations_script(AnimationMode::Slow(10.0))
+            .contains("const mode = 'slow', rate = 0.1;"));
+        assert!(
+            animations_script(AnimationMode::Disable).contains("const mode = 'disable', rate = 1;")
+        );
+        assert!(DISABLE_ANIMATIONS_CSS.contains("transition-duration: 0s !important"));
+    }
+
+    #[test]
+    fn test_css_override_rule() {
+        assert_eq!(
+            css_override_rule(" h1 ", "color: red; margin: 0 !IMPORTANT;;"),
+            "h1 { color: red !important; margin: 0 !IMPORTANT; }"
+        );
+        let script = css_script(Some("set:h1"), Some("h1 { color: red !important; }"));
+        assert!(script.contains(r#"const id = "set:h1", css = "h1 { color: red !important; }";"#));
+        assert!(css_script(None, None).contains("const id = null, css = null;"));
+    }
+
+    #[test]
+    fn test_find_script_options() {
+        let query = FindQuery {
+            text: Some("Checkout".to_string()),
+            attr: Some("data-testid=cart-button".to_string()),
+            tag: None,
+            visible_only: true,
+            limit: 5,
+        };
+        let script = find_script(&query);
+        assert!(script.contains(r#""attrName":"data-testid""#));
+        assert!(script.contains(r#""attrValue":"cart-button""#));
+        assert!(script.contains(r#""text":"checkout""#));
+        assert!(script.contains(r#""visibleOnly":true"#));
+
+        let query = FindQuery {
+            attr: Some("aria-expanded".to_string()),
+            ..FindQuery::default()
+        };
+        let script = find_script(&query);
+        assert!(script.contains(r#""attrName":"aria-expanded","attrValue":null"#));
+    }
+
+    #[test]
+    fn test_eval_source() {
+        assert_eq!(eval_source("1 + 1", &[]), "1 + 1");
+        let args = vec![
+            ("user".to_string(), "alice".to_string()),
+            ("quote".to_string(), "\"hi\"".to_string()),
+        ];
+        assert_eq!(
+            eval_source("params.user // trailing comment", &args),
+            "{\nconst params = Object.freeze({\"quote\":\"\\\"hi\\\"\",\"user\":\"alice\"});\nparams.user // trailing comment\n}"
+        );
+        assert_eq!("Isolated".parse::<EvalWorld>(), Ok(EvalWorld::Isolated));
+        assert_eq!(ref_script("1", false, None), "1");
+        let run = ref_script("this.value", false, Some("ref2"));
+        assert!(run.contains(r#"return eval("this.value"); }).call("#));
+        assert!(run.contains(r#"refs.get("ref2")"#));
+        let store = ref_script("document.body", true, None);
+        assert!(store.starts_with("(async () => { const value = await (function()"));
+        assert!(store.contains(".call(globalThis)"));
+        assert!("worker".parse::<EvalWorld>().is_err());
+    }
+
+    #[test]
+    fn test_console_level_filter() {
+        let entry = ConsoleEntry {
+            level: "warning".to_string(),
+            source: "console".to_string(),
+            text: "Deprecated API".to_string(),
+            url: None,
+            line: None,
+            stack_trace: None,
+            timestamp: 0.0,
+        };
+        let levels = |names: &[&str]| names.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        assert!(console_matches(&entry, None, &[]));
+        assert!(console_matches(&entry, None, &levels(&["error", "WARN"])));
+        assert!(!console_matches(&entry, None, &levels(&["error"])));
+        assert!(console_matches(
+            &entry,
+            Some("Deprecated"),
+            &levels(&["warning"])
+        ));
+        assert!(!console_matches(&entry, Some("TypeError"), &[]));
+    }
+
     #[test]
     fn test_debug_command_variants() {
         let dom = DebugCommand::Dom {
@@ -1108,9 +4029,17 @@ mod tests {
         let console = DebugCommand::Console {
             follow: false,
             filter: None,
+            duration_ms: None,
+            levels: Vec::new(),
         };
         let network = DebugCommand::Network { filter: None };
         let storage = DebugCommand::Storage;
+        let styles = DebugCommand::Styles {
+            selector: "h1".to_string(),
+            matched: true,
+            box_model: false,
+        };
+        assert!(format!("{:?}", styles).contains("matched: true"));
 
         assert!(format!("{:?}", dom).contains("Dom"));
         assert!(format!("{:?}", console).contains("Console"));
@@ -0,0 +1,206 @@
+//! DevTools endpoint discovery for DOMGuard
+//!
+//! Chrome publishes its browser WebSocket URL at `/json/version` and its
+//! targets at `/json/list`. Asking for them, instead of assuming a local
+//! Chrome's layout, lets DOMGuard connect to Chrome in Docker, browserless and
+//! other setups whose WebSocket path or token differs.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::config::Config;
+
+/// How long to wait for a discovery endpoint
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Browser details from `/json/version`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BrowserVersion {
+    #[serde(rename(deserialize = "Browser"), default)]
+    pub browser: String,
+    #[serde(rename(deserialize = "Protocol-Version"), default)]
+    pub protocol_version: String,
+    #[serde(rename(deserialize = "User-Agent"), default)]
+    pub user_agent: String,
+    #[serde(rename(deserialize = "webSocketDebuggerUrl"), default)]
+    pub web_socket_debugger_url: Option<String>,
+}
+
+/// A target from `/json/list`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiscoveredTarget {
+    #[serde(default)]
+    pub id: String,
+    #[serde(rename(deserialize = "type"), default)]
+    pub target_type: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(rename(deserialize = "webSocketDebuggerUrl"), default)]
+    pub web_socket_debugger_url: Option<String>,
+}
+
+/// Fetch `/json/version`
+pub fn fetch_version(config: &Config) -> Result<BrowserVersion> {
+    let body = http_get(config, "/json/version")?;
+    serde_json::from_str(&body).context("Invalid /json/version response")
+}
+
+/// Fetch `/json/list`
+pub fn fetch_targets(config: &Config) -> Result<Vec<DiscoveredTarget>> {
+    let body = http_get(config, "/json/list")?;
+    serde_json::from_str(&body).context("Invalid /json/list response")
+}
+
+/// Browser WebSocket URL reported by Chrome, pointed at the configured host
+pub fn discover_ws_url(config: &Config) -> Result<String> {
+    let version = fetch_version(config)?;
+    let url = version
+        .web_socket_debugger_url
+        .ok_or_else(|| anyhow!("/json/version has no webSocketDebuggerUrl"))?;
+    Ok(rewrite_ws_url(&url, config))
+}
+
+/// Run a discovery `request` on the blocking pool, giving up after `limit`
+///
+/// The requests block on HTTP; async callers go through here so a slow
+/// endpoint stalls neither the runtime nor the command's deadline.
+pub async fn off_runtime<T: Send + 'static>(
+    config: &Config,
+    limit: Duration,
+    request: fn(&Config) -> Result<T>,
+) -> Result<T> {
+    let config = config.clone();
+    let task = tokio::task::spawn_blocking(move || request(&config));
+    tokio::time::timeout(limit, task)
+        .await
+        .map_err(|_| anyhow!("Discovery request timed out after {}ms", limit.as_millis()))?
+        .context("Discovery task failed")?
+}
+
+/// Point a reported WebSocket URL at the configured host and port
+///
+/// Chrome reports the address it sees itself on (e.g., `127.0.0.1` inside a
+/// container, or `0.0.0.0`), which often isn't reachable from here. The path
+/// is kept, and the configured token is added if the URL doesn't carry one.
+pub fn rewrite_ws_url(reported: &str, config: &Config) -> String {
+    let rest = reported
+        .split_once("://")
+        .map_or(reported, |(_, rest)| rest);
+    let path = rest.find('/').map_or("/", |i| &rest[i..]);
+
+    let mut url = format!(
+        "{}://{}:{}{}",
+        if config.chrome.tls { "wss" } else { "ws" },
+        config.chrome.host,
+        config.chrome.port,
+        path
+    );
+    if let Some(token) = &config.chrome.token {
+        if !url.contains("token=") {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str("token=");
+            url.push_str(token);
+        }
+    }
+    url
+}
+
+/// Hide the configured token in a URL before showing it
+pub fn redact_token(url: &str, config: &Config) -> String {
+    match &config.chrome.token {
+        Some(token) if !token.is_empty() => url.replace(token.as_str(), crate::output::MASKED),
+        _ => url.to_string(),
+    }
+}
+
+/// GET a DevTools HTTP endpoint and return the body
+fn http_get(config: &Config, path: &str) -> Result<String> {
+    let path = match &config.chrome.token {
+        Some(token) => format!("{}?token={}", path, token),
+        None => path.to_string(),
+    };
+    let scheme = if config.chrome.tls { "https" } else { "http" };
+    let url = format!(
+        "{}://{}:{}{}",
+        scheme, config.chrome.host, config.chrome.port, path
+    );
+    crate::http::get_json(&url, DISCOVERY_TIMEOUT).map_err(|e| {
+        anyhow!(
+            "Discovery request failed: {}",
+            redact_token(&format!("{:#}", e), config)
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_ws_url() {
+        let mut config = Config::default();
+        config.chrome.host = "10.0.0.5".to_string();
+        assert_eq!(
+            rewrite_ws_url("ws://127.0.0.1/devtools/browser/abc", &config),
+            "ws://10.0.0.5:9222/devtools/browser/abc"
+        );
+
+        config.chrome.tls = true;
+        config.chrome.token = Some("t0k".to_string());
+        assert_eq!(
+            rewrite_ws_url("ws://0.0.0.0:3000/?launch=1", &config),
+            "wss://10.0.0.5:9222/?launch=1&token=t0k"
+        );
+        assert_eq!(
+            redact_token("wss://10.0.0.5:9222/?token=t0k", &config),
+            "wss://10.0.0.5:9222/?token=****"
+        );
+        assert_eq!(
+            rewrite_ws_url("wss://browserless/?token=theirs", &config),
+            "wss://10.0.0.5:9222/?token=theirs"
+        );
+    }
+
+    #[test]
+    fn test_parse_discovery_json() {
+        let version: BrowserVersion = serde_json::from_str(
+            r#"{"Browser": "Chrome/120.0.6099.71", "Protocol-Version": "1.3",
+                "webSocketDebuggerUrl": "ws://127.0.0.1:9222/devtools/browser/x"}"#,
+        )
+        .unwrap();
+        assert_eq!(version.browser, "Chrome/120.0.6099.71");
+        assert!(version.web_socket_debugger_url.is_some());
+
+        let targets: Vec<DiscoveredTarget> = serde_json::from_str(
+            r#"[{"id": "A1", "type": "page", "title": "Example", "url": "https://example.com/"},
+                {"id": "B2", "type": "service_worker", "url": "https://example.com/sw.js"}]"#,
+        )
+        .unwrap();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[1].target_type, "service_worker");
+        assert!(targets[0].web_socket_debugger_url.is_none());
+    }
+
+    #[test]
+    fn test_off_runtime_gives_up_at_its_limit() {
+        fn hanging(_: &Config) -> Result<String> {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok("late".to_string())
+        }
+        fn instant(config: &Config) -> Result<String> {
+            Ok(config.chrome.host.clone())
+        }
+
+        let config = Config::default();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let started = std::time::Instant::now();
+        let late = runtime.block_on(off_runtime(&config, Duration::from_millis(20), hanging));
+        assert!(late.unwrap_err().to_string().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_millis(400));
+        let host = runtime.block_on(off_runtime(&config, Duration::from_secs(1), instant));
+        assert_eq!(host.unwrap(), "127.0.0.1");
+    }
+}
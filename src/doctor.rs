@@ -0,0 +1,528 @@
+//! Environment diagnostics for DOMGuard
+//!
+//! `domguard doctor` checks the things that usually go wrong before the first
+//! command works: no Chrome binary, nothing listening on the debugging port,
+//! a Chrome too old for chromiumoxide's protocol, a broken config, an
+//! unwritable `.domguard` directory, and state files left behind by crashed
+//! runs. Each check that doesn't pass carries a fix.
+
+use serde::Serialize;
+use std::path::Path;
+
+use crate::config::Config;
+
+/// Oldest Chrome major version the protocol definitions bundled with
+/// chromiumoxide 0.7 are tested against
+const MIN_CHROME_MAJOR: u32 = 100;
+
+/// Session recordings and takeovers older than this are probably abandoned
+const STALE_AFTER_HOURS: i64 = 24;
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// A single diagnostic
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Ok,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// All diagnostics from one run
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    /// False if any check failed (warnings don't count)
+    pub healthy: bool,
+    pub checks: Vec<Check>,
+}
+
+/// A doctor run with failed checks, after the report was printed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Unhealthy {
+    /// Names of the failed checks
+    pub failed: Vec<&'static str>,
+}
+
+impl std::fmt::Display for Unhealthy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Doctor checks failed: {}", self.failed.join(", "))
+    }
+}
+
+impl std::error::Error for Unhealthy {}
+
+impl DoctorReport {
+    /// `Err` naming the failed checks unless the report is healthy
+    pub fn into_result(self) -> Result<(), Unhealthy> {
+        if self.healthy {
+            return Ok(());
+        }
+        Err(Unhealthy {
+            failed: self
+                .checks
+                .iter()
+                .filter(|check| check.status == CheckStatus::Fail)
+                .map(|check| check.name)
+                .collect(),
+        })
+    }
+}
+
+/// Run every check
+///
+/// `config_error` is the error from loading config.toml, if it didn't load;
+/// `config` is then the defaults with CLI overrides applied.
+pub fn run(config: &Config, config_error: Option<&anyhow::Error>) -> DoctorReport {
+    let mut checks = vec![check_config(config, config_error)];
+
+    let version = crate::discovery::fetch_version(config);
+    checks.push(check_chrome_binary(config, version.is_ok()));
+    checks.push(check_port(config, version.as_ref().err()));
+    if let Ok(version) = &version {
+        checks.push(check_version(&version.browser, &version.protocol_version));
+    }
+
+    match Config::find_domguard_dir() {
+        Some(dir) => {
+            checks.push(check_writable(&dir));
+            let profile_dir = std::env::temp_dir().join("domguard-chrome-profile");
+            checks.extend(check_stale_files(&dir, &profile_dir, version.is_ok()));
+        }
+        None => checks.push(Check::warn(
+            "domguard_dir",
+            "No .domguard directory in this directory or its parents",
+            "Run 'domguard init'",
+        )),
+    }
+
+    DoctorReport {
+        healthy: checks.iter().all(|c| c.status != CheckStatus::Fail),
+        checks,
+    }
+}
+
+fn check_config(config: &Config, config_error: Option<&anyhow::Error>) -> Check {
+    const NAME: &str = "config";
+    let path = Config::config_path();
+
+    if let Some(e) = config_error {
+        return Check::fail(
+            NAME,
+            format!("{}: {:#}", path.display(), e),
+            format!(
+                "Fix the file, or move it aside and run 'domguard init' for a fresh one ({})",
+                path.display()
+            ),
+        );
+    }
+    if config.defaults.timeout_ms == 0 {
+        return Check::warn(
+            NAME,
+            "defaults.timeout_ms is 0, so every wait times out immediately",
+            "Set defaults.timeout_ms in config.toml (default 30000)",
+        );
+    }
+    if !matches!(
+        config.defaults.screenshot_format.as_str(),
+        "png" | "jpeg" | "jpg" | "webp"
+    ) {
+        return Check::warn(
+            NAME,
+            format!(
+                "Unknown defaults.screenshot_format \"{}\"",
+                config.defaults.screenshot_format
+            ),
+            "Use png, jpeg or webp",
+        );
+    }
+    if !config.is_localhost() && !config.chrome.allow_remote {
+        return Check::warn(
+            NAME,
+            format!(
+                "chrome.host is {}, but remote connections are not allowed",
+                config.chrome.host
+            ),
+            "Set chrome.allow_remote = true or pass --allow-remote",
+        );
+    }
+
+    if path.exists() {
+        Check::ok(NAME, format!("{} is valid", path.display()))
+    } else {
+        Check::ok(NAME, "No config.toml, using defaults")
+    }
+}
+
+fn check_chrome_binary(config: &Config, reachable: bool) -> Check {
+    const NAME: &str = "chrome_binary";
+
+    if !config.is_localhost() {
+        return Check::ok(NAME, "Skipped for a remote Chrome");
+    }
+    match crate::cdp::CdpConnection::find_chrome() {
+        Ok(path) => Check::ok(NAME, path.display().to_string()),
+        // Something else (a container, a manual launch) is serving the port
+        Err(_) if reachable => Check::warn(
+            NAME,
+            "No Chrome binary found, but the debugging port is answering",
+            "Install Chrome or Chromium if DOMGuard should launch it itself",
+        ),
+        Err(_) => Check::fail(
+            NAME,
+            "No Chrome or Chromium binary found in PATH or the usual install locations",
+            "Install Chrome or Chromium, or run 'domguard chrome up' to use one in a container",
+        ),
+    }
+}
+
+fn check_port(config: &Config, error: Option<&anyhow::Error>) -> Check {
+    const NAME: &str = "debugging_port";
+    let address = format!("{}:{}", config.chrome.host, config.chrome.port);
+
+    match error {
+        None => Check::ok(NAME, format!("Chrome is answering on {}", address)),
+        // A local Chrome is launched on demand
+        Some(e) if config.is_localhost() => Check::warn(
+            NAME,
+            format!("Nothing answering on {} ({:#})", address, e),
+            format!(
+                "DOMGuard launches Chrome on first use; to start it yourself run \
+                 'chrome --remote-debugging-port={}' or 'domguard chrome up'",
+                config.chrome.port
+            ),
+        ),
+        Some(e) => Check::fail(
+            NAME,
+            format!("Nothing answering on {} ({:#})", address, e),
+            "Check that Chrome is running there with --remote-debugging-port, and the network path (see 'domguard tunnel')",
+        ),
+    }
+}
+
+fn check_version(browser: &str, protocol_version: &str) -> Check {
+    const NAME: &str = "chrome_version";
+
+    // "Chrome/120.0.6099.71", "HeadlessChrome/...", "Chromium/..."
+    let major = browser
+        .split_once('/')
+        .filter(|(product, _)| product.contains("Chrom"))
+        .and_then(|(_, version)| version.split('.').next()?.parse::<u32>().ok());
+
+    match major {
+        None => Check::warn(
+            NAME,
+            format!("\"{}\" is not a recognised Chrome build", browser),
+            "Use Chrome or Chromium; other DevTools implementations may lack commands DOMGuard uses",
+        ),
+        Some(major) if major < MIN_CHROME_MAJOR => Check::warn(
+            NAME,
+            format!("{} is older than Chrome {}", browser, MIN_CHROME_MAJOR),
+            "Update Chrome; older releases lack protocol commands chromiumoxide sends",
+        ),
+        Some(_) if protocol_version != "1.3" => Check::warn(
+            NAME,
+            format!("Unexpected protocol version {}", protocol_version),
+            "Update Chrome and DOMGuard",
+        ),
+        Some(_) => Check::ok(NAME, format!("{} (protocol {})", browser, protocol_version)),
+    }
+}
+
+fn check_writable(dir: &Path) -> Check {
+    const NAME: &str = "domguard_dir";
+
+    let probe = dir.join(".doctor-write-test");
+    match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            Check::ok(NAME, format!("{} is writable", dir.display()))
+        }
+        Err(e) => Check::fail(
+            NAME,
+            format!("Cannot write to {}: {}", dir.display(), e),
+            format!(
+                "Fix the permissions (e.g. 'chmod -R u+w {}')",
+                dir.display()
+            ),
+        ),
+    }
+}
+
+/// Look for active-session, takeover and Chrome profile lock files left by crashed runs
+fn check_stale_files(domguard_dir: &Path, profile_dir: &Path, chrome_running: bool) -> Vec<Check> {
+    const NAME: &str = "stale_files";
+
+    let mut checks = Vec::new();
+    let stale_before = chrono::Utc::now() - chrono::Duration::hours(STALE_AFTER_HOURS);
+
+    let active_session = domguard_dir.join("sessions").join("_active_session.json");
+    if active_session.exists() {
+        match crate::session::Session::load(&active_session) {
+            Err(_) => checks.push(Check::warn(
+                NAME,
+                format!("{} is unreadable", active_session.display()),
+                format!("Remove {}", active_session.display()),
+            )),
+            Ok(session) if session.started_at < stale_before => checks.push(Check::warn(
+                NAME,
+                format!(
+                    "Session {} has been recording since {}",
+                    session.id,
+                    session.started_at.format("%Y-%m-%d %H:%M")
+                ),
+                "Run 'domguard session stop' if it's abandoned",
+            )),
+            Ok(_) => {}
+        }
+    }
+
+    let takeover = domguard_dir.join("_takeover_state.json");
+    if takeover.exists() {
+        let session = std::fs::read_to_string(&takeover)
+            .ok()
+            .and_then(|c| serde_json::from_str::<crate::takeover::TakeoverSession>(&c).ok());
+        match session {
+            None => checks.push(Check::warn(
+                NAME,
+                format!("{} is unreadable", takeover.display()),
+                format!("Remove {}", takeover.display()),
+            )),
+            Some(session)
+                if session.ended_at.is_none()
+                    && session.started_at < stale_before
+                    && matches!(
+                        session.state,
+                        crate::takeover::TakeoverState::WaitingForUser
+                            | crate::takeover::TakeoverState::UserControl
+                    ) =>
+            {
+                checks.push(Check::warn(
+                    NAME,
+                    format!(
+                        "Takeover {} has been waiting since {}",
+                        session.id,
+                        session.started_at.format("%Y-%m-%d %H:%M")
+                    ),
+                    "Run 'domguard takeover cancel' if nobody is handling it",
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+
+    // Chrome leaves its profile lock behind when killed; with nothing running it blocks the next launch
+    let lock = profile_dir.join("SingletonLock");
+    if !chrome_running && std::fs::symlink_metadata(&lock).is_ok() {
+        checks.push(Check::warn(
+            NAME,
+            format!(
+                "Chrome profile lock {} exists but Chrome isn't answering",
+                lock.display()
+            ),
+            format!(
+                "Make sure no Chrome still uses the profile, then remove {}",
+                lock.display()
+            ),
+        ));
+    }
+
+    if checks.is_empty() {
+        checks.push(Check::ok(NAME, "No stale session, takeover or lock files"));
+    }
+    checks
+}
+
+/// What `domguard status` reports
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    pub initialized: bool,
+    pub config_path: String,
+    pub chrome_host: String,
+    pub chrome_port: u16,
+    pub connected: bool,
+    pub current_url: Option<String>,
+    #[serde(skip)]
+    pub title: Option<String>,
+    /// Why the connection failed
+    #[serde(skip)]
+    pub connect_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub browser: Option<crate::discovery::BrowserVersion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub targets: Option<Vec<crate::discovery::DiscoveredTarget>>,
+    /// The first of `version_error` and `targets_error`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discovery_error: Option<String>,
+    /// Why `/json/version` failed
+    #[serde(skip)]
+    pub version_error: Option<String>,
+    /// Why `/json/list` failed
+    #[serde(skip)]
+    pub targets_error: Option<String>,
+    /// Throttling DOMGuard left applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<crate::debug::ThrottleState>,
+}
+
+/// Check the project and the Chrome connection, and with `verbose` what the
+/// DevTools endpoints report
+pub async fn status(cdp: &mut crate::cdp::CdpConnection, config: &Config, verbose: bool) -> Status {
+    let throttle = crate::debug::PageState::load(&crate::debug::PageState::path())
+        .map(|state| state.throttle)
+        .unwrap_or_default();
+
+    let connected = cdp.connect().await;
+    let (current_url, title) = if connected.is_ok() {
+        (cdp.current_url().await.ok(), cdp.get_title().await.ok())
+    } else {
+        (None, None)
+    };
+
+    let mut status = Status {
+        initialized: Config::is_initialized(),
+        config_path: Config::config_path().to_string_lossy().to_string(),
+        chrome_host: config.chrome.host.clone(),
+        chrome_port: config.chrome.port,
+        connected: connected.is_ok(),
+        current_url,
+        title,
+        connect_error: connected.err().map(|e| e.to_string()),
+        browser: None,
+        targets: None,
+        discovery_error: None,
+        version_error: None,
+        targets_error: None,
+        throttle: Some(throttle).filter(|throttle| !throttle.is_off()),
+    };
+    if verbose {
+        let limit = std::time::Duration::from_millis(cdp.bounded(config.defaults.timeout_ms));
+        let version =
+            crate::discovery::off_runtime(config, limit, crate::discovery::fetch_version).await;
+        match version {
+            Ok(version) => status.browser = Some(version),
+            Err(e) => status.version_error = Some(e.to_string()),
+        }
+        let targets =
+            crate::discovery::off_runtime(config, limit, crate::discovery::fetch_targets).await;
+        match targets {
+            Ok(targets) => status.targets = Some(targets),
+            Err(e) => status.targets_error = Some(e.to_string()),
+        }
+        status.discovery_error = status
+            .version_error
+            .clone()
+            .or_else(|| status.targets_error.clone());
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unhealthy_report() {
+        let report = DoctorReport {
+            healthy: false,
+            checks: vec![
+                Check::ok("config", "Loaded"),
+                Check::fail("chrome", "Not reachable", "Run 'domguard chrome up'"),
+            ],
+        };
+        assert_eq!(report.into_result().unwrap_err().failed, vec!["chrome"]);
+    }
+
+    #[test]
+    fn test_check_version() {
+        assert_eq!(
+            check_version("Chrome/120.0.6099.71", "1.3").status,
+            CheckStatus::Ok
+        );
+        assert_eq!(
+            check_version("HeadlessChrome/131.0.0.0", "1.3").status,
+            CheckStatus::Ok
+        );
+        assert_eq!(
+            check_version("Chrome/88.0.1", "1.3").status,
+            CheckStatus::Warn
+        );
+        assert_eq!(
+            check_version("Firefox/128.0", "1.3").status,
+            CheckStatus::Warn
+        );
+    }
+
+    #[test]
+    fn test_check_stale_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = dir.path().join("profile");
+        std::fs::create_dir_all(dir.path().join("sessions")).unwrap();
+        std::fs::create_dir_all(&profile).unwrap();
+
+        let checks = check_stale_files(dir.path(), &profile, false);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].status, CheckStatus::Ok);
+
+        std::fs::write(dir.path().join("sessions/_active_session.json"), "{").unwrap();
+        std::fs::write(profile.join("SingletonLock"), "").unwrap();
+        let checks = check_stale_files(dir.path(), &profile, false);
+        assert_eq!(checks.len(), 2);
+        assert!(checks.iter().all(|c| c.fix.is_some()));
+
+        // The lock is expected while Chrome runs
+        assert_eq!(check_stale_files(dir.path(), &profile, true).len(), 1);
+    }
+
+    #[test]
+    fn test_check_config() {
+        let mut config = Config::default();
+        assert_eq!(check_config(&config, None).status, CheckStatus::Ok);
+
+        config.chrome.host = "10.0.0.5".to_string();
+        assert_eq!(check_config(&config, None).status, CheckStatus::Warn);
+
+        let error = anyhow::anyhow!("expected `=`");
+        assert_eq!(
+            check_config(&config, Some(&error)).status,
+            CheckStatus::Fail
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Webhooks for page events
+//!
+//! `events subscribe` stores a URL and the events it wants in
+//! `.domguard/webhooks.toml`; `events daemon` watches the browser and POSTs
+//! each matching event there as JSON, so an orchestrator can react to
+//! navigations, console errors, dialogs and downloads without polling.
+//! Delivery is best effort: a hook that fails is reported and skipped.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::cdp::CdpConnection;
+
+/// How long one webhook delivery may take
+const HOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventKind {
+    /// The main frame committed a new URL
+    Navigation,
+    /// console.error or an uncaught exception
+    ConsoleError,
+    /// alert, confirm, prompt or beforeunload opened
+    Dialog,
+    /// A download started
+    Download,
+}
+
+impl std::fmt::Display for EventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Navigation => write!(f, "navigation"),
+            Self::ConsoleError => write!(f, "console-error"),
+            Self::Dialog => write!(f, "dialog"),
+            Self::Download => write!(f, "download"),
+        }
+    }
+}
+
+impl std::str::FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "navigation" => Ok(Self::Navigation),
+            "console-error" => Ok(Self::ConsoleError),
+            "dialog" => Ok(Self::Dialog),
+            "download" => Ok(Self::Download),
+            other => Err(format!(
+                "Unknown event \"{}\" (expected navigation, console-error, dialog or download)",
+                other
+            )),
+        }
+    }
+}
+
+/// Body POSTed to a webhook
+#[derive(Debug, Clone, Serialize)]
+pub struct PageEvent {
+    pub event: EventKind,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Page the event happened on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Event details: the console message, dialog or download
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    pub data: serde_json::Value,
+}
+
+/// A URL that receives some page events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    pub on: Vec<EventKind>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Webhook {
+    pub fn wants(&self, kind: EventKind) -> bool {
+        self.on.contains(&kind)
+    }
+}
+
+/// Webhooks stored in .domguard/webhooks.toml
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+}
+
+impl WebhookConfig {
+    /// Load webhooks from file
+    pub fn load(path: &Path) -> Result<Self> {
+        if path.exists() {
+            let content = std::fs::read_to_string(path)?;
+            Ok(toml::from_str(&content)?)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Save webhooks to file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        crate::storage::write_atomic(path, &toml::to_string_pretty(self)?)
+    }
+
+    /// Where the project's webhooks are stored
+    pub fn path() -> PathBuf {
+        crate::config::Config::find_domguard_dir()
+            .unwrap_or_else(crate::config::Config::domguard_dir)
+            .join("webhooks.toml")
+    }
+
+    /// The project's webhooks
+    pub fn open() -> Result<Self> {
+        Self::load(&Self::path())
+    }
+
+    /// The project's webhooks, failing when there are none to send to
+    pub fn subscribed() -> Result<Self> {
+        let config = Self::open()?;
+        if config.webhooks.is_empty() {
+            return Err(anyhow!(
+                "No webhook subscriptions (add one with 'domguard events subscribe')"
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Save the project's webhooks
+    pub fn save_project(&self) -> Result<()> {
+        self.save(&Self::path())
+    }
+
+    /// Add a webhook for `url`, replacing an existing one for the same URL
+    pub fn subscribe(&mut self, url: &str, kinds: &[EventKind]) -> Result<Webhook> {
+        crate::http::HttpUrl::parse(url)?;
+        let mut on = Vec::new();
+        for kind in kinds {
+            if !on.contains(kind) {
+                on.push(*kind);
+            }
+        }
+        let id = self
+            .webhooks
+            .iter()
+            .find(|hook| hook.url == url)
+            .map_or_else(|| self.next_id(), |hook| hook.id.clone());
+        self.webhooks.retain(|hook| hook.url != url);
+        let hook = Webhook {
+            id,
+            url: url.to_string(),
+            on,
+            created_at: chrono::Utc::now(),
+        };
+        self.webhooks.push(hook.clone());
+        Ok(hook)
+    }
+
+    /// Remove a webhook by id or URL, returning whether one existed
+    pub fn unsubscribe(&mut self, id_or_url: &str) -> bool {
+        let before = self.webhooks.len();
+        self.webhooks
+            .retain(|hook| hook.id != id_or_url && hook.url != id_or_url);
+        self.webhooks.len() != before
+    }
+
+    /// `hook-N`, one past the highest in use
+    fn next_id(&self) -> String {
+        let highest = self
+            .webhooks
+            .iter()
+            .filter_map(|hook| hook.id.strip_prefix("hook-")?.parse::<u32>().ok())
+            .max()
+            .unwrap_or(0);
+        format!("hook-{}", highest + 1)
+    }
+}
+
+/// One event sent, or not, to one webhook
+pub struct Delivery<'a> {
+    pub event: &'a PageEvent,
+    pub webhook: &'a Webhook,
+    pub result: Result<()>,
+}
+
+/// Send each page event to the webhooks that want it until the page closes
+///
+/// `cdp` must be connected; `on_delivery` sees every delivery, failed or not.
+pub async fn run_daemon(
+    cdp: &CdpConnection,
+    config: &WebhookConfig,
+    on_delivery: &mut dyn FnMut(&Delivery),
+) -> Result<()> {
+    cdp.watch_page_events(&mut |event| {
+        for webhook in config
+            .webhooks
+            .iter()
+            .filter(|hook| hook.wants(event.event))
+        {
+            on_delivery(&Delivery {
+                event: &event,
+                webhook,
+                result: deliver(&webhook.url, &event),
+            });
+        }
+    })
+    .await
+}
+
+/// POST `event` as JSON to `url`
+pub fn deliver(url: &str, event: &PageEvent) -> Result<()> {
+    crate::http::post_json(url, &serde_json::to_string(event)?, HOOK_TIMEOUT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscribe_and_unsubscribe() {
+        let mut config = WebhookConfig::default();
+        let kinds: Vec<EventKind> = "navigation,console-error"
+            .split(',')
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let hook = config
+            .subscribe("http://localhost:8000/hook", &kinds)
+            .unwrap();
+        assert_eq!(hook.id, "hook-1");
+        assert!(hook.wants(EventKind::ConsoleError));
+        assert!(!hook.wants(EventKind::Download));
+
+        let again = config
+            .subscribe("http://localhost:8000/hook", &[EventKind::Download])
+            .unwrap();
+        assert_eq!(again.id, "hook-1");
+        assert_eq!(config.webhooks.len(), 1);
+        let other = config
+            .subscribe("http://localhost:9000/", &[EventKind::Dialog])
+            .unwrap();
+        assert_eq!(other.id, "hook-2");
+
+        let saved: WebhookConfig =
+            toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+        assert_eq!(saved.webhooks[0].on, vec![EventKind::Download]);
+        assert!(config.unsubscribe("hook-1"));
+        assert!(!config.unsubscribe("hook-1"));
+        assert!("popup".parse::<EventKind>().is_err());
+    }
+}
@@ -19,6 +19,21 @@ pub struct ExplanationContext {
     pub current_goal: Option<String>,
 }
 
+impl ExplanationContext {
+    /// The current page's URL and title, or nothing when Chrome is unreachable
+    pub async fn from_page(cdp: &mut crate::cdp::CdpConnection) -> Self {
+        if cdp.connect().await.is_err() {
+            return Self::default();
+        }
+        Self {
+            current_url: cdp.current_url().await.ok(),
+            current_title: cdp.get_title().await.ok(),
+            previous_action: None,
+            current_goal: None,
+        }
+    }
+}
+
 /// Action explanation with reasoning
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActionExplanation {
@@ -0,0 +1,258 @@
+//! Frame rate and long tasks while a command runs, for `debug fps --during`
+//!
+//! A `requestAnimationFrame` loop in the page records every frame interval
+//! and a `longtask` observer every task over 50ms, from just before the
+//! wrapped command starts until it ends. Dropped frames are counted against a
+//! 60 Hz display. The recorder lives in the page, so a command that navigates
+//! takes it with it. Background tabs don't render, so they show no frames.
+
+use serde::{Deserialize, Serialize};
+
+/// Starts recording in `window.__domguardFps`
+pub const MONITOR_SCRIPT: &str = r"(function() {
+    if (window.__domguardFps) window.__domguardFps.stop();
+    const state = { frames: [], longTasks: [], start: performance.now(), running: true };
+    let last = null;
+    const tick = now => {
+        if (!state.running) return;
+        if (last !== null && state.frames.length < 100000) state.frames.push(now - last);
+        last = now;
+        requestAnimationFrame(tick);
+    };
+    requestAnimationFrame(tick);
+    let observer = null;
+    try {
+        observer = new PerformanceObserver(list => {
+            for (const entry of list.getEntries()) {
+                const source = entry.attribution && entry.attribution[0];
+                state.longTasks.push({
+                    start_ms: entry.startTime - state.start,
+                    duration_ms: entry.duration,
+                    source: source ? source.containerSrc || source.containerName || source.name : null,
+                });
+            }
+        });
+        observer.observe({ type: 'longtask' });
+    } catch (e) {}
+    state.stop = () => {
+        state.running = false;
+        if (observer) observer.disconnect();
+    };
+    window.__domguardFps = state;
+    return true;
+})()";
+
+/// Stops recording and returns a `FrameSample`, or null when the recorder is gone
+pub const COLLECT_SCRIPT: &str = r"(function() {
+    const state = window.__domguardFps;
+    if (!state) return null;
+    state.stop();
+    delete window.__domguardFps;
+    return {
+        duration_ms: performance.now() - state.start,
+        frame_intervals: state.frames,
+        long_tasks: state.longTasks,
+        hidden: document.hidden,
+    };
+})()";
+
+/// One frame at 60 Hz
+const FRAME_BUDGET_MS: f64 = 1000.0 / 60.0;
+
+/// Frames slower than this count as jank
+const JANK_FRAME_MS: f64 = 50.0;
+
+/// Long tasks listed in a report, longest first
+const MAX_LISTED_TASKS: usize = 10;
+
+/// What the page recorded
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FrameSample {
+    pub duration_ms: f64,
+    #[serde(default)]
+    pub frame_intervals: Vec<f64>,
+    #[serde(default)]
+    pub long_tasks: Vec<LongTask>,
+    /// The tab was in the background, so no frames were drawn
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+/// A main-thread task over 50ms
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LongTask {
+    /// Since recording started
+    pub start_ms: f64,
+    pub duration_ms: f64,
+    /// Frame the task ran in, when Chrome attributes it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// Jank statistics for the wrapped command
+#[derive(Debug, Clone, Serialize)]
+pub struct FpsReport {
+    pub duration_ms: f64,
+    pub frames: usize,
+    pub average_fps: f64,
+    /// 95th percentile frame time
+    pub p95_frame_ms: f64,
+    pub longest_frame_ms: f64,
+    /// Frames a 60 Hz display had to skip
+    pub dropped_frames: u64,
+    /// Frames over 50ms
+    pub jank_frames: usize,
+    pub long_tasks: usize,
+    /// Time past the first 50ms of every long task, when input would wait
+    pub blocking_ms: f64,
+    /// The longest tasks
+    pub longest_tasks: Vec<LongTask>,
+    pub hidden: bool,
+}
+
+impl FpsReport {
+    pub fn from_sample(sample: FrameSample) -> Self {
+        let mut intervals = sample.frame_intervals;
+        intervals.sort_by(f64::total_cmp);
+        let frames = intervals.len();
+        let p95_frame_ms = if frames == 0 {
+            0.0
+        } else {
+            intervals[(frames * 95).div_ceil(100).clamp(1, frames) - 1]
+        };
+        let dropped_frames = intervals
+            .iter()
+            .map(|ms| ((ms / FRAME_BUDGET_MS).round() as u64).saturating_sub(1))
+            .sum();
+
+        let mut tasks = sample.long_tasks;
+        let blocking_ms = tasks
+            .iter()
+            .map(|task| (task.duration_ms - 50.0).max(0.0))
+            .sum();
+        let long_tasks = tasks.len();
+        tasks.sort_by(|a, b| b.duration_ms.total_cmp(&a.duration_ms));
+        tasks.truncate(MAX_LISTED_TASKS);
+
+        Self {
+            duration_ms: sample.duration_ms,
+            frames,
+            average_fps: if sample.duration_ms > 0.0 {
+                frames as f64 * 1000.0 / sample.duration_ms
+            } else {
+                0.0
+            },
+            p95_frame_ms,
+            longest_frame_ms: intervals.last().copied().unwrap_or_default(),
+            dropped_frames,
+            jank_frames: intervals.iter().filter(|&&ms| ms > JANK_FRAME_MS).count(),
+            long_tasks,
+            blocking_ms,
+            longest_tasks: tasks,
+            hidden: sample.hidden,
+        }
+    }
+}
+
+/// Split a command line into arguments, honouring quotes and backslashes
+///
+/// `interact click "#play button"` gives `["interact", "click", "#play button"]`.
+pub fn split_command(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| "Command ends with a backslash".to_string())?;
+                current.get_or_insert_with(String::new).push(escaped);
+            }
+            (Some(_), c) => current.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => args.extend(current.take()),
+            (None, c) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err("Unterminated quote in command".to_string());
+    }
+    args.extend(current);
+    if args.is_empty() {
+        return Err("No command given".to_string());
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_from_sample() {
+        let mut frame_intervals = vec![16.7; 57];
+        frame_intervals.extend([33.4, 50.1, 120.0]);
+        let sample = FrameSample {
+            duration_ms: 1500.0,
+            frame_intervals,
+            long_tasks: vec![
+                LongTask {
+                    start_ms: 200.0,
+                    duration_ms: 80.0,
+                    source: None,
+                },
+                LongTask {
+                    start_ms: 900.0,
+                    duration_ms: 130.0,
+                    source: Some("https://ads.test/frame".to_string()),
+                },
+            ],
+            hidden: false,
+        };
+        let report = FpsReport::from_sample(sample);
+        assert_eq!(report.frames, 60);
+        assert!((report.average_fps - 40.0).abs() < 1e-9);
+        assert!((report.p95_frame_ms - 16.7).abs() < 1e-9);
+        assert!((report.longest_frame_ms - 120.0).abs() < 1e-9);
+        // 1 + 2 + 6 frames skipped
+        assert_eq!(report.dropped_frames, 9);
+        assert_eq!(report.jank_frames, 2);
+        assert_eq!(report.long_tasks, 2);
+        assert!((report.blocking_ms - 110.0).abs() < 1e-9);
+        assert_eq!(
+            report.longest_tasks[0].source.as_deref(),
+            Some("https://ads.test/frame")
+        );
+
+        let empty = FpsReport::from_sample(FrameSample::default());
+        assert_eq!(empty.frames, 0);
+        assert_eq!(empty.dropped_frames, 0);
+    }
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command(r##"interact click "#play button" --text 'Go on' a\ b"##).unwrap(),
+            vec![
+                "interact",
+                "click",
+                "#play button",
+                "--text",
+                "Go on",
+                "a b"
+            ]
+        );
+        assert_eq!(
+            split_command(r#"type "" x"#).unwrap(),
+            vec!["type", "", "x"]
+        );
+        assert!(split_command("interact click \"#play").is_err());
+        assert!(split_command("   ").is_err());
+    }
+}
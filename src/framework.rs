@@ -0,0 +1,181 @@
+//! Framework detection and framework-aware input events
+//!
+//! Setting a value from script only reaches a component if it arrives as the
+//! events its framework listens for. React tracks values through the native
+//! setter and `input`; Vue's `v-model` wants a composed `InputEvent` and skips
+//! input while a composition is open; Angular forms also mark controls touched
+//! on blur and apply changes once the zone settles.
+
+use serde::{Deserialize, Serialize};
+
+/// Frameworks with their own input event handling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framework {
+    React,
+    Vue,
+    Angular,
+}
+
+impl std::fmt::Display for Framework {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::React => write!(f, "react"),
+            Self::Vue => write!(f, "vue"),
+            Self::Angular => write!(f, "angular"),
+        }
+    }
+}
+
+impl std::str::FromStr for Framework {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "react" => Ok(Self::React),
+            "vue" => Ok(Self::Vue),
+            "angular" => Ok(Self::Angular),
+            other => Err(format!(
+                "Unknown framework \"{}\" (expected react, vue or angular)",
+                other
+            )),
+        }
+    }
+}
+
+/// What `debug framework` found on the page
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetectedFramework {
+    /// react, vue, angular, angularjs or svelte
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// Meta-framework on top (next, nuxt)
+    pub meta: Option<String>,
+    pub meta_version: Option<String>,
+}
+
+impl DetectedFramework {
+    /// Input event style for the detected framework (plain events otherwise)
+    pub fn input_style(&self) -> Option<Framework> {
+        self.name.as_deref().and_then(|name| name.parse().ok())
+    }
+}
+
+/// JS function returning a `DetectedFramework`-shaped object
+///
+/// Reads the markers the framework devtools use: React fibers on root
+/// containers, Vue app instances, Angular's `ng-version` attribute.
+pub const DETECT_FRAMEWORK: &str = r"(function() {
+    const body = document.body;
+    const roots = body
+        ? [body, ...body.children, document.getElementById('__next'),
+           document.getElementById('root'), document.getElementById('app')].filter(Boolean)
+        : [];
+    const hasKey = (el, prefix) => Object.keys(el).some(k => k.startsWith(prefix));
+    let meta = null;
+    let metaVersion = null;
+    if (document.getElementById('__NEXT_DATA__') || window.__next_f) {
+        meta = 'next';
+        metaVersion = (window.next && window.next.version) || null;
+    } else if (window.__NUXT__ || document.getElementById('__NUXT_DATA__')) {
+        meta = 'nuxt';
+    }
+    const result = (name, version) => ({ name, version: version || null, meta, meta_version: metaVersion });
+
+    const ng = document.querySelector('[ng-version]');
+    if (ng) return result('angular', ng.getAttribute('ng-version'));
+    if (window.angular && window.angular.version) return result('angularjs', window.angular.version.full);
+
+    const vueRoot = roots.find(el => el.__vue_app__ || el.__vue__)
+        || document.querySelector('[data-v-app]');
+    if (vueRoot || meta === 'nuxt') {
+        const app = vueRoot && vueRoot.__vue_app__;
+        const legacy = vueRoot && vueRoot.__vue__;
+        return result('vue', (app && app.version)
+            || (legacy && legacy.$root.constructor.version)
+            || (window.Vue && window.Vue.version));
+    }
+
+    const react = roots.some(el => el._reactRootContainer
+        || hasKey(el, '__reactContainer$') || hasKey(el, '__reactFiber$'))
+        || document.querySelector('[data-reactroot]');
+    if (react || meta === 'next') {
+        const hook = window.__REACT_DEVTOOLS_GLOBAL_HOOK__;
+        const renderer = hook && hook.renderers && [...hook.renderers.values()][0];
+        return result('react', (renderer && renderer.version) || (window.React && window.React.version));
+    }
+
+    if (window.__svelte && window.__svelte.v) return result('svelte', [...window.__svelte.v][0]);
+    return result(null, null);
+})";
+
+/// JS function `(el, data)` that fires the value-change events a framework
+/// listens for, after the value has been set; returns the framework used
+///
+/// Without a hint the framework is detected in the page.
+pub fn input_events_script(hint: Option<Framework>) -> String {
+    format!(
+        r"(function(el, data) {{
+            const framework = {} || ({})().name;
+            const inputEvent = () => new InputEvent('input', {{
+                bubbles: true, composed: true, inputType: 'insertText', data
+            }});
+            if (framework === 'vue') {{
+                // v-model ignores input while it believes a composition is open
+                el.composing = false;
+                el.dispatchEvent(inputEvent());
+                el.dispatchEvent(new Event('change', {{ bubbles: true }}));
+            }} else if (framework === 'angular') {{
+                el.dispatchEvent(inputEvent());
+                el.dispatchEvent(new Event('change', {{ bubbles: true }}));
+                // Marks the control touched and flushes updateOn: 'blur'
+                el.dispatchEvent(new FocusEvent('blur'));
+                el.dispatchEvent(new FocusEvent('focusout', {{ bubbles: true }}));
+            }} else {{
+                // React's value tracker sees the native setter; onChange listens to input
+                if (el.tagName !== 'SELECT') el.dispatchEvent(new Event('input', {{ bubbles: true }}));
+                el.dispatchEvent(new Event('change', {{ bubbles: true }}));
+            }}
+            return framework || null;
+        }})",
+        hint.map_or_else(|| "null".to_string(), |f| format!("'{}'", f)),
+        DETECT_FRAMEWORK
+    )
+}
+
+/// JS promise resolving once every Angular zone is stable (at most 2s)
+pub const ANGULAR_STABLE: &str = r"new Promise(resolve => {
+    const all = window.getAllAngularTestabilities ? window.getAllAngularTestabilities() : [];
+    if (!all.length) return resolve(true);
+    let left = all.length;
+    all.forEach(t => t.whenStable(() => { if (--left === 0) resolve(true); }));
+    setTimeout(() => resolve(false), 2000);
+})";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_framework() {
+        assert_eq!("React".parse::<Framework>().unwrap(), Framework::React);
+        assert_eq!("angular".parse::<Framework>().unwrap(), Framework::Angular);
+        assert!("svelte".parse::<Framework>().is_err());
+
+        let detected = DetectedFramework {
+            name: Some("vue".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(detected.input_style(), Some(Framework::Vue));
+        let svelte = DetectedFramework {
+            name: Some("svelte".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(svelte.input_style(), None);
+    }
+
+    #[test]
+    fn test_input_events_script() {
+        assert!(input_events_script(Some(Framework::Vue)).contains("const framework = 'vue' ||"));
+        assert!(input_events_script(None).contains("const framework = null || (function()"));
+    }
+}
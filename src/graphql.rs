@@ -0,0 +1,230 @@
+//! GraphQL operations in captured fetch/XHR calls
+//!
+//! `debug xhr --decode-graphql` shows each operation as its name, variables
+//! and errors instead of the raw bodies. A request is GraphQL when its POST
+//! body (or, for GET, its URL) carries a `query` or a persisted-query hash.
+//! Batched requests are arrays of operations, answered by an array of
+//! responses in the same order.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::cdp::XhrCall;
+
+/// One GraphQL operation and what came back for it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphqlOperation {
+    /// "query", "mutation" or "subscription"
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub variables: Value,
+    /// Error messages, with their paths
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+    /// Top-level fields of the response's `data`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub data: Vec<String>,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<i64>,
+    pub duration_ms: u64,
+}
+
+/// The GraphQL operations in `call`, empty when it isn't GraphQL
+pub fn decode(call: &XhrCall) -> Vec<GraphqlOperation> {
+    let requests = match &call.request_body {
+        Some(Value::Array(items)) => items.clone(),
+        Some(body @ Value::Object(_)) => vec![body.clone()],
+        _ => url_operation(&call.url).into_iter().collect(),
+    };
+    if requests.is_empty() || !requests.iter().all(is_operation) {
+        return Vec::new();
+    }
+    let responses = match &call.response_body {
+        Some(Value::Array(items)) => items.clone(),
+        Some(body @ Value::Object(_)) => vec![body.clone()],
+        _ => Vec::new(),
+    };
+
+    requests
+        .iter()
+        .enumerate()
+        .map(|(i, request)| {
+            let query = request.get("query").and_then(Value::as_str).unwrap_or("");
+            let (kind, parsed_name) = operation_header(query);
+            let name = request
+                .get("operationName")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .or(parsed_name);
+            let response = responses.get(i);
+            GraphqlOperation {
+                kind,
+                name,
+                variables: request.get("variables").cloned().unwrap_or(Value::Null),
+                errors: response.map(response_errors).unwrap_or_default(),
+                data: response
+                    .and_then(|response| response.get("data"))
+                    .and_then(Value::as_object)
+                    .map(|data| data.keys().cloned().collect())
+                    .unwrap_or_default(),
+                url: call.url.clone(),
+                status: call.status,
+                duration_ms: call.duration_ms,
+            }
+        })
+        .collect()
+}
+
+/// Whether a request body entry is a GraphQL operation
+fn is_operation(request: &Value) -> bool {
+    request.get("query").is_some_and(Value::is_string)
+        || request
+            .pointer("/extensions/persistedQuery")
+            .is_some_and(Value::is_object)
+}
+
+/// Operation kind and name from the query text (`{ ... }` is an anonymous query)
+fn operation_header(query: &str) -> (String, Option<String>) {
+    regex::Regex::new(r"(?:^|[\s}])(query|mutation|subscription)\b\s*([_A-Za-z][_0-9A-Za-z]*)?")
+        .ok()
+        .and_then(|re| re.captures(query))
+        .map(|caps| {
+            let name = caps.get(2).map(|name| name.as_str().to_string());
+            (caps[1].to_string(), name)
+        })
+        .unwrap_or_else(|| ("query".to_string(), None))
+}
+
+/// "message (at path.to.field)" for each error in a response
+fn response_errors(response: &Value) -> Vec<String> {
+    let Some(errors) = response.get("errors").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    errors
+        .iter()
+        .map(|error| {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .map_or_else(|| error.to_string(), str::to_string);
+            let path: Vec<String> = error
+                .get("path")
+                .and_then(Value::as_array)
+                .map(|path| {
+                    path.iter()
+                        .map(|part| {
+                            part.as_str()
+                                .map_or_else(|| part.to_string(), str::to_string)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            if path.is_empty() {
+                message
+            } else {
+                format!("{} (at {})", message, path.join("."))
+            }
+        })
+        .collect()
+}
+
+/// The operation in a GET request's query string
+fn url_operation(url: &str) -> Option<Value> {
+    let (_, query) = url.split_once('?')?;
+    let mut operation = serde_json::Map::new();
+    for pair in query.split('&') {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value);
+        let value = match key {
+            "query" | "operationName" => Value::String(value),
+            "variables" | "extensions" => serde_json::from_str(&value).ok()?,
+            _ => continue,
+        };
+        operation.insert(key.to_string(), value);
+    }
+    Some(Value::Object(operation))
+}
+
+/// Decode `%XX` escapes and `+` in a query string value
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_decode_batched_operations() {
+        let call = XhrCall {
+            kind: "fetch".to_string(),
+            method: "POST".to_string(),
+            url: "https://shop.test/graphql".to_string(),
+            status: Some(200),
+            request_body: Some(json!([
+                {
+                    "query": "mutation AddToCart($id: ID!) { addToCart(id: $id) { total } }",
+                    "variables": { "id": "sku-1" }
+                },
+                { "operationName": "Cart", "query": "{ cart { total } }" }
+            ])),
+            response_body: Some(json!([
+                { "errors": [{ "message": "Out of stock", "path": ["addToCart", 0] }] },
+                { "data": { "cart": { "total": 0 } } }
+            ])),
+            ..XhrCall::default()
+        };
+        let operations = decode(&call);
+        assert_eq!(operations.len(), 2);
+        assert_eq!(operations[0].kind, "mutation");
+        assert_eq!(operations[0].name.as_deref(), Some("AddToCart"));
+        assert_eq!(operations[0].variables["id"], "sku-1");
+        assert_eq!(operations[0].errors, vec!["Out of stock (at addToCart.0)"]);
+        assert_eq!(operations[1].kind, "query");
+        assert_eq!(operations[1].name.as_deref(), Some("Cart"));
+        assert_eq!(operations[1].data, vec!["cart"]);
+
+        let rest = XhrCall {
+            request_body: Some(json!({ "name": "Ada" })),
+            ..call
+        };
+        assert!(decode(&rest).is_empty());
+    }
+
+    #[test]
+    fn test_decode_get_request() {
+        let call = XhrCall {
+            method: "GET".to_string(),
+            url: "https://shop.test/graphql?query=query%20Product%28%24id%3AID%29%7Bproduct%7D&variables=%7B%22id%22%3A%227%22%7D".to_string(),
+            ..XhrCall::default()
+        };
+        let operations = decode(&call);
+        assert_eq!(operations[0].name.as_deref(), Some("Product"));
+        assert_eq!(operations[0].variables, json!({ "id": "7" }));
+        assert_eq!(percent_decode("a+b%2Fc%zz"), "a b/c%zz");
+    }
+}
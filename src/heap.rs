@@ -0,0 +1,495 @@
+//! Heap snapshot summaries for `debug heap`
+//!
+//! A V8 heap snapshot runs to hundreds of megabytes, so only a summary is
+//! kept: for each constructor, how many objects there are, their own (shallow)
+//! size and the size they retain. Retained sizes come from the dominator tree
+//! of the object graph, with weak edges ignored, as in DevTools. Summaries are
+//! saved in `.domguard/heap/<id>.json`; comparing two is how to find what a
+//! repeated flow leaks.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+
+/// Constructors kept in a summary, largest retained size first
+const MAX_CLASSES: usize = 1000;
+
+/// What a heap snapshot holds, per constructor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeapSummary {
+    pub id: String,
+    pub taken_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Bytes of every object reachable from the roots
+    pub total_bytes: u64,
+    pub objects: u64,
+    pub classes: Vec<ClassStats>,
+}
+
+/// Objects of one constructor (or a kind like "(string)" or "(closure)")
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassStats {
+    pub name: String,
+    pub count: u64,
+    pub self_size: u64,
+    /// Bytes freed if every object of this constructor went away (an object
+    /// dominated by one of the same constructor is counted through it)
+    pub retained_size: u64,
+}
+
+impl HeapSummary {
+    /// Summarize a `.heapsnapshot` taken just now
+    pub fn from_snapshot(json: &str, url: Option<String>) -> Result<Self> {
+        let graph = HeapGraph::parse(json)?;
+        let taken_at = Utc::now();
+        Ok(Self {
+            id: taken_at.timestamp().to_string(),
+            taken_at,
+            url,
+            ..graph.summarize()
+        })
+    }
+
+    /// Where summaries are saved
+    pub fn dir() -> PathBuf {
+        Config::find_domguard_dir()
+            .unwrap_or_else(Config::domguard_dir)
+            .join("heap")
+    }
+
+    /// Save as `<dir>/<id>.json`
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.json", self.id));
+        crate::storage::write_atomic(&path, &serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    /// A saved summary, by id or by path
+    pub fn load(dir: &Path, id_or_path: &str) -> Result<Self> {
+        let path = if Path::new(id_or_path).is_file() {
+            PathBuf::from(id_or_path)
+        } else if !id_or_path.is_empty()
+            && id_or_path
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            dir.join(format!("{}.json", id_or_path))
+        } else {
+            bail!("Invalid heap snapshot id \"{}\"", id_or_path);
+        };
+        let content = std::fs::read_to_string(&path).with_context(|| {
+            format!(
+                "No heap snapshot \"{}\" (take one with 'domguard debug heap --snapshot')",
+                id_or_path
+            )
+        })?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid heap snapshot summary {}", path.display()))
+    }
+}
+
+/// How a constructor changed between two summaries
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClassGrowth {
+    pub name: String,
+    pub count: u64,
+    pub count_delta: i64,
+    pub self_size_delta: i64,
+    pub retained_size: u64,
+    pub retained_delta: i64,
+}
+
+/// Differences between an earlier summary and a later one
+#[derive(Debug, Clone, Serialize)]
+pub struct HeapComparison {
+    pub baseline: String,
+    pub current: String,
+    pub total_delta: i64,
+    pub objects_delta: i64,
+    /// Constructors with more objects or retained bytes, most retained growth first
+    pub growing: Vec<ClassGrowth>,
+}
+
+/// Compare `current` with the earlier `baseline`
+pub fn compare(baseline: &HeapSummary, current: &HeapSummary) -> HeapComparison {
+    let before: HashMap<&str, &ClassStats> = baseline
+        .classes
+        .iter()
+        .map(|class| (class.name.as_str(), class))
+        .collect();
+    let delta = |after: u64, before: u64| {
+        i64::try_from(after).unwrap_or(i64::MAX) - i64::try_from(before).unwrap_or(i64::MAX)
+    };
+    let mut growing: Vec<ClassGrowth> = current
+        .classes
+        .iter()
+        .map(|class| {
+            let (count, self_size, retained) = before
+                .get(class.name.as_str())
+                .map_or((0, 0, 0), |b| (b.count, b.self_size, b.retained_size));
+            ClassGrowth {
+                name: class.name.clone(),
+                count: class.count,
+                count_delta: delta(class.count, count),
+                self_size_delta: delta(class.self_size, self_size),
+                retained_size: class.retained_size,
+                retained_delta: delta(class.retained_size, retained),
+            }
+        })
+        .filter(|growth| growth.count_delta > 0 || growth.retained_delta > 0)
+        .collect();
+    growing.sort_by(|a, b| {
+        b.retained_delta
+            .cmp(&a.retained_delta)
+            .then(b.count_delta.cmp(&a.count_delta))
+    });
+    HeapComparison {
+        baseline: baseline.id.clone(),
+        current: current.id.clone(),
+        total_delta: delta(current.total_bytes, baseline.total_bytes),
+        objects_delta: delta(current.objects, baseline.objects),
+        growing,
+    }
+}
+
+#[derive(Deserialize)]
+struct RawSnapshot {
+    snapshot: RawHeader,
+    nodes: Vec<u64>,
+    edges: Vec<u64>,
+    strings: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RawHeader {
+    meta: RawMeta,
+}
+
+#[derive(Deserialize)]
+struct RawMeta {
+    node_fields: Vec<String>,
+    node_types: Vec<serde_json::Value>,
+    edge_fields: Vec<String>,
+    edge_types: Vec<serde_json::Value>,
+}
+
+/// The object graph of a snapshot; node 0 is the synthetic root
+struct HeapGraph {
+    /// Constructor index per node, `None` for synthetic nodes
+    class: Vec<Option<usize>>,
+    class_names: Vec<String>,
+    self_size: Vec<u64>,
+    /// Node `i`'s strong edges are `targets[first_edge[i]..first_edge[i + 1]]`
+    first_edge: Vec<usize>,
+    targets: Vec<usize>,
+}
+
+impl HeapGraph {
+    fn parse(json: &str) -> Result<Self> {
+        let raw: RawSnapshot = serde_json::from_str(json).context("Not a V8 heap snapshot")?;
+        let meta = &raw.snapshot.meta;
+        let field = |fields: &[String], name: &str| {
+            fields
+                .iter()
+                .position(|field| field == name)
+                .ok_or_else(|| anyhow!("Heap snapshot has no \"{}\" field", name))
+        };
+        let enum_names = |types: &[serde_json::Value]| -> Vec<String> {
+            types
+                .first()
+                .and_then(serde_json::Value::as_array)
+                .map(|names| {
+                    names
+                        .iter()
+                        .map(|name| name.as_str().unwrap_or_default().to_string())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let node_len = meta.node_fields.len();
+        let edge_len = meta.edge_fields.len();
+        if node_len == 0 || edge_len == 0 || raw.nodes.is_empty() {
+            bail!("Heap snapshot has no nodes");
+        }
+        let (type_at, name_at, size_at, edges_at) = (
+            field(&meta.node_fields, "type")?,
+            field(&meta.node_fields, "name")?,
+            field(&meta.node_fields, "self_size")?,
+            field(&meta.node_fields, "edge_count")?,
+        );
+        let (edge_type_at, to_at) = (
+            field(&meta.edge_fields, "type")?,
+            field(&meta.edge_fields, "to_node")?,
+        );
+        let node_types = enum_names(&meta.node_types);
+        let weak = enum_names(&meta.edge_types)
+            .iter()
+            .position(|name| name == "weak");
+
+        let count = raw.nodes.len() / node_len;
+        let mut class_index: HashMap<String, usize> = HashMap::new();
+        let mut class_names = Vec::new();
+        let mut graph = Self {
+            class: Vec::with_capacity(count),
+            class_names: Vec::new(),
+            self_size: Vec::with_capacity(count),
+            first_edge: Vec::with_capacity(count + 1),
+            targets: Vec::new(),
+        };
+        let mut edge = 0;
+        for node in raw.nodes.chunks_exact(node_len) {
+            let kind = node_types
+                .get(node[type_at] as usize)
+                .map_or("", String::as_str);
+            let name = raw
+                .strings
+                .get(node[name_at] as usize)
+                .map_or("", String::as_str);
+            graph.class.push(class_label(kind, name).map(|label| {
+                *class_index.entry(label.clone()).or_insert_with(|| {
+                    class_names.push(label);
+                    class_names.len() - 1
+                })
+            }));
+            graph.self_size.push(node[size_at]);
+
+            graph.first_edge.push(graph.targets.len());
+            for _ in 0..node[edges_at] {
+                let Some(fields) = raw.edges.get(edge * edge_len..(edge + 1) * edge_len) else {
+                    bail!("Heap snapshot edges are truncated");
+                };
+                edge += 1;
+                if Some(fields[edge_type_at] as usize) != weak {
+                    let to = fields[to_at] as usize / node_len;
+                    if to < count {
+                        graph.targets.push(to);
+                    }
+                }
+            }
+        }
+        graph.first_edge.push(graph.targets.len());
+        graph.class_names = class_names;
+        Ok(graph)
+    }
+
+    fn successors(&self, node: usize) -> &[usize] {
+        &self.targets[self.first_edge[node]..self.first_edge[node + 1]]
+    }
+
+    /// Per-constructor totals over the nodes reachable from the root
+    fn summarize(&self) -> HeapSummary {
+        let (order, idom) = self.dominators();
+        let mut retained = self.self_size.clone();
+        // A dominator finishes after everything it dominates
+        for &node in &order {
+            if node != 0 {
+                retained[idom[node]] += retained[node];
+            }
+        }
+
+        let mut classes: Vec<ClassStats> = self
+            .class_names
+            .iter()
+            .map(|name| ClassStats {
+                name: name.clone(),
+                count: 0,
+                self_size: 0,
+                retained_size: 0,
+            })
+            .collect();
+        let (mut total_bytes, mut objects) = (0, 0);
+        for &node in &order {
+            total_bytes += self.self_size[node];
+            let Some(class) = self.class[node] else {
+                continue;
+            };
+            objects += 1;
+            let stats = &mut classes[class];
+            stats.count += 1;
+            stats.self_size += self.self_size[node];
+            if node == 0 || self.class[idom[node]] != Some(class) {
+                stats.retained_size += retained[node];
+            }
+        }
+        classes.retain(|class| class.count > 0);
+        classes.sort_by(|a, b| {
+            b.retained_size
+                .cmp(&a.retained_size)
+                .then(b.self_size.cmp(&a.self_size))
+        });
+        classes.truncate(MAX_CLASSES);
+
+        HeapSummary {
+            id: String::new(),
+            taken_at: DateTime::<Utc>::default(),
+            url: None,
+            total_bytes,
+            objects,
+            classes,
+        }
+    }
+
+    /// Reachable nodes in DFS postorder, and each one's immediate dominator
+    ///
+    /// The iterative algorithm of Cooper, Harvey and Kennedy.
+    fn dominators(&self) -> (Vec<usize>, Vec<usize>) {
+        const UNSET: usize = usize::MAX;
+        let count = self.class.len();
+
+        let mut post = vec![UNSET; count];
+        let mut order = Vec::new();
+        let mut visited = vec![false; count];
+        visited[0] = true;
+        let mut stack = vec![(0, 0)];
+        while let Some(top) = stack.last_mut() {
+            let (node, next) = *top;
+            if let Some(&to) = self.successors(node).get(next) {
+                top.1 += 1;
+                if !visited[to] {
+                    visited[to] = true;
+                    stack.push((to, 0));
+                }
+            } else {
+                post[node] = order.len();
+                order.push(node);
+                stack.pop();
+            }
+        }
+
+        let mut preds: Vec<Vec<usize>> = vec![Vec::new(); count];
+        for &node in &order {
+            for &to in self.successors(node) {
+                preds[to].push(node);
+            }
+        }
+
+        let mut idom = vec![UNSET; count];
+        idom[0] = 0;
+        let intersect = |idom: &[usize], mut a: usize, mut b: usize| {
+            while a != b {
+                while post[a] < post[b] {
+                    a = idom[a];
+                }
+                while post[b] < post[a] {
+                    b = idom[b];
+                }
+            }
+            a
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in order.iter().rev().skip(1) {
+                let mut new_idom = UNSET;
+                for &pred in &preds[node] {
+                    if idom[pred] != UNSET {
+                        new_idom = if new_idom == UNSET {
+                            pred
+                        } else {
+                            intersect(&idom, pred, new_idom)
+                        };
+                    }
+                }
+                if new_idom != UNSET && idom[node] != new_idom {
+                    idom[node] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+        (order, idom)
+    }
+}
+
+/// What objects of a node type are grouped under, as DevTools names them
+fn class_label(kind: &str, name: &str) -> Option<String> {
+    Some(match kind {
+        "object" | "native" if name.is_empty() => "(anonymous)".to_string(),
+        "object" | "native" => name.to_string(),
+        "synthetic" => return None,
+        "hidden" | "object shape" => "(system)".to_string(),
+        "code" => "(compiled code)".to_string(),
+        "string" | "concatenated string" | "sliced string" => "(string)".to_string(),
+        other => format!("({})", other),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// root -> Window -> Cart -> Item, Item; Window -> Item (third); Cart -weak-> Window
+    fn snapshot() -> String {
+        json!({
+            "snapshot": { "meta": {
+                "node_fields": ["type", "name", "id", "self_size", "edge_count"],
+                "node_types": [["hidden", "object", "string", "synthetic"], "string", "number", "number", "number"],
+                "edge_fields": ["type", "name_or_index", "to_node"],
+                "edge_types": [["property", "element", "weak"], "string", "node"]
+            }},
+            "nodes": [
+                3, 0, 1, 0, 1,
+                1, 1, 2, 100, 2,
+                1, 2, 3, 50, 3,
+                1, 3, 4, 10, 0,
+                1, 3, 5, 10, 0,
+                1, 3, 6, 10, 0
+            ],
+            "edges": [
+                0, 0, 5,
+                0, 0, 10, 0, 0, 25,
+                1, 0, 15, 1, 1, 20, 2, 0, 5
+            ],
+            "strings": ["(root)", "Window", "Cart", "Item"]
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_summarize_snapshot() {
+        let summary = HeapGraph::parse(&snapshot()).unwrap().summarize();
+        assert_eq!(summary.total_bytes, 180);
+        assert_eq!(summary.objects, 5);
+        let names: Vec<&str> = summary.classes.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Window", "Cart", "Item"]);
+        assert_eq!(summary.classes[0].retained_size, 180);
+        // The weak edge back to Window doesn't keep it alive
+        assert_eq!(summary.classes[1].retained_size, 70);
+        assert_eq!(summary.classes[2].count, 3);
+        assert_eq!(summary.classes[2].retained_size, 30);
+        assert!(HeapGraph::parse("{}").is_err());
+    }
+
+    #[test]
+    fn test_compare_and_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let baseline = HeapSummary {
+            id: "1".to_string(),
+            ..HeapGraph::parse(&snapshot()).unwrap().summarize()
+        };
+        let mut current = HeapSummary {
+            id: "2".to_string(),
+            ..baseline.clone()
+        };
+        current.classes[2].count = 7;
+        current.classes[2].retained_size = 70;
+        current.total_bytes += 40;
+        current.save(dir.path()).unwrap();
+
+        let current = HeapSummary::load(dir.path(), "2").unwrap();
+        let comparison = compare(&baseline, &current);
+        assert_eq!(comparison.total_delta, 40);
+        assert_eq!(comparison.growing.len(), 1);
+        assert_eq!(comparison.growing[0].name, "Item");
+        assert_eq!(comparison.growing[0].count_delta, 4);
+        assert_eq!(comparison.growing[0].retained_delta, 40);
+        assert!(HeapSummary::load(dir.path(), "../x").is_err());
+        assert!(HeapSummary::load(dir.path(), "3").is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! The small HTTP client behind webhooks, trace export and DevTools discovery
+//!
+//! Plain `http://` requests go over a std TCP stream as HTTP/1.0, so the
+//! response is neither chunked nor kept alive. std has no TLS, so `https://`
+//! requests run curl instead, which then has to be in PATH.
+
+use anyhow::{anyhow, Context, Result};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Where an `http://` or `https://` URL points
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpUrl {
+    pub tls: bool,
+    pub host: String,
+    pub port: u16,
+    /// Path and query, starting with `/`
+    pub path: String,
+}
+
+impl HttpUrl {
+    pub fn parse(url: &str) -> Result<Self> {
+        let (tls, rest) = if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else {
+            return Err(anyhow!("URL must start with http:// or https://: {}", url));
+        };
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(i) if rest[i..].starts_with('/') => (&rest[..i], rest[i..].to_string()),
+            Some(i) => (&rest[..i], format!("/{}", &rest[i..])),
+            None => (rest, "/".to_string()),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => (
+                host,
+                port.parse()
+                    .map_err(|_| anyhow!("Invalid port in URL: {}", url))?,
+            ),
+            _ => (authority, if tls { 443 } else { 80 }),
+        };
+        if host.is_empty() {
+            return Err(anyhow!("URL has no host: {}", url));
+        }
+        Ok(Self {
+            tls,
+            host: host.to_string(),
+            port,
+            path,
+        })
+    }
+}
+
+/// Status and body of a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// What a request sends besides its method and URL
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Request<'a> {
+    /// Body and its content type
+    pub body: Option<(&'a str, &'a str)>,
+    /// `Accept` header
+    pub accept: Option<&'a str>,
+}
+
+/// Send a request, failing only when no response arrives within `timeout`
+pub fn send(method: &str, url: &str, request: Request<'_>, timeout: Duration) -> Result<Response> {
+    let target = HttpUrl::parse(url)?;
+    if target.tls {
+        send_with_curl(method, url, request, timeout)
+    } else {
+        send_plain(method, &target, request, timeout)
+            .with_context(|| format!("{} {} failed", method, url))
+    }
+}
+
+/// POST a JSON body, succeeding on any 2xx response
+pub fn post_json(url: &str, body: &str, timeout: Duration) -> Result<()> {
+    let request = Request {
+        body: Some(("application/json", body)),
+        ..Request::default()
+    };
+    let response = send("POST", url, request, timeout)?;
+    if !response.is_success() {
+        return Err(anyhow!("POST to {} failed: HTTP {}", url, response.status));
+    }
+    Ok(())
+}
+
+/// GET a JSON document, succeeding on 200 only
+pub fn get_json(url: &str, timeout: Duration) -> Result<String> {
+    let request = Request {
+        accept: Some("application/json"),
+        ..Request::default()
+    };
+    let response = send("GET", url, request, timeout)?;
+    if response.status != 200 {
+        return Err(anyhow!("GET {} failed: HTTP {}", url, response.status));
+    }
+    Ok(response.body)
+}
+
+fn send_plain(
+    method: &str,
+    target: &HttpUrl,
+    request: Request<'_>,
+    timeout: Duration,
+) -> Result<Response> {
+    let addr = (target.host.as_str(), target.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("Could not resolve {}", target.host))?;
+    let mut stream = TcpStream::connect_timeout(&addr, timeout)
+        .with_context(|| format!("{}:{} unreachable", target.host, target.port))?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let mut headers = vec![format!("Host: {}:{}", target.host, target.port)];
+    if let Some(accept) = request.accept {
+        headers.push(format!("Accept: {}", accept));
+    }
+    let body = match request.body {
+        Some((content_type, body)) => {
+            headers.push(format!("Content-Type: {}", content_type));
+            headers.push(format!("Content-Length: {}", body.len()));
+            body
+        }
+        None => "",
+    };
+    write!(
+        stream,
+        "{} {} HTTP/1.0\r\n{}\r\n\r\n{}",
+        method,
+        target.path,
+        headers.join("\r\n"),
+        body
+    )?;
+
+    let mut raw = Vec::new();
+    stream.read_to_end(&mut raw)?;
+    parse_response(&String::from_utf8_lossy(&raw))
+}
+
+fn send_with_curl(
+    method: &str,
+    url: &str,
+    request: Request<'_>,
+    timeout: Duration,
+) -> Result<Response> {
+    let curl = which::which("curl")
+        .map_err(|_| anyhow!("curl not found in PATH (needed for https:// requests)"))?;
+    let mut command = std::process::Command::new(curl);
+    command
+        .args(["-sS", "-X", method, "--max-time"])
+        .arg(format!("{:.3}", timeout.as_secs_f64()))
+        // The status goes on a line of its own after the body
+        .args(["-w", "\\n%{http_code}"]);
+    if let Some(accept) = request.accept {
+        command.arg("-H").arg(format!("Accept: {}", accept));
+    }
+    if let Some((content_type, body)) = request.body {
+        command
+            .arg("-H")
+            .arg(format!("Content-Type: {}", content_type))
+            .arg("--data-binary")
+            .arg(body);
+    }
+    let output = command.arg(url).output().context("Failed to run curl")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} {} failed: {}",
+            method,
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (body, status) = stdout
+        .rsplit_once('\n')
+        .ok_or_else(|| anyhow!("{} {} failed: no status from curl", method, url))?;
+    Ok(Response {
+        status: status
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("{} {} failed: no status from curl", method, url))?,
+        body: body.to_string(),
+    })
+}
+
+/// Split a raw HTTP response into its status and body
+fn parse_response(raw: &str) -> Result<Response> {
+    let (head, body) = raw
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| anyhow!("Malformed HTTP response"))?;
+    let status_line = head.lines().next().unwrap_or_default();
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| anyhow!("Malformed HTTP status: {}", status_line))?;
+    Ok(Response {
+        status,
+        body: body.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(
+            HttpUrl::parse("http://localhost:8000/hook").unwrap(),
+            HttpUrl {
+                tls: false,
+                host: "localhost".to_string(),
+                port: 8000,
+                path: "/hook".to_string(),
+            }
+        );
+        let tls = HttpUrl::parse("https://hooks.example.com?token=x").unwrap();
+        assert_eq!((tls.port, tls.path.as_str()), (443, "/?token=x"));
+        assert!(HttpUrl::parse("localhost:8000/hook").is_err());
+        assert!(HttpUrl::parse("http://localhost:port/").is_err());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let raw = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"a\":1}";
+        let response = parse_response(raw).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "{\"a\":1}");
+
+        let error = parse_response("HTTP/1.1 500 Internal Server Error\r\n\r\nHost header");
+        assert!(!error.unwrap().is_success());
+        assert!(parse_response("garbage").is_err());
+    }
+
+    #[test]
+    fn test_post_json_to_local_server() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !String::from_utf8_lossy(&request).ends_with("{\"ok\":true}") {
+                let n = stream.read(&mut chunk).unwrap();
+                request.extend_from_slice(&chunk[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.0 204 No Content\r\n\r\n")
+                .unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        post_json(&url, "{\"ok\":true}", Duration::from_secs(5)).unwrap();
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.0\r\n"));
+        assert!(request.contains("Content-Length: 11\r\n"));
+    }
+}
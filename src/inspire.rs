@@ -3,21 +3,95 @@
 //! Analyzes colors, typography, spacing, and layout patterns
 
 use anyhow::{Context, Result};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::Path;
 
 use crate::cdp::CdpConnection;
 use crate::config::Config;
 use crate::output::{
-    AnimationInfo, ColorInfo, DesignInspiration, Formatter, LayoutInfo, SpacingInfo, TypographyInfo,
+    AnimationInfo, Breakpoint, ColorInfo, DesignInspiration, DesignTokens, ElementChange,
+    Formatter, LayoutChange, LayoutInfo, PaletteColor, ResponsiveCapture, ResponsiveReport,
+    SpacingInfo, TokenValue, TypographyInfo,
 };
 
+/// What to extract and where to put it
+#[derive(Debug, Clone, Default)]
+pub struct InspireOptions<'a> {
+    /// CSS selector for a specific component
+    pub component: Option<&'a str>,
+    /// Save the inspiration under this name
+    pub save_name: Option<&'a str>,
+    /// Print (and save) only the design tokens, in this format
+    pub tokens: Option<TokenFormat>,
+    /// Write the component as a standalone HTML/CSS bundle to this directory
+    pub export: Option<&'a Path>,
+    /// Capture the saved inspiration at these viewport widths
+    pub responsive: Option<&'a [u32]>,
+}
+
+/// Export format for design tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenFormat {
+    Json,
+    /// CSS custom properties on `:root`
+    Css,
+    /// A `theme.extend` fragment for tailwind.config.js
+    Tailwind,
+}
+
+impl std::fmt::Display for TokenFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json => write!(f, "json"),
+            Self::Css => write!(f, "css"),
+            Self::Tailwind => write!(f, "tailwind"),
+        }
+    }
+}
+
+impl std::str::FromStr for TokenFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "css" => Ok(Self::Css),
+            "tailwind" => Ok(Self::Tailwind),
+            other => Err(format!(
+                "Unknown token format \"{}\" (expected json, css or tailwind)",
+                other
+            )),
+        }
+    }
+}
+
+impl TokenFormat {
+    /// File name for the export in a saved inspiration
+    fn file_name(self) -> &'static str {
+        match self {
+            Self::Json => "tokens.json",
+            Self::Css => "tokens.css",
+            Self::Tailwind => "tailwind.tokens.js",
+        }
+    }
+
+    /// Render tokens in this format
+    pub fn render(self, tokens: &DesignTokens, url: &str) -> String {
+        match self {
+            Self::Json => serde_json::to_string_pretty(tokens).unwrap_or_default(),
+            Self::Css => tokens_to_css(tokens, url),
+            Self::Tailwind => tokens_to_tailwind(tokens, url),
+        }
+    }
+}
+
 /// Run inspire mode on a URL
 pub async fn run_inspire(
     cdp: &CdpConnection,
     config: &Config,
     url: &str,
-    component: Option<&str>,
-    save_name: Option<&str>,
+    options: &InspireOptions<'_>,
     formatter: &Formatter,
 ) -> Result<()> {
     // Navigate to URL
@@ -26,11 +100,64 @@ pub async fn run_inspire(
     // Wait for page to load
     tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
 
+    if let Some(dir) = options.export {
+        let selector = options.component.unwrap_or("body");
+        let manifest = export_bundle(cdp, url, selector, dir).await?;
+        if formatter.is_json() {
+            formatter.output_json(&manifest);
+        } else {
+            formatter.success(&format!("Bundle exported to {}", dir.display()));
+            formatter.kv("Rules", &manifest.rules.to_string());
+            formatter.kv("Assets", &manifest.assets.len().to_string());
+            for asset in manifest.assets.iter().filter(|a| a.file.is_none()) {
+                formatter.warning(&format!("Kept remote: {}", asset.url));
+            }
+            formatter.hint(&format!(
+                "Open {} in a browser",
+                dir.join("index.html").display()
+            ));
+        }
+        return Ok(());
+    }
+
     // Extract design data
-    let inspiration = extract_design(cdp, url, component).await?;
+    let inspiration = extract_design(cdp, url, options.component).await?;
+
+    if let Some(format) = options.tokens {
+        let content = format.render(&inspiration.tokens, url);
+        let saved = match options.save_name {
+            Some(name) => {
+                let save_dir = config.inspirations_dir().join(name);
+                std::fs::create_dir_all(&save_dir)?;
+                let path = save_dir.join(format.file_name());
+                std::fs::write(&path, &content)?;
+                Some(path.to_string_lossy().to_string())
+            }
+            None => None,
+        };
+
+        if formatter.is_json() {
+            if format == TokenFormat::Json {
+                formatter.output_json(&inspiration.tokens);
+            } else {
+                formatter.output_json(&serde_json::json!({
+                    "format": format.to_string(),
+                    "content": content,
+                    "saved": saved
+                }));
+            }
+        } else {
+            println!("{}", content);
+            if let Some(path) = saved {
+                formatter.success(&format!("Tokens saved: {}", path));
+            }
+        }
+        return Ok(());
+    }
 
     // Save if requested
-    let screenshot_path = if let Some(name) = save_name {
+    let mut responsive = None;
+    let screenshot_path = if let Some(name) = options.save_name {
         let save_dir = config.inspirations_dir().join(name);
         std::fs::create_dir_all(&save_dir)?;
 
@@ -44,6 +171,18 @@ pub async fn run_inspire(
         let json = serde_json::to_string_pretty(&inspiration)?;
         std::fs::write(&json_path, json)?;
 
+        if let Some(widths) = options.responsive {
+            let report = capture_responsive(cdp, options.component, widths, &save_dir).await;
+            // Leave the viewport as we found it, even if a capture failed
+            cdp.clear_device_metrics().await?;
+            let report = report?;
+            std::fs::write(
+                save_dir.join("responsive.json"),
+                serde_json::to_string_pretty(&report)?,
+            )?;
+            responsive = Some(report);
+        }
+
         Some(screenshot_path.to_string_lossy().to_string())
     } else {
         None
@@ -57,7 +196,9 @@ pub async fn run_inspire(
         spacing: inspiration.spacing,
         layout: inspiration.layout,
         animations: inspiration.animations,
+        tokens: inspiration.tokens,
         screenshot_path,
+        responsive,
     };
 
     if formatter.is_json() {
@@ -69,18 +210,925 @@ pub async fn run_inspire(
     Ok(())
 }
 
-/// Internal design data (before screenshot path)
-#[derive(Debug, Serialize)]
+/// Internal design data (before screenshot path), as saved in design.json
+#[derive(Debug, Serialize, Deserialize)]
 struct DesignData {
     url: String,
+    #[serde(default)]
     colors: Vec<ColorInfo>,
+    #[serde(default)]
     typography: Vec<TypographyInfo>,
+    #[serde(default)]
     spacing: SpacingInfo,
+    #[serde(default)]
     layout: LayoutInfo,
+    #[serde(default)]
     animations: AnimationInfo,
+    // Saves from before design tokens existed have none
+    #[serde(default)]
+    tokens: DesignTokens,
+}
+
+/// A saved inspiration, as listed by `inspire list`
+#[derive(Debug, Clone, Serialize)]
+pub struct InspirationSummary {
+    pub name: String,
+    pub url: String,
+    pub saved_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub colors: usize,
+    pub fonts: usize,
+    pub has_screenshot: bool,
+    pub has_responsive: bool,
+}
+
+/// Values in one, the other or both of two inspirations
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ListDiff {
+    pub shared: Vec<String>,
+    pub only_a: Vec<String>,
+    pub only_b: Vec<String>,
+}
+
+/// Palette and typography differences between two saved inspirations
+#[derive(Debug, Clone, Serialize)]
+pub struct InspirationComparison {
+    pub a: String,
+    pub b: String,
+    pub palette: ListDiff,
+    pub font_families: ListDiff,
+    pub type_scale: ListDiff,
+    pub font_weights: ListDiff,
+}
+
+/// Directory of a saved inspiration, rejecting names that would escape the library
+fn inspiration_dir(library: &Path, name: &str) -> Result<std::path::PathBuf> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(anyhow::anyhow!("Invalid inspiration name \"{}\"", name));
+    }
+    let dir = library.join(name);
+    if !dir.join("design.json").is_file() {
+        return Err(anyhow::anyhow!(
+            "No saved inspiration \"{}\" (see 'domguard inspire list')",
+            name
+        ));
+    }
+    Ok(dir)
+}
+
+fn load_design(dir: &Path) -> Result<DesignData> {
+    let path = dir.join("design.json");
+    let json = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Saved inspirations in `library`, by name
+pub fn list_inspirations(library: &Path) -> Result<Vec<InspirationSummary>> {
+    let entries = match std::fs::read_dir(library) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", library.display()));
+        }
+    };
+
+    let mut summaries = Vec::new();
+    for entry in entries {
+        let dir = entry?.path();
+        let Ok(design) = load_design(&dir) else {
+            // Not an inspiration (or unreadable); `show` reports the details
+            continue;
+        };
+        let saved_at = std::fs::metadata(dir.join("design.json"))
+            .and_then(|m| m.modified())
+            .ok()
+            .map(chrono::DateTime::<chrono::Utc>::from);
+        summaries.push(InspirationSummary {
+            name: entry_name(&dir),
+            url: design.url,
+            saved_at,
+            colors: design.colors.len(),
+            fonts: design.typography.len(),
+            has_screenshot: dir.join("screenshot.png").is_file(),
+            has_responsive: dir.join("responsive.json").is_file(),
+        });
+    }
+    summaries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(summaries)
+}
+
+fn entry_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Load a saved inspiration, with its screenshot and responsive captures if present
+pub fn load_inspiration(library: &Path, name: &str) -> Result<DesignInspiration> {
+    let dir = inspiration_dir(library, name)?;
+    let design = load_design(&dir)?;
+
+    let screenshot = dir.join("screenshot.png");
+    let responsive = match std::fs::read_to_string(dir.join("responsive.json")) {
+        Ok(json) => Some(serde_json::from_str(&json).context("Failed to parse responsive.json")?),
+        Err(_) => None,
+    };
+
+    Ok(DesignInspiration {
+        url: design.url,
+        colors: design.colors,
+        typography: design.typography,
+        spacing: design.spacing,
+        layout: design.layout,
+        animations: design.animations,
+        tokens: design.tokens,
+        screenshot_path: screenshot
+            .is_file()
+            .then(|| screenshot.to_string_lossy().to_string()),
+        responsive,
+    })
+}
+
+/// Remove a saved inspiration and everything stored with it
+pub fn delete_inspiration(library: &Path, name: &str) -> Result<()> {
+    let dir = inspiration_dir(library, name)?;
+    std::fs::remove_dir_all(&dir).with_context(|| format!("Failed to delete {}", dir.display()))
+}
+
+/// Compare the palettes and typography of two saved inspirations
+pub fn compare_inspirations(library: &Path, a: &str, b: &str) -> Result<InspirationComparison> {
+    let first = load_design(&inspiration_dir(library, a)?)?;
+    let second = load_design(&inspiration_dir(library, b)?)?;
+    Ok(compare_designs(a, &first, b, &second))
+}
+
+fn compare_designs(
+    a: &str,
+    first: &DesignData,
+    b: &str,
+    second: &DesignData,
+) -> InspirationComparison {
+    let palette = |design: &DesignData| -> Vec<String> {
+        if design.tokens.palette.is_empty() {
+            design.colors.iter().map(|c| c.hex.to_lowercase()).collect()
+        } else {
+            design
+                .tokens
+                .palette
+                .iter()
+                .map(|c| c.hex.to_lowercase())
+                .collect()
+        }
+    };
+    let typography = |design: &DesignData, field: fn(&TypographyInfo) -> &String| -> Vec<String> {
+        design.typography.iter().map(|t| field(t).clone()).collect()
+    };
+    let type_scale = |design: &DesignData| -> Vec<String> {
+        if design.tokens.type_scale.is_empty() {
+            typography(design, |t| &t.font_size)
+        } else {
+            token_values(&design.tokens.type_scale)
+        }
+    };
+
+    InspirationComparison {
+        a: a.to_string(),
+        b: b.to_string(),
+        palette: list_diff(&palette(first), &palette(second)),
+        font_families: list_diff(
+            &typography(first, |t| &t.font_family),
+            &typography(second, |t| &t.font_family),
+        ),
+        type_scale: list_diff(&type_scale(first), &type_scale(second)),
+        font_weights: list_diff(
+            &typography(first, |t| &t.font_weight),
+            &typography(second, |t| &t.font_weight),
+        ),
+    }
+}
+
+/// Split two lists into shared and one-sided values, keeping first-seen order
+fn list_diff(a: &[String], b: &[String]) -> ListDiff {
+    let mut diff = ListDiff::default();
+    for value in a {
+        let target = if b.contains(value) {
+            &mut diff.shared
+        } else {
+            &mut diff.only_a
+        };
+        if !target.contains(value) {
+            target.push(value.clone());
+        }
+    }
+    for value in b {
+        if !a.contains(value) && !diff.only_b.contains(value) {
+            diff.only_b.push(value.clone());
+        }
+    }
+    diff
+}
+
+/// Token counts as collected by the page script
+#[derive(Debug, Default, Deserialize)]
+struct RawTokens {
+    #[serde(default)]
+    colors: Vec<ColorInfo>,
+    #[serde(default)]
+    font_stacks: Vec<TokenValue>,
+    #[serde(default)]
+    font_sizes: Vec<TokenValue>,
+    #[serde(default)]
+    spacing: Vec<TokenValue>,
+    #[serde(default)]
+    radii: Vec<TokenValue>,
+    #[serde(default)]
+    shadows: Vec<TokenValue>,
+    #[serde(default)]
+    media: Vec<RawMedia>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMedia {
+    query: String,
+    #[serde(default)]
+    matches: bool,
+}
+
+/// Most tokens kept per scale
+const MAX_TOKENS: usize = 16;
+
+/// Turn raw counts into sorted, capped scales
+fn build_tokens(raw: RawTokens) -> DesignTokens {
+    // One palette entry per color, across the properties it's used for
+    let mut palette: Vec<PaletteColor> = Vec::new();
+    for color in raw.colors {
+        match palette.iter_mut().find(|p| p.hex == color.hex) {
+            Some(entry) => {
+                entry.count += color.count;
+                if !entry.usages.contains(&color.usage) {
+                    entry.usages.push(color.usage);
+                }
+            }
+            None => palette.push(PaletteColor {
+                hex: color.hex,
+                count: color.count,
+                usages: vec![color.usage],
+            }),
+        }
+    }
+    palette.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.hex.cmp(&b.hex)));
+    palette.truncate(MAX_TOKENS * 2);
+
+    let mut breakpoints: Vec<Breakpoint> = raw
+        .media
+        .into_iter()
+        .filter_map(|media| {
+            let (min_width_px, max_width_px) = parse_width_query(&media.query);
+            (min_width_px.is_some() || max_width_px.is_some()).then_some(Breakpoint {
+                query: media.query,
+                min_width_px,
+                max_width_px,
+                matches: media.matches,
+            })
+        })
+        .collect();
+    breakpoints.sort_by_key(|b| b.min_width_px.or(b.max_width_px).unwrap_or(0));
+    breakpoints.dedup_by(|a, b| a.query == b.query);
+
+    DesignTokens {
+        palette,
+        font_stacks: by_usage(raw.font_stacks, 8),
+        type_scale: by_size(raw.font_sizes),
+        spacing_scale: by_size(raw.spacing),
+        radii: by_size(raw.radii),
+        shadows: by_usage(raw.shadows, 8),
+        breakpoints,
+    }
+}
+
+/// Most used first
+fn by_usage(mut values: Vec<TokenValue>, limit: usize) -> Vec<TokenValue> {
+    values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    values.truncate(limit);
+    values
+}
+
+/// The most used lengths, smallest first (lengths that aren't plain px go last)
+fn by_size(values: Vec<TokenValue>) -> Vec<TokenValue> {
+    let mut values = by_usage(values, MAX_TOKENS);
+    values.sort_by(|a, b| {
+        let (a, b) = (px_value(&a.value), px_value(&b.value));
+        a.unwrap_or(f64::MAX).total_cmp(&b.unwrap_or(f64::MAX))
+    });
+    values
+}
+
+/// `16px` as 16.0
+fn px_value(value: &str) -> Option<f64> {
+    value.trim().strip_suffix("px")?.trim().parse().ok()
+}
+
+/// Min and max width in px from a media query like `(min-width: 48em)`
+fn parse_width_query(query: &str) -> (Option<u32>, Option<u32>) {
+    let pattern =
+        regex::Regex::new(r"(min|max)-width\s*:\s*([\d.]+)(px|em|rem)").expect("valid regex");
+    let mut widths = (None, None);
+    for caps in pattern.captures_iter(query) {
+        let Ok(number) = caps[2].parse::<f64>() else {
+            continue;
+        };
+        let px = if &caps[3] == "px" {
+            number
+        } else {
+            number * 16.0
+        };
+        let px = px.round() as u32;
+        if &caps[1] == "min" {
+            widths.0 = Some(px);
+        } else {
+            widths.1 = Some(px);
+        }
+    }
+    widths
+}
+
+/// Tokens as CSS custom properties
+fn tokens_to_css(tokens: &DesignTokens, url: &str) -> String {
+    let mut css = format!(
+        "/* Design tokens extracted by DOMGuard from {} */\n:root {{\n",
+        url
+    );
+    let mut group = |comment: &str, prefix: &str, values: Vec<String>| {
+        if values.is_empty() {
+            return;
+        }
+        let _ = writeln!(css, "  /* {} */", comment);
+        for (i, value) in values.iter().enumerate() {
+            let _ = writeln!(css, "  --{}-{}: {};", prefix, i + 1, value);
+        }
+    };
+
+    group(
+        "Palette, most used first",
+        "color",
+        tokens.palette.iter().map(|c| c.hex.clone()).collect(),
+    );
+    group("Font stacks", "font", token_values(&tokens.font_stacks));
+    group("Type scale", "text", token_values(&tokens.type_scale));
+    group(
+        "Spacing scale",
+        "space",
+        token_values(&tokens.spacing_scale),
+    );
+    group("Radii", "radius", token_values(&tokens.radii));
+    group("Shadows", "shadow", token_values(&tokens.shadows));
+    group(
+        "Breakpoints (for reference; media queries can't use custom properties)",
+        "breakpoint",
+        breakpoint_widths(tokens),
+    );
+
+    css.push_str("}\n");
+    css
+}
+
+/// Tokens as a Tailwind `theme.extend` fragment
+fn tokens_to_tailwind(tokens: &DesignTokens, url: &str) -> String {
+    let quote = |s: &str| serde_json::Value::String(s.to_string()).to_string();
+    let mut sections: Vec<(&str, Vec<(String, String)>)> = Vec::new();
+    let mut section = |name: &'static str, prefix: &str, values: Vec<String>| {
+        if !values.is_empty() {
+            let entries = values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| (format!("{}-{}", prefix, i + 1), value))
+                .collect();
+            sections.push((name, entries));
+        }
+    };
+
+    section(
+        "colors",
+        "brand",
+        tokens.palette.iter().map(|c| quote(&c.hex)).collect(),
+    );
+    section(
+        "fontFamily",
+        "stack",
+        tokens
+            .font_stacks
+            .iter()
+            .map(|stack| {
+                let fonts: Vec<String> = stack
+                    .value
+                    .split(',')
+                    .map(|font| quote(font.trim().trim_matches(|c| c == '"' || c == '\'')))
+                    .collect();
+                format!("[{}]", fonts.join(", "))
+            })
+            .collect(),
+    );
+    let quoted = |tokens: &[TokenValue]| tokens.iter().map(|t| quote(&t.value)).collect();
+    section("fontSize", "scale", quoted(&tokens.type_scale));
+    section("spacing", "space", quoted(&tokens.spacing_scale));
+    section("borderRadius", "radius", quoted(&tokens.radii));
+    section("boxShadow", "shadow", quoted(&tokens.shadows));
+    // Tailwind screens are min-width breakpoints
+    section(
+        "screens",
+        "bp",
+        tokens
+            .breakpoints
+            .iter()
+            .filter_map(|b| b.min_width_px)
+            .map(|px| quote(&format!("{}px", px)))
+            .collect(),
+    );
+
+    let mut js = format!(
+        "// Design tokens extracted by DOMGuard from {}\nmodule.exports = {{\n  theme: {{\n    extend: {{\n",
+        url
+    );
+    for (name, entries) in sections {
+        let _ = writeln!(js, "      {}: {{", name);
+        for (key, value) in entries {
+            let _ = writeln!(js, "        {}: {},", quote(&key), value);
+        }
+        js.push_str("      },\n");
+    }
+    js.push_str("    },\n  },\n};\n");
+    js
+}
+
+fn token_values(tokens: &[TokenValue]) -> Vec<String> {
+    tokens.iter().map(|t| t.value.clone()).collect()
+}
+
+/// Breakpoint widths in px, narrowest first
+fn breakpoint_widths(tokens: &DesignTokens) -> Vec<String> {
+    let mut widths: Vec<u32> = tokens
+        .breakpoints
+        .iter()
+        .filter_map(|b| b.min_width_px.or(b.max_width_px))
+        .collect();
+    widths.dedup();
+    widths.iter().map(|px| format!("{}px", px)).collect()
+}
+
+/// What a component bundle contains
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub url: String,
+    pub selector: String,
+    pub captured_at: chrono::DateTime<chrono::Utc>,
+    /// Number of CSS rules in component.css
+    pub rules: usize,
+    pub assets: Vec<BundleAsset>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleAsset {
+    /// URL as referenced by the page
+    pub url: String,
+    /// Path inside the bundle, or none when it couldn't be fetched (the reference is left as is)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+/// Most assets copied into a bundle
+const MAX_ASSETS: usize = 40;
+
+/// Capture a component's HTML, the CSS rules that apply to it and its assets as a standalone bundle
+///
+/// Writes `index.html` (the component on an otherwise empty page, inside a
+/// wrapper carrying the styles it inherits), `component.html`,
+/// `component.css`, `assets/` and `manifest.json`.
+pub async fn export_bundle(
+    cdp: &CdpConnection,
+    url: &str,
+    selector: &str,
+    dir: &Path,
+) -> Result<BundleManifest> {
+    let rules = cdp.matched_css_rules(selector).await?;
+
+    // Serialize a copy with absolute asset URLs, plus the inherited text styles
+    let capture = cdp
+        .evaluate(&format!(
+            r#"(function() {{
+                const el = document.querySelector({});
+                if (!el) return null;
+                const clone = el.cloneNode(true);
+                const assets = [];
+                clone.querySelectorAll('[src], [poster], [srcset]').forEach(node => {{
+                    ['src', 'poster'].forEach(attr => {{
+                        const value = node.getAttribute(attr);
+                        if (value && !value.startsWith('data:')) {{
+                            const absolute = new URL(value, document.baseURI).href;
+                            node.setAttribute(attr, absolute);
+                            assets.push(absolute);
+                        }}
+                    }});
+                    if (node.hasAttribute('srcset')) {{
+                        // The chosen candidate is enough to reproduce the layout
+                        const current = node.currentSrc || node.src;
+                        node.removeAttribute('srcset');
+                        if (current && node.tagName === 'IMG') {{
+                            node.setAttribute('src', current);
+                            assets.push(current);
+                        }}
+                    }}
+                }});
+                const parent = el.parentElement ? getComputedStyle(el.parentElement) : null;
+                const inherited = parent ? ['color', 'font-family', 'font-size', 'font-weight',
+                    'line-height', 'letter-spacing', 'text-align', 'direction']
+                    .map(p => p + ': ' + parent.getPropertyValue(p)).join('; ') : '';
+                return {{ html: clone.outerHTML, inherited, assets, base: document.baseURI }};
+            }})()"#,
+            serde_json::to_string(selector)?
+        ))
+        .await?;
+    if capture.is_null() {
+        return Err(anyhow::anyhow!(
+            "No element matches selector \"{}\"",
+            selector
+        ));
+    }
+    let text = |key: &str| {
+        capture
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let (mut html, inherited, base) = (text("html"), text("inherited"), text("base"));
+    let mut css = rules.join("\n\n");
+
+    // Asset references: the copy's absolute URLs, then url(...) in the CSS
+    let mut references: Vec<(String, String)> = capture
+        .get("assets")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .map(|u| (u.to_string(), u.to_string()))
+        .collect();
+    for reference in css_urls(&css) {
+        let absolute = resolve_url(&base, &reference);
+        if !references.iter().any(|(_, known)| *known == absolute) {
+            references.push((reference, absolute));
+        }
+    }
+    references.truncate(MAX_ASSETS);
+
+    let assets_dir = dir.join("assets");
+    std::fs::create_dir_all(&assets_dir)
+        .with_context(|| format!("Failed to create {}", assets_dir.display()))?;
+
+    let mut assets = Vec::new();
+    for (i, (reference, absolute)) in references.into_iter().enumerate() {
+        let file = match fetch_asset(cdp, &absolute).await {
+            Some(bytes) => {
+                let name = format!("assets/{}-{}", i + 1, asset_file_name(&absolute));
+                std::fs::write(dir.join(&name), bytes)?;
+                html = html.replace(&absolute, &name);
+                css = css.replace(&reference, &name);
+                Some(name)
+            }
+            None => None,
+        };
+        assets.push(BundleAsset {
+            url: absolute,
+            file,
+        });
+    }
+
+    let index = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{} from {}</title>\n\
+         <link rel=\"stylesheet\" href=\"component.css\">\n</head>\n<body>\n\
+         <div class=\"domguard-context\" style=\"{}\">\n{}\n</div>\n</body>\n</html>\n",
+        html_escape(selector),
+        html_escape(url),
+        html_escape(&inherited),
+        html
+    );
+    std::fs::write(dir.join("index.html"), index)?;
+    std::fs::write(dir.join("component.html"), &html)?;
+    std::fs::write(dir.join("component.css"), &css)?;
+
+    let manifest = BundleManifest {
+        url: url.to_string(),
+        selector: selector.to_string(),
+        captured_at: chrono::Utc::now(),
+        rules: rules.len(),
+        assets,
+    };
+    std::fs::write(
+        dir.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+    Ok(manifest)
+}
+
+/// Fetch an asset from inside the page (so cookies apply); `None` if CORS or the network refuses
+async fn fetch_asset(cdp: &CdpConnection, url: &str) -> Option<Vec<u8>> {
+    use base64::Engine as _;
+
+    let script = format!(
+        r#"fetch({}).then(r => r.ok ? r.blob() : null).then(blob => blob && blob.size < 5000000
+            ? new Promise(done => {{
+                const reader = new FileReader();
+                reader.onload = () => done(reader.result.split(',')[1] || '');
+                reader.onerror = () => done(null);
+                reader.readAsDataURL(blob);
+            }})
+            : null).catch(() => null)"#,
+        serde_json::to_string(url).ok()?
+    );
+    let encoded = cdp.evaluate(&script).await.ok()?;
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded.as_str()?)
+        .ok()
+}
+
+/// `url(...)` references in CSS, except data URIs
+fn css_urls(css: &str) -> Vec<String> {
+    let pattern = regex::Regex::new(r#"url\(\s*['"]?([^'")]+?)['"]?\s*\)"#).expect("valid regex");
+    let mut urls: Vec<String> = Vec::new();
+    for caps in pattern.captures_iter(css) {
+        let url = caps[1].to_string();
+        if !url.starts_with("data:") && !url.starts_with('#') && !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+/// Resolve a reference against the page's base URL
+fn resolve_url(base: &str, reference: &str) -> String {
+    if reference.contains("://") {
+        return reference.to_string();
+    }
+    let scheme = base.split("://").next().unwrap_or("https");
+    if let Some(rest) = reference.strip_prefix("//") {
+        return format!("{}://{}", scheme, rest);
+    }
+    let after_scheme = base.split_once("://").map_or(base, |(_, rest)| rest);
+    let origin_len =
+        base.len() - after_scheme.len() + after_scheme.find('/').unwrap_or(after_scheme.len());
+    if reference.starts_with('/') {
+        return format!("{}{}", &base[..origin_len], reference);
+    }
+    // Relative to the base's directory (query and fragment dropped)
+    let path_end = base.find(['?', '#']).unwrap_or(base.len());
+    let dir_end = base[..path_end]
+        .rfind('/')
+        .filter(|&i| i >= origin_len)
+        .unwrap_or(origin_len);
+    format!("{}/{}", &base[..dir_end], reference)
+}
+
+/// A safe file name from the last path segment of a URL
+fn asset_file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let name: String = path
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(60)
+        .collect();
+    if name.is_empty() {
+        "asset".to_string()
+    } else {
+        name
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 /// Extract design patterns from the page
+/// Viewport height used for responsive captures
+const RESPONSIVE_HEIGHT: u32 = 900;
+
+/// Element changes kept per width step
+const MAX_ELEMENT_CHANGES: usize = 50;
+
+/// Layout of the target at one viewport width
+#[derive(Debug, Default, Deserialize)]
+struct LayoutSnapshot {
+    #[serde(default)]
+    matched_queries: Vec<String>,
+    /// Element path -> property -> value
+    #[serde(default)]
+    elements: std::collections::BTreeMap<String, std::collections::BTreeMap<String, String>>,
+    /// Bounding box of the target (x, y, width, height)
+    #[serde(default)]
+    rect: Option<[f64; 4]>,
+}
+
+/// Device class for a viewport width
+fn width_label(width: u32) -> &'static str {
+    match width {
+        0..=599 => "mobile",
+        600..=1023 => "tablet",
+        _ => "desktop",
+    }
+}
+
+/// Screenshot the page (or component) at each width and diff the layouts between widths
+async fn capture_responsive(
+    cdp: &CdpConnection,
+    component: Option<&str>,
+    widths: &[u32],
+    save_dir: &Path,
+) -> Result<ResponsiveReport> {
+    let dir = save_dir.join("responsive");
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let selector = component.unwrap_or("body");
+    let script = layout_script(selector)?;
+
+    let mut widths = widths.to_vec();
+    widths.sort_unstable();
+    widths.dedup();
+
+    let mut report = ResponsiveReport::default();
+    let mut previous: Option<(u32, LayoutSnapshot)> = None;
+    for width in widths {
+        let label = width_label(width);
+        cdp.set_device_metrics(width, RESPONSIVE_HEIGHT, label == "mobile")
+            .await?;
+        // Give media queries, resize observers and images a moment to settle
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+        let value = cdp.evaluate(&script).await?;
+        if value.is_null() {
+            return Err(anyhow::anyhow!(
+                "No element matches selector \"{}\" at {}px",
+                selector,
+                width
+            ));
+        }
+        let snapshot: LayoutSnapshot =
+            serde_json::from_value(value).context("Failed to read layout snapshot")?;
+
+        let image = match (component, snapshot.rect) {
+            (Some(_), Some([x, y, w, h])) if w >= 1.0 && h >= 1.0 => {
+                cdp.screenshot_region(x as i32, y as i32, w.ceil() as i32, h.ceil() as i32)
+                    .await?
+            }
+            _ => cdp.screenshot(true).await?,
+        };
+        let path = dir.join(format!("{}.png", width));
+        std::fs::write(&path, image)?;
+
+        if let Some((from_width, before)) = &previous {
+            report
+                .changes
+                .push(diff_layouts(*from_width, width, before, &snapshot));
+        }
+        report.captures.push(ResponsiveCapture {
+            width,
+            label: label.to_string(),
+            screenshot_path: path.to_string_lossy().to_string(),
+            matched_queries: snapshot.matched_queries.clone(),
+        });
+        previous = Some((width, snapshot));
+    }
+    Ok(report)
+}
+
+/// Script returning the matching media queries and the layout of the target's elements
+fn layout_script(selector: &str) -> Result<String> {
+    Ok(format!(
+        r"(function() {{
+            const root = document.querySelector({});
+            if (!root) return null;
+            root.scrollIntoView({{ block: 'start' }});
+
+            const queries = new Set();
+            const collect = rules => {{
+                for (const rule of rules) {{
+                    if (rule.media && rule.cssRules) {{
+                        queries.add(rule.media.mediaText);
+                        collect(rule.cssRules);
+                    }}
+                }}
+            }};
+            for (const sheet of document.styleSheets) {{
+                try {{ collect(sheet.cssRules); }} catch (e) {{}}
+            }}
+            const matched = [...queries].filter(q => window.matchMedia(q).matches).sort();
+
+            const describe = el => {{
+                let name = el.tagName.toLowerCase();
+                if (el.id) return name + '#' + el.id;
+                if (el.classList.length) name += '.' + el.classList[0];
+                const parent = el.parentElement;
+                if (parent) {{
+                    const index = [...parent.children].indexOf(el);
+                    name += ':nth-child(' + (index + 1) + ')';
+                }}
+                return name;
+            }};
+            const elements = {{}};
+            const walk = (el, path, depth) => {{
+                if (Object.keys(elements).length >= 400 || depth > 12) return;
+                const style = getComputedStyle(el);
+                const rect = el.getBoundingClientRect();
+                const props = {{
+                    display: style.display,
+                    visibility: style.display === 'none' || style.visibility === 'hidden'
+                        || rect.width === 0 ? 'hidden' : 'visible'
+                }};
+                if (style.display.includes('flex')) props['flex-direction'] = style.flexDirection;
+                if (style.display.includes('grid')) {{
+                    const tracks = style.gridTemplateColumns.split(' ').filter(t => t).length;
+                    props['grid-columns'] = String(tracks);
+                }}
+                elements[path] = props;
+                for (const child of el.children) walk(child, path + ' > ' + describe(child), depth + 1);
+            }};
+            walk(root, describe(root), 0);
+
+            const box = root.getBoundingClientRect();
+            return {{
+                matched_queries: matched,
+                elements,
+                // Page coordinates, as Page.captureScreenshot clips expect
+                rect: [box.left + window.scrollX, box.top + window.scrollY, box.width,
+                    Math.min(box.height, window.innerHeight - Math.max(box.top, 0))]
+            }};
+        }})()",
+        serde_json::to_string(selector)?
+    ))
+}
+
+/// What changed between two layout snapshots
+fn diff_layouts(
+    from_width: u32,
+    to_width: u32,
+    before: &LayoutSnapshot,
+    after: &LayoutSnapshot,
+) -> LayoutChange {
+    let queries_activated = after
+        .matched_queries
+        .iter()
+        .filter(|q| !before.matched_queries.contains(q))
+        .cloned()
+        .collect();
+    let queries_deactivated = before
+        .matched_queries
+        .iter()
+        .filter(|q| !after.matched_queries.contains(q))
+        .cloned()
+        .collect();
+
+    let mut elements = Vec::new();
+    'outer: for (element, old) in &before.elements {
+        let Some(new) = after.elements.get(element) else {
+            continue;
+        };
+        let properties: std::collections::BTreeSet<&String> =
+            old.keys().chain(new.keys()).collect();
+        for property in properties {
+            let from = old.get(property).map_or("-", String::as_str);
+            let to = new.get(property).map_or("-", String::as_str);
+            if from != to {
+                if elements.len() == MAX_ELEMENT_CHANGES {
+                    break 'outer;
+                }
+                elements.push(ElementChange {
+                    element: element.clone(),
+                    property: property.clone(),
+                    from: from.to_string(),
+                    to: to.to_string(),
+                });
+            }
+        }
+    }
+
+    LayoutChange {
+        from_width,
+        to_width,
+        queries_activated,
+        queries_deactivated,
+        elements,
+    }
+}
+
 async fn extract_design(
     cdp: &CdpConnection,
     url: &str,
@@ -114,6 +1162,16 @@ async fn extract_design(
             const durations = new Set();
             const transitions = new Set();
 
+            // Token counts
+            const fontStacks = new Map();
+            const fontSizes = new Map();
+            const spacing = new Map();
+            const radii = new Map();
+            const shadows = new Map();
+            function bump(map, key) {{
+                map.set(key, (map.get(key) || 0) + 1);
+            }}
+
             function walkElements(el) {{
                 const style = getComputedStyle(el);
 
@@ -137,17 +1195,32 @@ async fn extract_design(
                     style.lineHeight
                 ].join('|');
                 fonts.set(fontKey, (fonts.get(fontKey) || 0) + 1);
+                if (style.fontFamily) bump(fontStacks, style.fontFamily);
+                bump(fontSizes, style.fontSize);
 
                 // Spacing
                 ['paddingTop', 'paddingRight', 'paddingBottom', 'paddingLeft'].forEach(p => {{
                     const val = style[p];
-                    if (val && val !== '0px') paddings.add(val);
+                    if (val && val !== '0px') {{
+                        paddings.add(val);
+                        bump(spacing, val);
+                    }}
                 }});
                 ['marginTop', 'marginRight', 'marginBottom', 'marginLeft'].forEach(m => {{
                     const val = style[m];
-                    if (val && val !== '0px' && !val.startsWith('-')) margins.add(val);
+                    if (val && val !== '0px' && !val.startsWith('-')) {{
+                        margins.add(val);
+                        bump(spacing, val);
+                    }}
                 }});
-                if (style.gap && style.gap !== 'normal') gaps.add(style.gap);
+                if (style.gap && style.gap !== 'normal') {{
+                    gaps.add(style.gap);
+                    bump(spacing, style.gap);
+                }}
+
+                // Radii and shadows
+                if (style.borderRadius && style.borderRadius !== '0px') bump(radii, style.borderRadius);
+                if (style.boxShadow && style.boxShadow !== 'none') bump(shadows, style.boxShadow);
 
                 // Layout - Flex
                 if (style.display === 'flex' || style.display === 'inline-flex') {{
@@ -216,6 +1289,22 @@ async fn extract_design(
 
             walkElements(root);
 
+            // Media queries from every readable stylesheet (cross-origin ones throw)
+            const media = new Map();
+            function collectMedia(rules) {{
+                for (const rule of rules) {{
+                    if (rule.media && rule.cssRules) {{
+                        const query = rule.media.mediaText;
+                        if (query && !media.has(query)) media.set(query, window.matchMedia(query).matches);
+                    }}
+                    if (rule.cssRules) collectMedia(rule.cssRules);
+                }}
+            }}
+            for (const sheet of document.styleSheets) {{
+                try {{ collectMedia(sheet.cssRules); }} catch (e) {{}}
+            }}
+            const counts = map => [...map].map(([value, count]) => ({{ value, count }}));
+
             // Process colors
             const colorList = [];
             colors.forEach((count, key) => {{
@@ -223,6 +1312,7 @@ async fn extract_design(
                 colorList.push({{ hex, usage, count }});
             }});
             colorList.sort((a, b) => b.count - a.count);
+            const allColors = colorList.slice();
 
             // Process fonts
             const fontList = [];
@@ -264,6 +1354,15 @@ async fn extract_design(
                     timing_functions: [...timingFunctions].slice(0, 5),
                     durations: [...durations].slice(0, 5),
                     transitions: [...transitions].slice(0, 5)
+                }},
+                tokens: {{
+                    colors: allColors,
+                    font_stacks: counts(fontStacks),
+                    font_sizes: counts(fontSizes),
+                    spacing: counts(spacing),
+                    radii: counts(radii),
+                    shadows: counts(shadows),
+                    media: [...media].map(([query, matches]) => ({{ query, matches }}))
                 }}
             }};
         }})()
@@ -363,6 +1462,13 @@ async fn extract_design(
             .unwrap_or_default(),
     };
 
+    let tokens = build_tokens(
+        result
+            .get("tokens")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default(),
+    );
+
     Ok(DesignData {
         url: url.to_string(),
         colors,
@@ -370,6 +1476,7 @@ async fn extract_design(
         spacing,
         layout,
         animations,
+        tokens,
     })
 }
 
@@ -386,6 +1493,7 @@ mod tests {
             spacing: SpacingInfo::default(),
             layout: LayoutInfo::default(),
             animations: AnimationInfo::default(),
+            tokens: DesignTokens::default(),
         };
 
         let json = serde_json::to_string(&data).unwrap();
@@ -428,6 +1536,7 @@ mod tests {
                 durations: vec!["0.3s".to_string()],
                 transitions: vec!["all 0.3s ease".to_string()],
             },
+            tokens: DesignTokens::default(),
         };
 
         let json = serde_json::to_string(&data).unwrap();
@@ -436,4 +1545,223 @@ mod tests {
         assert!(json.contains("row"));
         assert!(json.contains("ease"));
     }
+
+    fn raw_tokens() -> RawTokens {
+        let token = |value: &str, count| TokenValue {
+            value: value.to_string(),
+            count,
+        };
+        let color = |hex: &str, usage: &str, count| ColorInfo {
+            hex: hex.to_string(),
+            usage: usage.to_string(),
+            count,
+        };
+        let media = |query: &str| RawMedia {
+            query: query.to_string(),
+            matches: true,
+        };
+        RawTokens {
+            colors: vec![
+                color("#111111", "color", 9),
+                color("#ffffff", "backgroundColor", 12),
+                color("#111111", "borderColor", 4),
+            ],
+            font_stacks: vec![token("\"Inter\", sans-serif", 20)],
+            spacing: vec![token("16px", 3), token("4px", 8), token("50%", 1)],
+            media: vec![
+                media("(min-width: 64em)"),
+                media("(min-width: 768px)"),
+                media("print"),
+            ],
+            ..RawTokens::default()
+        }
+    }
+
+    #[test]
+    fn test_build_tokens() {
+        let tokens = build_tokens(raw_tokens());
+
+        assert_eq!(tokens.palette[0].hex, "#111111");
+        assert_eq!(tokens.palette[0].count, 13);
+        assert_eq!(tokens.palette[0].usages, vec!["color", "borderColor"]);
+
+        let spacing: Vec<&str> = tokens
+            .spacing_scale
+            .iter()
+            .map(|t| t.value.as_str())
+            .collect();
+        assert_eq!(spacing, vec!["4px", "16px", "50%"]);
+
+        assert_eq!(tokens.breakpoints.len(), 2);
+        assert_eq!(tokens.breakpoints[0].min_width_px, Some(768));
+        assert_eq!(tokens.breakpoints[1].min_width_px, Some(1024));
+        assert_eq!(
+            parse_width_query("screen and (min-width: 600px) and (max-width:899.5px)"),
+            (Some(600), Some(900))
+        );
+    }
+
+    #[test]
+    fn test_token_exports() {
+        let tokens = build_tokens(raw_tokens());
+
+        let css = TokenFormat::Css.render(&tokens, "https://example.com");
+        assert!(css.contains(":root {"));
+        assert!(css.contains("  --color-1: #111111;"));
+        assert!(css.contains("  --space-2: 16px;"));
+        assert!(css.contains("  --breakpoint-2: 1024px;"));
+        assert!(!css.contains("--radius-"));
+
+        let tailwind = TokenFormat::Tailwind.render(&tokens, "https://example.com");
+        assert!(tailwind.contains("module.exports = {"));
+        assert!(tailwind.contains(r#""stack-1": ["Inter", "sans-serif"],"#));
+        assert!(tailwind.contains(r#""bp-1": "768px","#));
+
+        assert_eq!("Tailwind".parse::<TokenFormat>(), Ok(TokenFormat::Tailwind));
+        assert!("scss".parse::<TokenFormat>().is_err());
+    }
+
+    #[test]
+    fn test_list_diff() {
+        let strings = |values: &[&str]| values.iter().map(|v| (*v).to_string()).collect::<Vec<_>>();
+        let diff = list_diff(
+            &strings(&["#000", "#fff", "#000", "#f00"]),
+            &strings(&["#fff", "#00f", "#00f"]),
+        );
+        assert_eq!(diff.shared, strings(&["#fff"]));
+        assert_eq!(diff.only_a, strings(&["#000", "#f00"]));
+        assert_eq!(diff.only_b, strings(&["#00f"]));
+    }
+
+    #[test]
+    fn test_inspiration_library() {
+        let library = tempfile::tempdir().unwrap();
+        let save = |name: &str, json: &str| {
+            let dir = library.path().join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("design.json"), json).unwrap();
+        };
+        // The oldest saves have no tokens
+        save(
+            "plain",
+            r##"{"url": "https://a.example", "colors": [{"hex": "#FFF", "usage": "background", "count": 3}]}"##,
+        );
+        save(
+            "brand",
+            r##"{"url": "https://b.example", "colors": [], "tokens": {"palette": [{"hex": "#fff", "count": 2, "usages": []}, {"hex": "#123456", "count": 1, "usages": []}]}}"##,
+        );
+        std::fs::create_dir_all(library.path().join("bundle")).unwrap();
+
+        let names: Vec<String> = list_inspirations(library.path())
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, vec!["brand", "plain"]);
+
+        let comparison = compare_inspirations(library.path(), "plain", "brand").unwrap();
+        assert_eq!(comparison.palette.shared, vec!["#fff"]);
+        assert_eq!(comparison.palette.only_b, vec!["#123456"]);
+
+        let loaded = load_inspiration(library.path(), "plain").unwrap();
+        assert_eq!(loaded.url, "https://a.example");
+        assert!(loaded.screenshot_path.is_none());
+
+        assert!(load_inspiration(library.path(), "../plain").is_err());
+        assert!(delete_inspiration(library.path(), "bundle").is_err());
+        delete_inspiration(library.path(), "plain").unwrap();
+        assert!(!library.path().join("plain").exists());
+        assert!(list_inspirations(&library.path().join("missing"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_diff_layouts() {
+        let props = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect()
+        };
+        let before = LayoutSnapshot {
+            matched_queries: vec!["(max-width: 600px)".to_string()],
+            elements: [
+                (
+                    "nav".to_string(),
+                    props(&[("display", "none"), ("visibility", "hidden")]),
+                ),
+                (
+                    "main".to_string(),
+                    props(&[("display", "flex"), ("flex-direction", "column")]),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            rect: None,
+        };
+        let after = LayoutSnapshot {
+            matched_queries: vec!["(min-width: 768px)".to_string()],
+            elements: [
+                (
+                    "nav".to_string(),
+                    props(&[("display", "block"), ("visibility", "visible")]),
+                ),
+                (
+                    "main".to_string(),
+                    props(&[("display", "grid"), ("grid-columns", "3")]),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            rect: None,
+        };
+
+        let change = diff_layouts(375, 768, &before, &after);
+        assert_eq!(change.queries_activated, vec!["(min-width: 768px)"]);
+        assert_eq!(change.queries_deactivated, vec!["(max-width: 600px)"]);
+        assert!(change.elements.contains(&ElementChange {
+            element: "main".to_string(),
+            property: "flex-direction".to_string(),
+            from: "column".to_string(),
+            to: "-".to_string(),
+        }));
+        assert_eq!(change.elements.len(), 5);
+        assert!(diff_layouts(768, 768, &after, &after).elements.is_empty());
+
+        assert_eq!(width_label(375), "mobile");
+        assert_eq!(width_label(768), "tablet");
+        assert_eq!(width_label(1280), "desktop");
+    }
+
+    #[test]
+    fn test_bundle_urls() {
+        let css = r#".hero { background: url("../img/hero.png?v=2") } .x { mask: url(data:image/svg+xml;a) }
+            .logo { background-image: url( /logo.svg ) } .y { filter: url(#blur) }"#;
+        assert_eq!(css_urls(css), vec!["../img/hero.png?v=2", "/logo.svg"]);
+
+        let base = "https://example.com/docs/page.html?q=1";
+        assert_eq!(
+            resolve_url(base, "/logo.svg"),
+            "https://example.com/logo.svg"
+        );
+        assert_eq!(
+            resolve_url(base, "img/a.png"),
+            "https://example.com/docs/img/a.png"
+        );
+        assert_eq!(
+            resolve_url(base, "//cdn.example.com/a.png"),
+            "https://cdn.example.com/a.png"
+        );
+        assert_eq!(
+            resolve_url("https://example.com", "a.png"),
+            "https://example.com/a.png"
+        );
+
+        assert_eq!(
+            asset_file_name("https://example.com/img/hero.png?v=2"),
+            "hero.png"
+        );
+        assert_eq!(asset_file_name("https://example.com/"), "asset");
+    }
 }
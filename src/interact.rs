@@ -3,13 +3,30 @@
 //! Mouse events, keyboard input, navigation, screenshots, wait conditions
 
 use anyhow::Result;
-use serde::Serialize;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
-use crate::cdp::CdpConnection;
+use crate::backend::{BrowserBackend, CaptureOptions, ClipRect, ImageFormat};
+use crate::cdp::{
+    wait_for_network_idle, CalendarStep, CdpConnection, ComboOption, CoordinateOrigin,
+    ElementCondition, FocusStop, NetworkWatch, PageCondition, PdfOptions, PopupInfo, TableSnapshot,
+    TableTarget, WaitUntil,
+};
 use crate::config::Config;
-use crate::output::{CommandResult, Formatter};
+use crate::consent::{dismiss_script, ConsentDismissed};
+use crate::correction::{
+    classify_error, dismiss_overlay_script, plan_recovery, wait_stable_script, CorrectionConfig,
+    RecoveryAttempt, RecoveryStrategy,
+};
+use crate::framework::Framework;
+use crate::network::{Credential, NetworkConfig};
+use crate::output::{CommandResult, Formatter, Timing};
+use crate::security::{InteractAction, SecurityEnforcement, SensitiveActionDetection};
+use crate::site_instructions::{
+    expand_alias, extract_domain, ForbiddenBySite, SiteInstructions, SiteInstructionsManager,
+};
+use crate::takeover::TakeoverSession;
 
 /// Get current timestamp in seconds, with fallback to 0 if system clock is before UNIX epoch
 fn safe_timestamp() -> u64 {
@@ -42,25 +59,553 @@ impl std::fmt::Display for InteractResult {
     }
 }
 
+/// How long a click and the wait after it took (`click --then-*`)
+#[derive(Debug, Serialize)]
+struct PhaseTimings {
+    click_ms: u64,
+    wait_ms: u64,
+    waited_for: String,
+}
+
+/// Interact result together with the self-correction steps that led to it,
+/// any sensitive action it was allowed through with, and a popup it opened
+#[derive(Debug, Serialize)]
+struct RecoveredResult {
+    #[serde(flatten)]
+    result: InteractResult,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    recovery: Vec<RecoveryAttempt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    security: Option<SensitiveActionDetection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    popup: Option<PopupInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    phases: Option<PhaseTimings>,
+}
+
+impl std::fmt::Display for RecoveredResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.result)?;
+        if !self.recovery.is_empty() {
+            write!(f, " [recovered after: {}]", format_recovery(&self.recovery))?;
+        }
+        if let Some(popup) = &self.popup {
+            write!(f, " [popup: {}]", popup.url)?;
+        }
+        if let Some(phases) = &self.phases {
+            write!(
+                f,
+                " [click {}ms, then {}ms until {}]",
+                phases.click_ms, phases.wait_ms, phases.waited_for
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Self-correction state for one click or type command
+///
+/// After each failure the error is classified and the next untried strategy
+/// from the plan is applied before the action is retried.
+struct Recovery<'a> {
+    config: &'a CorrectionConfig,
+    action: &'static str,
+    selector: Option<&'a str>,
+    started: Instant,
+    attempts: Vec<RecoveryAttempt>,
+}
+
+impl<'a> Recovery<'a> {
+    fn new(config: &'a CorrectionConfig, action: &'static str, selector: Option<&'a str>) -> Self {
+        Self {
+            config,
+            action,
+            selector,
+            started: Instant::now(),
+            attempts: Vec::new(),
+        }
+    }
+
+    /// Apply the next strategy for `error`, returning false when there is nothing left to try
+    async fn recover(
+        &mut self,
+        cdp: &CdpConnection,
+        error: &anyhow::Error,
+        formatter: &Formatter,
+    ) -> bool {
+        let attempt = self.attempts.len() as u32;
+        if attempt >= self.config.max_retries
+            || self.started.elapsed().as_millis() as u64 >= self.config.max_recovery_time_ms
+        {
+            return false;
+        }
+
+        // The error can change between attempts, so plan again each time
+        let kind = classify_error(&error.to_string());
+        let Some(strategy) =
+            plan_recovery(self.config, &kind, self.action, self.selector.is_some())
+                .into_iter()
+                .find(|s| !self.attempts.iter().any(|a| a.strategy == *s))
+        else {
+            return false;
+        };
+
+        formatter.warning(&format!(
+            "{} failed ({}), trying: {}",
+            self.action, kind, strategy
+        ));
+        let applied = apply_strategy(cdp, &strategy, self.selector).await.is_ok();
+        if !matches!(strategy, RecoveryStrategy::WaitAndRetry { .. }) {
+            tokio::time::sleep(std::time::Duration::from_millis(
+                self.config.retry_delay_ms(attempt),
+            ))
+            .await;
+        }
+
+        self.attempts.push(RecoveryAttempt {
+            error: kind,
+            strategy,
+            applied,
+        });
+        true
+    }
+
+    /// Mention the strategies that were tried in a final error
+    fn annotate(&self, result: Result<InteractResult>) -> Result<InteractResult> {
+        match result {
+            Err(e) if !self.attempts.is_empty() => Err(anyhow::anyhow!(
+                "{} (recovery tried: {})",
+                e,
+                format_recovery(&self.attempts)
+            )),
+            result => result,
+        }
+    }
+}
+
+fn format_recovery(attempts: &[RecoveryAttempt]) -> String {
+    attempts
+        .iter()
+        .map(|a| a.strategy.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Correction config for the current page: `[correction]` from config.toml with
+/// any overrides from the matching site instructions file applied
+async fn effective_correction(cdp: &CdpConnection, config: &Config) -> CorrectionConfig {
+    let Ok(url) = cdp.current_url().await else {
+        return config.correction.clone();
+    };
+
+    match SiteInstructionsManager::for_project_url(&url).and_then(|s| s.correction) {
+        Some(overrides) => config.correction.with_overrides(&overrides),
+        None => config.correction.clone(),
+    }
+}
+
+/// Enforce the current site's instructions before an action runs
+///
+/// Resolves `[selectors]` names and `@name` aliases, refuses actions on `forbidden_selectors`
+/// (a [`ForbiddenBySite`] error), runs the action's `required_waits` entry,
+/// and points at the login workflow when on the login page. Returns the
+/// milliseconds spent in the required wait.
+async fn apply_site_rules(
+    cdp: &CdpConnection,
+    config: &Config,
+    command: &mut InteractCommand,
+    formatter: &Formatter,
+) -> Result<u64> {
+    let url = cdp.current_url().await.unwrap_or_default();
+    let site = SiteInstructionsManager::for_project_url(&url);
+    command.resolve_site_selectors(site.as_ref())?;
+    let Some(site) = site else {
+        return Ok(0);
+    };
+    let action = command.action_name();
+
+    if action != "wait" && !site.forbidden_selectors.is_empty() {
+        let selectors: Vec<String> = command
+            .selectors_mut()
+            .into_iter()
+            .map(|s| s.clone())
+            .collect();
+        for selector in selectors {
+            let rule = match site.forbidden_rule(&selector) {
+                Some(rule) => Some(rule.to_string()),
+                None => forbidden_match(cdp, &selector, &site.forbidden_selectors).await?,
+            };
+            if let Some(rule) = rule {
+                return Err(ForbiddenBySite {
+                    domain: site.domain.clone(),
+                    action: action.to_string(),
+                    selector,
+                    rule,
+                }
+                .into());
+            }
+        }
+    }
+
+    let waiting = Instant::now();
+    if let Some(wait) = site.required_wait(action) {
+        let timeout_ms = wait.timeout_ms.unwrap_or(config.defaults.timeout_ms);
+        if let Some(selector) = &wait.selector {
+            cdp.wait_for(selector, timeout_ms).await?;
+        }
+        if let Some(text) = &wait.text {
+            cdp.wait_for_text(text, timeout_ms).await?;
+        }
+        if wait.network_idle {
+            let network = cdp.watch_network().await?;
+            wait_for_network_idle(&network, cdp.bounded(timeout_ms)).await?;
+        }
+    }
+    let wait_ms = waiting.elapsed().as_millis() as u64;
+
+    if let Some(workflow) = site.login.as_ref().and_then(|l| l.workflow.as_deref()) {
+        if site.is_login_url(&url) {
+            formatter.hint(&format!(
+                "This is the login page for {}; sign in with 'domguard workflow run {}'",
+                site.domain, workflow
+            ));
+        }
+    }
+
+    Ok(wait_ms)
+}
+
+/// The forbidden selector an element matched by `selector` falls under
+async fn forbidden_match(
+    cdp: &CdpConnection,
+    selector: &str,
+    forbidden: &[String],
+) -> Result<Option<String>> {
+    let js = format!(
+        r"(function() {{
+            let els;
+            try {{ els = document.querySelectorAll({}); }} catch (e) {{ return null; }}
+            for (const rule of {}) {{
+                for (const el of els) {{
+                    try {{ if (el.closest(rule)) return rule; }} catch (e) {{}}
+                }}
+            }}
+            return null;
+        }})()",
+        serde_json::Value::String(selector.to_string()),
+        serde_json::json!(forbidden)
+    );
+    Ok(cdp.evaluate(&js).await?.as_str().map(str::to_string))
+}
+
+/// Prepare the page for a retry using one recovery strategy
+async fn apply_strategy(
+    cdp: &CdpConnection,
+    strategy: &RecoveryStrategy,
+    selector: Option<&str>,
+) -> Result<()> {
+    match strategy {
+        RecoveryStrategy::WaitAndRetry { delay_ms } => {
+            tokio::time::sleep(std::time::Duration::from_millis(*delay_ms)).await;
+        }
+        RecoveryStrategy::ScrollIntoView => {
+            if let Some(sel) = selector {
+                cdp.scroll_to_element(sel).await?;
+            }
+        }
+        RecoveryStrategy::FocusThenType => {
+            if let Some(sel) = selector {
+                cdp.focus(sel).await?;
+            }
+        }
+        RecoveryStrategy::DismissOverlay => {
+            cdp.evaluate(dismiss_overlay_script()).await?;
+        }
+        RecoveryStrategy::WaitForStable => {
+            cdp.evaluate(wait_stable_script()).await?;
+        }
+        RecoveryStrategy::RefreshPage => {
+            cdp.refresh().await?;
+            cdp.evaluate(wait_stable_script()).await?;
+        }
+        RecoveryStrategy::ScrollAndSearch => {
+            cdp.scroll_by(0, 600).await?;
+        }
+        // Not planned automatically (see `CorrectionConfig::allows`)
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Persisted policy for answering JavaScript dialogs without an explicit `interact dialog`
+///
+/// Stored in `.domguard/dialog.toml`, or `.domguard/dialog/<ID>.toml` with
+/// `--session`, and applied to every connection of that session until cleared.
+/// Agents running in parallel under their own sessions don't answer each
+/// other's dialogs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DialogPolicy {
+    /// Accept dialogs (true) or dismiss them (false)
+    pub accept: bool,
+
+    /// Text entered into prompt dialogs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_text: Option<String>,
+}
+
+impl DialogPolicy {
+    /// Where the policy of `session` (or of commands run without one) is stored
+    pub fn path(session: Option<&str>) -> Result<PathBuf> {
+        let dir = Config::find_domguard_dir().unwrap_or_else(Config::domguard_dir);
+        match session {
+            Some(id) => {
+                crate::session::validate_session_id(id)?;
+                Ok(dir.join("dialog").join(format!("{}.toml", id)))
+            }
+            None => Ok(dir.join("dialog.toml")),
+        }
+    }
+
+    /// Load the dialog policy, if one is set
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(Some(toml::from_str(&content)?))
+    }
+
+    /// Start answering dialogs with the policy of `session`, if one is set
+    pub async fn apply(
+        cdp: &CdpConnection,
+        session: Option<&str>,
+    ) -> Result<Option<crate::cdp::DialogLog>> {
+        match Self::load(&Self::path(session)?)? {
+            Some(policy) => Ok(Some(
+                cdp.auto_handle_dialogs(policy.accept, policy.prompt_text)
+                    .await?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Save the dialog policy
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string_pretty(self)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Remove the dialog policy, returning whether one was set
+    pub fn clear(path: &Path) -> Result<bool> {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+/// What `click --then-*` waits for after the click
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FollowUp {
+    /// An element matching the selector exists
+    Selector(String),
+    /// The URL contains the text
+    UrlContains(String),
+    /// No requests in flight for a moment
+    NetworkIdle,
+}
+
+impl std::fmt::Display for FollowUp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Selector(selector) => write!(f, "\"{}\" appeared", selector),
+            Self::UrlContains(text) => write!(f, "URL contained \"{}\"", text),
+            Self::NetworkIdle => write!(f, "network idle"),
+        }
+    }
+}
+
+/// Steps of `interact table`, run in this order: sort, find a row (paging
+/// forward until it shows up when `next_page` is set) or just page forward,
+/// then click a cell of the found row
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableAction {
+    /// Column whose header to click (1-based number or header text)
+    pub sort: Option<String>,
+    /// Text of a cell (or part of a row) to look for
+    pub find_row: Option<String>,
+    /// Column of the found row to click
+    pub click_cell: Option<String>,
+    pub next_page: bool,
+}
+
+/// Points of `interact gesture --path`, written "x,y;x,y;..."
+#[derive(Debug, Clone, PartialEq)]
+pub struct GesturePath(pub Vec<(f64, f64)>);
+
+impl std::str::FromStr for GesturePath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let points =
+            s.split(';')
+                .map(str::trim)
+                .filter(|point| !point.is_empty())
+                .map(|point| {
+                    let (x, y) = point
+                        .split_once(',')
+                        .ok_or_else(|| format!("Invalid point \"{}\" (expected x,y)", point))?;
+                    let coord = |value: &str| {
+                        value.trim().parse::<f64>().map_err(|_| {
+                            format!("Invalid coordinate \"{}\" in \"{}\"", value, point)
+                        })
+                    };
+                    Ok((coord(x)?, coord(y)?))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+        if points.len() < 2 {
+            return Err("A gesture path needs at least two points (x,y;x,y)".to_string());
+        }
+        Ok(Self(points))
+    }
+}
+
+/// What an `x,y` is measured from
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum CoordSpace {
+    /// The visible window's top-left (what input events use)
+    #[default]
+    Viewport,
+    /// The document's top-left, independent of scrolling
+    Page,
+    /// The top-left of the element matching a selector
+    Element(String),
+}
+
+impl std::fmt::Display for CoordSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Viewport => write!(f, "viewport"),
+            Self::Page => write!(f, "page"),
+            Self::Element(selector) => write!(f, "element:{}", selector),
+        }
+    }
+}
+
+impl std::str::FromStr for CoordSpace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("element", selector)) if !selector.trim().is_empty() => {
+                Ok(Self::Element(selector.trim().to_string()))
+            }
+            _ => match s {
+                "viewport" => Ok(Self::Viewport),
+                "page" => Ok(Self::Page),
+                other => Err(format!(
+                    "Unknown coordinate space \"{}\" (expected page, viewport or element:<selector>)",
+                    other
+                )),
+            },
+        }
+    }
+}
+
+impl CoordSpace {
+    fn selector(&self) -> Option<&str> {
+        match self {
+            Self::Element(selector) => Some(selector),
+            _ => None,
+        }
+    }
+
+    fn selector_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Self::Element(selector) => Some(selector),
+            _ => None,
+        }
+    }
+
+    /// `point` in this space as page coordinates
+    fn to_page(&self, (x, y): (f64, f64), origin: &CoordinateOrigin) -> (f64, f64) {
+        match self {
+            Self::Page => (x, y),
+            Self::Viewport => (x + origin.scroll_x, y + origin.scroll_y),
+            Self::Element(_) => {
+                let (left, top) = origin.element.unwrap_or_default();
+                (left + origin.scroll_x + x, top + origin.scroll_y + y)
+            }
+        }
+    }
+}
+
+/// `point` in `space` as viewport coordinates for input events
+///
+/// With `scroll`, a point outside the viewport is scrolled to its middle first.
+async fn viewport_point(
+    cdp: &CdpConnection,
+    space: &CoordSpace,
+    point: (f64, f64),
+    scroll: bool,
+) -> Result<(f64, f64)> {
+    let origin = cdp.coordinate_origin(space.selector()).await?;
+    let (page_x, page_y) = space.to_page(point, &origin);
+    let (x, y) = (page_x - origin.scroll_x, page_y - origin.scroll_y);
+    let visible = (0.0..origin.width).contains(&x) && (0.0..origin.height).contains(&y);
+    if visible || !scroll {
+        return Ok((x, y));
+    }
+    let (scroll_x, scroll_y) = cdp
+        .scroll_window_to(page_x - origin.width / 2.0, page_y - origin.height / 2.0)
+        .await?;
+    Ok((page_x - scroll_x, page_y - scroll_y))
+}
+
+/// How a point was given, for messages: "(x, y)" plus the space unless viewport
+fn describe_point((x, y): (f64, f64), space: &CoordSpace) -> String {
+    match space {
+        CoordSpace::Viewport => format!("({}, {})", x, y),
+        space => format!("({}, {}) in {}", x, y, space),
+    }
+}
+
 /// Interact subcommand types
 #[derive(Debug, Clone)]
 pub enum InteractCommand {
     Click {
         selector: Option<String>,
         coords: Option<(f64, f64)>,
+        /// What `coords` are measured from
+        space: CoordSpace,
         nth: i32,
         text: Option<String>,
+        then: Option<FollowUp>,
     },
     Type {
         selector: Option<String>,
         text: Option<String>,
         focused: bool,
+        /// Per-character key events with this base delay (None = set value at once)
+        keystroke_delay: Option<u64>,
+        /// Shape input events for this framework (None = detect)
+        framework: Option<Framework>,
     },
     Key {
         keys: String,
     },
     Hover {
         selector: String,
+        /// Screenshot taken while the hover is held
+        capture: Option<PathBuf>,
+        /// Element clicked while the hover is held (e.g. a menu item)
+        then_click: Option<String>,
     },
     Scroll {
         down: Option<i64>,
@@ -68,16 +613,32 @@ pub enum InteractCommand {
         left: Option<i64>,
         right: Option<i64>,
         to: Option<String>,
+        within: Option<String>,
+        until: Option<String>,
+        until_text: Option<String>,
+        max_scrolls: u32,
     },
     Screenshot {
         full: bool,
+        /// Compose the full page from viewport tiles instead of one capture
+        stitch: bool,
+        /// Clip to the element matching this selector
         element: Option<String>,
-        output: Option<PathBuf>,
+        options: ScreenshotOptions,
     },
     Navigate {
         url: String,
+        credential: Option<Credential>,
+        wait_until: WaitUntil,
+        referrer: Option<String>,
+        timeout_ms: u64,
+    },
+    Back {
+        steps: u32,
+    },
+    Forward {
+        steps: u32,
     },
-    Back,
     Refresh,
     Wait {
         selector: String,
@@ -86,26 +647,42 @@ pub enum InteractCommand {
         timeout_ms: u64,
         text: Option<String>,
         text_gone: Option<String>,
+        /// Wait on the URL or title instead of an element
+        page: Option<PageCondition>,
+        /// Once the element exists, also wait for it to be ready
+        state: Option<ElementCondition>,
     },
     Drag {
         from_selector: Option<String>,
         to_selector: Option<String>,
         from_coords: Option<(f64, f64)>,
         to_coords: Option<(f64, f64)>,
+        space: CoordSpace,
     },
     Select {
         selector: String,
         value: String,
         by_label: bool,
         by_index: bool,
+        framework: Option<Framework>,
+    },
+    Check {
+        selector: String,
+        checked: bool,
     },
     Upload {
         selector: String,
         files: Vec<PathBuf>,
+        /// Treat `selector` as a trigger that opens the native file chooser
+        via_chooser: bool,
     },
     Dialog {
         accept: bool,
         text: Option<String>,
+        timeout_ms: u64,
+    },
+    DismissConsent {
+        timeout_ms: u64,
     },
     Resize {
         width: u32,
@@ -113,21 +690,37 @@ pub enum InteractCommand {
     },
     Pdf {
         output: Option<PathBuf>,
-        landscape: bool,
+        options: PdfOptions,
     },
     // Anthropic Computer Use features
     MouseMove {
         coords: (f64, f64),
+        space: CoordSpace,
     },
     CursorPosition,
     HoldKey {
         key: String,
         duration_ms: u64,
     },
+    /// Press Tab repeatedly and report each focus stop
+    TabWalk {
+        max: usize,
+        screenshot_each: bool,
+    },
     TripleClick {
         selector: Option<String>,
         coords: Option<(f64, f64)>,
     },
+    RightClick {
+        selector: Option<String>,
+        coords: Option<(f64, f64)>,
+        capture: bool,
+    },
+    DoubleClick {
+        selector: Option<String>,
+        coords: Option<(f64, f64)>,
+        capture: bool,
+    },
     MouseDown {
         button: String,
     },
@@ -136,43 +729,559 @@ pub enum InteractCommand {
     },
     ScreenshotRegion {
         region: (i32, i32, i32, i32), // x, y, width, height
-        output: Option<PathBuf>,
+        /// What the region's x,y are measured from
+        space: CoordSpace,
+        options: ScreenshotOptions,
     },
     WaitDuration {
         duration_ms: u64,
     },
+    /// Client-side (SPA) navigation, which fires no load event
+    WaitRouteChange {
+        timeout_ms: u64,
+    },
+    Table {
+        selector: String,
+        action: TableAction,
+    },
+    /// Fill a date input or picker, trying native value, typing, then the calendar
+    Date {
+        selector: String,
+        date: chrono::NaiveDate,
+    },
+    /// Type into a typeahead combobox and pick matching options (several for a multi-select)
+    Combobox {
+        selector: String,
+        choose: Vec<String>,
+        /// Use the list's create entry, or Enter, when nothing matches
+        create_if_missing: bool,
+    },
+    /// Press, move along a path and release, for canvas and WebGL apps
+    Gesture {
+        path: GesturePath,
+        duration_ms: u64,
+        touch: bool,
+    },
+    /// Touch gestures for pages that only listen to touch events
+    Tap {
+        selector: Option<String>,
+        coords: Option<(f64, f64)>,
+    },
+    Swipe {
+        from: (f64, f64),
+        to: (f64, f64),
+        duration_ms: u64,
+    },
+    /// Two fingers moving apart (`scale` > 1) or together around a point
+    Pinch {
+        selector: Option<String>,
+        coords: Option<(f64, f64)>,
+        scale: f64,
+        /// Starting distance between the fingers in pixels
+        distance: f64,
+        duration_ms: u64,
+    },
+}
+
+impl InteractCommand {
+    /// Action name used by site instruction rules
+    fn action_name(&self) -> &'static str {
+        match self {
+            Self::Click { .. } => "click",
+            Self::Type { .. } => "type",
+            Self::Key { .. } => "key",
+            Self::Hover { .. } => "hover",
+            Self::Scroll { .. } => "scroll",
+            Self::Screenshot { .. } | Self::ScreenshotRegion { .. } => "screenshot",
+            Self::Navigate { .. } => "navigate",
+            Self::Back { .. } => "back",
+            Self::Forward { .. } => "forward",
+            Self::Refresh => "refresh",
+            Self::Wait { .. } | Self::WaitDuration { .. } | Self::WaitRouteChange { .. } => "wait",
+            Self::Drag { .. } => "drag",
+            Self::Select { .. } => "select",
+            Self::Check { .. } => "check",
+            Self::Upload { .. } => "upload",
+            Self::Dialog { .. } => "dialog",
+            Self::DismissConsent { .. } => "dismiss-consent",
+            Self::Resize { .. } => "resize",
+            Self::Pdf { .. } => "pdf",
+            Self::MouseMove { .. } => "mouse-move",
+            Self::CursorPosition => "cursor-position",
+            Self::HoldKey { .. } => "hold-key",
+            Self::TabWalk { .. } => "tab-walk",
+            Self::TripleClick { .. } => "triple-click",
+            Self::RightClick { .. } => "right-click",
+            Self::DoubleClick { .. } => "double-click",
+            Self::MouseDown { .. } => "mouse-down",
+            Self::MouseUp { .. } => "mouse-up",
+            Self::Table { .. } => "table",
+            Self::Date { .. } => "date",
+            Self::Combobox { .. } => "combobox",
+            Self::Gesture { .. } => "gesture",
+            Self::Tap { .. } => "tap",
+            Self::Swipe { .. } => "swipe",
+            Self::Pinch { .. } => "pinch",
+        }
+    }
+
+    /// What the sensitive-action checks look at, for the commands they cover
+    fn sensitive_action(&self) -> Option<InteractAction<'_>> {
+        Some(match self {
+            Self::Type {
+                selector,
+                text,
+                focused,
+                ..
+            } => {
+                // With --focused the first positional argument is the text
+                let (selector, text) = if *focused {
+                    (None, selector.as_ref().or(text.as_ref()))
+                } else {
+                    (selector.as_ref(), text.as_ref())
+                };
+                InteractAction::Type {
+                    selector: selector.map(String::as_str),
+                    text: text.map_or("", String::as_str),
+                }
+            }
+            Self::Click { selector, text, .. } => InteractAction::Click {
+                selector: selector.as_deref(),
+                text: text.as_deref(),
+            },
+            Self::Navigate { url, .. } => InteractAction::Navigate { url },
+            Self::Upload { files, .. } => InteractAction::Upload { files },
+            _ => return None,
+        })
+    }
+
+    /// Replace `@name` aliases and `[selectors]` names with the selectors
+    /// they stand for, so later checks see what the action will touch
+    fn resolve_site_selectors(&mut self, site: Option<&SiteInstructions>) -> Result<()> {
+        for selector in self.selectors_mut() {
+            expand_alias(selector, site)?;
+            if let Some(resolved) = site.and_then(|site| site.resolve_selector(selector)) {
+                *selector = resolved.to_string();
+            }
+        }
+        Ok(())
+    }
+
+    /// CSS selectors this command targets
+    fn selectors_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Self::Click {
+                selector,
+                then,
+                space,
+                ..
+            } => {
+                let mut selectors: Vec<&mut String> = selector.iter_mut().collect();
+                if let Some(FollowUp::Selector(next)) = then {
+                    selectors.push(next);
+                }
+                selectors.extend(space.selector_mut());
+                selectors
+            }
+            Self::Type { selector, .. }
+            | Self::TripleClick { selector, .. }
+            | Self::Tap { selector, .. }
+            | Self::Pinch { selector, .. }
+            | Self::RightClick { selector, .. }
+            | Self::DoubleClick { selector, .. } => selector.iter_mut().collect(),
+            Self::Hover {
+                selector,
+                then_click,
+                ..
+            } => std::iter::once(selector)
+                .chain(then_click.iter_mut())
+                .collect(),
+            Self::Scroll {
+                to, within, until, ..
+            } => to
+                .iter_mut()
+                .chain(within.iter_mut())
+                .chain(until.iter_mut())
+                .collect(),
+            Self::Screenshot { element, .. } => element.iter_mut().collect(),
+            Self::Wait { selector, .. } if !selector.is_empty() => vec![selector],
+            Self::Drag {
+                from_selector,
+                to_selector,
+                space,
+                ..
+            } => from_selector
+                .iter_mut()
+                .chain(to_selector.iter_mut())
+                .chain(space.selector_mut())
+                .collect(),
+            Self::MouseMove { space, .. } | Self::ScreenshotRegion { space, .. } => {
+                space.selector_mut().into_iter().collect()
+            }
+            Self::Select { selector, .. }
+            | Self::Check { selector, .. }
+            | Self::Upload { selector, .. }
+            | Self::Table { selector, .. }
+            | Self::Date { selector, .. }
+            | Self::Combobox { selector, .. } => vec![selector],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Points this command takes
+    fn coords_mut(&mut self) -> Vec<&mut (f64, f64)> {
+        match self {
+            Self::Click { coords, .. }
+            | Self::TripleClick { coords, .. }
+            | Self::RightClick { coords, .. }
+            | Self::DoubleClick { coords, .. }
+            | Self::Tap { coords, .. }
+            | Self::Pinch { coords, .. } => coords.iter_mut().collect(),
+            Self::Drag {
+                from_coords,
+                to_coords,
+                ..
+            } => from_coords.iter_mut().chain(to_coords.iter_mut()).collect(),
+            Self::MouseMove { coords, .. } => vec![coords],
+            Self::Swipe { from, to, .. } => vec![from, to],
+            Self::Gesture { path, .. } => path.0.iter_mut().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Divide every point (and a screenshot region) by `ratio`
+    fn scale_coords(&mut self, ratio: f64) {
+        for (x, y) in self.coords_mut() {
+            *x /= ratio;
+            *y /= ratio;
+        }
+        if let Self::ScreenshotRegion { region, .. } = self {
+            let scale = |v: i32| (f64::from(v) / ratio).round() as i32;
+            *region = (
+                scale(region.0),
+                scale(region.1),
+                scale(region.2),
+                scale(region.3),
+            );
+        }
+    }
+}
+
+/// Flags that let an interact command past its guards
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GuardOverrides {
+    /// Navigate to a blocked site anyway (`--override-block`, if the config allows it)
+    pub override_block: bool,
+    /// The user confirmed a sensitive action (`--confirm-sensitive`)
+    pub confirm_sensitive: bool,
+}
+
+/// An interact command whose arguments passed the blocked sites list and
+/// the sensitive-action checks
+///
+/// [`run_interact`] and [`run_backend_interact`] only take screened
+/// commands, so no caller can skip the guards.
+#[derive(Debug, Clone)]
+pub struct Screened {
+    command: InteractCommand,
+    overrides: GuardOverrides,
+}
+
+/// Check what an interact command can be judged on before connecting
+///
+/// Navigations are refused on blocked sites, and navigations and uploads
+/// go through the sensitive-action checks (`[security] on_sensitive`).
+pub fn screen(
+    config: &Config,
+    command: InteractCommand,
+    overrides: GuardOverrides,
+) -> Result<Screened> {
+    if let InteractCommand::Navigate { url, .. } = &command {
+        crate::security::enforce_site_policy(
+            config,
+            "interact navigate",
+            url,
+            overrides.override_block,
+        )?;
+    }
+    if config.security.on_sensitive != SecurityEnforcement::Off {
+        if let Some(detection) = command
+            .sensitive_action()
+            .and_then(|action| action.check_args())
+        {
+            crate::security::enforce(
+                &detection,
+                config.security.on_sensitive,
+                overrides.confirm_sensitive,
+            )?;
+        }
+    }
+    Ok(Screened { command, overrides })
+}
+
+/// Check a command on the page it is about to touch, warning about what is let through
+async fn check_sensitive(
+    cdp: &CdpConnection,
+    config: &Config,
+    command: &InteractCommand,
+    confirmed: bool,
+    formatter: &Formatter,
+) -> Result<Option<SensitiveActionDetection>> {
+    if config.security.on_sensitive == SecurityEnforcement::Off {
+        return Ok(None);
+    }
+    let Some(action) = command.sensitive_action() else {
+        return Ok(None);
+    };
+    let Some(detection) = action.check(cdp).await else {
+        return Ok(None);
+    };
+    crate::security::enforce(&detection, config.security.on_sensitive, confirmed)?;
+    formatter.warning(&format!(
+        "Sensitive action ({:?}): {}",
+        detection.severity,
+        detection.reason.as_deref().unwrap_or("detected")
+    ));
+    Ok(Some(detection))
+}
+
+/// Tell the human about a takeover the command requested
+fn notify_takeover(takeover: &TakeoverSession, config: &Config, formatter: &Formatter) {
+    crate::takeover::notify(
+        takeover,
+        config.takeover.notify_desktop,
+        config.takeover.webhook_url.as_deref(),
+    )
+    .warn_failures(formatter);
+}
+
+/// Charge the command to its domain's action budget
+async fn enforce_budget(
+    cdp: &CdpConnection,
+    config: &Config,
+    command: &InteractCommand,
+    session: Option<&str>,
+    formatter: &Formatter,
+) -> Result<()> {
+    let navigate_to = match command {
+        InteractCommand::Navigate { url, .. } => Some(url.as_str()),
+        _ => None,
+    };
+    let Some(refusal) = crate::budget::enforce(cdp, config, navigate_to, session).await? else {
+        return Ok(());
+    };
+    if let Some(takeover) = &refusal.takeover {
+        notify_takeover(takeover, config, formatter);
+        formatter.warning(&format!("Takeover {} requested", takeover.id));
+    }
+    Err(refusal.exceeded.into())
+}
+
+/// Start a takeover if the page shows an unsolved CAPTCHA, returning its ID
+async fn request_captcha_takeover(
+    cdp: &CdpConnection,
+    config: &Config,
+    formatter: &Formatter,
+) -> Option<String> {
+    let takeover = match crate::takeover::request_for_captcha(cdp).await {
+        Ok(takeover) => takeover?,
+        Err(e) => {
+            formatter.warning(&format!("Failed to request takeover: {}", e));
+            return None;
+        }
+    };
+    notify_takeover(&takeover, config, formatter);
+    formatter.warning(&format!(
+        "{} - takeover {} requested",
+        takeover.message, takeover.id
+    ));
+    formatter.hint("Solve it in the browser, then run 'domguard takeover done'");
+    Some(takeover.id)
+}
+
+/// Treat the command's coordinates as screenshot image pixels
+///
+/// Screenshots are taken at the device pixel ratio, so on a 2x display a
+/// point read off one is twice its CSS position. Divides by the page's ratio.
+pub async fn coords_from_screenshot(
+    cdp: &CdpConnection,
+    command: &mut Screened,
+    formatter: &Formatter,
+) -> Result<()> {
+    let ratio = cdp.device_pixel_ratio().await?;
+    if (ratio - 1.0).abs() > f64::EPSILON {
+        command.command.scale_coords(ratio);
+        formatter.hint(&format!(
+            "Screenshot coordinates scaled by 1/{} (devicePixelRatio)",
+            ratio
+        ));
+    }
+    Ok(())
 }
 
 /// Run interact command
+///
+/// Site rules go first, so the sensitive-action check sees the element an
+/// `@alias` stands for. The command is then charged to its domain's budget
+/// (`session` is the recorded session navigations count against), and with
+/// `takeover_on_captcha` a CAPTCHA it runs into becomes a takeover request.
 pub async fn run_interact(
     cdp: &CdpConnection,
     config: &Config,
-    command: InteractCommand,
+    command: Screened,
+    session: Option<&str>,
     formatter: &Formatter,
 ) -> Result<()> {
     let start = Instant::now();
+    let Screened {
+        mut command,
+        overrides,
+    } = command;
+    let resolving = Instant::now();
+    let wait_ms = apply_site_rules(cdp, config, &mut command, formatter).await?;
+    let resolve_ms = (resolving.elapsed().as_millis() as u64).saturating_sub(wait_ms);
+    let security = check_sensitive(
+        cdp,
+        config,
+        &command,
+        overrides.confirm_sensitive,
+        formatter,
+    )
+    .await?;
+    enforce_budget(cdp, config, &command, session, formatter).await?;
+
+    let navigation = matches!(command, InteractCommand::Navigate { .. });
+    let prepared = Prepared {
+        command,
+        security,
+        start,
+        resolve_ms,
+        wait_ms,
+    };
+    let result = perform(cdp, config, prepared, formatter).await;
+
+    // A CAPTCHA after navigating, or behind a failed action, needs a human
+    if config.takeover.takeover_on_captcha && (navigation || result.is_err()) {
+        if let Some(id) = request_captcha_takeover(cdp, config, formatter).await {
+            return result.map_err(|e| {
+                anyhow::anyhow!("{} (CAPTCHA detected; takeover {} requested)", e, id)
+            });
+        }
+    }
+    result
+}
+
+/// A command past its checks, and the time spent getting it there
+struct Prepared {
+    command: InteractCommand,
+    security: Option<SensitiveActionDetection>,
+    start: Instant,
+    resolve_ms: u64,
+    wait_ms: u64,
+}
+
+async fn perform(
+    cdp: &CdpConnection,
+    config: &Config,
+    prepared: Prepared,
+    formatter: &Formatter,
+) -> Result<()> {
+    let Prepared {
+        mut command,
+        security,
+        start,
+        resolve_ms,
+        mut wait_ms,
+    } = prepared;
+    let mut recovery_path = Vec::new();
+    let mut phases = None;
+    let action = command.action_name();
+    crate::telemetry::set_attribute("domguard.action", action);
+    let selectors: Vec<String> = command
+        .selectors_mut()
+        .into_iter()
+        .map(|s| s.clone())
+        .collect();
+    if !selectors.is_empty() {
+        crate::telemetry::set_attribute("domguard.selector", &selectors.join(", "));
+    }
+    // Lets a later `wait --spa-navigation` see route changes this action causes
+    let _ = cdp.track_page_activity().await;
+
+    // Tabs this action opens (OAuth popups, target=_blank links) become the active tab
+    let popups = if config.defaults.follow_popups {
+        Some(cdp.watch_popups().await?)
+    } else {
+        None
+    };
 
     let result = match command {
         InteractCommand::Click {
             selector,
             coords,
+            space,
             nth,
             text,
+            then,
         } => {
-            interact_click(
-                cdp,
-                selector.as_deref(),
-                coords,
-                nth,
-                text.as_deref(),
-                formatter,
-            )
-            .await
+            // Watch before clicking so requests the click starts are counted
+            let network = match then {
+                Some(FollowUp::NetworkIdle) => Some(cdp.watch_network().await?),
+                _ => None,
+            };
+            let clicked = Instant::now();
+            let correction = effective_correction(cdp, config).await;
+            let mut recovery = Recovery::new(&correction, "click", selector.as_deref());
+            let result = loop {
+                let attempt = interact_click(
+                    cdp,
+                    selector.as_deref(),
+                    coords,
+                    &space,
+                    nth,
+                    text.as_deref(),
+                    formatter,
+                )
+                .await;
+                if let Err(e) = &attempt {
+                    if recovery.recover(cdp, e, formatter).await {
+                        continue;
+                    }
+                }
+                break attempt;
+            };
+            let result = recovery.annotate(result);
+            recovery_path = recovery.attempts;
+            match (result, then) {
+                (Ok(result), Some(follow_up)) => {
+                    let click_ms = clicked.elapsed().as_millis() as u64;
+                    let waiting = Instant::now();
+                    wait_follow_up(
+                        cdp,
+                        &follow_up,
+                        network.as_ref(),
+                        config.defaults.timeout_ms,
+                    )
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{} (after clicking in {}ms)", e, click_ms))?;
+                    let wait_ms = waiting.elapsed().as_millis() as u64;
+                    formatter.success(&format!("{} after {}ms", follow_up, wait_ms));
+                    phases = Some(PhaseTimings {
+                        click_ms,
+                        wait_ms,
+                        waited_for: follow_up.to_string(),
+                    });
+                    Ok(result)
+                }
+                (result, _) => result,
+            }
         }
         InteractCommand::Type {
             selector,
             text,
             focused,
+            keystroke_delay,
+            framework,
         } => {
             // When --focused is used, the first positional arg (selector) is actually the text
             let (actual_selector, actual_text) = if focused {
@@ -182,35 +1291,135 @@ pub async fn run_interact(
             };
             match actual_text {
                 Some(t) => {
-                    interact_type(
-                        cdp,
-                        actual_selector.map(|s| s.as_str()),
-                        t,
-                        focused,
-                        formatter,
-                    )
-                    .await
+                    let actual_selector = actual_selector.map(|s| s.as_str());
+                    let correction = effective_correction(cdp, config).await;
+                    let mut recovery = Recovery::new(&correction, "type", actual_selector);
+                    let result = loop {
+                        let attempt = interact_type(
+                            cdp,
+                            actual_selector,
+                            t,
+                            focused,
+                            keystroke_delay,
+                            framework,
+                            formatter,
+                        )
+                        .await;
+                        if let Err(e) = &attempt {
+                            if recovery.recover(cdp, e, formatter).await {
+                                continue;
+                            }
+                        }
+                        break attempt;
+                    };
+                    let result = recovery.annotate(result);
+                    recovery_path = recovery.attempts;
+                    result
                 }
                 None => Err(anyhow::anyhow!("Text to type is required")),
             }
         }
         InteractCommand::Key { keys } => interact_key(cdp, &keys, formatter).await,
-        InteractCommand::Hover { selector } => interact_hover(cdp, &selector, formatter).await,
+        InteractCommand::Hover {
+            selector,
+            capture: None,
+            then_click: None,
+        } => interact_hover(cdp, &selector, formatter).await,
+        InteractCommand::Hover {
+            selector,
+            capture,
+            then_click,
+        } => {
+            interact_hover_hold(
+                cdp,
+                &selector,
+                capture.as_deref(),
+                then_click.as_deref(),
+                formatter,
+            )
+            .await
+        }
         InteractCommand::Scroll {
             down,
             up,
             left,
             right,
             to,
-        } => interact_scroll(cdp, down, up, left, right, to.as_deref(), formatter).await,
+            within,
+            until,
+            until_text,
+            max_scrolls,
+        } => {
+            let x = right.unwrap_or(0) - left.unwrap_or(0);
+            let y = down.unwrap_or(0) - up.unwrap_or(0);
+            if until.is_some() || until_text.is_some() {
+                interact_scroll_until(
+                    cdp,
+                    until.as_deref(),
+                    until_text.as_deref(),
+                    within.as_deref(),
+                    y,
+                    max_scrolls,
+                    formatter,
+                )
+                .await
+            } else {
+                interact_scroll(cdp, x, y, to.as_deref(), within.as_deref(), formatter).await
+            }
+        }
         InteractCommand::Screenshot {
             full,
+            stitch,
             element,
-            output,
-        } => interact_screenshot(cdp, config, full, element.as_deref(), output, formatter).await,
-        InteractCommand::Navigate { url } => interact_navigate(cdp, &url, formatter).await,
-        InteractCommand::Back => interact_back(cdp, formatter).await,
+            options,
+        } => {
+            interact_screenshot(
+                cdp,
+                config,
+                full,
+                stitch,
+                element.as_deref(),
+                options,
+                formatter,
+            )
+            .await
+        }
+        InteractCommand::Navigate {
+            url,
+            credential,
+            wait_until,
+            referrer,
+            timeout_ms,
+        } => {
+            interact_navigate(
+                cdp,
+                &url,
+                credential,
+                wait_until,
+                referrer.as_deref(),
+                timeout_ms,
+                formatter,
+            )
+            .await
+        }
+        InteractCommand::Back { steps } => {
+            interact_history(cdp, -i64::from(steps), formatter).await
+        }
+        InteractCommand::Forward { steps } => {
+            interact_history(cdp, i64::from(steps), formatter).await
+        }
         InteractCommand::Refresh => interact_refresh(cdp, formatter).await,
+        InteractCommand::Wait {
+            timeout_ms,
+            page: Some(condition),
+            ..
+        } => interact_wait_page(cdp, &condition, timeout_ms, formatter).await,
+        InteractCommand::Wait {
+            selector,
+            timeout_ms,
+            state: Some(condition),
+            ..
+        } => interact_wait_state(cdp, &selector, &condition, timeout_ms, formatter).await,
         InteractCommand::Wait {
             selector,
             visible,
@@ -218,6 +1427,8 @@ pub async fn run_interact(
             timeout_ms,
             text,
             text_gone,
+            page: None,
+            state: None,
         } => {
             interact_wait(
                 cdp,
@@ -236,6 +1447,7 @@ pub async fn run_interact(
             to_selector,
             from_coords,
             to_coords,
+            space,
         } => {
             interact_drag(
                 cdp,
@@ -243,6 +1455,7 @@ pub async fn run_interact(
                 to_selector.as_deref(),
                 from_coords,
                 to_coords,
+                &space,
                 formatter,
             )
             .await
@@ -252,63 +1465,326 @@ pub async fn run_interact(
             value,
             by_label,
             by_index,
-        } => interact_select(cdp, &selector, &value, by_label, by_index, formatter).await,
-        InteractCommand::Upload { selector, files } => {
-            interact_upload(cdp, &selector, &files, formatter).await
+            framework,
+        } => {
+            interact_select(
+                cdp, &selector, &value, by_label, by_index, framework, formatter,
+            )
+            .await
         }
-        InteractCommand::Dialog { accept, text } => {
-            interact_dialog(cdp, accept, text.as_deref(), formatter).await
+        InteractCommand::Check { selector, checked } => {
+            interact_check(cdp, &selector, checked, formatter).await
+        }
+        InteractCommand::Upload {
+            selector,
+            files,
+            via_chooser,
+        } => interact_upload(cdp, &selector, &files, via_chooser, formatter).await,
+        InteractCommand::Dialog {
+            accept,
+            text,
+            timeout_ms,
+        } => interact_dialog(cdp, accept, text.as_deref(), timeout_ms, formatter).await,
+        InteractCommand::DismissConsent { timeout_ms } => {
+            interact_dismiss_consent(cdp, config, timeout_ms, formatter).await
         }
         InteractCommand::Resize { width, height } => {
             interact_resize(cdp, width, height, formatter).await
         }
-        InteractCommand::Pdf { output, landscape } => {
-            interact_pdf(cdp, config, output, landscape, formatter).await
+        InteractCommand::Pdf { output, options } => {
+            interact_pdf(cdp, config, output, options, formatter).await
         }
         // Anthropic Computer Use features
-        InteractCommand::MouseMove { coords } => interact_mouse_move(cdp, coords, formatter).await,
+        InteractCommand::MouseMove { coords, space } => {
+            interact_mouse_move(cdp, coords, &space, formatter).await
+        }
         InteractCommand::CursorPosition => interact_cursor_position(cdp, formatter).await,
         InteractCommand::HoldKey { key, duration_ms } => {
             interact_hold_key(cdp, &key, duration_ms, formatter).await
         }
+        InteractCommand::TabWalk {
+            max,
+            screenshot_each,
+        } => interact_tab_walk(cdp, max, screenshot_each, formatter).await,
         InteractCommand::TripleClick { selector, coords } => {
             interact_triple_click(cdp, selector.as_deref(), coords, formatter).await
         }
+        InteractCommand::RightClick {
+            selector,
+            coords,
+            capture,
+        } => {
+            interact_native_click(
+                cdp,
+                selector.as_deref(),
+                coords,
+                "right",
+                1,
+                capture,
+                formatter,
+            )
+            .await
+        }
+        InteractCommand::DoubleClick {
+            selector,
+            coords,
+            capture,
+        } => {
+            interact_native_click(
+                cdp,
+                selector.as_deref(),
+                coords,
+                "left",
+                2,
+                capture,
+                formatter,
+            )
+            .await
+        }
         InteractCommand::MouseDown { button } => interact_mouse_down(cdp, &button, formatter).await,
         InteractCommand::MouseUp { button } => interact_mouse_up(cdp, &button, formatter).await,
-        InteractCommand::ScreenshotRegion { region, output } => {
-            interact_screenshot_region(cdp, config, region, output, formatter).await
-        }
+        InteractCommand::ScreenshotRegion {
+            region,
+            space,
+            options,
+        } => interact_screenshot_region(cdp, config, region, &space, options, formatter).await,
         InteractCommand::WaitDuration { duration_ms } => {
             interact_wait_duration(duration_ms, formatter).await
         }
+        InteractCommand::WaitRouteChange { timeout_ms } => {
+            let url = cdp.wait_for_route_change(timeout_ms).await?;
+            formatter.success(&format!("Client-side navigation to {}", url));
+            Ok(InteractResult {
+                action: "wait".to_string(),
+                target: Some("spa-navigation".to_string()),
+                details: Some(format!("url: {}", url)),
+            })
+        }
+        InteractCommand::Table { selector, action } => {
+            interact_table(
+                cdp,
+                &selector,
+                &action,
+                config.defaults.timeout_ms,
+                formatter,
+            )
+            .await
+        }
+        InteractCommand::Date { selector, date } => {
+            interact_date(cdp, &selector, date, formatter).await
+        }
+        InteractCommand::Gesture {
+            path,
+            duration_ms,
+            touch,
+        } => interact_gesture(cdp, &path, duration_ms, touch, formatter).await,
+        InteractCommand::Tap { selector, coords } => {
+            interact_tap(cdp, selector.as_deref(), coords, formatter).await
+        }
+        InteractCommand::Swipe {
+            from,
+            to,
+            duration_ms,
+        } => interact_swipe(cdp, from, to, duration_ms, formatter).await,
+        InteractCommand::Pinch {
+            selector,
+            coords,
+            scale,
+            distance,
+            duration_ms,
+        } => {
+            let (center, target) = touch_target(cdp, selector.as_deref(), coords).await?;
+            interact_pinch(
+                cdp,
+                center,
+                &target,
+                scale,
+                distance,
+                duration_ms,
+                formatter,
+            )
+            .await
+        }
+        InteractCommand::Combobox {
+            selector,
+            choose,
+            create_if_missing,
+        } => {
+            interact_combobox(
+                cdp,
+                &selector,
+                &choose,
+                create_if_missing,
+                config.defaults.timeout_ms,
+                formatter,
+            )
+            .await
+        }
     }?;
 
+    if config.defaults.auto_dismiss_consent
+        && matches!(action, "navigate" | "back" | "forward" | "refresh")
+    {
+        match dismiss_consent(cdp, config, AUTO_CONSENT_WAIT_MS).await {
+            Ok(Some(dismissed)) => formatter.success(&format!(
+                "Accepted cookie consent ({}: {})",
+                dismissed.rule, dismissed.target
+            )),
+            Ok(None) => {}
+            Err(e) => formatter.warning(&format!("Could not check for a consent banner: {}", e)),
+        }
+    }
+
+    if let Some(phases) = &phases {
+        wait_ms += phases.wait_ms;
+    }
+    let following = Instant::now();
+    let popup = match &popups {
+        Some(popups) => cdp.follow_popup(popups).await?,
+        None => None,
+    };
+    if let Some(popup) = &popup {
+        formatter.success(&format!("Following popup {} ({})", popup.tab_id, popup.url));
+    }
+    wait_ms += following.elapsed().as_millis() as u64;
+
     let elapsed = start.elapsed().as_millis() as u64;
 
     // Use formatter.output for consistent output formatting
-    let cmd_result = CommandResult::success(result).with_timing(elapsed);
+    let cmd_result = CommandResult::success(RecoveredResult {
+        result,
+        recovery: recovery_path,
+        security,
+        popup,
+        phases,
+    })
+    .with_timing_breakdown(Timing::new(cdp.connect_ms(), resolve_ms, wait_ms, elapsed));
     formatter.output(&cmd_result);
 
     Ok(())
 }
 
+/// Run a core interact command through another browser backend
+///
+/// Covers what `BrowserBackend` offers: navigate, click and type by selector,
+/// and screenshots. Clicks and typing are judged sensitive by their
+/// selectors, as there is no CDP to describe the element.
+pub async fn run_backend_interact(
+    backend: &dyn BrowserBackend,
+    config: &Config,
+    command: Screened,
+    formatter: &Formatter,
+) -> Result<()> {
+    let start = Instant::now();
+    if config.security.on_sensitive != SecurityEnforcement::Off {
+        if let Some(detection) = command
+            .command
+            .sensitive_action()
+            .and_then(|action| action.check_selector())
+        {
+            crate::security::enforce(
+                &detection,
+                config.security.on_sensitive,
+                command.overrides.confirm_sensitive,
+            )?;
+            formatter.warning(&format!(
+                "Sensitive action ({:?}): {}",
+                detection.severity,
+                detection.reason.as_deref().unwrap_or("detected")
+            ));
+        }
+    }
+
+    let result = match command.command {
+        InteractCommand::Navigate { url, .. } => {
+            backend.navigate(&url).await?;
+            let title = backend
+                .evaluate("document.title")
+                .await?
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            formatter.success(&format!("Navigated to: {} - {}", url, title));
+            InteractResult {
+                action: "navigate".to_string(),
+                target: Some(backend.current_url().await.unwrap_or(url)),
+                details: Some(title),
+            }
+        }
+        InteractCommand::Click {
+            selector: Some(selector),
+            ..
+        } => {
+            backend.click(&selector).await?;
+            formatter.success(&format!("Clicked \"{}\"", selector));
+            InteractResult {
+                action: "click".to_string(),
+                target: Some(selector),
+                details: None,
+            }
+        }
+        InteractCommand::Type {
+            selector: Some(selector),
+            text: Some(text),
+            ..
+        } => {
+            backend.type_into(&selector, &text).await?;
+            formatter.success(&format!("Typed into \"{}\"", selector));
+            InteractResult {
+                action: "type".to_string(),
+                target: Some(selector),
+                details: None, // Don't log text for security
+            }
+        }
+        InteractCommand::Screenshot {
+            full,
+            stitch,
+            element,
+            options,
+        } => {
+            interact_screenshot(
+                backend,
+                config,
+                full,
+                stitch,
+                element.as_deref(),
+                options,
+                formatter,
+            )
+            .await?
+        }
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Not supported by the {} backend (it covers navigate, click, type, screenshot and eval)",
+                backend.name()
+            ))
+        }
+    };
+
+    let cmd_result = CommandResult::success(result).with_timing(start.elapsed().as_millis() as u64);
+    formatter.output(&cmd_result);
+    Ok(())
+}
+
 /// Click element or coordinates
 async fn interact_click(
     cdp: &CdpConnection,
     selector: Option<&str>,
     coords: Option<(f64, f64)>,
+    space: &CoordSpace,
     nth: i32,
     text: Option<&str>,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
-    if let Some((x, y)) = coords {
+    if let Some(point) = coords {
+        let (x, y) = viewport_point(cdp, space, point, true).await?;
         cdp.click_at(x, y).await?;
-        formatter.success(&format!("Clicked at ({}, {})", x, y));
+        let target = describe_point(point, space);
+        formatter.success(&format!("Clicked at {}", target));
         Ok(InteractResult {
             action: "click".to_string(),
-            target: Some(format!("({}, {})", x, y)),
-            details: None,
+            target: Some(target),
+            details: (*space != CoordSpace::Viewport).then(|| format!("viewport: ({}, {})", x, y)),
         })
     } else if let Some(txt) = text {
         cdp.click_by_text(txt, nth).await?;
@@ -328,21 +1804,25 @@ async fn interact_click(
             },
         })
     } else if let Some(sel) = selector {
-        cdp.click(sel, nth).await?;
+        let fallback = cdp.click_unoccluded(sel, nth).await?;
         let nth_info = if nth != 0 {
             format!(" (nth: {})", nth)
         } else {
             String::new()
         };
-        formatter.success(&format!("Clicked \"{}\"{}", sel, nth_info));
+        let via = fallback
+            .as_ref()
+            .map_or_else(String::new, |fallback| format!(" via {}", fallback));
+        formatter.success(&format!("Clicked \"{}\"{}{}", sel, nth_info, via));
+        let details: Vec<String> = (nth != 0)
+            .then(|| format!("nth: {}", nth))
+            .into_iter()
+            .chain(fallback.map(|fallback| format!("fallback: {}", fallback)))
+            .collect();
         Ok(InteractResult {
             action: "click".to_string(),
             target: Some(sel.to_string()),
-            details: if nth != 0 {
-                Some(format!("nth: {}", nth))
-            } else {
-                None
-            },
+            details: (!details.is_empty()).then(|| details.join(", ")),
         })
     } else {
         Err(anyhow::anyhow!(
@@ -357,10 +1837,34 @@ async fn interact_type(
     selector: Option<&str>,
     text: &str,
     focused: bool,
+    keystroke_delay: Option<u64>,
+    framework: Option<Framework>,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
+    if let Some(delay) = keystroke_delay {
+        if let Some(sel) = selector.filter(|_| !focused) {
+            cdp.focus(sel).await?;
+        }
+        cdp.type_keystrokes(text, delay).await?;
+        let target = if focused {
+            "focused"
+        } else {
+            selector.unwrap_or("focused")
+        };
+        formatter.success(&format!(
+            "Typed {} keystrokes into \"{}\"",
+            text.chars().count(),
+            target
+        ));
+        return Ok(InteractResult {
+            action: "type".to_string(),
+            target: Some(target.to_string()),
+            details: None, // Don't log text for security
+        });
+    }
+
     if focused {
-        cdp.type_focused(text).await?;
+        cdp.type_focused(text, framework).await?;
         formatter.success("Typed into focused element");
         Ok(InteractResult {
             action: "type".to_string(),
@@ -368,8 +1872,12 @@ async fn interact_type(
             details: None, // Don't log text for security
         })
     } else if let Some(sel) = selector {
-        cdp.type_into(sel, text).await?;
-        formatter.success(&format!("Typed into \"{}\"", sel));
+        let used = cdp.type_into_as(sel, text, framework).await?;
+        formatter.success(&format!(
+            "Typed into \"{}\"{}",
+            sel,
+            events_note(used.as_deref())
+        ));
         Ok(InteractResult {
             action: "type".to_string(),
             target: Some(sel.to_string()),
@@ -386,26 +1894,8 @@ async fn interact_key(
     keys: &str,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
-    // Parse key sequence (space-separated)
-    let key_list: Vec<&str> = keys.split_whitespace().collect();
-
-    for key in &key_list {
-        // Handle modifier combinations like "cmd+k"
-        if key.contains('+') {
-            let parts: Vec<&str> = key.split('+').collect();
-            // For now, just press the final key (full modifier support needs more work)
-            if let Some(final_key) = parts.last() {
-                cdp.press_key(final_key).await?;
-            }
-        } else {
-            cdp.press_key(key).await?;
-        }
-
-        // Small delay between keys
-        if key_list.len() > 1 {
-            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
-        }
-    }
+    // Space-separated sequence; each entry may carry modifiers ("cmd+k")
+    cdp.press_key(keys).await?;
 
     formatter.success(&format!("Pressed: {}", keys));
     Ok(InteractResult {
@@ -415,6 +1905,9 @@ async fn interact_key(
     })
 }
 
+/// How long a held hover waits before capturing or clicking
+const HOVER_SETTLE_MS: u64 = 300;
+
 /// Hover over element
 async fn interact_hover(
     cdp: &CdpConnection,
@@ -430,14 +1923,49 @@ async fn interact_hover(
     })
 }
 
+/// Hover with the real pointer, then capture or click before the hover ends
+///
+/// Menus that only exist while hovered are gone by the time a separate
+/// command runs, so both happen within this one.
+async fn interact_hover_hold(
+    cdp: &CdpConnection,
+    selector: &str,
+    capture: Option<&Path>,
+    then_click: Option<&str>,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    cdp.hover_pointer(selector).await?;
+    // Let hover menus finish opening (transitions, delayed handlers)
+    tokio::time::sleep(std::time::Duration::from_millis(HOVER_SETTLE_MS)).await;
+
+    let mut done = Vec::new();
+    if let Some(path) = capture {
+        let data = cdp.capture(&CaptureOptions::default()).await?;
+        std::fs::write(path, &data)?;
+        formatter.success(&format!("Captured hover state: {}", path.display()));
+        done.push(format!("captured {}", path.display()));
+    }
+    if let Some(target) = then_click {
+        let (x, y) = cdp.element_click_point(target).await?;
+        cdp.dispatch_click_at(x, y, "left", 1).await?;
+        formatter.success(&format!("Clicked \"{}\" while hovering", target));
+        done.push(format!("clicked \"{}\"", target));
+    }
+
+    Ok(InteractResult {
+        action: "hover".to_string(),
+        target: Some(selector.to_string()),
+        details: Some(done.join(", ")),
+    })
+}
+
 /// Scroll page
 async fn interact_scroll(
     cdp: &CdpConnection,
-    down: Option<i64>,
-    up: Option<i64>,
-    left: Option<i64>,
-    right: Option<i64>,
+    x: i64,
+    y: i64,
     to: Option<&str>,
+    within: Option<&str>,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
     if let Some(sel) = to {
@@ -448,9 +1976,15 @@ async fn interact_scroll(
             target: Some(sel.to_string()),
             details: None,
         })
+    } else if let Some(container) = within {
+        cdp.scroll_within(container, x, y).await?;
+        formatter.success(&format!("Scrolled \"{}\" by ({}, {})", container, x, y));
+        Ok(InteractResult {
+            action: "scroll".to_string(),
+            target: Some(container.to_string()),
+            details: Some(format!("({}, {})", x, y)),
+        })
     } else {
-        let x = right.unwrap_or(0) - left.unwrap_or(0);
-        let y = down.unwrap_or(0) - up.unwrap_or(0);
         cdp.scroll_by(x, y).await?;
         formatter.success(&format!("Scrolled by ({}, {})", x, y));
         Ok(InteractResult {
@@ -461,75 +1995,387 @@ async fn interact_scroll(
     }
 }
 
+/// Scroll repeatedly until an element or text appears (infinite scroll)
+async fn interact_scroll_until(
+    cdp: &CdpConnection,
+    selector: Option<&str>,
+    text: Option<&str>,
+    within: Option<&str>,
+    step: i64,
+    max_scrolls: u32,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let scrolls = cdp
+        .scroll_until(selector, text, within, step, max_scrolls)
+        .await?;
+
+    let target = selector.or(text).unwrap_or_default();
+    formatter.success(&format!("Found \"{}\" after {} scroll(s)", target, scrolls));
+    Ok(InteractResult {
+        action: "scroll".to_string(),
+        target: Some(target.to_string()),
+        details: Some(format!("{} scroll(s)", scrolls)),
+    })
+}
+
 /// Capture screenshot
 async fn interact_screenshot(
-    cdp: &CdpConnection,
+    cdp: &dyn BrowserBackend,
     _config: &Config,
     full: bool,
+    stitch: bool,
     element: Option<&str>,
-    output: Option<PathBuf>,
+    options: ScreenshotOptions,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
+    let format = options.resolve_format()?;
     let data = if let Some(sel) = element {
-        // Element screenshot via JS
-        let js = format!(
-            r#"
-            (async function() {{
-                const el = document.querySelector('{}');
-                if (!el) return null;
-                el.scrollIntoView({{ block: 'center' }});
-                await new Promise(r => setTimeout(r, 100));
-                const rect = el.getBoundingClientRect();
-                return {{ x: rect.x, y: rect.y, width: rect.width, height: rect.height }};
-            }})()
-        "#,
-            sel
-        );
-
-        let rect = cdp.evaluate(&js).await?;
-        if rect.is_null() {
-            return Err(anyhow::anyhow!("No element matches selector \"{}\"", sel));
+        let clip = element_clip(cdp, sel).await?;
+        cdp.capture(&CaptureOptions {
+            format,
+            quality: options.quality,
+            full_page: false,
+            clip: Some(clip),
+        })
+        .await?
+    } else if stitch {
+        if format != ImageFormat::Png {
+            return Err(anyhow::anyhow!("--stitch writes PNG only"));
         }
-
-        // For now, take full screenshot (element clipping needs CDP clip parameter)
-        cdp.screenshot(false).await?
+        stitched_screenshot(cdp, formatter).await?
     } else {
-        cdp.screenshot(full).await?
+        cdp.capture(&CaptureOptions {
+            format,
+            quality: options.quality,
+            full_page: full,
+            clip: None,
+        })
+        .await?
     };
 
-    // Determine output path
-    let output_path = output.unwrap_or_else(|| {
-        Config::find_domguard_dir()
-            .unwrap_or_else(|| std::env::current_dir().unwrap_or_default())
-            .join("screenshots")
-            .join(format!("screenshot_{}.png", safe_timestamp()))
-    });
-
-    // Ensure directory exists
-    if let Some(parent) = output_path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-
+    let url = cdp.current_url().await.unwrap_or_default();
+    let output_path = options.output_path(&url, None, format)?;
     std::fs::write(&output_path, &data)?;
     formatter.success(&format!("Screenshot saved: {}", output_path.display()));
 
     Ok(InteractResult {
         action: "screenshot".to_string(),
-        target: None,
+        target: element.map(str::to_string),
         details: Some(output_path.display().to_string()),
     })
 }
 
+/// Format, quality and destination for the screenshot commands
+#[derive(Debug, Clone, Default)]
+pub struct ScreenshotOptions {
+    /// Defaults to the output file's extension, then PNG
+    pub format: Option<ImageFormat>,
+    /// 0-100, jpeg and webp only
+    pub quality: Option<u8>,
+    /// File, or directory for a generated name
+    pub output: Option<PathBuf>,
+}
+
+impl ScreenshotOptions {
+    fn resolve_format(&self) -> Result<ImageFormat> {
+        let format = self
+            .format
+            .or_else(|| {
+                self.output
+                    .as_deref()
+                    .and_then(Path::extension)
+                    .and_then(|ext| ext.to_str()?.parse().ok())
+            })
+            .unwrap_or_default();
+        if self.quality.is_some() && format == ImageFormat::Png {
+            return Err(anyhow::anyhow!(
+                "--quality applies to jpeg and webp screenshots"
+            ));
+        }
+        Ok(format)
+    }
+
+    /// Where the screenshot goes, creating the directory
+    ///
+    /// Without `--output` (or with a directory), files are named
+    /// `<host>_<timestamp>[_<kind>].<ext>` under `.domguard/screenshots/`.
+    fn output_path(&self, url: &str, kind: Option<&str>, format: ImageFormat) -> Result<PathBuf> {
+        let dir = match &self.output {
+            Some(path) if !(path.is_dir() || path.to_string_lossy().ends_with('/')) => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(parent)?;
+                }
+                return Ok(path.clone());
+            }
+            Some(dir) => dir.clone(),
+            None => Config::find_domguard_dir()
+                .unwrap_or_else(|| std::env::current_dir().unwrap_or_default())
+                .join("screenshots"),
+        };
+        std::fs::create_dir_all(&dir)?;
+
+        let name = screenshot_file_name(url, kind, chrono::Utc::now(), format);
+        let mut path = dir.join(&name);
+        let mut n = 2;
+        while path.exists() {
+            let (stem, ext) = name.rsplit_once('.').unwrap_or((&name, ""));
+            path = dir.join(format!("{}-{}.{}", stem, n, ext));
+            n += 1;
+        }
+        Ok(path)
+    }
+}
+
+/// `<host>_<YYYYmmdd-HHMMSS>[_<kind>].<ext>`, with "page" for non-web URLs
+fn screenshot_file_name(
+    url: &str,
+    kind: Option<&str>,
+    at: chrono::DateTime<chrono::Utc>,
+    format: ImageFormat,
+) -> String {
+    let host = if url.starts_with("http://") || url.starts_with("https://") {
+        extract_domain(url)
+    } else {
+        None
+    }
+    .filter(|host| !host.is_empty())
+    .unwrap_or_else(|| "page".to_string());
+    let host: String = host
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let kind = kind.map(|k| format!("_{}", k)).unwrap_or_default();
+    format!(
+        "{}_{}{}.{}",
+        host,
+        at.format("%Y%m%d-%H%M%S"),
+        kind,
+        format.extension()
+    )
+}
+
+/// Page rectangle of the element matching `selector`, after scrolling it into view
+async fn element_clip(backend: &dyn BrowserBackend, selector: &str) -> Result<ClipRect> {
+    let js = format!(
+        r"(async function() {{
+            const el = document.querySelector({});
+            if (!el) return null;
+            el.scrollIntoView({{ block: 'center' }});
+            await new Promise(r => setTimeout(r, 100));
+            const rect = el.getBoundingClientRect();
+            return {{
+                x: rect.x + window.scrollX,
+                y: rect.y + window.scrollY,
+                width: rect.width,
+                height: rect.height
+            }};
+        }})()",
+        serde_json::to_string(selector)?
+    );
+
+    let rect = backend.evaluate(&js).await?;
+    if rect.is_null() {
+        return Err(anyhow::anyhow!(
+            "No element matches selector \"{}\"",
+            selector
+        ));
+    }
+    let value = |key: &str| rect[key].as_f64().unwrap_or(0.0);
+    if value("width") < 1.0 || value("height") < 1.0 {
+        return Err(anyhow::anyhow!(
+            "Element \"{}\" has no visible area to capture",
+            selector
+        ));
+    }
+    Ok(ClipRect {
+        x: value("x"),
+        y: value("y"),
+        width: value("width"),
+        height: value("height"),
+    })
+}
+
+/// Most viewport tiles in a stitched screenshot
+const MAX_STITCH_TILES: usize = 50;
+
+/// Scroll position and page size after scrolling to a tile
+#[derive(Debug, Deserialize)]
+struct ScrollState {
+    y: f64,
+    height: f64,
+    viewport: f64,
+}
+
+/// Scroll to `y`, then wait for lazy-loaded images in view (up to 2s)
+fn scroll_tile_script(y: f64) -> String {
+    format!(
+        r"(async function() {{
+            window.scrollTo(0, {});
+            await new Promise(r => requestAnimationFrame(() => setTimeout(r, 150)));
+            const pending = [...document.images].filter(img => {{
+                if (img.complete) return false;
+                const rect = img.getBoundingClientRect();
+                return rect.bottom > 0 && rect.top < window.innerHeight;
+            }});
+            await Promise.race([
+                Promise.all(pending.map(img => new Promise(r => {{
+                    img.addEventListener('load', r, {{ once: true }});
+                    img.addEventListener('error', r, {{ once: true }});
+                }}))),
+                new Promise(r => setTimeout(r, 2000))
+            ]);
+            return {{
+                y: window.scrollY,
+                height: Math.max(document.documentElement.scrollHeight,
+                    document.body ? document.body.scrollHeight : 0),
+                viewport: window.innerHeight
+            }};
+        }})()",
+        y
+    )
+}
+
+/// Hide fixed and sticky elements so they appear in the first tile only
+const HIDE_FIXED_SCRIPT: &str = r"(function() {
+    for (const el of document.querySelectorAll('body *')) {
+        const position = getComputedStyle(el).position;
+        if (position === 'fixed' || position === 'sticky') {
+            el.dataset.domguardStitch = el.style.visibility;
+            el.style.visibility = 'hidden';
+        }
+    }
+})()";
+
+const RESTORE_FIXED_SCRIPT: &str = r"(function() {
+    for (const el of document.querySelectorAll('[data-domguard-stitch]')) {
+        el.style.visibility = el.dataset.domguardStitch;
+        delete el.dataset.domguardStitch;
+    }
+})()";
+
+/// Full-page screenshot composed from viewport tiles
+///
+/// `captureBeyondViewport` fails or corrupts past Chrome's texture limits on very
+/// tall pages; scrolling tile by tile avoids that and triggers lazy loading too.
+async fn stitched_screenshot(
+    backend: &dyn BrowserBackend,
+    formatter: &Formatter,
+) -> Result<Vec<u8>> {
+    let start = backend
+        .evaluate("window.scrollY")
+        .await?
+        .as_f64()
+        .unwrap_or(0.0);
+
+    let stitched = stitch_tiles(backend, formatter).await;
+
+    // Put the page back as it was, even if a tile failed
+    backend.evaluate(RESTORE_FIXED_SCRIPT).await?;
+    backend
+        .evaluate(&format!("window.scrollTo(0, {})", start))
+        .await?;
+
+    Ok(crate::png::encode(&stitched?))
+}
+
+async fn stitch_tiles(
+    backend: &dyn BrowserBackend,
+    formatter: &Formatter,
+) -> Result<crate::png::Image> {
+    let mut canvas: Option<crate::png::Image> = None;
+    let mut previous_y = None;
+    let mut target = 0.0;
+
+    for tile in 0..MAX_STITCH_TILES {
+        let state: ScrollState =
+            serde_json::from_value(backend.evaluate(&scroll_tile_script(target)).await?)?;
+        if previous_y.is_some_and(|y| state.y <= y) {
+            // The page can't scroll any further
+            break;
+        }
+
+        let image = crate::png::decode(&backend.screenshot(false).await?)?;
+        // Device pixels per CSS pixel
+        let scale = f64::from(image.height) / state.viewport.max(1.0);
+        let row = (state.y * scale).round() as u32;
+        match &mut canvas {
+            Some(canvas) => canvas.paste(&image, row),
+            None => {
+                let mut first = crate::png::Image::new(image.width, 0);
+                first.paste(&image, row);
+                canvas = Some(first);
+                backend.evaluate(HIDE_FIXED_SCRIPT).await?;
+            }
+        }
+
+        if state.y + state.viewport >= state.height {
+            break;
+        }
+        if tile + 1 == MAX_STITCH_TILES {
+            formatter.warning(&format!(
+                "Stopped after {} tiles; the rest of the page is not in the screenshot",
+                MAX_STITCH_TILES
+            ));
+        }
+        previous_y = Some(state.y);
+        target = state.y + state.viewport;
+    }
+
+    canvas.ok_or_else(|| anyhow::anyhow!("No tiles captured"))
+}
+
 /// Navigate to URL
 async fn interact_navigate(
     cdp: &CdpConnection,
     url: &str,
+    credential: Option<Credential>,
+    wait_until: WaitUntil,
+    referrer: Option<&str>,
+    timeout_ms: u64,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
-    cdp.navigate(url).await?;
+    // Stored per-domain credentials, plus any given for this navigation
+    let network_config_path = Config::find_domguard_dir()
+        .unwrap_or_else(Config::domguard_dir)
+        .join("network.toml");
+    let mut credentials = NetworkConfig::load(&network_config_path)
+        .unwrap_or_default()
+        .credentials;
+    if let Some(credential) = credential {
+        let domain = extract_domain(url)
+            .ok_or_else(|| anyhow::anyhow!("Cannot determine domain of URL: {}", url))?;
+        credentials.insert(domain, credential);
+    }
+
+    let interception = if credentials.is_empty() {
+        None
+    } else {
+        Some(
+            cdp.start_request_interception(Vec::new(), credentials)
+                .await?,
+        )
+    };
+
+    let navigated = cdp
+        .navigate_with(
+            url,
+            wait_until,
+            referrer,
+            std::time::Duration::from_millis(timeout_ms),
+        )
+        .await;
 
-    // Wait a bit for navigation
-    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    if let Some(handle) = interception {
+        handle.abort();
+        cdp.stop_request_interception().await?;
+    }
+    navigated?;
 
     let title = cdp.get_title().await.unwrap_or_default();
     formatter.success(&format!("Navigated to: {} - {}", url, title));
@@ -541,15 +2387,29 @@ async fn interact_navigate(
     })
 }
 
-/// Go back in history
-async fn interact_back(cdp: &CdpConnection, formatter: &Formatter) -> Result<InteractResult> {
-    cdp.go_back().await?;
+/// Go back (negative delta) or forward in history
+async fn interact_history(
+    cdp: &CdpConnection,
+    delta: i64,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let entry = cdp.go_history(delta).await?;
     tokio::time::sleep(std::time::Duration::from_millis(300)).await;
-    formatter.success("Navigated back");
+
+    let action = if delta < 0 { "back" } else { "forward" };
+    let steps = delta.unsigned_abs();
+    if steps == 1 {
+        formatter.success(&format!("Navigated {}: {}", action, entry.url));
+    } else {
+        formatter.success(&format!(
+            "Navigated {} {} steps: {}",
+            action, steps, entry.url
+        ));
+    }
     Ok(InteractResult {
-        action: "back".to_string(),
-        target: None,
-        details: None,
+        action: action.to_string(),
+        target: Some(entry.url),
+        details: (steps != 1).then(|| format!("steps: {}", steps)),
     })
 }
 
@@ -617,6 +2477,63 @@ async fn interact_wait(
     }
 }
 
+/// Wait for an element to be enabled, stable or carry an attribute value
+async fn interact_wait_state(
+    cdp: &CdpConnection,
+    selector: &str,
+    condition: &ElementCondition,
+    timeout_ms: u64,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    cdp.wait_for_element(selector, condition, timeout_ms)
+        .await?;
+    formatter.success(&format!("Element \"{}\" is {}", selector, condition));
+    Ok(InteractResult {
+        action: "wait".to_string(),
+        target: Some(selector.to_string()),
+        details: Some(condition.to_string()),
+    })
+}
+
+/// Wait for the page URL or title
+async fn interact_wait_page(
+    cdp: &CdpConnection,
+    condition: &PageCondition,
+    timeout_ms: u64,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let (url, title) = cdp.wait_for_page(condition, timeout_ms).await?;
+    formatter.success(&format!("Page reached {}", condition));
+    Ok(InteractResult {
+        action: "wait".to_string(),
+        target: Some(condition.to_string()),
+        details: Some(match condition {
+            PageCondition::TitleContains(_) => format!("title: {}", title),
+            _ => format!("url: {}", url),
+        }),
+    })
+}
+
+/// Block until the condition a click was expected to cause holds
+async fn wait_follow_up(
+    cdp: &CdpConnection,
+    follow_up: &FollowUp,
+    network: Option<&NetworkWatch>,
+    timeout_ms: u64,
+) -> Result<()> {
+    match (follow_up, network) {
+        (FollowUp::Selector(selector), _) => cdp.wait_for(selector, timeout_ms).await,
+        (FollowUp::UrlContains(text), _) => cdp
+            .wait_for_page(&PageCondition::UrlContains(text.clone()), timeout_ms)
+            .await
+            .map(|_| ()),
+        (FollowUp::NetworkIdle, Some(network)) => {
+            wait_for_network_idle(network, cdp.bounded(timeout_ms)).await
+        }
+        (FollowUp::NetworkIdle, None) => Err(anyhow::anyhow!("Network activity was not watched")),
+    }
+}
+
 /// Drag and drop
 async fn interact_drag(
     cdp: &CdpConnection,
@@ -624,10 +2541,13 @@ async fn interact_drag(
     to_selector: Option<&str>,
     from_coords: Option<(f64, f64)>,
     to_coords: Option<(f64, f64)>,
+    space: &CoordSpace,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
-    let (from_x, from_y) = if let Some((x, y)) = from_coords {
-        (x, y)
+    // Only the start may scroll; the drop point is resolved after it so both
+    // are measured against the same scroll position
+    let (from_x, from_y) = if let Some(point) = from_coords {
+        viewport_point(cdp, space, point, true).await?
     } else if let Some(sel) = from_selector {
         cdp.get_element_center(sel).await?
     } else {
@@ -636,55 +2556,733 @@ async fn interact_drag(
         ));
     };
 
-    let (to_x, to_y) = if let Some((x, y)) = to_coords {
-        (x, y)
-    } else if let Some(sel) = to_selector {
-        cdp.get_element_center(sel).await?
-    } else {
-        return Err(anyhow::anyhow!("Either to_selector or to_coords required"));
-    };
+    let (to_x, to_y) = if let Some(point) = to_coords {
+        viewport_point(cdp, space, point, false).await?
+    } else if let Some(sel) = to_selector {
+        cdp.get_element_center(sel).await?
+    } else {
+        return Err(anyhow::anyhow!("Either to_selector or to_coords required"));
+    };
+
+    cdp.drag(from_x, from_y, to_x, to_y).await?;
+
+    let point_str = |selector: Option<&str>, coords: Option<(f64, f64)>| {
+        selector.map_or_else(
+            || describe_point(coords.unwrap_or_default(), space),
+            ToString::to_string,
+        )
+    };
+    let from_str = point_str(from_selector, from_coords);
+    let to_str = point_str(to_selector, to_coords);
+
+    formatter.success(&format!("Dragged from {} to {}", from_str, to_str));
+    Ok(InteractResult {
+        action: "drag".to_string(),
+        target: Some(from_str),
+        details: Some(to_str),
+    })
+}
+
+/// Select dropdown option
+async fn interact_select(
+    cdp: &CdpConnection,
+    selector: &str,
+    value: &str,
+    by_label: bool,
+    by_index: bool,
+    framework: Option<Framework>,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let used = cdp
+        .select_option(selector, value, by_label, by_index, framework)
+        .await?;
+
+    let method = if by_label {
+        "label"
+    } else if by_index {
+        "index"
+    } else {
+        "value"
+    };
+    formatter.success(&format!(
+        "Selected {} \"{}\" in \"{}\"{}",
+        method,
+        value,
+        selector,
+        events_note(used.as_deref())
+    ));
+    Ok(InteractResult {
+        action: "select".to_string(),
+        target: Some(selector.to_string()),
+        details: Some(value.to_string()),
+    })
+}
+
+/// " (vue events)" when input events were shaped for a framework
+fn events_note(framework: Option<&str>) -> String {
+    framework.map_or_else(String::new, |name| format!(" ({} events)", name))
+}
+
+/// Most pages `table --find-row --next-page` looks through
+const MAX_TABLE_PAGES: u32 = 20;
+
+/// Sort, page through and click into a table
+async fn interact_table(
+    cdp: &CdpConnection,
+    selector: &str,
+    action: &TableAction,
+    timeout_ms: u64,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let mut snapshot = cdp.table_snapshot(selector).await?;
+
+    if let Some(column) = &action.sort {
+        let (x, y) = cdp
+            .table_click_point(selector, &TableTarget::Header(column.clone()))
+            .await?;
+        cdp.dispatch_click_at(x, y, "left", 1).await?;
+        // A table that is already in this order doesn't change
+        snapshot = match cdp
+            .wait_for_table_change(selector, &snapshot.signature, timeout_ms)
+            .await?
+        {
+            Some(changed) => changed,
+            None => cdp.table_snapshot(selector).await?,
+        };
+        let order = snapshot
+            .sort
+            .as_ref()
+            .map_or_else(String::new, |sort| format!(" ({})", sort));
+        formatter.success(&format!("Sorted by \"{}\"{}", column, order));
+    }
+
+    let mut page = 1;
+    let row = match &action.find_row {
+        Some(text) => loop {
+            if let Some(row) = cdp.find_table_row(selector, text).await? {
+                break Some(row);
+            }
+            if !action.next_page {
+                return Err(anyhow::anyhow!(
+                    "No row in \"{}\" contains \"{}\" (add --next-page to search later pages)",
+                    selector,
+                    text
+                ));
+            }
+            if page >= MAX_TABLE_PAGES {
+                return Err(anyhow::anyhow!(
+                    "No row in \"{}\" contains \"{}\" in the first {} pages",
+                    selector,
+                    text,
+                    page
+                ));
+            }
+            snapshot = next_table_page(cdp, selector, &snapshot, timeout_ms)
+                .await
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "No row in \"{}\" contains \"{}\" ({} page(s) searched): {}",
+                        selector,
+                        text,
+                        page,
+                        e
+                    )
+                })?;
+            page += 1;
+        },
+        None => {
+            if action.next_page {
+                snapshot = next_table_page(cdp, selector, &snapshot, timeout_ms).await?;
+                page += 1;
+                formatter.success(&format!(
+                    "Next page of \"{}\" ({} rows)",
+                    selector, snapshot.rows
+                ));
+            }
+            None
+        }
+    };
+
+    if let Some(row) = &row {
+        formatter.success(&format!(
+            "Row {} of \"{}\"{}",
+            row.index,
+            selector,
+            if page > 1 {
+                format!(" on page {}", page)
+            } else {
+                String::new()
+            }
+        ));
+        for (i, cell) in row.cells.iter().enumerate() {
+            let header = row
+                .headers
+                .get(i)
+                .filter(|header| !header.is_empty())
+                .cloned()
+                .unwrap_or_else(|| format!("Column {}", i + 1));
+            formatter.kv(&header, cell);
+        }
+        if let Some(column) = &action.click_cell {
+            let target = TableTarget::Cell {
+                row: row.index,
+                column: column.clone(),
+            };
+            let (x, y) = cdp.table_click_point(selector, &target).await?;
+            cdp.dispatch_click_at(x, y, "left", 1).await?;
+            formatter.success(&format!("Clicked column {} of row {}", column, row.index));
+        }
+    } else if action.sort.is_none() && !action.next_page {
+        formatter.success(&format!(
+            "\"{}\": {} rows, columns: {}",
+            selector,
+            snapshot.rows,
+            snapshot.headers.join(", ")
+        ));
+    }
+
+    let details = serde_json::json!({ "page": page, "table": snapshot, "row": row });
+    Ok(InteractResult {
+        action: "table".to_string(),
+        target: Some(selector.to_string()),
+        details: Some(details.to_string()),
+    })
+}
+
+/// Click the table's next-page control and wait for the new rows
+async fn next_table_page(
+    cdp: &CdpConnection,
+    selector: &str,
+    current: &TableSnapshot,
+    timeout_ms: u64,
+) -> Result<TableSnapshot> {
+    let (x, y) = cdp
+        .table_click_point(selector, &TableTarget::NextPage)
+        .await?;
+    cdp.dispatch_click_at(x, y, "left", 1).await?;
+    cdp.wait_for_table_change(selector, &current.signature, timeout_ms)
+        .await?
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "The table did not change within {}ms of clicking next page",
+                timeout_ms
+            )
+        })
+}
+
+/// Most clicks `interact date` makes through calendar months (five years)
+const MAX_CALENDAR_STEPS: u32 = 60;
+
+/// Set a date input or date picker, trying in order: the native value (for
+/// `type=date`), typing it in the placeholder's or page locale's format, and
+/// clicking through the calendar widget; each is checked against the value
+async fn interact_date(
+    cdp: &CdpConnection,
+    selector: &str,
+    date: chrono::NaiveDate,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let iso = date.format("%Y-%m-%d").to_string();
+    let info = cdp.date_input_info(selector).await?;
+    let mut failures = Vec::new();
+
+    let strategy = 'strategy: {
+        if info.input_type == "date" {
+            cdp.type_into_as(selector, &iso, None).await?;
+            let value = cdp.input_value(selector).await?;
+            if value == iso {
+                break 'strategy "native value".to_string();
+            }
+            failures.push(format!("native value: input holds \"{}\"", value));
+        } else {
+            failures.push(format!(
+                "native value: not a date input (type={})",
+                info.input_type
+            ));
+        }
+
+        if info.read_only {
+            failures.push("typing: input is read-only".to_string());
+        } else {
+            let (text, source) = match format_date(date, &info.placeholder) {
+                Some(text) => (text, "placeholder"),
+                None => (
+                    format_date(date, &info.locale_pattern).unwrap_or_else(|| iso.clone()),
+                    "locale",
+                ),
+            };
+            cdp.select_input_text(selector).await?;
+            cdp.type_keystrokes(&text, 0).await?;
+            // Many pickers only parse what was typed on blur
+            cdp.press_key("Tab").await?;
+            let value = cdp.input_value(selector).await?;
+            if date_value_matches(&value, date) {
+                break 'strategy format!("typed \"{}\" ({} format)", text, source);
+            }
+            failures.push(format!("typing \"{}\": input holds \"{}\"", text, value));
+        }
+
+        // Pickers open their calendar on click
+        let (x, y) = cdp.element_click_point(selector).await?;
+        cdp.dispatch_click_at(x, y, "left", 1).await?;
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        let mut months = 0;
+        loop {
+            match cdp.calendar_step(date, &info.locale).await? {
+                CalendarStep::Day { x, y } => {
+                    cdp.dispatch_click_at(x, y, "left", 1).await?;
+                    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                    let value = cdp.input_value(selector).await?;
+                    if date_value_matches(&value, date) {
+                        break 'strategy format!("calendar ({} month steps)", months);
+                    }
+                    failures.push(format!(
+                        "calendar: clicked the day, input holds \"{}\"",
+                        value
+                    ));
+                    break;
+                }
+                CalendarStep::Nav { .. } if months >= MAX_CALENDAR_STEPS => {
+                    failures.push(format!("calendar: not reached in {} months", months));
+                    break;
+                }
+                CalendarStep::Nav { x, y, .. } => {
+                    cdp.dispatch_click_at(x, y, "left", 1).await?;
+                    months += 1;
+                    tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+                }
+                CalendarStep::Stuck { reason } => {
+                    failures.push(format!("calendar: {}", reason));
+                    break;
+                }
+            }
+        }
+
+        return Err(anyhow::anyhow!(
+            "Could not set \"{}\" to {} (tried {})",
+            selector,
+            iso,
+            failures.join("; ")
+        ));
+    };
+
+    formatter.success(&format!("Set \"{}\" to {} by {}", selector, iso, strategy));
+    Ok(InteractResult {
+        action: "date".to_string(),
+        target: Some(selector.to_string()),
+        details: Some(format!("{} by {}", iso, strategy)),
+    })
+}
+
+/// `date` in a pattern like "DD/MM/YYYY" or "m/d/yy" (case-insensitive);
+/// `None` unless the pattern has exactly a year, month and day and no other letters
+fn format_date(date: chrono::NaiveDate, pattern: &str) -> Option<String> {
+    use chrono::Datelike;
+
+    let chars: Vec<char> = pattern.trim().chars().collect();
+    let mut out = String::new();
+    let mut seen = (false, false, false);
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].to_ascii_lowercase();
+        let run = chars[i..]
+            .iter()
+            .take_while(|other| other.to_ascii_lowercase() == c)
+            .count();
+        match (c, run) {
+            ('y', 4) if !seen.0 => {
+                out.push_str(&format!("{:04}", date.year()));
+                seen.0 = true;
+            }
+            ('y', 2) if !seen.0 => {
+                out.push_str(&format!("{:02}", date.year().rem_euclid(100)));
+                seen.0 = true;
+            }
+            ('m', 1 | 2) if !seen.1 => {
+                out.push_str(&format!("{:0width$}", date.month(), width = run));
+                seen.1 = true;
+            }
+            ('d', 1 | 2) if !seen.2 => {
+                out.push_str(&format!("{:0width$}", date.day(), width = run));
+                seen.2 = true;
+            }
+            _ if c.is_alphabetic() => return None,
+            _ => out.extend(&chars[i..i + run]),
+        }
+        i += run;
+    }
+    (seen == (true, true, true)).then_some(out)
+}
+
+/// Whether an input's value plausibly holds `date`: its year (4 or 2 digits)
+/// and day appear among the numbers, whatever the format
+fn date_value_matches(value: &str, date: chrono::NaiveDate) -> bool {
+    use chrono::Datelike;
+
+    let numbers: Vec<&str> = value
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|run| !run.is_empty())
+        .collect();
+    let year = date.year();
+    let has_year = numbers.iter().any(|run| match run.len() {
+        4 => run.parse() == Ok(year),
+        2 => run.parse() == Ok(year.rem_euclid(100)),
+        _ => false,
+    });
+    let has_day = numbers.iter().any(|run| run.parse() == Ok(date.day()));
+    has_year && has_day
+}
+
+/// Move events are spaced about one frame apart
+const GESTURE_FRAME_MS: u64 = 16;
+
+/// Trusted press-move-release along a path, with a move event per frame
+async fn interact_gesture(
+    cdp: &CdpConnection,
+    path: &GesturePath,
+    duration_ms: u64,
+    touch: bool,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let corners = path.0.len();
+    let steps = usize::try_from(duration_ms / GESTURE_FRAME_MS)
+        .unwrap_or(usize::MAX)
+        .max(corners - 1);
+    let points = interpolate_path(&path.0, steps);
+    let pause = std::time::Duration::from_millis(duration_ms / steps as u64);
+    cdp.dispatch_gesture(&points, pause, touch).await?;
+
+    let input = if touch { "touch" } else { "mouse" };
+    formatter.success(&format!(
+        "Gesture through {} points over {}ms ({}, {} moves)",
+        corners,
+        duration_ms,
+        input,
+        points.len() - 1
+    ));
+    Ok(InteractResult {
+        action: "gesture".to_string(),
+        target: Some(format!("{:?} -> {:?}", path.0[0], path.0[corners - 1])),
+        details: Some(format!("{} over {}ms", input, duration_ms)),
+    })
+}
+
+/// `steps + 1` points evenly spaced along the path, from its first to its last point
+fn interpolate_path(path: &[(f64, f64)], steps: usize) -> Vec<(f64, f64)> {
+    let lengths: Vec<f64> = path
+        .windows(2)
+        .map(|pair| (pair[1].0 - pair[0].0).hypot(pair[1].1 - pair[0].1))
+        .collect();
+    let total: f64 = lengths.iter().sum();
+    if path.len() < 2 || steps == 0 || total == 0.0 {
+        return path.to_vec();
+    }
+
+    let mut points = Vec::with_capacity(steps + 1);
+    let mut segment = 0;
+    let mut start = 0.0;
+    for step in 0..=steps {
+        let distance = total * step as f64 / steps as f64;
+        while segment + 1 < lengths.len() && distance > start + lengths[segment] {
+            start += lengths[segment];
+            segment += 1;
+        }
+        let (from, to) = (path[segment], path[segment + 1]);
+        let t = if lengths[segment] == 0.0 {
+            0.0
+        } else {
+            ((distance - start) / lengths[segment]).min(1.0)
+        };
+        points.push((from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t));
+    }
+    points
+}
+
+/// Point to touch for a selector or `--coords`, and how to report it
+async fn touch_target(
+    cdp: &CdpConnection,
+    selector: Option<&str>,
+    coords: Option<(f64, f64)>,
+) -> Result<((f64, f64), String)> {
+    if let Some((x, y)) = coords {
+        Ok(((x, y), format!("({}, {})", x, y)))
+    } else if let Some(sel) = selector {
+        Ok((cdp.element_click_point(sel).await?, format!("\"{}\"", sel)))
+    } else {
+        Err(anyhow::anyhow!("Either selector or --coords required"))
+    }
+}
+
+/// Touch down and lift at one point
+async fn interact_tap(
+    cdp: &CdpConnection,
+    selector: Option<&str>,
+    coords: Option<(f64, f64)>,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let (point, target) = touch_target(cdp, selector, coords).await?;
+    cdp.dispatch_touch(&[vec![point]], std::time::Duration::ZERO)
+        .await?;
+    formatter.success(&format!("Tapped {}", target));
+    Ok(InteractResult {
+        action: "tap".to_string(),
+        target: Some(target),
+        details: None,
+    })
+}
+
+/// One finger moving in a straight line, a move event per frame
+async fn interact_swipe(
+    cdp: &CdpConnection,
+    from: (f64, f64),
+    to: (f64, f64),
+    duration_ms: u64,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let steps = frame_count(duration_ms);
+    let frames: Vec<Vec<(f64, f64)>> = interpolate_path(&[from, to], steps)
+        .into_iter()
+        .map(|point| vec![point])
+        .collect();
+    let pause = std::time::Duration::from_millis(duration_ms / steps as u64);
+    cdp.dispatch_touch(&frames, pause).await?;
+
+    let target = format!("{:?} -> {:?}", from, to);
+    formatter.success(&format!("Swiped {} over {}ms", target, duration_ms));
+    Ok(InteractResult {
+        action: "swipe".to_string(),
+        target: Some(target),
+        details: Some(format!("{}ms", duration_ms)),
+    })
+}
+
+/// Two fingers on either side of `center`, ending `scale` times as far apart
+async fn interact_pinch(
+    cdp: &CdpConnection,
+    center: (f64, f64),
+    target: &str,
+    scale: f64,
+    distance: f64,
+    duration_ms: u64,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    if scale <= 0.0 || distance <= 0.0 {
+        return Err(anyhow::anyhow!(
+            "--scale and --distance must be greater than 0"
+        ));
+    }
+    let steps = frame_count(duration_ms);
+    let frames = pinch_frames(center, distance, scale, steps);
+    let pause = std::time::Duration::from_millis(duration_ms / steps as u64);
+    cdp.dispatch_touch(&frames, pause).await?;
+
+    let kind = if scale > 1.0 { "out" } else { "in" };
+    formatter.success(&format!(
+        "Pinched {} on {} (x{}, {}px -> {}px)",
+        kind,
+        target,
+        scale,
+        distance,
+        distance * scale
+    ));
+    Ok(InteractResult {
+        action: "pinch".to_string(),
+        target: Some(target.to_string()),
+        details: Some(format!("scale {} over {}ms", scale, duration_ms)),
+    })
+}
+
+/// Move steps for a touch gesture lasting `duration_ms` (at least one)
+fn frame_count(duration_ms: u64) -> usize {
+    usize::try_from(duration_ms / GESTURE_FRAME_MS)
+        .unwrap_or(usize::MAX)
+        .max(1)
+}
+
+/// Finger positions for a horizontal pinch, `steps + 1` frames of two points
+fn pinch_frames(
+    center: (f64, f64),
+    distance: f64,
+    scale: f64,
+    steps: usize,
+) -> Vec<Vec<(f64, f64)>> {
+    let (start, end) = (distance / 2.0, distance * scale / 2.0);
+    (0..=steps)
+        .map(|step| {
+            let half = start + (end - start) * step as f64 / steps as f64;
+            vec![(center.0 - half, center.1), (center.0 + half, center.1)]
+        })
+        .collect()
+}
+
+/// Delay between typeahead keystrokes, so debounced searches see typing
+const COMBOBOX_KEY_DELAY_MS: u64 = 30;
+
+/// A settled list without a match is given up on after this long
+const COMBOBOX_SETTLE_MS: u64 = 1000;
+
+/// Type each choice into a combobox, wait for its (possibly async) option
+/// list and click the matching option
+async fn interact_combobox(
+    cdp: &CdpConnection,
+    selector: &str,
+    choose: &[String],
+    create_if_missing: bool,
+    timeout_ms: u64,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let mut chosen = Vec::new();
+    for choice in choose {
+        cdp.open_combobox(selector).await?;
+        cdp.type_keystrokes(choice, COMBOBOX_KEY_DELAY_MS).await?;
+
+        let start = Instant::now();
+        let mut unmatched_since: Option<Instant> = None;
+        let picked = loop {
+            let list = cdp.combobox_options(selector).await?;
+            let allow_create = create_if_missing && !list.loading;
+            if let Some(option) = pick_option(&list.options, choice, allow_create) {
+                break Some(option.clone());
+            }
+            if list.loading || list.options.is_empty() {
+                unmatched_since = None;
+            } else if unmatched_since
+                .get_or_insert_with(Instant::now)
+                .elapsed()
+                .as_millis()
+                >= u128::from(COMBOBOX_SETTLE_MS)
+            {
+                break None;
+            }
+            if start.elapsed().as_millis() >= u128::from(timeout_ms) {
+                break None;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+        };
 
-    cdp.drag(from_x, from_y, to_x, to_y).await?;
+        match picked {
+            Some(option) => {
+                cdp.dispatch_click_at(option.x, option.y, "left", 1).await?;
+                let created = is_create_option(&option.text, choice);
+                formatter.success(&format!(
+                    "{} \"{}\" in \"{}\"",
+                    if created { "Created" } else { "Chose" },
+                    option.text,
+                    selector
+                ));
+                chosen.push(option.text);
+            }
+            None if create_if_missing => {
+                // Creatable inputs without a create entry take Enter
+                cdp.press_key("Enter").await?;
+                formatter.success(&format!("Entered new \"{}\" in \"{}\"", choice, selector));
+                chosen.push(choice.clone());
+            }
+            None => {
+                let list = cdp.combobox_options(selector).await.unwrap_or_default();
+                let available: Vec<&str> = list
+                    .options
+                    .iter()
+                    .take(10)
+                    .map(|option| option.text.as_str())
+                    .collect();
+                return Err(anyhow::anyhow!(
+                    "No option matching \"{}\" in \"{}\"{}",
+                    choice,
+                    selector,
+                    if available.is_empty() {
+                        " (no options appeared)".to_string()
+                    } else {
+                        format!(" (options: {})", available.join(", "))
+                    }
+                ));
+            }
+        }
 
-    let from_str =
-        from_selector.map_or_else(|| format!("({}, {})", from_x, from_y), |s| s.to_string());
-    let to_str = to_selector.map_or_else(|| format!("({}, {})", to_x, to_y), |s| s.to_string());
+        tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+        if !cdp.combobox_shows(selector, choice).await.unwrap_or(true) {
+            formatter.warning(&format!(
+                "\"{}\" was clicked but the combobox doesn't show it",
+                choice
+            ));
+        }
+    }
 
-    formatter.success(&format!("Dragged from {} to {}", from_str, to_str));
     Ok(InteractResult {
-        action: "drag".to_string(),
-        target: Some(from_str),
-        details: Some(to_str),
+        action: "combobox".to_string(),
+        target: Some(selector.to_string()),
+        details: Some(chosen.join(", ")),
     })
 }
 
-/// Select dropdown option
-async fn interact_select(
+/// Option to click for `choice`: an exact match (case and spacing ignored),
+/// else one starting with it, else the only one containing it; then the
+/// list's create entry (`Create "x"`, `Add "x"`) if allowed
+fn pick_option<'a>(
+    options: &'a [ComboOption],
+    choice: &str,
+    allow_create: bool,
+) -> Option<&'a ComboOption> {
+    let normalize = |text: &str| {
+        text.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+    let want = normalize(choice);
+    let candidates: Vec<(&ComboOption, String)> = options
+        .iter()
+        .filter(|option| !option.disabled && !is_create_option(&option.text, choice))
+        .map(|option| (option, normalize(&option.text)))
+        .collect();
+
+    if let Some((option, _)) = candidates.iter().find(|(_, text)| *text == want) {
+        return Some(*option);
+    }
+    if let Some((option, _)) = candidates.iter().find(|(_, text)| text.starts_with(&want)) {
+        return Some(*option);
+    }
+    let containing: Vec<_> = candidates
+        .iter()
+        .filter(|(_, text)| text.contains(&want))
+        .collect();
+    if let [(option, _)] = containing.as_slice() {
+        return Some(*option);
+    }
+    if allow_create {
+        return options
+            .iter()
+            .find(|option| !option.disabled && is_create_option(&option.text, choice));
+    }
+    None
+}
+
+/// Whether an option is a creatable list's `Create "x"` / `Add "x"` entry
+fn is_create_option(text: &str, choice: &str) -> bool {
+    let text = text.to_lowercase();
+    (text.starts_with("create") || text.starts_with("add "))
+        && text.contains(&choice.to_lowercase())
+}
+
+/// Set checkbox/radio state idempotently
+async fn interact_check(
     cdp: &CdpConnection,
     selector: &str,
-    value: &str,
-    by_label: bool,
-    by_index: bool,
+    checked: bool,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
-    cdp.select_option(selector, value, by_label, by_index)
-        .await?;
+    let (previous, current) = cdp.set_checked(selector, checked).await?;
 
-    let method = if by_label {
-        "label"
-    } else if by_index {
-        "index"
+    let state = |c: bool| if c { "checked" } else { "unchecked" };
+    if previous == current {
+        formatter.success(&format!("\"{}\" already {}", selector, state(current)));
     } else {
-        "value"
-    };
-    formatter.success(&format!(
-        "Selected {} \"{}\" in \"{}\"",
-        method, value, selector
-    ));
+        formatter.success(&format!("\"{}\" is now {}", selector, state(current)));
+    }
     Ok(InteractResult {
-        action: "select".to_string(),
+        action: "check".to_string(),
         target: Some(selector.to_string()),
-        details: Some(value.to_string()),
+        details: Some(format!("{} -> {}", state(previous), state(current))),
     })
 }
 
@@ -693,6 +3291,7 @@ async fn interact_upload(
     cdp: &CdpConnection,
     selector: &str,
     files: &[PathBuf],
+    via_chooser: bool,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
     // Validate files exist
@@ -702,7 +3301,11 @@ async fn interact_upload(
         }
     }
 
-    cdp.upload_files(selector, files).await?;
+    if via_chooser {
+        cdp.upload_via_chooser(selector, files, 5000).await?;
+    } else {
+        cdp.upload_files(selector, files).await?;
+    }
 
     let file_names: Vec<_> = files
         .iter()
@@ -710,10 +3313,12 @@ async fn interact_upload(
         .map(|f| f.to_string_lossy().to_string())
         .collect();
 
+    let via = if via_chooser { " via file chooser" } else { "" };
     formatter.success(&format!(
-        "Uploaded {} file(s) to \"{}\"",
+        "Uploaded {} file(s) to \"{}\"{}",
         files.len(),
-        selector
+        selector,
+        via
     ));
     Ok(InteractResult {
         action: "upload".to_string(),
@@ -722,21 +3327,25 @@ async fn interact_upload(
     })
 }
 
-/// Handle JavaScript dialog
+/// Wait for a JavaScript dialog and answer it
 async fn interact_dialog(
     cdp: &CdpConnection,
     accept: bool,
     text: Option<&str>,
+    timeout_ms: u64,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
-    cdp.handle_dialog(accept, text).await?;
+    let dialog = cdp.handle_dialog(accept, text, timeout_ms).await?;
 
     let action_str = if accept { "accepted" } else { "dismissed" };
-    formatter.success(&format!("Dialog {}", action_str));
+    formatter.success(&format!(
+        "Dialog {} ({}): \"{}\"",
+        action_str, dialog.dialog_type, dialog.message
+    ));
     Ok(InteractResult {
         action: "dialog".to_string(),
-        target: None,
-        details: Some(action_str.to_string()),
+        target: Some(dialog.dialog_type),
+        details: Some(format!("{}: {}", action_str, dialog.message)),
     })
 }
 
@@ -762,10 +3371,23 @@ async fn interact_pdf(
     cdp: &CdpConnection,
     _config: &Config,
     output: Option<PathBuf>,
-    landscape: bool,
+    mut options: PdfOptions,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
-    let data = cdp.print_to_pdf(landscape).await?;
+    options.header_template = options.header_template.map(read_template).transpose()?;
+    options.footer_template = options.footer_template.map(read_template).transpose()?;
+    options.validate()?;
+
+    let data = match cdp.print_to_pdf(&options).await {
+        Ok(data) => data,
+        Err(e) => {
+            formatter.warning(&format!(
+                "{:#}; printing in a temporary headless Chrome instead",
+                e
+            ));
+            cdp.print_to_pdf_headless(&options).await?
+        }
+    };
 
     // Determine output path
     let output_path = output.unwrap_or_else(|| {
@@ -790,6 +3412,15 @@ async fn interact_pdf(
     })
 }
 
+/// A header/footer template given inline or as `@path`
+fn read_template(template: String) -> Result<String> {
+    match template.strip_prefix('@') {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read template {}: {}", path, e)),
+        None => Ok(template),
+    }
+}
+
 // ============================================================================
 // Anthropic Computer Use feature implementations
 // ============================================================================
@@ -798,14 +3429,17 @@ async fn interact_pdf(
 async fn interact_mouse_move(
     cdp: &CdpConnection,
     coords: (f64, f64),
+    space: &CoordSpace,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
-    cdp.mouse_move(coords.0, coords.1).await?;
-    formatter.success(&format!("Moved cursor to ({}, {})", coords.0, coords.1));
+    let (x, y) = viewport_point(cdp, space, coords, true).await?;
+    cdp.mouse_move(x, y).await?;
+    let target = describe_point(coords, space);
+    formatter.success(&format!("Moved cursor to {}", target));
     Ok(InteractResult {
         action: "mouse_move".to_string(),
-        target: Some(format!("({}, {})", coords.0, coords.1)),
-        details: None,
+        target: Some(target),
+        details: (*space != CoordSpace::Viewport).then(|| format!("viewport: ({}, {})", x, y)),
     })
 }
 
@@ -867,6 +3501,233 @@ async fn interact_triple_click(
     }
 }
 
+/// How long `auto_dismiss_consent` waits for a banner after a navigation
+const AUTO_CONSENT_WAIT_MS: u64 = 1500;
+
+/// Consent managers often render a moment after load
+const CONSENT_POLL_MS: u64 = 250;
+
+/// Accept the cookie-consent banner, waiting up to `timeout_ms` for one
+async fn interact_dismiss_consent(
+    cdp: &CdpConnection,
+    config: &Config,
+    timeout_ms: u64,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let dismissed = dismiss_consent(cdp, config, timeout_ms).await?;
+    match &dismissed {
+        Some(dismissed) => formatter.success(&format!(
+            "Accepted cookie consent ({}: {})",
+            dismissed.rule, dismissed.target
+        )),
+        None => formatter.warning(&format!("No consent banner found within {}ms", timeout_ms)),
+    }
+    Ok(InteractResult {
+        action: "dismiss_consent".to_string(),
+        target: dismissed.as_ref().map(|dismissed| dismissed.target.clone()),
+        details: dismissed.map(|dismissed| dismissed.rule),
+    })
+}
+
+/// Click the first accept button a consent rule finds, polling until `timeout_ms`
+async fn dismiss_consent(
+    cdp: &CdpConnection,
+    config: &Config,
+    timeout_ms: u64,
+) -> Result<Option<ConsentDismissed>> {
+    let script = dismiss_script(&config.consent.all_rules());
+    let deadline = Instant::now() + std::time::Duration::from_millis(timeout_ms);
+    loop {
+        let found = cdp.evaluate(&script).await?;
+        if !found.is_null() {
+            return Ok(Some(serde_json::from_value(found)?));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(CONSENT_POLL_MS)).await;
+    }
+}
+
+/// Time for focus handlers and scrolling after each Tab
+const TAB_SETTLE_MS: u64 = 100;
+
+/// Press Tab until focus cycles back, leaves the page or stops moving,
+/// recording each stop for a focus-order review
+async fn interact_tab_walk(
+    cdp: &CdpConnection,
+    max: usize,
+    screenshot_each: bool,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let shots_dir = if screenshot_each {
+        let dir = Config::find_domguard_dir()
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_default())
+            .join("screenshots");
+        std::fs::create_dir_all(&dir)?;
+        Some(dir)
+    } else {
+        None
+    };
+    let id = safe_timestamp();
+
+    let mut stops: Vec<FocusStop> = Vec::new();
+    let mut end = None;
+    while stops.len() < max {
+        cdp.press_key("Tab").await?;
+        tokio::time::sleep(std::time::Duration::from_millis(TAB_SETTLE_MS)).await;
+        let next = cdp.focused_element().await?;
+        end = walk_end(&stops, next.as_ref());
+        let (None, Some(mut stop)) = (end, next) else {
+            break;
+        };
+        if let Some(dir) = &shots_dir {
+            let path = dir.join(format!("tab_walk_{}_{:02}.png", id, stops.len() + 1));
+            std::fs::write(&path, cdp.screenshot(false).await?)?;
+            stop.screenshot = Some(path.display().to_string());
+        }
+        stops.push(stop);
+    }
+    let end = end.unwrap_or("limit reached");
+
+    let missing = stops.iter().filter(|stop| stop.indicator.is_none()).count();
+    if !formatter.is_json() {
+        formatter.header("Focus order");
+        for (n, stop) in stops.iter().enumerate() {
+            let indicator = stop.indicator.as_deref().unwrap_or("NO FOCUS INDICATOR");
+            formatter.item(&format!(
+                "{}. {} \"{}\" {} ({})",
+                n + 1,
+                stop.role.as_deref().unwrap_or(&stop.tag),
+                stop.name.as_deref().unwrap_or(""),
+                stop.selector,
+                indicator
+            ));
+        }
+    }
+    formatter.success(&format!("{} focus stops ({})", stops.len(), end));
+    if missing > 0 {
+        formatter.warning(&format!(
+            "{} stop(s) show no outline or box-shadow when focused",
+            missing
+        ));
+    }
+    if end == "focus stuck" {
+        formatter.warning("Tab did not move focus (possible keyboard trap)");
+    }
+
+    Ok(InteractResult {
+        action: "tab_walk".to_string(),
+        target: None,
+        details: Some(serde_json::json!({ "stops": stops, "end": end }).to_string()),
+    })
+}
+
+/// Why a Tab walk ends at `next`, or `None` to keep going
+fn walk_end(stops: &[FocusStop], next: Option<&FocusStop>) -> Option<&'static str> {
+    let Some(next) = next else {
+        return Some("focus left the page");
+    };
+    if stops
+        .first()
+        .is_some_and(|first| first.selector == next.selector)
+    {
+        Some("cycled back to the first stop")
+    } else if stops
+        .last()
+        .is_some_and(|last| last.selector == next.selector)
+    {
+        Some("focus stuck")
+    } else {
+        None
+    }
+}
+
+/// Right-click or double-click with real mouse events, optionally capturing resulting UI
+async fn interact_native_click(
+    cdp: &CdpConnection,
+    selector: Option<&str>,
+    coords: Option<(f64, f64)>,
+    button: &str,
+    click_count: i64,
+    capture: bool,
+    formatter: &Formatter,
+) -> Result<InteractResult> {
+    let (action, label) = if click_count > 1 {
+        ("double_click", "Double-clicked")
+    } else {
+        ("right_click", "Right-clicked")
+    };
+
+    let ((x, y), target) = if let Some((x, y)) = coords {
+        ((x, y), format!("({}, {})", x, y))
+    } else if let Some(sel) = selector {
+        (cdp.element_click_point(sel).await?, sel.to_string())
+    } else {
+        return Err(anyhow::anyhow!("Either selector or --coords required"));
+    };
+
+    if capture {
+        cdp.watch_ui_changes().await?;
+    }
+
+    cdp.dispatch_click_at(x, y, button, click_count).await?;
+
+    let details = if capture {
+        // Give menus and editors a moment to render
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        let changes = cdp.take_ui_changes().await?;
+        if !formatter.is_json() {
+            print_ui_changes(&changes);
+        }
+        Some(changes.to_string())
+    } else {
+        None
+    };
+
+    if coords.is_some() {
+        formatter.success(&format!("{} at {}", label, target));
+    } else {
+        formatter.success(&format!("{} \"{}\"", label, target));
+    }
+    Ok(InteractResult {
+        action: action.to_string(),
+        target: Some(target),
+        details,
+    })
+}
+
+/// Print UI changes captured after a click
+fn print_ui_changes(changes: &serde_json::Value) {
+    let elements = changes
+        .get("elements")
+        .and_then(|e| e.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    if elements.is_empty() {
+        println!("  No new UI appeared");
+    }
+    for el in &elements {
+        let tag = el.get("tag").and_then(|v| v.as_str()).unwrap_or("?");
+        let id = el
+            .get("id")
+            .and_then(|v| v.as_str())
+            .map(|id| format!("#{}", id))
+            .unwrap_or_default();
+        let role = el
+            .get("role")
+            .and_then(|v| v.as_str())
+            .map(|r| format!(" [role={}]", r))
+            .unwrap_or_default();
+        let text = el.get("text").and_then(|v| v.as_str()).unwrap_or("");
+        println!("  + {}{}{} {}", tag, id, role, text);
+    }
+    if let Some(selection) = changes.get("selection").and_then(|v| v.as_str()) {
+        println!("  Selected: \"{}\"", selection);
+    }
+}
+
 /// Press mouse button down (without releasing)
 async fn interact_mouse_down(
     cdp: &CdpConnection,
@@ -902,25 +3763,35 @@ async fn interact_screenshot_region(
     cdp: &CdpConnection,
     _config: &Config,
     region: (i32, i32, i32, i32),
-    output: Option<PathBuf>,
+    space: &CoordSpace,
+    options: ScreenshotOptions,
     formatter: &Formatter,
 ) -> Result<InteractResult> {
     let (x, y, width, height) = region;
-    let data = cdp.screenshot_region(x, y, width, height).await?;
-
-    // Determine output path
-    let output_path = output.unwrap_or_else(|| {
-        Config::find_domguard_dir()
-            .unwrap_or_else(|| std::env::current_dir().unwrap_or_default())
-            .join("screenshots")
-            .join(format!("region_{}.png", safe_timestamp()))
-    });
-
-    // Ensure directory exists
-    if let Some(parent) = output_path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
+    // Capture clips are in page coordinates
+    let (clip_x, clip_y) = if *space == CoordSpace::Page {
+        (f64::from(x), f64::from(y))
+    } else {
+        let origin = cdp.coordinate_origin(space.selector()).await?;
+        space.to_page((f64::from(x), f64::from(y)), &origin)
+    };
+    let format = options.resolve_format()?;
+    let data = cdp
+        .capture(&CaptureOptions {
+            format,
+            quality: options.quality,
+            full_page: false,
+            clip: Some(ClipRect {
+                x: clip_x,
+                y: clip_y,
+                width: f64::from(width),
+                height: f64::from(height),
+            }),
+        })
+        .await?;
 
+    let url = cdp.current_url().await.unwrap_or_default();
+    let output_path = options.output_path(&url, Some("region"), format)?;
     std::fs::write(&output_path, &data)?;
     formatter.success(&format!(
         "Screenshot region saved: {}",
@@ -929,7 +3800,10 @@ async fn interact_screenshot_region(
 
     Ok(InteractResult {
         action: "screenshot_region".to_string(),
-        target: Some(format!("({}, {}, {}x{})", x, y, width, height)),
+        target: Some(match space {
+            CoordSpace::Page => format!("({}, {}, {}x{})", x, y, width, height),
+            space => format!("({}, {}, {}x{}) in {}", x, y, width, height, space),
+        }),
         details: Some(output_path.display().to_string()),
     })
 }
@@ -956,6 +3830,67 @@ mod tests {
         assert!(ts > 1577836800, "timestamp should be after 2020");
     }
 
+    #[test]
+    fn test_screenshot_file_name() {
+        use chrono::TimeZone;
+        let at = chrono::Utc.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap();
+        assert_eq!(
+            screenshot_file_name(
+                "https://www.example.com:8443/a?b",
+                None,
+                at,
+                ImageFormat::Png
+            ),
+            "www.example.com_20240309-140507.png"
+        );
+        assert_eq!(
+            screenshot_file_name("about:blank", Some("region"), at, ImageFormat::Jpeg),
+            "page_20240309-140507_region.jpg"
+        );
+    }
+
+    #[test]
+    fn test_screenshot_format_resolution() {
+        let options = |format: Option<ImageFormat>, quality: Option<u8>, output: Option<&str>| {
+            ScreenshotOptions {
+                format,
+                quality,
+                output: output.map(PathBuf::from),
+            }
+            .resolve_format()
+        };
+        assert_eq!(options(None, None, None).unwrap(), ImageFormat::Png);
+        assert_eq!(
+            options(None, Some(80), Some("shot.JPG")).unwrap(),
+            ImageFormat::Jpeg
+        );
+        assert_eq!(
+            options(Some(ImageFormat::Webp), None, Some("shot.png")).unwrap(),
+            ImageFormat::Webp
+        );
+        assert!(options(None, Some(80), Some("shot.png")).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let nested = ScreenshotOptions {
+            output: Some(dir.path().join("a/b/shot.png")),
+            ..ScreenshotOptions::default()
+        };
+        let path = nested
+            .output_path("https://example.com", None, ImageFormat::Png)
+            .unwrap();
+        assert!(path.parent().unwrap().is_dir());
+
+        let into_dir = ScreenshotOptions {
+            output: Some(dir.path().to_path_buf()),
+            ..ScreenshotOptions::default()
+        };
+        let path = into_dir
+            .output_path("https://example.com", Some("region"), ImageFormat::Webp)
+            .unwrap();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.starts_with("example.com_") && name.ends_with("_region.webp"));
+    }
+
     #[test]
     fn test_interact_result_display() {
         let result = InteractResult {
@@ -1000,16 +3935,307 @@ mod tests {
         let _click = InteractCommand::Click {
             selector: Some("#btn".to_string()),
             coords: None,
+            space: CoordSpace::default(),
             nth: 0,
             text: None,
+            then: Some(FollowUp::NetworkIdle),
         };
         let _type = InteractCommand::Type {
             selector: Some("input".to_string()),
             text: Some("hello".to_string()),
             focused: false,
+            keystroke_delay: None,
+            framework: Some(Framework::Vue),
         };
         let _nav = InteractCommand::Navigate {
             url: "https://example.com".to_string(),
+            credential: None,
+            wait_until: WaitUntil::default(),
+            referrer: None,
+            timeout_ms: 30000,
+        };
+    }
+
+    #[test]
+    fn test_dialog_policy_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dialog.toml");
+        assert_eq!(DialogPolicy::load(&path).unwrap(), None);
+
+        let policy = DialogPolicy {
+            accept: true,
+            prompt_text: Some("yes".to_string()),
+        };
+        policy.save(&path).unwrap();
+        assert_eq!(DialogPolicy::load(&path).unwrap(), Some(policy));
+
+        assert!(DialogPolicy::clear(&path).unwrap());
+        assert!(!DialogPolicy::clear(&path).unwrap());
+
+        // Each --session keeps its own policy
+        let agent = DialogPolicy::path(Some("agent-1")).unwrap();
+        assert!(agent.ends_with("dialog/agent-1.toml"));
+        assert_ne!(agent, DialogPolicy::path(Some("agent-2")).unwrap());
+        assert!(DialogPolicy::path(None).unwrap().ends_with("dialog.toml"));
+        assert!(DialogPolicy::path(Some("../x")).is_err());
+    }
+
+    #[test]
+    fn test_format_date() {
+        let date = chrono::NaiveDate::from_ymd_opt(2025, 3, 4).unwrap();
+        assert_eq!(
+            format_date(date, "MM/DD/YYYY").as_deref(),
+            Some("03/04/2025")
+        );
+        assert_eq!(
+            format_date(date, "dd.mm.yyyy").as_deref(),
+            Some("04.03.2025")
+        );
+        assert_eq!(format_date(date, "d/m/yy").as_deref(), Some("4/3/25"));
+        assert_eq!(
+            format_date(date, "YYYY-MM-DD").as_deref(),
+            Some("2025-03-04")
+        );
+        assert_eq!(format_date(date, "Select a date"), None);
+        assert_eq!(format_date(date, "MMM D, YYYY"), None);
+        assert_eq!(format_date(date, "MM/YYYY"), None);
+    }
+
+    #[test]
+    fn test_date_value_matches() {
+        let date = chrono::NaiveDate::from_ymd_opt(2025, 3, 14).unwrap();
+        assert!(date_value_matches("03/14/2025", date));
+        assert!(date_value_matches("Mar 14, 2025", date));
+        assert!(date_value_matches("14.03.25", date));
+        assert!(!date_value_matches("", date));
+        assert!(!date_value_matches("03/15/2025", date));
+    }
+
+    #[test]
+    fn test_pick_option() {
+        let option = |text: &str| ComboOption {
+            text: text.to_string(),
+            x: 0.0,
+            y: 0.0,
+            disabled: false,
+        };
+        let options = vec![
+            option("Georgia"),
+            option("Germany (DE)"),
+            option("East  Germany"),
+            option("Create \"Germ\""),
+        ];
+        let text = |picked: Option<&ComboOption>| picked.map(|o| o.text.clone());
+
+        assert_eq!(
+            text(pick_option(&options, "georgia", false)).as_deref(),
+            Some("Georgia")
+        );
+        assert_eq!(
+            text(pick_option(&options, "Germany", false)).as_deref(),
+            Some("Germany (DE)")
+        );
+        assert_eq!(
+            text(pick_option(&options, "east germany", false)).as_deref(),
+            Some("East  Germany")
+        );
+        // A real option starting with the text wins over the create entry
+        assert_eq!(
+            text(pick_option(&options, "Germ", true)).as_deref(),
+            Some("Germany (DE)")
+        );
+        assert_eq!(pick_option(&options, "France", true), None);
+        assert_eq!(pick_option(&options, "many", false), None);
+
+        let creatable = vec![option("Georgia"), option("Create \"Wakanda\"")];
+        assert_eq!(
+            text(pick_option(&creatable, "Wakanda", true)).as_deref(),
+            Some("Create \"Wakanda\"")
+        );
+        assert_eq!(pick_option(&creatable, "Wakanda", false), None);
+    }
+
+    #[test]
+    fn test_gesture_path() {
+        let path: GesturePath = "100,100; 200,100;200,300".parse().unwrap();
+        assert_eq!(path.0, vec![(100.0, 100.0), (200.0, 100.0), (200.0, 300.0)]);
+        assert!("100,100".parse::<GesturePath>().is_err());
+        assert!("100;200,150".parse::<GesturePath>().is_err());
+
+        // 300px of path in 3 steps: a point every 100px, around the corner
+        let points = interpolate_path(&path.0, 3);
+        assert_eq!(
+            points,
+            vec![
+                (100.0, 100.0),
+                (200.0, 100.0),
+                (200.0, 200.0),
+                (200.0, 300.0)
+            ]
+        );
+        assert_eq!(interpolate_path(&path.0, 30).len(), 31);
+        assert_eq!(
+            *interpolate_path(&path.0, 7).last().unwrap(),
+            (200.0, 300.0)
+        );
+    }
+
+    #[test]
+    fn test_pinch_frames() {
+        let frames = pinch_frames((200.0, 300.0), 100.0, 2.0, 4);
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0], vec![(150.0, 300.0), (250.0, 300.0)]);
+        assert_eq!(frames[4], vec![(100.0, 300.0), (300.0, 300.0)]);
+        assert_eq!(frame_count(300), 18);
+        assert_eq!(frame_count(0), 1);
+    }
+
+    #[test]
+    fn test_walk_end() {
+        let stop = |selector: &str| FocusStop {
+            selector: selector.to_string(),
+            tag: "a".to_string(),
+            role: Some("link".to_string()),
+            name: None,
+            focus_visible: true,
+            indicator: None,
+            screenshot: None,
+        };
+        let stops = vec![stop("#home"), stop("#about")];
+        assert_eq!(walk_end(&stops, Some(&stop("#contact"))), None);
+        assert_eq!(
+            walk_end(&stops, Some(&stop("#home"))),
+            Some("cycled back to the first stop")
+        );
+        assert_eq!(walk_end(&stops, Some(&stop("#about"))), Some("focus stuck"));
+        assert_eq!(walk_end(&stops, None), Some("focus left the page"));
+        assert_eq!(walk_end(&[], Some(&stop("#home"))), None);
+    }
+
+    #[test]
+    fn test_scale_coords() {
+        let mut drag = InteractCommand::Drag {
+            from_selector: None,
+            to_selector: Some("#bin".to_string()),
+            from_coords: Some((200.0, 400.0)),
+            to_coords: None,
+            space: CoordSpace::Viewport,
+        };
+        drag.scale_coords(2.0);
+        assert_eq!(drag.coords_mut(), vec![&mut (100.0, 200.0)]);
+
+        let mut region = InteractCommand::ScreenshotRegion {
+            region: (0, 300, 801, 600),
+            space: CoordSpace::Page,
+            options: ScreenshotOptions::default(),
+        };
+        region.scale_coords(1.5);
+        assert!(matches!(
+            region,
+            InteractCommand::ScreenshotRegion {
+                region: (0, 200, 534, 400),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_screen_and_sensitive_action() {
+        let mut config = Config::default();
+        config.security.on_sensitive = SecurityEnforcement::Confirm;
+        let navigate = InteractCommand::Navigate {
+            url: "https://www.paypal.com/".to_string(),
+            credential: None,
+            wait_until: WaitUntil::Load,
+            referrer: None,
+            timeout_ms: 1000,
+        };
+        let error = screen(&config, navigate.clone(), GuardOverrides::default()).unwrap_err();
+        assert!(error.to_string().contains("--confirm-sensitive"));
+        let confirmed = GuardOverrides {
+            confirm_sensitive: true,
+            ..GuardOverrides::default()
+        };
+        assert!(screen(&config, navigate, confirmed).is_ok());
+
+        // With --focused the selector argument is what gets typed
+        let focused = InteractCommand::Type {
+            selector: Some("hunter2".to_string()),
+            text: None,
+            focused: true,
+            keystroke_delay: None,
+            framework: None,
+        };
+        assert!(matches!(
+            focused.sensitive_action(),
+            Some(InteractAction::Type {
+                selector: None,
+                text: "hunter2"
+            })
+        ));
+        assert!(InteractCommand::Refresh.sensitive_action().is_none());
+    }
+
+    #[test]
+    fn test_aliased_password_field_is_sensitive() {
+        let mut site = SiteInstructions {
+            domain: "bank.example".to_string(),
+            ..Default::default()
+        };
+        site.selectors
+            .insert("pw".to_string(), "input[type=password]".to_string());
+        let mut command = InteractCommand::Type {
+            selector: Some("@pw".to_string()),
+            text: Some("hunter2".to_string()),
+            focused: false,
+            keystroke_delay: None,
+            framework: None,
+        };
+
+        // The alias alone says nothing about the field
+        let unresolved = command.sensitive_action().unwrap();
+        assert!(unresolved.check_selector().is_none());
+
+        command.resolve_site_selectors(Some(&site)).unwrap();
+        let detection = command
+            .sensitive_action()
+            .and_then(|action| action.check_selector())
+            .unwrap();
+        assert_eq!(
+            detection.action_type,
+            Some(crate::security::SensitiveActionType::PasswordInput)
+        );
+    }
+
+    #[test]
+    fn test_coord_space() {
+        assert_eq!("page".parse::<CoordSpace>().unwrap(), CoordSpace::Page);
+        assert_eq!(
+            "element:#map canvas".parse::<CoordSpace>().unwrap(),
+            CoordSpace::Element("#map canvas".to_string())
+        );
+        assert!("element:".parse::<CoordSpace>().is_err());
+        assert!("screen".parse::<CoordSpace>().is_err());
+        assert_eq!(CoordSpace::default().to_string(), "viewport");
+
+        let origin = CoordinateOrigin {
+            scroll_x: 0.0,
+            scroll_y: 500.0,
+            width: 1280.0,
+            height: 720.0,
+            element: Some((100.0, 50.0)),
         };
+        assert_eq!(
+            CoordSpace::Page.to_page((10.0, 600.0), &origin),
+            (10.0, 600.0)
+        );
+        assert_eq!(
+            CoordSpace::Viewport.to_page((10.0, 100.0), &origin),
+            (10.0, 600.0)
+        );
+        assert_eq!(
+            CoordSpace::Element("#map".to_string()).to_page((5.0, 5.0), &origin),
+            (105.0, 555.0)
+        );
     }
 }
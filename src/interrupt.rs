@@ -0,0 +1,255 @@
+//! Cleaning up after interrupted commands
+//!
+//! Long-running commands (waits, `debug console --follow`, `session replay`,
+//! `workflow run`, daemons) stop at Ctrl-C through `tokio::signal::ctrl_c`:
+//! the CLI drops the command and, still in the same process, removes
+//! DOMGuard's overlays and hidden fixed elements from the page and saves the
+//! session it was recording as `interrupted` ([`RunGuard::recover`]).
+//!
+//! While they run they also leave a marker in `.domguard/running/`. It is
+//! only needed when the process dies without a chance to clean up (killed,
+//! crashed, terminal closed): the next DOMGuard command finds the marker of
+//! a process that is gone and cleans up after it.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::cdp::CdpConnection;
+use crate::config::Config;
+use crate::session::{Session, SessionRecorder, SessionStatus};
+
+/// Removes highlights, un-hides elements a stitched screenshot hid and stops
+/// the UI change observer and the `debug fps` recorder
+pub const PAGE_CLEANUP_SCRIPT: &str = r"(function() {
+    const highlights = document.querySelectorAll('.__domguard_highlight__, #__domguard_highlight__');
+    highlights.forEach(el => el.remove());
+    const hidden = document.querySelectorAll('[data-domguard-stitch]');
+    for (const el of hidden) {
+        el.style.visibility = el.dataset.domguardStitch;
+        delete el.dataset.domguardStitch;
+    }
+    if (window.__domguardUiObserver) window.__domguardUiObserver.disconnect();
+    window.__domguardUiObserver = null;
+    window.__domguardUiChanges = null;
+    if (window.__domguardFps) window.__domguardFps.stop();
+    delete window.__domguardFps;
+    return { highlights: highlights.length, hidden: hidden.length };
+})()";
+
+/// A long-running command in progress
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunMarker {
+    pub pid: u32,
+    /// e.g. "workflow run"
+    pub command: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    /// `--session` the command ran with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    /// Session that was recording when it started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recording: Option<String>,
+}
+
+impl RunMarker {
+    pub fn new(command: &str, session: Option<&str>, recording: Option<String>) -> Self {
+        Self {
+            pid: std::process::id(),
+            command: command.to_string(),
+            started_at: chrono::Utc::now(),
+            session: session.map(str::to_string),
+            recording,
+        }
+    }
+}
+
+/// Marker of a running command, removed when it finishes (or fails)
+pub struct RunGuard {
+    path: PathBuf,
+    marker: RunMarker,
+}
+
+impl RunGuard {
+    /// Write `marker` to `dir` (`.domguard/running`)
+    pub fn begin(dir: &Path, marker: RunMarker) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.json", marker.pid));
+        crate::storage::write_atomic(&path, &serde_json::to_string(&marker)?)?;
+        Ok(Self { path, marker })
+    }
+
+    pub fn marker(&self) -> &RunMarker {
+        &self.marker
+    }
+
+    /// Clean up after this run's command, stopped by Ctrl-C
+    ///
+    /// The command must have been dropped first, so nothing else uses `cdp`.
+    pub async fn recover(self, cdp: &mut CdpConnection) -> Recovery {
+        // Dropping the guard afterwards removes the marker
+        recover_run(cdp, self.marker.clone()).await
+    }
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// A long-running command stopped by Ctrl-C (after cleaning up)
+#[derive(Debug)]
+pub struct Interrupted {
+    pub command: String,
+}
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}' stopped by Ctrl-C", self.command)
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// Markers in `dir` whose command is no longer running
+pub fn interrupted(dir: &Path) -> Vec<(PathBuf, RunMarker)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let marker: RunMarker =
+                serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+            (marker.pid != std::process::id() && !process_alive(marker.pid))
+                .then_some((path, marker))
+        })
+        .collect()
+}
+
+/// Take responsibility for cleaning up after `path`'s command
+///
+/// Removing the marker is the claim, so two commands starting at once never
+/// both clean up.
+pub fn claim(path: &Path) -> bool {
+    std::fs::remove_file(path).is_ok()
+}
+
+/// The project's `.domguard/running`
+pub fn running_dir() -> PathBuf {
+    Config::find_domguard_dir()
+        .unwrap_or_else(Config::domguard_dir)
+        .join("running")
+}
+
+/// Leave a marker for `command`, noting the session it is recording into
+pub fn begin_run(command: &str, session: Option<&str>) -> Result<RunGuard> {
+    let recording = SessionRecorder::open(session)
+        .ok()
+        .and_then(|recorder| recorder.get_summary())
+        .filter(|summary| summary.status == SessionStatus::Recording)
+        .map(|summary| summary.id);
+    RunGuard::begin(&running_dir(), RunMarker::new(command, session, recording))
+}
+
+/// What cleaning up after one interrupted command did
+#[derive(Debug)]
+pub struct Recovery {
+    pub marker: RunMarker,
+    /// The session it was recording, saved as interrupted (None if there was none)
+    pub session: Result<Option<Session>>,
+    /// Whether the page was tidied; fails when Chrome is gone
+    pub page_cleanup: Result<()>,
+}
+
+/// Save the session an interrupted command was recording and tidy the page
+///
+/// Also used after a Ctrl-C in the same process, whose connection is still
+/// open; attaching again just reconnects to the same Chrome.
+pub async fn recover_run(cdp: &mut CdpConnection, marker: RunMarker) -> Recovery {
+    let session = match &marker.recording {
+        Some(id) => SessionRecorder::open(marker.session.as_deref())
+            .and_then(|recorder| recorder.interrupt(id, &marker.command)),
+        None => Ok(None),
+    };
+
+    // Only a Chrome that is still running has a page to tidy
+    let page_cleanup = match cdp.attach().await {
+        Ok(()) => cdp.evaluate(PAGE_CLEANUP_SCRIPT).await.map(|_| ()),
+        Err(e) => Err(e),
+    };
+
+    Recovery {
+        marker,
+        session,
+        page_cleanup,
+    }
+}
+
+/// Clean up after every interrupted command found
+pub async fn recover_interrupted(cdp: &mut CdpConnection) -> Vec<Recovery> {
+    let mut recovered = Vec::new();
+    for (path, marker) in interrupted(&running_dir()) {
+        if claim(&path) {
+            recovered.push(recover_run(cdp, marker).await);
+        }
+    }
+    recovered
+}
+
+/// Whether a process with this id is running
+pub fn process_alive(pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        Path::new("/proc").join(pid.to_string()).exists()
+    }
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+    #[cfg(windows)]
+    {
+        std::process::Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+            .output()
+            .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markers_of_dead_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard = RunGuard::begin(
+            dir.path(),
+            RunMarker::new("workflow run", None, Some("abc".to_string())),
+        )
+        .unwrap();
+        let own = dir.path().join(format!("{}.json", std::process::id()));
+        // Our own marker is never reported
+        assert!(interrupted(dir.path()).is_empty());
+        assert!(own.exists());
+        assert_eq!(guard.marker().command, "workflow run");
+        drop(guard);
+        assert!(!own.exists());
+
+        let dead = RunMarker {
+            pid: u32::MAX - 1,
+            ..RunMarker::new("session replay", Some("agent-1"), None)
+        };
+        let path = dir.path().join(format!("{}.json", dead.pid));
+        std::fs::write(&path, serde_json::to_string(&dead).unwrap()).unwrap();
+        let found = interrupted(dir.path());
+        assert_eq!(found, vec![(path.clone(), dead)]);
+        assert!(claim(&path));
+        assert!(!claim(&path));
+    }
+}
@@ -0,0 +1,307 @@
+//! Keyboard input parsing for DOMGuard
+//!
+//! Turns key specs like "Enter", "ctrl+shift+p" or "Tab Tab Enter", and text
+//! typed character by character, into keystrokes that can be dispatched with
+//! CDP `Input.dispatchKeyEvent`.
+
+use anyhow::{anyhow, Result};
+
+/// CDP modifier bitmask values
+pub const MODIFIER_ALT: i64 = 1;
+pub const MODIFIER_CTRL: i64 = 2;
+pub const MODIFIER_META: i64 = 4;
+pub const MODIFIER_SHIFT: i64 = 8;
+
+/// A single key press, optionally with modifiers held
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyStroke {
+    /// DOM `key` value (e.g., "Enter", "a", "A")
+    pub key: String,
+
+    /// DOM `code` value (e.g., "Enter", "KeyA")
+    pub code: String,
+
+    /// Windows virtual key code
+    pub key_code: i64,
+
+    /// Text produced by the key, if any
+    pub text: Option<String>,
+
+    /// Modifier bitmask (see `MODIFIER_*`)
+    pub modifiers: i64,
+}
+
+/// Modifier keys in press order: (bit, key, code, key code)
+const MODIFIER_KEYS: [(i64, &str, &str, i64); 4] = [
+    (MODIFIER_CTRL, "Control", "ControlLeft", 17),
+    (MODIFIER_SHIFT, "Shift", "ShiftLeft", 16),
+    (MODIFIER_ALT, "Alt", "AltLeft", 18),
+    (MODIFIER_META, "Meta", "MetaLeft", 91),
+];
+
+impl KeyStroke {
+    /// Modifier keys to hold down for this stroke with their bits, in press order
+    pub fn modifier_keys(&self) -> Vec<(i64, KeyStroke)> {
+        MODIFIER_KEYS
+            .iter()
+            .filter(|(bit, ..)| self.modifiers & bit != 0)
+            .map(|(bit, key, code, key_code)| (*bit, named_key(key, code, *key_code)))
+            .collect()
+    }
+}
+
+/// Parse a whitespace-separated key sequence (e.g., "Tab Tab Enter")
+pub fn parse_key_sequence(keys: &str) -> Result<Vec<KeyStroke>> {
+    let strokes = keys
+        .split_whitespace()
+        .map(parse_key_combo)
+        .collect::<Result<Vec<_>>>()?;
+
+    if strokes.is_empty() {
+        return Err(anyhow!("No keys given"));
+    }
+    Ok(strokes)
+}
+
+/// Parse a single key combination (e.g., "Enter", "cmd+k", "ctrl+shift+p")
+pub fn parse_key_combo(combo: &str) -> Result<KeyStroke> {
+    // A trailing "+" is the plus key itself ("ctrl++")
+    let (modifier_part, key_part) = if let Some(prefix) = combo.strip_suffix("++") {
+        (Some(prefix), "+")
+    } else if combo == "+" {
+        (None, "+")
+    } else {
+        match combo.rsplit_once('+') {
+            Some((prefix, key)) => (Some(prefix), key),
+            None => (None, combo),
+        }
+    };
+
+    let mut modifiers = 0;
+    if let Some(prefix) = modifier_part {
+        for name in prefix.split('+') {
+            modifiers |= parse_modifier(name)
+                .ok_or_else(|| anyhow!("Unknown modifier \"{}\" in \"{}\"", name, combo))?;
+        }
+    }
+
+    // A bare modifier ("shift") is pressed like any other key
+    if modifier_part.is_none() {
+        if let Some(bit) = parse_modifier(key_part) {
+            if let Some((_, key, code, key_code)) = MODIFIER_KEYS.iter().find(|(b, ..)| *b == bit) {
+                return Ok(named_key(key, code, *key_code));
+            }
+        }
+    }
+
+    let mut stroke = lookup_key(key_part)
+        .ok_or_else(|| anyhow!("Unknown key \"{}\" in \"{}\"", key_part, combo))?;
+    stroke.modifiers = modifiers;
+
+    // Shift changes the produced letter; other modifiers produce shortcuts, not text
+    if modifiers & MODIFIER_SHIFT != 0 && stroke.key.len() == 1 {
+        stroke.key = stroke.key.to_uppercase();
+        stroke.text = stroke.text.map(|t| t.to_uppercase());
+    }
+    if modifiers & (MODIFIER_CTRL | MODIFIER_ALT | MODIFIER_META) != 0 {
+        stroke.text = None;
+    }
+
+    Ok(stroke)
+}
+
+/// Keystroke for typing a single character
+pub fn char_key(c: char) -> KeyStroke {
+    let mut stroke = lookup_key(&c.to_string()).unwrap_or_else(|| KeyStroke {
+        key: c.to_string(),
+        code: String::new(),
+        key_code: 0,
+        text: Some(c.to_string()),
+        modifiers: 0,
+    });
+
+    if c.is_ascii_uppercase() {
+        stroke.modifiers = MODIFIER_SHIFT;
+    }
+    stroke
+}
+
+/// Vary a typing delay by up to ±50% so keystrokes don't arrive at a fixed rhythm
+pub fn human_delay(base_ms: u64, seed: u64) -> u64 {
+    if base_ms == 0 {
+        return 0;
+    }
+    // Cheap integer hash of the seed spreads consecutive values apart
+    let spread = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32;
+    base_ms / 2 + spread % (base_ms + 1)
+}
+
+fn parse_modifier(name: &str) -> Option<i64> {
+    match name.to_lowercase().as_str() {
+        "ctrl" | "control" => Some(MODIFIER_CTRL),
+        "shift" => Some(MODIFIER_SHIFT),
+        "alt" | "option" | "opt" => Some(MODIFIER_ALT),
+        "cmd" | "command" | "meta" | "super" | "win" => Some(MODIFIER_META),
+        _ => None,
+    }
+}
+
+fn named_key(key: &str, code: &str, key_code: i64) -> KeyStroke {
+    KeyStroke {
+        key: key.to_string(),
+        code: code.to_string(),
+        key_code,
+        text: None,
+        modifiers: 0,
+    }
+}
+
+fn text_key(key: &str, code: &str, key_code: i64) -> KeyStroke {
+    KeyStroke {
+        text: Some(key.to_string()),
+        ..named_key(key, code, key_code)
+    }
+}
+
+/// Look up a key by name or character
+fn lookup_key(name: &str) -> Option<KeyStroke> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return lookup_char(c);
+    }
+
+    let stroke = match name.to_lowercase().as_str() {
+        "enter" | "return" => KeyStroke {
+            text: Some("\r".to_string()),
+            ..named_key("Enter", "Enter", 13)
+        },
+        "tab" => named_key("Tab", "Tab", 9),
+        "escape" | "esc" => named_key("Escape", "Escape", 27),
+        "backspace" => named_key("Backspace", "Backspace", 8),
+        "delete" | "del" => named_key("Delete", "Delete", 46),
+        "insert" => named_key("Insert", "Insert", 45),
+        "space" => text_key(" ", "Space", 32),
+        "arrowup" | "up" => named_key("ArrowUp", "ArrowUp", 38),
+        "arrowdown" | "down" => named_key("ArrowDown", "ArrowDown", 40),
+        "arrowleft" | "left" => named_key("ArrowLeft", "ArrowLeft", 37),
+        "arrowright" | "right" => named_key("ArrowRight", "ArrowRight", 39),
+        "home" => named_key("Home", "Home", 36),
+        "end" => named_key("End", "End", 35),
+        "pageup" => named_key("PageUp", "PageUp", 33),
+        "pagedown" => named_key("PageDown", "PageDown", 34),
+        "plus" => text_key("+", "Equal", 187),
+        lower => {
+            // Function keys F1-F12
+            let n: i64 = lower.strip_prefix('f')?.parse().ok()?;
+            if !(1..=12).contains(&n) {
+                return None;
+            }
+            let key = format!("F{}", n);
+            named_key(&key, &key, 111 + n)
+        }
+    };
+    Some(stroke)
+}
+
+/// Key codes for a printable character on a US layout
+fn lookup_char(c: char) -> Option<KeyStroke> {
+    let lower = c.to_ascii_lowercase();
+    let key = c.to_string();
+
+    let stroke = if lower.is_ascii_lowercase() {
+        let upper = lower.to_ascii_uppercase();
+        text_key(&key, &format!("Key{}", upper), i64::from(u32::from(upper)))
+    } else if c.is_ascii_digit() {
+        text_key(&key, &format!("Digit{}", c), i64::from(u32::from(c)))
+    } else {
+        let (code, key_code) = match c {
+            ' ' => ("Space", 32),
+            '-' | '_' => ("Minus", 189),
+            '=' | '+' => ("Equal", 187),
+            '[' | '{' => ("BracketLeft", 219),
+            ']' | '}' => ("BracketRight", 221),
+            '\\' | '|' => ("Backslash", 220),
+            ';' | ':' => ("Semicolon", 186),
+            '\'' | '"' => ("Quote", 222),
+            ',' | '<' => ("Comma", 188),
+            '.' | '>' => ("Period", 190),
+            '/' | '?' => ("Slash", 191),
+            '`' | '~' => ("Backquote", 192),
+            '\n' => return lookup_key("enter"),
+            '\t' => return lookup_key("tab"),
+            _ if c.is_control() => return None,
+            _ => ("", 0),
+        };
+        text_key(&key, code, key_code)
+    };
+    Some(stroke)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_named_key() {
+        let stroke = parse_key_combo("Enter").unwrap();
+        assert_eq!(stroke.key, "Enter");
+        assert_eq!(stroke.key_code, 13);
+        assert_eq!(stroke.modifiers, 0);
+
+        let f5 = parse_key_combo("f5").unwrap();
+        assert_eq!(f5.key, "F5");
+        assert_eq!(f5.key_code, 116);
+    }
+
+    #[test]
+    fn test_parse_modifier_combo() {
+        let stroke = parse_key_combo("ctrl+shift+p").unwrap();
+        assert_eq!(stroke.key, "P");
+        assert_eq!(stroke.code, "KeyP");
+        assert_eq!(stroke.modifiers, MODIFIER_CTRL | MODIFIER_SHIFT);
+        assert_eq!(stroke.text, None);
+        assert_eq!(stroke.modifier_keys().len(), 2);
+
+        let cmd_k = parse_key_combo("cmd+k").unwrap();
+        assert_eq!(cmd_k.modifiers, MODIFIER_META);
+
+        let plus = parse_key_combo("ctrl++").unwrap();
+        assert_eq!(plus.key, "+");
+        assert_eq!(plus.modifiers, MODIFIER_CTRL);
+    }
+
+    #[test]
+    fn test_parse_key_sequence() {
+        let strokes = parse_key_sequence("Tab Tab Enter").unwrap();
+        let keys: Vec<&str> = strokes.iter().map(|s| s.key.as_str()).collect();
+        assert_eq!(keys, vec!["Tab", "Tab", "Enter"]);
+
+        assert!(parse_key_sequence("").is_err());
+        assert!(parse_key_sequence("hyper+x").is_err());
+        assert!(parse_key_sequence("NotAKey").is_err());
+    }
+
+    #[test]
+    fn test_char_key() {
+        let a = char_key('a');
+        assert_eq!(a.code, "KeyA");
+        assert_eq!(a.text.as_deref(), Some("a"));
+
+        let upper = char_key('A');
+        assert_eq!(upper.key, "A");
+        assert_eq!(upper.modifiers, MODIFIER_SHIFT);
+
+        let accented = char_key('é');
+        assert_eq!(accented.text.as_deref(), Some("é"));
+        assert_eq!(accented.key_code, 0);
+    }
+
+    #[test]
+    fn test_human_delay() {
+        assert_eq!(human_delay(0, 42), 0);
+        for seed in 0..100 {
+            let delay = human_delay(50, seed);
+            assert!((25..=75).contains(&delay));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! DOMGuard as a library
+//!
+//! The `domguard` binary is a clap front end over these modules; other Rust
+//! tools and integration tests can drive Chrome the same way without spawning
+//! the CLI:
+//!
+//! ```no_run
+//! use domguard::interact::{screen, GuardOverrides};
+//! use domguard::{run_interact, CdpConnection, Config, Formatter, InteractCommand};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let config = Config::load()?;
+//! let command = screen(
+//!     &config,
+//!     InteractCommand::Navigate {
+//!         url: "https://example.com".to_string(),
+//!         credential: None,
+//!         wait_until: domguard::cdp::WaitUntil::Load,
+//!         referrer: None,
+//!         timeout_ms: 30000,
+//!     },
+//!     GuardOverrides::default(),
+//! )?;
+//! let mut cdp = CdpConnection::new(config.clone());
+//! cdp.connect().await?;
+//! let formatter = Formatter::new(true);
+//! run_interact(&cdp, &config, command, None, &formatter).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Results go through [`Formatter`] as they do on the command line; with JSON
+//! output each command prints one JSON document. The project's guards apply
+//! as they do to the CLI: [`interact::screen`] refuses blocked sites and
+//! sensitive navigations and uploads before connecting, and [`run_interact`]
+//! adds site rules, sensitive-action checks on the page, action budgets and
+//! CAPTCHA takeovers.
+
+// The API takes the std HashMaps its callers already have
+#![allow(clippy::implicit_hasher)]
+
+pub mod auth;
+pub mod backend;
+pub mod bidi;
+pub mod budget;
+pub mod captcha;
+pub mod cdp;
+pub mod config;
+pub mod consent;
+pub mod container;
+pub mod correction;
+pub mod debug;
+pub mod discovery;
+pub mod doctor;
+pub mod events;
+pub mod explanation;
+pub mod fps;
+pub mod framework;
+pub mod graphql;
+pub mod heap;
+pub mod http;
+pub mod inspire;
+pub mod interact;
+pub mod interrupt;
+pub mod keyboard;
+pub mod network;
+pub mod output;
+pub mod perf;
+pub mod png;
+pub mod retry;
+pub mod runner;
+pub mod secrets;
+pub mod security;
+pub mod serve;
+pub mod session;
+pub mod site_instructions;
+pub mod sourcemap;
+pub mod storage;
+pub mod takeover;
+pub mod telemetry;
+pub mod traffic;
+pub mod tunnel;
+pub mod workflow;
+
+pub use cdp::CdpConnection;
+pub use config::Config;
+pub use debug::{run_debug, DebugCommand};
+pub use interact::{run_interact, InteractCommand, InteractResult};
+pub use output::Formatter;
+pub use session::{Session, SessionRecorder};
+pub use workflow::{Workflow, WorkflowManager};
@@ -2,31 +2,22 @@
 //!
 //! Direct CDP access for AI agents. No middleware, no servers, sub-ms local response.
 
-mod captcha;
-mod cdp;
-mod config;
-mod correction;
-mod debug;
-mod explanation;
-mod inspire;
-mod interact;
-mod output;
-mod security;
-mod session;
-mod site_instructions;
-mod takeover;
-mod workflow;
+use domguard::{
+    auth, backend, bidi, budget, cdp, config, container, correction, debug, discovery, doctor,
+    events, explanation, fps, framework, inspire, interact, interrupt, network, output, perf,
+    retry, runner, secrets, security, serve, session, site_instructions, storage, takeover,
+    telemetry, tunnel, workflow,
+};
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use colored::*;
-use std::fmt::Write as _;
 use std::path::PathBuf;
 
 use crate::cdp::CdpConnection;
 use crate::config::{init_domguard, Config};
 use crate::debug::DebugCommand;
-use crate::interact::InteractCommand;
+use crate::interact::{FollowUp, InteractCommand};
 use crate::output::{CommandResult, Formatter};
 use crate::session::SessionRecorder;
 
@@ -40,6 +31,10 @@ struct Cli {
     #[arg(long, global = true)]
     json: bool,
 
+    /// Config profile to merge over the base config ([profiles.<name>] in config.toml)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
     /// Chrome DevTools host (default: 127.0.0.1)
     #[arg(long, global = true)]
     host: Option<String>,
@@ -48,10 +43,50 @@ struct Cli {
     #[arg(long, global = true)]
     port: Option<u16>,
 
-    /// Command timeout in milliseconds
+    /// Command timeout in milliseconds (bounds the whole command and every wait
+    /// in it; for long-running commands such as daemons, only the waits)
     #[arg(long, global = true)]
     timeout: Option<u64>,
 
+    /// Record interact commands into a session, starting one if none is active
+    #[arg(long, global = true)]
+    record: bool,
+
+    /// Record into (or start, with --record or 'session start') the session with this ID,
+    /// kept apart from sessions other processes are recording
+    #[arg(long, global = true, value_name = "ID")]
+    session: Option<String>,
+
+    /// Show sensitive values unmasked in eval, DOM and session output (local debugging)
+    #[arg(long, global = true)]
+    no_mask: bool,
+
+    /// Allow connecting to Chrome on a non-localhost host
+    #[arg(long, global = true)]
+    allow_remote: bool,
+
+    /// Only print results and errors (no progress, warnings or hints)
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Write JSON results to this file instead of stdout (implies --json)
+    #[arg(long, global = true)]
+    output_file: Option<PathBuf>,
+
+    /// Machine-readable output format: json, yaml or toml (implies --json)
+    // `session export` and the screenshot commands have their own --format under
+    // the same id, so clap shares one string value; see `data_format`
+    #[arg(long, global = true)]
+    format: Option<String>,
+
+    /// Newline-delimited JSON; streaming commands (e.g. debug console --follow) emit each item as it arrives
+    #[arg(long, global = true)]
+    ndjson: bool,
+
+    /// Browser backend: chrome, or firefox (experimental; navigate, click, type, screenshot, eval)
+    #[arg(long, global = true)]
+    browser: Option<crate::backend::BackendKind>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -62,12 +97,30 @@ enum Commands {
     Init,
 
     /// Check Chrome connection status
-    Status,
+    Status {
+        /// Show the browser version and every DevTools target
+        #[arg(long)]
+        verbose: bool,
+    },
+
+    /// Diagnose the Chrome setup, config and .domguard state, with fixes
+    Doctor,
+
+    /// Read and change config.toml settings
+    Config {
+        #[command(subcommand)]
+        command: ConfigSubcommand,
+    },
 
-    /// Extract design patterns from websites
+    /// Extract design patterns from websites (or browse saved ones)
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Inspire {
+        #[command(subcommand)]
+        action: Option<InspireAction>,
+
         /// URL to analyze
-        url: String,
+        #[arg(required = true)]
+        url: Option<String>,
 
         /// CSS selector for specific component
         #[arg(long)]
@@ -76,6 +129,22 @@ enum Commands {
         /// Save inspiration with name
         #[arg(long)]
         save: Option<String>,
+
+        /// Print only the design tokens, as json, css (custom properties) or tailwind (theme fragment)
+        #[arg(long)]
+        tokens: Option<crate::inspire::TokenFormat>,
+
+        /// Write the --component as a standalone HTML/CSS/assets bundle to this directory
+        #[arg(long, requires = "component")]
+        export: Option<PathBuf>,
+
+        /// Also screenshot the saved inspiration at several viewport widths and record layout changes
+        #[arg(long, requires = "save")]
+        responsive: bool,
+
+        /// Viewport widths for --responsive (default: `[inspire] responsive_widths`)
+        #[arg(long, value_delimiter = ',', requires = "responsive")]
+        widths: Option<Vec<u32>>,
     },
 
     /// Inspect page state (DOM, console, network, storage)
@@ -88,6 +157,22 @@ enum Commands {
     Interact {
         #[command(subcommand)]
         command: InteractSubcommand,
+
+        /// Go ahead with a sensitive action when `[security] on_sensitive = "confirm"`
+        #[arg(long, global = true)]
+        confirm_sensitive: bool,
+
+        /// Navigate to a blocked site anyway (needs `[security] allow_block_override = true`)
+        #[arg(long, global = true)]
+        override_block: bool,
+
+        /// Switch to any popup or new tab the action opens (config: defaults.follow_popups)
+        #[arg(long, global = true)]
+        follow_popups: bool,
+
+        /// Coordinates were read off a screenshot: convert image pixels to CSS pixels
+        #[arg(long, global = true)]
+        coords_from_screenshot: bool,
     },
 
     /// Record and manage browser sessions
@@ -131,6 +216,95 @@ enum Commands {
         #[command(subcommand)]
         command: CorrectionSubcommand,
     },
+
+    /// Control network traffic (request mocking, blocking, headers, auth)
+    Network {
+        #[command(subcommand)]
+        command: NetworkSubcommand,
+    },
+
+    /// POST page events (navigation, console errors, dialogs, downloads) to webhooks
+    Events {
+        #[command(subcommand)]
+        command: EventsSubcommand,
+    },
+
+    /// Manage encrypted secrets for ${secret:NAME} workflow placeholders
+    Secrets {
+        #[command(subcommand)]
+        command: SecretsSubcommand,
+    },
+
+    /// Sign in with a site's login workflow and reuse the session across runs
+    Auth {
+        #[command(subcommand)]
+        command: AuthSubcommand,
+    },
+
+    /// Run Chrome in a Docker/Podman container
+    Chrome {
+        #[command(subcommand)]
+        command: ChromeSubcommand,
+    },
+
+    /// Forward a remote Chrome debugging port over SSH (runs until interrupted)
+    Tunnel {
+        /// SSH destination (e.g., user@host)
+        destination: String,
+
+        /// Debugging port on the remote machine
+        #[arg(long, default_value = "9222")]
+        remote_port: u16,
+
+        /// Local port to listen on (default: the configured Chrome port)
+        #[arg(long)]
+        local_port: Option<u16>,
+    },
+
+    /// Serve the gRPC API in proto/domguard.proto (runs until interrupted; needs domguard-grpc)
+    Serve {
+        /// Address to listen on, e.g. 127.0.0.1:7331 (other than loopback needs --allow-remote)
+        #[arg(long, value_name = "ADDR")]
+        grpc: std::net::SocketAddr,
+    },
+
+    /// Run another command again after failures, waiting longer each time
+    Retry {
+        /// Total attempts, including the first
+        #[arg(long, default_value = "3", value_parser = clap::value_parser!(u32).range(1..=100))]
+        attempts: u32,
+
+        /// How much each delay grows, e.g. 2x
+        #[arg(long, default_value = "2x", value_parser = parse_backoff)]
+        backoff: f64,
+
+        /// Delay before the first retry in milliseconds
+        #[arg(long, default_value = "500")]
+        delay: u64,
+
+        /// Longest delay between attempts in milliseconds
+        #[arg(long, default_value = "30000")]
+        max_delay: u64,
+
+        /// Retry every error, not only those a re-run may fix (timeouts, missing elements, ...)
+        #[arg(long)]
+        any_error: bool,
+
+        /// Command to run, after `--` (e.g. -- interact click "#flaky"); global options go before `--`
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+
+    /// Index saved sessions written by older versions (makes 'session list' fast),
+    /// or copy sessions and workflows into SQLite with --to sqlite
+    MigrateStorage {
+        /// Backend to migrate to: fs (index the files) or sqlite (needs the sqlite feature)
+        #[arg(long, default_value = "fs")]
+        to: storage::StorageBackend,
+    },
+
+    /// Clean up after commands that died mid-run (page overlays, session recordings)
+    Recover,
 }
 
 #[derive(Subcommand)]
@@ -145,30 +319,164 @@ enum DebugSubcommand {
     Styles {
         /// CSS selector
         selector: String,
+
+        /// Also list matched CSS rules with source, specificity and overridden declarations
+        #[arg(long)]
+        matched: bool,
+
+        /// Also show the margin, border, padding and content boxes
+        #[arg(long)]
+        box_model: bool,
+    },
+
+    /// Inject CSS into the page; it survives SPA route changes until `css clear` or a reload
+    Css {
+        #[command(subcommand)]
+        action: CssAction,
+    },
+
+    /// Freeze or slow animations and transitions so screenshots are deterministic
+    Animations {
+        /// Jump every animation and transition to its end state
+        #[arg(long, required_unless_present_any = ["slow", "enable"], conflicts_with_all = ["slow", "enable"])]
+        disable: bool,
+
+        /// Slow animations down, e.g. --slow 10x
+        #[arg(long, value_parser = parse_slowdown, conflicts_with = "enable")]
+        slow: Option<f64>,
+
+        /// Restore normal animation
+        #[arg(long)]
+        enable: bool,
+    },
+
+    /// Run a script in the page; with --persist, before page scripts on every page DOMGuard loads
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Inject {
+        #[command(subcommand)]
+        action: Option<InjectAction>,
+
+        /// JavaScript file (e.g. shims that disable animations or stub Date.now)
+        #[arg(long, required = true)]
+        script: Option<PathBuf>,
+
+        /// Keep injecting it until 'debug inject remove'
+        #[arg(long)]
+        persist: bool,
     },
 
     /// View console messages
     Console {
-        /// Stream console messages live
+        /// Stream console messages live until Ctrl-C (or --duration)
         #[arg(long)]
         follow: bool,
 
         /// Filter messages by text
         #[arg(long)]
         filter: Option<String>,
+
+        /// Stop following after this many milliseconds and print the summary
+        #[arg(long, requires = "follow")]
+        duration: Option<u64>,
+
+        /// Only these levels, comma-separated (e.g. error,warn)
+        #[arg(long, value_delimiter = ',')]
+        level: Vec<String>,
+    },
+
+    /// Report JavaScript exceptions live, with source-mapped locations
+    Exceptions {
+        /// Pause in the debugger on each uncaught exception before resuming
+        #[arg(long)]
+        pause: bool,
+
+        /// Pause on caught exceptions too
+        #[arg(long, requires = "pause")]
+        caught: bool,
+
+        /// Save a screenshot and DOM snapshot at each exception
+        #[arg(long, requires = "pause")]
+        capture: bool,
+
+        /// Directory for captures (default: .domguard/exceptions)
+        #[arg(short, long, requires = "capture")]
+        output: Option<PathBuf>,
+
+        /// Stop after this many milliseconds (default: until Ctrl-C)
+        #[arg(long)]
+        duration: Option<u64>,
     },
 
     /// View network requests
     Network {
+        #[command(subcommand)]
+        action: Option<NetworkAction>,
+
         /// Filter requests by URL
         #[arg(long)]
         filter: Option<String>,
     },
 
+    /// Capture fetch/XHR request and response bodies (and Server-Sent Events)
+    Xhr {
+        /// Print calls live as they complete, until Ctrl-C (or --duration)
+        #[arg(long)]
+        follow: bool,
+
+        /// Only calls whose URL contains this (e.g. /api/)
+        #[arg(long)]
+        url_filter: Option<String>,
+
+        /// Only calls with this HTTP method
+        #[arg(long)]
+        method: Option<String>,
+
+        /// Keep at most this many bytes of each body
+        #[arg(long, default_value_t = crate::cdp::DEFAULT_XHR_BODY_BYTES)]
+        max_body: usize,
+
+        /// Stop after this many milliseconds (without --follow, default: 5000)
+        #[arg(long)]
+        duration: Option<u64>,
+
+        /// Show GraphQL operations (name, variables, errors) instead of raw bodies
+        #[arg(long)]
+        decode_graphql: bool,
+    },
+
     /// Execute JavaScript expression
     Eval {
         /// JavaScript expression
-        expression: String,
+        #[arg(required_unless_present = "file", conflicts_with = "file")]
+        expression: Option<String>,
+
+        /// Run a script file instead of an expression
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Expose a string as params.KEY (repeatable), e.g. --arg user=alice
+        #[arg(long = "arg", value_parser = parse_param)]
+        args: Vec<(String, String)>,
+
+        /// Allow top-level await and wait for the returned promise
+        #[arg(long = "await")]
+        await_promise: bool,
+
+        /// Where to run: main (alongside page scripts) or isolated (out of their reach)
+        #[arg(long, default_value = "main")]
+        world: crate::debug::EvalWorld,
+
+        /// Levels to expand for results JSON can't hold (DOM nodes, Maps, class instances)
+        #[arg(long, default_value = "3")]
+        depth: u32,
+
+        /// Keep the result in the page and print a ref for later --this calls
+        #[arg(long, conflicts_with = "await_promise")]
+        return_by_ref: bool,
+
+        /// Run with a stored ref (from --return-by-ref) as `this`
+        #[arg(long = "this", value_name = "REF", conflicts_with = "await_promise")]
+        this_ref: Option<String>,
     },
 
     /// View localStorage and sessionStorage
@@ -189,10 +497,92 @@ enum DebugSubcommand {
         action: TabAction,
     },
 
+    /// Manage browser contexts created with 'tabs new --incognito'
+    Contexts {
+        #[command(subcommand)]
+        action: ContextAction,
+    },
+
+    /// List the navigation history of the active tab
+    History,
+
     // Chrome DevTools MCP features
     /// Get performance metrics (Core Web Vitals, heap size, etc.)
     Performance,
 
+    /// Check the page against performance limits; exits 1 listing each one exceeded
+    PerfAssert {
+        /// Largest Contentful Paint limit in milliseconds
+        #[arg(long)]
+        lcp_max: Option<f64>,
+
+        /// First Contentful Paint limit in milliseconds
+        #[arg(long)]
+        fcp_max: Option<f64>,
+
+        /// Time to First Byte limit in milliseconds
+        #[arg(long)]
+        ttfb_max: Option<f64>,
+
+        /// Cumulative Layout Shift limit (e.g. 0.1)
+        #[arg(long)]
+        cls_max: Option<f64>,
+
+        /// Total transfer size limit (e.g. 1.5MB, 300KB)
+        #[arg(long, value_parser = perf::parse_size)]
+        transfer_max: Option<u64>,
+
+        /// Request count limit, the document included
+        #[arg(long)]
+        requests_max: Option<u64>,
+    },
+
+    /// Frame rate, dropped frames and long tasks while another command runs
+    Fps {
+        /// Command to run and measure, e.g. "interact click #play"
+        #[arg(long, value_name = "COMMAND")]
+        during: String,
+    },
+
+    /// Heap snapshot summary by constructor, or what grew since an earlier one (leak hunting)
+    Heap {
+        /// Take a snapshot and save its summary in .domguard/heap
+        #[arg(long, required_unless_present = "compare", conflicts_with = "compare")]
+        snapshot: bool,
+
+        /// Take a snapshot and compare it with an earlier one (id or path)
+        #[arg(long, value_name = "ID")]
+        compare: Option<String>,
+
+        /// Force garbage collection before the snapshot
+        #[arg(long)]
+        gc: bool,
+
+        /// Number of constructors to show
+        #[arg(long, default_value = "20")]
+        top: usize,
+    },
+
+    /// Readiness score: load state, pending fetch/XHR, framework hydration, SPA route changes
+    Readiness,
+
+    /// Detected frontend framework and version, and how input events are shaped for it
+    Framework,
+
+    /// Export a canvas bitmap (for vision models), or a screenshot of it when unreadable
+    Canvas {
+        /// CSS selector of the canvas
+        selector: String,
+
+        /// Image format: png, jpeg or webp
+        #[arg(long, default_value = "png")]
+        export: crate::backend::ImageFormat,
+
+        /// Output file path
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
     /// Export full DOM as HTML snapshot
     Snapshot {
         /// Output file path
@@ -236,92 +626,348 @@ enum DebugSubcommand {
 
     /// Detect CAPTCHAs on the current page
     Captcha,
-}
 
-#[derive(Subcommand)]
-enum ThrottleAction {
-    /// Disable all throttling
-    Off,
+    /// List overlays and modals covering the viewport: z-index, click interception, dismiss buttons
+    Overlays {
+        /// Smallest share of the viewport to report, in percent
+        #[arg(long, default_value = "10", value_parser = clap::value_parser!(u8).range(1..=100))]
+        min_coverage: u8,
+    },
 
-    /// Slow down CPU (rate = slowdown factor, e.g., 4 = 4x slower)
-    Cpu {
-        /// Slowdown rate (1 = normal, 4 = 4x slower)
-        rate: f64,
+    /// Read element attributes/properties (or modify them with 'attr set')
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Attr {
+        #[command(subcommand)]
+        action: Option<AttrAction>,
+
+        /// CSS selector
+        #[arg(required = true)]
+        selector: Option<String>,
+
+        /// Attribute or property name (e.g., value, href, dataset, disabled); default: all
+        name: Option<String>,
     },
 
-    /// Simulate 3G network (1.6 Mbps, 300ms latency)
-    Network3g,
+    /// Read element text or value
+    Text {
+        /// CSS selector
+        selector: String,
 
-    /// Simulate slow 3G network (400 Kbps, 2s latency)
-    NetworkSlow3g,
+        /// Read every matching element instead of the first
+        #[arg(long)]
+        all: bool,
 
-    /// Simulate offline mode
-    NetworkOffline,
+        /// Rendered text (innerText) instead of textContent
+        #[arg(long, conflicts_with_all = ["value", "trimmed"])]
+        inner: bool,
 
-    /// Custom network conditions
-    NetworkCustom {
-        /// Download speed in Kbps
+        /// Form control value
+        #[arg(long, conflicts_with = "trimmed")]
+        value: bool,
+
+        /// Collapse whitespace and trim
         #[arg(long)]
-        download: f64,
+        trimmed: bool,
+    },
 
-        /// Upload speed in Kbps
+    /// Count elements matching a selector (exit code 1 if none)
+    Count {
+        /// CSS selector
+        selector: String,
+    },
+
+    /// Check whether any element matches a selector (exit code 1 if none)
+    Exists {
+        /// CSS selector
+        selector: String,
+    },
+
+    /// Find elements by text, attribute or tag, shadow roots included (exit code 1 if none)
+    Find {
+        /// Text the element shows or is labelled with (case-insensitive)
+        #[arg(long, required_unless_present_any = ["attr", "tag"])]
+        text: Option<String>,
+
+        /// Attribute the element has, optionally with a value (e.g. data-testid=checkout)
         #[arg(long)]
-        upload: f64,
+        attr: Option<String>,
 
-        /// Latency in milliseconds
+        /// Tag name (e.g. button)
         #[arg(long)]
-        latency: f64,
+        tag: Option<String>,
+
+        /// Skip hidden and zero-size elements
+        #[arg(long)]
+        visible_only: bool,
+
+        /// Maximum number of matches
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Compare the current DOM with an earlier snapshot: added, removed and changed elements
+    DomDiff {
+        /// Baseline HTML file (e.g. from `debug snapshot -o`)
+        #[arg(
+            long,
+            required_unless_present = "baseline_id",
+            conflicts_with = "baseline_id"
+        )]
+        before: Option<PathBuf>,
+
+        /// Baseline id printed by `debug snapshot`
+        #[arg(long)]
+        baseline_id: Option<String>,
     },
 }
 
 #[derive(Subcommand)]
-enum TabAction {
-    /// List all open tabs
+enum InspireAction {
+    /// List saved inspirations
     List,
 
-    /// Create a new tab
-    New {
-        /// URL to open (default: about:blank)
-        url: Option<String>,
+    /// Show a saved inspiration
+    Show {
+        /// Inspiration name (as given to --save)
+        name: String,
     },
 
-    /// Switch to a tab by ID
-    Switch {
-        /// Tab ID to switch to
-        id: String,
+    /// Compare the palettes and typography of two saved inspirations
+    Compare {
+        /// First inspiration
+        a: String,
+
+        /// Second inspiration
+        b: String,
     },
 
-    /// Close a tab by ID
-    Close {
-        /// Tab ID to close
-        id: String,
+    /// Delete a saved inspiration
+    Delete {
+        /// Inspiration name
+        name: String,
     },
 }
 
 #[derive(Subcommand)]
-enum InteractSubcommand {
-    /// Click element or coordinates
-    Click {
+enum AttrAction {
+    /// Set an attribute (or DOM property with --property)
+    Set {
         /// CSS selector
-        selector: Option<String>,
+        selector: String,
 
-        /// Click at coordinates (x,y)
-        #[arg(long, value_parser = parse_coords)]
-        coords: Option<(f64, f64)>,
+        /// Attribute or property name
+        name: String,
 
-        /// Select nth matching element (0-indexed, -1 for last)
-        #[arg(long, default_value = "0", allow_hyphen_values = true)]
-        nth: i32,
+        /// New value (with --property, JSON literals like true or 42 keep their type)
+        value: String,
 
-        /// Click element containing this text
+        /// Set the DOM property instead of the HTML attribute
         #[arg(long)]
-        text: Option<String>,
+        property: bool,
     },
+}
 
-    /// Type text into element
-    Type {
-        /// CSS selector (or use --focused)
-        selector: Option<String>,
+#[derive(Subcommand)]
+enum CssAction {
+    /// Inject a stylesheet (injecting the same file again replaces it)
+    Inject {
+        /// CSS file
+        file: PathBuf,
+    },
+
+    /// Override declarations for a selector, e.g. css set "h1" "color: red; margin: 0"
+    Set {
+        /// CSS selector
+        selector: String,
+
+        /// Declarations; each is made !important so it wins over page styles
+        declarations: String,
+    },
+
+    /// Remove everything DOMGuard injected
+    Clear,
+}
+
+#[derive(Subcommand)]
+enum InjectAction {
+    /// List persisted scripts
+    List,
+
+    /// Stop injecting a persisted script
+    Remove {
+        /// Script id from 'inject list'
+        #[arg(required_unless_present = "all")]
+        id: Option<u32>,
+
+        /// Remove every persisted script
+        #[arg(long, conflicts_with = "id")]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum NetworkAction {
+    /// Search headers and bodies of traffic captured by 'debug xhr'
+    Grep {
+        /// Text to find (a regular expression with --regex)
+        pattern: String,
+
+        /// Treat the pattern as a regular expression
+        #[arg(long)]
+        regex: bool,
+
+        /// Only calls made since the current page loaded
+        #[arg(long)]
+        since_navigation: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ThrottleAction {
+    /// Disable all throttling
+    Off,
+
+    /// Show the throttling DOMGuard left applied
+    Status,
+
+    /// Slow down CPU (rate = slowdown factor, e.g., 4 = 4x slower)
+    Cpu {
+        /// Slowdown rate (1 = normal, 4 = 4x slower)
+        rate: f64,
+    },
+
+    /// Simulate 3G network (1.6 Mbps, 300ms latency)
+    Network3g,
+
+    /// Simulate slow 3G network (400 Kbps, 2s latency)
+    NetworkSlow3g,
+
+    /// Simulate offline mode
+    NetworkOffline,
+
+    /// Custom network conditions
+    NetworkCustom {
+        /// Download speed in Kbps
+        #[arg(long)]
+        download: f64,
+
+        /// Upload speed in Kbps
+        #[arg(long)]
+        upload: f64,
+
+        /// Latency in milliseconds
+        #[arg(long)]
+        latency: f64,
+    },
+}
+
+#[derive(Subcommand)]
+enum TabAction {
+    /// List all open tabs
+    List,
+
+    /// Create a new tab
+    New {
+        /// URL to open (default: about:blank)
+        url: Option<String>,
+
+        /// Open a blocked site anyway (needs `[security] allow_block_override = true`)
+        #[arg(long)]
+        override_block: bool,
+
+        /// Open in a fresh browser context with its own cookies and storage
+        #[arg(long)]
+        incognito: bool,
+
+        /// Open in a new window
+        #[arg(long)]
+        window: bool,
+    },
+
+    /// Bring a tab to the front; later commands run against it
+    Switch {
+        /// Tab ID to switch to
+        #[arg(
+            required_unless_present = "url_contains",
+            conflicts_with = "url_contains"
+        )]
+        id: Option<String>,
+
+        /// Switch to the tab whose URL contains this text
+        #[arg(long)]
+        url_contains: Option<String>,
+    },
+
+    /// Open a tab's URL in a new tab
+    Duplicate {
+        /// Tab ID to duplicate
+        id: String,
+    },
+
+    /// Close a tab by ID
+    Close {
+        /// Tab ID to close
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ContextAction {
+    /// List browser contexts and their tabs
+    List,
+
+    /// Close a browser context and all of its tabs
+    Dispose {
+        /// Context ID (from 'contexts list' or 'tabs new --incognito')
+        #[arg(required_unless_present = "all")]
+        id: Option<String>,
+
+        /// Dispose every context except the default one
+        #[arg(long, conflicts_with = "id")]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum InteractSubcommand {
+    /// Click element or coordinates
+    Click {
+        /// CSS selector
+        selector: Option<String>,
+
+        /// Click at coordinates (x,y)
+        #[arg(long, value_parser = parse_coords)]
+        coords: Option<(f64, f64)>,
+
+        /// What --coords are measured from: viewport, page or element:<selector>
+        #[arg(long, default_value = "viewport", requires = "coords")]
+        space: crate::interact::CoordSpace,
+
+        /// Select nth matching element (0-indexed, -1 for last)
+        #[arg(long, default_value = "0", allow_hyphen_values = true)]
+        nth: i32,
+
+        /// Click element containing this text
+        #[arg(long)]
+        text: Option<String>,
+
+        /// Then wait for an element matching this selector (uses --timeout)
+        #[arg(long, value_name = "SELECTOR", conflicts_with_all = ["then_wait_url", "then_network_idle"])]
+        then_wait: Option<String>,
+
+        /// Then wait for the URL to contain this text
+        #[arg(long, value_name = "PATTERN", conflicts_with = "then_network_idle")]
+        then_wait_url: Option<String>,
+
+        /// Then wait until no requests have been in flight for 500ms
+        #[arg(long)]
+        then_network_idle: bool,
+    },
+
+    /// Type text into element
+    Type {
+        /// CSS selector (or use --focused)
+        selector: Option<String>,
 
         /// Text to type
         text: Option<String>,
@@ -329,6 +975,19 @@ enum InteractSubcommand {
         /// Type into currently focused element
         #[arg(long)]
         focused: bool,
+
+        /// Send real key events per character instead of setting the value at once
+        #[arg(long)]
+        keystrokes: bool,
+
+        /// Delay between keystrokes in milliseconds, varied ±50% (implies --keystrokes)
+        #[arg(long, default_value = "0")]
+        delay: u64,
+
+        /// Fire input events the way this framework expects: react, vue or angular
+        /// (detected by default)
+        #[arg(long, conflicts_with_all = ["keystrokes", "delay"])]
+        framework: Option<crate::framework::Framework>,
     },
 
     /// Press key or key sequence
@@ -341,6 +1000,14 @@ enum InteractSubcommand {
     Hover {
         /// CSS selector
         selector: String,
+
+        /// Screenshot the page while the hover is held (for menus that close on mouse-out)
+        #[arg(long, value_name = "PATH")]
+        capture: Option<PathBuf>,
+
+        /// Click this element while the hover is held, e.g. a menu item
+        #[arg(long, value_name = "SELECTOR")]
+        then_click: Option<String>,
     },
 
     /// Scroll page
@@ -364,6 +1031,22 @@ enum InteractSubcommand {
         /// Scroll to element (CSS selector)
         #[arg(long)]
         to: Option<String>,
+
+        /// Scroll inside this overflow container instead of the window
+        #[arg(long)]
+        within: Option<String>,
+
+        /// Keep scrolling until this element appears (infinite scroll)
+        #[arg(long, conflicts_with = "to")]
+        until: Option<String>,
+
+        /// Keep scrolling until this text appears (infinite scroll)
+        #[arg(long, conflicts_with = "to")]
+        until_text: Option<String>,
+
+        /// Maximum scrolls for --until / --until-text
+        #[arg(long, default_value = "20")]
+        max_scrolls: u32,
     },
 
     /// Capture screenshot
@@ -372,23 +1055,67 @@ enum InteractSubcommand {
         #[arg(long)]
         full: bool,
 
-        /// Capture specific element
-        #[arg(long)]
+        /// Build the full page from scrolled viewport tiles (for pages too tall for one capture)
+        #[arg(long, requires = "full", conflicts_with = "element")]
+        stitch: bool,
+
+        /// Capture only this element (its page rectangle as the capture clip)
+        #[arg(long, visible_alias = "clip-selector")]
         element: Option<String>,
 
-        /// Output file path
+        /// Output file, or a directory for a generated <host>_<timestamp> name
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        /// Image format: png, jpeg or webp (default: from --output's extension, else png)
+        // Same id as the global --format, so the value stays a string
+        #[arg(long)]
+        format: Option<String>,
+
+        /// JPEG/WebP quality, 0-100
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        quality: Option<u8>,
     },
 
     /// Navigate to URL
     Navigate {
         /// URL to navigate to
         url: String,
+
+        /// HTTP Basic auth credentials for the target site (user:pass)
+        #[arg(long, value_parser = crate::network::parse_basic_auth, conflicts_with = "bearer")]
+        basic_auth: Option<crate::network::Credential>,
+
+        /// Bearer token sent to the target site as an Authorization header
+        #[arg(long)]
+        bearer: Option<String>,
+
+        /// When navigation is done: load, domcontentloaded or networkidle
+        #[arg(long, default_value = "load")]
+        wait_until: crate::cdp::WaitUntil,
+
+        /// Referrer URL sent with the navigation request
+        #[arg(long)]
+        referrer: Option<String>,
+
+        /// Navigation timeout in milliseconds
+        #[arg(long, default_value = "30000")]
+        timeout: u64,
     },
 
     /// Go back in browser history
-    Back,
+    Back {
+        /// Number of history entries to go back
+        #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..))]
+        steps: u32,
+    },
+
+    /// Go forward in browser history
+    Forward {
+        /// Number of history entries to go forward
+        #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..))]
+        steps: u32,
+    },
 
     /// Refresh the page
     Refresh,
@@ -414,6 +1141,34 @@ enum InteractSubcommand {
         #[arg(long)]
         text_gone: Option<String>,
 
+        /// Wait for the URL to contain this text (e.g. after a redirect)
+        #[arg(long, conflicts_with_all = ["selector", "text", "text_gone", "url_matches", "title_contains"])]
+        url_contains: Option<String>,
+
+        /// Wait for the URL to match this regex
+        #[arg(long, conflicts_with_all = ["selector", "text", "text_gone", "title_contains"])]
+        url_matches: Option<regex::Regex>,
+
+        /// Wait for the page title to contain this text
+        #[arg(long, conflicts_with_all = ["selector", "text", "text_gone"])]
+        title_contains: Option<String>,
+
+        /// Wait for the element to be enabled (not disabled or aria-disabled)
+        #[arg(long, requires = "selector", conflicts_with_all = ["gone", "stable", "attr"])]
+        enabled: bool,
+
+        /// Wait for the element's bounding box to stay unchanged for MS (default 500)
+        #[arg(long, value_name = "MS", num_args = 0..=1, default_missing_value = "500", requires = "selector", conflicts_with_all = ["gone", "attr"])]
+        stable: Option<u64>,
+
+        /// Wait for an attribute to have a value, e.g. --attr aria-busy=false
+        #[arg(long, value_parser = parse_param, requires = "selector", conflicts_with = "gone")]
+        attr: Option<(String, String)>,
+
+        /// Wait for a client-side route change, e.g. after clicking a router link
+        #[arg(long, conflicts_with_all = ["selector", "visible", "gone", "text", "text_gone", "url_contains", "url_matches", "title_contains", "enabled", "stable", "attr"])]
+        spa_navigation: bool,
+
         /// Timeout in milliseconds
         #[arg(long, default_value = "5000")]
         timeout: u64,
@@ -436,6 +1191,72 @@ enum InteractSubcommand {
         /// Target coordinates (x,y)
         #[arg(long, value_parser = parse_coords)]
         to_coords: Option<(f64, f64)>,
+
+        /// What the coordinates are measured from: viewport, page or element:<selector>
+        #[arg(long, default_value = "viewport")]
+        space: crate::interact::CoordSpace,
+    },
+
+    /// Press, move along a path of coordinates and release (canvas and WebGL apps)
+    Gesture {
+        /// Points as "x,y;x,y;..." (at least two)
+        #[arg(long)]
+        path: crate::interact::GesturePath,
+
+        /// Time to travel the whole path in milliseconds
+        #[arg(long, default_value = "500")]
+        duration: u64,
+
+        /// Send touch events instead of mouse events
+        #[arg(long)]
+        touch: bool,
+    },
+
+    /// Tap element or coordinates with touch events (enables touch emulation)
+    Tap {
+        /// CSS selector
+        selector: Option<String>,
+
+        /// Tap at coordinates (x,y)
+        #[arg(long, value_parser = parse_coords)]
+        coords: Option<(f64, f64)>,
+    },
+
+    /// Swipe one finger between two points with touch events
+    Swipe {
+        /// Start coordinates (x,y)
+        #[arg(long, value_parser = parse_coords)]
+        from: (f64, f64),
+
+        /// End coordinates (x,y)
+        #[arg(long, value_parser = parse_coords)]
+        to: (f64, f64),
+
+        /// Duration in milliseconds
+        #[arg(long, default_value = "300")]
+        duration: u64,
+    },
+
+    /// Pinch two fingers around an element or point (--scale above 1 zooms in)
+    Pinch {
+        /// CSS selector
+        selector: Option<String>,
+
+        /// Pinch around coordinates (x,y)
+        #[arg(long, value_parser = parse_coords)]
+        coords: Option<(f64, f64)>,
+
+        /// Final finger distance as a multiple of the starting one
+        #[arg(long)]
+        scale: f64,
+
+        /// Starting distance between the fingers in pixels
+        #[arg(long, default_value = "100")]
+        distance: f64,
+
+        /// Duration in milliseconds
+        #[arg(long, default_value = "300")]
+        duration: u64,
     },
 
     /// Select option from dropdown
@@ -453,27 +1274,107 @@ enum InteractSubcommand {
         /// Select by index (0-based)
         #[arg(long)]
         by_index: bool,
+
+        /// Fire change events the way this framework expects: react, vue or angular
+        /// (detected by default)
+        #[arg(long)]
+        framework: Option<crate::framework::Framework>,
+    },
+
+    /// Set checkbox/radio state (no-op if already in that state)
+    Check {
+        /// CSS selector for checkbox or radio
+        selector: String,
+
+        /// Uncheck instead of check
+        #[arg(long)]
+        uncheck: bool,
+    },
+
+    /// Read, sort, page through and click into a table (<table> or ARIA grid)
+    Table {
+        /// CSS selector of the table (or an element containing it)
+        selector: String,
+
+        /// Find the first row with a cell matching this text and print its cells
+        #[arg(long, value_name = "TEXT")]
+        find_row: Option<String>,
+
+        /// Click this column of the found row (1-based number or header text)
+        #[arg(long, value_name = "COLUMN", requires = "find_row")]
+        click_cell: Option<String>,
+
+        /// Go to the next page; with --find-row, keep paging until the row shows up
+        #[arg(long)]
+        next_page: bool,
+
+        /// Click this column's header to sort by it first
+        #[arg(long, value_name = "COLUMN")]
+        sort: Option<String>,
+    },
+
+    /// Fill a date input or date picker: native value, typed in the page's format, or calendar clicks
+    Date {
+        /// CSS selector of the date input
+        selector: String,
+
+        /// Date to set (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_date)]
+        value: chrono::NaiveDate,
+    },
+
+    /// Type into a typeahead combobox (react-select, MUI Autocomplete, downshift) and pick an option
+    Combobox {
+        /// CSS selector of the combobox input or its wrapper
+        selector: String,
+
+        /// Option to pick; repeat for multi-selects
+        #[arg(long, required = true, value_name = "TEXT")]
+        choose: Vec<String>,
+
+        /// Create the entry when no option matches (the list's "Create" entry, or Enter)
+        #[arg(long)]
+        create_if_missing: bool,
     },
 
     /// Upload file(s) to file input
     Upload {
-        /// CSS selector for file input
-        selector: String,
+        /// CSS selector for file input (omitted with --via-chooser)
+        selector: Option<String>,
 
         /// File path(s) to upload
-        #[arg(required = true)]
         files: Vec<PathBuf>,
+
+        /// Click this element and answer the file chooser it opens instead
+        #[arg(long, value_name = "TRIGGER_SELECTOR")]
+        via_chooser: Option<String>,
     },
 
     /// Handle browser dialog (alert, confirm, prompt)
     Dialog {
         /// Accept the dialog (default: dismiss)
-        #[arg(long)]
+        #[arg(long, conflicts_with = "policy")]
         accept: bool,
 
         /// Text to enter for prompt dialogs
         #[arg(long)]
         text: Option<String>,
+
+        /// How long to wait for a dialog to appear in milliseconds
+        #[arg(long, default_value = "5000", conflicts_with = "policy")]
+        timeout: u64,
+
+        /// Answer all later dialogs automatically instead of waiting for one
+        /// (only in this --session, when one is given)
+        #[arg(long, value_parser = ["accept", "dismiss", "off"])]
+        policy: Option<String>,
+    },
+
+    /// Accept a cookie-consent banner (OneTrust, Cookiebot, Didomi, "Accept all" buttons, config rules)
+    DismissConsent {
+        /// How long to wait for a banner to appear in milliseconds
+        #[arg(long, default_value = "5000")]
+        timeout: u64,
     },
 
     /// Resize browser viewport
@@ -494,18 +1395,50 @@ enum InteractSubcommand {
         /// Landscape orientation
         #[arg(long)]
         landscape: bool,
-    },
 
-    // Anthropic Computer Use features
-    /// Move mouse cursor to coordinates (without clicking)
-    MouseMove {
-        /// Target coordinates (x,y)
-        #[arg(value_parser = parse_coords)]
-        coords: (f64, f64),
-    },
+        /// Paper size: a3, a4, a5, letter, legal, tabloid or WxH (e.g. 210x297mm, 8.5x11in)
+        #[arg(long)]
+        paper: Option<crate::cdp::PaperSize>,
 
-    /// Get current cursor position
-    CursorPosition,
+        /// Margins like CSS: 1, 2 or 4 comma-separated lengths (e.g. 1cm or 0.5in,1in)
+        #[arg(long)]
+        margin: Option<crate::cdp::Margins>,
+
+        /// Rendering scale, 0.1 to 2
+        #[arg(long, value_parser = parse_pdf_scale)]
+        scale: Option<f64>,
+
+        /// Header HTML template, or @file to read it from a file
+        #[arg(long)]
+        header_template: Option<String>,
+
+        /// Footer HTML template, or @file to read it from a file
+        #[arg(long)]
+        footer_template: Option<String>,
+
+        /// Pages to print, e.g. "1-5, 8"
+        #[arg(long)]
+        page_ranges: Option<String>,
+
+        /// Use the page's CSS @page size instead of --paper
+        #[arg(long)]
+        prefer_css_page_size: bool,
+    },
+
+    // Anthropic Computer Use features
+    /// Move mouse cursor to coordinates (without clicking)
+    MouseMove {
+        /// Target coordinates (x,y)
+        #[arg(value_parser = parse_coords)]
+        coords: (f64, f64),
+
+        /// What the coordinates are measured from: viewport, page or element:<selector>
+        #[arg(long, default_value = "viewport")]
+        space: crate::interact::CoordSpace,
+    },
+
+    /// Get current cursor position
+    CursorPosition,
 
     /// Hold a key for specified duration
     HoldKey {
@@ -517,6 +1450,17 @@ enum InteractSubcommand {
         duration: u64,
     },
 
+    /// Press Tab repeatedly and report the focus order (role, name, focus indicator per stop)
+    TabWalk {
+        /// Most Tab presses before stopping
+        #[arg(long, default_value = "50")]
+        max: usize,
+
+        /// Save a viewport screenshot at each stop
+        #[arg(long)]
+        screenshot_each: bool,
+    },
+
     /// Triple-click to select paragraph/block
     TripleClick {
         /// CSS selector
@@ -527,6 +1471,34 @@ enum InteractSubcommand {
         coords: Option<(f64, f64)>,
     },
 
+    /// Right-click element or coordinates (opens context menu)
+    RightClick {
+        /// CSS selector
+        selector: Option<String>,
+
+        /// Click at coordinates (x,y)
+        #[arg(long, value_parser = parse_coords)]
+        coords: Option<(f64, f64)>,
+
+        /// Report UI that appears after the click (e.g., the context menu)
+        #[arg(long)]
+        capture: bool,
+    },
+
+    /// Double-click element or coordinates
+    DoubleClick {
+        /// CSS selector
+        selector: Option<String>,
+
+        /// Click at coordinates (x,y)
+        #[arg(long, value_parser = parse_coords)]
+        coords: Option<(f64, f64)>,
+
+        /// Report UI that appears after the click (e.g., inline editors, selection)
+        #[arg(long)]
+        capture: bool,
+    },
+
     /// Press mouse button down (without releasing)
     MouseDown {
         /// Button to press: left, middle, right
@@ -547,9 +1519,21 @@ enum InteractSubcommand {
         #[arg(value_parser = parse_region)]
         region: (i32, i32, i32, i32),
 
-        /// Output file path
+        /// What the region's x,y are measured from: page, viewport or element:<selector>
+        #[arg(long, default_value = "page")]
+        space: crate::interact::CoordSpace,
+
+        /// Output file, or a directory for a generated <host>_<timestamp>_region name
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        /// Image format: png, jpeg or webp (default: from --output's extension, else png)
+        #[arg(long)]
+        format: Option<String>,
+
+        /// JPEG/WebP quality, 0-100
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        quality: Option<u8>,
     },
 
     /// Wait for specified duration (in milliseconds)
@@ -573,6 +1557,10 @@ enum SessionSubcommand {
         /// Optional session name
         #[arg(short, long)]
         name: Option<String>,
+
+        /// Capture a thumbnail screenshot after each recorded action
+        #[arg(long)]
+        screenshots: bool,
     },
 
     /// Stop the current recording
@@ -580,6 +1568,10 @@ enum SessionSubcommand {
         /// Delete screenshots after stopping
         #[arg(long)]
         cleanup: bool,
+
+        /// Turn off CPU and network throttling after stopping
+        #[arg(long)]
+        reset_throttle: bool,
     },
 
     /// Pause the current recording
@@ -591,8 +1583,20 @@ enum SessionSubcommand {
     /// Show current session status
     Status,
 
-    /// List all saved sessions
-    List,
+    /// List saved sessions, newest first
+    List {
+        /// Only sessions with this status: recording, paused, completed or failed
+        #[arg(long)]
+        status: Option<crate::session::SessionStatus>,
+
+        /// Only sessions whose name contains this (case-insensitive)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Show at most this many
+        #[arg(long)]
+        limit: Option<usize>,
+    },
 
     /// Show details of a specific session
     Show {
@@ -802,9 +1806,21 @@ enum WorkflowSubcommand {
         #[arg(long)]
         dry_run: bool,
 
+        /// With --dry-run, check each step's selector against the current page
+        #[arg(long, requires = "dry_run")]
+        live: bool,
+
         /// Delay between steps in milliseconds
         #[arg(long, default_value = "500")]
         delay: u64,
+
+        /// Mock rules file (TOML or JSON) applied for the duration of the run
+        #[arg(long)]
+        mocks: Option<PathBuf>,
+
+        /// Let navigate steps open blocked sites (needs `[security] allow_block_override = true`)
+        #[arg(long)]
+        override_block: bool,
     },
 
     /// Delete a workflow
@@ -813,6 +1829,55 @@ enum WorkflowSubcommand {
         id: String,
     },
 
+    /// Schedule a workflow to run from `workflow daemon`
+    Schedule {
+        /// Workflow ID or name
+        id: String,
+
+        /// Cron expression (minute hour day month weekday), e.g. "0 9 * * *"
+        #[arg(long, required_unless_present = "watch_url")]
+        cron: Option<String>,
+
+        /// Page to poll; the workflow runs when --when-text appears on it
+        #[arg(long, requires = "when_text")]
+        watch_url: Option<String>,
+
+        /// Text that triggers a run when it appears on --watch-url
+        #[arg(long, requires = "watch_url")]
+        when_text: Option<String>,
+
+        /// Seconds between --watch-url checks
+        #[arg(long, default_value = "300")]
+        interval: u64,
+
+        /// Parameters as key=value pairs
+        #[arg(short, long, value_parser = parse_param)]
+        param: Vec<(String, String)>,
+    },
+
+    /// Remove a workflow's schedule
+    Unschedule {
+        /// Workflow ID or name
+        id: String,
+    },
+
+    /// List scheduled workflows
+    Schedules,
+
+    /// Run scheduled workflows as they come due
+    Daemon {
+        /// Check every schedule once and exit
+        #[arg(long)]
+        once: bool,
+    },
+
+    /// Show history of workflow runs
+    Runs {
+        /// Maximum number of runs to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+
     /// Edit workflow in default editor
     Edit {
         /// Workflow ID
@@ -838,6 +1903,14 @@ enum TakeoverSubcommand {
         /// Expected outcome after user action
         #[arg(short, long)]
         expected: Option<String>,
+
+        /// Show a desktop notification (also via [takeover] notify_desktop)
+        #[arg(long)]
+        notify: bool,
+
+        /// POST the request to this webhook (also via [takeover] webhook_url)
+        #[arg(long)]
+        webhook: Option<String>,
     },
 
     /// Mark takeover as complete and resume automation
@@ -857,6 +1930,21 @@ enum TakeoverSubcommand {
     /// Check current takeover status
     Status,
 
+    /// Block until the takeover is done or the expected page state appears
+    Wait {
+        /// Maximum time to wait in seconds
+        #[arg(long, default_value = "300")]
+        timeout: u64,
+
+        /// Complete the takeover once this selector exists on the page
+        #[arg(long, conflicts_with = "until_url_matches")]
+        until_selector: Option<String>,
+
+        /// Complete the takeover once the page URL matches this regex
+        #[arg(long)]
+        until_url_matches: Option<String>,
+    },
+
     /// List takeover history
     History {
         /// Maximum number of entries to show
@@ -867,8 +1955,16 @@ enum TakeoverSubcommand {
 
 #[derive(Subcommand)]
 enum CorrectionSubcommand {
-    /// Show current self-correction configuration
-    Config,
+    /// Show (and optionally change) the self-correction configuration
+    Config {
+        /// Maximum recovery attempts per failed action
+        #[arg(long)]
+        max_retries: Option<u32>,
+
+        /// Base delay between retries in milliseconds
+        #[arg(long)]
+        base_delay: Option<u64>,
+    },
 
     /// Enable self-correction
     Enable,
@@ -909,6 +2005,335 @@ enum CorrectionSubcommand {
     },
 }
 
+#[derive(Subcommand)]
+enum ChromeSubcommand {
+    /// Start a Chrome container, wait until it's ready and point the config at it
+    Up {
+        /// Container image (Chrome flags are passed as its arguments)
+        #[arg(long, default_value = crate::container::DEFAULT_IMAGE)]
+        image: String,
+
+        /// Run Chrome headless (needed for images without a display)
+        #[arg(long)]
+        headless: bool,
+
+        /// Local port for the debugging endpoint (default: the configured Chrome port)
+        #[arg(long = "local-port")]
+        local_port: Option<u16>,
+
+        /// Container name
+        #[arg(long, default_value = crate::container::DEFAULT_NAME)]
+        name: String,
+
+        /// Seconds to wait for Chrome to become ready
+        #[arg(long, default_value = "30")]
+        wait: u64,
+    },
+
+    /// Stop and remove the Chrome container
+    Down {
+        /// Container name
+        #[arg(long, default_value = crate::container::DEFAULT_NAME)]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigSubcommand {
+    /// Print a setting, e.g. defaults.timeout_ms
+    Get {
+        /// Dotted key
+        key: String,
+    },
+
+    /// Change a setting, checked against the config schema
+    Set {
+        /// Dotted key, e.g. defaults.timeout_ms
+        key: String,
+
+        /// New value (lists as a,b or ["a", "b"])
+        value: String,
+    },
+
+    /// Print every setting
+    List,
+}
+
+#[derive(Subcommand)]
+enum SecretsSubcommand {
+    /// Store a secret (prompts for the value when it isn't given)
+    Set {
+        /// Secret name, referenced as ${secret:NAME}
+        name: String,
+
+        /// Secret value (read from stdin when omitted)
+        value: Option<String>,
+    },
+
+    /// List stored secret names
+    List,
+
+    /// Remove a secret
+    Rm {
+        /// Secret name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthSubcommand {
+    /// Run the domain's login workflow (`[login] workflow` in its site instructions),
+    /// check it worked and save cookies and storage to .domguard/auth/<domain>.json
+    Login {
+        /// Domain with site instructions (e.g., "example.com")
+        domain: String,
+
+        /// Workflow parameters as key=value pairs
+        #[arg(short, long, value_parser = parse_param)]
+        param: Vec<(String, String)>,
+    },
+
+    /// Put a saved login's cookies and storage back into the browser
+    Restore {
+        /// Domain saved with 'auth login'
+        domain: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum EventsSubcommand {
+    /// Send events to a URL while 'events daemon' runs
+    Subscribe {
+        /// Webhook URL (http:// or https://)
+        #[arg(long)]
+        url: String,
+
+        /// Events to send: navigation, console-error, dialog, download
+        #[arg(long, value_delimiter = ',', required = true)]
+        on: Vec<events::EventKind>,
+    },
+
+    /// List webhook subscriptions
+    List,
+
+    /// Remove a subscription
+    Unsubscribe {
+        /// Subscription id or URL
+        id: String,
+    },
+
+    /// Watch the browser and POST events to subscribed webhooks (runs until interrupted)
+    Daemon,
+}
+
+#[derive(Subcommand)]
+enum NetworkSubcommand {
+    /// Mock responses for requests matching URL patterns
+    Mock {
+        #[command(subcommand)]
+        action: MockAction,
+    },
+
+    /// Block requests by URL pattern or resource type (applied to every command)
+    Block {
+        /// URL patterns to block (e.g., "*.doubleclick.net*")
+        patterns: Vec<String>,
+
+        #[command(flatten)]
+        resources: ResourceFlags,
+    },
+
+    /// Remove blocked URL patterns or resource types
+    Unblock {
+        /// URL patterns to unblock
+        patterns: Vec<String>,
+
+        #[command(flatten)]
+        resources: ResourceFlags,
+
+        /// Remove all blocking
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Show blocked URL patterns and resource types
+    Blocked,
+
+    /// Extra request headers and user agent override (applied to every command)
+    Headers {
+        #[command(subcommand)]
+        action: HeadersAction,
+    },
+
+    /// Per-domain credentials used to answer auth challenges
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthAction {
+    /// Store credentials for a domain
+    Add {
+        /// Domain or pattern (e.g., "staging.example.com", "*.example.com")
+        domain: String,
+
+        /// HTTP Basic auth credentials (user:pass)
+        #[arg(long, value_parser = crate::network::parse_basic_auth, conflicts_with = "bearer", required_unless_present = "bearer")]
+        basic: Option<crate::network::Credential>,
+
+        /// Bearer token sent as an Authorization header
+        #[arg(long)]
+        bearer: Option<String>,
+    },
+
+    /// List stored credentials (secrets masked)
+    List,
+
+    /// Remove credentials for a domain
+    Remove {
+        /// Domain or pattern
+        domain: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum HeadersAction {
+    /// Set extra headers and/or the user agent
+    Set {
+        /// Header in "Name: value" format
+        #[arg(value_parser = crate::network::parse_header)]
+        headers: Vec<(String, String)>,
+
+        /// Override the browser user agent
+        #[arg(long)]
+        user_agent: Option<String>,
+    },
+
+    /// Remove extra headers by name and/or the user agent override
+    Unset {
+        /// Header names
+        names: Vec<String>,
+
+        /// Remove the user agent override
+        #[arg(long)]
+        user_agent: bool,
+    },
+
+    /// Show extra headers and user agent override
+    List,
+
+    /// Remove all extra headers and the user agent override
+    Clear,
+}
+
+#[derive(clap::Args)]
+struct ResourceFlags {
+    /// Images (png, jpg, gif, webp, svg, ...)
+    #[arg(long)]
+    images: bool,
+
+    /// Web fonts (woff, woff2, ttf, otf)
+    #[arg(long)]
+    fonts: bool,
+
+    /// Audio and video (mp4, webm, mp3, ...)
+    #[arg(long)]
+    media: bool,
+
+    /// CSS stylesheets
+    #[arg(long)]
+    stylesheets: bool,
+}
+
+impl ResourceFlags {
+    fn filters(&self) -> Vec<crate::network::ResourceFilter> {
+        use crate::network::ResourceFilter;
+
+        [
+            (self.images, ResourceFilter::Images),
+            (self.fonts, ResourceFilter::Fonts),
+            (self.media, ResourceFilter::Media),
+            (self.stylesheets, ResourceFilter::Stylesheets),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, filter)| enabled.then_some(filter))
+        .collect()
+    }
+}
+
+#[derive(Subcommand)]
+enum MockAction {
+    /// Add a mock rule
+    Add {
+        /// URL pattern (* and ? wildcards, matched against the full URL)
+        pattern: String,
+
+        /// Response status code
+        #[arg(long, default_value = "200")]
+        status: u16,
+
+        /// Response body
+        #[arg(long, conflicts_with = "body_file")]
+        body: Option<String>,
+
+        /// Read response body from file
+        #[arg(long)]
+        body_file: Option<PathBuf>,
+
+        /// Response header in "Name: value" format (repeatable)
+        #[arg(short = 'H', long = "header", value_parser = crate::network::parse_header)]
+        headers: Vec<(String, String)>,
+
+        /// Delay before responding in milliseconds
+        #[arg(long, default_value = "0")]
+        delay: u64,
+
+        /// Abort matching requests with a network error
+        #[arg(long)]
+        abort: bool,
+    },
+
+    /// List mock rules
+    List,
+
+    /// Remove a mock rule by its number in 'network mock list'
+    Remove {
+        /// Rule number (1-based)
+        index: usize,
+    },
+
+    /// Remove all mock rules
+    Clear,
+
+    /// Apply mock rules to the current page until stopped
+    Run {
+        /// Rules file (TOML or JSON) instead of saved rules
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// How long to keep mocking in milliseconds (0 = until interrupted)
+        #[arg(long, default_value = "0")]
+        duration: u64,
+    },
+}
+
+/// The script for `debug eval`: the expression or file, with `--arg` bindings
+fn eval_script(
+    expression: Option<&str>,
+    file: Option<&std::path::Path>,
+    args: &[(String, String)],
+) -> Result<String> {
+    let source = match (expression, file) {
+        (Some(expression), _) => expression.to_string(),
+        (None, Some(path)) => std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?,
+        (None, None) => anyhow::bail!("Provide an expression or --file"),
+    };
+    Ok(crate::debug::eval_source(&source, args))
+}
+
 fn parse_param(s: &str) -> Result<(String, String), String> {
     let parts: Vec<&str> = s.splitn(2, '=').collect();
     if parts.len() != 2 {
@@ -917,6 +2342,11 @@ fn parse_param(s: &str) -> Result<(String, String), String> {
     Ok((parts[0].to_string(), parts[1].to_string()))
 }
 
+fn parse_date(s: &str) -> Result<chrono::NaiveDate, String> {
+    chrono::NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date \"{}\" (expected YYYY-MM-DD)", s))
+}
+
 fn parse_region(s: &str) -> Result<(i32, i32, i32, i32), String> {
     let parts: Vec<&str> = s.split(',').collect();
     if parts.len() != 4 {
@@ -941,8 +2371,45 @@ fn parse_region(s: &str) -> Result<(i32, i32, i32, i32), String> {
     Ok((x, y, width, height))
 }
 
-fn parse_coords(s: &str) -> Result<(f64, f64), String> {
-    let parts: Vec<&str> = s.split(',').collect();
+fn parse_pdf_scale(s: &str) -> Result<f64, String> {
+    let scale = s
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid scale \"{}\"", s))?;
+    if !(0.1..=2.0).contains(&scale) {
+        return Err("Scale must be between 0.1 and 2".to_string());
+    }
+    Ok(scale)
+}
+
+fn parse_backoff(s: &str) -> Result<f64, String> {
+    let trimmed = s.trim();
+    let factor = trimmed
+        .strip_suffix(['x', 'X'])
+        .unwrap_or(trimmed)
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid backoff \"{}\" (e.g. 2x)", s))?;
+    if !(1.0..=10.0).contains(&factor) {
+        return Err("Backoff must be between 1x and 10x".to_string());
+    }
+    Ok(factor)
+}
+
+fn parse_slowdown(s: &str) -> Result<f64, String> {
+    let trimmed = s.trim();
+    let factor = trimmed
+        .strip_suffix(['x', 'X'])
+        .unwrap_or(trimmed)
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid slowdown \"{}\" (e.g. 10x)", s))?;
+    if !(1.0..=1000.0).contains(&factor) {
+        return Err("Slowdown must be between 1x and 1000x".to_string());
+    }
+    Ok(factor)
+}
+
+fn parse_coords(s: &str) -> Result<(f64, f64), String> {
+    let parts: Vec<&str> = s.split(',').collect();
     if parts.len() != 2 {
         return Err("Coordinates must be in format: x,y".to_string());
     }
@@ -957,39 +2424,389 @@ fn parse_coords(s: &str) -> Result<(f64, f64), String> {
     Ok((x, y))
 }
 
+/// The global --format, unless the command reads --format itself
+fn data_format(cli: &Cli) -> Option<crate::output::DataFormat> {
+    let owned = matches!(
+        &cli.command,
+        Commands::Session {
+            command: SessionSubcommand::Export { .. }
+        } | Commands::Interact {
+            command: InteractSubcommand::Screenshot { .. }
+                | InteractSubcommand::ScreenshotRegion { .. },
+            ..
+        }
+    );
+    let raw = cli.format.as_deref().filter(|_| !owned)?;
+    match raw.parse() {
+        Ok(format) => Some(format),
+        Err(e) => {
+            use clap::CommandFactory;
+            Cli::command()
+                .error(clap::error::ErrorKind::InvalidValue, e)
+                .exit()
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    let formatter = Formatter::new(cli.json);
+    let formatter = Formatter::new(cli.json)
+        .with_quiet(cli.quiet)
+        .with_format(data_format(&cli))
+        .with_ndjson(cli.ndjson)
+        .with_output_file(cli.output_file.clone());
+    let action = error_action(&cli.command);
+    if crate::telemetry::configured() {
+        crate::telemetry::init(&invocation_name());
+    }
 
     // Run the actual command and handle errors with proper formatting
     let result = run_command(cli, &formatter).await;
+    crate::telemetry::finish(result.as_ref().err().map(ToString::to_string).as_deref());
 
     if let Err(e) = &result {
+        if already_reported(e) {
+            std::process::exit(1);
+        }
         if formatter.is_json() {
-            let err_result: CommandResult<()> = CommandResult::error(e.to_string());
-            formatter.output_json(&err_result);
+            // Policy refusals carry their details for agents to act on
+            let data = if let Some(blocked) = e.downcast_ref::<crate::security::BlockedByPolicy>() {
+                Some(serde_json::json!({ "blocked_by_policy": blocked }))
+            } else if let Some(forbidden) =
+                e.downcast_ref::<crate::site_instructions::ForbiddenBySite>()
+            {
+                Some(serde_json::json!({ "forbidden_by_site": forbidden }))
+            } else if let Some(exhausted) = e.downcast_ref::<crate::retry::RetryExhausted>() {
+                Some(serde_json::json!({ "retry": exhausted }))
+            } else if let Some(timeout) = e.downcast_ref::<crate::cdp::CommandTimeout>() {
+                Some(serde_json::json!({ "command_timeout": timeout }))
+            } else {
+                e.downcast_ref::<crate::budget::BudgetExceeded>()
+                    .map(|exceeded| serde_json::json!({ "budget_exceeded": exceeded }))
+            };
+            formatter.output_json(&CommandResult {
+                data,
+                ..CommandResult::error(e.to_string())
+                    .with_error_details(crate::correction::describe_error(e, action))
+            });
         } else {
             formatter.error(&e.to_string());
         }
-        std::process::exit(1);
+        // 130 is what a shell reports for a command ended by SIGINT
+        std::process::exit(if e.is::<crate::interrupt::Interrupted>() {
+            130
+        } else {
+            1
+        });
+    }
+
+    result
+}
+
+/// Failures whose command already printed its result, so only the exit status is left
+fn already_reported(error: &anyhow::Error) -> bool {
+    use crate::{debug::NotFound, doctor::Unhealthy, perf::BudgetFailed, workflow::StepsNotReady};
+
+    error.is::<NotFound>()
+        || error.is::<BudgetFailed>()
+        || error.is::<StepsNotReady>()
+        || error.is::<Unhealthy>()
+}
+
+/// Run a command up to `policy.attempts` times, backing off between failures
+///
+/// Errors that a re-run won't fix (see `ErrorCode::retryable`) end the loop
+/// early unless `any_error` is set.
+async fn run_retry(
+    outer: &Cli,
+    policy: crate::retry::RetryPolicy,
+    any_error: bool,
+    args: &[String],
+    formatter: &Formatter,
+) -> Result<()> {
+    use crate::retry::{RetryAttempt, RetryExhausted};
+
+    // Clap consumes the parsed command, so each attempt parses it again
+    let parse = || -> Result<Cli> {
+        let mut inner =
+            Cli::try_parse_from(std::iter::once("domguard").chain(args.iter().map(String::as_str)))
+                .map_err(|e| anyhow::anyhow!("Invalid command to retry: {}", e))?;
+        inherit_globals(&mut inner, outer);
+        Ok(inner)
+    };
+    let first = parse()?;
+    if matches!(first.command, Commands::Retry { .. }) {
+        anyhow::bail!("retry can't run another retry");
+    }
+    let action = error_action(&first.command);
+
+    let mut next = Some(first);
+    let mut failures = Vec::new();
+    for attempt in 1..=policy.attempts {
+        let cli = match next.take() {
+            Some(cli) => cli,
+            None => parse()?,
+        };
+        let error = match Box::pin(run_command(cli, formatter)).await {
+            Ok(()) => {
+                if attempt > 1 {
+                    formatter.warning(&format!(
+                        "Succeeded on attempt {}/{}",
+                        attempt, policy.attempts
+                    ));
+                }
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+
+        let retryable = any_error || crate::correction::describe_error(&error, action).retryable;
+        let delay_ms =
+            (retryable && attempt < policy.attempts).then(|| policy.delay_after(attempt));
+        failures.push(RetryAttempt {
+            attempt,
+            error: error.to_string(),
+            retryable,
+            delay_ms,
+        });
+        let Some(delay_ms) = delay_ms else {
+            break;
+        };
+        formatter.warning(&format!(
+            "Attempt {}/{} failed: {}; retrying in {}ms",
+            attempt, policy.attempts, error, delay_ms
+        ));
+        tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+    }
+
+    Err(RetryExhausted {
+        attempts: failures,
+        max_attempts: policy.attempts,
+    }
+    .into())
+}
+
+/// The command `debug fps --during` measures, with the outer command's globals
+fn measured_command(outer: &Cli, during: &str) -> Result<Cli> {
+    let args = crate::fps::split_command(during)
+        .map_err(|e| anyhow::anyhow!("Invalid --during command: {}", e))?;
+    let mut inner =
+        Cli::try_parse_from(std::iter::once("domguard").chain(args.iter().map(String::as_str)))
+            .map_err(|e| anyhow::anyhow!("Invalid command to measure: {}", e))?;
+    if matches!(
+        inner.command,
+        Commands::Debug {
+            command: DebugSubcommand::Fps { .. }
+        }
+    ) {
+        anyhow::bail!("debug fps can't measure another debug fps");
+    }
+    inherit_globals(&mut inner, outer);
+    Ok(inner)
+}
+
+/// Run a command while the page records its frame rate and long tasks
+///
+/// The command runs in this process like a retried one, so the report
+/// follows its output. Its error, if any, is returned after the report.
+async fn run_fps(
+    cdp: &CdpConnection,
+    inner: Cli,
+    during: &str,
+    formatter: &Formatter,
+) -> Result<()> {
+    use crate::fps::{FpsReport, FrameSample, COLLECT_SCRIPT, MONITOR_SCRIPT};
+
+    cdp.evaluate(MONITOR_SCRIPT).await?;
+    let result = Box::pin(run_command(inner, formatter)).await;
+    let sample = cdp.evaluate(COLLECT_SCRIPT).await?;
+    if sample.is_null() {
+        result?;
+        anyhow::bail!("The page navigated during the command, so its frames weren't recorded");
     }
+    let report = FpsReport::from_sample(serde_json::from_value::<FrameSample>(sample)?);
 
+    if formatter.is_json() {
+        formatter.output_json(&serde_json::json!({
+            "command": during,
+            "success": result.is_ok(),
+            "fps": report,
+        }));
+        return result;
+    }
+    formatter.header(&format!("Frame Rate during '{}'", during));
+    if report.hidden {
+        formatter.warning("The tab was in the background, so the page drew no frames");
+    }
+    formatter.kv(
+        "Frames",
+        &format!(
+            "{} in {:.0}ms ({:.1} fps)",
+            report.frames, report.duration_ms, report.average_fps
+        ),
+    );
+    formatter.kv(
+        "Frame time",
+        &format!(
+            "p95 {:.1}ms, longest {:.1}ms",
+            report.p95_frame_ms, report.longest_frame_ms
+        ),
+    );
+    formatter.kv(
+        "Dropped frames",
+        &format!(
+            "{} at 60 Hz ({} over 50ms)",
+            report.dropped_frames, report.jank_frames
+        ),
+    );
+    formatter.kv(
+        "Long tasks",
+        &format!(
+            "{} ({:.0}ms blocking)",
+            report.long_tasks, report.blocking_ms
+        ),
+    );
+    for task in &report.longest_tasks {
+        formatter.item(&format!(
+            "{:.0}ms at +{:.0}ms{}",
+            task.duration_ms,
+            task.start_ms,
+            task.source
+                .as_deref()
+                .map(|source| format!(" ({})", source))
+                .unwrap_or_default()
+        ));
+    }
     result
 }
 
+/// Global options given to `retry` also apply to the command it runs
+fn inherit_globals(inner: &mut Cli, outer: &Cli) {
+    inner.json |= outer.json;
+    inner.record |= outer.record;
+    inner.no_mask |= outer.no_mask;
+    inner.allow_remote |= outer.allow_remote;
+    inner.quiet |= outer.quiet;
+    inner.ndjson |= outer.ndjson;
+    inner.profile = inner.profile.take().or_else(|| outer.profile.clone());
+    inner.host = inner.host.take().or_else(|| outer.host.clone());
+    inner.port = inner.port.or(outer.port);
+    inner.timeout = inner.timeout.or(outer.timeout);
+    inner.session = inner.session.take().or_else(|| outer.session.clone());
+    inner.output_file = inner
+        .output_file
+        .take()
+        .or_else(|| outer.output_file.clone());
+    inner.format = inner.format.take().or_else(|| outer.format.clone());
+    inner.browser = inner.browser.or(outer.browser);
+}
+
+/// Interact action a command performs, for the recovery suggestions in JSON errors
+/// Span name for this invocation, e.g. "domguard interact click"
+fn invocation_name() -> String {
+    use clap::CommandFactory;
+
+    let mut name = String::from("domguard");
+    if let Ok(matches) = Cli::command().try_get_matches_from(std::env::args_os()) {
+        let mut current = &matches;
+        while let Some((subcommand, sub_matches)) = current.subcommand() {
+            name.push(' ');
+            name.push_str(subcommand);
+            current = sub_matches;
+        }
+    }
+    name
+}
+
+fn error_action(command: &Commands) -> &'static str {
+    match command {
+        Commands::Interact { command, .. } => match command {
+            InteractSubcommand::Click { .. } => "click",
+            InteractSubcommand::Type { .. } => "type",
+            InteractSubcommand::Navigate { .. } => "navigate",
+            _ => "interact",
+        },
+        _ => "",
+    }
+}
+
+/// Run a command, within the global `--timeout` when one is given
+///
+/// A retry's attempts each get the full timeout rather than sharing one.
+/// Long-running commands aren't cut off from outside, which would drop them
+/// before they stop their session and remove their run marker; the deadline
+/// still bounds each of their waits, which then fail and unwind normally.
 async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
+    let cut_off =
+        !matches!(cli.command, Commands::Retry { .. }) && long_running(&cli.command).is_none();
+    match cli.timeout {
+        Some(timeout_ms) if cut_off => tokio::time::timeout(
+            std::time::Duration::from_millis(timeout_ms),
+            execute_command(cli, formatter),
+        )
+        .await
+        .unwrap_or_else(|_| Err(crate::cdp::CommandTimeout { timeout_ms }.into())),
+        _ => execute_command(cli, formatter).await,
+    }
+}
+
+async fn execute_command(cli: Cli, formatter: &Formatter) -> Result<()> {
     // Handle init command specially (doesn't need Chrome connection)
     if matches!(cli.command, Commands::Init) {
         return handle_init(formatter);
     }
 
-    // Load config
-    let mut config = Config::load()?;
+    // The server runs domguard for each call, which loads the config then
+    if let Commands::Serve { grpc } = &cli.command {
+        let options = serve::ServeOptions {
+            profile: cli.profile.clone(),
+            host: cli.host.clone(),
+            port: cli.port,
+            allow_remote: cli.allow_remote,
+        };
+        formatter.success(&format!("Serving gRPC on {}", grpc));
+        formatter.hint("Press Ctrl+C to stop");
+        return serve::run(*grpc, &options);
+    }
+
+    // Each attempt of a retried command loads its own config and connection
+    if let Commands::Retry {
+        attempts,
+        backoff,
+        delay,
+        max_delay,
+        any_error,
+        command,
+    } = &cli.command
+    {
+        let policy = crate::retry::RetryPolicy {
+            attempts: *attempts,
+            delay_ms: *delay,
+            backoff: *backoff,
+            max_delay_ms: *max_delay,
+        };
+        return run_retry(&cli, policy, *any_error, command, formatter).await;
+    }
+
+    // Parsed before the globals below are taken from `cli`
+    let measured = match &cli.command {
+        Commands::Debug {
+            command: DebugSubcommand::Fps { during },
+        } => Some(measured_command(&cli, during)?),
+        _ => None,
+    };
+
+    // Load config (doctor reports a broken one instead of failing on it)
+    let (mut config, config_error) = match Config::load_profile(cli.profile.as_deref()) {
+        Ok(config) => (config, None),
+        Err(e) if matches!(cli.command, Commands::Doctor) => (Config::default(), Some(e)),
+        Err(e) => return Err(e),
+    };
 
     // Override config with CLI args
-    if let Some(host) = cli.host {
-        config.chrome.host = host;
+    if let Some(host) = &cli.host {
+        config.chrome.host.clone_from(host);
     }
     if let Some(port) = cli.port {
         config.chrome.port = port;
@@ -997,46 +2814,307 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
     if let Some(timeout) = cli.timeout {
         config.defaults.timeout_ms = timeout;
     }
+    if cli.no_mask {
+        config.masking.enabled = false;
+    }
+    if let Some(browser) = cli.browser {
+        config.chrome.browser = browser;
+    }
+    if cli.allow_remote {
+        config.chrome.allow_remote = true;
+    }
+    if matches!(
+        cli.command,
+        Commands::Interact {
+            follow_popups: true,
+            ..
+        }
+    ) {
+        config.defaults.follow_popups = true;
+    }
+    if let Some(token) = &config.chrome.token {
+        config.chrome.token = Some(crate::secrets::expand_placeholders(
+            token,
+            &std::collections::BTreeMap::new(),
+        )?);
+    }
+    if !config.is_localhost() && config.chrome.allow_remote && !config.chrome.tls {
+        formatter.warning(&format!(
+            "Connecting to {} without TLS; CDP traffic is unencrypted (see 'domguard tunnel')",
+            config.chrome.host
+        ));
+    }
 
     // Connect to Chrome
     let mut cdp = CdpConnection::new(config.clone());
+    cdp.set_deadline(
+        cli.timeout
+            .map(|ms| std::time::Instant::now() + std::time::Duration::from_millis(ms)),
+    );
+
+    // Clean up after earlier commands that died, and let this one be cleaned up after
+    if !matches!(cli.command, Commands::Recover) {
+        for recovery in crate::interrupt::recover_interrupted(&mut cdp).await {
+            print_recovery(&recovery, formatter);
+        }
+    }
+    let run = long_running(&cli.command)
+        .and_then(|name| begin_run(name, cli.session.as_deref(), formatter));
+
+    let command = dispatch(&cli, &mut cdp, &config, config_error, measured, formatter);
+    let Some(run) = run else {
+        return command.await;
+    };
+    // Ctrl-C stops a long-running command here, so it is cleaned up after while
+    // the process is still alive; commands that handle Ctrl-C themselves finish first
+    tokio::select! {
+        biased;
+        result = command => result,
+        _ = tokio::signal::ctrl_c() => {
+            let name = run.marker().command.clone();
+            print_recovery(&run.recover(&mut cdp).await, formatter);
+            Err(crate::interrupt::Interrupted { command: name }.into())
+        }
+    }
+}
 
+/// Run the command itself, once the config is loaded and Chrome is set up
+async fn dispatch(
+    cli: &Cli,
+    cdp: &mut CdpConnection,
+    config: &Config,
+    config_error: Option<anyhow::Error>,
+    measured: Option<Cli>,
+    formatter: &Formatter,
+) -> Result<()> {
     match &cli.command {
-        Commands::Status => handle_status(&mut cdp, formatter).await,
+        Commands::Status { verbose } => handle_status(cdp, config, *verbose, formatter).await,
+        Commands::Inspire {
+            action: Some(action),
+            ..
+        } => handle_inspire_library(config, action, formatter),
         Commands::Inspire {
+            action: None,
             url,
             component,
             save,
+            tokens,
+            export,
+            responsive,
+            widths,
         } => {
+            let widths = widths
+                .as_deref()
+                .unwrap_or(&config.inspire.responsive_widths);
+            if *responsive && (widths.is_empty() || widths.contains(&0)) {
+                anyhow::bail!("--responsive needs at least one non-zero viewport width");
+            }
+            let url = url.as_deref().unwrap_or_default();
             cdp.connect().await?;
-            inspire::run_inspire(
-                &cdp,
-                &config,
-                url,
-                component.as_deref(),
-                save.as_deref(),
-                formatter,
-            )
-            .await
+            crate::network::apply_settings(cdp).await?;
+            crate::debug::InitScripts::apply(cdp).await?;
+            let options = inspire::InspireOptions {
+                component: component.as_deref(),
+                save_name: save.as_deref(),
+                tokens: *tokens,
+                export: export.as_deref(),
+                responsive: responsive.then_some(widths),
+            };
+            inspire::run_inspire(cdp, config, url, &options, formatter).await
         }
         Commands::Debug { command } => {
+            // Managing persisted init scripts only touches .domguard
+            if let DebugSubcommand::Inject {
+                action: Some(action),
+                ..
+            } = command
+            {
+                return match action {
+                    InjectAction::List => debug::list_init_scripts(formatter),
+                    InjectAction::Remove { id, .. } => debug::remove_init_scripts(*id, formatter),
+                };
+            }
+            if let DebugSubcommand::Throttle {
+                mode: ThrottleAction::Status,
+            } = command
+            {
+                return debug::throttle_status(formatter);
+            }
+            // Searching captured traffic only needs the page for --since-navigation
+            if let DebugSubcommand::Network {
+                action:
+                    Some(NetworkAction::Grep {
+                        pattern,
+                        regex,
+                        since_navigation,
+                    }),
+                ..
+            } = command
+            {
+                if *since_navigation {
+                    cdp.connect().await?;
+                }
+                return debug::grep_traffic(cdp, pattern, *regex, *since_navigation, formatter)
+                    .await;
+            }
+
+            if config.chrome.browser == crate::backend::BackendKind::Firefox {
+                let DebugSubcommand::Eval {
+                    expression,
+                    file,
+                    args,
+                    await_promise,
+                    world,
+                    return_by_ref,
+                    this_ref,
+                    ..
+                } = command
+                else {
+                    anyhow::bail!("Only 'debug eval' is supported by the firefox backend");
+                };
+                if *await_promise
+                    || *world != crate::debug::EvalWorld::Main
+                    || *return_by_ref
+                    || this_ref.is_some()
+                {
+                    anyhow::bail!(
+                        "--await, --world, --return-by-ref and --this need the chrome backend"
+                    );
+                }
+                let script = eval_script(expression.as_deref(), file.as_deref(), args)?;
+                let backend = crate::bidi::FirefoxBackend::connect(config, cdp.deadline()).await?;
+                let masker = crate::output::Masker::new(&config.masking)?;
+                return crate::debug::debug_eval(&backend, &script, &masker, formatter).await;
+            }
+
             cdp.connect().await?;
+            if let (DebugSubcommand::Fps { during }, Some(inner)) = (command, measured) {
+                return run_fps(cdp, inner, during, formatter).await;
+            }
             let cmd = match command {
                 DebugSubcommand::Dom { selector } => DebugCommand::Dom {
                     selector: selector.clone(),
                 },
-                DebugSubcommand::Styles { selector } => DebugCommand::Styles {
+                DebugSubcommand::Styles {
+                    selector,
+                    matched,
+                    box_model,
+                } => DebugCommand::Styles {
                     selector: selector.clone(),
+                    matched: *matched,
+                    box_model: *box_model,
+                },
+                DebugSubcommand::Css { action } => DebugCommand::Css {
+                    action: match action {
+                        CssAction::Inject { file } => debug::CssCommand::Inject {
+                            name: file
+                                .file_name()
+                                .map_or_else(|| "style.css".into(), |name| name.to_string_lossy())
+                                .into_owned(),
+                            css: std::fs::read_to_string(file).map_err(|e| {
+                                anyhow::anyhow!("Failed to read {}: {}", file.display(), e)
+                            })?,
+                        },
+                        CssAction::Set {
+                            selector,
+                            declarations,
+                        } => debug::CssCommand::Set {
+                            selector: selector.clone(),
+                            declarations: declarations.clone(),
+                        },
+                        CssAction::Clear => debug::CssCommand::Clear,
+                    },
                 },
-                DebugSubcommand::Console { follow, filter } => DebugCommand::Console {
+                DebugSubcommand::Animations { disable, slow, .. } => DebugCommand::Animations {
+                    mode: match (disable, slow) {
+                        (true, _) => debug::AnimationMode::Disable,
+                        (false, Some(factor)) => debug::AnimationMode::Slow(*factor),
+                        (false, None) => debug::AnimationMode::Enable,
+                    },
+                },
+                DebugSubcommand::Inject {
+                    script, persist, ..
+                } => {
+                    let path = script
+                        .as_deref()
+                        .unwrap_or_else(|| std::path::Path::new(""));
+                    DebugCommand::Inject {
+                        name: path.file_name().map_or_else(
+                            || path.display().to_string(),
+                            |name| name.to_string_lossy().into_owned(),
+                        ),
+                        source: std::fs::read_to_string(path).map_err(|e| {
+                            anyhow::anyhow!("Failed to read {}: {}", path.display(), e)
+                        })?,
+                        persist: *persist,
+                    }
+                }
+                DebugSubcommand::Console {
+                    follow,
+                    filter,
+                    duration,
+                    level,
+                } => DebugCommand::Console {
                     follow: *follow,
                     filter: filter.clone(),
+                    duration_ms: *duration,
+                    levels: level.clone(),
+                },
+                DebugSubcommand::Exceptions {
+                    pause,
+                    caught,
+                    capture,
+                    output,
+                    duration,
+                } => DebugCommand::Exceptions {
+                    watch: crate::cdp::ExceptionWatch {
+                        pause: *pause,
+                        caught: *caught,
+                        capture: *capture,
+                        duration_ms: *duration,
+                    },
+                    output: output.clone(),
                 },
-                DebugSubcommand::Network { filter } => DebugCommand::Network {
+                DebugSubcommand::Network { filter, .. } => DebugCommand::Network {
                     filter: filter.clone(),
                 },
-                DebugSubcommand::Eval { expression } => DebugCommand::Eval {
-                    expression: expression.clone(),
+                DebugSubcommand::Xhr {
+                    follow,
+                    url_filter,
+                    method,
+                    max_body,
+                    duration,
+                    decode_graphql,
+                } => DebugCommand::Xhr {
+                    watch: crate::cdp::XhrWatch {
+                        url_filter: url_filter.clone(),
+                        method: method.clone(),
+                        max_body_bytes: *max_body,
+                        duration_ms: *duration,
+                    },
+                    follow: *follow,
+                    decode_graphql: *decode_graphql,
+                },
+                DebugSubcommand::Eval {
+                    expression,
+                    file,
+                    args,
+                    await_promise,
+                    world,
+                    depth,
+                    return_by_ref,
+                    this_ref,
+                } => DebugCommand::Eval {
+                    expression: crate::debug::ref_script(
+                        &eval_script(expression.as_deref(), file.as_deref(), args)?,
+                        *return_by_ref,
+                        this_ref.as_deref(),
+                    ),
+                    options: crate::cdp::EvalOptions {
+                        isolated: *world == crate::debug::EvalWorld::Isolated,
+                        top_level_await: *await_promise,
+                        depth: *depth,
+                    },
                 },
                 DebugSubcommand::Storage => DebugCommand::Storage,
                 DebugSubcommand::Cookies => DebugCommand::Cookies,
@@ -1046,20 +3124,99 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
                 DebugSubcommand::Tabs { action } => {
                     let tab_action = match action {
                         TabAction::List => debug::TabCommand::List,
-                        TabAction::New { url } => debug::TabCommand::New { url: url.clone() },
-                        TabAction::Switch { id } => debug::TabCommand::Switch { id: id.clone() },
+                        TabAction::New {
+                            url,
+                            override_block,
+                            incognito,
+                            window,
+                        } => {
+                            if let Some(url) = url {
+                                crate::security::enforce_site_policy(
+                                    config,
+                                    "debug tabs new",
+                                    url,
+                                    *override_block,
+                                )?;
+                            }
+                            debug::TabCommand::New {
+                                url: url.clone(),
+                                incognito: *incognito,
+                                window: *window,
+                            }
+                        }
+                        TabAction::Switch { id: Some(id), .. } => {
+                            debug::TabCommand::Switch { id: id.clone() }
+                        }
+                        TabAction::Switch { url_contains, .. } => debug::TabCommand::SwitchUrl {
+                            pattern: url_contains.clone().unwrap_or_default(),
+                        },
+                        TabAction::Duplicate { id } => {
+                            debug::TabCommand::Duplicate { id: id.clone() }
+                        }
                         TabAction::Close { id } => debug::TabCommand::Close { id: id.clone() },
                     };
                     DebugCommand::Tabs { action: tab_action }
                 }
+                DebugSubcommand::Contexts { action } => DebugCommand::Contexts {
+                    action: match action {
+                        ContextAction::List => debug::ContextCommand::List,
+                        ContextAction::Dispose { id, .. } => {
+                            debug::ContextCommand::Dispose { id: id.clone() }
+                        }
+                    },
+                },
+                DebugSubcommand::History => DebugCommand::History,
                 // Chrome DevTools MCP features
                 DebugSubcommand::Performance => DebugCommand::Performance,
+                DebugSubcommand::PerfAssert {
+                    lcp_max,
+                    fcp_max,
+                    ttfb_max,
+                    cls_max,
+                    transfer_max,
+                    requests_max,
+                } => {
+                    let budget = perf::PerfBudget {
+                        lcp_max_ms: *lcp_max,
+                        fcp_max_ms: *fcp_max,
+                        ttfb_max_ms: *ttfb_max,
+                        cls_max: *cls_max,
+                        transfer_max_bytes: *transfer_max,
+                        requests_max: *requests_max,
+                    };
+                    if budget.is_empty() {
+                        anyhow::bail!(
+                            "Give at least one limit (--lcp-max, --fcp-max, --ttfb-max, --cls-max, --transfer-max or --requests-max)"
+                        );
+                    }
+                    DebugCommand::PerfAssert { budget }
+                }
+                DebugSubcommand::Fps { .. } => unreachable!("handled above"),
+                DebugSubcommand::Heap {
+                    compare, gc, top, ..
+                } => DebugCommand::Heap {
+                    compare: compare.clone(),
+                    gc: *gc,
+                    top: *top,
+                },
+                DebugSubcommand::Readiness => DebugCommand::Readiness,
+                DebugSubcommand::Framework => DebugCommand::Framework,
+                DebugSubcommand::Canvas {
+                    selector,
+                    export,
+                    output,
+                } => DebugCommand::Canvas {
+                    selector: selector.clone(),
+                    format: *export,
+                    output: output.clone(),
+                },
                 DebugSubcommand::Snapshot { output } => DebugCommand::Snapshot {
                     output: output.clone(),
                 },
                 DebugSubcommand::Throttle { mode } => {
                     let throttle_mode = match mode {
                         ThrottleAction::Off => debug::ThrottleMode::Off,
+                        ThrottleAction::Status => unreachable!("handled above"),
                         ThrottleAction::Cpu { rate } => debug::ThrottleMode::Cpu { rate: *rate },
                         ThrottleAction::Network3g => debug::ThrottleMode::Network3g,
                         ThrottleAction::NetworkSlow3g => debug::ThrottleMode::NetworkSlow3g,
@@ -1095,10 +3252,97 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
                 },
                 DebugSubcommand::ClearHighlights => DebugCommand::ClearHighlights,
                 DebugSubcommand::Captcha => DebugCommand::Captcha,
+                DebugSubcommand::Overlays { min_coverage } => DebugCommand::Overlays {
+                    min_coverage: f64::from(*min_coverage) / 100.0,
+                },
+                DebugSubcommand::Attr {
+                    action,
+                    selector,
+                    name,
+                } => {
+                    let attr_action = match action {
+                        Some(AttrAction::Set {
+                            selector,
+                            name,
+                            value,
+                            property,
+                        }) => debug::AttrCommand::Set {
+                            selector: selector.clone(),
+                            name: name.clone(),
+                            value: value.clone(),
+                            property: *property,
+                        },
+                        None => debug::AttrCommand::Get {
+                            selector: selector.clone().unwrap_or_default(),
+                            name: name.clone(),
+                        },
+                    };
+                    DebugCommand::Attr {
+                        action: attr_action,
+                    }
+                }
+                DebugSubcommand::Text {
+                    selector,
+                    all,
+                    inner,
+                    value,
+                    trimmed,
+                } => DebugCommand::Text {
+                    selector: selector.clone(),
+                    all: *all,
+                    mode: if *inner {
+                        debug::TextMode::Inner
+                    } else if *value {
+                        debug::TextMode::Value
+                    } else if *trimmed {
+                        debug::TextMode::Trimmed
+                    } else {
+                        debug::TextMode::Content
+                    },
+                },
+                DebugSubcommand::Count { selector } => DebugCommand::Count {
+                    selector: selector.clone(),
+                    exists: false,
+                },
+                DebugSubcommand::Exists { selector } => DebugCommand::Count {
+                    selector: selector.clone(),
+                    exists: true,
+                },
+                DebugSubcommand::Find {
+                    text,
+                    attr,
+                    tag,
+                    visible_only,
+                    limit,
+                } => DebugCommand::Find {
+                    query: debug::FindQuery {
+                        text: text.clone(),
+                        attr: attr.clone(),
+                        tag: tag.clone(),
+                        visible_only: *visible_only,
+                        limit: *limit,
+                    },
+                },
+                DebugSubcommand::DomDiff {
+                    before,
+                    baseline_id,
+                } => DebugCommand::DomDiff {
+                    baseline: match (before, baseline_id) {
+                        (Some(path), _) => debug::DiffBaseline::File(path.clone()),
+                        (None, Some(id)) => debug::DiffBaseline::Id(id.clone()),
+                        (None, None) => anyhow::bail!("Provide --before or --baseline-id"),
+                    },
+                },
             };
-            debug::run_debug(&cdp, cmd, formatter).await
+            debug::run_debug(cdp, config, cmd, formatter).await
         }
-        Commands::Interact { command } => {
+        Commands::Interact {
+            command,
+            confirm_sensitive,
+            override_block,
+            coords_from_screenshot,
+            ..
+        } => {
             // Handle cleanup command separately (doesn't need CDP)
             if let InteractSubcommand::Cleanup { dry_run } = command {
                 let screenshots_dir = Config::find_domguard_dir()
@@ -1165,6 +3409,16 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
                 return Ok(());
             }
 
+            // Setting the dialog policy only touches .domguard
+            if let InteractSubcommand::Dialog {
+                policy: Some(policy),
+                text,
+                ..
+            } = command
+            {
+                return set_dialog_policy(policy, text.clone(), cli.session.as_deref(), formatter);
+            }
+
             // Early validation for commands that require arguments
             // This prevents unnecessary CDP connection attempts when args are missing
             match command {
@@ -1181,22 +3435,51 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
                 {
                     anyhow::bail!("Type requires TEXT argument or --focused flag");
                 }
+                InteractSubcommand::RightClick {
+                    selector, coords, ..
+                }
+                | InteractSubcommand::DoubleClick {
+                    selector, coords, ..
+                } if selector.is_none() && coords.is_none() => {
+                    anyhow::bail!("Requires SELECTOR or --coords");
+                }
+                InteractSubcommand::Upload {
+                    selector,
+                    files,
+                    via_chooser,
+                } if selector.is_none() || (via_chooser.is_none() && files.is_empty()) => {
+                    anyhow::bail!("Upload requires SELECTOR and FILES, or --via-chooser and FILES");
+                }
                 _ => {}
             }
 
-            cdp.connect().await?;
+            let overrides = interact::GuardOverrides {
+                override_block: *override_block,
+                confirm_sensitive: *confirm_sensitive,
+            };
+            if config.chrome.browser == crate::backend::BackendKind::Firefox {
+                return run_firefox_interact(config, command, overrides, cdp.deadline(), formatter)
+                    .await;
+            }
 
             // Build action info for session recording
             let (cmd_name, selector, args) = match command {
                 InteractSubcommand::Click {
                     selector,
                     coords,
+                    space,
                     nth,
                     text,
+                    ..
                 } => (
                     "click",
                     selector.clone(),
-                    serde_json::json!({ "coords": coords, "nth": nth, "text": text }),
+                    serde_json::json!({
+                        "coords": coords,
+                        "space": space.to_string(),
+                        "nth": nth,
+                        "text": text
+                    }),
                 ),
                 InteractSubcommand::Type { selector, text, .. } => (
                     "type",
@@ -1206,28 +3489,95 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
                 InteractSubcommand::Key { keys } => {
                     ("key", None, serde_json::json!({ "keys": keys }))
                 }
-                InteractSubcommand::Hover { selector } => {
-                    ("hover", Some(selector.clone()), serde_json::json!({}))
-                }
-                InteractSubcommand::Navigate { url } => {
+                InteractSubcommand::Hover {
+                    selector,
+                    capture,
+                    then_click,
+                } => (
+                    "hover",
+                    Some(selector.clone()),
+                    serde_json::json!({ "capture": capture, "then_click": then_click }),
+                ),
+                InteractSubcommand::Navigate { url, .. } => {
                     ("navigate", None, serde_json::json!({ "url": url }))
                 }
                 InteractSubcommand::Screenshot { .. } => {
                     ("screenshot", None, serde_json::json!({}))
                 }
-                InteractSubcommand::Scroll { to, .. } => {
-                    ("scroll", to.clone(), serde_json::json!({}))
+                InteractSubcommand::Scroll {
+                    to,
+                    within,
+                    until,
+                    until_text,
+                    ..
+                } => (
+                    "scroll",
+                    to.clone(),
+                    serde_json::json!({ "within": within, "until": until, "until_text": until_text }),
+                ),
+                InteractSubcommand::Back { steps } => {
+                    ("back", None, serde_json::json!({ "steps": steps }))
+                }
+                InteractSubcommand::Forward { steps } => {
+                    ("forward", None, serde_json::json!({ "steps": steps }))
                 }
-                InteractSubcommand::Back => ("back", None, serde_json::json!({})),
                 InteractSubcommand::Refresh => ("refresh", None, serde_json::json!({})),
                 InteractSubcommand::Wait { selector, text, .. } => (
                     "wait",
                     selector.clone(),
                     serde_json::json!({ "text": text }),
                 ),
-                InteractSubcommand::Drag { from, to, .. } => {
-                    ("drag", from.clone(), serde_json::json!({ "to": to }))
-                }
+                InteractSubcommand::Drag {
+                    from,
+                    to,
+                    from_coords,
+                    to_coords,
+                    space,
+                } => (
+                    "drag",
+                    from.clone(),
+                    serde_json::json!({
+                        "to": to,
+                        "from_coords": from_coords,
+                        "to_coords": to_coords,
+                        "space": space.to_string()
+                    }),
+                ),
+                InteractSubcommand::Gesture {
+                    path,
+                    duration,
+                    touch,
+                } => (
+                    "gesture",
+                    None,
+                    serde_json::json!({ "path": path.0, "duration": duration, "touch": touch }),
+                ),
+                InteractSubcommand::Tap { selector, coords } => (
+                    "tap",
+                    selector.clone(),
+                    serde_json::json!({ "coords": coords }),
+                ),
+                InteractSubcommand::Swipe { from, to, duration } => (
+                    "swipe",
+                    None,
+                    serde_json::json!({ "from": from, "to": to, "duration": duration }),
+                ),
+                InteractSubcommand::Pinch {
+                    selector,
+                    coords,
+                    scale,
+                    distance,
+                    duration,
+                } => (
+                    "pinch",
+                    selector.clone(),
+                    serde_json::json!({
+                        "coords": coords,
+                        "scale": scale,
+                        "distance": distance,
+                        "duration": duration
+                    }),
+                ),
                 InteractSubcommand::Select {
                     selector, value, ..
                 } => (
@@ -1235,25 +3585,71 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
                     Some(selector.clone()),
                     serde_json::json!({ "value": value }),
                 ),
-                InteractSubcommand::Upload { selector, files } => (
-                    "upload",
+                InteractSubcommand::Check { selector, uncheck } => (
+                    "check",
+                    Some(selector.clone()),
+                    serde_json::json!({ "checked": !uncheck }),
+                ),
+                InteractSubcommand::Table {
+                    selector,
+                    find_row,
+                    click_cell,
+                    next_page,
+                    sort,
+                } => (
+                    "table",
+                    Some(selector.clone()),
+                    serde_json::json!({
+                        "find_row": find_row,
+                        "click_cell": click_cell,
+                        "next_page": next_page,
+                        "sort": sort,
+                    }),
+                ),
+                InteractSubcommand::Date { selector, value } => (
+                    "date",
+                    Some(selector.clone()),
+                    serde_json::json!({ "value": value }),
+                ),
+                InteractSubcommand::Combobox {
+                    selector,
+                    choose,
+                    create_if_missing,
+                } => (
+                    "combobox",
                     Some(selector.clone()),
-                    serde_json::json!({ "files": files }),
+                    serde_json::json!({ "choose": choose, "create_if_missing": create_if_missing }),
+                ),
+                InteractSubcommand::Upload {
+                    selector,
+                    files,
+                    via_chooser,
+                } => (
+                    "upload",
+                    via_chooser.clone().or_else(|| selector.clone()),
+                    serde_json::json!({ "files": files, "via_chooser": via_chooser.is_some() }),
                 ),
-                InteractSubcommand::Dialog { accept, text } => (
+                InteractSubcommand::Dialog { accept, text, .. } => (
                     "dialog",
                     None,
                     serde_json::json!({ "accept": accept, "text": text }),
                 ),
+                InteractSubcommand::DismissConsent { timeout } => (
+                    "dismiss_consent",
+                    None,
+                    serde_json::json!({ "timeout": timeout }),
+                ),
                 InteractSubcommand::Resize { width, height } => (
                     "resize",
                     None,
                     serde_json::json!({ "width": width, "height": height }),
                 ),
                 InteractSubcommand::Pdf { .. } => ("pdf", None, serde_json::json!({})),
-                InteractSubcommand::MouseMove { coords } => {
-                    ("mouse_move", None, serde_json::json!({ "coords": coords }))
-                }
+                InteractSubcommand::MouseMove { coords, space } => (
+                    "mouse_move",
+                    None,
+                    serde_json::json!({ "coords": coords, "space": space.to_string() }),
+                ),
                 InteractSubcommand::CursorPosition => {
                     ("cursor_position", None, serde_json::json!({}))
                 }
@@ -1262,21 +3658,43 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
                     None,
                     serde_json::json!({ "key": key, "duration": duration }),
                 ),
+                InteractSubcommand::TabWalk {
+                    max,
+                    screenshot_each,
+                } => (
+                    "tab_walk",
+                    None,
+                    serde_json::json!({ "max": max, "screenshot_each": screenshot_each }),
+                ),
                 InteractSubcommand::TripleClick { selector, coords } => (
                     "triple_click",
                     selector.clone(),
                     serde_json::json!({ "coords": coords }),
                 ),
+                InteractSubcommand::RightClick {
+                    selector, coords, ..
+                } => (
+                    "right_click",
+                    selector.clone(),
+                    serde_json::json!({ "coords": coords }),
+                ),
+                InteractSubcommand::DoubleClick {
+                    selector, coords, ..
+                } => (
+                    "double_click",
+                    selector.clone(),
+                    serde_json::json!({ "coords": coords }),
+                ),
                 InteractSubcommand::MouseDown { button } => {
                     ("mouse_down", None, serde_json::json!({ "button": button }))
                 }
                 InteractSubcommand::MouseUp { button } => {
                     ("mouse_up", None, serde_json::json!({ "button": button }))
                 }
-                InteractSubcommand::ScreenshotRegion { region, .. } => (
+                InteractSubcommand::ScreenshotRegion { region, space, .. } => (
                     "screenshot_region",
                     None,
-                    serde_json::json!({ "region": region }),
+                    serde_json::json!({ "region": region, "space": space.to_string() }),
                 ),
                 InteractSubcommand::WaitDuration { duration } => (
                     "wait_duration",
@@ -1290,26 +3708,48 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
                 InteractSubcommand::Click {
                     selector,
                     coords,
+                    space,
                     nth,
                     text,
+                    then_wait,
+                    then_wait_url,
+                    then_network_idle,
                 } => InteractCommand::Click {
                     selector: selector.clone(),
                     coords: *coords,
+                    space: space.clone(),
                     nth: *nth,
                     text: text.clone(),
+                    then: match (then_wait, then_wait_url) {
+                        (Some(selector), _) => Some(FollowUp::Selector(selector.clone())),
+                        (None, Some(text)) => Some(FollowUp::UrlContains(text.clone())),
+                        (None, None) if *then_network_idle => Some(FollowUp::NetworkIdle),
+                        (None, None) => None,
+                    },
                 },
                 InteractSubcommand::Type {
                     selector,
                     text,
                     focused,
+                    keystrokes,
+                    delay,
+                    framework,
                 } => InteractCommand::Type {
                     selector: selector.clone(),
                     text: text.clone(),
                     focused: *focused,
+                    keystroke_delay: (*keystrokes || *delay > 0).then_some(*delay),
+                    framework: *framework,
                 },
                 InteractSubcommand::Key { keys } => InteractCommand::Key { keys: keys.clone() },
-                InteractSubcommand::Hover { selector } => InteractCommand::Hover {
+                InteractSubcommand::Hover {
+                    selector,
+                    capture,
+                    then_click,
+                } => InteractCommand::Hover {
                     selector: selector.clone(),
+                    capture: capture.clone(),
+                    then_click: then_click.clone(),
                 },
                 InteractSubcommand::Scroll {
                     down,
@@ -1317,34 +3757,76 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
                     left,
                     right,
                     to,
+                    within,
+                    until,
+                    until_text,
+                    max_scrolls,
                 } => InteractCommand::Scroll {
                     down: *down,
                     up: *up,
                     left: *left,
                     right: *right,
                     to: to.clone(),
+                    within: within.clone(),
+                    until: until.clone(),
+                    until_text: until_text.clone(),
+                    max_scrolls: *max_scrolls,
                 },
                 InteractSubcommand::Screenshot {
                     full,
+                    stitch,
                     element,
                     output,
+                    format,
+                    quality,
                 } => InteractCommand::Screenshot {
                     full: *full,
+                    stitch: *stitch,
                     element: element.clone(),
-                    output: output.clone(),
+                    options: screenshot_options(format.as_deref(), *quality, output.clone())?,
                 },
-                InteractSubcommand::Navigate { url } => {
-                    InteractCommand::Navigate { url: url.clone() }
-                }
-                InteractSubcommand::Back => InteractCommand::Back,
+                InteractSubcommand::Navigate {
+                    url,
+                    basic_auth,
+                    bearer,
+                    wait_until,
+                    referrer,
+                    timeout,
+                } => InteractCommand::Navigate {
+                    url: url.clone(),
+                    credential: basic_auth.clone().or_else(|| {
+                        bearer
+                            .clone()
+                            .map(|token| crate::network::Credential::Bearer { token })
+                    }),
+                    wait_until: *wait_until,
+                    referrer: referrer.clone(),
+                    timeout_ms: *timeout,
+                },
+                InteractSubcommand::Back { steps } => InteractCommand::Back { steps: *steps },
+                InteractSubcommand::Forward { steps } => InteractCommand::Forward { steps: *steps },
                 InteractSubcommand::Refresh => InteractCommand::Refresh,
+                InteractSubcommand::Wait {
+                    spa_navigation: true,
+                    timeout,
+                    ..
+                } => InteractCommand::WaitRouteChange {
+                    timeout_ms: *timeout,
+                },
                 InteractSubcommand::Wait {
                     selector,
                     visible,
                     gone,
                     text,
                     text_gone,
+                    url_contains,
+                    url_matches,
+                    title_contains,
+                    enabled,
+                    stable,
+                    attr,
                     timeout,
+                    spa_navigation: false,
                 } => InteractCommand::Wait {
                     selector: selector.clone().unwrap_or_default(),
                     visible: *visible,
@@ -1352,93 +3834,332 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
                     timeout_ms: *timeout,
                     text: text.clone(),
                     text_gone: text_gone.clone(),
+                    page: match (url_contains, url_matches, title_contains) {
+                        (Some(text), _, _) => {
+                            Some(crate::cdp::PageCondition::UrlContains(text.clone()))
+                        }
+                        (_, Some(pattern), _) => {
+                            Some(crate::cdp::PageCondition::UrlMatches(pattern.clone()))
+                        }
+                        (_, _, Some(text)) => {
+                            Some(crate::cdp::PageCondition::TitleContains(text.clone()))
+                        }
+                        _ => None,
+                    },
+                    state: match (enabled, stable, attr) {
+                        (true, _, _) => Some(crate::cdp::ElementCondition::Enabled),
+                        (false, Some(ms), _) => Some(crate::cdp::ElementCondition::Stable(*ms)),
+                        (false, None, Some((name, value))) => Some(
+                            crate::cdp::ElementCondition::Attribute(name.clone(), value.clone()),
+                        ),
+                        (false, None, None) => None,
+                    },
                 },
                 InteractSubcommand::Drag {
                     from,
                     to,
                     from_coords,
                     to_coords,
+                    space,
                 } => InteractCommand::Drag {
                     from_selector: from.clone(),
                     to_selector: to.clone(),
                     from_coords: *from_coords,
                     to_coords: *to_coords,
+                    space: space.clone(),
+                },
+                InteractSubcommand::Gesture {
+                    path,
+                    duration,
+                    touch,
+                } => InteractCommand::Gesture {
+                    path: path.clone(),
+                    duration_ms: *duration,
+                    touch: *touch,
+                },
+                InteractSubcommand::Tap { selector, coords } => InteractCommand::Tap {
+                    selector: selector.clone(),
+                    coords: *coords,
+                },
+                InteractSubcommand::Swipe { from, to, duration } => InteractCommand::Swipe {
+                    from: *from,
+                    to: *to,
+                    duration_ms: *duration,
+                },
+                InteractSubcommand::Pinch {
+                    selector,
+                    coords,
+                    scale,
+                    distance,
+                    duration,
+                } => InteractCommand::Pinch {
+                    selector: selector.clone(),
+                    coords: *coords,
+                    scale: *scale,
+                    distance: *distance,
+                    duration_ms: *duration,
                 },
                 InteractSubcommand::Select {
                     selector,
                     value,
                     by_label,
                     by_index,
+                    framework,
                 } => InteractCommand::Select {
                     selector: selector.clone(),
                     value: value.clone(),
                     by_label: *by_label,
                     by_index: *by_index,
+                    framework: *framework,
+                },
+                InteractSubcommand::Check { selector, uncheck } => InteractCommand::Check {
+                    selector: selector.clone(),
+                    checked: !uncheck,
+                },
+                InteractSubcommand::Table {
+                    selector,
+                    find_row,
+                    click_cell,
+                    next_page,
+                    sort,
+                } => InteractCommand::Table {
+                    selector: selector.clone(),
+                    action: crate::interact::TableAction {
+                        sort: sort.clone(),
+                        find_row: find_row.clone(),
+                        click_cell: click_cell.clone(),
+                        next_page: *next_page,
+                    },
+                },
+                InteractSubcommand::Date { selector, value } => InteractCommand::Date {
+                    selector: selector.clone(),
+                    date: *value,
                 },
-                InteractSubcommand::Upload { selector, files } => InteractCommand::Upload {
+                InteractSubcommand::Combobox {
+                    selector,
+                    choose,
+                    create_if_missing,
+                } => InteractCommand::Combobox {
                     selector: selector.clone(),
-                    files: files.clone(),
+                    choose: choose.clone(),
+                    create_if_missing: *create_if_missing,
+                },
+                InteractSubcommand::Upload {
+                    selector,
+                    files,
+                    via_chooser,
+                } => match via_chooser {
+                    // Without an input selector every positional argument is a file
+                    Some(trigger) => InteractCommand::Upload {
+                        selector: trigger.clone(),
+                        files: selector
+                            .iter()
+                            .map(PathBuf::from)
+                            .chain(files.iter().cloned())
+                            .collect(),
+                        via_chooser: true,
+                    },
+                    None => InteractCommand::Upload {
+                        selector: selector.clone().unwrap_or_default(),
+                        files: files.clone(),
+                        via_chooser: false,
+                    },
                 },
-                InteractSubcommand::Dialog { accept, text } => InteractCommand::Dialog {
+                InteractSubcommand::Dialog {
+                    accept,
+                    text,
+                    timeout,
+                    ..
+                } => InteractCommand::Dialog {
                     accept: *accept,
                     text: text.clone(),
+                    timeout_ms: *timeout,
+                },
+                InteractSubcommand::DismissConsent { timeout } => InteractCommand::DismissConsent {
+                    timeout_ms: *timeout,
                 },
                 InteractSubcommand::Resize { width, height } => InteractCommand::Resize {
                     width: *width,
                     height: *height,
                 },
-                InteractSubcommand::Pdf { output, landscape } => InteractCommand::Pdf {
+                InteractSubcommand::Pdf {
+                    output,
+                    landscape,
+                    paper,
+                    margin,
+                    scale,
+                    header_template,
+                    footer_template,
+                    page_ranges,
+                    prefer_css_page_size,
+                } => InteractCommand::Pdf {
                     output: output.clone(),
-                    landscape: *landscape,
+                    options: crate::cdp::PdfOptions {
+                        landscape: *landscape,
+                        paper: *paper,
+                        margins: *margin,
+                        scale: *scale,
+                        header_template: header_template.clone(),
+                        footer_template: footer_template.clone(),
+                        page_ranges: page_ranges.clone(),
+                        prefer_css_page_size: *prefer_css_page_size,
+                    },
                 },
                 // Anthropic Computer Use features
-                InteractSubcommand::MouseMove { coords } => {
-                    InteractCommand::MouseMove { coords: *coords }
-                }
+                InteractSubcommand::MouseMove { coords, space } => InteractCommand::MouseMove {
+                    coords: *coords,
+                    space: space.clone(),
+                },
                 InteractSubcommand::CursorPosition => InteractCommand::CursorPosition,
                 InteractSubcommand::HoldKey { key, duration } => InteractCommand::HoldKey {
                     key: key.clone(),
                     duration_ms: *duration,
                 },
+                InteractSubcommand::TabWalk {
+                    max,
+                    screenshot_each,
+                } => InteractCommand::TabWalk {
+                    max: *max,
+                    screenshot_each: *screenshot_each,
+                },
                 InteractSubcommand::TripleClick { selector, coords } => {
                     InteractCommand::TripleClick {
                         selector: selector.clone(),
                         coords: *coords,
                     }
                 }
+                InteractSubcommand::RightClick {
+                    selector,
+                    coords,
+                    capture,
+                } => InteractCommand::RightClick {
+                    selector: selector.clone(),
+                    coords: *coords,
+                    capture: *capture,
+                },
+                InteractSubcommand::DoubleClick {
+                    selector,
+                    coords,
+                    capture,
+                } => InteractCommand::DoubleClick {
+                    selector: selector.clone(),
+                    coords: *coords,
+                    capture: *capture,
+                },
                 InteractSubcommand::MouseDown { button } => InteractCommand::MouseDown {
                     button: button.clone(),
                 },
                 InteractSubcommand::MouseUp { button } => InteractCommand::MouseUp {
                     button: button.clone(),
                 },
-                InteractSubcommand::ScreenshotRegion { region, output } => {
-                    InteractCommand::ScreenshotRegion {
-                        region: *region,
-                        output: output.clone(),
-                    }
-                }
+                InteractSubcommand::ScreenshotRegion {
+                    region,
+                    space,
+                    output,
+                    format,
+                    quality,
+                } => InteractCommand::ScreenshotRegion {
+                    region: *region,
+                    space: space.clone(),
+                    options: screenshot_options(format.as_deref(), *quality, output.clone())?,
+                },
                 InteractSubcommand::WaitDuration { duration } => InteractCommand::WaitDuration {
                     duration_ms: *duration,
                 },
                 InteractSubcommand::Cleanup { .. } => unreachable!("handled above"),
             };
+            // Navigations and uploads are judged by their arguments alone, so a
+            // refused one is stopped before connecting
+            let mut cmd = interact::screen(config, cmd, overrides)?;
+
+            cdp.connect().await?;
+            crate::network::apply_settings(cdp).await?;
+            crate::debug::InitScripts::apply(cdp).await?;
+
+            // An explicit `interact dialog` answers the dialog itself
+            let auto_dialogs = if matches!(command, InteractSubcommand::Dialog { .. }) {
+                None
+            } else {
+                interact::DialogPolicy::apply(cdp, cli.session.as_deref()).await?
+            };
+
+            // Auto-record mode starts a session when none is active
+            let recorder = SessionRecorder::open(cli.session.as_deref())?;
+            let auto_started = if cli.record || config.session.auto_record {
+                recorder.start_auto()?
+            } else {
+                None
+            };
+            if let Some(id) = &auto_started {
+                formatter.hint(&format!("Recording session {}", id));
+            }
+            // A session started by --record alone ends with this command, even one
+            // that fails early; one started by `session.auto_record` stays open
+            // until `session stop`
+            let one_shot = auto_started
+                .as_deref()
+                .filter(|_| !config.session.auto_record)
+                .map(|id| recorder.stop_on_drop(id));
+            let recording = recorder.is_recording();
+
+            let session_id = recorder.get_summary().map(|s| s.id);
 
             // Build action for recording
             use crate::session::ActionBuilder;
+            let url_before = if recording {
+                cdp.current_url().await.ok()
+            } else {
+                None
+            };
+            // Recorded sessions don't keep what was typed into sensitive fields
+            let mut args = args;
+            let masker = crate::output::Masker::new(&config.masking)?;
+            // An `@alias` is judged by the selector it stands for
+            let mut target = selector.clone();
+            if let Some(target) = target.as_mut() {
+                let _ = crate::site_instructions::expand_page_aliases(cdp, vec![target]).await;
+            }
+            if masker.is_enabled()
+                && cmd_name == "type"
+                && target.as_deref().is_some_and(|sel| {
+                    crate::security::SecurityChecker::check_type_action(sel, "").detected
+                        || crate::output::is_sensitive_key(sel)
+                })
+            {
+                args["value"] = crate::output::MASKED.into();
+            }
+            masker.mask_value(&mut args);
             let action_builder = ActionBuilder::new(cmd_name)
                 .with_args(args)
-                .with_selector(selector);
+                .with_selector(selector)
+                .with_page_url(url_before);
+
+            if *coords_from_screenshot {
+                interact::coords_from_screenshot(cdp, &mut cmd, formatter).await?;
+            }
 
             // Execute the command
-            let result = interact::run_interact(&cdp, &config, cmd, formatter).await;
+            let result =
+                interact::run_interact(cdp, config, cmd, session_id.as_deref(), formatter).await;
+
+            if let Some(dialogs) = auto_dialogs {
+                for dialog in dialogs.lock().await.iter() {
+                    formatter.warning(&format!(
+                        "Dialog handled by policy ({}): \"{}\"",
+                        dialog.dialog_type, dialog.message
+                    ));
+                }
+            }
 
             // Record the action if a session is active
-            let sessions_dir = Config::find_domguard_dir()
-                .unwrap_or_else(Config::domguard_dir)
-                .join("sessions");
-            let recorder = SessionRecorder::new(sessions_dir);
-            if recorder.is_recording() {
+            if recording {
+                let screenshot = match recorder.next_screenshot_path() {
+                    Some(path) => crate::session::capture_thumbnail(cdp, path).await,
+                    None => None,
+                };
+                let action_builder = action_builder
+                    .with_page_url_after(cdp.current_url().await.ok())
+                    .with_screenshot(screenshot);
                 let action = if result.is_ok() {
                     action_builder.success()
                 } else {
@@ -1446,7 +4167,7 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
                         result
                             .as_ref()
                             .err()
-                            .map(|e| e.to_string())
+                            .map(|e| masker.mask_text(&e.to_string()))
                             .unwrap_or_default()
                             .as_str(),
                     )
@@ -1454,19 +4175,265 @@ async fn run_command(cli: Cli, formatter: &Formatter) -> Result<()> {
                 let _ = recorder.record_action(action);
             }
 
+            if let Some(one_shot) = one_shot {
+                one_shot.stop()?;
+            }
+
             result
         }
         Commands::Session { command } => {
-            handle_session(&mut cdp, &config, command, formatter).await
+            handle_session(cdp, config, command, cli.session.as_deref(), formatter).await
         }
         Commands::Security { command } => handle_security(command, formatter),
-        Commands::Explain { command } => handle_explain(&mut cdp, command, formatter).await,
-        Commands::Sites { command } => handle_sites(&mut cdp, command, formatter).await,
-        Commands::Workflow { command } => handle_workflow(&mut cdp, command, formatter).await,
-        Commands::Takeover { command } => handle_takeover(&mut cdp, command, formatter).await,
-        Commands::Correction { command } => handle_correction(&mut cdp, command, formatter).await,
-        Commands::Init => unreachable!(),
+        Commands::Explain { command } => handle_explain(cdp, command, formatter).await,
+        Commands::Sites { command } => handle_sites(cdp, command, formatter).await,
+        Commands::Workflow { command } => {
+            handle_workflow(cdp, command, cli.session.as_deref(), formatter).await
+        }
+        Commands::Takeover { command } => handle_takeover(cdp, command, formatter).await,
+        Commands::Correction { command } => handle_correction(cdp, command, formatter).await,
+        Commands::Network { command } => handle_network(cdp, command, formatter).await,
+        Commands::Events { command } => handle_events(cdp, command, formatter).await,
+        Commands::Secrets { command } => handle_secrets(command, formatter),
+        Commands::Auth { command } => {
+            handle_auth(cdp, command, cli.session.as_deref(), formatter).await
+        }
+        Commands::Config { command } => handle_config(command, formatter),
+        Commands::Chrome { command } => handle_chrome(config, command, formatter).await,
+        Commands::Doctor => handle_doctor(config, config_error.as_ref(), formatter),
+        Commands::MigrateStorage { to } => handle_migrate_storage(*to, formatter),
+        Commands::Recover => handle_recover(cdp, formatter).await,
+        Commands::Tunnel {
+            destination,
+            remote_port,
+            local_port,
+        } => handle_tunnel(
+            destination,
+            *remote_port,
+            local_port.unwrap_or(config.chrome.port),
+            formatter,
+        ),
+        Commands::Init | Commands::Retry { .. } | Commands::Serve { .. } => unreachable!(),
+    }
+}
+
+/// Browse the saved inspirations library
+fn handle_inspire_library(
+    config: &Config,
+    action: &InspireAction,
+    formatter: &Formatter,
+) -> Result<()> {
+    let library = config.inspirations_dir();
+    match action {
+        InspireAction::List => {
+            let inspirations = inspire::list_inspirations(&library)?;
+            if formatter.is_json() {
+                formatter.output_json(&inspirations);
+            } else if inspirations.is_empty() {
+                println!("No saved inspirations");
+                formatter.hint("Use 'domguard inspire <url> --save <name>' to save one");
+            } else {
+                formatter.header("Saved Inspirations");
+                for inspiration in &inspirations {
+                    let mut extras = Vec::new();
+                    if inspiration.has_screenshot {
+                        extras.push("screenshot");
+                    }
+                    if inspiration.has_responsive {
+                        extras.push("responsive");
+                    }
+                    println!(
+                        "  {} - {} ({} colors, {} fonts{})",
+                        inspiration.name.cyan(),
+                        inspiration.url,
+                        inspiration.colors,
+                        inspiration.fonts,
+                        if extras.is_empty() {
+                            String::new()
+                        } else {
+                            format!(", {}", extras.join(", "))
+                        }
+                    );
+                    if let Some(saved_at) = inspiration.saved_at {
+                        println!("    Saved: {}", saved_at.format("%Y-%m-%d %H:%M:%S"));
+                    }
+                }
+            }
+        }
+        InspireAction::Show { name } => {
+            let inspiration = inspire::load_inspiration(&library, name)?;
+            if formatter.is_json() {
+                formatter.output_json(&inspiration);
+            } else {
+                println!("{}", inspiration);
+            }
+        }
+        InspireAction::Compare { a, b } => {
+            let comparison = inspire::compare_inspirations(&library, a, b)?;
+            if formatter.is_json() {
+                formatter.output_json(&comparison);
+            } else {
+                formatter.header(&format!("{} vs {}", a, b));
+                let sections = [
+                    ("Palette", &comparison.palette),
+                    ("Font families", &comparison.font_families),
+                    ("Type scale", &comparison.type_scale),
+                    ("Font weights", &comparison.font_weights),
+                ];
+                for (title, diff) in sections {
+                    println!("\n{}", title.bold());
+                    println!("  Shared: {}", list_or_none(&diff.shared));
+                    println!("  Only {}: {}", a, list_or_none(&diff.only_a));
+                    println!("  Only {}: {}", b, list_or_none(&diff.only_b));
+                }
+            }
+        }
+        InspireAction::Delete { name } => {
+            inspire::delete_inspiration(&library, name)?;
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "success": true,
+                    "deleted": name
+                }));
+            } else {
+                formatter.success(&format!("Deleted inspiration {}", name));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn list_or_none(values: &[String]) -> String {
+    if values.is_empty() {
+        "-".to_string()
+    } else {
+        values.join(", ")
+    }
+}
+
+/// Parse the screenshot commands' --format (a string, as it shares the global flag's id)
+fn screenshot_options(
+    format: Option<&str>,
+    quality: Option<u8>,
+    output: Option<PathBuf>,
+) -> Result<crate::interact::ScreenshotOptions> {
+    Ok(crate::interact::ScreenshotOptions {
+        format: format
+            .map(str::parse)
+            .transpose()
+            .map_err(|e: String| anyhow::anyhow!(e))?,
+        quality,
+        output,
+    })
+}
+
+/// Run a core interact command on Firefox through WebDriver BiDi
+async fn run_firefox_interact(
+    config: &Config,
+    command: &InteractSubcommand,
+    overrides: interact::GuardOverrides,
+    deadline: Option<std::time::Instant>,
+    formatter: &Formatter,
+) -> Result<()> {
+    let cmd = match command {
+        InteractSubcommand::Navigate { url, timeout, .. } => InteractCommand::Navigate {
+            url: url.clone(),
+            credential: None,
+            wait_until: crate::cdp::WaitUntil::Load,
+            referrer: None,
+            timeout_ms: *timeout,
+        },
+        InteractSubcommand::Click {
+            selector: Some(selector),
+            coords: None,
+            space: _,
+            nth: 0,
+            text: None,
+            then_wait: None,
+            then_wait_url: None,
+            then_network_idle: false,
+        } => InteractCommand::Click {
+            selector: Some(selector.clone()),
+            coords: None,
+            space: crate::interact::CoordSpace::default(),
+            nth: 0,
+            text: None,
+            then: None,
+        },
+        InteractSubcommand::Type {
+            selector: Some(selector),
+            text: Some(text),
+            focused: false,
+            keystrokes: false,
+            ..
+        } => InteractCommand::Type {
+            selector: Some(selector.clone()),
+            text: Some(text.clone()),
+            focused: false,
+            keystroke_delay: None,
+            framework: None,
+        },
+        InteractSubcommand::Screenshot {
+            full,
+            stitch,
+            element,
+            output,
+            format,
+            quality,
+        } => InteractCommand::Screenshot {
+            full: *full,
+            stitch: *stitch,
+            element: element.clone(),
+            options: screenshot_options(format.as_deref(), *quality, output.clone())?,
+        },
+        _ => anyhow::bail!(
+            "Not supported by the firefox backend (it covers navigate, click <selector>, type <selector> <text>, screenshot and debug eval)"
+        ),
+    };
+
+    let cmd = interact::screen(config, cmd, overrides)?;
+    let backend = crate::bidi::FirefoxBackend::connect(config, deadline).await?;
+    crate::interact::run_backend_interact(&backend, config, cmd, formatter).await
+}
+
+fn set_dialog_policy(
+    policy: &str,
+    text: Option<String>,
+    session: Option<&str>,
+    formatter: &Formatter,
+) -> Result<()> {
+    let path = interact::DialogPolicy::path(session)?;
+
+    if policy == "off" {
+        let cleared = interact::DialogPolicy::clear(&path)?;
+        if formatter.is_json() {
+            formatter.output_json(&serde_json::json!({ "policy": "off", "cleared": cleared }));
+        } else if cleared {
+            formatter.success("Dialog policy cleared; dialogs are left open");
+        } else {
+            println!("No dialog policy set");
+        }
+        return Ok(());
+    }
+
+    let policy = interact::DialogPolicy {
+        accept: policy == "accept",
+        prompt_text: text,
+    };
+    policy.save(&path)?;
+
+    if formatter.is_json() {
+        formatter.output_json(&policy);
+    } else {
+        let action = if policy.accept {
+            "accepted"
+        } else {
+            "dismissed"
+        };
+        formatter.success(&format!("Dialogs will be {} automatically", action));
+        formatter.hint("Run 'domguard interact dialog --policy off' to stop");
     }
+    Ok(())
 }
 
 fn handle_init(formatter: &Formatter) -> Result<()> {
@@ -1535,152 +4502,325 @@ fn handle_init(formatter: &Formatter) -> Result<()> {
     Ok(())
 }
 
-async fn handle_status(cdp: &mut CdpConnection, formatter: &Formatter) -> Result<()> {
-    let config = Config::load()?;
-
-    if formatter.is_json() {
-        #[derive(serde::Serialize)]
-        struct Status {
-            initialized: bool,
-            config_path: String,
-            chrome_host: String,
-            chrome_port: u16,
-            connected: bool,
-            current_url: Option<String>,
+fn handle_migrate_storage(to: storage::StorageBackend, formatter: &Formatter) -> Result<()> {
+    let domguard_dir = Config::find_domguard_dir().unwrap_or_else(Config::domguard_dir);
+    let migration = storage::migrate(&domguard_dir, to)?;
+
+    match (formatter.is_json(), to) {
+        (true, storage::StorageBackend::Fs) => formatter.output_json(&serde_json::json!({
+            "sessions_dir": migration.sessions_dir,
+            "sessions_indexed": migration.sessions
+        })),
+        (true, storage::StorageBackend::Sqlite) => formatter.output_json(&serde_json::json!({
+            "backend": migration.backend,
+            "sessions_copied": migration.sessions,
+            "workflows_copied": migration.workflows
+        })),
+        (false, storage::StorageBackend::Fs) => formatter.success(&format!(
+            "Indexed {} saved session(s) in {}",
+            migration.sessions,
+            migration.sessions_dir.display()
+        )),
+        (false, storage::StorageBackend::Sqlite) => {
+            formatter.success(&format!(
+                "Copied {} session(s) and {} workflow(s) into SQLite",
+                migration.sessions, migration.workflows
+            ));
+            formatter
+                .item("Set [storage] backend = \"sqlite\" in .domguard/config.toml to use them");
         }
+    }
+    Ok(())
+}
 
-        let connected = cdp.connect().await.is_ok();
-        let current_url = if connected {
-            cdp.current_url().await.ok()
-        } else {
+/// Commands that run until something happens (or forever), named for run markers
+fn long_running(command: &Commands) -> Option<&'static str> {
+    match command {
+        Commands::Interact {
+            command: InteractSubcommand::Wait { .. },
+            ..
+        } => Some("interact wait"),
+        Commands::Interact {
+            command: InteractSubcommand::WaitDuration { .. },
+            ..
+        } => Some("interact wait-duration"),
+        Commands::Debug {
+            command: DebugSubcommand::Console { follow: true, .. },
+        } => Some("debug console --follow"),
+        Commands::Debug {
+            command: DebugSubcommand::Xhr { follow: true, .. },
+        } => Some("debug xhr --follow"),
+        Commands::Session {
+            command: SessionSubcommand::Replay { .. },
+        } => Some("session replay"),
+        Commands::Workflow {
+            command: WorkflowSubcommand::Run { .. },
+        } => Some("workflow run"),
+        Commands::Workflow {
+            command: WorkflowSubcommand::Daemon { .. },
+        } => Some("workflow daemon"),
+        Commands::Takeover {
+            command: TakeoverSubcommand::Wait { .. },
+        } => Some("takeover wait"),
+        Commands::Events {
+            command: EventsSubcommand::Daemon,
+        } => Some("events daemon"),
+        _ => None,
+    }
+}
+
+/// Leave a run marker, so a process that dies mid-run is cleaned up after
+/// by the next command
+fn begin_run(
+    name: &str,
+    session: Option<&str>,
+    formatter: &Formatter,
+) -> Option<crate::interrupt::RunGuard> {
+    match crate::interrupt::begin_run(name, session) {
+        Ok(guard) => Some(guard),
+        Err(e) => {
+            formatter.warning(&format!("Could not write run marker: {}", e));
             None
-        };
+        }
+    }
+}
 
-        let status = Status {
-            initialized: Config::is_initialized(),
-            config_path: Config::config_path().to_string_lossy().to_string(),
-            chrome_host: config.chrome.host.clone(),
-            chrome_port: config.chrome.port,
-            connected,
-            current_url,
-        };
+/// Report cleaning up after an interrupted command
+fn print_recovery(recovery: &crate::interrupt::Recovery, formatter: &Formatter) {
+    let marker = &recovery.marker;
+    formatter.warning(&format!(
+        "'{}' (pid {}) was interrupted; cleaning up",
+        marker.command, marker.pid
+    ));
+    match &recovery.session {
+        Ok(Some(session)) => formatter.hint(&format!(
+            "Session {} saved as interrupted ({} actions)",
+            session.id,
+            session.actions.len()
+        )),
+        Ok(None) => {}
+        Err(e) => formatter.warning(&format!(
+            "Could not save session {}: {}",
+            marker.recording.as_deref().unwrap_or_default(),
+            e
+        )),
+    }
+    if let Err(e) = &recovery.page_cleanup {
+        formatter.hint(&format!("Page not cleaned up: {}", e));
+    }
+}
 
-        formatter.output_json(&status);
-    } else {
-        println!("{}", "DOMGuard Status".cyan().bold());
-        println!();
+async fn handle_recover(cdp: &mut CdpConnection, formatter: &Formatter) -> Result<()> {
+    let recovered = crate::interrupt::recover_interrupted(cdp).await;
+    for recovery in &recovered {
+        print_recovery(recovery, formatter);
+    }
 
-        // Check initialization
-        if Config::is_initialized() {
-            println!("  {} Initialized", "✓".green());
-            println!("    Config: {}", Config::config_path().display());
-        } else {
-            println!("  {} Not initialized", "✗".red());
-            println!("    Run: domguard init");
-        }
+    if formatter.is_json() {
+        let markers: Vec<_> = recovered.iter().map(|recovery| &recovery.marker).collect();
+        formatter.output_json(&serde_json::json!({ "recovered": markers }));
+    } else if recovered.is_empty() {
+        println!("No interrupted commands to clean up after");
+    } else {
+        formatter.success(&format!("Cleaned up after {} command(s)", recovered.len()));
+    }
+    Ok(())
+}
 
-        println!();
+fn handle_doctor(
+    config: &Config,
+    config_error: Option<&anyhow::Error>,
+    formatter: &Formatter,
+) -> Result<()> {
+    use crate::doctor::CheckStatus;
 
-        // Check Chrome connection
-        println!("  Chrome: {}:{}", config.chrome.host, config.chrome.port);
+    let report = crate::doctor::run(config, config_error);
 
-        match cdp.connect().await {
-            Ok(_) => {
-                println!("  {} Connected", "✓".green());
-                if let Ok(url) = cdp.current_url().await {
-                    println!("    Current page: {}", url);
-                }
-                if let Ok(title) = cdp.get_title().await {
-                    if !title.is_empty() {
-                        println!("    Title: {}", title);
-                    }
-                }
-            }
-            Err(e) => {
-                println!("  {} Not connected", "✗".red());
-                println!("    {}", e.to_string().dimmed());
-                println!();
-                formatter.hint(&format!(
-                    "Start Chrome with: chrome --remote-debugging-port={}",
-                    config.chrome.port
-                ));
+    if formatter.is_json() {
+        formatter.output_json(&report);
+    } else {
+        formatter.header("DOMGuard Doctor");
+        for check in &report.checks {
+            let mark = match check.status {
+                CheckStatus::Ok => "✓".green(),
+                CheckStatus::Warn => "!".yellow(),
+                CheckStatus::Fail => "✗".red(),
+            };
+            println!("  {} {}: {}", mark, check.name.bold(), check.message);
+            if let Some(fix) = &check.fix {
+                println!("      {} {}", "→".dimmed(), fix);
             }
         }
+        println!();
+        if report.healthy {
+            formatter.success("No problems that would stop DOMGuard");
+        } else {
+            formatter.error("Some checks failed, see the fixes above");
+        }
     }
 
-    Ok(())
+    Ok(report.into_result()?)
 }
 
-async fn handle_session(
+async fn handle_status(
     cdp: &mut CdpConnection,
     config: &Config,
-    command: &SessionSubcommand,
+    verbose: bool,
     formatter: &Formatter,
 ) -> Result<()> {
-    use crate::session::SessionStatus;
+    let status = crate::doctor::status(cdp, config, verbose).await;
 
-    let sessions_dir = Config::find_domguard_dir()
-        .unwrap_or_else(Config::domguard_dir)
-        .join("sessions");
-    let recorder = SessionRecorder::new(sessions_dir.clone());
+    if formatter.is_json() {
+        formatter.output_json(&status);
+        return Ok(());
+    }
 
-    match command {
-        SessionSubcommand::Start { name } => {
-            cdp.connect().await?;
-            let initial_url = cdp.current_url().await.ok();
-            let id = recorder.start(name.clone(), initial_url)?;
+    println!("{}", "DOMGuard Status".cyan().bold());
+    println!();
 
-            if formatter.is_json() {
-                formatter.output_json(&serde_json::json!({
-                    "success": true,
-                    "session_id": id,
-                    "status": "recording"
-                }));
-            } else {
-                println!("{}", "Session recording started".green().bold());
-                println!("  ID: {}", id);
-                if let Some(n) = name {
-                    println!("  Name: {}", n);
-                }
-                println!();
-                formatter.hint("Use 'domguard session stop' to end recording");
-            }
-        }
+    // Check initialization
+    if status.initialized {
+        println!("  {} Initialized", "✓".green());
+        println!("    Config: {}", status.config_path);
+    } else {
+        println!("  {} Not initialized", "✗".red());
+        println!("    Run: domguard init");
+    }
 
-        SessionSubcommand::Stop { cleanup } => {
-            if let Some(session) = recorder.stop()? {
-                if formatter.is_json() {
-                    formatter.output_json(&session.summary());
-                } else {
-                    println!("{}", "Session recording stopped".green().bold());
-                    println!();
-                    print_session_summary(&session.summary(), formatter);
-                }
-
-                // Cleanup screenshots if requested or if auto_cleanup is enabled
-                let should_cleanup = *cleanup || config.defaults.auto_cleanup_screenshots;
-                if should_cleanup {
-                    let screenshots_dir = Config::find_domguard_dir()
-                        .unwrap_or_else(Config::domguard_dir)
-                        .join("screenshots");
-
-                    if screenshots_dir.exists() {
-                        let mut deleted = 0;
-                        for entry in std::fs::read_dir(&screenshots_dir)? {
-                            let entry = entry?;
-                            let path = entry.path();
-                            if path.is_file() {
-                                if let Some(ext) = path.extension() {
-                                    if ext == "png" || ext == "pdf" || ext == "html" {
-                                        std::fs::remove_file(&path)?;
-                                        deleted += 1;
-                                    }
-                                }
-                            }
-                        }
-                        if !formatter.is_json() && deleted > 0 {
-                            println!("Cleaned up {} screenshot files", deleted);
-                        }
+    println!();
+
+    // Check Chrome connection
+    println!("  Chrome: {}:{}", status.chrome_host, status.chrome_port);
+
+    if status.connected {
+        println!("  {} Connected", "✓".green());
+        if let Some(url) = &status.current_url {
+            println!("    Current page: {}", url);
+        }
+        if let Some(title) = status.title.as_deref().filter(|title| !title.is_empty()) {
+            println!("    Title: {}", title);
+        }
+    } else {
+        println!("  {} Not connected", "✗".red());
+        println!(
+            "    {}",
+            status.connect_error.as_deref().unwrap_or_default().dimmed()
+        );
+        println!();
+        formatter.hint(&format!(
+            "Start Chrome with: chrome --remote-debugging-port={}",
+            status.chrome_port
+        ));
+    }
+
+    if let Some(throttle) = &status.throttle {
+        println!();
+        println!("  {} Throttling: {}", "⚠".yellow(), throttle.describe());
+        if let Some(url) = &throttle.url {
+            println!("    Applied on: {}", url);
+        }
+        println!("    Turn off with: domguard debug throttle off");
+    }
+
+    if verbose {
+        println!();
+        println!("  {}", "Discovery".bold());
+        if let Some(version) = &status.browser {
+            println!("    Browser: {}", version.browser);
+            println!("    Protocol: {}", version.protocol_version);
+            if let Some(url) = &version.web_socket_debugger_url {
+                let redact = |url: &str| crate::discovery::redact_token(url, config);
+                println!("    WebSocket: {}", redact(url));
+                println!(
+                    "    Connecting via: {}",
+                    redact(&crate::discovery::rewrite_ws_url(url, config))
+                );
+            }
+        }
+        if let Some(e) = &status.version_error {
+            println!("    /json/version: {}", e.dimmed());
+        }
+        if let Some(targets) = &status.targets {
+            println!("    Targets ({}):", targets.len());
+            for target in targets {
+                println!(
+                    "      [{}] {} {}",
+                    target.target_type,
+                    target.id,
+                    if target.title.is_empty() {
+                        &target.url
+                    } else {
+                        &target.title
+                    }
+                );
+                if !target.title.is_empty() {
+                    println!("        {}", target.url.dimmed());
+                }
+            }
+        }
+        if let Some(e) = &status.targets_error {
+            println!("    /json/list: {}", e.dimmed());
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_session(
+    cdp: &mut CdpConnection,
+    config: &Config,
+    command: &SessionSubcommand,
+    session: Option<&str>,
+    formatter: &Formatter,
+) -> Result<()> {
+    use crate::session::SessionStatus;
+
+    let recorder = SessionRecorder::open(session)?;
+
+    match command {
+        SessionSubcommand::Start { name, screenshots } => {
+            cdp.connect().await?;
+            let initial_url = cdp.current_url().await.ok();
+            let id = recorder.start(name.clone(), initial_url, *screenshots)?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "success": true,
+                    "session_id": id,
+                    "status": "recording"
+                }));
+            } else {
+                println!("{}", "Session recording started".green().bold());
+                println!("  ID: {}", id);
+                if let Some(n) = name {
+                    println!("  Name: {}", n);
+                }
+                if *screenshots {
+                    println!("  Screenshots: after each action");
+                }
+                println!();
+                formatter.hint("Use 'domguard session stop' to end recording");
+            }
+        }
+
+        SessionSubcommand::Stop {
+            cleanup,
+            reset_throttle,
+        } => {
+            let stopped =
+                crate::session::stop(cdp, &recorder, config, *cleanup, *reset_throttle).await?;
+            if let Some(report) = stopped {
+                if formatter.is_json() {
+                    formatter.output_json(&report.session.summary());
+                } else {
+                    println!("{}", "Session recording stopped".green().bold());
+                    println!();
+                    print_session_summary(&report.session.summary(), formatter);
+                    if report.screenshots_removed > 0 {
+                        println!("Cleaned up {} screenshot files", report.screenshots_removed);
+                    }
+                    if let Some(was) = &report.throttle_reset {
+                        formatter.success(&format!("Throttling reset (was {})", was.describe()));
                     }
                 }
             } else if formatter.is_json() {
@@ -1737,8 +4877,16 @@ async fn handle_session(
             }
         }
 
-        SessionSubcommand::List => {
-            let sessions = recorder.list_sessions()?;
+        SessionSubcommand::List {
+            status,
+            name,
+            limit,
+        } => {
+            let sessions = recorder.list_sessions(&crate::storage::SessionFilter {
+                status: status.clone(),
+                name: name.clone(),
+                limit: *limit,
+            })?;
 
             if formatter.is_json() {
                 formatter.output_json(&sessions);
@@ -1752,6 +4900,7 @@ async fn handle_session(
                     let status_color = match session.status {
                         SessionStatus::Completed => "✓".green(),
                         SessionStatus::Failed => "✗".red(),
+                        SessionStatus::Interrupted => "⏹".yellow(),
                         SessionStatus::Paused => "⏸".yellow(),
                         SessionStatus::Recording => "●".cyan(),
                     };
@@ -1823,12 +4972,7 @@ async fn handle_session(
         SessionSubcommand::Export { id, format, output } => {
             let session = recorder.load_session(id)?;
 
-            let content = match format.as_str() {
-                "json" => serde_json::to_string_pretty(&session)?,
-                "bash" => export_session_as_bash(&session),
-                "markdown" | "md" => export_session_as_markdown(&session),
-                _ => anyhow::bail!("Unknown format: {}. Use json, bash, or markdown", format),
-            };
+            let content = session.export(format)?;
 
             if let Some(path) = output {
                 std::fs::write(path, &content)?;
@@ -1866,28 +5010,24 @@ async fn handle_session(
                 println!();
             }
 
-            // Note: Full replay implementation would execute each action
-            // For now, we show what would be executed
-            for (i, action) in session.actions.iter().enumerate() {
+            let total = session.actions.len();
+            let replayed = crate::session::replay(&session, *delay, &mut |i, action| {
                 if !formatter.is_json() {
                     println!(
                         "  [{}/{}] {} {}",
                         i + 1,
-                        session.actions.len(),
+                        total,
                         action.command,
                         action.args.to_string().dimmed()
                     );
                 }
-
-                // In a full implementation, we'd execute the action here
-                // For now just wait
-                tokio::time::sleep(tokio::time::Duration::from_millis(*delay)).await;
-            }
+            })
+            .await;
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
                     "success": true,
-                    "replayed_actions": session.actions.len()
+                    "replayed_actions": replayed
                 }));
             } else {
                 println!();
@@ -1907,6 +5047,7 @@ fn print_session_summary(summary: &crate::session::SessionSummary, _formatter: &
         SessionStatus::Paused => "Paused".yellow(),
         SessionStatus::Completed => "Completed".green(),
         SessionStatus::Failed => "Failed".red(),
+        SessionStatus::Interrupted => "Interrupted".yellow(),
     };
 
     if let Some(name) = &summary.name {
@@ -1928,95 +5069,10 @@ fn print_session_summary(summary: &crate::session::SessionSummary, _formatter: &
     }
 }
 
-fn export_session_as_bash(session: &crate::session::Session) -> String {
-    let mut output = String::new();
-    output.push_str("#!/bin/bash\n");
-    output.push_str("# DOMGuard Session Export\n");
-    if let Some(name) = &session.name {
-        let _ = writeln!(output, "# Session: {}", name);
-    }
-    let _ = writeln!(output, "# ID: {}", session.id);
-    let _ = writeln!(
-        output,
-        "# Recorded: {}",
-        session.started_at.format("%Y-%m-%d %H:%M:%S")
-    );
-    output.push('\n');
-    output.push_str("set -e  # Exit on error\n\n");
-
-    for action in &session.actions {
-        // Convert action back to CLI command
-        let cmd = format_action_as_command(action);
-        let _ = writeln!(output, "{}", cmd);
-    }
-
-    output
-}
-
-fn export_session_as_markdown(session: &crate::session::Session) -> String {
-    let mut output = String::new();
-    output.push_str("# DOMGuard Session\n\n");
-    if let Some(name) = &session.name {
-        let _ = writeln!(output, "**Name:** {}\n", name);
-    }
-    let _ = writeln!(output, "**ID:** `{}`\n", session.id);
-    let _ = writeln!(
-        output,
-        "**Recorded:** {}\n",
-        session.started_at.format("%Y-%m-%d %H:%M:%S")
-    );
-    let _ = writeln!(output, "**Total Actions:** {}\n", session.actions.len());
-    output.push_str("## Actions\n\n");
-
-    for (i, action) in session.actions.iter().enumerate() {
-        let status = match action.status {
-            crate::session::ActionStatus::Success => "✅",
-            crate::session::ActionStatus::Failed => "❌",
-            crate::session::ActionStatus::Skipped => "⏭️",
-            crate::session::ActionStatus::Paused => "⏸️",
-        };
-        let _ = writeln!(
-            output,
-            "{}. {} **{}** ({}ms)",
-            i + 1,
-            status,
-            action.command,
-            action.duration_ms
-        );
-        if let Some(selector) = &action.selector {
-            let _ = writeln!(output, "   - Selector: `{}`", selector);
-        }
-        if let Some(error) = &action.error {
-            let _ = writeln!(output, "   - Error: {}", error);
-        }
-        output.push('\n');
-    }
-
-    output.push_str("## Replay Commands\n\n```bash\n");
-    for action in &session.actions {
-        let _ = writeln!(output, "{}", format_action_as_command(action));
-    }
-    output.push_str("```\n");
-
-    output
-}
-
-fn format_action_as_command(action: &crate::session::RecordedAction) -> String {
-    // Convert recorded action back to CLI command
-    let base = format!("domguard interact {}", action.command);
-
-    if let Some(selector) = &action.selector {
-        format!("{} \"{}\"", base, selector)
-    } else {
-        base
-    }
-}
-
 fn handle_security(command: &SecuritySubcommand, formatter: &Formatter) -> Result<()> {
     use crate::security::{format_security_warning, BlockedSitesConfig, SecurityChecker};
 
-    let config_dir = Config::find_domguard_dir().unwrap_or_else(Config::domguard_dir);
-    let blocked_sites_path = config_dir.join("blocked_sites.toml");
+    let mut config = BlockedSitesConfig::open();
 
     match command {
         SecuritySubcommand::Check {
@@ -2024,23 +5080,7 @@ fn handle_security(command: &SecuritySubcommand, formatter: &Formatter) -> Resul
             target,
             value,
         } => {
-            let config = BlockedSitesConfig::load(&blocked_sites_path).unwrap_or_default();
-            let checker = SecurityChecker::new(config);
-
-            let detection = match action.as_str() {
-                "type" => {
-                    SecurityChecker::check_type_action(target, value.as_deref().unwrap_or(""))
-                }
-                "click" => SecurityChecker::check_click_action(target),
-                "navigate" => checker.check_navigation(target),
-                "upload" => SecurityChecker::check_upload(&[PathBuf::from(target)]),
-                _ => {
-                    anyhow::bail!(
-                        "Unknown action type: {}. Use: type, click, navigate, or upload",
-                        action
-                    );
-                }
-            };
+            let detection = SecurityChecker::new(config).check(action, target, value.as_deref())?;
 
             if formatter.is_json() {
                 formatter.output_json(&detection);
@@ -2052,20 +5092,11 @@ fn handle_security(command: &SecuritySubcommand, formatter: &Formatter) -> Resul
         }
 
         SecuritySubcommand::ListBlocked => {
-            let config = BlockedSitesConfig::load(&blocked_sites_path).unwrap_or_default();
-
             if formatter.is_json() {
                 formatter.output_json(&config);
             } else {
                 formatter.header("Blocked Sites Configuration");
-                println!(
-                    "  Mode: {}",
-                    if config.default_block {
-                        "block by default"
-                    } else {
-                        "allow by default"
-                    }
-                );
+                println!("  Mode: {}", config.mode_description());
                 println!();
 
                 if !config.blocked.is_empty() {
@@ -2088,11 +5119,8 @@ fn handle_security(command: &SecuritySubcommand, formatter: &Formatter) -> Resul
         }
 
         SecuritySubcommand::Block { pattern } => {
-            let mut config = BlockedSitesConfig::load(&blocked_sites_path).unwrap_or_default();
             config.block(pattern);
-
-            std::fs::create_dir_all(&config_dir)?;
-            config.save(&blocked_sites_path)?;
+            config.save_project()?;
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
@@ -2106,11 +5134,8 @@ fn handle_security(command: &SecuritySubcommand, formatter: &Formatter) -> Resul
         }
 
         SecuritySubcommand::Unblock { pattern } => {
-            let mut config = BlockedSitesConfig::load(&blocked_sites_path).unwrap_or_default();
             config.unblock(pattern);
-
-            std::fs::create_dir_all(&config_dir)?;
-            config.save(&blocked_sites_path)?;
+            config.save_project()?;
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
@@ -2124,11 +5149,8 @@ fn handle_security(command: &SecuritySubcommand, formatter: &Formatter) -> Resul
         }
 
         SecuritySubcommand::Allow { pattern } => {
-            let mut config = BlockedSitesConfig::load(&blocked_sites_path).unwrap_or_default();
             config.allow(pattern);
-
-            std::fs::create_dir_all(&config_dir)?;
-            config.save(&blocked_sites_path)?;
+            config.save_project()?;
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
@@ -2142,16 +5164,8 @@ fn handle_security(command: &SecuritySubcommand, formatter: &Formatter) -> Resul
         }
 
         SecuritySubcommand::SetMode { mode } => {
-            let mut config = BlockedSitesConfig::load(&blocked_sites_path).unwrap_or_default();
-
-            match mode.as_str() {
-                "allow" => config.default_block = false,
-                "block" => config.default_block = true,
-                _ => anyhow::bail!("Invalid mode: {}. Use 'allow' or 'block'", mode),
-            }
-
-            std::fs::create_dir_all(&config_dir)?;
-            config.save(&blocked_sites_path)?;
+            config.set_mode(mode)?;
+            config.save_project()?;
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
@@ -2160,38 +5174,24 @@ fn handle_security(command: &SecuritySubcommand, formatter: &Formatter) -> Resul
                     "default_block": config.default_block
                 }));
             } else {
-                println!(
-                    "Security mode set to: {}",
-                    if config.default_block {
-                        "block by default"
-                    } else {
-                        "allow by default"
-                    }
-                );
+                println!("Security mode set to: {}", config.mode_description());
             }
         }
 
         SecuritySubcommand::Config => {
-            let config = BlockedSitesConfig::load(&blocked_sites_path).unwrap_or_default();
+            let path = BlockedSitesConfig::path();
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
-                    "config_path": blocked_sites_path.display().to_string(),
+                    "config_path": path.display().to_string(),
                     "default_block": config.default_block,
                     "blocked_count": config.blocked.len(),
                     "allowed_count": config.allowed.len()
                 }));
             } else {
                 formatter.header("Security Configuration");
-                println!("  Config file: {}", blocked_sites_path.display());
-                println!(
-                    "  Mode: {}",
-                    if config.default_block {
-                        "block by default"
-                    } else {
-                        "allow by default"
-                    }
-                );
+                println!("  Config file: {}", path.display());
+                println!("  Mode: {}", config.mode_description());
                 println!("  Blocked patterns: {}", config.blocked.len());
                 println!("  Allowed patterns: {}", config.allowed.len());
             }
@@ -2208,17 +5208,7 @@ async fn handle_explain(
 ) -> Result<()> {
     use crate::explanation::{explain_action, format_explanation, ExplanationContext};
 
-    // Try to get context from current page
-    let context = if cdp.connect().await.is_ok() {
-        ExplanationContext {
-            current_url: cdp.current_url().await.ok(),
-            current_title: cdp.get_title().await.ok(),
-            previous_action: None,
-            current_goal: None,
-        }
-    } else {
-        ExplanationContext::default()
-    };
+    let context = ExplanationContext::from_page(cdp).await;
 
     let explanation = match command {
         ExplainSubcommand::Click { target } => explain_action("click", Some(target), &context),
@@ -2249,12 +5239,7 @@ async fn handle_sites(
 ) -> Result<()> {
     use crate::site_instructions::{format_instructions, SiteInstructionsManager};
 
-    let sites_dir = Config::find_domguard_dir()
-        .unwrap_or_else(Config::domguard_dir)
-        .join("sites");
-
-    let mut manager = SiteInstructionsManager::new(sites_dir.clone());
-    manager.load_all()?;
+    let mut manager = SiteInstructionsManager::open()?;
 
     match command {
         SitesSubcommand::List => {
@@ -2279,14 +5264,7 @@ async fn handle_sites(
         }
 
         SitesSubcommand::Show { domain } => {
-            // Try to find instructions for this domain
-            let url = if domain.contains("://") {
-                domain.clone()
-            } else {
-                format!("https://{}", domain)
-            };
-
-            if let Some(instructions) = manager.get_for_url(&url) {
+            if let Some(instructions) = manager.get_for_domain(domain) {
                 if formatter.is_json() {
                     formatter.output_json(instructions);
                 } else {
@@ -2307,8 +5285,7 @@ async fn handle_sites(
         }
 
         SitesSubcommand::Create { domain } => {
-            let template = SiteInstructionsManager::create_template(domain);
-            let path = manager.save(&template)?;
+            let path = manager.create(domain)?;
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
@@ -2389,15 +5366,7 @@ async fn handle_sites(
         }
 
         SitesSubcommand::Edit { domain } => {
-            // Find or create the file
-            let filename = domain.replace(['*', '.'], "_") + ".toml";
-            let path = sites_dir.join(&filename);
-
-            if !path.exists() {
-                // Create template first
-                let template = SiteInstructionsManager::create_template(domain);
-                manager.save(&template)?;
-            }
+            let path = manager.file(domain)?;
 
             // Open in editor
             let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
@@ -2426,32 +5395,21 @@ async fn handle_sites(
 async fn handle_workflow(
     cdp: &mut CdpConnection,
     command: &WorkflowSubcommand,
+    session: Option<&str>,
     formatter: &Formatter,
 ) -> Result<()> {
+    use crate::runner::{RunOptions, WorkflowStore};
     use crate::workflow::{
-        format_workflow, format_workflow_list, substitute_params, WorkflowManager,
+        format_workflow, format_workflow_list, mask_secrets, resolve_params, secret_values,
+        substitute_params, RunRecord, WorkflowSchedule,
     };
+    use std::io::IsTerminal;
 
-    let workflows_dir = Config::find_domguard_dir()
-        .unwrap_or_else(Config::domguard_dir)
-        .join("workflows");
-
-    let sessions_dir = Config::find_domguard_dir()
-        .unwrap_or_else(Config::domguard_dir)
-        .join("sessions");
-
-    let mut manager = WorkflowManager::new(workflows_dir.clone());
-    manager.load_all()?;
+    let mut store = WorkflowStore::open()?;
 
     match command {
         WorkflowSubcommand::List { tag, domain } => {
-            let workflows = if let Some(t) = tag {
-                manager.list_by_tag(t)
-            } else if let Some(d) = domain {
-                manager.list_for_domain(d)
-            } else {
-                manager.list()
-            };
+            let workflows = store.list(tag.as_deref(), domain.as_deref());
 
             if formatter.is_json() {
                 formatter.output_json(&workflows);
@@ -2466,10 +5424,7 @@ async fn handle_workflow(
         }
 
         WorkflowSubcommand::Show { id } => {
-            if let Some(workflow) = manager
-                .get(id)
-                .or_else(|| manager.find_by_name(id).first().copied())
-            {
+            if let Some(workflow) = store.find(id) {
                 if formatter.is_json() {
                     formatter.output_json(workflow);
                 } else {
@@ -2486,11 +5441,7 @@ async fn handle_workflow(
         }
 
         WorkflowSubcommand::FromSession { session_id, name } => {
-            let session_recorder = crate::session::SessionRecorder::new(sessions_dir);
-            let session = session_recorder.load_session(session_id)?;
-
-            let workflow = WorkflowManager::from_session(&session, name);
-            let path = manager.save(workflow.clone())?;
+            let (workflow, path) = store.from_session(session_id, name)?;
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
@@ -2510,8 +5461,7 @@ async fn handle_workflow(
         }
 
         WorkflowSubcommand::Create { name } => {
-            let workflow = WorkflowManager::create_empty(name);
-            let path = manager.save(workflow.clone())?;
+            let (workflow, path) = store.create(name)?;
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
@@ -2535,25 +5485,79 @@ async fn handle_workflow(
             id,
             param,
             dry_run,
+            live,
             delay,
+            mocks,
+            override_block,
         } => {
-            let workflow = manager
-                .get(id)
-                .or_else(|| manager.find_by_name(id).first().copied())
-                .ok_or_else(|| anyhow::anyhow!("Workflow not found: {}", id))?
-                .clone();
+            let workflow = store.require(id)?.clone();
+
+            // Build parameter map, asking for missing required values on a terminal
+            let interactive = !formatter.is_json() && std::io::stdin().is_terminal();
+            let params = resolve_params(&workflow, param.iter().cloned().collect(), |p| {
+                if interactive {
+                    prompt_param(p).map(Some)
+                } else {
+                    Ok(None)
+                }
+            })?;
+            let secrets = secret_values(&workflow, &params);
+
+            if *dry_run && *live {
+                cdp.connect().await?;
+                crate::network::apply_settings(cdp).await?;
 
-            // Build parameter map
-            let params: std::collections::HashMap<String, String> = param.iter().cloned().collect();
+                let report =
+                    crate::runner::check_readiness(cdp, &workflow, &params, &secrets).await?;
+                let ready = report.iter().all(|r| r.ready);
 
-            // Check required parameters
-            for p in &workflow.parameters {
-                if p.required && !params.contains_key(&p.name) && p.default.is_none() {
-                    anyhow::bail!("Missing required parameter: {}", p.name);
+                if formatter.is_json() {
+                    formatter.output_json(&serde_json::json!({
+                        "dry_run": true,
+                        "live": true,
+                        "workflow": workflow.name,
+                        "steps": workflow.steps.len(),
+                        "ready": ready,
+                        "report": report
+                    }));
+                } else {
+                    println!(
+                        "{}",
+                        "Dry Run - Step readiness on current page:".cyan().bold()
+                    );
+                    println!();
+                    for r in &report {
+                        let mark = if r.ready { "✓".green() } else { "✗".red() };
+                        println!(
+                            "  {} {}. {} {}",
+                            mark,
+                            r.index + 1,
+                            r.action,
+                            r.target.as_deref().unwrap_or("")
+                        );
+                        if let Some(note) = &r.note {
+                            println!("       {}", note.dimmed());
+                        }
+                    }
+                    println!();
+                    if ready {
+                        formatter.success("All selectors found");
+                    } else {
+                        formatter.warning("Some steps are not ready to run");
+                    }
                 }
-            }
 
-            if *dry_run {
+                if !ready {
+                    return Err(crate::workflow::StepsNotReady {
+                        steps: report
+                            .iter()
+                            .filter(|r| !r.ready)
+                            .map(|r| r.index + 1)
+                            .collect(),
+                    }
+                    .into());
+                }
+            } else if *dry_run {
                 // Just show what would be done
                 if formatter.is_json() {
                     formatter.output_json(&serde_json::json!({
@@ -2568,7 +5572,7 @@ async fn handle_workflow(
                         let target = step
                             .target
                             .as_ref()
-                            .map(|t| substitute_params(t, &params))
+                            .map(|t| mask_secrets(&substitute_params(t, &params), &secrets))
                             .unwrap_or_default();
                         println!("  {}. {} {}", i + 1, step.action, target);
                     }
@@ -2576,84 +5580,43 @@ async fn handle_workflow(
             } else {
                 // Actually run the workflow
                 cdp.connect().await?;
-
-                if !formatter.is_json() {
-                    println!(
-                        "{}",
-                        format!("Running workflow: {}", workflow.name).cyan().bold()
-                    );
-                    println!();
-                }
-
-                let start = std::time::Instant::now();
-                let mut step_results = Vec::new();
-
-                for (i, step) in workflow.steps.iter().enumerate() {
-                    let step_start = std::time::Instant::now();
-
-                    let target = step.target.as_ref().map(|t| substitute_params(t, &params));
-                    let value = step.value.as_ref().map(|v| substitute_params(v, &params));
-
-                    if !formatter.is_json() {
-                        println!(
-                            "  [{}/{}] {} {}",
-                            i + 1,
-                            workflow.steps.len(),
-                            step.action,
-                            target.as_deref().unwrap_or("")
-                        );
-                    }
-
-                    // Execute the step
-                    let result =
-                        execute_workflow_step(cdp, step, target.as_deref(), value.as_deref()).await;
-
-                    let step_result = crate::workflow::StepResult {
-                        index: i,
-                        name: step.name.clone(),
-                        success: result.is_ok(),
-                        duration_ms: step_start.elapsed().as_millis() as u64,
-                        skipped: false,
-                        retries: 0,
-                        error: result.err().map(|e| e.to_string()),
-                    };
-
-                    step_results.push(step_result);
-
-                    // Wait between steps
-                    tokio::time::sleep(std::time::Duration::from_millis(*delay)).await;
-                }
-
-                let duration_ms = start.elapsed().as_millis() as u64;
-                let success = step_results
-                    .iter()
-                    .all(|r| r.success || !workflow.steps[r.index].required);
-
-                manager.record_run(&workflow.id, success)?;
+                crate::network::apply_settings(cdp).await?;
+                crate::debug::InitScripts::apply(cdp).await?;
+                interact::DialogPolicy::apply(cdp, session).await?;
+
+                let options = RunOptions {
+                    delay_ms: *delay,
+                    mocks: mocks.clone(),
+                    override_block: *override_block,
+                };
+                let started_at = chrono::Utc::now();
+                print_workflow_start(&workflow.name, formatter);
+                let result = crate::runner::execute_workflow(
+                    cdp,
+                    &workflow,
+                    &params,
+                    &options,
+                    &mut |step| print_workflow_step(step, formatter),
+                )
+                .await?;
+                store.save_run(&RunRecord::from_result(&result, "manual", started_at))?;
 
                 if formatter.is_json() {
-                    formatter.output_json(&crate::workflow::WorkflowResult {
-                        workflow_id: workflow.id.clone(),
-                        success,
-                        duration_ms,
-                        step_results,
-                        error: None,
-                        screenshots: vec![],
-                    });
+                    formatter.output_json(&result);
                 } else {
                     println!();
-                    if success {
+                    if result.success {
                         println!("{}", "Workflow completed successfully".green().bold());
                     } else {
                         println!("{}", "Workflow completed with errors".red().bold());
                     }
-                    println!("  Duration: {}ms", duration_ms);
+                    println!("  Duration: {}ms", result.duration_ms);
                 }
             }
         }
 
         WorkflowSubcommand::Delete { id } => {
-            if manager.delete(id)? {
+            if store.manager.delete(id)? {
                 if formatter.is_json() {
                     formatter.output_json(&serde_json::json!({
                         "success": true,
@@ -2672,14 +5635,117 @@ async fn handle_workflow(
             }
         }
 
-        WorkflowSubcommand::Edit { id } => {
-            let filename = format!("{}.toml", id);
-            let path = workflows_dir.join(&filename);
+        WorkflowSubcommand::Schedule {
+            id,
+            cron,
+            watch_url,
+            when_text,
+            interval,
+            param,
+        } => {
+            let schedule = store.schedule(WorkflowSchedule {
+                workflow_id: id.clone(),
+                cron: cron.clone(),
+                watch_url: watch_url.clone(),
+                when_text: when_text.clone(),
+                interval_secs: *interval,
+                params: param.iter().cloned().collect(),
+                created_at: chrono::Utc::now(),
+            })?;
+
+            if formatter.is_json() {
+                formatter.output_json(&schedule);
+            } else {
+                formatter.success(&format!("Scheduled workflow: {}", schedule.workflow_id));
+                print_schedule(&schedule);
+                formatter.hint("Run 'domguard workflow daemon' to start the scheduler");
+            }
+        }
+
+        WorkflowSubcommand::Unschedule { id } => {
+            let (workflow_id, removed) = store.unschedule(id)?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "workflow_id": workflow_id,
+                    "removed": removed
+                }));
+            } else if removed {
+                formatter.success(&format!("Removed schedule for {}", workflow_id));
+            } else {
+                println!("No schedule for {}", workflow_id);
+            }
+        }
+
+        WorkflowSubcommand::Schedules => {
+            let schedules = store.schedules()?;
+
+            if formatter.is_json() {
+                formatter.output_json(&schedules);
+            } else if schedules.is_empty() {
+                println!("No scheduled workflows");
+                formatter.hint("Use 'domguard workflow schedule <id> --cron \"0 9 * * *\"'");
+            } else {
+                println!("{}", "Scheduled Workflows".cyan().bold());
+                println!();
+                for schedule in &schedules {
+                    println!("  {}", schedule.workflow_id);
+                    print_schedule(schedule);
+                }
+            }
+        }
+
+        WorkflowSubcommand::Daemon { once } => {
+            cdp.connect().await?;
+            crate::network::apply_settings(cdp).await?;
+            crate::debug::InitScripts::apply(cdp).await?;
+            interact::DialogPolicy::apply(cdp, session).await?;
+
+            if !formatter.is_json() {
+                println!("{}", "Workflow daemon started".cyan().bold());
+                formatter.hint(&format!("Run history: {}", store.runs_dir().display()));
+            }
+            crate::runner::run_daemon(cdp, &mut store, *once, &mut |event| {
+                print_daemon_event(&event, formatter);
+            })
+            .await?;
+        }
 
-            if !path.exists() {
-                anyhow::bail!("Workflow not found: {}", id);
+        WorkflowSubcommand::Runs { limit } => {
+            let runs = store.runs(*limit)?;
+
+            if formatter.is_json() {
+                formatter.output_json(&runs);
+            } else if runs.is_empty() {
+                println!("No workflow runs recorded");
+            } else {
+                println!("{}", "Workflow Runs".cyan().bold());
+                println!();
+                for run in &runs {
+                    let status = if run.success {
+                        "ok".green()
+                    } else {
+                        "failed".red()
+                    };
+                    println!(
+                        "  {} {} ({}, {}ms) {}",
+                        run.started_at
+                            .with_timezone(&chrono::Local)
+                            .format("%Y-%m-%d %H:%M:%S"),
+                        run.workflow_id,
+                        run.trigger,
+                        run.duration_ms,
+                        status
+                    );
+                    if let Some(error) = &run.error {
+                        println!("      {}", error.dimmed());
+                    }
+                }
             }
+        }
 
+        WorkflowSubcommand::Edit { id } => {
+            let path = store.file(id)?;
             let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
 
             if formatter.is_json() {
@@ -2703,62 +5769,133 @@ async fn handle_workflow(
     Ok(())
 }
 
-/// Execute a single workflow step
-async fn execute_workflow_step(
-    cdp: &CdpConnection,
-    step: &crate::workflow::WorkflowStep,
-    target: Option<&str>,
-    value: Option<&str>,
-) -> Result<()> {
-    match step.action.as_str() {
-        "click" => {
-            if let Some(sel) = target {
-                cdp.click(sel, 0).await?;
-            }
-        }
-        "type" => {
-            if let Some(sel) = target {
-                if let Some(text) = value {
-                    cdp.type_into(sel, text).await?;
-                }
-            }
-        }
-        "navigate" => {
-            if let Some(url) = target {
-                cdp.navigate(url).await?;
-                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-            }
-        }
-        "wait" => {
-            if let Some(sel) = target {
-                let timeout = step.timeout_ms.unwrap_or(5000);
-                cdp.wait_for(sel, timeout).await?;
-            }
-        }
-        "screenshot" => {
-            cdp.screenshot(false).await?;
-        }
-        "scroll" => {
-            if let Some(sel) = target {
-                cdp.scroll_to_element(sel).await?;
-            }
-        }
-        "hover" => {
-            if let Some(sel) = target {
-                cdp.hover(sel).await?;
-            }
-        }
-        "key" => {
-            if let Some(key) = target {
-                cdp.press_key(key).await?;
-            }
-        }
-        _ => {
-            // Unknown action, skip
-        }
+fn print_schedule(schedule: &crate::workflow::WorkflowSchedule) {
+    if let Some(cron) = &schedule.cron {
+        println!("    Cron: {}", cron);
     }
-
-    Ok(())
+    if let Some(next) = crate::runner::next_run(schedule) {
+        println!("    Next run: {}", next.format("%Y-%m-%d %H:%M"));
+    }
+    if let (Some(url), Some(text)) = (&schedule.watch_url, &schedule.when_text) {
+        println!(
+            "    Watch: {} for \"{}\" every {}s",
+            url, text, schedule.interval_secs
+        );
+    }
+}
+
+fn print_workflow_start(name: &str, formatter: &Formatter) {
+    if !formatter.is_json() {
+        println!("{}", format!("Running workflow: {}", name).cyan().bold());
+        println!();
+    }
+}
+
+fn print_workflow_step(step: &crate::runner::StepProgress, formatter: &Formatter) {
+    if !formatter.is_json() {
+        println!(
+            "  [{}/{}] {} {}",
+            step.index + 1,
+            step.total,
+            step.action,
+            step.target
+        );
+    }
+}
+
+fn print_daemon_event(event: &crate::runner::DaemonEvent, formatter: &Formatter) {
+    use crate::runner::DaemonEvent;
+
+    match event {
+        DaemonEvent::Started { workflow, .. } => print_workflow_start(workflow, formatter),
+        DaemonEvent::Step(step) => print_workflow_step(step, formatter),
+        DaemonEvent::Finished(record) => {
+            if formatter.is_json() {
+                formatter.output_json(record);
+            } else if record.success {
+                formatter.success(&format!(
+                    "{} ({}) completed",
+                    record.workflow_id, record.trigger
+                ));
+            } else {
+                formatter.warning(&format!(
+                    "{} ({}) failed: {}",
+                    record.workflow_id,
+                    record.trigger,
+                    record.error.as_deref().unwrap_or("step errors")
+                ));
+            }
+        }
+        DaemonEvent::Warning(warning) => formatter.warning(warning),
+    }
+}
+
+/// Ask for a workflow parameter on the terminal, hiding input for secrets
+fn prompt_param(param: &crate::workflow::WorkflowParameter) -> Result<String> {
+    let mut label = param.name.clone();
+    if let Some(desc) = &param.description {
+        label = format!("{} ({})", label, desc);
+    }
+    if !param.choices.is_empty() {
+        label = format!("{} [{}]", label, param.choices.join("/"));
+    }
+
+    loop {
+        let value = read_prompt_line(&label, param.is_secret())?
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: {}", param.name))?;
+        match param.validate(&value) {
+            Ok(()) if !value.is_empty() => return Ok(value),
+            Ok(()) => eprintln!("{} is required", param.name),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
+
+/// Prompt on stderr and read one line from stdin, or `None` at end of input
+///
+/// With `hidden`, terminal echo is turned off while the line is typed.
+fn read_prompt_line(label: &str, hidden: bool) -> Result<Option<String>> {
+    use std::io::Write as _;
+
+    eprint!("{}: ", label);
+    std::io::stderr().flush()?;
+
+    let hidden = hidden
+        && std::process::Command::new("stty")
+            .arg("-echo")
+            .stdin(std::process::Stdio::inherit())
+            .status()
+            .is_ok_and(|s| s.success());
+
+    let mut line = String::new();
+    let read = std::io::stdin().read_line(&mut line);
+
+    if hidden {
+        let _ = std::process::Command::new("stty")
+            .arg("echo")
+            .stdin(std::process::Stdio::inherit())
+            .status();
+        eprintln!();
+    }
+
+    if read? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+/// Tell the human about a takeover request, warning (not failing) when a channel errors
+///
+/// Returns which channels were notified, for JSON output.
+fn notify_takeover(
+    session: &crate::takeover::TakeoverSession,
+    desktop: bool,
+    webhook: Option<&str>,
+    formatter: &Formatter,
+) -> serde_json::Value {
+    let notified = crate::takeover::notify(session, desktop, webhook);
+    notified.warn_failures(formatter);
+    notified.to_json()
 }
 
 async fn handle_takeover(
@@ -2766,7 +5903,7 @@ async fn handle_takeover(
     command: &TakeoverSubcommand,
     formatter: &Formatter,
 ) -> Result<()> {
-    use crate::takeover::{format_takeover, TakeoverManager, TakeoverReason, TakeoverSession};
+    use crate::takeover::{format_takeover, TakeoverManager, WaitCondition};
 
     let domguard_dir = Config::find_domguard_dir().unwrap_or_else(Config::domguard_dir);
     let manager = TakeoverManager::new(&domguard_dir);
@@ -2777,6 +5914,8 @@ async fn handle_takeover(
             message,
             instructions,
             expected,
+            notify,
+            webhook,
         } => {
             // Check if already in takeover
             if manager.is_active() {
@@ -2796,68 +5935,32 @@ async fn handle_takeover(
                 return Ok(());
             }
 
-            // Parse reason
-            let takeover_reason = match reason.to_lowercase().as_str() {
-                "captcha" => TakeoverReason::Captcha,
-                "auth" | "authentication" | "login" => TakeoverReason::Authentication,
-                "sensitive" => TakeoverReason::SensitiveAction,
-                "error" => TakeoverReason::Error,
-                "uncertain" => TakeoverReason::Uncertain,
-                "complex" => TakeoverReason::ComplexInteraction,
-                "2fa" | "mfa" | "twofactor" => TakeoverReason::TwoFactorAuth,
-                "payment" | "pay" => TakeoverReason::Payment,
-                "user" | "requested" => TakeoverReason::UserRequested,
-                custom => TakeoverReason::Custom(custom.to_string()),
-            };
-
-            // Build default message if not provided
-            let msg = message.clone().unwrap_or_else(|| match &takeover_reason {
-                TakeoverReason::Captcha => {
-                    "CAPTCHA detected - human verification required".to_string()
-                }
-                TakeoverReason::Authentication => {
-                    "Authentication required - please log in".to_string()
-                }
-                TakeoverReason::SensitiveAction => {
-                    "Sensitive action requires human confirmation".to_string()
-                }
-                TakeoverReason::Error => "Error occurred - human intervention needed".to_string(),
-                TakeoverReason::Uncertain => {
-                    "Agent unsure how to proceed - please help".to_string()
-                }
-                TakeoverReason::ComplexInteraction => {
-                    "Complex interaction - human control needed".to_string()
-                }
-                TakeoverReason::TwoFactorAuth => "Two-factor authentication required".to_string(),
-                TakeoverReason::Payment => "Payment action requires human confirmation".to_string(),
-                TakeoverReason::UserRequested => "User requested control".to_string(),
-                TakeoverReason::Custom(s) => s.clone(),
-            });
-
-            let mut session = TakeoverSession::new(takeover_reason, &msg);
-
-            if let Some(instr) = instructions {
-                session = session.with_instructions(instr);
-            }
-
-            if let Some(exp) = expected {
-                session = session.with_expected_outcome(exp);
-            }
-
-            // Try to get current URL
-            if cdp.connect().await.is_ok() {
-                if let Ok(url) = cdp.current_url().await {
-                    session = session.with_url(&url);
-                }
-            }
-
+            let session = crate::takeover::request(
+                cdp,
+                reason,
+                message.as_deref(),
+                instructions.as_deref(),
+                expected.as_deref(),
+            )
+            .await;
             let id = manager.start(&session)?;
 
+            let takeover_config = Config::load()?.takeover;
+            let notified = notify_takeover(
+                &session,
+                *notify || takeover_config.notify_desktop,
+                webhook
+                    .as_deref()
+                    .or(takeover_config.webhook_url.as_deref()),
+                formatter,
+            );
+
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
                     "success": true,
                     "takeover_id": id,
-                    "session": session
+                    "session": session,
+                    "notified": notified
                 }));
             } else {
                 println!("{}", "TAKEOVER REQUESTED".yellow().bold());
@@ -2950,6 +6053,50 @@ async fn handle_takeover(
             }
         }
 
+        TakeoverSubcommand::Wait {
+            timeout,
+            until_selector,
+            until_url_matches,
+        } => {
+            let Some(session) = manager.get_current() else {
+                if formatter.is_json() {
+                    formatter.output_json(&serde_json::json!({
+                        "active": false
+                    }));
+                } else {
+                    formatter.warning("No active takeover to wait for");
+                }
+                return Ok(());
+            };
+
+            let until =
+                WaitCondition::new(until_selector.as_deref(), until_url_matches.as_deref())?;
+
+            if !formatter.is_json() {
+                println!("{}", "Waiting for takeover to finish...".yellow().bold());
+                formatter.hint("Run 'domguard takeover done' when finished");
+            }
+
+            let crate::takeover::WaitOutcome { completed, by } =
+                manager.wait(cdp, &session, &until, *timeout).await?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "completed": true,
+                    "completed_by": by,
+                    "session": completed
+                }));
+            } else {
+                println!("{}", "TAKEOVER FINISHED".green().bold());
+                println!("  Completed by: {}", by);
+                if let Some(notes) = completed.as_ref().and_then(|s| s.user_notes.as_ref()) {
+                    println!("  Notes: {}", notes);
+                }
+                println!();
+                println!("Automation resumed.");
+            }
+        }
+
         TakeoverSubcommand::History { limit } => {
             let history = manager.get_history()?;
             let limited: Vec<_> = history.iter().take(*limit).collect();
@@ -2992,16 +6139,30 @@ async fn handle_correction(
     formatter: &Formatter,
 ) -> Result<()> {
     use crate::correction::{
-        classify_error, dismiss_overlay_script, get_recovery_strategies, wait_stable_script,
-        AutomationError, CorrectionConfig, RecoveryStrategy,
+        classify_error, get_recovery_strategies, AutomationError, RecoveryStrategy,
     };
 
+    // Load without CLI overrides so saving doesn't persist --host/--port
+    let mut project_config = Config::load()?;
+
     match command {
-        CorrectionSubcommand::Config => {
-            let config = CorrectionConfig::default();
+        CorrectionSubcommand::Config {
+            max_retries,
+            base_delay,
+        } => {
+            if max_retries.is_some() || base_delay.is_some() {
+                if let Some(n) = max_retries {
+                    project_config.correction.max_retries = *n;
+                }
+                if let Some(ms) = base_delay {
+                    project_config.correction.base_delay_ms = *ms;
+                }
+                project_config.save()?;
+            }
+            let config = &project_config.correction;
 
             if formatter.is_json() {
-                formatter.output_json(&config);
+                formatter.output_json(config);
             } else {
                 println!("{}", "Self-Correction Configuration".cyan().bold());
                 println!();
@@ -3044,11 +6205,21 @@ async fn handle_correction(
                         "No"
                     }
                 );
+                println!(
+                    "  Auto-refresh: {}",
+                    if config.auto_refresh { "Yes" } else { "No" }
+                );
+                println!();
+                formatter.hint(
+                    "Override per site with a [correction] table in .domguard/sites/<site>.toml",
+                );
             }
         }
 
         CorrectionSubcommand::Enable => {
-            // In a full implementation, this would persist the setting
+            project_config.correction.enabled = true;
+            project_config.save()?;
+
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
                     "success": true,
@@ -3061,6 +6232,9 @@ async fn handle_correction(
         }
 
         CorrectionSubcommand::Disable => {
+            project_config.correction.enabled = false;
+            project_config.save()?;
+
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
                     "success": true,
@@ -3100,96 +6274,30 @@ async fn handle_correction(
         CorrectionSubcommand::Test { strategy, target } => {
             cdp.connect().await?;
 
-            let result = match strategy.to_lowercase().as_str() {
-                "scroll" | "scroll-into-view" => {
-                    if let Some(sel) = target {
-                        match cdp.scroll_to_element(sel).await {
-                            Ok(_) => (true, "Element scrolled into view".to_string()),
-                            Err(e) => (false, e.to_string()),
-                        }
-                    } else {
-                        (
-                            false,
-                            "Target selector required for scroll strategy".to_string(),
-                        )
-                    }
-                }
-                "dismiss-overlay" | "overlay" => {
-                    let script = dismiss_overlay_script();
-                    match cdp.evaluate(script).await {
-                        Ok(result) => {
-                            let dismissed = result.as_bool().unwrap_or(false);
-                            (
-                                dismissed,
-                                if dismissed {
-                                    "Overlays dismissed".to_string()
-                                } else {
-                                    "No overlays found".to_string()
-                                },
-                            )
-                        }
-                        Err(e) => (false, e.to_string()),
-                    }
-                }
-                "wait-stable" | "stable" => {
-                    let script = wait_stable_script();
-                    match cdp.evaluate(script).await {
-                        Ok(result) => {
-                            let stable = result
-                                .get("stable")
-                                .and_then(|v| v.as_bool())
-                                .unwrap_or(false);
-                            let duration =
-                                result.get("duration").and_then(|v| v.as_u64()).unwrap_or(0);
-                            (
-                                stable,
-                                format!(
-                                    "Page {} after {}ms",
-                                    if stable {
-                                        "stabilized"
-                                    } else {
-                                        "still changing"
-                                    },
-                                    duration
-                                ),
-                            )
-                        }
-                        Err(e) => (false, e.to_string()),
-                    }
-                }
-                "refresh" => match cdp.refresh().await {
-                    Ok(_) => (true, "Page refreshed".to_string()),
-                    Err(e) => (false, e.to_string()),
-                },
-                _ => (
-                    false,
-                    format!(
-                        "Unknown strategy: {}. Use: scroll, dismiss-overlay, wait-stable, refresh",
-                        strategy
-                    ),
-                ),
-            };
+            let result = crate::correction::test_strategy(cdp, strategy, target.as_deref()).await;
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
                     "strategy": strategy,
                     "target": target,
-                    "success": result.0,
-                    "message": result.1
+                    "success": result.success,
+                    "message": result.message
                 }));
             } else {
-                let status = if result.0 { "✓".green() } else { "✗".red() };
+                let status = if result.success {
+                    "✓".green()
+                } else {
+                    "✗".red()
+                };
                 println!("{} Strategy: {}", status, strategy);
-                println!("  {}", result.1);
+                println!("  {}", result.message);
             }
         }
 
         CorrectionSubcommand::DismissOverlay => {
             cdp.connect().await?;
 
-            let script = dismiss_overlay_script();
-            let result = cdp.evaluate(script).await?;
-            let dismissed = result.as_bool().unwrap_or(false);
+            let dismissed = crate::correction::dismiss_overlays(cdp).await?;
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
@@ -3206,13 +6314,10 @@ async fn handle_correction(
         CorrectionSubcommand::WaitStable => {
             cdp.connect().await?;
 
-            let script = wait_stable_script();
-            let result = cdp.evaluate(script).await?;
-            let stable = result
-                .get("stable")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-            let duration = result.get("duration").and_then(|v| v.as_u64()).unwrap_or(0);
+            let crate::correction::Stability {
+                stable,
+                duration_ms: duration,
+            } = crate::correction::wait_stable(cdp).await?;
 
             if formatter.is_json() {
                 formatter.output_json(&serde_json::json!({
@@ -3233,23 +6338,7 @@ async fn handle_correction(
         }
 
         CorrectionSubcommand::Strategies { error_type } => {
-            let error = match error_type.to_lowercase().replace(['-', '_'], " ").as_str() {
-                "element not found" | "not found" => AutomationError::ElementNotFound,
-                "element not visible" | "not visible" => AutomationError::ElementNotVisible,
-                "element not interactable" | "not interactable" => {
-                    AutomationError::ElementNotInteractable
-                }
-                "navigation timeout" | "timeout" => AutomationError::NavigationTimeout,
-                "network" | "network error" => AutomationError::NetworkError,
-                "javascript" | "js error" => AutomationError::JavaScriptError,
-                "captcha" => AutomationError::CaptchaDetected,
-                "auth" | "authentication" => AutomationError::AuthRequired,
-                "dialog" | "unexpected dialog" => AutomationError::UnexpectedDialog,
-                "stale" | "stale element" => AutomationError::StaleElement,
-                "click intercepted" | "intercepted" => AutomationError::ClickIntercepted,
-                "page change" | "unexpected page" => AutomationError::UnexpectedPageChange,
-                other => AutomationError::Unknown(other.to_string()),
-            };
+            let error = AutomationError::from_name(error_type);
 
             let strategies = get_recovery_strategies(&error, "click");
 
@@ -3282,3 +6371,731 @@ async fn handle_correction(
 
     Ok(())
 }
+
+async fn handle_chrome(
+    config: &Config,
+    command: &ChromeSubcommand,
+    formatter: &Formatter,
+) -> Result<()> {
+    match command {
+        ChromeSubcommand::Up {
+            image,
+            headless,
+            local_port,
+            name,
+            wait,
+        } => {
+            let port = local_port.unwrap_or(config.chrome.port);
+            let running = crate::container::up(config, name, image, port, *headless, *wait).await?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "container": running.id,
+                    "name": name,
+                    "image": image,
+                    "host": running.host,
+                    "port": running.port,
+                    "browser": running.browser,
+                    "config_path": Config::config_path(),
+                }));
+            } else {
+                formatter.success(&format!(
+                    "{} running in container {} on {}:{}",
+                    running.browser, name, running.host, running.port
+                ));
+                println!("  Config: {}", Config::config_path().display());
+                formatter.hint(&format!(
+                    "Stop it with: domguard chrome down{}",
+                    if name == crate::container::DEFAULT_NAME {
+                        String::new()
+                    } else {
+                        format!(" --name {}", name)
+                    }
+                ));
+            }
+        }
+
+        ChromeSubcommand::Down { name } => {
+            let removed = crate::container::stop(name)?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "name": name,
+                    "stopped": removed,
+                }));
+            } else if removed {
+                formatter.success(&format!("Stopped container {}", name));
+            } else {
+                formatter.warning(&format!("No container named {}", name));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_tunnel(
+    destination: &str,
+    remote_port: u16,
+    local_port: u16,
+    formatter: &Formatter,
+) -> Result<()> {
+    let tunnel = crate::tunnel::Tunnel::new(destination, remote_port, local_port)?;
+
+    if formatter.is_json() {
+        formatter.output_json(&serde_json::json!({
+            "destination": destination,
+            "local_port": local_port,
+            "remote_port": remote_port,
+            "status": "opening"
+        }));
+    } else {
+        formatter.success(&format!(
+            "Opening tunnel to {}: localhost:{} -> remote port {}",
+            destination, local_port, remote_port
+        ));
+        formatter.hint("DOMGuard connects to localhost as usual; press Ctrl+C to close");
+    }
+
+    tunnel.run()
+}
+
+fn handle_config(command: &ConfigSubcommand, formatter: &Formatter) -> Result<()> {
+    // Strings print bare so scripts can use them directly
+    fn display(value: &toml::Value) -> String {
+        match value {
+            toml::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    // The file's own settings, without --profile or CLI overrides
+    let mut config = Config::load()?;
+
+    match command {
+        ConfigSubcommand::Get { key } => {
+            let value = config.get_key(key)?;
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({ "key": key, "value": value }));
+            } else {
+                match &value {
+                    Some(value) => println!("{}", display(value)),
+                    None => anyhow::bail!("{} is not set", key),
+                }
+            }
+        }
+
+        ConfigSubcommand::Set { key, value } => {
+            let stored = config.set_key(key, value)?;
+            config.save()?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "success": true,
+                    "key": key,
+                    "value": stored
+                }));
+            } else {
+                formatter.success(&format!("{} = {}", key, stored));
+            }
+        }
+
+        ConfigSubcommand::List => {
+            let keys = config.list_keys()?;
+
+            if formatter.is_json() {
+                let map: serde_json::Map<String, serde_json::Value> = keys
+                    .into_iter()
+                    .map(|(key, value)| (key, serde_json::to_value(value).unwrap_or_default()))
+                    .collect();
+                formatter.output_json(&map);
+            } else {
+                for (key, value) in keys {
+                    println!("{} = {}", key.cyan(), value);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Sign in through a site's login workflow and save the session, or restore a saved one
+async fn handle_auth(
+    cdp: &mut CdpConnection,
+    command: &AuthSubcommand,
+    session: Option<&str>,
+    formatter: &Formatter,
+) -> Result<()> {
+    match command {
+        AuthSubcommand::Login { domain, param } => {
+            let (login, workflow) = crate::auth::login_workflow(domain)?;
+            let params = crate::workflow::resolve_params(
+                &workflow,
+                param.iter().cloned().collect(),
+                |_| Ok(None),
+            )?;
+
+            print_workflow_start(&workflow.name, formatter);
+            let saved = crate::auth::login(
+                cdp,
+                domain,
+                &login,
+                &workflow,
+                &params,
+                session,
+                &mut |step| print_workflow_step(step, formatter),
+            )
+            .await?;
+            if !saved.verified {
+                formatter.warning(
+                    "No success_indicator under [login]; saved without checking the login worked",
+                );
+            }
+
+            let state = &saved.state;
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "success": true,
+                    "domain": domain,
+                    "workflow": workflow.id,
+                    "cookies": state.cookies.len(),
+                    "local_storage": state.local_storage.len(),
+                    "session_storage": state.session_storage.len(),
+                    "path": saved.path.display().to_string()
+                }));
+            } else {
+                formatter.success(&format!(
+                    "Logged in to {} and saved {} cookie(s): {}",
+                    domain,
+                    state.cookies.len(),
+                    saved.path.display()
+                ));
+                formatter.hint(&format!(
+                    "Restore it in later runs with 'domguard auth restore {}'",
+                    domain
+                ));
+            }
+        }
+
+        AuthSubcommand::Restore { domain } => {
+            let state = crate::auth::restore(cdp, domain).await?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "success": true,
+                    "domain": domain,
+                    "url": state.url,
+                    "cookies": state.cookies.len(),
+                    "saved_at": state.saved_at
+                }));
+            } else {
+                formatter.success(&format!(
+                    "Restored login for {} ({} cookie(s), saved {})",
+                    domain,
+                    state.cookies.len(),
+                    state
+                        .saved_at
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_events(
+    cdp: &mut CdpConnection,
+    command: &EventsSubcommand,
+    formatter: &Formatter,
+) -> Result<()> {
+    use crate::events::WebhookConfig;
+
+    match command {
+        EventsSubcommand::Subscribe { url, on } => {
+            let mut config = WebhookConfig::open()?;
+            let hook = config.subscribe(url, on)?;
+            config.save_project()?;
+
+            if formatter.is_json() {
+                formatter.output_json(&hook);
+            } else {
+                let kinds: Vec<String> = hook.on.iter().map(ToString::to_string).collect();
+                formatter.success(&format!(
+                    "Subscribed {} ({}) to {}",
+                    hook.url,
+                    hook.id,
+                    kinds.join(", ")
+                ));
+                formatter.hint("Events are sent while 'domguard events daemon' runs");
+            }
+        }
+
+        EventsSubcommand::List => {
+            let config = WebhookConfig::open()?;
+
+            if formatter.is_json() {
+                formatter.output_json(&config.webhooks);
+            } else if config.webhooks.is_empty() {
+                println!("No webhook subscriptions");
+                formatter.hint("Use 'domguard events subscribe --url <url> --on navigation'");
+            } else {
+                formatter.header("Webhooks");
+                for hook in &config.webhooks {
+                    let kinds: Vec<String> = hook.on.iter().map(ToString::to_string).collect();
+                    println!("  {} {} [{}]", hook.id.cyan(), hook.url, kinds.join(", "));
+                }
+            }
+        }
+
+        EventsSubcommand::Unsubscribe { id } => {
+            let mut config = WebhookConfig::open()?;
+            if !config.unsubscribe(id) {
+                return Err(anyhow::anyhow!("No webhook subscription: {}", id));
+            }
+            config.save_project()?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({ "unsubscribed": id }));
+            } else {
+                formatter.success(&format!("Unsubscribed {}", id));
+            }
+        }
+
+        EventsSubcommand::Daemon => {
+            let config = WebhookConfig::subscribed()?;
+            cdp.connect().await?;
+
+            if !formatter.is_json() {
+                println!("{}", "Events daemon started".cyan().bold());
+                formatter.hint(&format!("{} webhook(s) subscribed", config.webhooks.len()));
+            }
+
+            crate::events::run_daemon(cdp, &config, &mut |delivery| {
+                let (event, hook) = (delivery.event, delivery.webhook);
+                if formatter.is_json() {
+                    formatter.output_json(&serde_json::json!({
+                        "event": event.event,
+                        "webhook": hook.id,
+                        "delivered": delivery.result.is_ok(),
+                        "error": delivery.result.as_ref().err().map(ToString::to_string),
+                    }));
+                } else {
+                    match &delivery.result {
+                        Ok(()) => println!("  {} {} -> {}", "→".green(), event.event, hook.id),
+                        Err(e) => formatter.warning(&e.to_string()),
+                    }
+                }
+            })
+            .await?;
+
+            formatter.warning("Page closed; events daemon stopped");
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_secrets(command: &SecretsSubcommand, formatter: &Formatter) -> Result<()> {
+    use crate::secrets::SecretStore;
+    use std::io::IsTerminal;
+
+    let mut store = SecretStore::open()?;
+
+    match command {
+        SecretsSubcommand::Set { name, value } => {
+            let value = match value {
+                Some(value) => value.clone(),
+                None => {
+                    let label = if std::io::stdin().is_terminal() {
+                        name.as_str()
+                    } else {
+                        ""
+                    };
+                    read_prompt_line(label, true)?
+                        .ok_or_else(|| anyhow::anyhow!("No value given for secret {}", name))?
+                }
+            };
+
+            store.set_with_user_key(name, &value)?;
+            store.save_project()?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "success": true,
+                    "action": "set",
+                    "name": name
+                }));
+            } else {
+                formatter.success(&format!("Stored secret: {}", name));
+                formatter.hint(&format!(
+                    "Reference it in workflows as ${{secret:{}}}",
+                    name
+                ));
+            }
+        }
+
+        SecretsSubcommand::List => {
+            let names: Vec<&String> = store.secrets.keys().collect();
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({ "secrets": names }));
+            } else if names.is_empty() {
+                println!("No secrets stored");
+            } else {
+                for name in names {
+                    println!("  {}", name);
+                }
+            }
+        }
+
+        SecretsSubcommand::Rm { name } => {
+            let removed = store.remove(name);
+            if removed {
+                store.save_project()?;
+            }
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "success": removed,
+                    "action": "rm",
+                    "name": name
+                }));
+            } else if removed {
+                formatter.success(&format!("Removed secret: {}", name));
+            } else {
+                println!("Secret not found: {}", name);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_network(
+    cdp: &mut CdpConnection,
+    command: &NetworkSubcommand,
+    formatter: &Formatter,
+) -> Result<()> {
+    use crate::network::{format_mock_rules, MockRule, Mocking, NetworkConfig};
+
+    let mut config = NetworkConfig::open();
+
+    match command {
+        NetworkSubcommand::Mock { action } => match action {
+            MockAction::Add {
+                pattern,
+                status,
+                body,
+                body_file,
+                headers,
+                delay,
+                abort,
+            } => {
+                let mut rule = MockRule::new(pattern);
+                rule.status = *status;
+                rule.body = match body_file {
+                    Some(path) => Some(std::fs::read_to_string(path)?),
+                    None => body.clone(),
+                };
+                rule.headers = headers.iter().cloned().collect();
+                rule.delay_ms = *delay;
+                rule.abort = *abort;
+                config.mocks.push(rule.clone());
+                config.save_project()?;
+
+                if formatter.is_json() {
+                    formatter.output_json(&serde_json::json!({
+                        "success": true,
+                        "action": "add",
+                        "rule": rule,
+                        "total": config.mocks.len()
+                    }));
+                } else {
+                    println!("Added mock rule: {}", pattern);
+                    formatter.hint("Use 'domguard network mock run' to apply the rules");
+                }
+            }
+
+            MockAction::List => {
+                if formatter.is_json() {
+                    formatter.output_json(&config.mocks);
+                } else if config.mocks.is_empty() {
+                    println!("No mock rules defined");
+                    formatter.hint("Use 'domguard network mock add <pattern>' to create one");
+                } else {
+                    formatter.header("Mock Rules");
+                    print!("{}", format_mock_rules(&config.mocks));
+                }
+            }
+
+            MockAction::Remove { index } => {
+                let removed = config.remove_mock(*index)?;
+                config.save_project()?;
+
+                if formatter.is_json() {
+                    formatter.output_json(&serde_json::json!({
+                        "success": true,
+                        "action": "remove",
+                        "rule": removed
+                    }));
+                } else {
+                    println!("Removed mock rule: {}", removed.pattern);
+                }
+            }
+
+            MockAction::Clear => {
+                let cleared = config.mocks.len();
+                config.mocks.clear();
+                config.save_project()?;
+
+                if formatter.is_json() {
+                    formatter.output_json(&serde_json::json!({
+                        "success": true,
+                        "action": "clear",
+                        "cleared": cleared
+                    }));
+                } else {
+                    println!("Cleared {} mock rule(s)", cleared);
+                }
+            }
+
+            MockAction::Run { file, duration } => {
+                let mocking = Mocking::start(cdp, file.as_deref()).await?;
+
+                if formatter.is_json() {
+                    formatter.output_json(&serde_json::json!({
+                        "success": true,
+                        "action": "mock",
+                        "rules": mocking.rules,
+                        "duration_ms": duration
+                    }));
+                } else {
+                    println!(
+                        "{}",
+                        format!("Mocking requests with {} rule(s)", mocking.rules)
+                            .green()
+                            .bold()
+                    );
+                    if *duration > 0 {
+                        println!("  Duration: {}ms", duration);
+                    } else {
+                        formatter.hint("Press Ctrl-C to stop mocking");
+                    }
+                }
+
+                mocking.run(cdp, *duration).await?;
+            }
+        },
+
+        NetworkSubcommand::Block {
+            patterns,
+            resources,
+        } => {
+            let filters = resources.filters();
+            config.block_all(patterns, &filters)?;
+            config.save_project()?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "success": true,
+                    "action": "block",
+                    "blocked": config.blocked,
+                    "blocked_resources": config.blocked_resources
+                }));
+            } else {
+                for pattern in patterns {
+                    println!("Blocked: {}", pattern);
+                }
+                for filter in &filters {
+                    println!("Blocked: {}", filter);
+                }
+                formatter
+                    .hint("Blocking is applied to every interact, inspire, and workflow command");
+            }
+        }
+
+        NetworkSubcommand::Unblock {
+            patterns,
+            resources,
+            all,
+        } => {
+            let filters = resources.filters();
+            if *all {
+                config.blocked.clear();
+                config.blocked_resources.clear();
+            } else {
+                config.unblock_all(patterns, &filters)?;
+            }
+            config.save_project()?;
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "success": true,
+                    "action": "unblock",
+                    "blocked": config.blocked,
+                    "blocked_resources": config.blocked_resources
+                }));
+            } else if *all {
+                println!("Removed all request blocking");
+            } else {
+                for pattern in patterns {
+                    println!("Unblocked: {}", pattern);
+                }
+                for filter in &filters {
+                    println!("Unblocked: {}", filter);
+                }
+            }
+        }
+
+        NetworkSubcommand::Blocked => {
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "blocked": config.blocked,
+                    "blocked_resources": config.blocked_resources,
+                    "url_patterns": config.blocked_url_patterns()
+                }));
+            } else {
+                formatter.header("Blocked Requests");
+                if config.blocked.is_empty() && config.blocked_resources.is_empty() {
+                    println!("  (nothing blocked)");
+                }
+                for pattern in &config.blocked {
+                    println!("  - {}", pattern);
+                }
+                for filter in &config.blocked_resources {
+                    println!("  - {} ({})", filter, filter.url_patterns().join(", "));
+                }
+            }
+        }
+
+        NetworkSubcommand::Headers { action } => {
+            match action {
+                HeadersAction::Set {
+                    headers,
+                    user_agent,
+                } => config.set_headers(headers, user_agent.as_deref())?,
+                HeadersAction::Unset { names, user_agent } => {
+                    config.unset_headers(names, *user_agent)?;
+                }
+                HeadersAction::Clear => {
+                    config.headers.clear();
+                    config.user_agent = None;
+                }
+                HeadersAction::List => {}
+            }
+
+            if !matches!(action, HeadersAction::List) {
+                config.save_project()?;
+            }
+
+            if formatter.is_json() {
+                formatter.output_json(&serde_json::json!({
+                    "headers": config.headers,
+                    "user_agent": config.user_agent
+                }));
+            } else {
+                formatter.header("Extra Headers");
+                let headers = config.sorted_headers();
+                if headers.is_empty() {
+                    println!("  (none)");
+                }
+                for (name, value) in headers {
+                    println!("  {}: {}", name, value);
+                }
+                if let Some(ua) = &config.user_agent {
+                    println!();
+                    println!("User-Agent: {}", ua);
+                }
+                if !matches!(action, HeadersAction::List) {
+                    formatter.hint(
+                        "Headers are applied to every interact, inspire, and workflow command",
+                    );
+                }
+            }
+        }
+
+        NetworkSubcommand::Auth { action } => match action {
+            AuthAction::Add {
+                domain,
+                basic,
+                bearer,
+            } => {
+                let credential = match (basic, bearer) {
+                    (Some(credential), _) => credential.clone(),
+                    (None, Some(token)) => crate::network::Credential::Bearer {
+                        token: token.clone(),
+                    },
+                    (None, None) => {
+                        anyhow::bail!("Specify --basic user:pass or --bearer <token>")
+                    }
+                };
+                let masked = credential.masked();
+                let domain = config.add_credential(domain, credential);
+                config.save_project()?;
+
+                if formatter.is_json() {
+                    formatter.output_json(&serde_json::json!({
+                        "success": true,
+                        "action": "add",
+                        "domain": domain,
+                        "credential": masked
+                    }));
+                } else {
+                    println!("Stored {} credentials for: {}", masked, domain);
+                    formatter.warning(&format!(
+                        "Credentials are stored in plain text in {}",
+                        NetworkConfig::path().display()
+                    ));
+                }
+            }
+
+            AuthAction::List => {
+                let entries = config.masked_credentials();
+
+                if formatter.is_json() {
+                    let list: Vec<_> = entries
+                        .iter()
+                        .map(|(domain, credential)| {
+                            serde_json::json!({ "domain": domain, "credential": credential })
+                        })
+                        .collect();
+                    formatter.output_json(&list);
+                } else if entries.is_empty() {
+                    println!("No stored credentials");
+                    formatter.hint(
+                        "Add one with 'domguard network auth add <domain> --basic user:pass'",
+                    );
+                } else {
+                    formatter.header("Stored Credentials");
+                    for (domain, credential) in &entries {
+                        println!("  - {}: {}", domain, credential);
+                    }
+                }
+            }
+
+            AuthAction::Remove { domain } => {
+                let removed = config.remove_credential(domain);
+                if removed {
+                    config.save_project()?;
+                }
+
+                if formatter.is_json() {
+                    formatter.output_json(&serde_json::json!({
+                        "success": removed,
+                        "action": "remove",
+                        "domain": domain
+                    }));
+                } else if removed {
+                    println!("Removed credentials for: {}", domain);
+                } else {
+                    formatter.warning(&format!("No credentials stored for: {}", domain));
+                }
+            }
+        },
+    }
+
+    Ok(())
+}
@@ -0,0 +1,805 @@
+//! Network control for DOMGuard
+//!
+//! Request mocking rules and per-domain credentials applied through the CDP
+//! Fetch domain, request blocking applied through `Network.setBlockedURLs`,
+//! and extra headers / user agent overrides applied after each connection.
+//! Settings are stored in `.domguard/network.toml`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// A mock rule: requests whose URL matches `pattern` are answered locally
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MockRule {
+    /// URL pattern (`*` matches any run of characters, `?` matches one)
+    pub pattern: String,
+
+    /// HTTP status code to respond with
+    #[serde(default = "default_status")]
+    pub status: u16,
+
+    /// Response body
+    #[serde(default)]
+    pub body: Option<String>,
+
+    /// Response headers
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    /// Delay before responding in milliseconds
+    #[serde(default)]
+    pub delay_ms: u64,
+
+    /// Abort the request with a network error instead of responding
+    #[serde(default)]
+    pub abort: bool,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+impl MockRule {
+    /// Create a rule that responds with an empty 200
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            status: default_status(),
+            body: None,
+            headers: HashMap::new(),
+            delay_ms: 0,
+            abort: false,
+        }
+    }
+
+    /// Check whether a request URL is handled by this rule
+    pub fn matches(&self, url: &str) -> bool {
+        glob_matches(&self.pattern, url)
+    }
+}
+
+/// Resource categories that can be blocked as a group
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceFilter {
+    Images,
+    Fonts,
+    Media,
+    Stylesheets,
+}
+
+impl ResourceFilter {
+    /// URL patterns covering this resource category
+    ///
+    /// `Network.setBlockedURLs` only matches URLs, so categories are expressed
+    /// as file extension patterns (with a trailing `*` for query strings).
+    pub fn url_patterns(self) -> &'static [&'static str] {
+        match self {
+            Self::Images => &[
+                "*.png*", "*.jpg*", "*.jpeg*", "*.gif*", "*.webp*", "*.avif*", "*.svg*", "*.ico*",
+            ],
+            Self::Fonts => &["*.woff*", "*.woff2*", "*.ttf*", "*.otf*", "*.eot*"],
+            Self::Media => &["*.mp4*", "*.webm*", "*.ogg*", "*.mp3*", "*.wav*", "*.m4a*"],
+            Self::Stylesheets => &["*.css*"],
+        }
+    }
+}
+
+impl std::fmt::Display for ResourceFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Images => write!(f, "images"),
+            Self::Fonts => write!(f, "fonts"),
+            Self::Media => write!(f, "media"),
+            Self::Stylesheets => write!(f, "stylesheets"),
+        }
+    }
+}
+
+/// Network configuration persisted in `.domguard/network.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Request mock rules
+    #[serde(default)]
+    pub mocks: Vec<MockRule>,
+
+    /// Blocked URL patterns (e.g., "*.doubleclick.net*")
+    #[serde(default)]
+    pub blocked: Vec<String>,
+
+    /// Blocked resource categories
+    #[serde(default)]
+    pub blocked_resources: Vec<ResourceFilter>,
+
+    /// Extra HTTP headers sent with every request
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    /// User agent override
+    #[serde(default)]
+    pub user_agent: Option<String>,
+
+    /// Credentials keyed by domain pattern (e.g., "staging.example.com", "*.example.com")
+    #[serde(default)]
+    pub credentials: HashMap<String, Credential>,
+}
+
+/// Credentials used to answer authentication for a domain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Credential {
+    /// HTTP Basic/Digest credentials (answers `Fetch.authRequired` challenges)
+    Basic { username: String, password: String },
+    /// Bearer token (sent as an `Authorization` header)
+    Bearer { token: String },
+}
+
+impl Credential {
+    /// Short description with secrets masked
+    pub fn masked(&self) -> String {
+        match self {
+            Self::Basic { username, .. } => format!("basic ({}:****)", username),
+            Self::Bearer { .. } => "bearer (****)".to_string(),
+        }
+    }
+
+    /// Whether any field contains `marker`, such as `"${secret:"`
+    fn references(&self, marker: &str) -> bool {
+        match self {
+            Self::Basic { username, password } => {
+                username.contains(marker) || password.contains(marker)
+            }
+            Self::Bearer { token } => token.contains(marker),
+        }
+    }
+
+    /// Copy with `${env:...}` and `${secret:...}` placeholders expanded
+    pub fn expand(&self, secrets: &BTreeMap<String, String>) -> Result<Self> {
+        let expand = |text: &str| crate::secrets::expand_placeholders(text, secrets);
+        Ok(match self {
+            Self::Basic { username, password } => Self::Basic {
+                username: expand(username)?,
+                password: expand(password)?,
+            },
+            Self::Bearer { token } => Self::Bearer {
+                token: expand(token)?,
+            },
+        })
+    }
+}
+
+impl NetworkConfig {
+    /// Add a blocked URL pattern
+    pub fn block(&mut self, pattern: &str) {
+        if !self.blocked.contains(&pattern.to_string()) {
+            self.blocked.push(pattern.to_string());
+        }
+    }
+
+    /// Remove a blocked URL pattern
+    pub fn unblock(&mut self, pattern: &str) {
+        self.blocked.retain(|p| p != pattern);
+    }
+
+    /// Add a blocked resource category
+    pub fn block_resource(&mut self, filter: ResourceFilter) {
+        if !self.blocked_resources.contains(&filter) {
+            self.blocked_resources.push(filter);
+        }
+    }
+
+    /// Remove a blocked resource category
+    pub fn unblock_resource(&mut self, filter: ResourceFilter) {
+        self.blocked_resources.retain(|f| *f != filter);
+    }
+
+    /// All URL patterns to pass to `Network.setBlockedURLs`
+    pub fn blocked_url_patterns(&self) -> Vec<String> {
+        let mut patterns = self.blocked.clone();
+        for filter in &self.blocked_resources {
+            for pattern in filter.url_patterns() {
+                if !patterns.iter().any(|p| p == pattern) {
+                    patterns.push((*pattern).to_string());
+                }
+            }
+        }
+        patterns
+    }
+
+    /// Where the settings are stored
+    pub fn path() -> PathBuf {
+        crate::config::Config::find_domguard_dir()
+            .unwrap_or_else(crate::config::Config::domguard_dir)
+            .join("network.toml")
+    }
+
+    /// Load network config from file
+    pub fn load(config_path: &PathBuf) -> Result<Self> {
+        if config_path.exists() {
+            let content = std::fs::read_to_string(config_path)?;
+            let config: NetworkConfig = toml::from_str(&content)?;
+            Ok(config)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Save network config to file
+    pub fn save(&self, config_path: &PathBuf) -> Result<()> {
+        let content = toml::to_string_pretty(self)?;
+        std::fs::write(config_path, content)?;
+        Ok(())
+    }
+
+    /// The project's settings, or none when the file is missing or unreadable
+    pub fn open() -> Self {
+        Self::load(&Self::path()).unwrap_or_default()
+    }
+
+    /// Save the project's settings, creating `.domguard` if needed
+    pub fn save_project(&self) -> Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.save(&path)
+    }
+
+    /// Remove mock rule `index`, numbered from 1 as `mock list` shows them
+    pub fn remove_mock(&mut self, index: usize) -> Result<MockRule> {
+        if index == 0 || index > self.mocks.len() {
+            anyhow::bail!(
+                "No mock rule #{} ({} rule(s) defined)",
+                index,
+                self.mocks.len()
+            );
+        }
+        Ok(self.mocks.remove(index - 1))
+    }
+
+    /// Block URL patterns and resource categories, at least one of them
+    pub fn block_all(&mut self, patterns: &[String], filters: &[ResourceFilter]) -> Result<()> {
+        if patterns.is_empty() && filters.is_empty() {
+            anyhow::bail!(
+                "Specify at least one URL pattern or resource type (--images, --fonts, --media, --stylesheets)"
+            );
+        }
+        for pattern in patterns {
+            self.block(pattern);
+        }
+        for filter in filters {
+            self.block_resource(*filter);
+        }
+        Ok(())
+    }
+
+    /// Unblock URL patterns and resource categories, at least one of them
+    pub fn unblock_all(&mut self, patterns: &[String], filters: &[ResourceFilter]) -> Result<()> {
+        if patterns.is_empty() && filters.is_empty() {
+            anyhow::bail!("Specify URL patterns, resource types, or --all to remove blocking");
+        }
+        for pattern in patterns {
+            self.unblock(pattern);
+        }
+        for filter in filters {
+            self.unblock_resource(*filter);
+        }
+        Ok(())
+    }
+
+    /// Set extra headers and the user agent, at least one of them
+    pub fn set_headers(
+        &mut self,
+        headers: &[(String, String)],
+        user_agent: Option<&str>,
+    ) -> Result<()> {
+        if headers.is_empty() && user_agent.is_none() {
+            anyhow::bail!("Specify at least one header (\"Name: value\") or --user-agent");
+        }
+        for (name, value) in headers {
+            self.headers.insert(name.clone(), value.clone());
+        }
+        if let Some(user_agent) = user_agent {
+            self.user_agent = Some(user_agent.to_string());
+        }
+        Ok(())
+    }
+
+    /// Remove extra headers, and the user agent with `user_agent`
+    pub fn unset_headers(&mut self, names: &[String], user_agent: bool) -> Result<()> {
+        if names.is_empty() && !user_agent {
+            anyhow::bail!("Specify header names or --user-agent to remove");
+        }
+        for name in names {
+            self.headers.remove(name);
+        }
+        if user_agent {
+            self.user_agent = None;
+        }
+        Ok(())
+    }
+
+    /// Extra headers sorted by name
+    pub fn sorted_headers(&self) -> Vec<(&String, &String)> {
+        let mut headers: Vec<_> = self.headers.iter().collect();
+        headers.sort();
+        headers
+    }
+
+    /// Store credentials for a domain, returning the domain as stored
+    pub fn add_credential(&mut self, domain: &str, credential: Credential) -> String {
+        let domain = domain.to_lowercase();
+        self.credentials.insert(domain.clone(), credential);
+        domain
+    }
+
+    /// Remove a domain's credentials, returning whether there were any
+    pub fn remove_credential(&mut self, domain: &str) -> bool {
+        self.credentials.remove(&domain.to_lowercase()).is_some()
+    }
+
+    /// Stored domains with their credentials masked, sorted by domain
+    pub fn masked_credentials(&self) -> Vec<(String, String)> {
+        let mut entries: Vec<_> = self
+            .credentials
+            .iter()
+            .map(|(domain, credential)| (domain.clone(), credential.masked()))
+            .collect();
+        entries.sort();
+        entries
+    }
+}
+
+/// Requests being answered by mock rules
+pub struct Mocking {
+    /// How many rules are applied
+    pub rules: usize,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl Mocking {
+    /// Start mocking with the rules in `file`, or the saved ones
+    pub async fn start(cdp: &mut crate::cdp::CdpConnection, file: Option<&Path>) -> Result<Self> {
+        let config = NetworkConfig::open();
+        let rules = match file {
+            Some(path) => load_mock_rules(path)?,
+            None => config.mocks,
+        };
+        if rules.is_empty() {
+            anyhow::bail!(
+                "No mock rules defined. Add one with 'domguard network mock add <pattern>'"
+            );
+        }
+
+        cdp.connect().await?;
+        let count = rules.len();
+        let handle = cdp
+            .start_request_interception(rules, config.credentials)
+            .await?;
+        Ok(Self {
+            rules: count,
+            handle,
+        })
+    }
+
+    /// Keep mocking for `duration_ms`, or until the page goes away when it is 0
+    pub async fn run(self, cdp: &crate::cdp::CdpConnection, duration_ms: u64) -> Result<()> {
+        if duration_ms > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(duration_ms)).await;
+            self.handle.abort();
+            cdp.stop_request_interception().await?;
+        } else {
+            // Runs until the page goes away or the process is interrupted
+            let _ = self.handle.await;
+        }
+        Ok(())
+    }
+}
+
+/// Re-apply the persisted blocking, headers and user agent after connecting
+///
+/// CDP network overrides only last as long as the connection that set them,
+/// so each command that drives the page applies them again.
+pub async fn apply_settings(cdp: &crate::cdp::CdpConnection) -> Result<()> {
+    let network = NetworkConfig::load(&NetworkConfig::path()).unwrap_or_default();
+
+    let blocked = network.blocked_url_patterns();
+    if !blocked.is_empty() {
+        cdp.set_blocked_urls(blocked).await?;
+    }
+    if !network.headers.is_empty() {
+        cdp.set_extra_headers(&network.headers).await?;
+    }
+    if let Some(user_agent) = &network.user_agent {
+        cdp.set_user_agent(user_agent).await?;
+    }
+
+    Ok(())
+}
+
+/// Load mock rules from a TOML or JSON file
+///
+/// TOML files use `[[mocks]]` tables. JSON files may contain either an array
+/// of rules or an object with a `mocks` array.
+pub fn load_mock_rules(path: &Path) -> Result<Vec<MockRule>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read mock rules: {}", path.display()))?;
+
+    if path.extension().is_some_and(|e| e == "json") {
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse mock rules: {}", path.display()))?;
+        let rules = if value.is_array() {
+            serde_json::from_value(value)?
+        } else {
+            serde_json::from_value::<NetworkConfig>(value)?.mocks
+        };
+        Ok(rules)
+    } else {
+        let config: NetworkConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse mock rules: {}", path.display()))?;
+        Ok(config.mocks)
+    }
+}
+
+/// Parse a header in `Name: value` format
+pub fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| "Header must be in format: Name: value".to_string())?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Header name cannot be empty".to_string());
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// Parse basic auth credentials in `user:pass` format
+pub fn parse_basic_auth(s: &str) -> Result<Credential, String> {
+    let (username, password) = s
+        .split_once(':')
+        .ok_or_else(|| "Credentials must be in format: user:pass".to_string())?;
+    if username.is_empty() {
+        return Err("Username cannot be empty".to_string());
+    }
+    Ok(Credential::Basic {
+        username: username.to_string(),
+        password: password.to_string(),
+    })
+}
+
+/// Expand secret and environment placeholders in stored credentials
+///
+/// The secret store is only decrypted when a credential references it.
+pub fn resolve_credentials(
+    credentials: HashMap<String, Credential>,
+) -> Result<HashMap<String, Credential>> {
+    if !credentials
+        .values()
+        .any(|credential| credential.references("${"))
+    {
+        return Ok(credentials);
+    }
+
+    let secrets = if credentials
+        .values()
+        .any(|credential| credential.references("${secret:"))
+    {
+        let store = crate::secrets::SecretStore::open()?;
+        store.decrypt_all(&crate::secrets::load_or_create_key(
+            &crate::secrets::default_key_path(),
+        )?)?
+    } else {
+        BTreeMap::new()
+    };
+
+    credentials
+        .into_iter()
+        .map(|(domain, credential)| {
+            let expanded = credential
+                .expand(&secrets)
+                .with_context(|| format!("Invalid credentials for {}", domain))?;
+            Ok((domain, expanded))
+        })
+        .collect()
+}
+
+/// Find the credential whose domain pattern matches a URL or origin
+///
+/// The most specific entry wins: the exact host, then the longest matching
+/// wildcard, so `*.staging.example.com` beats `*.example.com`.
+pub fn find_credential<'a>(
+    credentials: &'a HashMap<String, Credential>,
+    url: &str,
+) -> Option<&'a Credential> {
+    let domain = crate::site_instructions::extract_domain(url)?;
+
+    if let Some(credential) = credentials.get(&domain) {
+        return Some(credential);
+    }
+    credentials
+        .iter()
+        .filter(|(pattern, _)| {
+            crate::site_instructions::matches_domain_pattern(&pattern.to_lowercase(), &domain)
+        })
+        // Patterns of equal length fall back to name order, not hash order
+        .max_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+        .map(|(_, credential)| credential)
+}
+
+/// Match a URL against a CDP-style wildcard pattern
+///
+/// The whole URL must match; `*` matches zero or more characters and `?`
+/// matches exactly one.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<usize> = None;
+    let mut star_ti = 0;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some(pi);
+            star_ti = ti;
+            pi += 1;
+        } else if let Some(s) = star {
+            pi = s + 1;
+            star_ti += 1;
+            ti = star_ti;
+        } else {
+            return false;
+        }
+    }
+
+    while pi < p.len() && p[pi] == '*' {
+        pi += 1;
+    }
+
+    pi == p.len()
+}
+
+/// Format mock rules for display
+pub fn format_mock_rules(rules: &[MockRule]) -> String {
+    let mut output = String::new();
+
+    for (i, rule) in rules.iter().enumerate() {
+        let response = if rule.abort {
+            "abort".to_string()
+        } else {
+            let size = rule.body.as_ref().map_or(0, String::len);
+            format!("{} ({} bytes)", rule.status, size)
+        };
+        let _ = writeln!(output, "  {}. {} -> {}", i + 1, rule.pattern, response);
+
+        if rule.delay_ms > 0 {
+            let _ = writeln!(output, "     Delay: {}ms", rule.delay_ms);
+        }
+        for (name, value) in &rule.headers {
+            let _ = writeln!(output, "     {}: {}", name, value);
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("*", "https://example.com/"));
+        assert!(glob_matches("*/api/*", "https://example.com/api/users"));
+        assert!(glob_matches(
+            "https://example.com/?",
+            "https://example.com/a"
+        ));
+        assert!(!glob_matches("*/api/*", "https://example.com/users"));
+        assert!(!glob_matches("https://example.com", "https://example.com/"));
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("Content-Type: application/json"),
+            Ok(("Content-Type".to_string(), "application/json".to_string()))
+        );
+        assert!(parse_header("no-colon").is_err());
+        assert!(parse_header(": value").is_err());
+    }
+
+    #[test]
+    fn test_parse_mock_rules_toml() {
+        let toml_str = r#"
+[[mocks]]
+pattern = "*/api/users*"
+status = 500
+body = '{"error": "boom"}'
+delay_ms = 250
+
+[[mocks]]
+pattern = "*.png"
+abort = true
+"#;
+        let config: NetworkConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.mocks.len(), 2);
+        assert_eq!(config.mocks[0].status, 500);
+        assert_eq!(config.mocks[0].delay_ms, 250);
+        assert_eq!(config.mocks[1].status, 200);
+        assert!(config.mocks[1].abort);
+        assert!(config.mocks[1].matches("https://example.com/logo.png"));
+    }
+
+    #[test]
+    fn test_blocked_url_patterns() {
+        let mut config = NetworkConfig::default();
+        config.block("*.doubleclick.net*");
+        config.block("*.doubleclick.net*");
+        config.block_resource(ResourceFilter::Fonts);
+
+        let patterns = config.blocked_url_patterns();
+        assert_eq!(patterns[0], "*.doubleclick.net*");
+        assert!(patterns.contains(&"*.woff2*".to_string()));
+        assert_eq!(
+            patterns.len(),
+            1 + ResourceFilter::Fonts.url_patterns().len()
+        );
+
+        config.unblock("*.doubleclick.net*");
+        config.unblock_resource(ResourceFilter::Fonts);
+        assert!(config.blocked_url_patterns().is_empty());
+    }
+
+    #[test]
+    fn test_headers_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("network.toml");
+
+        let mut config = NetworkConfig::default();
+        config
+            .headers
+            .insert("X-Debug".to_string(), "1".to_string());
+        config.user_agent = Some("DOMGuard-Test/1.0".to_string());
+        config.block("*.doubleclick.net*");
+        config.mocks.push(MockRule::new("*/health"));
+        config.save(&path).unwrap();
+
+        let loaded = NetworkConfig::load(&path).unwrap();
+        assert_eq!(loaded.headers.get("X-Debug").map(String::as_str), Some("1"));
+        assert_eq!(loaded.user_agent.as_deref(), Some("DOMGuard-Test/1.0"));
+        assert_eq!(loaded.blocked, vec!["*.doubleclick.net*".to_string()]);
+        assert_eq!(loaded.mocks.len(), 1);
+    }
+
+    #[test]
+    fn test_edit_settings() {
+        let mut config = NetworkConfig::default();
+        assert!(config.block_all(&[], &[]).is_err());
+        config
+            .block_all(&["*.ads.com*".to_string()], &[ResourceFilter::Fonts])
+            .unwrap();
+        config
+            .unblock_all(&["*.ads.com*".to_string()], &[])
+            .unwrap();
+        assert!(config.blocked.is_empty());
+        assert_eq!(config.blocked_resources, [ResourceFilter::Fonts]);
+
+        config.mocks.push(MockRule::new("*/api/*"));
+        assert!(config.remove_mock(0).is_err());
+        assert_eq!(config.remove_mock(1).unwrap().pattern, "*/api/*");
+
+        let domain = config.add_credential(
+            "Staging.Example.com",
+            Credential::Bearer {
+                token: "t".to_string(),
+            },
+        );
+        assert_eq!(domain, "staging.example.com");
+        assert_eq!(
+            config.masked_credentials(),
+            [(domain.clone(), "bearer (****)".to_string())]
+        );
+        assert!(config.remove_credential("STAGING.example.com"));
+        assert!(!config.remove_credential(&domain));
+    }
+
+    #[test]
+    fn test_find_credential() {
+        let mut credentials = HashMap::new();
+        credentials.insert(
+            "*.example.com".to_string(),
+            Credential::Bearer {
+                token: "abc".to_string(),
+            },
+        );
+        credentials.insert(
+            "staging.example.com".to_string(),
+            parse_basic_auth("admin:s3cret:x").unwrap(),
+        );
+        credentials.insert(
+            "*.api.example.com".to_string(),
+            Credential::Bearer {
+                token: "api".to_string(),
+            },
+        );
+
+        assert_eq!(
+            find_credential(&credentials, "https://staging.example.com/login"),
+            Some(&Credential::Basic {
+                username: "admin".to_string(),
+                password: "s3cret:x".to_string(),
+            })
+        );
+        assert!(matches!(
+            find_credential(&credentials, "https://api.example.com"),
+            Some(Credential::Bearer { .. })
+        ));
+        // The longest wildcard wins whatever the map's iteration order
+        assert_eq!(
+            find_credential(&credentials, "https://v2.api.example.com/users"),
+            Some(&Credential::Bearer {
+                token: "api".to_string(),
+            })
+        );
+        assert!(find_credential(&credentials, "https://other.org/").is_none());
+        // A wildcard doesn't reach domains that merely end in the same letters
+        assert!(find_credential(&credentials, "https://evilexample.com/").is_none());
+        assert!(parse_basic_auth("nocolon").is_err());
+    }
+
+    #[test]
+    fn test_credential_expand() {
+        let mut secrets = BTreeMap::new();
+        secrets.insert("STAGING_PASSWORD".to_string(), "hunter2".to_string());
+
+        let credential = Credential::Basic {
+            username: "admin".to_string(),
+            password: "${secret:STAGING_PASSWORD}".to_string(),
+        };
+        assert!(credential.references("${secret:"));
+        assert_eq!(
+            credential.expand(&secrets).unwrap(),
+            Credential::Basic {
+                username: "admin".to_string(),
+                password: "hunter2".to_string(),
+            }
+        );
+
+        let missing = Credential::Bearer {
+            token: "${secret:NOPE}".to_string(),
+        };
+        assert!(missing.expand(&secrets).is_err());
+
+        // Credentials without placeholders pass through without opening the store
+        let mut credentials = HashMap::new();
+        credentials.insert(
+            "example.com".to_string(),
+            Credential::Bearer {
+                token: "abc".to_string(),
+            },
+        );
+        assert_eq!(
+            resolve_credentials(credentials.clone()).unwrap(),
+            credentials
+        );
+    }
+
+    #[test]
+    fn test_load_mock_rules_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mocks.json");
+        std::fs::write(&path, r#"[{"pattern": "*/health", "body": "ok"}]"#).unwrap();
+
+        let rules = load_mock_rules(&path).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].body.as_deref(), Some("ok"));
+        assert_eq!(rules[0].status, 200);
+    }
+}
@@ -1,11 +1,15 @@
 //! Output formatting for DOMGuard
 //!
-//! Provides human-readable and JSON output modes
+//! Provides human-readable and machine-readable (JSON, YAML, TOML) output modes
 
 use colored::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 
 /// Output format mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,6 +18,41 @@ pub enum OutputFormat {
     Json,
 }
 
+/// Encoding for machine-readable output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataFormat {
+    #[default]
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl std::fmt::Display for DataFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json => write!(f, "json"),
+            Self::Yaml => write!(f, "yaml"),
+            Self::Toml => write!(f, "toml"),
+        }
+    }
+}
+
+impl std::str::FromStr for DataFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            "toml" => Ok(Self::Toml),
+            other => Err(format!(
+                "Unknown format \"{}\" (expected json, yaml or toml)",
+                other
+            )),
+        }
+    }
+}
+
 /// Result wrapper for consistent output
 #[derive(Debug, Serialize)]
 pub struct CommandResult<T: Serialize> {
@@ -22,8 +61,45 @@ pub struct CommandResult<T: Serialize> {
     pub data: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Error code, retryability, selector and suggestions for a failed command
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub error_details: Option<ErrorDetails>,
+    /// Time spent running the command; with a `timing` breakdown this is
+    /// `resolve_ms + action_ms + wait_ms`, leaving out connecting
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timing_ms: Option<u64>,
+    /// Where `timing_ms` went, plus connecting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<Timing>,
+}
+
+/// Phases of an interact command, in milliseconds
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Timing {
+    /// Connecting to the browser (absent when no connection was made)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<u64>,
+    /// Site rules and selector aliases, before the action
+    pub resolve_ms: u64,
+    /// The action itself, including self-correction retries
+    pub action_ms: u64,
+    /// Site `required_waits`, `--then-*` follow-ups and popups
+    pub wait_ms: u64,
+    /// Everything above: `timing_ms` plus `connect_ms`
+    pub total_ms: u64,
+}
+
+impl Timing {
+    /// Split `elapsed` (the command, without connecting) into its phases
+    pub fn new(connect_ms: Option<u64>, resolve_ms: u64, wait_ms: u64, elapsed: u64) -> Self {
+        Self {
+            connect_ms,
+            resolve_ms,
+            action_ms: elapsed.saturating_sub(resolve_ms + wait_ms),
+            wait_ms,
+            total_ms: elapsed + connect_ms.unwrap_or(0),
+        }
+    }
 }
 
 impl<T: Serialize> CommandResult<T> {
@@ -32,30 +108,114 @@ impl<T: Serialize> CommandResult<T> {
             success: true,
             data: Some(data),
             error: None,
+            error_details: None,
             timing_ms: None,
+            timing: None,
         }
     }
 
-    pub fn with_timing(mut self, ms: u64) -> Self {
-        self.timing_ms = Some(ms);
-        self
-    }
-}
-
-impl CommandResult<()> {
     pub fn error(msg: impl Into<String>) -> Self {
         Self {
             success: false,
             data: None,
             error: Some(msg.into()),
+            error_details: None,
             timing_ms: None,
+            timing: None,
         }
     }
+
+    #[must_use]
+    pub fn with_timing(mut self, ms: u64) -> Self {
+        self.timing_ms = Some(ms);
+        self
+    }
+
+    #[must_use]
+    pub fn with_timing_breakdown(mut self, timing: Timing) -> Self {
+        self.timing_ms = Some(timing.resolve_ms + timing.action_ms + timing.wait_ms);
+        self.timing = Some(timing);
+        self
+    }
+
+    #[must_use]
+    pub fn with_error_details(mut self, details: ErrorDetails) -> Self {
+        self.error_details = Some(details);
+        self
+    }
+}
+
+/// Stable error codes in JSON output, for agents to branch on instead of messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    ElementNotFound,
+    ElementNotVisible,
+    ElementNotInteractable,
+    ClickIntercepted,
+    StaleElement,
+    Timeout,
+    NetworkError,
+    NavBlocked,
+    ActionForbidden,
+    BudgetExceeded,
+    SensitiveAction,
+    ChromeUnreachable,
+    RemoteNotAllowed,
+    CaptchaDetected,
+    AuthRequired,
+    UnexpectedDialog,
+    PageChanged,
+    JavascriptError,
+    InvalidArgument,
+    Unknown,
+}
+
+impl ErrorCode {
+    /// Whether running the same command again may succeed without changes
+    pub fn retryable(self) -> bool {
+        matches!(
+            self,
+            Self::ElementNotFound
+                | Self::ElementNotVisible
+                | Self::ElementNotInteractable
+                | Self::ClickIntercepted
+                | Self::StaleElement
+                | Self::Timeout
+                | Self::NetworkError
+                | Self::ChromeUnreachable
+                | Self::UnexpectedDialog
+                | Self::PageChanged
+        )
+    }
+}
+
+/// Machine-readable part of an error result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorDetails {
+    pub code: ErrorCode,
+    pub retryable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
 }
 
 /// Output formatter
 pub struct Formatter {
     format: OutputFormat,
+    /// Encoding of machine-readable output
+    data_format: DataFormat,
+    /// Suppress human-mode chatter (success lines, warnings, hints, headers)
+    quiet: bool,
+    /// Emit one compact JSON document per line
+    ndjson: bool,
+    /// Write JSON results here instead of stdout
+    output_file: Option<PathBuf>,
+    /// Whether `output_file` has been written this run (later writes append)
+    wrote_file: AtomicBool,
+    /// Keep machine output here instead of writing it (see [`Formatter::capturing`])
+    captured: Option<Mutex<Vec<String>>>,
 }
 
 impl Formatter {
@@ -66,29 +226,160 @@ impl Formatter {
             } else {
                 OutputFormat::Human
             },
+            data_format: DataFormat::Json,
+            quiet: false,
+            ndjson: false,
+            output_file: None,
+            wrote_file: AtomicBool::new(false),
+            captured: None,
         }
     }
 
+    /// Suppress human chatter; results and errors are still printed
+    #[must_use]
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Encode machine-readable output as JSON, YAML or TOML (implies machine output)
+    #[must_use]
+    pub fn with_format(mut self, format: Option<DataFormat>) -> Self {
+        if let Some(format) = format {
+            self.data_format = format;
+            self.format = OutputFormat::Json;
+        }
+        self
+    }
+
+    /// Newline-delimited JSON (implies JSON output, whatever `--format` says)
+    #[must_use]
+    pub fn with_ndjson(mut self, ndjson: bool) -> Self {
+        if ndjson {
+            self.ndjson = true;
+            self.format = OutputFormat::Json;
+        }
+        self
+    }
+
+    /// Write JSON results to a file (implies JSON output)
+    #[must_use]
+    pub fn with_output_file(mut self, path: Option<PathBuf>) -> Self {
+        if path.is_some() {
+            self.format = OutputFormat::Json;
+        }
+        self.output_file = path;
+        self
+    }
+
+    /// Keep each JSON document in memory instead of printing it (implies
+    /// compact JSON output), for embedders that want results back as data
+    #[must_use]
+    pub fn capturing(mut self) -> Self {
+        self.format = OutputFormat::Json;
+        self.data_format = DataFormat::Json;
+        self.ndjson = true;
+        self.captured = Some(Mutex::new(Vec::new()));
+        self
+    }
+
+    /// Documents captured so far, oldest first, leaving none behind
+    pub fn take_captured(&self) -> Vec<String> {
+        self.captured
+            .as_ref()
+            .map(|captured| {
+                std::mem::take(
+                    &mut *captured
+                        .lock()
+                        .unwrap_or_else(std::sync::PoisonError::into_inner),
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether output is machine-readable (in any `DataFormat`)
     pub fn is_json(&self) -> bool {
         self.format == OutputFormat::Json
     }
 
+    /// Whether items should be streamed one JSON line at a time as they arrive
+    pub fn is_ndjson(&self) -> bool {
+        self.ndjson
+    }
+
+    /// Whether human chatter is suppressed
+    fn is_chatty(&self) -> bool {
+        self.format == OutputFormat::Human && !self.quiet
+    }
+
+    /// Write a document to stdout or the output file in the chosen format
+    fn emit_json<T: Serialize + ?Sized>(&self, value: &T) {
+        let text = match self.data_format {
+            _ if self.ndjson => serde_json::to_string(value).unwrap_or_default(),
+            DataFormat::Json => serde_json::to_string_pretty(value).unwrap_or_default(),
+            DataFormat::Yaml => serde_json::to_value(value)
+                .map(|value| to_yaml(&value))
+                .unwrap_or_default(),
+            DataFormat::Toml => serde_json::to_value(value)
+                .ok()
+                .and_then(|value| to_toml(&value))
+                .unwrap_or_default(),
+        };
+        self.write_line(&text);
+    }
+
+    /// Write machine output to stdout or the output file
+    fn write_line(&self, text: &str) {
+        if let Some(captured) = &self.captured {
+            captured
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .push(text.to_string());
+            return;
+        }
+        let Some(path) = &self.output_file else {
+            println!("{}", text);
+            // Streamed lines must reach pipes without waiting for a full buffer
+            let _ = std::io::stdout().flush();
+            return;
+        };
+        // The first write of a run replaces the file, later ones append
+        let append = self.wrote_file.swap(true, Ordering::Relaxed);
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", text));
+        if let Err(e) = written {
+            eprintln!(
+                "{} Failed to write {}: {}",
+                "Error:".red().bold(),
+                path.display(),
+                e
+            );
+        }
+    }
+
+    /// Stream one item as a JSON line, whatever the data format
+    pub fn stream_json<T: Serialize>(&self, item: &T) {
+        self.write_line(&serde_json::to_string(item).unwrap_or_default());
+    }
+
     /// Output a result
     pub fn output<T: Serialize + std::fmt::Display>(&self, result: &CommandResult<T>) {
         match self.format {
-            OutputFormat::Json => {
-                println!(
-                    "{}",
-                    serde_json::to_string_pretty(result).unwrap_or_default()
-                );
-            }
+            OutputFormat::Json => self.emit_json(result),
             OutputFormat::Human => {
                 if result.success {
                     if let Some(data) = &result.data {
                         println!("{}", data);
                     }
                     if let Some(ms) = result.timing_ms {
-                        println!("{} {}ms", "Completed in".dimmed(), ms);
+                        if !self.quiet {
+                            println!("{} {}ms", "Completed in".dimmed(), ms);
+                        }
                     }
                 } else if let Some(err) = &result.error {
                     eprintln!("{} {}", "Error:".red().bold(), err);
@@ -100,9 +391,7 @@ impl Formatter {
     /// Output raw JSON data
     pub fn output_json<T: Serialize>(&self, data: &T) {
         match self.format {
-            OutputFormat::Json => {
-                println!("{}", serde_json::to_string_pretty(data).unwrap_or_default());
-            }
+            OutputFormat::Json => self.emit_json(data),
             OutputFormat::Human => {
                 println!("{}", serde_json::to_string_pretty(data).unwrap_or_default());
             }
@@ -111,7 +400,7 @@ impl Formatter {
 
     /// Print a section header
     pub fn header(&self, text: &str) {
-        if self.format == OutputFormat::Human {
+        if self.is_chatty() {
             println!("\n{}", text.cyan().bold());
             println!("{}", "─".repeat(text.len()).dimmed());
         }
@@ -133,14 +422,14 @@ impl Formatter {
 
     /// Print success message
     pub fn success(&self, msg: &str) {
-        if self.format == OutputFormat::Human {
+        if self.is_chatty() {
             println!("{} {}", "✓".green(), msg);
         }
     }
 
     /// Print warning message
     pub fn warning(&self, msg: &str) {
-        if self.format == OutputFormat::Human {
+        if self.is_chatty() {
             eprintln!("{} {}", "⚠".yellow(), msg);
         }
     }
@@ -150,10 +439,7 @@ impl Formatter {
         match self.format {
             OutputFormat::Json => {
                 let result: CommandResult<()> = CommandResult::error(msg);
-                println!(
-                    "{}",
-                    serde_json::to_string_pretty(&result).unwrap_or_default()
-                );
+                self.emit_json(&result);
             }
             OutputFormat::Human => {
                 eprintln!("{} {}", "Error:".red().bold(), msg);
@@ -163,12 +449,131 @@ impl Formatter {
 
     /// Print a hint message
     pub fn hint(&self, msg: &str) {
-        if self.format == OutputFormat::Human {
+        if self.is_chatty() {
             eprintln!("{} {}", "Hint:".blue(), msg);
         }
     }
 }
 
+/// Render a JSON value as a YAML block document
+fn to_yaml(value: &serde_json::Value) -> String {
+    let mut out = String::new();
+    write_yaml(&mut out, value, 0);
+    out.trim_end().to_string()
+}
+
+fn write_yaml(out: &mut String, value: &serde_json::Value, indent: usize) {
+    use serde_json::Value;
+
+    let pad = " ".repeat(indent);
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, item) in map {
+                let _ = write!(out, "{}{}:", pad, yaml_string(key));
+                if is_yaml_block(item) {
+                    out.push('\n');
+                    write_yaml(out, item, indent + 2);
+                } else {
+                    let _ = writeln!(out, " {}", yaml_scalar(item));
+                }
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for item in items {
+                // Render the item one level deeper, then hang its first line off the dash
+                let mut nested = String::new();
+                write_yaml(&mut nested, item, indent + 2);
+                let _ = write!(out, "{}- {}", pad, &nested[indent + 2..]);
+            }
+        }
+        _ => {
+            let _ = writeln!(out, "{}{}", pad, yaml_scalar(value));
+        }
+    }
+}
+
+/// Whether a value is written as an indented block rather than inline
+fn is_yaml_block(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Object(map) => !map.is_empty(),
+        serde_json::Value::Array(items) => !items.is_empty(),
+        _ => false,
+    }
+}
+
+fn yaml_scalar(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => yaml_string(s),
+        Value::Array(_) => "[]".to_string(),
+        Value::Object(_) => "{}".to_string(),
+    }
+}
+
+/// A string as a YAML scalar, double-quoted unless it can't be misread
+fn yaml_string(s: &str) -> String {
+    const RESERVED: &[&str] = &["true", "false", "null", "yes", "no", "on", "off", "y", "n"];
+
+    let plain = s
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '/')
+        && !s.ends_with([' ', ':'])
+        && !s.contains(": ")
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || " _-./:@+=()".contains(c))
+        && !RESERVED.contains(&s.to_lowercase().as_str());
+
+    if plain {
+        s.to_string()
+    } else {
+        // JSON string escapes are valid in YAML double-quoted scalars
+        serde_json::Value::String(s.to_string()).to_string()
+    }
+}
+
+/// Render a JSON value as a TOML document
+///
+/// TOML has no null, so nulls are dropped, and a document must be a table, so
+/// anything else is wrapped as `value = ...`.
+fn to_toml(value: &serde_json::Value) -> Option<String> {
+    let table = match json_to_toml(value)? {
+        toml::Value::Table(table) => table,
+        other => {
+            let mut table = toml::Table::new();
+            table.insert("value".to_string(), other);
+            table
+        }
+    };
+    toml::to_string(&table)
+        .ok()
+        .map(|text| text.trim_end().to_string())
+}
+
+fn json_to_toml(value: &serde_json::Value) -> Option<toml::Value> {
+    use serde_json::Value;
+
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(b) => toml::Value::Boolean(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => toml::Value::Integer(i),
+            None => toml::Value::Float(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => toml::Value::String(s.clone()),
+        Value::Array(items) => toml::Value::Array(items.iter().filter_map(json_to_toml).collect()),
+        Value::Object(map) => toml::Value::Table(
+            map.iter()
+                .filter_map(|(key, item)| Some((key.clone(), json_to_toml(item)?)))
+                .collect(),
+        ),
+    })
+}
+
 /// DOM node representation for output
 #[derive(Debug, Serialize)]
 pub struct DomNode {
@@ -205,6 +610,13 @@ impl DomNode {
                 let _ = write!(tag_str, " class=\"{}\"", classes.join(" "));
             }
         }
+        if let Some(attributes) = &self.attributes {
+            let mut names: Vec<&String> = attributes.keys().collect();
+            names.sort();
+            for name in names {
+                let _ = write!(tag_str, " {}=\"{}\"", name, attributes[name]);
+            }
+        }
         tag_str.push('>');
 
         writeln!(f, "{}{}", pad, tag_str)?;
@@ -303,8 +715,11 @@ pub struct DesignInspiration {
     pub spacing: SpacingInfo,
     pub layout: LayoutInfo,
     pub animations: AnimationInfo,
+    pub tokens: DesignTokens,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub screenshot_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub responsive: Option<ResponsiveReport>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -359,6 +774,96 @@ pub struct AnimationInfo {
     pub transitions: Vec<String>,
 }
 
+/// Design tokens distilled from the computed styles
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DesignTokens {
+    /// Colors by total usage
+    #[serde(default)]
+    pub palette: Vec<PaletteColor>,
+    /// Full `font-family` stacks by usage
+    #[serde(default)]
+    pub font_stacks: Vec<TokenValue>,
+    /// Font sizes, smallest first
+    #[serde(default)]
+    pub type_scale: Vec<TokenValue>,
+    /// Padding, margin and gap lengths, smallest first
+    #[serde(default)]
+    pub spacing_scale: Vec<TokenValue>,
+    /// Border radii, smallest first
+    #[serde(default)]
+    pub radii: Vec<TokenValue>,
+    /// Box shadows by usage
+    #[serde(default)]
+    pub shadows: Vec<TokenValue>,
+    /// Width breakpoints from the stylesheets' media queries, narrowest first
+    #[serde(default)]
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaletteColor {
+    pub hex: String,
+    pub count: u32,
+    /// Properties the color is used for (color, backgroundColor, borderColor)
+    pub usages: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenValue {
+    pub value: String,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Breakpoint {
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_width_px: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_width_px: Option<u32>,
+    /// Whether the query matches the current viewport
+    pub matches: bool,
+}
+
+/// Screenshots and layout changes across viewport widths
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ResponsiveReport {
+    pub captures: Vec<ResponsiveCapture>,
+    /// What changed between each width and the next
+    pub changes: Vec<LayoutChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponsiveCapture {
+    pub width: u32,
+    /// mobile, tablet or desktop
+    pub label: String,
+    pub screenshot_path: String,
+    /// Stylesheet media queries matching at this width
+    pub matched_queries: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutChange {
+    pub from_width: u32,
+    pub to_width: u32,
+    #[serde(default)]
+    pub queries_activated: Vec<String>,
+    #[serde(default)]
+    pub queries_deactivated: Vec<String>,
+    /// Elements whose display, flex direction, grid columns or visibility changed
+    #[serde(default)]
+    pub elements: Vec<ElementChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementChange {
+    pub element: String,
+    pub property: String,
+    pub from: String,
+    pub to: String,
+}
+
 impl std::fmt::Display for DesignInspiration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "{}", "Design Inspiration".cyan().bold())?;
@@ -430,6 +935,64 @@ impl std::fmt::Display for DesignInspiration {
             )?;
         }
 
+        let values = |tokens: &[TokenValue]| {
+            tokens
+                .iter()
+                .map(|t| t.value.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        writeln!(f, "\n{}", "Tokens:".bold())?;
+        writeln!(f, "  Type scale: {}", values(&self.tokens.type_scale))?;
+        writeln!(f, "  Spacing scale: {}", values(&self.tokens.spacing_scale))?;
+        if !self.tokens.radii.is_empty() {
+            writeln!(f, "  Radii: {}", values(&self.tokens.radii))?;
+        }
+        for shadow in &self.tokens.shadows {
+            writeln!(f, "  Shadow: {} ({}x)", shadow.value, shadow.count)?;
+        }
+        for stack in &self.tokens.font_stacks {
+            writeln!(f, "  Font stack: {} ({}x)", stack.value, stack.count)?;
+        }
+        if !self.tokens.breakpoints.is_empty() {
+            let queries: Vec<&str> = self
+                .tokens
+                .breakpoints
+                .iter()
+                .map(|b| b.query.as_str())
+                .collect();
+            writeln!(f, "  Breakpoints: {}", queries.join(" | "))?;
+        }
+
+        if let Some(responsive) = &self.responsive {
+            writeln!(f, "\n{}", "Responsive:".bold())?;
+            for capture in &responsive.captures {
+                writeln!(
+                    f,
+                    "  {}px ({}): {}",
+                    capture.width, capture.label, capture.screenshot_path
+                )?;
+            }
+            for change in &responsive.changes {
+                writeln!(
+                    f,
+                    "  {}px -> {}px: {} queries on, {} off, {} element changes",
+                    change.from_width,
+                    change.to_width,
+                    change.queries_activated.len(),
+                    change.queries_deactivated.len(),
+                    change.elements.len()
+                )?;
+                for element in change.elements.iter().take(5) {
+                    writeln!(
+                        f,
+                        "    {} {}: {} -> {}",
+                        element.element, element.property, element.from, element.to
+                    )?;
+                }
+            }
+        }
+
         if let Some(path) = &self.screenshot_path {
             writeln!(f, "\nScreenshot saved: {}", path)?;
         }
@@ -517,6 +1080,132 @@ pub fn mask_sensitive(text: &str) -> String {
     result
 }
 
+/// Replacement for masked values
+pub const MASKED: &str = "****";
+
+/// Masking configuration (`[masking]` in .domguard/config.toml)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaskingConfig {
+    /// Mask sensitive values in eval, DOM and session output (default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Regexes for sensitive values (default: credit card and SSN numbers)
+    #[serde(default = "default_mask_patterns")]
+    pub patterns: Vec<String>,
+}
+
+impl Default for MaskingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            patterns: default_mask_patterns(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_mask_patterns() -> Vec<String> {
+    vec![
+        // Visa, Mastercard, Amex and Discover numbers, optionally grouped
+        r"\b(?:4\d{3}|5[1-5]\d{2}|3[47]\d{2}|6011)[ -]?\d{4}[ -]?\d{4}[ -]?\d{1,4}\b".to_string(),
+        // US Social Security numbers
+        r"\b\d{3}-\d{2}-\d{4}\b".to_string(),
+    ]
+}
+
+/// Redacts sensitive values from command output
+pub struct Masker {
+    enabled: bool,
+    patterns: Vec<regex::Regex>,
+}
+
+impl Masker {
+    /// Build a masker from config, failing on invalid patterns
+    pub fn new(config: &MaskingConfig) -> anyhow::Result<Self> {
+        let patterns = config
+            .patterns
+            .iter()
+            .map(|p| {
+                regex::Regex::new(p)
+                    .map_err(|e| anyhow::anyhow!("Invalid masking pattern \"{}\": {}", p, e))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            enabled: config.enabled,
+            patterns,
+        })
+    }
+
+    /// Whether masking is on
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Mask credentials and configured patterns in text
+    pub fn mask_text(&self, text: &str) -> String {
+        if !self.enabled {
+            return text.to_string();
+        }
+        let mut result = mask_sensitive(text);
+        for re in &self.patterns {
+            result = re.replace_all(&result, MASKED).to_string();
+        }
+        result
+    }
+
+    /// Mask a JSON value in place: strings are masked as text, values under sensitive
+    /// keys are replaced, and so is the `value` of any object with `type: "password"`
+    pub fn mask_value(&self, value: &mut serde_json::Value) {
+        if !self.enabled {
+            return;
+        }
+        match value {
+            serde_json::Value::String(s) => *s = self.mask_text(s),
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    self.mask_value(item);
+                }
+            }
+            serde_json::Value::Object(map) => {
+                let password_field =
+                    map.get("type").and_then(serde_json::Value::as_str) == Some("password");
+                for (key, item) in map.iter_mut() {
+                    if item.is_null() {
+                        continue;
+                    }
+                    if (password_field && key == "value") || is_sensitive_key(key) {
+                        *item = MASKED.into();
+                    } else {
+                        self.mask_value(item);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether a field or property name suggests a sensitive value
+pub fn is_sensitive_key(key: &str) -> bool {
+    let key = key.to_lowercase().replace(['-', '_'], "");
+    [
+        "password",
+        "passwd",
+        "secret",
+        "token",
+        "apikey",
+        "authorization",
+        "cvv",
+        "cvc",
+        "ssn",
+    ]
+    .iter()
+    .any(|pattern| key.contains(pattern))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -537,6 +1226,137 @@ mod tests {
         assert_eq!(result.error, Some("test error".to_string()));
     }
 
+    #[test]
+    fn test_timing_breakdown() {
+        let result = CommandResult::success("clicked".to_string())
+            .with_timing_breakdown(Timing::new(Some(40), 5, 120, 300));
+        assert_eq!(result.timing_ms, Some(300));
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["timing"]["action_ms"], 175);
+        assert_eq!(json["timing"]["total_ms"], 340);
+        assert_eq!(json["timing"]["connect_ms"], 40);
+        // timing_ms is the sum of the phases; total_ms adds connecting on top
+        let timing = result.timing.as_ref().unwrap();
+        assert_eq!(
+            timing.resolve_ms + timing.action_ms + timing.wait_ms,
+            result.timing_ms.unwrap()
+        );
+        assert_eq!(
+            result.timing_ms.unwrap() + timing.connect_ms.unwrap(),
+            timing.total_ms
+        );
+
+        let cached = Timing::new(None, 0, 0, 10);
+        assert_eq!(cached.total_ms, 10);
+        assert!(serde_json::to_value(&cached)
+            .unwrap()
+            .get("connect_ms")
+            .is_none());
+    }
+
+    #[test]
+    fn test_command_result_error_details() {
+        let result: CommandResult<()> = CommandResult::error("No element matches selector \"#go\"")
+            .with_error_details(ErrorDetails {
+                code: ErrorCode::ElementNotFound,
+                retryable: true,
+                selector: Some("#go".to_string()),
+                suggestions: Vec::new(),
+            });
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["code"], "ELEMENT_NOT_FOUND");
+        assert_eq!(json["retryable"], true);
+        assert_eq!(json["selector"], "#go");
+        assert!(json.get("suggestions").is_none());
+
+        let plain: CommandResult<()> = CommandResult::error("oops");
+        assert!(serde_json::to_value(&plain).unwrap().get("code").is_none());
+    }
+
+    #[test]
+    fn test_formatter_output_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("result.json");
+        std::fs::write(&path, "stale").unwrap();
+
+        let formatter = Formatter::new(false)
+            .with_ndjson(true)
+            .with_output_file(Some(path.clone()));
+        assert!(formatter.is_json());
+        formatter.output_json(&serde_json::json!({ "n": 1 }));
+        formatter.stream_json(&serde_json::json!({ "n": 2 }));
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "{\"n\":1}\n{\"n\":2}\n");
+    }
+
+    #[test]
+    fn test_formatter_capturing() {
+        let formatter = Formatter::new(false).capturing();
+        assert!(formatter.is_json());
+        formatter.warning("not captured");
+        formatter.output(&CommandResult::success("done"));
+        formatter.error("failed");
+
+        let captured = formatter.take_captured();
+        assert_eq!(captured.len(), 2);
+        assert!(captured[0].starts_with("{\"success\":true"));
+        assert!(captured[1].contains("\"error\":\"failed\""));
+        assert!(formatter.take_captured().is_empty());
+    }
+
+    #[test]
+    fn test_format_yaml() {
+        let value = serde_json::json!({
+            "data": {
+                "count": 3,
+                "empty": {},
+                "nodes": [{ "id": null, "tag": "div" }, []],
+                "tags": ["no", "ok"],
+                "title": "Sign in: step 2",
+                "url": "https://example.com/a"
+            },
+            "success": true
+        });
+        let expected = "\
+data:
+  count: 3
+  empty: {}
+  nodes:
+    - id: null
+      tag: div
+    - []
+  tags:
+    - \"no\"
+    - ok
+  title: \"Sign in: step 2\"
+  url: https://example.com/a
+success: true";
+        assert_eq!(to_yaml(&value), expected);
+        assert_eq!(to_yaml(&serde_json::json!("1.5")), "\"1.5\"");
+        assert_eq!("YML".parse::<DataFormat>(), Ok(DataFormat::Yaml));
+        assert!("xml".parse::<DataFormat>().is_err());
+    }
+
+    #[test]
+    fn test_format_toml() {
+        let value = serde_json::json!({
+            "success": true,
+            "error": null,
+            "data": { "url": "https://example.com/", "sizes": [1, null, 2] }
+        });
+        let text = to_toml(&value).unwrap();
+        let parsed: toml::Table = toml::from_str(&text).unwrap();
+        assert_eq!(parsed["success"].as_bool(), Some(true));
+        assert!(!parsed.contains_key("error"));
+        assert_eq!(parsed["data"]["sizes"].as_array().map(Vec::len), Some(2));
+
+        assert_eq!(
+            to_toml(&serde_json::json!([1, 2])).as_deref(),
+            Some("value = [1, 2]")
+        );
+    }
+
     #[test]
     fn test_formatter_json_mode() {
         let formatter = Formatter::new(true);
@@ -548,4 +1368,48 @@ mod tests {
         let formatter = Formatter::new(false);
         assert!(!formatter.is_json());
     }
+
+    #[test]
+    fn test_masker_patterns() {
+        let masker = Masker::new(&MaskingConfig::default()).unwrap();
+        assert_eq!(
+            masker.mask_text("card 4111 1111 1111 1111, ssn 123-45-6789"),
+            "card ****, ssn ****"
+        );
+        assert_eq!(
+            masker.mask_text("order 1700000000000"),
+            "order 1700000000000"
+        );
+
+        let disabled = Masker::new(&MaskingConfig {
+            enabled: false,
+            ..MaskingConfig::default()
+        })
+        .unwrap();
+        assert_eq!(disabled.mask_text("123-45-6789"), "123-45-6789");
+
+        assert!(Masker::new(&MaskingConfig {
+            enabled: true,
+            patterns: vec!["(".to_string()],
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_masker_json_values() {
+        let masker = Masker::new(&MaskingConfig::default()).unwrap();
+        let mut value = serde_json::json!({
+            "tag": "input",
+            "attributes": { "type": "password", "name": "pw", "value": "hunter2" },
+            "children": [{ "api_key": "abc", "text": "Call 123-45-6789" }],
+            "id": null
+        });
+        masker.mask_value(&mut value);
+
+        assert_eq!(value["attributes"]["value"], MASKED);
+        assert_eq!(value["attributes"]["name"], "pw");
+        assert_eq!(value["children"][0]["api_key"], MASKED);
+        assert_eq!(value["children"][0]["text"], "Call ****");
+        assert!(value["id"].is_null());
+    }
 }
@@ -0,0 +1,303 @@
+//! Performance budgets for `debug perf-assert` and workflow `perf-assert` steps
+//!
+//! The page's own timing entries are read (LCP and CLS through buffered
+//! performance observers) and compared against the given limits, so CI can
+//! fail a build on a slow page. Transfer size only counts what the browser
+//! may report: cross-origin resources without `Timing-Allow-Origin` show up
+//! as 0 bytes, though they still count as requests.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::cdp::{CdpConnection, EvalOptions};
+
+/// Resolves to a `PerfMetrics` for the current page
+const METRICS_SCRIPT: &str = r"new Promise(resolve => {
+    // Buffered entries are delivered in the observer's first callback
+    const observed = type => new Promise(done => {
+        const entries = [];
+        try {
+            const observer = new PerformanceObserver(list => entries.push(...list.getEntries()));
+            observer.observe({ type, buffered: true });
+            setTimeout(() => { observer.disconnect(); done(entries); }, 100);
+        } catch (e) {
+            done(entries);
+        }
+    });
+    Promise.all([observed('largest-contentful-paint'), observed('layout-shift')]).then(([lcp, shifts]) => {
+        const nav = performance.getEntriesByType('navigation')[0];
+        const resources = performance.getEntriesByType('resource');
+        const fcp = performance.getEntriesByType('paint').find(e => e.name === 'first-contentful-paint');
+        const transfer = resources.reduce((sum, e) => sum + (e.transferSize || 0), nav ? nav.transferSize || 0 : 0);
+        resolve({
+            lcp_ms: lcp.length ? lcp[lcp.length - 1].startTime : null,
+            fcp_ms: fcp ? fcp.startTime : null,
+            ttfb_ms: nav ? nav.responseStart : null,
+            cls: shifts.filter(e => !e.hadRecentInput).reduce((sum, e) => sum + e.value, 0),
+            transfer_bytes: Math.round(transfer),
+            requests: resources.length + (nav ? 1 : 0),
+        });
+    });
+})";
+
+/// Limits a page must stay within; unset ones aren't checked
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PerfBudget {
+    pub lcp_max_ms: Option<f64>,
+    pub fcp_max_ms: Option<f64>,
+    pub ttfb_max_ms: Option<f64>,
+    pub cls_max: Option<f64>,
+    pub transfer_max_bytes: Option<u64>,
+    pub requests_max: Option<u64>,
+}
+
+impl PerfBudget {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl std::str::FromStr for PerfBudget {
+    type Err = String;
+
+    /// `lcp-max=2500, transfer-max=1.5MB, requests-max=80`, as in a workflow step's value
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut budget = Self::default();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Expected limit=value, got \"{}\"", part))?;
+            let value = value.trim();
+            let number = || {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| format!("Invalid number for {}: \"{}\"", key, value))
+            };
+            match key.trim() {
+                "lcp-max" => budget.lcp_max_ms = Some(number()?),
+                "fcp-max" => budget.fcp_max_ms = Some(number()?),
+                "ttfb-max" => budget.ttfb_max_ms = Some(number()?),
+                "cls-max" => budget.cls_max = Some(number()?),
+                "transfer-max" => budget.transfer_max_bytes = Some(parse_size(value)?),
+                "requests-max" => {
+                    budget.requests_max = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid count for requests-max: \"{}\"", value))?,
+                    );
+                }
+                other => {
+                    return Err(format!(
+                        "Unknown limit \"{}\" (expected lcp-max, fcp-max, ttfb-max, cls-max, transfer-max or requests-max)",
+                        other
+                    ))
+                }
+            }
+        }
+        if budget.is_empty() {
+            return Err("No limits given".to_string());
+        }
+        Ok(budget)
+    }
+}
+
+/// Bytes in a size like "1.5MB", "300KB" or "2048" (KB = 1024 bytes)
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let upper = s.trim().to_uppercase();
+    let split = upper
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(upper.len());
+    let (number, unit) = upper.split_at(split);
+    let factor = match unit.trim() {
+        "" | "B" => 1.0,
+        "KB" | "K" | "KIB" => 1024.0,
+        "MB" | "M" | "MIB" => 1024.0 * 1024.0,
+        "GB" | "G" | "GIB" => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(format!("Invalid size \"{}\" (e.g. 1.5MB, 300KB)", s)),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid size \"{}\" (e.g. 1.5MB, 300KB)", s))?;
+    Ok((number * factor).round() as u64)
+}
+
+/// Metrics of the current page
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PerfMetrics {
+    pub lcp_ms: Option<f64>,
+    pub fcp_ms: Option<f64>,
+    pub ttfb_ms: Option<f64>,
+    #[serde(default)]
+    pub cls: f64,
+    #[serde(default)]
+    pub transfer_bytes: u64,
+    #[serde(default)]
+    pub requests: u64,
+}
+
+/// One limit compared with what was measured
+#[derive(Debug, Clone, Serialize)]
+pub struct PerfCheck {
+    pub metric: String,
+    pub limit: String,
+    /// "not measured" when the page didn't report it
+    pub actual: String,
+    pub passed: bool,
+}
+
+/// Every check of a budget against the page
+#[derive(Debug, Clone, Serialize)]
+pub struct PerfReport {
+    pub passed: bool,
+    pub metrics: PerfMetrics,
+    pub checks: Vec<PerfCheck>,
+}
+
+impl PerfReport {
+    pub fn violations(&self) -> impl Iterator<Item = &PerfCheck> {
+        self.checks.iter().filter(|check| !check.passed)
+    }
+
+    /// "LCP 3200ms > 2500ms; requests 95 > 80"
+    pub fn summary(&self) -> String {
+        self.violations()
+            .map(|check| format!("{} {} > {}", check.metric, check.actual, check.limit))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// A page over its performance budget
+///
+/// `debug perf-assert` has printed the report by then, so `main` only exits
+/// with status 1; workflow steps and library callers get the report here.
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetFailed {
+    pub report: PerfReport,
+}
+
+impl std::fmt::Display for BudgetFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Performance budget exceeded: {}", self.report.summary())
+    }
+}
+
+impl std::error::Error for BudgetFailed {}
+
+/// Compare `metrics` with `budget`
+///
+/// A limited metric the page didn't report (e.g. no LCP yet) fails: a gate
+/// that can't measure shouldn't pass.
+pub fn check(budget: &PerfBudget, metrics: PerfMetrics) -> PerfReport {
+    let mut checks = Vec::new();
+    let mut timing = |metric: &str, limit: Option<f64>, actual: Option<f64>| {
+        if let Some(limit) = limit {
+            checks.push(PerfCheck {
+                metric: metric.to_string(),
+                limit: format!("{}ms", limit),
+                actual: actual
+                    .map_or_else(|| "not measured".to_string(), |ms| format!("{:.0}ms", ms)),
+                passed: actual.is_some_and(|ms| ms <= limit),
+            });
+        }
+    };
+    timing("LCP", budget.lcp_max_ms, metrics.lcp_ms);
+    timing("FCP", budget.fcp_max_ms, metrics.fcp_ms);
+    timing("TTFB", budget.ttfb_max_ms, metrics.ttfb_ms);
+    if let Some(limit) = budget.cls_max {
+        checks.push(PerfCheck {
+            metric: "CLS".to_string(),
+            limit: limit.to_string(),
+            actual: format!("{:.3}", metrics.cls),
+            passed: metrics.cls <= limit,
+        });
+    }
+    if let Some(limit) = budget.transfer_max_bytes {
+        checks.push(PerfCheck {
+            metric: "transfer".to_string(),
+            limit: format_size(limit),
+            actual: format_size(metrics.transfer_bytes),
+            passed: metrics.transfer_bytes <= limit,
+        });
+    }
+    if let Some(limit) = budget.requests_max {
+        checks.push(PerfCheck {
+            metric: "requests".to_string(),
+            limit: limit.to_string(),
+            actual: metrics.requests.to_string(),
+            passed: metrics.requests <= limit,
+        });
+    }
+    PerfReport {
+        passed: checks.iter().all(|check| check.passed),
+        metrics,
+        checks,
+    }
+}
+
+/// Measure the current page and check it against `budget`
+pub async fn assert_budget(cdp: &CdpConnection, budget: &PerfBudget) -> Result<PerfReport> {
+    let metrics = cdp
+        .evaluate_in(METRICS_SCRIPT, &EvalOptions::default())
+        .await?;
+    Ok(check(budget, serde_json::from_value(metrics)?))
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.2}MB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1}KB", b as f64 / 1024.0),
+        b => format!("{}B", b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_budget() {
+        let budget: PerfBudget = "lcp-max=2500, transfer-max=1.5MB, requests-max=80"
+            .parse()
+            .unwrap();
+        assert_eq!(budget.lcp_max_ms, Some(2500.0));
+        assert_eq!(budget.transfer_max_bytes, Some(1_572_864));
+        assert_eq!(budget.requests_max, Some(80));
+        assert_eq!(parse_size("300kb"), Ok(307_200));
+        assert_eq!(parse_size("2048"), Ok(2048));
+        assert!(parse_size("1.5 parsecs").is_err());
+        assert!("speed-max=3".parse::<PerfBudget>().is_err());
+        assert!("".parse::<PerfBudget>().is_err());
+    }
+
+    #[test]
+    fn test_check_budget() {
+        let budget = PerfBudget {
+            lcp_max_ms: Some(2500.0),
+            cls_max: Some(0.1),
+            transfer_max_bytes: Some(1_572_864),
+            requests_max: Some(80),
+            ..PerfBudget::default()
+        };
+        let metrics = PerfMetrics {
+            lcp_ms: Some(3200.4),
+            cls: 0.02,
+            transfer_bytes: 900_000,
+            requests: 95,
+            ..PerfMetrics::default()
+        };
+        let report = check(&budget, metrics);
+        assert!(!report.passed);
+        assert_eq!(report.checks.len(), 4);
+        assert_eq!(report.summary(), "LCP 3200ms > 2500ms; requests 95 > 80");
+
+        assert_eq!(
+            BudgetFailed { report }.to_string(),
+            "Performance budget exceeded: LCP 3200ms > 2500ms; requests 95 > 80"
+        );
+
+        let unmeasured = check(&budget, PerfMetrics::default());
+        assert_eq!(unmeasured.violations().count(), 1);
+        assert_eq!(unmeasured.checks[0].actual, "not measured");
+    }
+}
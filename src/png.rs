@@ -0,0 +1,713 @@
+//! Minimal PNG codec for stitched screenshots
+//!
+//! Decodes the 8-bit, non-interlaced PNGs browsers produce and encodes RGBA
+//! images, so viewport tiles can be composed without an image library.
+
+use anyhow::{anyhow, Result};
+
+/// An RGBA image, 8 bits per channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl Image {
+    /// A transparent image
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            rgba: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Copy `tile` onto this image with its top edge at row `y`, growing the image to fit
+    pub fn paste(&mut self, tile: &Image, y: u32) {
+        let bottom = y + tile.height;
+        if bottom > self.height {
+            self.rgba
+                .resize(self.width as usize * bottom as usize * 4, 0);
+            self.height = bottom;
+        }
+        let row_bytes = self.width.min(tile.width) as usize * 4;
+        let dst_stride = self.width as usize * 4;
+        let src_stride = tile.width as usize * 4;
+        for row in 0..tile.height as usize {
+            let dst = (y as usize + row) * dst_stride;
+            let src = row * src_stride;
+            self.rgba[dst..dst + row_bytes].copy_from_slice(&tile.rgba[src..src + row_bytes]);
+        }
+    }
+}
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// Largest IDAT chunk written by `encode`
+const MAX_CHUNK: usize = 1 << 20;
+
+/// Decode an 8-bit, non-interlaced grayscale, RGB or RGBA PNG
+pub fn decode(data: &[u8]) -> Result<Image> {
+    if !data.starts_with(&SIGNATURE) {
+        return Err(anyhow!("Not a PNG image"));
+    }
+
+    let mut header = None;
+    let mut compressed = Vec::new();
+    let mut pos = SIGNATURE.len();
+    while pos + 8 <= data.len() {
+        let length =
+            u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let body = data
+            .get(pos + 8..pos + 8 + length)
+            .ok_or_else(|| anyhow!("Truncated PNG chunk"))?;
+        match kind {
+            b"IHDR" if length >= 13 => header = Some(Header::parse(body)?),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        // Length, type, data and CRC
+        pos += 12 + length;
+    }
+
+    let header = header.ok_or_else(|| anyhow!("PNG has no IHDR chunk"))?;
+    let raw = zlib_decompress(&compressed)?;
+    header.unfilter(&raw)
+}
+
+/// Encode an RGBA image as a PNG
+pub fn encode(image: &Image) -> Vec<u8> {
+    let stride = image.width as usize * 4;
+    let mut filtered = Vec::with_capacity((stride + 1) * image.height as usize);
+    for (row, pixels) in image.rgba.chunks_exact(stride.max(1)).enumerate() {
+        if row == 0 {
+            // Sub: difference from the pixel to the left
+            filtered.push(1);
+            for (i, &byte) in pixels.iter().enumerate() {
+                let left = if i >= 4 { pixels[i - 4] } else { 0 };
+                filtered.push(byte.wrapping_sub(left));
+            }
+        } else {
+            // Up: difference from the pixel above, which screenshots mostly repeat
+            filtered.push(2);
+            let above = &image.rgba[(row - 1) * stride..row * stride];
+            filtered.extend(pixels.iter().zip(above).map(|(b, a)| b.wrapping_sub(*a)));
+        }
+    }
+
+    let mut png = SIGNATURE.to_vec();
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&image.width.to_be_bytes());
+    ihdr.extend_from_slice(&image.height.to_be_bytes());
+    // 8-bit RGBA, deflate, adaptive filtering, no interlace
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    let compressed = zlib_compress(&filtered);
+    for chunk in compressed.chunks(MAX_CHUNK) {
+        write_chunk(&mut png, b"IDAT", chunk);
+    }
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+    png.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(body);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+struct Header {
+    width: u32,
+    height: u32,
+    channels: usize,
+}
+
+impl Header {
+    fn parse(body: &[u8]) -> Result<Self> {
+        let width = u32::from_be_bytes([body[0], body[1], body[2], body[3]]);
+        let height = u32::from_be_bytes([body[4], body[5], body[6], body[7]]);
+        let (depth, color, interlace) = (body[8], body[9], body[12]);
+        let channels = match color {
+            0 => 1,
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => return Err(anyhow!("Unsupported PNG color type {}", color)),
+        };
+        if depth != 8 || interlace != 0 {
+            return Err(anyhow!(
+                "Unsupported PNG (bit depth {}, interlace {})",
+                depth,
+                interlace
+            ));
+        }
+        Ok(Self {
+            width,
+            height,
+            channels,
+        })
+    }
+
+    /// Undo the per-row filters and expand to RGBA
+    fn unfilter(&self, raw: &[u8]) -> Result<Image> {
+        let bpp = self.channels;
+        let stride = self.width as usize * bpp;
+        let rows = self.height as usize;
+        if raw.len() < rows * (stride + 1) {
+            return Err(anyhow!("PNG image data is truncated"));
+        }
+
+        let mut pixels = vec![0u8; rows * stride];
+        for row in 0..rows {
+            let filter = raw[row * (stride + 1)];
+            let line = &raw[row * (stride + 1) + 1..(row + 1) * (stride + 1)];
+            let (done, rest) = pixels.split_at_mut(row * stride);
+            let above = if row == 0 {
+                None
+            } else {
+                Some(&done[(row - 1) * stride..])
+            };
+            let out = &mut rest[..stride];
+            for i in 0..stride {
+                let left = if i >= bpp { out[i - bpp] } else { 0 };
+                let up = above.map_or(0, |a| a[i]);
+                let up_left = match above {
+                    Some(a) if i >= bpp => a[i - bpp],
+                    _ => 0,
+                };
+                let predicted = match filter {
+                    0 => 0,
+                    1 => left,
+                    2 => up,
+                    3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                    4 => paeth(left, up, up_left),
+                    _ => return Err(anyhow!("Invalid PNG filter type {}", filter)),
+                };
+                out[i] = line[i].wrapping_add(predicted);
+            }
+        }
+
+        let rgba = match self.channels {
+            4 => pixels,
+            3 => pixels
+                .chunks_exact(3)
+                .flat_map(|p| [p[0], p[1], p[2], 255])
+                .collect(),
+            2 => pixels
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            _ => pixels.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+        };
+        Ok(Image {
+            width: self.width,
+            height: self.height,
+            rgba,
+        })
+    }
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = i16::from(left) + i16::from(up) - i16::from(up_left);
+    let to_left = (estimate - i16::from(left)).abs();
+    let to_up = (estimate - i16::from(up)).abs();
+    let to_up_left = (estimate - i16::from(up_left)).abs();
+    if to_left <= to_up && to_left <= to_up_left {
+        left
+    } else if to_up <= to_up_left {
+        up
+    } else {
+        up_left
+    }
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        CRC_TABLE[((crc ^ u32::from(byte)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+// Deflate length and distance codes (RFC 1951, section 3.2.5)
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Order in which code length code lengths are stored
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitReader<'_> {
+    /// Read `n` (at most 16) bits, least significant first
+    fn bits(&mut self, n: u32) -> Result<u32> {
+        while self.count < n {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or_else(|| anyhow!("Compressed PNG data ends early"))?;
+            self.buffer |= u32::from(byte) << self.count;
+            self.count += 8;
+            self.pos += 1;
+        }
+        let value = self.buffer & ((1 << n) - 1);
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// Skip to the next byte boundary
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+}
+
+/// Canonical Huffman code: symbol counts per length, then symbols in code order
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader<'_>) -> Result<u16> {
+        let (mut code, mut first, mut index) = (0u32, 0u32, 0u32);
+        for &count in &self.counts[1..] {
+            code |= reader.bits(1)?;
+            let count = u32::from(count);
+            if code < first + count {
+                return self
+                    .symbols
+                    .get((index + code - first) as usize)
+                    .copied()
+                    .ok_or_else(|| anyhow!("Invalid Huffman code in PNG data"));
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(anyhow!("Invalid Huffman code in PNG data"))
+    }
+}
+
+fn fixed_lengths() -> (Vec<u8>, Vec<u8>) {
+    let mut literals = vec![8u8; 288];
+    literals[144..256].fill(9);
+    literals[256..280].fill(7);
+    (literals, vec![5u8; 30])
+}
+
+/// Inflate a zlib stream
+fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < 2
+        || data[0] & 0x0F != 8
+        || ((u16::from(data[0]) << 8) | u16::from(data[1])) % 31 != 0
+    {
+        return Err(anyhow!("Invalid zlib header in PNG data"));
+    }
+    let mut reader = BitReader {
+        data: &data[2..],
+        pos: 0,
+        buffer: 0,
+        count: 0,
+    };
+    let mut out = Vec::new();
+
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let start = reader.pos;
+                let header = reader
+                    .data
+                    .get(start..start + 4)
+                    .ok_or_else(|| anyhow!("Compressed PNG data ends early"))?;
+                let length = usize::from(u16::from_le_bytes([header[0], header[1]]));
+                let block = reader
+                    .data
+                    .get(start + 4..start + 4 + length)
+                    .ok_or_else(|| anyhow!("Compressed PNG data ends early"))?;
+                out.extend_from_slice(block);
+                reader.pos = start + 4 + length;
+            }
+            1 => {
+                let (literals, distances) = fixed_lengths();
+                inflate_block(
+                    &mut reader,
+                    &Huffman::new(&literals),
+                    &Huffman::new(&distances),
+                    &mut out,
+                )?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_lengths(&mut reader)?;
+                inflate_block(
+                    &mut reader,
+                    &Huffman::new(&literals),
+                    &Huffman::new(&distances),
+                    &mut out,
+                )?;
+            }
+            _ => return Err(anyhow!("Invalid deflate block type in PNG data")),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn read_dynamic_lengths(reader: &mut BitReader<'_>) -> Result<(Vec<u8>, Vec<u8>)> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_count] {
+        code_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match code_lengths.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or_else(|| anyhow!("Invalid code lengths in PNG data"))?;
+                (previous, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.resize(lengths.len() + repeat as usize, value);
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err(anyhow!("Invalid code lengths in PNG data"));
+    }
+    let distances = lengths.split_off(literal_count);
+    Ok((lengths, distances))
+}
+
+fn inflate_block(
+    reader: &mut BitReader<'_>,
+    literals: &Huffman,
+    distances: &Huffman,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    loop {
+        let symbol = literals.decode(reader)?;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let index = usize::from(symbol - 257);
+                if index >= LENGTH_BASE.len() {
+                    return Err(anyhow!("Invalid length code in PNG data"));
+                }
+                let length = usize::from(LENGTH_BASE[index])
+                    + reader.bits(u32::from(LENGTH_EXTRA[index]))? as usize;
+                let index = usize::from(distances.decode(reader)?);
+                if index >= DIST_BASE.len() {
+                    return Err(anyhow!("Invalid distance code in PNG data"));
+                }
+                let distance = usize::from(DIST_BASE[index])
+                    + reader.bits(u32::from(DIST_EXTRA[index]))? as usize;
+                if distance > out.len() {
+                    return Err(anyhow!("Invalid back-reference in PNG data"));
+                }
+                // Byte by byte: the copy may overlap what it produces
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+    }
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u32, bits: u32) {
+        self.buffer |= u64::from(value) << self.count;
+        self.count += bits;
+        while self.count >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Write a Huffman code, which deflate stores most significant bit first
+    fn put_code(&mut self, code: u32, bits: u32) {
+        self.put(code.reverse_bits() >> (32 - bits), bits);
+    }
+
+    fn literal(&mut self, symbol: u16) {
+        let symbol = u32::from(symbol);
+        match symbol {
+            0..=143 => self.put_code(0x30 + symbol, 8),
+            144..=255 => self.put_code(0x190 + symbol - 144, 9),
+            256..=279 => self.put_code(symbol - 256, 7),
+            _ => self.put_code(0xC0 + symbol - 280, 8),
+        }
+    }
+
+    fn back_reference(&mut self, length: usize, distance: usize) {
+        let index = LENGTH_BASE
+            .iter()
+            .rposition(|&base| usize::from(base) <= length)
+            .unwrap_or(0);
+        self.literal(257 + index as u16);
+        self.put(
+            (length - usize::from(LENGTH_BASE[index])) as u32,
+            u32::from(LENGTH_EXTRA[index]),
+        );
+
+        let index = DIST_BASE
+            .iter()
+            .rposition(|&base| usize::from(base) <= distance)
+            .unwrap_or(0);
+        self.put_code(index as u32, 5);
+        self.put(
+            (distance - usize::from(DIST_BASE[index])) as u32,
+            u32::from(DIST_EXTRA[index]),
+        );
+    }
+}
+
+const WINDOW: usize = 32768;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+
+fn hash(bytes: &[u8]) -> usize {
+    let value = (u32::from(bytes[0]) << 16) | (u32::from(bytes[1]) << 8) | u32::from(bytes[2]);
+    (value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+/// Deflate into a zlib stream: one fixed-Huffman block with greedy LZ77 matching
+fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        out: vec![0x78, 0x9C],
+        buffer: 0,
+        count: 0,
+    };
+    // Final block, fixed Huffman codes
+    writer.put(1, 1);
+    writer.put(1, 2);
+
+    // Most recent position (plus one) of each 3-byte hash
+    let mut head = vec![0usize; 1 << HASH_BITS];
+    let mut i = 0;
+    while i < data.len() {
+        let mut matched = 0;
+        let mut distance = 0;
+        if i + 3 <= data.len() {
+            let slot = hash(&data[i..]);
+            let candidate = head[slot];
+            head[slot] = i + 1;
+            if candidate > 0 && i - (candidate - 1) <= WINDOW {
+                let start = candidate - 1;
+                let limit = MAX_MATCH.min(data.len() - i);
+                while matched < limit && data[start + matched] == data[i + matched] {
+                    matched += 1;
+                }
+                distance = i - start;
+            }
+        }
+
+        if matched >= 3 {
+            writer.back_reference(matched, distance);
+            for j in i + 1..(i + matched).min(data.len().saturating_sub(2)) {
+                head[hash(&data[j..])] = j + 1;
+            }
+            i += matched;
+        } else {
+            writer.literal(u16::from(data[i]));
+            i += 1;
+        }
+    }
+    writer.literal(256);
+    if writer.count > 0 {
+        writer.put(0, 8 - writer.count);
+    }
+
+    let mut out = writer.out;
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32) -> Image {
+        let mut image = Image::new(width, height);
+        for (i, pixel) in image.rgba.chunks_exact_mut(4).enumerate() {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            pixel.copy_from_slice(&[(x * 7) as u8, (y * 3) as u8, ((x ^ y) & 0xFF) as u8, 255]);
+        }
+        image
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let image = gradient(37, 23);
+        let png = encode(&image);
+        assert!(png.starts_with(&SIGNATURE));
+        assert_eq!(decode(&png).unwrap(), image);
+
+        let blank = Image::new(300, 200);
+        let png = encode(&blank);
+        // Repeated rows compress to almost nothing
+        assert!(png.len() < blank.rgba.len() / 50);
+        assert_eq!(decode(&png).unwrap(), blank);
+    }
+
+    #[test]
+    fn test_decode_filters() {
+        // 5x4 RGB written by Python's zlib, rows filtered Average, Paeth, Sub, Paeth
+        let png = [
+            137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 5, 0, 0, 0, 4,
+            8, 2, 0, 0, 0, 201, 81, 98, 23, 0, 0, 0, 61, 73, 68, 65, 84, 120, 218, 99, 102, 96, 96,
+            208, 224, 148, 179, 81, 208, 13, 176, 183, 73, 201, 240, 102, 97, 55, 146, 99, 231,
+            124, 196, 46, 109, 195, 174, 251, 136, 221, 200, 134, 145, 47, 197, 6, 40, 175, 33,
+            189, 76, 67, 87, 78, 195, 126, 26, 92, 94, 14, 44, 47, 7, 0, 113, 173, 12, 69, 199,
+            115, 94, 227, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96, 130,
+        ];
+        let image = decode(&png).unwrap();
+        assert_eq!((image.width, image.height), (5, 4));
+        let expected: Vec<u8> = (0..4u32)
+            .flat_map(|y| {
+                (0..5u32).flat_map(move |x| {
+                    [
+                        ((x * 40 + y * 7) % 256) as u8,
+                        ((x * x * 9 + y * 50) % 256) as u8,
+                        ((x ^ y) * 30 % 256) as u8,
+                        255,
+                    ]
+                })
+            })
+            .collect();
+        assert_eq!(image.rgba, expected);
+    }
+
+    #[test]
+    fn test_inflate_blocks() {
+        // Dynamic Huffman block, as zlib writes for short skewed input
+        let dynamic = [
+            120, 218, 29, 199, 55, 1, 0, 0, 12, 195, 48, 172, 118, 194, 31, 67, 199, 39, 97, 11,
+            88, 13, 43, 63, 37, 146, 254, 56, 51, 58, 89, 15, 79,
+        ];
+        assert_eq!(
+            zlib_decompress(&dynamic).unwrap(),
+            b"abddaaabdbbcadaabaaabddacbacdabaaaaacbaa"
+        );
+
+        let data = b"stored";
+        let stored = [
+            &[0x78, 0x01, 0x01, 6, 0, 0xF9, 0xFF][..],
+            data,
+            &adler32(data).to_be_bytes(),
+        ]
+        .concat();
+        assert_eq!(zlib_decompress(&stored).unwrap(), data);
+        assert!(zlib_decompress(&[0x78, 0x9C, 0xFF]).is_err());
+    }
+
+    #[test]
+    fn test_paste_grows_image() {
+        let mut canvas = Image::new(4, 2);
+        let tile = gradient(4, 3);
+        canvas.paste(&tile, 1);
+        assert_eq!(canvas.height, 4);
+        assert_eq!(&canvas.rgba[16..], &tile.rgba[..]);
+        assert!(canvas.rgba[..16].iter().all(|&b| b == 0));
+    }
+}
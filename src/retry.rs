@@ -0,0 +1,121 @@
+//! Retry with backoff for `domguard retry`
+//!
+//! Runs another DOMGuard command again after a failure, waiting longer each
+//! time, so shell-driven agents don't need their own retry loops. Each failed
+//! attempt is kept for the final report.
+
+use serde::Serialize;
+
+/// How often and how patiently to retry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Total runs, including the first
+    pub attempts: u32,
+    /// Delay before the first retry in milliseconds
+    pub delay_ms: u64,
+    /// Factor each later delay grows by
+    pub backoff: f64,
+    /// Delays never exceed this
+    pub max_delay_ms: u64,
+}
+
+impl RetryPolicy {
+    /// Delay after the failed attempt `attempt` (1-based)
+    pub fn delay_after(&self, attempt: u32) -> u64 {
+        let exponent = i32::try_from(attempt.saturating_sub(1).min(63)).unwrap_or(63);
+        let factor = self.backoff.powi(exponent);
+        let delay = self.delay_ms as f64 * factor;
+        if delay >= self.max_delay_ms as f64 {
+            self.max_delay_ms
+        } else {
+            delay.round() as u64
+        }
+    }
+}
+
+/// One failed run
+#[derive(Debug, Clone, Serialize)]
+pub struct RetryAttempt {
+    pub attempt: u32,
+    pub error: String,
+    /// Whether the error looked worth retrying
+    pub retryable: bool,
+    /// Wait before the next attempt (none after the last)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay_ms: Option<u64>,
+}
+
+/// Every attempt failed, or one failed in a way retrying won't fix
+#[derive(Debug, Clone, Serialize)]
+pub struct RetryExhausted {
+    pub attempts: Vec<RetryAttempt>,
+    /// Total runs allowed
+    pub max_attempts: u32,
+}
+
+impl std::fmt::Display for RetryExhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(last) = self.attempts.last() else {
+            return write!(f, "No attempts were made");
+        };
+        if last.retryable && self.attempts.len() as u32 >= self.max_attempts {
+            write!(
+                f,
+                "Failed after {} attempts: {}",
+                self.attempts.len(),
+                last.error
+            )
+        } else {
+            write!(
+                f,
+                "Gave up on attempt {} (not retryable): {}",
+                last.attempt, last.error
+            )
+        }
+    }
+}
+
+impl std::error::Error for RetryExhausted {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_after() {
+        let policy = RetryPolicy {
+            attempts: 5,
+            delay_ms: 500,
+            backoff: 2.0,
+            max_delay_ms: 3000,
+        };
+        assert_eq!(policy.delay_after(1), 500);
+        assert_eq!(policy.delay_after(2), 1000);
+        assert_eq!(policy.delay_after(3), 2000);
+        assert_eq!(policy.delay_after(4), 3000);
+        assert_eq!(policy.delay_after(100), 3000);
+    }
+
+    #[test]
+    fn test_exhausted_message() {
+        let attempt = |attempt, retryable| RetryAttempt {
+            attempt,
+            error: "Element not found".to_string(),
+            retryable,
+            delay_ms: None,
+        };
+        let exhausted = RetryExhausted {
+            attempts: vec![attempt(1, true), attempt(2, true)],
+            max_attempts: 2,
+        };
+        assert_eq!(
+            exhausted.to_string(),
+            "Failed after 2 attempts: Element not found"
+        );
+        let stopped = RetryExhausted {
+            attempts: vec![attempt(1, false)],
+            max_attempts: 5,
+        };
+        assert!(stopped.to_string().starts_with("Gave up on attempt 1"));
+    }
+}
@@ -0,0 +1,717 @@
+//! Running workflows against the browser
+//!
+//! `workflow run`, `auth login` and the schedule daemon all go through
+//! [`execute_workflow`]; [`WorkflowStore`] holds the workflows, schedules and
+//! run history under `.domguard`. Nothing here prints: progress goes to the
+//! callers' callbacks and results come back for them to show.
+
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use crate::cdp::CdpConnection;
+use crate::config::Config;
+use crate::storage::StorageBackend;
+use crate::workflow::{
+    action_targets_selector, mask_secrets, resolve_params, secret_values, substitute_params,
+    CronSchedule, RunRecord, ScheduleConfig, StepReadiness, StepResult, Workflow, WorkflowManager,
+    WorkflowResult, WorkflowSchedule, WorkflowStep,
+};
+
+/// How often the daemon checks its schedules
+pub const DAEMON_TICK_SECS: u64 = 15;
+
+/// The workflows, schedules and run history of the current project
+pub struct WorkflowStore {
+    pub manager: WorkflowManager,
+    domguard_dir: PathBuf,
+    backend: StorageBackend,
+}
+
+impl WorkflowStore {
+    /// Load the workflows under `.domguard/workflows` from the `[storage]` backend
+    pub fn open() -> Result<Self> {
+        let domguard_dir = Config::find_domguard_dir().unwrap_or_else(Config::domguard_dir);
+        let backend = Config::load()?.storage.backend;
+        let mut manager = WorkflowManager::with_storage(crate::storage::workflow_storage(
+            backend,
+            &domguard_dir.join("workflows"),
+        )?);
+        manager.load_all()?;
+        Ok(Self {
+            manager,
+            domguard_dir,
+            backend,
+        })
+    }
+
+    /// Where run records are kept
+    pub fn runs_dir(&self) -> PathBuf {
+        self.domguard_dir.join("runs")
+    }
+
+    /// Where the daemon's schedules are kept
+    pub fn schedules_path(&self) -> PathBuf {
+        self.domguard_dir.join("schedules.toml")
+    }
+
+    /// A workflow by ID, or else the first one with that name
+    pub fn find(&self, id: &str) -> Option<&Workflow> {
+        self.manager
+            .get(id)
+            .or_else(|| self.manager.find_by_name(id).first().copied())
+    }
+
+    /// Like [`find`](Self::find), failing when there is none
+    pub fn require(&self, id: &str) -> Result<&Workflow> {
+        self.find(id)
+            .ok_or_else(|| anyhow!("Workflow not found: {}", id))
+    }
+
+    /// Workflows with `tag`, else those for `domain`, else all of them
+    pub fn list(&self, tag: Option<&str>, domain: Option<&str>) -> Vec<&Workflow> {
+        match (tag, domain) {
+            (Some(tag), _) => self.manager.list_by_tag(tag),
+            (None, Some(domain)) => self.manager.list_for_domain(domain),
+            (None, None) => self.manager.list(),
+        }
+    }
+
+    /// Save a workflow made from a recorded session, returning it and its file
+    pub fn from_session(&mut self, session_id: &str, name: &str) -> Result<(Workflow, PathBuf)> {
+        let recorder = crate::session::SessionRecorder::with_backend(
+            self.domguard_dir.join("sessions"),
+            self.backend,
+        )?;
+        let session = recorder.load_session(session_id)?;
+        let workflow = WorkflowManager::from_session(&session, name);
+        let path = self.manager.save(workflow.clone())?;
+        Ok((workflow, path))
+    }
+
+    /// Save a new workflow without steps, returning it and its file
+    pub fn create(&mut self, name: &str) -> Result<(Workflow, PathBuf)> {
+        let workflow = WorkflowManager::create_empty(name);
+        let path = self.manager.save(workflow.clone())?;
+        Ok((workflow, path))
+    }
+
+    /// The file of a saved workflow, for editing
+    pub fn file(&self, id: &str) -> Result<PathBuf> {
+        if let Some(path) = self.manager.file(id) {
+            return Ok(path);
+        }
+        if self.backend == StorageBackend::Sqlite && self.manager.get(id).is_some() {
+            anyhow::bail!(
+                "Workflow {} is kept in SQLite, which has no file to edit; \
+                 'workflow edit' needs [storage] backend = \"fs\"",
+                id
+            );
+        }
+        anyhow::bail!("Workflow not found: {}", id)
+    }
+
+    /// Add to the run history and the workflow's stats
+    pub fn save_run(&mut self, record: &RunRecord) -> Result<()> {
+        record.save(&self.runs_dir())?;
+        self.manager.record_run(&record.workflow_id, record.success)
+    }
+
+    /// The latest `limit` runs, newest first
+    pub fn runs(&self, limit: usize) -> Result<Vec<RunRecord>> {
+        RunRecord::load_all(&self.runs_dir(), limit)
+    }
+
+    /// Store `schedule`, replacing the workflow's earlier one
+    ///
+    /// `schedule.workflow_id` may also be a workflow name; the stored
+    /// schedule, returned, has the ID.
+    pub fn schedule(&self, mut schedule: WorkflowSchedule) -> Result<WorkflowSchedule> {
+        schedule.workflow_id = self.require(&schedule.workflow_id)?.id.clone();
+        if let Some(expr) = &schedule.cron {
+            CronSchedule::parse(expr)?;
+        }
+
+        let path = self.schedules_path();
+        let mut schedules = ScheduleConfig::load(&path)?;
+        schedules.upsert(schedule.clone());
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        schedules.save(&path)?;
+        Ok(schedule)
+    }
+
+    /// Remove a workflow's schedule, returning its ID and whether it had one
+    pub fn unschedule(&self, id: &str) -> Result<(String, bool)> {
+        let workflow_id = self
+            .find(id)
+            .map_or_else(|| id.to_string(), |w| w.id.clone());
+        let path = self.schedules_path();
+        let mut schedules = ScheduleConfig::load(&path)?;
+        let removed = schedules.remove(&workflow_id);
+        if removed {
+            schedules.save(&path)?;
+        }
+        Ok((workflow_id, removed))
+    }
+
+    /// All stored schedules
+    pub fn schedules(&self) -> Result<Vec<WorkflowSchedule>> {
+        Ok(ScheduleConfig::load(&self.schedules_path())?.schedules)
+    }
+}
+
+/// How a workflow runs
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    /// Pause after each step
+    pub delay_ms: u64,
+    /// Mock rules file (TOML or JSON) active for the run
+    pub mocks: Option<PathBuf>,
+    /// Let navigate steps open blocked sites
+    pub override_block: bool,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            delay_ms: 500,
+            mocks: None,
+            override_block: false,
+        }
+    }
+}
+
+/// A step about to run, as it may be shown
+#[derive(Debug, Clone)]
+pub struct StepProgress {
+    pub index: usize,
+    pub total: usize,
+    pub action: String,
+    /// Target with parameters filled in and secrets masked
+    pub target: String,
+}
+
+/// Run a workflow's steps on the connected page
+///
+/// Mock rules from `options.mocks` and stored credentials are active only
+/// for the run. `on_step` sees each step before it runs.
+pub async fn execute_workflow(
+    cdp: &CdpConnection,
+    workflow: &Workflow,
+    params: &HashMap<String, String>,
+    options: &RunOptions,
+    on_step: &mut dyn FnMut(&StepProgress),
+) -> Result<WorkflowResult> {
+    let domguard_dir = Config::find_domguard_dir().unwrap_or_else(Config::domguard_dir);
+
+    // Mock rules and stored credentials stay active only while the workflow runs
+    let rules = match &options.mocks {
+        Some(path) => crate::network::load_mock_rules(path)?,
+        None => Vec::new(),
+    };
+    let credentials = crate::network::NetworkConfig::load(&domguard_dir.join("network.toml"))
+        .unwrap_or_default()
+        .credentials;
+    let interception = if rules.is_empty() && credentials.is_empty() {
+        None
+    } else {
+        Some(cdp.start_request_interception(rules, credentials).await?)
+    };
+
+    // Stored secrets are decrypted only when the workflow references them
+    let stored_secrets = if uses_secrets(workflow) {
+        let store = crate::secrets::SecretStore::open()?;
+        store.decrypt_all(&crate::secrets::load_or_create_key(
+            &crate::secrets::default_key_path(),
+        )?)?
+    } else {
+        BTreeMap::new()
+    };
+
+    // Secret values never reach the terminal, JSON results or recordings
+    let mut secrets = secret_values(workflow, params);
+    secrets.extend(stored_secrets.values().filter(|v| !v.is_empty()).cloned());
+    let mask = |text: &str| mask_secrets(text, &secrets);
+    let recorder = crate::session::SessionRecorder::new(domguard_dir.join("sessions"));
+    let recording = recorder.is_recording() && !recorder.is_paused();
+
+    let start = std::time::Instant::now();
+    let mut step_results = Vec::new();
+
+    for (i, step) in workflow.steps.iter().enumerate() {
+        let step_start = std::time::Instant::now();
+        let mut span = crate::telemetry::Span::start(&format!("workflow step {}", step.action));
+        span.set_attribute("domguard.workflow", &workflow.id);
+        span.set_attribute("domguard.action", &step.action);
+
+        // Shown text keeps ${env:..}/${secret:..} placeholders; only the page sees values
+        let target = step.target.as_ref().map(|t| substitute_params(t, params));
+        let value = step.value.as_ref().map(|v| substitute_params(v, params));
+        let expand = |text: Option<&String>| {
+            text.map(|t| crate::secrets::expand_placeholders(t, &stored_secrets))
+                .transpose()
+        };
+
+        on_step(&StepProgress {
+            index: i,
+            total: workflow.steps.len(),
+            action: step.action.clone(),
+            target: mask(target.as_deref().unwrap_or("")),
+        });
+
+        // Execute the step
+        let result = match (expand(target.as_ref()), expand(value.as_ref())) {
+            (Ok(target), Ok(value)) => {
+                execute_step(
+                    cdp,
+                    step,
+                    target.as_deref(),
+                    value.as_deref(),
+                    options.override_block,
+                )
+                .await
+            }
+            (Err(e), _) | (_, Err(e)) => Err(e),
+        };
+        let error = result.err().map(|e| mask(&e.to_string()));
+        if let Some(target) = &target {
+            span.set_attribute("domguard.selector", &mask(target));
+        }
+        span.end(error.as_deref());
+
+        if recording {
+            let builder = crate::session::ActionBuilder::new(&format!("workflow {}", step.action))
+                .with_args(serde_json::json!({
+                    "workflow": workflow.id,
+                    "target": target.as_deref().map(mask),
+                    "value": value.as_deref().map(mask),
+                }))
+                .with_selector(target.as_deref().map(mask));
+            let action = match &error {
+                Some(e) => builder.failed(e),
+                None => builder.success(),
+            };
+            recorder.record_action(action)?;
+        }
+
+        step_results.push(StepResult {
+            index: i,
+            name: step.name.clone(),
+            success: error.is_none(),
+            duration_ms: step_start.elapsed().as_millis() as u64,
+            skipped: false,
+            retries: 0,
+            error,
+        });
+
+        // Wait between steps
+        tokio::time::sleep(std::time::Duration::from_millis(options.delay_ms)).await;
+    }
+
+    if let Some(handle) = interception {
+        handle.abort();
+        cdp.stop_request_interception().await?;
+    }
+
+    let duration_ms = start.elapsed().as_millis() as u64;
+    let success = step_results
+        .iter()
+        .all(|r| r.success || !workflow.steps[r.index].required);
+
+    Ok(WorkflowResult {
+        workflow_id: workflow.id.clone(),
+        success,
+        duration_ms,
+        step_results,
+        error: None,
+        screenshots: vec![],
+    })
+}
+
+/// Check each selector-targeting step against the current page without running it
+pub async fn check_readiness(
+    cdp: &CdpConnection,
+    workflow: &Workflow,
+    params: &HashMap<String, String>,
+    secrets: &[String],
+) -> Result<Vec<StepReadiness>> {
+    let mut report = Vec::new();
+    let mut after_navigation = false;
+
+    for (index, step) in workflow.steps.iter().enumerate() {
+        if step.action == "navigate" {
+            after_navigation = true;
+            continue;
+        }
+        let Some(target) = step.target.as_ref() else {
+            continue;
+        };
+        if !action_targets_selector(&step.action) {
+            continue;
+        }
+
+        // Selectors may use ${env:..}; secrets don't belong in selectors
+        let selector = substitute_params(target, params);
+        let shown = mask_secrets(&selector, secrets);
+        let selector = crate::secrets::expand_placeholders(&selector, &BTreeMap::new());
+        let selector = match selector {
+            Ok(mut selector) => {
+                crate::site_instructions::expand_page_aliases(cdp, vec![&mut selector])
+                    .await
+                    .map(|()| selector)
+            }
+            Err(e) => Err(e),
+        };
+        let selector = match selector {
+            Ok(selector) => selector,
+            Err(e) => {
+                report.push(StepReadiness {
+                    index,
+                    action: step.action.clone(),
+                    target: Some(shown),
+                    count: 0,
+                    visible: false,
+                    ready: false,
+                    note: Some(e.to_string()),
+                });
+                continue;
+            }
+        };
+
+        let js = format!(
+            r#"(() => {{
+                try {{
+                    const els = document.querySelectorAll({});
+                    const el = els[0];
+                    let visible = false;
+                    if (el) {{
+                        const rect = el.getBoundingClientRect();
+                        const style = getComputedStyle(el);
+                        visible = rect.width > 0 && rect.height > 0
+                            && style.visibility !== 'hidden' && style.display !== 'none';
+                    }}
+                    return {{ count: els.length, visible }};
+                }} catch (e) {{
+                    return {{ error: e.message }};
+                }}
+            }})()"#,
+            serde_json::to_string(&selector)?
+        );
+        let probe = cdp.evaluate(&js).await?;
+
+        let readiness = if let Some(error) = probe.get("error").and_then(|e| e.as_str()) {
+            StepReadiness {
+                index,
+                action: step.action.clone(),
+                target: Some(shown),
+                count: 0,
+                visible: false,
+                ready: false,
+                note: Some(format!("invalid selector: {}", error)),
+            }
+        } else {
+            StepReadiness::from_probe(
+                index,
+                &step.action,
+                &shown,
+                probe["count"].as_u64().unwrap_or(0),
+                probe["visible"].as_bool().unwrap_or(false),
+                after_navigation,
+            )
+        };
+        report.push(readiness);
+    }
+
+    Ok(report)
+}
+
+/// Whether any step references a `${secret:NAME}` placeholder
+fn uses_secrets(workflow: &Workflow) -> bool {
+    workflow.steps.iter().any(|step| {
+        [&step.target, &step.value]
+            .into_iter()
+            .flatten()
+            .any(|text| text.contains("${secret:"))
+    })
+}
+
+/// Execute a single workflow step
+async fn execute_step(
+    cdp: &CdpConnection,
+    step: &WorkflowStep,
+    target: Option<&str>,
+    value: Option<&str>,
+    override_block: bool,
+) -> Result<()> {
+    // `@name` selector aliases from the site the step runs on
+    let mut target = target.map(str::to_string);
+    if action_targets_selector(&step.action) {
+        crate::site_instructions::expand_page_aliases(cdp, target.iter_mut().collect()).await?;
+    }
+    let target = target.as_deref();
+
+    match step.action.as_str() {
+        "click" => {
+            if let Some(sel) = target {
+                cdp.click(sel, 0).await?;
+            }
+        }
+        "type" => {
+            if let Some(sel) = target {
+                if let Some(text) = value {
+                    cdp.type_into(sel, text).await?;
+                }
+            }
+        }
+        "navigate" => {
+            if let Some(url) = target {
+                crate::security::enforce_site_policy(
+                    cdp.config(),
+                    "workflow navigate",
+                    url,
+                    override_block,
+                )?;
+                cdp.navigate(url).await?;
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            }
+        }
+        "wait" => {
+            if let Some(sel) = target {
+                let timeout = step.timeout_ms.unwrap_or(5000);
+                cdp.wait_for(sel, timeout).await?;
+            }
+        }
+        "screenshot" => {
+            cdp.screenshot(false).await?;
+        }
+        "scroll" => {
+            if let Some(sel) = target {
+                cdp.scroll_to_element(sel).await?;
+            }
+        }
+        "hover" => {
+            if let Some(sel) = target {
+                cdp.hover(sel).await?;
+            }
+        }
+        "key" => {
+            if let Some(key) = target {
+                cdp.press_key(key).await?;
+            }
+        }
+        "perf-assert" => {
+            // value: "lcp-max=2500, transfer-max=1.5MB, requests-max=80"
+            let budget: crate::perf::PerfBudget = value
+                .unwrap_or_default()
+                .parse()
+                .map_err(|e| anyhow!("Invalid perf-assert budget: {}", e))?;
+            let report = crate::perf::assert_budget(cdp, &budget).await?;
+            if !report.passed {
+                return Err(crate::perf::BudgetFailed { report }.into());
+            }
+        }
+        _ => {
+            // Unknown action, skip
+        }
+    }
+
+    Ok(())
+}
+
+/// What the daemon is doing, for its caller to report
+#[derive(Debug, Clone)]
+pub enum DaemonEvent {
+    /// A schedule fired and its workflow starts
+    Started {
+        workflow: String,
+        trigger: &'static str,
+    },
+    /// A step of the running workflow
+    Step(StepProgress),
+    /// A run finished (or couldn't start) and was recorded
+    Finished(RunRecord),
+    /// A schedule that couldn't be checked or run
+    Warning(String),
+}
+
+/// Run scheduled workflows until stopped (or one round with `once`)
+///
+/// Schedules are re-read every tick so `workflow schedule` changes apply
+/// without restarting. Watch triggers fire when the text appears, not on
+/// every check while it stays on the page.
+pub async fn run_daemon(
+    cdp: &CdpConnection,
+    store: &mut WorkflowStore,
+    once: bool,
+    on_event: &mut dyn FnMut(DaemonEvent),
+) -> Result<()> {
+    let mut last_cron_minute: HashMap<String, i64> = HashMap::new();
+    let mut last_watch: HashMap<String, std::time::Instant> = HashMap::new();
+    let mut text_seen: HashMap<String, bool> = HashMap::new();
+
+    loop {
+        let schedules = store.schedules()?;
+        store.manager.load_all()?;
+        let now = chrono::Local::now();
+
+        for schedule in &schedules {
+            let id = &schedule.workflow_id;
+            let mut triggers = Vec::new();
+
+            if let Some(expr) = &schedule.cron {
+                match CronSchedule::parse(expr) {
+                    Ok(cron) => {
+                        let minute = now.timestamp() / 60;
+                        if cron.matches(&now) && last_cron_minute.get(id) != Some(&minute) {
+                            last_cron_minute.insert(id.clone(), minute);
+                            triggers.push("cron");
+                        }
+                    }
+                    Err(e) => on_event(DaemonEvent::Warning(format!("Skipping {}: {}", id, e))),
+                }
+            }
+
+            if let (Some(url), Some(text)) = (&schedule.watch_url, &schedule.when_text) {
+                let interval = std::time::Duration::from_secs(schedule.interval_secs);
+                if last_watch.get(id).is_none_or(|t| t.elapsed() >= interval) {
+                    last_watch.insert(id.clone(), std::time::Instant::now());
+                    match page_has_text(cdp, url, text).await {
+                        Ok(present) => {
+                            let was_present = text_seen.insert(id.clone(), present);
+                            if present && was_present != Some(true) {
+                                triggers.push("watch");
+                            }
+                        }
+                        Err(e) => on_event(DaemonEvent::Warning(format!(
+                            "Watch check for {} failed: {}",
+                            id, e
+                        ))),
+                    }
+                }
+            }
+
+            for trigger in triggers {
+                let Some(workflow) = store.manager.get(id).cloned() else {
+                    on_event(DaemonEvent::Warning(format!(
+                        "Scheduled workflow not found: {}",
+                        id
+                    )));
+                    continue;
+                };
+
+                on_event(DaemonEvent::Started {
+                    workflow: workflow.name.clone(),
+                    trigger,
+                });
+                let started_at = chrono::Utc::now();
+                // Scheduled runs can't prompt, so missing parameters fail the run
+                let run = match resolve_params(&workflow, schedule.params.clone(), |_| Ok(None)) {
+                    Ok(params) => {
+                        execute_workflow(
+                            cdp,
+                            &workflow,
+                            &params,
+                            &RunOptions::default(),
+                            &mut |step| on_event(DaemonEvent::Step(step.clone())),
+                        )
+                        .await
+                    }
+                    Err(e) => Err(e),
+                };
+                let record = match run {
+                    Ok(result) => RunRecord::from_result(&result, trigger, started_at),
+                    Err(e) => RunRecord {
+                        workflow_id: workflow.id.clone(),
+                        trigger: trigger.to_string(),
+                        started_at,
+                        success: false,
+                        duration_ms: (chrono::Utc::now() - started_at).num_milliseconds() as u64,
+                        error: Some(e.to_string()),
+                    },
+                };
+                store.save_run(&record)?;
+                on_event(DaemonEvent::Finished(record));
+            }
+        }
+
+        if once {
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(DAEMON_TICK_SECS)).await;
+    }
+}
+
+/// Navigate to `url` and check whether the page text contains `text`
+async fn page_has_text(cdp: &CdpConnection, url: &str, text: &str) -> Result<bool> {
+    cdp.navigate(url).await?;
+    let found = cdp
+        .evaluate(&format!(
+            "(document.body ? document.body.innerText : '').includes({})",
+            serde_json::to_string(text)?
+        ))
+        .await?;
+    Ok(found.as_bool().unwrap_or(false))
+}
+
+/// The next time a schedule's cron expression fires, if it has a valid one
+pub fn next_run(schedule: &WorkflowSchedule) -> Option<chrono::DateTime<chrono::Local>> {
+    schedule
+        .cron
+        .as_deref()
+        .and_then(|expr| CronSchedule::parse(expr).ok())
+        .and_then(|cron| cron.next_after(chrono::Local::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uses_secrets() {
+        let mut workflow = WorkflowManager::create_empty("login");
+        assert!(!uses_secrets(&workflow));
+        let step = WorkflowStep {
+            action: "type".to_string(),
+            target: Some("#password".to_string()),
+            value: Some("${secret:SHOP_PASSWORD}".to_string()),
+            ..workflow.steps[0].clone()
+        };
+        workflow.steps.push(step);
+        assert!(uses_secrets(&workflow));
+    }
+
+    #[test]
+    fn test_schedules_resolve_workflow_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = WorkflowStore {
+            manager: WorkflowManager::new(dir.path().join("workflows")),
+            domguard_dir: dir.path().to_path_buf(),
+            backend: StorageBackend::Fs,
+        };
+        let (workflow, _) = store.create("Daily report").unwrap();
+
+        let schedule = store
+            .schedule(WorkflowSchedule {
+                workflow_id: "Daily report".to_string(),
+                cron: Some("0 9 * * *".to_string()),
+                watch_url: None,
+                when_text: None,
+                interval_secs: 300,
+                params: HashMap::new(),
+                created_at: chrono::Utc::now(),
+            })
+            .unwrap();
+        assert_eq!(schedule.workflow_id, workflow.id);
+        assert!(next_run(&schedule).is_some());
+        assert_eq!(store.schedules().unwrap().len(), 1);
+
+        assert_eq!(
+            store.unschedule("Daily report").unwrap(),
+            (workflow.id.clone(), true)
+        );
+        assert!(!store.unschedule(&workflow.id).unwrap().1);
+        assert!(store.schedules().unwrap().is_empty());
+    }
+}
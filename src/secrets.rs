@@ -0,0 +1,560 @@
+//! Secret storage and placeholder expansion for DOMGuard
+//!
+//! Workflows can reference `${secret:NAME}` values kept in `.domguard/secrets.toml`
+//! and `${env:VAR}` values read from the environment at run time. Each stored
+//! secret is encrypted with ChaCha20-Poly1305 (RFC 8439) under a per-user key
+//! that lives outside the project (in the user config directory), so the secrets
+//! file can't be read on its own. The secret's name is authenticated along with
+//! its value, so an edited value, or one moved to another name, is rejected
+//! rather than decrypted.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// Key used to encrypt stored secrets
+pub type SecretKey = [u8; KEY_LEN];
+
+/// Encrypted secrets file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecretStore {
+    /// Secret name -> base64 of nonce, ciphertext and Poly1305 tag
+    #[serde(default)]
+    pub secrets: BTreeMap<String, String>,
+}
+
+impl SecretStore {
+    /// Load the secrets file, or an empty store if it doesn't exist
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Save the secrets file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Where the project's secrets are stored
+    pub fn path() -> PathBuf {
+        crate::config::Config::find_domguard_dir()
+            .unwrap_or_else(crate::config::Config::domguard_dir)
+            .join("secrets.toml")
+    }
+
+    /// The project's secrets
+    pub fn open() -> Result<Self> {
+        Self::load(&Self::path())
+    }
+
+    /// Save the project's secrets
+    pub fn save_project(&self) -> Result<()> {
+        self.save(&Self::path())
+    }
+
+    /// Encrypt and store a secret with the user's key, creating the key on first use
+    pub fn set_with_user_key(&mut self, name: &str, value: &str) -> Result<()> {
+        let key = load_or_create_key(&default_key_path())?;
+        self.set(name, value, &key)
+    }
+
+    /// Encrypt and store a secret, replacing any previous value
+    pub fn set(&mut self, name: &str, value: &str, key: &SecretKey) -> Result<()> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        {
+            return Err(anyhow!(
+                "Invalid secret name \"{}\" (use letters, digits, '_', '-' or '.')",
+                name
+            ));
+        }
+
+        let nonce: [u8; NONCE_LEN] = random_bytes()?;
+        let mut data = value.as_bytes().to_vec();
+        chacha20_xor(key, &nonce, 1, &mut data);
+        let tag = aead_tag(key, &nonce, name.as_bytes(), &data);
+
+        let mut stored = nonce.to_vec();
+        stored.extend(data);
+        stored.extend(tag);
+        self.secrets.insert(
+            name.to_string(),
+            base64::engine::general_purpose::STANDARD.encode(stored),
+        );
+        Ok(())
+    }
+
+    /// Remove a secret, returning whether it existed
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.secrets.remove(name).is_some()
+    }
+
+    /// Decrypt a single secret, failing if it doesn't authenticate
+    pub fn get(&self, name: &str, key: &SecretKey) -> Result<Option<String>> {
+        let Some(stored) = self.secrets.get(name) else {
+            return Ok(None);
+        };
+
+        let mut data = base64::engine::general_purpose::STANDARD
+            .decode(stored)
+            .with_context(|| format!("Secret {} is corrupt", name))?;
+        if data.len() < NONCE_LEN + TAG_LEN {
+            return Err(anyhow!("Secret {} is corrupt", name));
+        }
+        let tag = data.split_off(data.len() - TAG_LEN);
+        let mut ciphertext = data.split_off(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = data
+            .try_into()
+            .map_err(|_| anyhow!("Secret {} is corrupt", name))?;
+
+        let expected = aead_tag(key, &nonce, name.as_bytes(), &ciphertext);
+        // Compared in constant time so the tag can't be guessed byte by byte
+        if expected
+            .iter()
+            .zip(&tag)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            != 0
+        {
+            return Err(anyhow!(
+                "Secret {} failed its integrity check (was it edited, or set with another key? Set it again with 'domguard secrets set {}')",
+                name,
+                name
+            ));
+        }
+        chacha20_xor(key, &nonce, 1, &mut ciphertext);
+
+        String::from_utf8(ciphertext)
+            .map(Some)
+            .map_err(|_| anyhow!("Secret {} is corrupt", name))
+    }
+
+    /// Decrypt every stored secret
+    pub fn decrypt_all(&self, key: &SecretKey) -> Result<BTreeMap<String, String>> {
+        let mut values = BTreeMap::new();
+        for name in self.secrets.keys() {
+            if let Some(value) = self.get(name, key)? {
+                values.insert(name.clone(), value);
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// Default location of the per-user secret key
+pub fn default_key_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("domguard")
+        .join("secrets.key")
+}
+
+/// Read the secret key, generating one (readable only by the user) on first use
+pub fn load_or_create_key(path: &Path) -> Result<SecretKey> {
+    use std::io::Write;
+
+    if path.exists() {
+        return read_key(path);
+    }
+
+    let key: SecretKey = random_bytes()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Created with its final mode so the key is never readable by others, even briefly
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    match options.open(path) {
+        Ok(mut file) => file
+            .write_all(&key)
+            .and_then(|()| file.sync_all())
+            .with_context(|| format!("Failed to write {}", path.display()))?,
+        // Another DOMGuard process created it first
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return read_key(path),
+        Err(e) => return Err(e).with_context(|| format!("Failed to create {}", path.display())),
+    }
+
+    Ok(key)
+}
+
+fn read_key(path: &Path) -> Result<SecretKey> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("Secret key {} is corrupt", path.display()))
+}
+
+/// Expand `${env:VAR}` and `${secret:NAME}` placeholders in `text`
+pub fn expand_placeholders(text: &str, secrets: &BTreeMap<String, String>) -> Result<String> {
+    let re = regex::Regex::new(r"\$\{(env|secret):([A-Za-z0-9_.-]+)\}")?;
+
+    let mut output = String::with_capacity(text.len());
+    let mut last = 0;
+    for caps in re.captures_iter(text) {
+        let whole = caps.get(0).ok_or_else(|| anyhow!("Invalid placeholder"))?;
+        let name = &caps[2];
+        let value = if &caps[1] == "env" {
+            std::env::var(name).map_err(|_| anyhow!("Environment variable not set: {}", name))?
+        } else {
+            secrets.get(name).cloned().ok_or_else(|| {
+                anyhow!(
+                    "Secret not found: {} (add it with 'domguard secrets set {}')",
+                    name,
+                    name
+                )
+            })?
+        };
+
+        output.push_str(&text[last..whole.start()]);
+        output.push_str(&value);
+        last = whole.end();
+    }
+    output.push_str(&text[last..]);
+
+    Ok(output)
+}
+
+/// Fill an array with OS randomness
+///
+/// Keys and nonces need a cryptographic source, so there is no fallback
+/// where `/dev/urandom` is missing (Windows): storing secrets fails instead.
+fn random_bytes<const N: usize>() -> Result<[u8; N]> {
+    use std::io::Read;
+
+    let mut urandom = std::fs::File::open("/dev/urandom").map_err(|e| {
+        anyhow!(
+            "No secure random source for encrypting secrets (/dev/urandom: {}); use ${{env:VAR}} placeholders instead",
+            e
+        )
+    })?;
+    let mut bytes = [0u8; N];
+    urandom
+        .read_exact(&mut bytes)
+        .context("Failed to read /dev/urandom")?;
+    Ok(bytes)
+}
+
+/// ChaCha20-Poly1305 (RFC 8439) tag over `aad` and `ciphertext`
+fn aead_tag(
+    key: &SecretKey,
+    nonce: &[u8; NONCE_LEN],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> [u8; TAG_LEN] {
+    // The one-time Poly1305 key is the first half of keystream block 0
+    let mut one_time_key = [0u8; 32];
+    chacha20_xor(key, nonce, 0, &mut one_time_key);
+
+    let mut mac_data = Vec::with_capacity(aad.len() + ciphertext.len() + 48);
+    for part in [aad, ciphertext] {
+        mac_data.extend_from_slice(part);
+        mac_data.resize(mac_data.len().next_multiple_of(16), 0);
+    }
+    mac_data.extend((aad.len() as u64).to_le_bytes());
+    mac_data.extend((ciphertext.len() as u64).to_le_bytes());
+    poly1305(&one_time_key, &mac_data)
+}
+
+/// Poly1305 (RFC 8439) one-time authenticator, in 26-bit limbs
+fn poly1305(key: &[u8; 32], message: &[u8]) -> [u8; TAG_LEN] {
+    const MASK: u64 = 0x3ff_ffff;
+    let word = |bytes: &[u8], i: usize| {
+        u64::from(u32::from_le_bytes([
+            bytes[i],
+            bytes[i + 1],
+            bytes[i + 2],
+            bytes[i + 3],
+        ]))
+    };
+
+    // r, clamped as the RFC requires
+    let r = [
+        word(key, 0) & 0x3ff_ffff,
+        (word(key, 3) >> 2) & 0x3ff_ff03,
+        (word(key, 6) >> 4) & 0x3ff_c0ff,
+        (word(key, 9) >> 6) & 0x3f0_3fff,
+        (word(key, 12) >> 8) & 0x00f_ffff,
+    ];
+    // 2^130 = 5 (mod p), so limbs past the top wrap around times 5
+    let s = [0, r[1] * 5, r[2] * 5, r[3] * 5, r[4] * 5];
+
+    let mut h = [0u64; 5];
+    for chunk in message.chunks(16) {
+        // Each block gets a 1 appended above its last byte
+        let mut block = [0u8; 17];
+        block[..chunk.len()].copy_from_slice(chunk);
+        block[chunk.len()] = 1;
+        h[0] += word(&block, 0) & MASK;
+        h[1] += (word(&block, 3) >> 2) & MASK;
+        h[2] += (word(&block, 6) >> 4) & MASK;
+        h[3] += (word(&block, 9) >> 6) & MASK;
+        h[4] += (word(&block, 12) >> 8) | (u64::from(block[16]) << 24);
+
+        let mut d = [
+            h[0] * r[0] + h[1] * s[4] + h[2] * s[3] + h[3] * s[2] + h[4] * s[1],
+            h[0] * r[1] + h[1] * r[0] + h[2] * s[4] + h[3] * s[3] + h[4] * s[2],
+            h[0] * r[2] + h[1] * r[1] + h[2] * r[0] + h[3] * s[4] + h[4] * s[3],
+            h[0] * r[3] + h[1] * r[2] + h[2] * r[1] + h[3] * r[0] + h[4] * s[4],
+            h[0] * r[4] + h[1] * r[3] + h[2] * r[2] + h[3] * r[1] + h[4] * r[0],
+        ];
+        for i in 0..4 {
+            d[i + 1] += d[i] >> 26;
+            d[i] &= MASK;
+        }
+        d[0] += (d[4] >> 26) * 5;
+        d[4] &= MASK;
+        d[1] += d[0] >> 26;
+        d[0] &= MASK;
+        h = d;
+    }
+
+    // Fully carry, then subtract p = 2^130 - 5 if h is at least p
+    for i in 0..4 {
+        h[i + 1] += h[i] >> 26;
+        h[i] &= MASK;
+    }
+    h[0] += (h[4] >> 26) * 5;
+    h[4] &= MASK;
+    h[1] += h[0] >> 26;
+    h[0] &= MASK;
+
+    let mut g = [0u64; 5];
+    let mut carry = 5;
+    for i in 0..4 {
+        g[i] = h[i] + carry;
+        carry = g[i] >> 26;
+        g[i] &= MASK;
+    }
+    g[4] = (h[4] + carry).wrapping_sub(1 << 26);
+    // All ones when the subtraction didn't borrow, without branching on h
+    let use_g = (g[4] >> 63).wrapping_sub(1);
+    for (h, g) in h.iter_mut().zip(g) {
+        *h = (*h & !use_g) | (g & use_g & MASK);
+    }
+
+    // h mod 2^128 in 32-bit words, plus s (the key's second half)
+    let words = [
+        (h[0] | (h[1] << 26)) & 0xffff_ffff,
+        ((h[1] >> 6) | (h[2] << 20)) & 0xffff_ffff,
+        ((h[2] >> 12) | (h[3] << 14)) & 0xffff_ffff,
+        ((h[3] >> 18) | (h[4] << 8)) & 0xffff_ffff,
+    ];
+    let mut tag = [0u8; TAG_LEN];
+    let mut sum = 0u64;
+    for (i, w) in words.into_iter().enumerate() {
+        sum = w + word(key, 16 + i * 4) + (sum >> 32);
+        tag[i * 4..i * 4 + 4].copy_from_slice(&(sum as u32).to_le_bytes());
+    }
+    tag
+}
+
+/// ChaCha20 (RFC 8439) keystream XORed into `data`
+fn chacha20_xor(key: &SecretKey, nonce: &[u8; NONCE_LEN], counter: u32, data: &mut [u8]) {
+    let word = |bytes: &[u8], i: usize| {
+        u32::from_le_bytes([
+            bytes[i * 4],
+            bytes[i * 4 + 1],
+            bytes[i * 4 + 2],
+            bytes[i * 4 + 3],
+        ])
+    };
+
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&[0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574]);
+    for (i, slot) in state[4..12].iter_mut().enumerate() {
+        *slot = word(key, i);
+    }
+    for (i, slot) in state[13..].iter_mut().enumerate() {
+        *slot = word(nonce, i);
+    }
+
+    for (block, chunk) in data.chunks_mut(64).enumerate() {
+        state[12] = counter.wrapping_add(block as u32);
+
+        let mut working = state;
+        for _ in 0..10 {
+            for (a, b, c, d) in [
+                (0, 4, 8, 12),
+                (1, 5, 9, 13),
+                (2, 6, 10, 14),
+                (3, 7, 11, 15),
+                (0, 5, 10, 15),
+                (1, 6, 11, 12),
+                (2, 7, 8, 13),
+                (3, 4, 9, 14),
+            ] {
+                working[a] = working[a].wrapping_add(working[b]);
+                working[d] = (working[d] ^ working[a]).rotate_left(16);
+                working[c] = working[c].wrapping_add(working[d]);
+                working[b] = (working[b] ^ working[c]).rotate_left(12);
+                working[a] = working[a].wrapping_add(working[b]);
+                working[d] = (working[d] ^ working[a]).rotate_left(8);
+                working[c] = working[c].wrapping_add(working[d]);
+                working[b] = (working[b] ^ working[c]).rotate_left(7);
+            }
+        }
+
+        let keystream: Vec<u8> = working
+            .iter()
+            .zip(state.iter())
+            .flat_map(|(w, s)| w.wrapping_add(*s).to_le_bytes())
+            .collect();
+        for (byte, k) in chunk.iter_mut().zip(keystream) {
+            *byte ^= k;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chacha20_rfc8439_vector() {
+        let key: SecretKey = std::array::from_fn(|i| i as u8);
+        let nonce = [0, 0, 0, 0, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+        let mut data = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.".to_vec();
+        chacha20_xor(&key, &nonce, 1, &mut data);
+        assert_eq!(
+            &data[..16],
+            &[
+                0x6e, 0x2e, 0x35, 0x9a, 0x25, 0x68, 0xf9, 0x80, 0x41, 0xba, 0x07, 0x28, 0xdd, 0x0d,
+                0x69, 0x81
+            ]
+        );
+    }
+
+    #[test]
+    fn test_poly1305_rfc8439_vectors() {
+        let key: [u8; 32] = [
+            0x85, 0xd6, 0xbe, 0x78, 0x57, 0x55, 0x6d, 0x33, 0x7f, 0x44, 0x52, 0xfe, 0x42, 0xd5,
+            0x06, 0xa8, 0x01, 0x03, 0x80, 0x8a, 0xfb, 0x0d, 0xb2, 0xfd, 0x4a, 0xbf, 0xf6, 0xaf,
+            0x41, 0x49, 0xf5, 0x1b,
+        ];
+        assert_eq!(
+            poly1305(&key, b"Cryptographic Forum Research Group"),
+            [
+                0xa8, 0x06, 0x1d, 0xc1, 0x30, 0x51, 0x36, 0xc6, 0xc2, 0x2b, 0x8b, 0xaf, 0x0c, 0x01,
+                0x27, 0xa9
+            ]
+        );
+
+        // The AEAD example of section 2.8.2
+        let key: SecretKey = std::array::from_fn(|i| 0x80 + i as u8);
+        let nonce = [
+            0x07, 0, 0, 0, 0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47,
+        ];
+        let aad = [
+            0x50, 0x51, 0x52, 0x53, 0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7,
+        ];
+        let mut data = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.".to_vec();
+        chacha20_xor(&key, &nonce, 1, &mut data);
+        assert_eq!(
+            aead_tag(&key, &nonce, &aad, &data),
+            [
+                0x1a, 0xe1, 0x0b, 0x59, 0x4f, 0x09, 0xe2, 0x6a, 0x7e, 0x90, 0x2e, 0xcb, 0xd0, 0x60,
+                0x06, 0x91
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tampered_secret_is_rejected() {
+        let key: SecretKey = [7; KEY_LEN];
+        let mut store = SecretStore::default();
+        store.set("API_TOKEN", "s3cr3t", &key).unwrap();
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        let mut bytes = engine.decode(&store.secrets["API_TOKEN"]).unwrap();
+        bytes[NONCE_LEN] ^= 1;
+        let mut tampered = store.clone();
+        tampered
+            .secrets
+            .insert("API_TOKEN".to_string(), engine.encode(&bytes));
+        assert!(tampered.get("API_TOKEN", &key).is_err());
+
+        // A valid value moved to another name doesn't authenticate either
+        let mut moved = store.clone();
+        let value = moved.secrets["API_TOKEN"].clone();
+        moved.secrets.insert("OTHER".to_string(), value);
+        assert!(moved.get("OTHER", &key).is_err());
+
+        assert!(store.get("API_TOKEN", &[8; KEY_LEN]).is_err());
+        assert_eq!(
+            store.get("API_TOKEN", &key).unwrap().as_deref(),
+            Some("s3cr3t")
+        );
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = load_or_create_key(&dir.path().join("secrets.key")).unwrap();
+        let path = dir.path().join("secrets.toml");
+
+        let mut store = SecretStore::default();
+        store.set("API_TOKEN", "s3cr3t", &key).unwrap();
+        assert!(store.set("bad name", "x", &key).is_err());
+        store.save(&path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("s3cr3t"));
+
+        let loaded = SecretStore::load(&path).unwrap();
+        assert_eq!(
+            loaded.get("API_TOKEN", &key).unwrap().as_deref(),
+            Some("s3cr3t")
+        );
+        assert_eq!(
+            load_or_create_key(&dir.path().join("secrets.key")).unwrap(),
+            key
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join("secrets.key"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        let mut secrets = BTreeMap::new();
+        secrets.insert("PASSWORD".to_string(), "hunter2".to_string());
+        std::env::set_var("DOMGUARD_TEST_USER", "alice");
+
+        let expanded =
+            expand_placeholders("${env:DOMGUARD_TEST_USER}:${secret:PASSWORD}!", &secrets).unwrap();
+        assert_eq!(expanded, "alice:hunter2!");
+
+        assert!(expand_placeholders("${secret:MISSING}", &secrets).is_err());
+        assert_eq!(
+            expand_placeholders("{{param}} $HOME", &secrets).unwrap(),
+            "{{param}} $HOME"
+        );
+    }
+}
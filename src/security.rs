@@ -2,10 +2,13 @@
 //!
 //! Implements sensitive action detection and blocked site list.
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 use std::fmt::Write as _;
-use std::path::PathBuf;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::cdp::CdpConnection;
 
 /// Sensitive action types that require extra attention
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -75,14 +78,32 @@ impl Default for SensitiveActionDetection {
     }
 }
 
+/// How interact commands respond when they detect a sensitive action
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityEnforcement {
+    /// Don't check
+    Off,
+    /// Print a warning and go ahead
+    #[default]
+    Warn,
+    /// Refuse unless the command is run with `--confirm-sensitive`
+    Confirm,
+    /// Always refuse
+    Block,
+}
+
 /// Blocked sites configuration
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BlockedSitesConfig {
     /// List of blocked domains/patterns
+    #[serde(default)]
     pub blocked: Vec<String>,
     /// Whether to block by default and only allow certain sites
+    #[serde(default)]
     pub default_block: bool,
     /// Allowed sites (when default_block is true)
+    #[serde(default)]
     pub allowed: Vec<String>,
 }
 
@@ -107,19 +128,26 @@ impl BlockedSitesConfig {
 
     /// Check if a URL is blocked
     pub fn is_blocked(&self, url: &str) -> bool {
+        self.blocking_rule(url).is_some()
+    }
+
+    /// The rule that blocks a URL, if any
+    pub fn blocking_rule(&self, url: &str) -> Option<String> {
         let url_lower = url.to_lowercase();
 
         if self.default_block {
             // Block by default, check if in allowed list
-            !self
+            let allowed = self
                 .allowed
                 .iter()
-                .any(|pattern| url_lower.contains(&pattern.to_lowercase()))
+                .any(|pattern| url_lower.contains(&pattern.to_lowercase()));
+            (!allowed).then(|| "default block (not in allowed list)".to_string())
         } else {
             // Allow by default, check if in blocked list
             self.blocked
                 .iter()
-                .any(|pattern| url_lower.contains(&pattern.to_lowercase()))
+                .find(|pattern| url_lower.contains(&pattern.to_lowercase()))
+                .cloned()
         }
     }
 
@@ -141,6 +169,154 @@ impl BlockedSitesConfig {
             self.allowed.push(pattern.to_string());
         }
     }
+
+    /// Block by default (`block`) or allow by default (`allow`)
+    pub fn set_mode(&mut self, mode: &str) -> Result<()> {
+        match mode {
+            "allow" => self.default_block = false,
+            "block" => self.default_block = true,
+            _ => bail!("Invalid mode: {}. Use 'allow' or 'block'", mode),
+        }
+        Ok(())
+    }
+
+    /// The mode for display
+    pub fn mode_description(&self) -> &'static str {
+        if self.default_block {
+            "block by default"
+        } else {
+            "allow by default"
+        }
+    }
+
+    /// Where the project's list is stored
+    pub fn path() -> PathBuf {
+        crate::config::Config::find_domguard_dir()
+            .unwrap_or_else(crate::config::Config::domguard_dir)
+            .join("blocked_sites.toml")
+    }
+
+    /// The project's list, or an empty one when the file is missing or unreadable
+    pub fn open() -> Self {
+        Self::load(&Self::path()).unwrap_or_default()
+    }
+
+    /// Save the project's list, creating `.domguard` if needed
+    pub fn save_project(&self) -> Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.save(&path)
+    }
+}
+
+/// A navigation refused by the blocked sites list
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockedByPolicy {
+    /// Command that tried to open the URL (e.g., "interact navigate")
+    pub command: String,
+    pub url: String,
+    /// Blocked site pattern that matched
+    pub rule: String,
+    /// `--override-block` was given but overrides aren't enabled
+    pub override_refused: bool,
+}
+
+impl std::fmt::Display for BlockedByPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Blocked by policy: {} matches blocked site rule \"{}\"",
+            self.url, self.rule
+        )?;
+        if self.override_refused {
+            write!(
+                f,
+                " (--override-block needs allow_block_override = true under [security] in .domguard/config.toml)"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for BlockedByPolicy {}
+
+/// Entry in `.domguard/audit.log` (one JSON object per line)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub command: String,
+    pub url: String,
+    pub rule: String,
+    /// Whether the block was overridden with `--override-block`
+    pub overridden: bool,
+}
+
+/// Check `url` against `blocked_sites.toml` in `domguard_dir` plus `extra_blocked`,
+/// recording every hit in the audit log
+///
+/// A blocked URL only goes through when `override_block` is requested and
+/// `allow_override` permits it; otherwise the error is a [`BlockedByPolicy`].
+pub fn check_site_policy(
+    domguard_dir: &Path,
+    command: &str,
+    url: &str,
+    override_block: bool,
+    allow_override: bool,
+    extra_blocked: &[String],
+) -> Result<()> {
+    let mut blocked_sites =
+        BlockedSitesConfig::load(&domguard_dir.join("blocked_sites.toml")).unwrap_or_default();
+    blocked_sites.blocked.extend_from_slice(extra_blocked);
+    let Some(rule) = blocked_sites.blocking_rule(url) else {
+        return Ok(());
+    };
+
+    let overridden = override_block && allow_override;
+    let entry = AuditEntry {
+        timestamp: chrono::Utc::now(),
+        command: command.to_string(),
+        url: url.to_string(),
+        rule: rule.clone(),
+        overridden,
+    };
+    let mut log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(domguard_dir.join("audit.log"))?;
+    writeln!(log, "{}", serde_json::to_string(&entry)?)?;
+
+    if overridden {
+        return Ok(());
+    }
+    Err(BlockedByPolicy {
+        command: command.to_string(),
+        url: url.to_string(),
+        rule,
+        override_refused: override_block,
+    }
+    .into())
+}
+
+/// [`check_site_policy`] for the current project and its `[security]` settings
+pub fn enforce_site_policy(
+    config: &crate::config::Config,
+    command: &str,
+    url: &str,
+    override_block: bool,
+) -> Result<()> {
+    use crate::config::Config;
+
+    let domguard_dir = Config::find_domguard_dir().unwrap_or_else(Config::domguard_dir);
+    check_site_policy(
+        &domguard_dir,
+        command,
+        url,
+        override_block,
+        config.security.allow_block_override,
+        &config.security.blocked_sites,
+    )
 }
 
 /// Security checker for detecting sensitive actions
@@ -153,6 +329,25 @@ impl SecurityChecker {
         Self { blocked_sites }
     }
 
+    /// Check a `type`, `click`, `navigate` or `upload` action on `target`
+    pub fn check(
+        &self,
+        action: &str,
+        target: &str,
+        value: Option<&str>,
+    ) -> Result<SensitiveActionDetection> {
+        Ok(match action {
+            "type" => Self::check_type_action(target, value.unwrap_or("")),
+            "click" => Self::check_click_action(target),
+            "navigate" => self.check_navigation(target),
+            "upload" => Self::check_upload(&[PathBuf::from(target)]),
+            _ => bail!(
+                "Unknown action type: {}. Use: type, click, navigate, or upload",
+                action
+            ),
+        })
+    }
+
     /// Check if typing into a selector is sensitive
     pub fn check_type_action(selector: &str, _text: &str) -> SensitiveActionDetection {
         let selector_lower = selector.to_lowercase();
@@ -193,6 +388,9 @@ impl SecurityChecker {
             ("tax-id", "Tax ID"),
             ("passport", "Passport Number"),
             ("license", "License Number"),
+            ("cc-number", "Credit Card Number"),
+            ("cc-csc", "Card Security Code"),
+            ("cc-exp", "Expiration Date"),
         ];
 
         for (pattern, description) in sensitive_patterns {
@@ -421,6 +619,144 @@ impl SecurityChecker {
     }
 }
 
+/// An interact command, as far as the sensitive-action checks care
+#[derive(Debug, Clone, Copy)]
+pub enum InteractAction<'a> {
+    /// Typing into `selector`, or the focused element when it is None
+    Type {
+        selector: Option<&'a str>,
+        text: &'a str,
+    },
+    /// Clicking `selector` and/or the element showing `text`
+    Click {
+        selector: Option<&'a str>,
+        text: Option<&'a str>,
+    },
+    Navigate {
+        url: &'a str,
+    },
+    Upload {
+        files: &'a [PathBuf],
+    },
+}
+
+impl InteractAction<'_> {
+    /// The checks that don't need the page (navigations and uploads)
+    ///
+    /// The blocked sites list is enforced separately, before connecting.
+    pub fn check_args(&self) -> Option<SensitiveActionDetection> {
+        let detection = match self {
+            Self::Navigate { url } => {
+                SecurityChecker::new(BlockedSitesConfig::default()).check_navigation(url)
+            }
+            Self::Upload { files } => SecurityChecker::check_upload(files),
+            Self::Type { .. } | Self::Click { .. } => return None,
+        };
+        detection.detected.then_some(detection)
+    }
+
+    /// Every check, judging type and click targets by the element on the page
+    pub async fn check(&self, cdp: &CdpConnection) -> Option<SensitiveActionDetection> {
+        let target = match *self {
+            Self::Type { selector, .. }
+            | Self::Click {
+                selector: selector @ Some(_),
+                ..
+            } => describe_target(cdp, selector).await,
+            Self::Click { selector: None, .. } => String::new(),
+            Self::Navigate { .. } | Self::Upload { .. } => return self.check_args(),
+        };
+        self.check_target(target)
+    }
+
+    /// Every check, judging type and click targets by their selectors alone
+    /// (for backends that can't describe an element)
+    pub fn check_selector(&self) -> Option<SensitiveActionDetection> {
+        let target = match *self {
+            Self::Type { selector, .. } | Self::Click { selector, .. } => {
+                selector.unwrap_or_default().to_string()
+            }
+            Self::Navigate { .. } | Self::Upload { .. } => return self.check_args(),
+        };
+        self.check_target(target)
+    }
+
+    fn check_target(&self, mut target: String) -> Option<SensitiveActionDetection> {
+        let detection = match *self {
+            Self::Type { text, .. } => SecurityChecker::check_type_action(&target, text),
+            Self::Click { text, .. } => {
+                if let Some(text) = text {
+                    target.push(' ');
+                    target.push_str(text);
+                }
+                SecurityChecker::check_click_action(&target)
+            }
+            Self::Navigate { .. } | Self::Upload { .. } => return self.check_args(),
+        };
+        detection.detected.then_some(detection)
+    }
+}
+
+/// Selector plus what the element itself says it is (`input[type=password]#pw`, its form's
+/// action), so checks don't depend on how the selector happens to be written
+pub async fn describe_target(cdp: &CdpConnection, selector: Option<&str>) -> String {
+    let element = match selector {
+        Some(selector) => format!(
+            "document.querySelector({})",
+            serde_json::to_string(selector).unwrap_or_default()
+        ),
+        None => "document.activeElement".to_string(),
+    };
+    let script = format!(
+        r"(() => {{
+            let el;
+            try {{ el = {}; }} catch (e) {{ return ''; }}
+            if (!el || !el.tagName) return '';
+            const attr = (name) => el.getAttribute(name) ? '[' + name + '=' + el.getAttribute(name) + ']' : '';
+            const classes = typeof el.className === 'string' && el.className.trim()
+                ? '.' + el.className.trim().split(/\s+/).join('.') : '';
+            const form = el.form || el.closest('form');
+            const action = form && form.getAttribute('action') ? ' form[action=' + form.getAttribute('action') + ']' : '';
+            return el.tagName.toLowerCase() + attr('type') + attr('name') + attr('autocomplete')
+                + (el.id ? '#' + el.id : '') + classes + action;
+        }})()",
+        element
+    );
+
+    let described = cdp
+        .evaluate(&script)
+        .await
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default();
+    format!("{} {}", selector.unwrap_or_default(), described)
+}
+
+/// Decide whether a detected sensitive action may go ahead under `mode`
+pub fn enforce(
+    detection: &SensitiveActionDetection,
+    mode: SecurityEnforcement,
+    confirmed: bool,
+) -> Result<()> {
+    if !detection.detected {
+        return Ok(());
+    }
+
+    let reason = detection
+        .reason
+        .as_deref()
+        .unwrap_or("Sensitive action detected");
+    match mode {
+        SecurityEnforcement::Off | SecurityEnforcement::Warn => Ok(()),
+        SecurityEnforcement::Confirm if confirmed => Ok(()),
+        SecurityEnforcement::Confirm => bail!(
+            "Sensitive action: {} (re-run with --confirm-sensitive to proceed)",
+            reason
+        ),
+        SecurityEnforcement::Block => bail!("Blocked by security policy: {}", reason),
+    }
+}
+
 /// Format security warning for display
 pub fn format_security_warning(detection: &SensitiveActionDetection) -> String {
     if !detection.detected {
@@ -490,6 +826,23 @@ mod tests {
         assert_eq!(result.action_type, Some(SensitiveActionType::BlockedSite));
     }
 
+    #[test]
+    fn test_check_and_mode() {
+        let checker = SecurityChecker::new(BlockedSitesConfig::default());
+        assert!(
+            checker
+                .check("type", "#password", Some("x"))
+                .unwrap()
+                .detected
+        );
+        assert!(checker.check("hover", "#menu", None).is_err());
+
+        let mut config = BlockedSitesConfig::default();
+        config.set_mode("block").unwrap();
+        assert_eq!(config.mode_description(), "block by default");
+        assert!(config.set_mode("deny").is_err());
+    }
+
     #[test]
     fn test_financial_site_detection() {
         let checker = SecurityChecker::new(BlockedSitesConfig::default());
@@ -505,6 +858,96 @@ mod tests {
         assert_eq!(result.action_type, Some(SensitiveActionType::FileUpload));
     }
 
+    #[test]
+    fn test_interact_action_check_args() {
+        let files = [PathBuf::from("/home/user/passport.pdf")];
+        let upload = InteractAction::Upload { files: &files }.check_args();
+        assert!(upload.is_some());
+
+        let navigate = InteractAction::Navigate {
+            url: "https://www.bankofamerica.com/",
+        };
+        assert!(navigate.check_args().is_some());
+
+        // Typing needs the page to judge its target
+        let typing = InteractAction::Type {
+            selector: Some("#password"),
+            text: "hunter2",
+        };
+        assert!(typing.check_args().is_none());
+    }
+
+    #[test]
+    fn test_enforce_modes() {
+        let detection = SecurityChecker::check_type_action("input[autocomplete=cc-number]", "4111");
+        assert!(detection.detected);
+
+        assert!(enforce(&detection, SecurityEnforcement::Warn, false).is_ok());
+        assert!(enforce(&detection, SecurityEnforcement::Confirm, true).is_ok());
+        let err = enforce(&detection, SecurityEnforcement::Confirm, false).unwrap_err();
+        assert!(err.to_string().contains("--confirm-sensitive"));
+        assert!(enforce(&detection, SecurityEnforcement::Block, true).is_err());
+
+        let normal = SensitiveActionDetection::default();
+        assert!(enforce(&normal, SecurityEnforcement::Block, false).is_ok());
+    }
+
+    #[test]
+    fn test_site_policy_audit_and_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = BlockedSitesConfig::default();
+        config.block("malicious-site.com");
+        config.save(&dir.path().join("blocked_sites.toml")).unwrap();
+
+        assert!(
+            check_site_policy(dir.path(), "test", "https://example.com", false, false, &[]).is_ok()
+        );
+
+        let err = check_site_policy(
+            dir.path(),
+            "test",
+            "https://malicious-site.com/x",
+            true,
+            false,
+            &[],
+        )
+        .unwrap_err();
+        let blocked = err.downcast_ref::<BlockedByPolicy>().unwrap();
+        assert_eq!(blocked.rule, "malicious-site.com");
+        assert!(blocked.override_refused);
+
+        assert!(check_site_policy(
+            dir.path(),
+            "test",
+            "https://malicious-site.com/x",
+            true,
+            true,
+            &[],
+        )
+        .is_ok());
+
+        let log = std::fs::read_to_string(dir.path().join("audit.log")).unwrap();
+        let entries: Vec<AuditEntry> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert!(!entries[0].overridden);
+        assert!(entries[1].overridden);
+
+        // Patterns from [security] blocked_sites count too
+        let extra = vec!["admin.example.com".to_string()];
+        assert!(check_site_policy(
+            dir.path(),
+            "test",
+            "https://admin.example.com/",
+            false,
+            false,
+            &extra,
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_normal_action() {
         let result = SecurityChecker::check_type_action("#search-input", "hello");
@@ -0,0 +1,120 @@
+//! gRPC control server for DOMGuard
+//!
+//! `domguard serve --grpc <addr>` serves the API in `proto/domguard.proto`.
+//! The server is its own binary, `domguard-grpc` (the `grpc/` crate), so the
+//! CLI doesn't pull in tonic and prost; this module finds and runs it.
+
+use anyhow::{anyhow, Context, Result};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Name of the server binary
+pub const SERVER_BINARY: &str = "domguard-grpc";
+
+/// The Chrome connection every call of the server uses
+#[derive(Debug, Clone, Default)]
+pub struct ServeOptions {
+    pub profile: Option<String>,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub allow_remote: bool,
+}
+
+/// Find `domguard-grpc`: next to this binary first, then in PATH
+pub fn find_server() -> Result<PathBuf> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| {
+            let mut sibling = exe.with_file_name(SERVER_BINARY);
+            if let Some(extension) = exe.extension() {
+                sibling.set_extension(extension);
+            }
+            sibling.is_file().then_some(sibling)
+        })
+        .or_else(|| which::which(SERVER_BINARY).ok())
+        .ok_or_else(|| {
+            anyhow!(
+                "{} not found next to domguard or in PATH (install it with 'cargo install --path grpc')",
+                SERVER_BINARY
+            )
+        })
+}
+
+/// Arguments for `domguard-grpc`, which runs `domguard` for each call
+pub fn server_args(
+    listen: SocketAddr,
+    domguard: &std::path::Path,
+    options: &ServeOptions,
+) -> Vec<String> {
+    let mut args = vec![
+        "--listen".to_string(),
+        listen.to_string(),
+        "--domguard".to_string(),
+        domguard.display().to_string(),
+    ];
+    if let Some(profile) = &options.profile {
+        args.extend(["--profile".to_string(), profile.clone()]);
+    }
+    if let Some(host) = &options.host {
+        args.extend(["--host".to_string(), host.clone()]);
+    }
+    if let Some(port) = options.port {
+        args.extend(["--port".to_string(), port.to_string()]);
+    }
+    if options.allow_remote {
+        args.push("--allow-remote".to_string());
+    }
+    args
+}
+
+/// Serve on `listen` until the server exits
+///
+/// Anyone who can reach the address controls the browser, so addresses
+/// other than loopback need `allow_remote`.
+pub fn run(listen: SocketAddr, options: &ServeOptions) -> Result<()> {
+    if !listen.ip().is_loopback() && !options.allow_remote {
+        return Err(anyhow!(
+            "Refusing to serve on {}: anyone who can reach it controls the browser (listen on 127.0.0.1, or pass --allow-remote)",
+            listen
+        ));
+    }
+    let domguard = std::env::current_exe().context("Failed to locate the domguard binary")?;
+    let status = Command::new(find_server()?)
+        .args(server_args(listen, &domguard, options))
+        .status()
+        .with_context(|| format!("Failed to run {}", SERVER_BINARY))?;
+    if !status.success() {
+        return Err(anyhow!("{} exited with {}", SERVER_BINARY, status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_args() {
+        let listen: SocketAddr = "127.0.0.1:7331".parse().unwrap();
+        let options = ServeOptions {
+            port: Some(9333),
+            ..ServeOptions::default()
+        };
+        assert_eq!(
+            server_args(listen, std::path::Path::new("/bin/domguard"), &options),
+            [
+                "--listen",
+                "127.0.0.1:7331",
+                "--domguard",
+                "/bin/domguard",
+                "--port",
+                "9333"
+            ]
+        );
+
+        let open: SocketAddr = "0.0.0.0:7331".parse().unwrap();
+        let error = run(open, &ServeOptions::default()).unwrap_err();
+        assert!(error.to_string().contains("--allow-remote"));
+    }
+}
@@ -7,7 +7,13 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::cdp::CdpConnection;
+use crate::config::Config;
+use crate::debug::ThrottleState;
+use crate::storage::{FileLock, FsSessionStore, SessionFilter, SessionStore, StorageBackend};
 
 /// A single recorded action
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,11 +37,27 @@ pub struct RecordedAction {
     /// Page URL at time of action
     #[serde(skip_serializing_if = "Option::is_none")]
     pub page_url: Option<String>,
+    /// Page URL after the action finished
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_url_after: Option<String>,
     /// Element selector if applicable
     #[serde(skip_serializing_if = "Option::is_none")]
     pub selector: Option<String>,
 }
 
+impl RecordedAction {
+    /// The CLI command that repeats this action
+    pub fn as_command(&self) -> String {
+        let base = format!("domguard interact {}", self.command);
+
+        if let Some(selector) = &self.selector {
+            format!("{} \"{}\"", base, selector)
+        } else {
+            base
+        }
+    }
+}
+
 /// Status of an action
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -79,6 +101,26 @@ pub enum SessionStatus {
     Paused,
     Completed,
     Failed,
+    /// The command recording it was cut off by Ctrl-C
+    Interrupted,
+}
+
+impl std::str::FromStr for SessionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "recording" => Ok(Self::Recording),
+            "paused" => Ok(Self::Paused),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            "interrupted" => Ok(Self::Interrupted),
+            other => Err(format!(
+                "Unknown session status \"{}\" (expected recording, paused, completed, failed or interrupted)",
+                other
+            )),
+        }
+    }
 }
 
 /// Session metadata
@@ -96,6 +138,12 @@ pub struct SessionMetadata {
     /// Viewport dimensions at start
     #[serde(skip_serializing_if = "Option::is_none")]
     pub viewport: Option<(u32, u32)>,
+    /// Started implicitly by auto-record mode rather than `session start`
+    #[serde(default)]
+    pub auto_recorded: bool,
+    /// Capture a thumbnail screenshot after each action
+    #[serde(default)]
+    pub screenshots: bool,
 }
 
 impl Session {
@@ -144,6 +192,16 @@ impl Session {
         }
     }
 
+    /// Mark as cut off while `command` was running
+    pub fn interrupt(&mut self, command: &str) {
+        self.status = SessionStatus::Interrupted;
+        self.ended_at = Some(Utc::now());
+        if let Some(last) = self.actions.last_mut() {
+            last.error
+                .get_or_insert_with(|| format!("Interrupted during `{}`", command));
+        }
+    }
+
     /// Get total duration in milliseconds
     pub fn total_duration_ms(&self) -> u64 {
         self.actions.iter().map(|a| a.duration_ms).sum()
@@ -165,9 +223,8 @@ impl Session {
     /// Save session to file
     pub fn save(&self, path: &PathBuf) -> Result<()> {
         let content = serde_json::to_string_pretty(self).context("Failed to serialize session")?;
-        std::fs::write(path, content)
-            .with_context(|| format!("Failed to save session to {}", path.display()))?;
-        Ok(())
+        crate::storage::write_atomic(path, &content)
+            .with_context(|| format!("Failed to save session to {}", path.display()))
     }
 
     /// Load session from file
@@ -210,6 +267,97 @@ impl Session {
             ended_at: self.ended_at,
         }
     }
+
+    /// Export as `json`, `bash` or `markdown` (`md`)
+    pub fn export(&self, format: &str) -> Result<String> {
+        match format {
+            "json" => Ok(serde_json::to_string_pretty(self)?),
+            "bash" => Ok(self.as_bash()),
+            "markdown" | "md" => Ok(self.as_markdown()),
+            _ => anyhow::bail!("Unknown format: {}. Use json, bash, or markdown", format),
+        }
+    }
+
+    /// A bash script repeating the actions
+    fn as_bash(&self) -> String {
+        let mut output = String::new();
+        output.push_str("#!/bin/bash\n");
+        output.push_str("# DOMGuard Session Export\n");
+        if let Some(name) = &self.name {
+            let _ = writeln!(output, "# Session: {}", name);
+        }
+        let _ = writeln!(output, "# ID: {}", self.id);
+        let _ = writeln!(
+            output,
+            "# Recorded: {}",
+            self.started_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        output.push('\n');
+        output.push_str("set -e  # Exit on error\n\n");
+
+        for action in &self.actions {
+            let _ = writeln!(output, "{}", action.as_command());
+        }
+
+        output
+    }
+
+    /// A markdown report of the actions, with the commands repeating them
+    fn as_markdown(&self) -> String {
+        let mut output = String::new();
+        output.push_str("# DOMGuard Session\n\n");
+        if let Some(name) = &self.name {
+            let _ = writeln!(output, "**Name:** {}\n", name);
+        }
+        let _ = writeln!(output, "**ID:** `{}`\n", self.id);
+        let _ = writeln!(
+            output,
+            "**Recorded:** {}\n",
+            self.started_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        let _ = writeln!(output, "**Total Actions:** {}\n", self.actions.len());
+        output.push_str("## Actions\n\n");
+
+        for (i, action) in self.actions.iter().enumerate() {
+            let status = match action.status {
+                ActionStatus::Success => "✅",
+                ActionStatus::Failed => "❌",
+                ActionStatus::Skipped => "⏭️",
+                ActionStatus::Paused => "⏸️",
+            };
+            let _ = writeln!(
+                output,
+                "{}. {} **{}** ({}ms)",
+                i + 1,
+                status,
+                action.command,
+                action.duration_ms
+            );
+            if let Some(selector) = &action.selector {
+                let _ = writeln!(output, "   - Selector: `{}`", selector);
+            }
+            if let Some(error) = &action.error {
+                let _ = writeln!(output, "   - Error: {}", error);
+            }
+            if let Some(screenshot) = &action.screenshot {
+                let _ = writeln!(
+                    output,
+                    "\n   ![After {}]({})",
+                    action.command,
+                    screenshot.display()
+                );
+            }
+            output.push('\n');
+        }
+
+        output.push_str("## Replay Commands\n\n```bash\n");
+        for action in &self.actions {
+            let _ = writeln!(output, "{}", action.as_command());
+        }
+        output.push_str("```\n");
+
+        output
+    }
 }
 
 /// Summary of a session for display
@@ -228,21 +376,93 @@ pub struct SessionSummary {
 }
 
 /// Session recorder with file-based persistence
-/// Survives between CLI invocations by storing active session in a file
+/// Survives between CLI invocations by storing active session in a file;
+/// finished sessions go to the configured [`SessionStore`]
+///
+/// Every change to the active session holds its lock file, so processes
+/// running at the same time don't overwrite each other's actions.
 #[derive(Clone)]
 pub struct SessionRecorder {
     sessions_dir: PathBuf,
     active_session_path: PathBuf,
+    store: std::sync::Arc<dyn SessionStore + Send + Sync>,
+    /// ID given with `--session`
+    target: Option<String>,
 }
 
 impl SessionRecorder {
     /// Create a new recorder
     pub fn new(sessions_dir: PathBuf) -> Self {
+        let store = std::sync::Arc::new(FsSessionStore::new(sessions_dir.clone()));
+        Self::with_store(sessions_dir, store)
+    }
+
+    /// A recorder saving finished sessions with `backend`
+    pub fn with_backend(sessions_dir: PathBuf, backend: StorageBackend) -> Result<Self> {
+        let store = crate::storage::session_store(backend, &sessions_dir)?;
+        Ok(Self::with_store(sessions_dir, store))
+    }
+
+    fn with_store(
+        sessions_dir: PathBuf,
+        store: std::sync::Arc<dyn SessionStore + Send + Sync>,
+    ) -> Self {
         let active_session_path = sessions_dir.join("_active_session.json");
         Self {
+            store,
             sessions_dir,
             active_session_path,
+            target: None,
+        }
+    }
+
+    /// Recorder for the session `--session <id>` names
+    ///
+    /// Sessions started with an ID are kept apart from the default one, so
+    /// parallel agents passing their own IDs never interleave recordings.
+    /// The ID of a session started without `--session` targets that one.
+    pub fn for_session(sessions_dir: PathBuf, id: &str) -> Result<Self> {
+        Self::new(sessions_dir).targeting(id)
+    }
+
+    fn targeting(mut self, id: &str) -> Result<Self> {
+        validate_session_id(id)?;
+        if self.get_active_session().is_none_or(|s| s.id != id) {
+            self.active_session_path = self.sessions_dir.join(format!("_active_{}.json", id));
+        }
+        self.target = Some(id.to_string());
+        Ok(self)
+    }
+
+    /// The recorder for `--session <id>`, or for the default session, in
+    /// this project's `.domguard/sessions` with its `[storage]` backend
+    pub fn open(session: Option<&str>) -> Result<Self> {
+        let sessions_dir = Config::find_domguard_dir()
+            .unwrap_or_else(Config::domguard_dir)
+            .join("sessions");
+        let recorder = Self::with_backend(sessions_dir, Config::load()?.storage.backend)?;
+        match session {
+            Some(id) => recorder.targeting(id),
+            None => Ok(recorder),
+        }
+    }
+
+    /// Hold the active session's lock while reading and writing it
+    fn lock(&self) -> Result<FileLock> {
+        std::fs::create_dir_all(&self.sessions_dir)?;
+        FileLock::acquire(&self.active_session_path)
+    }
+
+    /// A new session, with the `--session` ID when there is one
+    fn new_session(&self, name: Option<String>) -> Result<Session> {
+        let mut session = Session::new(name);
+        if let Some(id) = &self.target {
+            if self.store.load(id).is_ok() {
+                anyhow::bail!("A saved session with ID {} already exists", id);
+            }
+            session.id.clone_from(id);
         }
+        Ok(session)
     }
 
     /// Get the currently active session (if any)
@@ -269,7 +489,13 @@ impl SessionRecorder {
     }
 
     /// Start a new recording session
-    pub fn start(&self, name: Option<String>, initial_url: Option<String>) -> Result<String> {
+    pub fn start(
+        &self,
+        name: Option<String>,
+        initial_url: Option<String>,
+        screenshots: bool,
+    ) -> Result<String> {
+        let _lock = self.lock()?;
         // Check if there's already an active session
         if let Some(existing) = self.get_active_session() {
             if existing.status == SessionStatus::Recording
@@ -282,8 +508,9 @@ impl SessionRecorder {
             }
         }
 
-        let mut session = Session::new(name);
+        let mut session = self.new_session(name)?;
         session.initial_url = initial_url;
+        session.metadata.screenshots = screenshots;
         let id = session.id.clone();
 
         self.save_active_session(&session)?;
@@ -291,8 +518,32 @@ impl SessionRecorder {
         Ok(id)
     }
 
+    /// Start an auto-recorded session unless one is already active or paused
+    /// Returns the new session ID, if one was started
+    pub fn start_auto(&self) -> Result<Option<String>> {
+        let _lock = self.lock()?;
+        if self.get_active_session().is_some_and(|s| {
+            s.status == SessionStatus::Recording || s.status == SessionStatus::Paused
+        }) {
+            return Ok(None);
+        }
+
+        let name = format!("auto-{}", Utc::now().format("%Y%m%d-%H%M%S"));
+        let mut session = self.new_session(Some(name))?;
+        session.metadata.auto_recorded = true;
+        let id = session.id.clone();
+
+        self.save_active_session(&session)?;
+
+        Ok(Some(id))
+    }
+
     /// Stop the current recording
     pub fn stop(&self) -> Result<Option<Session>> {
+        if !self.active_session_path.exists() {
+            return Ok(None);
+        }
+        let _lock = self.lock()?;
         if let Some(mut session) = self.get_active_session() {
             session.end();
 
@@ -308,8 +559,55 @@ impl SessionRecorder {
         Ok(None)
     }
 
+    /// Stop the current recording only if it is still `session_id`
+    pub fn stop_if(&self, session_id: &str) -> Result<Option<Session>> {
+        if !self.active_session_path.exists() {
+            return Ok(None);
+        }
+        let _lock = self.lock()?;
+        match self.get_active_session() {
+            Some(mut session) if session.id == session_id => {
+                session.end();
+                self.save_session(&session)?;
+                self.clear_active_session()?;
+                Ok(Some(session))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Stop `session_id` when the returned guard drops, however the command
+    /// that started it ends
+    pub fn stop_on_drop(&self, session_id: &str) -> StopOnDrop<'_> {
+        StopOnDrop {
+            recorder: self,
+            session_id: Some(session_id.to_string()),
+        }
+    }
+
+    /// End the current recording as interrupted by `command` and save it
+    ///
+    /// Only when the active session is still `session_id`, so a newer
+    /// recording started since is left alone.
+    pub fn interrupt(&self, session_id: &str, command: &str) -> Result<Option<Session>> {
+        if !self.active_session_path.exists() {
+            return Ok(None);
+        }
+        let _lock = self.lock()?;
+        match self.get_active_session() {
+            Some(mut session) if session.id == session_id => {
+                session.interrupt(command);
+                self.save_session(&session)?;
+                self.clear_active_session()?;
+                Ok(Some(session))
+            }
+            _ => Ok(None),
+        }
+    }
+
     /// Pause the current recording
     pub fn pause(&self) -> Result<()> {
+        let _lock = self.lock()?;
         if let Some(mut session) = self.get_active_session() {
             session.pause();
             self.save_active_session(&session)?;
@@ -321,6 +619,7 @@ impl SessionRecorder {
 
     /// Resume the current recording
     pub fn resume(&self) -> Result<()> {
+        let _lock = self.lock()?;
         if let Some(mut session) = self.get_active_session() {
             session.resume();
             self.save_active_session(&session)?;
@@ -342,8 +641,24 @@ impl SessionRecorder {
             .is_some_and(|s| s.status == SessionStatus::Paused)
     }
 
+    /// Path for the screenshot of the next recorded action, if the active
+    /// session captures screenshots
+    pub fn next_screenshot_path(&self) -> Option<PathBuf> {
+        let session = self.get_active_session()?;
+        if session.status != SessionStatus::Recording || !session.metadata.screenshots {
+            return None;
+        }
+        let filename = format!("action_{:03}.jpg", session.actions.len() + 1);
+        Some(self.sessions_dir.join(&session.id).join(filename))
+    }
+
     /// Record an action
     pub fn record_action(&self, action: RecordedAction) -> Result<()> {
+        if !self.active_session_path.exists() {
+            return Ok(());
+        }
+        // Agents running commands in parallel append to the same session
+        let _lock = self.lock()?;
         if let Some(mut session) = self.get_active_session() {
             if session.status == SessionStatus::Recording {
                 session.add_action(action);
@@ -363,57 +678,163 @@ impl SessionRecorder {
         self.get_active_session().map(|s| s.status)
     }
 
-    /// Save session to permanent file
+    /// Save session to permanent storage
     fn save_session(&self, session: &Session) -> Result<()> {
-        std::fs::create_dir_all(&self.sessions_dir)?;
-        let filename = format!("session_{}.json", session.id);
-        let path = self.sessions_dir.join(filename);
-        session.save(&path)
+        self.store.save(session)
     }
 
-    /// List all saved sessions (excluding active)
-    pub fn list_sessions(&self) -> Result<Vec<SessionSummary>> {
-        let mut sessions = Vec::new();
+    /// List saved sessions (excluding active), newest first
+    pub fn list_sessions(&self, filter: &SessionFilter) -> Result<Vec<SessionSummary>> {
+        self.store.list(filter)
+    }
 
-        if !self.sessions_dir.exists() {
-            return Ok(sessions);
-        }
+    /// Load a session by ID
+    pub fn load_session(&self, id: &str) -> Result<Session> {
+        self.store.load(id)
+    }
 
-        for entry in std::fs::read_dir(&self.sessions_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            // Skip the active session file
-            if path
-                .file_name()
-                .is_some_and(|n| n == "_active_session.json")
-            {
-                continue;
-            }
-            if path.extension().is_some_and(|e| e == "json") {
-                if let Ok(session) = Session::load(&path) {
-                    sessions.push(session.summary());
-                }
-            }
+    /// Delete a session by ID
+    pub fn delete_session(&self, id: &str) -> Result<()> {
+        self.store.delete(id)
+    }
+}
+
+/// Check a `--session` ID, which also names files under `.domguard`
+pub fn validate_session_id(id: &str) -> Result<()> {
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!(
+            "Invalid session ID \"{}\": use letters, digits, '-' and '_'",
+            id
+        );
+    }
+    Ok(())
+}
+
+/// What `session stop` did besides ending the recording
+#[derive(Debug)]
+pub struct StopReport {
+    pub session: Session,
+    /// Screenshot, PDF and HTML files removed from `.domguard/screenshots`
+    pub screenshots_removed: usize,
+    /// Throttling that was turned off, if any was reset
+    pub throttle_reset: Option<ThrottleState>,
+}
+
+/// Stop the recording, then tidy up after it
+///
+/// Screenshots are removed with `cleanup` or `defaults.auto_cleanup_screenshots`.
+/// Throttling is reset with `reset_throttle`, or with
+/// `defaults.reset_throttle_on_stop` when some is applied.
+pub async fn stop(
+    cdp: &mut CdpConnection,
+    recorder: &SessionRecorder,
+    config: &Config,
+    cleanup: bool,
+    reset_throttle: bool,
+) -> Result<Option<StopReport>> {
+    let Some(session) = recorder.stop()? else {
+        return Ok(None);
+    };
+
+    let screenshots_removed = if cleanup || config.defaults.auto_cleanup_screenshots {
+        clean_screenshots(
+            &Config::find_domguard_dir()
+                .unwrap_or_else(Config::domguard_dir)
+                .join("screenshots"),
+        )?
+    } else {
+        0
+    };
+
+    let throttled = !crate::debug::PageState::load(&crate::debug::PageState::path())?
+        .throttle
+        .is_off();
+    let throttle_reset = if reset_throttle || (config.defaults.reset_throttle_on_stop && throttled)
+    {
+        cdp.connect().await?;
+        Some(crate::debug::reset_throttling(cdp).await?)
+    } else {
+        None
+    };
+
+    Ok(Some(StopReport {
+        session,
+        screenshots_removed,
+        throttle_reset,
+    }))
+}
+
+/// Remove the screenshot, PDF and HTML files in `dir`, returning how many
+pub fn clean_screenshots(dir: &Path) -> Result<usize> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let mut deleted = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let captured = path
+            .extension()
+            .is_some_and(|ext| ext == "png" || ext == "pdf" || ext == "html");
+        if path.is_file() && captured {
+            std::fs::remove_file(&path)?;
+            deleted += 1;
         }
+    }
+    Ok(deleted)
+}
 
-        // Sort by start time, newest first
-        sessions.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+/// Save a thumbnail of the page after a recorded action
+/// A failed capture leaves the action without a screenshot rather than failing it
+pub async fn capture_thumbnail(cdp: &CdpConnection, path: PathBuf) -> Option<PathBuf> {
+    let data = cdp.thumbnail(320).await.ok()?;
+    std::fs::create_dir_all(path.parent()?).ok()?;
+    std::fs::write(&path, data).ok()?;
+    Some(path)
+}
 
-        Ok(sessions)
+/// Step through a session's actions, `delay_ms` apart
+///
+/// Actions aren't executed yet; `on_action` gets each one with its index.
+pub async fn replay(
+    session: &Session,
+    delay_ms: u64,
+    on_action: &mut dyn FnMut(usize, &RecordedAction),
+) -> usize {
+    for (i, action) in session.actions.iter().enumerate() {
+        on_action(i, action);
+        tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
     }
+    session.actions.len()
+}
 
-    /// Load a session by ID
-    pub fn load_session(&self, id: &str) -> Result<Session> {
-        let filename = format!("session_{}.json", id);
-        let path = self.sessions_dir.join(filename);
-        Session::load(&path)
+/// Stops a session started for a single command (`interact --record`)
+///
+/// An early return before the action is recorded still ends the session,
+/// rather than leaving it open for whatever runs next.
+pub struct StopOnDrop<'a> {
+    recorder: &'a SessionRecorder,
+    session_id: Option<String>,
+}
+
+impl StopOnDrop<'_> {
+    /// Stop the session now, reporting a failure to save it
+    pub fn stop(mut self) -> Result<()> {
+        match self.session_id.take() {
+            Some(id) => self.recorder.stop_if(&id).map(|_| ()),
+            None => Ok(()),
+        }
     }
+}
 
-    /// Delete a session by ID
-    pub fn delete_session(&self, id: &str) -> Result<()> {
-        let filename = format!("session_{}.json", id);
-        let path = self.sessions_dir.join(filename);
-        std::fs::remove_file(&path).with_context(|| format!("Failed to delete session {}", id))
+impl Drop for StopOnDrop<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.session_id.take() {
+            let _ = self.recorder.stop_if(&id);
+        }
     }
 }
 
@@ -423,7 +844,9 @@ pub struct ActionBuilder {
     args: serde_json::Value,
     start_time: std::time::Instant,
     page_url: Option<String>,
+    page_url_after: Option<String>,
     selector: Option<String>,
+    screenshot: Option<PathBuf>,
 }
 
 impl ActionBuilder {
@@ -433,20 +856,37 @@ impl ActionBuilder {
             args: serde_json::json!({}),
             start_time: std::time::Instant::now(),
             page_url: None,
+            page_url_after: None,
             selector: None,
+            screenshot: None,
         }
     }
 
+    #[must_use]
     pub fn with_args(mut self, args: serde_json::Value) -> Self {
         self.args = args;
         self
     }
 
+    #[must_use]
     pub fn with_page_url(mut self, url: Option<String>) -> Self {
         self.page_url = url;
         self
     }
 
+    #[must_use]
+    pub fn with_page_url_after(mut self, url: Option<String>) -> Self {
+        self.page_url_after = url;
+        self
+    }
+
+    #[must_use]
+    pub fn with_screenshot(mut self, path: Option<PathBuf>) -> Self {
+        self.screenshot = path;
+        self
+    }
+
+    #[must_use]
     pub fn with_selector(mut self, selector: Option<String>) -> Self {
         self.selector = selector;
         self
@@ -459,9 +899,10 @@ impl ActionBuilder {
             command: self.command,
             args: self.args,
             status: ActionStatus::Success,
-            screenshot: None,
+            screenshot: self.screenshot,
             error: None,
             page_url: self.page_url,
+            page_url_after: self.page_url_after,
             selector: self.selector,
         }
     }
@@ -473,9 +914,10 @@ impl ActionBuilder {
             command: self.command,
             args: self.args,
             status: ActionStatus::Failed,
-            screenshot: None,
+            screenshot: self.screenshot,
             error: Some(error.to_string()),
             page_url: self.page_url,
+            page_url_after: self.page_url_after,
             selector: self.selector,
         }
     }
@@ -554,4 +996,119 @@ mod tests {
 
         assert!((session.success_rate() - 0.75).abs() < 0.01);
     }
+
+    #[test]
+    fn test_start_auto() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorder = SessionRecorder::new(dir.path().to_path_buf());
+
+        let id = recorder.start_auto().unwrap();
+        assert!(id.is_some());
+        assert!(recorder.is_recording());
+
+        // An active session is reused rather than replaced
+        assert_eq!(recorder.start_auto().unwrap(), None);
+
+        let session = recorder.stop().unwrap().unwrap();
+        assert_eq!(Some(session.id), id);
+        assert!(session.metadata.auto_recorded);
+    }
+
+    #[test]
+    fn test_stop_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorder = SessionRecorder::new(dir.path().to_path_buf());
+
+        let id = recorder.start_auto().unwrap().unwrap();
+        drop(recorder.stop_on_drop(&id));
+        assert!(!recorder.is_recording());
+        assert_eq!(recorder.load_session(&id).unwrap().id, id);
+
+        // A newer session started meanwhile is left recording
+        let newer = recorder.start(None, None, false).unwrap();
+        recorder.stop_on_drop(&id).stop().unwrap();
+        assert_eq!(recorder.get_summary().unwrap().id, newer);
+    }
+
+    #[test]
+    fn test_targeted_sessions_record_separately() {
+        let dir = tempfile::tempdir().unwrap();
+        let default = SessionRecorder::new(dir.path().to_path_buf());
+        let agent = SessionRecorder::for_session(dir.path().to_path_buf(), "agent-1").unwrap();
+
+        let default_id = default.start(None, None, false).unwrap();
+        assert_eq!(agent.start(None, None, false).unwrap(), "agent-1");
+        agent
+            .record_action(ActionBuilder::new("click").success())
+            .unwrap();
+        assert_eq!(default.get_summary().unwrap().total_actions, 0);
+        assert_eq!(agent.get_summary().unwrap().total_actions, 1);
+
+        // The default session's own ID reaches it too
+        let by_id = SessionRecorder::for_session(dir.path().to_path_buf(), &default_id).unwrap();
+        by_id
+            .record_action(ActionBuilder::new("type").success())
+            .unwrap();
+        assert_eq!(default.get_summary().unwrap().total_actions, 1);
+
+        assert_eq!(agent.stop().unwrap().unwrap().id, "agent-1");
+        assert!(agent.start(None, None, false).is_err());
+        assert!(SessionRecorder::for_session(dir.path().to_path_buf(), "../x").is_err());
+    }
+
+    #[test]
+    fn test_next_screenshot_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorder = SessionRecorder::new(dir.path().to_path_buf());
+
+        recorder.start(None, None, false).unwrap();
+        assert_eq!(recorder.next_screenshot_path(), None);
+        recorder.stop().unwrap();
+
+        let id = recorder.start(None, None, true).unwrap();
+        let first = recorder.next_screenshot_path().unwrap();
+        assert_eq!(first, dir.path().join(&id).join("action_001.jpg"));
+
+        recorder
+            .record_action(
+                ActionBuilder::new("click")
+                    .with_screenshot(Some(first))
+                    .success(),
+            )
+            .unwrap();
+        let second = recorder.next_screenshot_path().unwrap();
+        assert!(second.ends_with("action_002.jpg"));
+    }
+
+    #[test]
+    fn test_export() {
+        let mut session = Session::new(Some("Login".to_string()));
+        session.add_action(
+            ActionBuilder::new("click")
+                .with_selector(Some("#submit".to_string()))
+                .success(),
+        );
+        session.add_action(ActionBuilder::new("wait-duration").success());
+
+        let bash = session.export("bash").unwrap();
+        assert!(bash.starts_with("#!/bin/bash\n"));
+        assert!(bash.contains("domguard interact click \"#submit\"\n"));
+        assert!(bash.ends_with("domguard interact wait-duration\n"));
+        assert!(session
+            .export("md")
+            .unwrap()
+            .contains("**Total Actions:** 2"));
+        assert!(session.export("yaml").is_err());
+    }
+
+    #[test]
+    fn test_clean_screenshots() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.png", "b.pdf", "c.html", "notes.txt"] {
+            std::fs::write(dir.path().join(name), "x").unwrap();
+        }
+        assert_eq!(clean_screenshots(dir.path()).unwrap(), 3);
+        assert!(dir.path().join("notes.txt").exists());
+        assert_eq!(clean_screenshots(&dir.path().join("missing")).unwrap(), 0);
+    }
 }
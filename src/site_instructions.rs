@@ -3,12 +3,16 @@
 //! Allows defining per-site behaviors, custom selectors, and automation rules.
 //! Instructions are stored in `.domguard/sites/` directory.
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Write as _;
 use std::path::PathBuf;
 
+use crate::cdp::CdpConnection;
+use crate::config::Config;
+use crate::correction::CorrectionOverrides;
+
 /// Site-specific instructions and behaviors
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SiteInstructions {
@@ -23,10 +27,22 @@ pub struct SiteInstructions {
     #[serde(default)]
     pub login: Option<LoginInstructions>,
 
-    /// Custom element selectors for common actions
+    /// Named selector aliases, e.g. `login_button = "button[data-test=login]"`
+    ///
+    /// Commands given `@login_button` (or, for interact, the bare name) as a
+    /// selector use the mapped selector instead.
     #[serde(default)]
     pub selectors: HashMap<String, String>,
 
+    /// Interact commands refuse to act on elements matching these selectors
+    #[serde(default)]
+    pub forbidden_selectors: Vec<String>,
+
+    /// Waits that run before an interact action, keyed by action name
+    /// ("click", "type", ...) or "*" for every action
+    #[serde(default)]
+    pub required_waits: HashMap<String, WaitCondition>,
+
     /// Pre-action hooks (run before specific actions)
     #[serde(default)]
     pub before_actions: HashMap<String, Vec<ActionStep>>,
@@ -51,6 +67,10 @@ pub struct SiteInstructions {
     #[serde(default)]
     pub timeouts: Option<TimeoutConfig>,
 
+    /// Self-correction overrides (e.g., no auto-refresh on banking sites)
+    #[serde(default)]
+    pub correction: Option<CorrectionOverrides>,
+
     /// Notes and tips for automation
     #[serde(default)]
     pub notes: Vec<String>,
@@ -80,6 +100,10 @@ pub struct LoginInstructions {
     /// Additional steps before/after login
     #[serde(default)]
     pub extra_steps: Vec<ActionStep>,
+
+    /// Saved workflow that signs in, suggested when on the login page
+    #[serde(default)]
+    pub workflow: Option<String>,
 }
 
 /// Action step for custom workflows
@@ -186,6 +210,97 @@ pub struct TimeoutConfig {
     pub script_ms: Option<u64>,
 }
 
+impl SiteInstructions {
+    /// The selector a `[selectors]` name stands for, given as `@name` or `name`
+    pub fn resolve_selector(&self, selector: &str) -> Option<&str> {
+        let name = selector.strip_prefix('@').unwrap_or(selector);
+        self.selectors.get(name).map(String::as_str)
+    }
+
+    /// Wait to run before `action`, falling back to the "*" entry
+    pub fn required_wait(&self, action: &str) -> Option<&WaitCondition> {
+        self.required_waits
+            .get(action)
+            .or_else(|| self.required_waits.get("*"))
+    }
+
+    /// Forbidden selector written exactly as `selector`
+    ///
+    /// Elements that only match a forbidden selector are caught page-side.
+    pub fn forbidden_rule(&self, selector: &str) -> Option<&str> {
+        self.forbidden_selectors
+            .iter()
+            .find(|rule| rule.trim() == selector.trim())
+            .map(String::as_str)
+    }
+
+    /// Whether `url` is this site's login page
+    pub fn is_login_url(&self, url: &str) -> bool {
+        let Some(login_path) = self.login.as_ref().and_then(|l| l.url.as_deref()) else {
+            return false;
+        };
+        let path = url
+            .split_once("://")
+            .map_or(url, |(_, rest)| rest.find('/').map_or("/", |i| &rest[i..]));
+        path.starts_with(login_path)
+    }
+}
+
+/// Replace an `@name` selector alias with the site's `[selectors]` entry
+///
+/// Anything else is left alone; an alias the site doesn't define is an error.
+pub fn expand_alias(selector: &mut String, site: Option<&SiteInstructions>) -> Result<()> {
+    if !selector.starts_with('@') {
+        return Ok(());
+    }
+    match site.and_then(|site| site.resolve_selector(selector)) {
+        Some(resolved) => {
+            *selector = resolved.to_string();
+            Ok(())
+        }
+        None => Err(anyhow!(
+            "Unknown selector alias \"{}\" (no [selectors] entry in the site instructions for {})",
+            selector,
+            site.map_or("this page", |site| site.domain.as_str())
+        )),
+    }
+}
+
+/// [`expand_alias`] for each selector, against the site the browser is on
+pub async fn expand_page_aliases(cdp: &CdpConnection, selectors: Vec<&mut String>) -> Result<()> {
+    if !selectors.iter().any(|selector| selector.starts_with('@')) {
+        return Ok(());
+    }
+    let url = cdp.current_url().await.unwrap_or_default();
+    let site = SiteInstructionsManager::for_project_url(&url);
+    for selector in selectors {
+        expand_alias(selector, site.as_ref())?;
+    }
+    Ok(())
+}
+
+/// An interact action refused by a site's `forbidden_selectors`
+#[derive(Debug, Clone, Serialize)]
+pub struct ForbiddenBySite {
+    pub domain: String,
+    pub action: String,
+    pub selector: String,
+    /// Forbidden selector that matched
+    pub rule: String,
+}
+
+impl std::fmt::Display for ForbiddenBySite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Forbidden by site instructions: {} on \"{}\" matches forbidden selector \"{}\" for {}",
+            self.action, self.selector, self.rule, self.domain
+        )
+    }
+}
+
+impl std::error::Error for ForbiddenBySite {}
+
 /// Site instructions manager
 pub struct SiteInstructionsManager {
     /// Directory where site instructions are stored
@@ -247,13 +362,57 @@ impl SiteInstructionsManager {
         None
     }
 
+    /// Get instructions for a domain, or for a URL
+    pub fn get_for_domain(&self, domain: &str) -> Option<&SiteInstructions> {
+        if domain.contains("://") {
+            self.get_for_url(domain)
+        } else {
+            self.get_for_url(&format!("https://{}", domain))
+        }
+    }
+
+    /// The project's `.domguard/sites/`, loaded
+    pub fn open() -> Result<Self> {
+        let mut sites = Self::new(
+            Config::find_domguard_dir()
+                .unwrap_or_else(Config::domguard_dir)
+                .join("sites"),
+        );
+        sites.load_all()?;
+        Ok(sites)
+    }
+
+    /// Instructions matching `url` from the project's `.domguard/sites/`
+    pub fn for_project_url(url: &str) -> Option<SiteInstructions> {
+        Self::open().ok()?.get_for_url(url).cloned()
+    }
+
+    /// The file holding a domain's instructions
+    fn path_for(&self, domain: &str) -> PathBuf {
+        self.sites_dir
+            .join(domain.replace(['*', '.'], "_") + ".toml")
+    }
+
+    /// Save the template for `domain`, returning its file
+    pub fn create(&self, domain: &str) -> Result<PathBuf> {
+        self.save(&Self::create_template(domain))
+    }
+
+    /// The file to edit for `domain`, saved from the template if there is none
+    pub fn file(&self, domain: &str) -> Result<PathBuf> {
+        let path = self.path_for(domain);
+        if path.exists() {
+            Ok(path)
+        } else {
+            self.create(domain)
+        }
+    }
+
     /// Save instructions for a site
     pub fn save(&self, instructions: &SiteInstructions) -> Result<PathBuf> {
         std::fs::create_dir_all(&self.sites_dir)?;
 
-        // Create filename from domain
-        let filename = instructions.domain.replace(['*', '.'], "_") + ".toml";
-        let path = self.sites_dir.join(filename);
+        let path = self.path_for(&instructions.domain);
 
         let content = toml::to_string_pretty(instructions)?;
         std::fs::write(&path, content)?;
@@ -270,8 +429,7 @@ impl SiteInstructionsManager {
     pub fn delete(&mut self, domain: &str) -> Result<bool> {
         if self.cache.remove(domain).is_some() {
             // Also delete file
-            let filename = domain.replace(['*', '.'], "_") + ".toml";
-            let path = self.sites_dir.join(filename);
+            let path = self.path_for(domain);
             if path.exists() {
                 std::fs::remove_file(path)?;
             }
@@ -294,6 +452,7 @@ impl SiteInstructionsManager {
                 success_indicator: Some(".dashboard, .welcome".to_string()),
                 failure_indicator: Some(".error, .alert-danger".to_string()),
                 extra_steps: vec![],
+                workflow: None,
             }),
             selectors: {
                 let mut map = HashMap::new();
@@ -301,6 +460,8 @@ impl SiteInstructionsManager {
                 map.insert("submit".to_string(), "[type='submit']".to_string());
                 map
             },
+            forbidden_selectors: vec![],
+            required_waits: HashMap::new(),
             before_actions: HashMap::new(),
             after_actions: HashMap::new(),
             wait_ready: Some(WaitCondition {
@@ -321,13 +482,14 @@ impl SiteInstructionsManager {
             }),
             captcha: None,
             timeouts: None,
+            correction: None,
             notes: vec!["Add custom notes about this site here".to_string()],
         }
     }
 }
 
 /// Extract domain from URL
-fn extract_domain(url: &str) -> Option<String> {
+pub fn extract_domain(url: &str) -> Option<String> {
     // Handle URLs with and without protocol
     let url = url.trim();
     let url = url
@@ -345,10 +507,11 @@ fn extract_domain(url: &str) -> Option<String> {
 }
 
 /// Check if a domain pattern matches a domain
-fn matches_domain_pattern(pattern: &str, domain: &str) -> bool {
+pub fn matches_domain_pattern(pattern: &str, domain: &str) -> bool {
     if let Some(suffix) = pattern.strip_prefix("*.") {
-        // Wildcard pattern
-        domain.ends_with(suffix) || domain == suffix.trim_start_matches('.')
+        // Wildcard pattern, on a label boundary so evilexample.com isn't *.example.com
+        let suffix = suffix.trim_start_matches('.');
+        domain == suffix || domain.ends_with(&format!(".{}", suffix))
     } else {
         pattern == domain
     }
@@ -375,6 +538,9 @@ pub fn format_instructions(instructions: &SiteInstructions) -> String {
         if let Some(sel) = &login.password_selector {
             let _ = writeln!(output, "    Password: {}", sel);
         }
+        if let Some(workflow) = &login.workflow {
+            let _ = writeln!(output, "    Workflow: {}", workflow);
+        }
     }
 
     if !instructions.selectors.is_empty() {
@@ -384,6 +550,26 @@ pub fn format_instructions(instructions: &SiteInstructions) -> String {
         }
     }
 
+    if !instructions.forbidden_selectors.is_empty() {
+        output.push_str("\n  Forbidden Selectors:\n");
+        for selector in &instructions.forbidden_selectors {
+            let _ = writeln!(output, "    {}", selector);
+        }
+    }
+
+    if !instructions.required_waits.is_empty() {
+        output.push_str("\n  Required Waits:\n");
+        for (action, wait) in &instructions.required_waits {
+            let condition = match (&wait.selector, &wait.text) {
+                (Some(selector), _) => selector.clone(),
+                (None, Some(text)) => format!("text \"{}\"", text),
+                (None, None) if wait.network_idle => "network idle".to_string(),
+                (None, None) => "nothing".to_string(),
+            };
+            let _ = writeln!(output, "    {}: {}", action, condition);
+        }
+    }
+
     if let Some(cookie) = &instructions.cookie_consent {
         output.push_str("\n  Cookie Consent:\n");
         let _ = writeln!(output, "    Action: {}", cookie.action);
@@ -392,6 +578,19 @@ pub fn format_instructions(instructions: &SiteInstructions) -> String {
         }
     }
 
+    if let Some(correction) = &instructions.correction {
+        output.push_str("\n  Self-Correction Overrides:\n");
+        if let Some(enabled) = correction.enabled {
+            let _ = writeln!(output, "    Enabled: {}", enabled);
+        }
+        if let Some(max_retries) = correction.max_retries {
+            let _ = writeln!(output, "    Max retries: {}", max_retries);
+        }
+        if let Some(auto_refresh) = correction.auto_refresh {
+            let _ = writeln!(output, "    Auto-refresh: {}", auto_refresh);
+        }
+    }
+
     if !instructions.notes.is_empty() {
         output.push_str("\n  Notes:\n");
         for note in &instructions.notes {
@@ -431,6 +630,12 @@ mod tests {
             "*.example.com",
             "deep.sub.example.com"
         ));
+        assert!(matches_domain_pattern("*.example.com", "example.com"));
+        assert!(!matches_domain_pattern("*.example.com", "evilexample.com"));
+        assert!(!matches_domain_pattern(
+            "*.example.com",
+            "sub.evilexample.com"
+        ));
     }
 
     #[test]
@@ -441,6 +646,22 @@ mod tests {
         assert!(template.cookie_consent.is_some());
     }
 
+    #[test]
+    fn test_create_and_find_by_domain() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sites = SiteInstructionsManager::new(dir.path().to_path_buf());
+        let path = sites.file("*.example.com").unwrap();
+        assert_eq!(path, dir.path().join("__example_com.toml"));
+        assert_eq!(sites.file("*.example.com").unwrap(), path);
+
+        sites.load_all().unwrap();
+        assert!(sites.get_for_domain("app.example.com").is_some());
+        assert!(sites
+            .get_for_domain("http://app.example.com/login")
+            .is_some());
+        assert!(sites.get_for_domain("example.org").is_none());
+    }
+
     #[test]
     fn test_serialize_deserialize() {
         let template = SiteInstructionsManager::create_template("test.com");
@@ -448,4 +669,78 @@ mod tests {
         let parsed: SiteInstructions = toml::from_str(&toml).unwrap();
         assert_eq!(parsed.domain, "test.com");
     }
+
+    #[test]
+    fn test_enforcement_rules() {
+        let parsed: SiteInstructions = toml::from_str(
+            r##"
+domain = "shop.example"
+forbidden_selectors = ["#delete-account", ".admin *"]
+
+[selectors]
+checkout = "button[data-test='checkout']"
+
+[required_waits."*"]
+network_idle = true
+
+[required_waits.click]
+selector = ".app-ready"
+
+[login]
+url = "/signin"
+workflow = "shop-login"
+"##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed.resolve_selector("checkout"),
+            Some("button[data-test='checkout']")
+        );
+        assert_eq!(parsed.resolve_selector("#other"), None);
+        assert_eq!(
+            parsed.forbidden_rule(" #delete-account"),
+            Some("#delete-account")
+        );
+        assert_eq!(parsed.forbidden_rule("#account"), None);
+        let click = parsed.required_wait("click").unwrap();
+        assert_eq!(click.selector.as_deref(), Some(".app-ready"));
+        assert!(parsed.required_wait("type").unwrap().network_idle);
+        assert!(parsed.is_login_url("https://shop.example/signin?next=/cart"));
+        assert!(!parsed.is_login_url("https://shop.example/"));
+    }
+
+    #[test]
+    fn test_expand_alias() {
+        let mut site = SiteInstructions {
+            domain: "shop.example".to_string(),
+            ..Default::default()
+        };
+        site.selectors.insert(
+            "login_button".to_string(),
+            "button[data-test=login]".to_string(),
+        );
+
+        let mut selector = "@login_button".to_string();
+        expand_alias(&mut selector, Some(&site)).unwrap();
+        assert_eq!(selector, "button[data-test=login]");
+
+        let mut plain = "#login".to_string();
+        expand_alias(&mut plain, None).unwrap();
+        assert_eq!(plain, "#login");
+
+        let err = expand_alias(&mut "@missing".to_string(), Some(&site)).unwrap_err();
+        assert!(err.to_string().contains("shop.example"));
+        assert!(expand_alias(&mut "@login_button".to_string(), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_correction_overrides() {
+        let parsed: SiteInstructions =
+            toml::from_str("domain = \"bank.example\"\n\n[correction]\nauto_refresh = false\n")
+                .unwrap();
+        let overrides = parsed.correction.unwrap();
+        assert_eq!(overrides.auto_refresh, Some(false));
+        assert_eq!(overrides.max_retries, None);
+    }
 }
@@ -0,0 +1,271 @@
+//! Source map lookup for exception reports
+//!
+//! Parses version 3 source maps (the `mappings` VLQ string) so locations in
+//! bundled or minified scripts can be reported against the original sources.
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// A location in an original source file, 1-based
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OriginalLocation {
+    pub source: String,
+    pub line: u32,
+    pub column: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// One mapping segment: generated column to original position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    column: u32,
+    /// (source index, line, column, name index), all 0-based
+    original: Option<(usize, u32, u32, Option<usize>)>,
+}
+
+/// A parsed source map
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    sources: Vec<String>,
+    names: Vec<String>,
+    /// Segments per generated line, sorted by column
+    lines: Vec<Vec<Segment>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    #[serde(default)]
+    source_root: Option<String>,
+    #[serde(default)]
+    sources: Vec<Option<String>>,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    mappings: Option<String>,
+    #[serde(default)]
+    sections: Option<serde_json::Value>,
+}
+
+impl SourceMap {
+    /// Parse a source map document
+    pub fn parse(json: &str) -> Result<Self> {
+        let raw: RawSourceMap =
+            serde_json::from_str(json).map_err(|e| anyhow!("Invalid source map: {}", e))?;
+        if raw.sections.is_some() {
+            return Err(anyhow!("Indexed source maps are not supported"));
+        }
+        let root = raw.source_root.filter(|root| !root.is_empty()).map(|root| {
+            if root.ends_with('/') {
+                root
+            } else {
+                format!("{}/", root)
+            }
+        });
+        let sources = raw
+            .sources
+            .into_iter()
+            .map(|source| {
+                let source = source.unwrap_or_default();
+                match &root {
+                    Some(root) => format!("{}{}", root, source),
+                    None => source,
+                }
+            })
+            .collect();
+        let lines = parse_mappings(raw.mappings.as_deref().unwrap_or(""))?;
+        Ok(Self {
+            sources,
+            names: raw.names,
+            lines,
+        })
+    }
+
+    /// Original position for a 0-based generated line and column
+    pub fn lookup(&self, line: u32, column: u32) -> Option<OriginalLocation> {
+        let segments = self.lines.get(line as usize)?;
+        let index = segments.partition_point(|segment| segment.column <= column);
+        let (source, line, column, name) = segments[..index].last()?.original?;
+        Some(OriginalLocation {
+            source: self.sources.get(source)?.clone(),
+            line: line + 1,
+            column: column + 1,
+            name: name.and_then(|name| self.names.get(name).cloned()),
+        })
+    }
+}
+
+/// Decode the `mappings` field into per-line segments
+fn parse_mappings(mappings: &str) -> Result<Vec<Vec<Segment>>> {
+    // Everything but the generated column carries over between lines
+    let (mut source, mut line, mut column, mut name) = (0i64, 0i64, 0i64, 0i64);
+    let mut lines = Vec::new();
+    for text in mappings.split(';') {
+        let mut generated = 0i64;
+        let mut segments = Vec::new();
+        for field in text.split(',').filter(|field| !field.is_empty()) {
+            let values = decode_vlq(field)?;
+            generated += values[0];
+            let original = match values.len() {
+                1 => None,
+                4 | 5 => {
+                    source += values[1];
+                    line += values[2];
+                    column += values[3];
+                    let named = if values.len() == 5 {
+                        name += values[4];
+                        Some(to_index(name)?)
+                    } else {
+                        None
+                    };
+                    Some((to_index(source)?, to_u32(line)?, to_u32(column)?, named))
+                }
+                n => return Err(anyhow!("Source map segment with {} fields", n)),
+            };
+            segments.push(Segment {
+                column: to_u32(generated)?,
+                original,
+            });
+        }
+        segments.sort_by_key(|segment| segment.column);
+        lines.push(segments);
+    }
+    Ok(lines)
+}
+
+fn to_u32(value: i64) -> Result<u32> {
+    u32::try_from(value).map_err(|_| anyhow!("Source map position {} out of range", value))
+}
+
+fn to_index(value: i64) -> Result<usize> {
+    usize::try_from(value).map_err(|_| anyhow!("Source map index {} out of range", value))
+}
+
+/// Decode one segment of base64 VLQ numbers
+fn decode_vlq(field: &str) -> Result<Vec<i64>> {
+    let mut values = Vec::new();
+    let (mut value, mut shift) = (0i64, 0u32);
+    for c in field.bytes() {
+        let digit = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(anyhow!("Invalid VLQ character '{}'", char::from(c))),
+        };
+        if shift > 60 {
+            return Err(anyhow!("VLQ value too large in \"{}\"", field));
+        }
+        value |= i64::from(digit & 31) << shift;
+        if digit & 32 == 0 {
+            let magnitude = value >> 1;
+            values.push(if value & 1 == 1 {
+                -magnitude
+            } else {
+                magnitude
+            });
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 5;
+        }
+    }
+    if shift != 0 {
+        return Err(anyhow!("Truncated VLQ in \"{}\"", field));
+    }
+    Ok(values)
+}
+
+/// Resolve a `sourceMappingURL` against the script URL
+pub fn resolve_url(base: &str, reference: &str) -> String {
+    if reference.starts_with("data:") || reference.contains("://") {
+        return reference.to_string();
+    }
+    let scheme_end = base.find("://").map_or(0, |i| i + 3);
+    if let Some(rest) = reference.strip_prefix("//") {
+        return format!("{}{}", &base[..scheme_end], rest);
+    }
+    if reference.starts_with('/') {
+        let origin_end = base[scheme_end..]
+            .find('/')
+            .map_or(base.len(), |i| scheme_end + i);
+        return format!("{}{}", &base[..origin_end], reference);
+    }
+    let path = base.split(['?', '#']).next().unwrap_or(base);
+    let dir_end = path.rfind('/').map_or(path.len(), |i| i + 1);
+    format!("{}{}", &path[..dir_end], reference)
+}
+
+/// The contents of an inline `data:` source map
+pub fn decode_data_url(url: &str) -> Option<String> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    if header.ends_with(";base64") {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .ok()?;
+        String::from_utf8(bytes).ok()
+    } else {
+        Some(data.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_vlq() {
+        assert_eq!(decode_vlq("AAAA").unwrap(), vec![0, 0, 0, 0]);
+        assert_eq!(decode_vlq("D").unwrap(), vec![-1]);
+        assert_eq!(decode_vlq("gB").unwrap(), vec![16]);
+        assert_eq!(decode_vlq("2HwRAAC").unwrap(), vec![123, 280, 0, 0, 1]);
+        assert!(decode_vlq("g").is_err());
+        assert!(decode_vlq("A!").is_err());
+    }
+
+    #[test]
+    fn test_lookup() {
+        // Line 1: col 0 -> a.ts 1:1, col 10 -> a.ts 2:5 named "boom";
+        // line 2: col 4 -> b.ts 3:1
+        let map = SourceMap::parse(
+            r#"{"version":3,"sourceRoot":"src","sources":["a.ts","b.ts"],
+                "names":["boom"],"mappings":"AAAA,UACIA;ICCJ"}"#,
+        )
+        .unwrap();
+
+        let location = map.lookup(0, 12).unwrap();
+        assert_eq!(location.source, "src/a.ts");
+        assert_eq!((location.line, location.column), (2, 5));
+        assert_eq!(location.name.as_deref(), Some("boom"));
+        assert_eq!(map.lookup(0, 3).unwrap().line, 1);
+        let location = map.lookup(1, 4).unwrap();
+        assert_eq!((location.source.as_str(), location.line), ("src/b.ts", 3));
+        assert!(map.lookup(1, 2).is_none());
+        assert!(map.lookup(5, 0).is_none());
+        assert!(SourceMap::parse(r#"{"version":3,"sections":[]}"#).is_err());
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        let base = "https://example.com/static/js/app.js?v=2";
+        assert_eq!(
+            resolve_url(base, "app.js.map"),
+            "https://example.com/static/js/app.js.map"
+        );
+        assert_eq!(
+            resolve_url(base, "/maps/app.map"),
+            "https://example.com/maps/app.map"
+        );
+        assert_eq!(
+            resolve_url(base, "//cdn.example.com/app.map"),
+            "https://cdn.example.com/app.map"
+        );
+        assert_eq!(
+            decode_data_url("data:application/json;base64,eyJ2ZXJzaW9uIjozfQ==").as_deref(),
+            Some(r#"{"version":3}"#)
+        );
+    }
+}
@@ -0,0 +1,835 @@
+//! Storage for recorded sessions and saved workflows
+//!
+//! By default sessions are one JSON file each, plus `_index.json` with their
+//! summaries so `session list` reads one file instead of parsing every
+//! session, and workflows are one TOML file each. Files are written under a
+//! temporary name and renamed into place, so a concurrent reader never sees
+//! half a file, and read-modify-write updates hold a lock file.
+//!
+//! Built with the `sqlite` feature, `[storage] backend = "sqlite"` keeps them
+//! in `.domguard/sessions/sessions.db` and `.domguard/workflows/workflows.db`
+//! instead: listings are indexed queries, and WAL mode with a busy timeout
+//! lets parallel agents write at once. `domguard migrate-storage` indexes
+//! session directories written by older versions, or with `--to sqlite`
+//! copies the files into the databases.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::session::{Session, SessionStatus, SessionSummary};
+use crate::workflow::Workflow;
+
+/// Which backend keeps sessions and workflows (`[storage] backend`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// JSON and TOML files (the default)
+    #[default]
+    Fs,
+    /// SQLite databases; needs the `sqlite` feature
+    Sqlite,
+}
+
+impl std::fmt::Display for StorageBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fs => write!(f, "fs"),
+            Self::Sqlite => write!(f, "sqlite"),
+        }
+    }
+}
+
+impl std::str::FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fs" | "files" => Ok(Self::Fs),
+            "sqlite" => Ok(Self::Sqlite),
+            other => Err(format!(
+                "Unknown storage backend \"{}\" (expected fs or sqlite)",
+                other
+            )),
+        }
+    }
+}
+
+/// Storage settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Where sessions and workflows are kept (default: fs)
+    #[serde(default)]
+    pub backend: StorageBackend,
+}
+
+/// Database file of the SQLite backend in `dir`
+#[cfg(feature = "sqlite")]
+fn database(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.db", name))
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn sqlite_unavailable() -> anyhow::Error {
+    anyhow!(
+        "SQLite storage needs DOMGuard built with `--features sqlite`; \
+         set [storage] backend = \"fs\" or rebuild"
+    )
+}
+
+/// The session store `backend` keeps in `sessions_dir`
+pub fn session_store(
+    backend: StorageBackend,
+    sessions_dir: &Path,
+) -> Result<Arc<dyn SessionStore + Send + Sync>> {
+    match backend {
+        StorageBackend::Fs => Ok(Arc::new(FsSessionStore::new(sessions_dir.to_path_buf()))),
+        #[cfg(feature = "sqlite")]
+        StorageBackend::Sqlite => Ok(Arc::new(SqliteSessionStore::open(&database(
+            sessions_dir,
+            "sessions",
+        ))?)),
+        #[cfg(not(feature = "sqlite"))]
+        StorageBackend::Sqlite => Err(sqlite_unavailable()),
+    }
+}
+
+/// The workflow storage `backend` keeps in `workflows_dir`
+pub fn workflow_storage(
+    backend: StorageBackend,
+    workflows_dir: &Path,
+) -> Result<Box<dyn WorkflowStorage + Send + Sync>> {
+    match backend {
+        StorageBackend::Fs => Ok(Box::new(FsWorkflowStorage::new(
+            workflows_dir.to_path_buf(),
+        ))),
+        #[cfg(feature = "sqlite")]
+        StorageBackend::Sqlite => Ok(Box::new(SqliteWorkflowStorage::open(&database(
+            workflows_dir,
+            "workflows",
+        ))?)),
+        #[cfg(not(feature = "sqlite"))]
+        StorageBackend::Sqlite => Err(sqlite_unavailable()),
+    }
+}
+
+/// Where finished sessions are kept
+pub trait SessionStore {
+    fn save(&self, session: &Session) -> Result<()>;
+    fn load(&self, id: &str) -> Result<Session>;
+    fn delete(&self, id: &str) -> Result<()>;
+    /// Summaries matching `filter`, newest first
+    fn list(&self, filter: &SessionFilter) -> Result<Vec<SessionSummary>>;
+}
+
+/// Which sessions `session list` shows
+#[derive(Debug, Clone, Default)]
+pub struct SessionFilter {
+    pub status: Option<SessionStatus>,
+    /// Case-insensitive part of the name
+    pub name: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl SessionFilter {
+    pub fn matches(&self, summary: &SessionSummary) -> bool {
+        self.status
+            .as_ref()
+            .is_none_or(|status| summary.status == *status)
+            && self.name.as_ref().is_none_or(|name| {
+                summary
+                    .name
+                    .as_ref()
+                    .is_some_and(|n| n.to_lowercase().contains(&name.to_lowercase()))
+            })
+    }
+}
+
+const INDEX_FILE: &str = "_index.json";
+
+/// Longest wait for another process's lock
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A lock this old was left behind by a process that died holding it
+const STALE_LOCK: Duration = Duration::from_secs(30);
+
+/// Exclusive lock held while a file is read, changed and written back
+///
+/// Created with `create_new`, so only one process gets it; removed on drop.
+pub struct FileLock(PathBuf);
+
+impl FileLock {
+    /// Lock `path` (by creating `<path>.lock`), waiting for other holders
+    pub fn acquire(path: &Path) -> Result<Self> {
+        let mut lock = path.as_os_str().to_owned();
+        lock.push(".lock");
+        let lock = PathBuf::from(lock);
+        let start = Instant::now();
+        loop {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock)
+            {
+                Ok(_) => return Ok(Self(lock)),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = std::fs::metadata(&lock)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > STALE_LOCK);
+                    if stale {
+                        let _ = std::fs::remove_file(&lock);
+                    } else if start.elapsed() > LOCK_TIMEOUT {
+                        return Err(anyhow!(
+                            "Timed out waiting for {} (delete it if no other DOMGuard is running)",
+                            lock.display()
+                        ));
+                    } else {
+                        std::thread::sleep(Duration::from_millis(20));
+                    }
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create {}", lock.display()))
+                }
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Replace `path` with `content` without readers ever seeing a partial file
+pub fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Not a file path: {}", path.display()))?;
+    let tmp = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    std::fs::write(&tmp, content).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+/// Where saved workflows are kept
+pub trait WorkflowStorage {
+    /// Every readable workflow
+    fn load_all(&self) -> Result<Vec<Workflow>>;
+    /// Add or replace a workflow, returning the file it is kept in
+    fn save(&self, workflow: &Workflow) -> Result<PathBuf>;
+    fn delete(&self, id: &str) -> Result<()>;
+    /// The workflow's own file, for `workflow edit`; None when it has none
+    fn file(&self, id: &str) -> Option<PathBuf>;
+}
+
+/// Workflows as `<id>.toml` files
+#[derive(Debug, Clone)]
+pub struct FsWorkflowStorage {
+    dir: PathBuf,
+}
+
+impl FsWorkflowStorage {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn workflow_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.toml", id))
+    }
+}
+
+impl WorkflowStorage for FsWorkflowStorage {
+    fn load_all(&self) -> Result<Vec<Workflow>> {
+        let mut workflows = Vec::new();
+        if !self.dir.exists() {
+            return Ok(workflows);
+        }
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "toml") {
+                if let Ok(content) = std::fs::read_to_string(&path) {
+                    if let Ok(workflow) = toml::from_str::<Workflow>(&content) {
+                        workflows.push(workflow);
+                    }
+                }
+            }
+        }
+        Ok(workflows)
+    }
+
+    fn save(&self, workflow: &Workflow) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.workflow_path(&workflow.id);
+        write_atomic(&path, &toml::to_string_pretty(workflow)?)?;
+        Ok(path)
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        let path = self.workflow_path(id);
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to delete {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn file(&self, id: &str) -> Option<PathBuf> {
+        Some(self.workflow_path(id)).filter(|path| path.exists())
+    }
+}
+
+/// What `migrate-storage` did
+#[derive(Debug, Clone, Serialize)]
+pub struct Migration {
+    pub backend: StorageBackend,
+    pub sessions_dir: PathBuf,
+    /// Sessions indexed (fs) or copied into the database (sqlite)
+    pub sessions: usize,
+    /// Workflows copied into the database (sqlite only)
+    pub workflows: usize,
+}
+
+/// Index the session files under `domguard_dir`, or copy them and the
+/// workflow files into the `to` backend's databases
+///
+/// Copying leaves the files in place, and running it again overwrites the
+/// copies, so it can be repeated until the config switches backends.
+pub fn migrate(domguard_dir: &Path, to: StorageBackend) -> Result<Migration> {
+    let sessions_dir = domguard_dir.join("sessions");
+    let files = FsSessionStore::new(sessions_dir.clone());
+    let (sessions, workflows) = match to {
+        StorageBackend::Fs => (files.rebuild_index()?, 0),
+        StorageBackend::Sqlite => {
+            let store = session_store(to, &sessions_dir)?;
+            let sessions = files.scan()?;
+            for summary in &sessions {
+                store.save(&files.load(&summary.id)?)?;
+            }
+            let workflows_dir = domguard_dir.join("workflows");
+            let storage = workflow_storage(to, &workflows_dir)?;
+            let workflows = FsWorkflowStorage::new(workflows_dir).load_all()?;
+            for workflow in &workflows {
+                storage.save(workflow)?;
+            }
+            (sessions.len(), workflows.len())
+        }
+    };
+    Ok(Migration {
+        backend: to,
+        sessions_dir,
+        sessions,
+        workflows,
+    })
+}
+
+/// Sessions as `session_<id>.json` files with a summary index
+#[derive(Debug, Clone)]
+pub struct FsSessionStore {
+    dir: PathBuf,
+}
+
+impl FsSessionStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn session_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("session_{}.json", id))
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join(INDEX_FILE)
+    }
+
+    /// Summaries of every session file, newest first
+    fn scan(&self) -> Result<Vec<SessionSummary>> {
+        let mut summaries = Vec::new();
+        if !self.dir.exists() {
+            return Ok(summaries);
+        }
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let is_session = path.file_name().is_some_and(|n| {
+                let n = n.to_string_lossy();
+                n.starts_with("session_") && n.ends_with(".json")
+            });
+            if is_session {
+                if let Ok(session) = Session::load(&path) {
+                    summaries.push(session.summary());
+                }
+            }
+        }
+        summaries.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        Ok(summaries)
+    }
+
+    /// The index, or None when there is none yet (or it's unreadable)
+    fn read_index(&self) -> Option<Vec<SessionSummary>> {
+        let content = std::fs::read_to_string(self.index_path()).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Change the index under its lock, building it first if missing
+    fn update_index(&self, change: impl FnOnce(&mut Vec<SessionSummary>)) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let index_path = self.index_path();
+        let _lock = FileLock::acquire(&index_path)?;
+        let mut index = match self.read_index() {
+            Some(index) => index,
+            None => self.scan()?,
+        };
+        change(&mut index);
+        index.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        write_atomic(&index_path, &serde_json::to_string(&index)?)
+    }
+
+    /// Build the index from the session files; returns how many it holds
+    pub fn rebuild_index(&self) -> Result<usize> {
+        std::fs::create_dir_all(&self.dir)?;
+        let index_path = self.index_path();
+        let _lock = FileLock::acquire(&index_path)?;
+        let summaries = self.scan()?;
+        write_atomic(&index_path, &serde_json::to_string(&summaries)?)?;
+        Ok(summaries.len())
+    }
+}
+
+impl SessionStore for FsSessionStore {
+    fn save(&self, session: &Session) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        session.save(&self.session_path(&session.id))?;
+        let summary = session.summary();
+        self.update_index(|index| {
+            index.retain(|s| s.id != summary.id);
+            index.push(summary);
+        })
+    }
+
+    fn load(&self, id: &str) -> Result<Session> {
+        Session::load(&self.session_path(id))
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        std::fs::remove_file(self.session_path(id))
+            .with_context(|| format!("Failed to delete session {}", id))?;
+        self.update_index(|index| index.retain(|s| s.id != id))
+    }
+
+    fn list(&self, filter: &SessionFilter) -> Result<Vec<SessionSummary>> {
+        let index = match self.read_index() {
+            Some(index) => index,
+            None => {
+                let summaries = self.scan()?;
+                if !summaries.is_empty() {
+                    // Later listings read the index; failing to write it only costs speed
+                    let _ = self.rebuild_index();
+                }
+                summaries
+            }
+        };
+        Ok(index
+            .into_iter()
+            .filter(|s| filter.matches(s))
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::{SqliteSessionStore, SqliteWorkflowStorage};
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use anyhow::{anyhow, Context, Result};
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use rusqlite::{params, Connection, OptionalExtension};
+    use std::path::{Path, PathBuf};
+
+    use super::{SessionFilter, SessionStore, WorkflowStorage, LOCK_TIMEOUT};
+    use crate::session::{Session, SessionStatus, SessionSummary};
+    use crate::workflow::Workflow;
+
+    const SESSIONS_SCHEMA: &str = "
+        CREATE TABLE IF NOT EXISTS sessions (
+            id TEXT PRIMARY KEY,
+            name TEXT,
+            status TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            summary TEXT NOT NULL,
+            body TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS sessions_status ON sessions (status, started_at);
+        CREATE INDEX IF NOT EXISTS sessions_name ON sessions (name);
+        CREATE INDEX IF NOT EXISTS sessions_started_at ON sessions (started_at);
+    ";
+
+    const WORKFLOWS_SCHEMA: &str = "
+        CREATE TABLE IF NOT EXISTS workflows (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            body TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS workflows_name ON workflows (name);
+    ";
+
+    /// Open `path` with WAL, a busy timeout and `schema` in place
+    ///
+    /// Each call gets its own connection, so stores stay `Send + Sync` and
+    /// processes sharing the file only wait on SQLite's own locks.
+    fn connect(path: &Path, schema: &str) -> Result<Connection> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn =
+            Connection::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        conn.busy_timeout(LOCK_TIMEOUT)?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        conn.execute_batch(schema)?;
+        Ok(conn)
+    }
+
+    /// Sortable text for a timestamp (fixed width, unlike serde's)
+    fn timestamp(at: &DateTime<Utc>) -> String {
+        at.to_rfc3339_opts(SecondsFormat::Micros, true)
+    }
+
+    /// A status as stored in its JSON, e.g. `completed`
+    fn status_text(status: &SessionStatus) -> Result<String> {
+        serde_json::to_value(status)?
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| anyhow!("Session status is not a string"))
+    }
+
+    /// Sessions as rows of one table, listed by indexed queries
+    #[derive(Debug, Clone)]
+    pub struct SqliteSessionStore {
+        path: PathBuf,
+    }
+
+    impl SqliteSessionStore {
+        /// The store in the database at `path`, created if missing
+        pub fn open(path: &Path) -> Result<Self> {
+            connect(path, SESSIONS_SCHEMA)?;
+            Ok(Self {
+                path: path.to_path_buf(),
+            })
+        }
+
+        fn conn(&self) -> Result<Connection> {
+            connect(&self.path, SESSIONS_SCHEMA)
+        }
+    }
+
+    impl SessionStore for SqliteSessionStore {
+        fn save(&self, session: &Session) -> Result<()> {
+            let summary = session.summary();
+            self.conn()?.execute(
+                "INSERT OR REPLACE INTO sessions (id, name, status, started_at, summary, body)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    session.id,
+                    session.name,
+                    status_text(&session.status)?,
+                    timestamp(&session.started_at),
+                    serde_json::to_string(&summary)?,
+                    serde_json::to_string(session)?,
+                ],
+            )?;
+            Ok(())
+        }
+
+        fn load(&self, id: &str) -> Result<Session> {
+            let body: Option<String> = self
+                .conn()?
+                .query_row("SELECT body FROM sessions WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })
+                .optional()?;
+            let body = body.ok_or_else(|| anyhow!("Session not found: {}", id))?;
+            serde_json::from_str(&body).with_context(|| format!("Failed to parse session {}", id))
+        }
+
+        fn delete(&self, id: &str) -> Result<()> {
+            let deleted = self
+                .conn()?
+                .execute("DELETE FROM sessions WHERE id = ?1", [id])?;
+            if deleted == 0 {
+                anyhow::bail!("Failed to delete session {}: not found", id);
+            }
+            Ok(())
+        }
+
+        fn list(&self, filter: &SessionFilter) -> Result<Vec<SessionSummary>> {
+            let status = filter.status.as_ref().map(status_text).transpose()?;
+            let name = filter.name.as_ref().map(|n| n.to_lowercase());
+            // SQLite treats a negative LIMIT as no limit
+            let limit = filter
+                .limit
+                .map_or(-1, |l| i64::try_from(l).unwrap_or(i64::MAX));
+            let conn = self.conn()?;
+            let mut stmt = conn.prepare(
+                "SELECT summary FROM sessions
+                 WHERE (?1 IS NULL OR status = ?1)
+                   AND (?2 IS NULL OR instr(lower(name), ?2) > 0)
+                 ORDER BY started_at DESC
+                 LIMIT ?3",
+            )?;
+            let rows =
+                stmt.query_map(params![status, name, limit], |row| row.get::<_, String>(0))?;
+            let mut summaries = Vec::new();
+            for row in rows {
+                summaries.push(serde_json::from_str(&row?)?);
+            }
+            Ok(summaries)
+        }
+    }
+
+    /// Workflows as rows of one table
+    #[derive(Debug, Clone)]
+    pub struct SqliteWorkflowStorage {
+        path: PathBuf,
+    }
+
+    impl SqliteWorkflowStorage {
+        /// The storage in the database at `path`, created if missing
+        pub fn open(path: &Path) -> Result<Self> {
+            connect(path, WORKFLOWS_SCHEMA)?;
+            Ok(Self {
+                path: path.to_path_buf(),
+            })
+        }
+
+        fn conn(&self) -> Result<Connection> {
+            connect(&self.path, WORKFLOWS_SCHEMA)
+        }
+    }
+
+    impl WorkflowStorage for SqliteWorkflowStorage {
+        fn load_all(&self) -> Result<Vec<Workflow>> {
+            let conn = self.conn()?;
+            let mut stmt = conn.prepare("SELECT body FROM workflows")?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            let mut workflows = Vec::new();
+            for row in rows {
+                // Like unreadable files, rows from a newer schema are skipped
+                if let Ok(workflow) = serde_json::from_str(&row?) {
+                    workflows.push(workflow);
+                }
+            }
+            Ok(workflows)
+        }
+
+        fn save(&self, workflow: &Workflow) -> Result<PathBuf> {
+            self.conn()?.execute(
+                "INSERT OR REPLACE INTO workflows (id, name, body) VALUES (?1, ?2, ?3)",
+                params![workflow.id, workflow.name, serde_json::to_string(workflow)?],
+            )?;
+            Ok(self.path.clone())
+        }
+
+        fn delete(&self, id: &str) -> Result<()> {
+            self.conn()?
+                .execute("DELETE FROM workflows WHERE id = ?1", [id])?;
+            Ok(())
+        }
+
+        fn file(&self, _id: &str) -> Option<PathBuf> {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_tracks_saves_and_deletes() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FsSessionStore::new(dir.path().to_path_buf());
+        let mut checkout = Session::new(Some("Checkout flow".to_string()));
+        checkout.end();
+        let login = Session::new(Some("login".to_string()));
+        store.save(&checkout).unwrap();
+        store.save(&login).unwrap();
+        assert!(dir.path().join(INDEX_FILE).exists());
+
+        let all = store.list(&SessionFilter::default()).unwrap();
+        assert_eq!(all.len(), 2);
+        let completed = store
+            .list(&SessionFilter {
+                status: Some(SessionStatus::Completed),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].id, checkout.id);
+        let named = store
+            .list(&SessionFilter {
+                name: Some("CHECKOUT".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(named.len(), 1);
+
+        store.delete(&checkout.id).unwrap();
+        let left = store.list(&SessionFilter::default()).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].id, login.id);
+        assert!(!dir.path().join("_index.json.lock").exists());
+    }
+
+    #[test]
+    fn test_rebuild_index_picks_up_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = Session::new(None);
+        session
+            .save(&dir.path().join(format!("session_{}.json", session.id)))
+            .unwrap();
+        let store = FsSessionStore::new(dir.path().to_path_buf());
+        assert_eq!(store.rebuild_index().unwrap(), 1);
+        let listed = store.list(&SessionFilter::default()).unwrap();
+        assert_eq!(listed[0].id, session.id);
+    }
+
+    #[test]
+    fn test_workflow_manager_goes_through_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FsWorkflowStorage::new(dir.path().to_path_buf());
+        let mut manager = crate::workflow::WorkflowManager::with_storage(Box::new(storage.clone()));
+        let workflow = crate::workflow::WorkflowManager::create_empty("Checkout");
+        let path = manager.save(workflow.clone()).unwrap();
+        assert_eq!(storage.file(&workflow.id), Some(path));
+
+        manager.record_run(&workflow.id, true).unwrap();
+        let saved = storage.load_all().unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].run_count, 1);
+
+        assert!(manager.delete(&workflow.id).unwrap());
+        assert!(storage.load_all().unwrap().is_empty());
+        assert_eq!(storage.file(&workflow.id), None);
+    }
+
+    #[test]
+    fn test_storage_backend_parses() {
+        assert_eq!("fs".parse::<StorageBackend>(), Ok(StorageBackend::Fs));
+        assert_eq!(
+            "SQLite".parse::<StorageBackend>(),
+            Ok(StorageBackend::Sqlite)
+        );
+        assert!("redis".parse::<StorageBackend>().is_err());
+        let config: StorageConfig = toml::from_str("backend = \"sqlite\"").unwrap();
+        assert_eq!(config.backend, StorageBackend::Sqlite);
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    #[test]
+    fn test_sqlite_needs_the_feature() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(session_store(StorageBackend::Sqlite, dir.path()).is_err());
+        assert!(migrate(dir.path(), StorageBackend::Sqlite).is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_session_store_filters_in_queries() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SqliteSessionStore::open(&dir.path().join("sessions.db")).unwrap();
+        let mut checkout = Session::new(Some("Checkout flow".to_string()));
+        checkout.end();
+        let login = Session::new(Some("login".to_string()));
+        store.save(&checkout).unwrap();
+        store.save(&login).unwrap();
+        // Saving again replaces the row
+        store.save(&checkout).unwrap();
+
+        assert_eq!(store.list(&SessionFilter::default()).unwrap().len(), 2);
+        let completed = store
+            .list(&SessionFilter {
+                status: Some(SessionStatus::Completed),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].id, checkout.id);
+        let named = store
+            .list(&SessionFilter {
+                name: Some("CHECKOUT".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(named.len(), 1);
+        let limited = store
+            .list(&SessionFilter {
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(limited.len(), 1);
+
+        assert_eq!(store.load(&login.id).unwrap().id, login.id);
+        store.delete(&checkout.id).unwrap();
+        assert!(store.load(&checkout.id).is_err());
+        assert!(store.delete(&checkout.id).is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_takes_parallel_writers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.db");
+        SqliteSessionStore::open(&path).unwrap();
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let store = SqliteSessionStore::open(&path).unwrap();
+                    for n in 0..10 {
+                        let mut session = Session::new(None);
+                        session.id = format!("writer{}-{}", writer, n);
+                        store.save(&session).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let store = SqliteSessionStore::open(&path).unwrap();
+        assert_eq!(store.list(&SessionFilter::default()).unwrap().len(), 40);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_migrate_copies_files_into_sqlite() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = Session::new(Some("old".to_string()));
+        FsSessionStore::new(dir.path().join("sessions"))
+            .save(&session)
+            .unwrap();
+        let workflow = crate::workflow::WorkflowManager::create_empty("Report");
+        FsWorkflowStorage::new(dir.path().join("workflows"))
+            .save(&workflow)
+            .unwrap();
+
+        let migration = migrate(dir.path(), StorageBackend::Sqlite).unwrap();
+        assert_eq!((migration.sessions, migration.workflows), (1, 1));
+        let sessions = session_store(StorageBackend::Sqlite, &dir.path().join("sessions")).unwrap();
+        assert_eq!(sessions.load(&session.id).unwrap().name, session.name);
+        let workflows =
+            workflow_storage(StorageBackend::Sqlite, &dir.path().join("workflows")).unwrap();
+        let copied = workflows.load_all().unwrap();
+        assert_eq!(copied[0].id, workflow.id);
+        assert_eq!(workflows.file(&workflow.id), None);
+    }
+}
@@ -3,11 +3,16 @@
 //! Allows the agent to pause automation and hand control back to the human user.
 //! The user can perform actions manually, and the agent can resume when ready.
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::fmt::Write as _;
 use std::path::PathBuf;
 
+use crate::captcha::{detect_captcha, CaptchaRecommendation};
+use crate::cdp::CdpConnection;
+use crate::config::Config;
+use crate::output::Formatter;
+
 /// Takeover state
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
@@ -49,6 +54,44 @@ pub enum TakeoverReason {
     Custom(String),
 }
 
+impl TakeoverReason {
+    /// The reason named on the command line; unknown names are custom reasons
+    pub fn parse(reason: &str) -> Self {
+        match reason.to_lowercase().as_str() {
+            "captcha" => TakeoverReason::Captcha,
+            "auth" | "authentication" | "login" => TakeoverReason::Authentication,
+            "sensitive" => TakeoverReason::SensitiveAction,
+            "error" => TakeoverReason::Error,
+            "uncertain" => TakeoverReason::Uncertain,
+            "complex" => TakeoverReason::ComplexInteraction,
+            "2fa" | "mfa" | "twofactor" => TakeoverReason::TwoFactorAuth,
+            "payment" | "pay" => TakeoverReason::Payment,
+            "user" | "requested" => TakeoverReason::UserRequested,
+            custom => TakeoverReason::Custom(custom.to_string()),
+        }
+    }
+
+    /// Message shown to the human when the request gives none
+    pub fn default_message(&self) -> String {
+        match self {
+            TakeoverReason::Captcha => "CAPTCHA detected - human verification required".to_string(),
+            TakeoverReason::Authentication => "Authentication required - please log in".to_string(),
+            TakeoverReason::SensitiveAction => {
+                "Sensitive action requires human confirmation".to_string()
+            }
+            TakeoverReason::Error => "Error occurred - human intervention needed".to_string(),
+            TakeoverReason::Uncertain => "Agent unsure how to proceed - please help".to_string(),
+            TakeoverReason::ComplexInteraction => {
+                "Complex interaction - human control needed".to_string()
+            }
+            TakeoverReason::TwoFactorAuth => "Two-factor authentication required".to_string(),
+            TakeoverReason::Payment => "Payment action requires human confirmation".to_string(),
+            TakeoverReason::UserRequested => "User requested control".to_string(),
+            TakeoverReason::Custom(s) => s.clone(),
+        }
+    }
+}
+
 /// Takeover session information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TakeoverSession {
@@ -94,6 +137,10 @@ pub struct TakeoverSession {
     /// Notes from user (optional)
     #[serde(default)]
     pub user_notes: Option<String>,
+
+    /// Screenshot of the page when takeover started
+    #[serde(default)]
+    pub screenshot: Option<std::path::PathBuf>,
 }
 
 impl TakeoverSession {
@@ -112,27 +159,38 @@ impl TakeoverSession {
             duration_secs: None,
             success: None,
             user_notes: None,
+            screenshot: None,
         }
     }
 
     /// Add instructions for the user
+    #[must_use]
     pub fn with_instructions(mut self, instructions: &str) -> Self {
         self.instructions = Some(instructions.to_string());
         self
     }
 
     /// Add expected outcome
+    #[must_use]
     pub fn with_expected_outcome(mut self, outcome: &str) -> Self {
         self.expected_outcome = Some(outcome.to_string());
         self
     }
 
     /// Add URL context
+    #[must_use]
     pub fn with_url(mut self, url: &str) -> Self {
         self.url = Some(url.to_string());
         self
     }
 
+    /// Attach a screenshot of the page
+    #[must_use]
+    pub fn with_screenshot(mut self, path: std::path::PathBuf) -> Self {
+        self.screenshot = Some(path);
+        self
+    }
+
     /// Mark takeover as complete
     pub fn complete(&mut self, success: bool, notes: Option<String>) {
         self.state = TakeoverState::ResumeRequested;
@@ -278,6 +336,298 @@ fn generate_id() -> String {
     format!("takeover-{:x}", now)[..20].to_string()
 }
 
+/// What `takeover wait` waits for besides `takeover done` or `cancel`
+#[derive(Debug, Default)]
+pub struct WaitCondition {
+    /// Finish when this selector appears on the page
+    pub selector: Option<String>,
+    /// Finish when the page URL matches
+    pub url_pattern: Option<regex::Regex>,
+}
+
+impl WaitCondition {
+    pub fn new(selector: Option<&str>, url_pattern: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            selector: selector.map(str::to_string),
+            url_pattern: url_pattern
+                .map(regex::Regex::new)
+                .transpose()
+                .map_err(|e| anyhow!("Invalid --until-url-matches pattern: {}", e))?,
+        })
+    }
+
+    /// Whether the page has to be watched
+    pub fn watches_page(&self) -> bool {
+        self.selector.is_some() || self.url_pattern.is_some()
+    }
+
+    /// What on the page finished the takeover, if anything did yet
+    async fn detect(&self, cdp: &CdpConnection) -> Result<Option<String>> {
+        if let Some(selector) = &self.selector {
+            let js = format!(
+                "document.querySelector({}) !== null",
+                serde_json::to_string(selector)?
+            );
+            return Ok(cdp
+                .evaluate(&js)
+                .await
+                .ok()
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+                .then(|| format!("selector {} appeared", selector)));
+        }
+        let url = cdp.current_url().await.unwrap_or_default();
+        Ok(self
+            .url_pattern
+            .as_ref()
+            .filter(|re| re.is_match(&url))
+            .map(|_| format!("URL {} matched", url)))
+    }
+}
+
+/// How a takeover `takeover wait` waited for ended
+#[derive(Debug)]
+pub struct WaitOutcome {
+    /// The finished takeover, or None when it was cancelled
+    pub completed: Option<TakeoverSession>,
+    /// `user`, `cancelled` or `page`
+    pub by: &'static str,
+}
+
+impl TakeoverManager {
+    /// Wait for `session` to finish, completing it when `until` is met on the page
+    pub async fn wait(
+        &self,
+        cdp: &mut CdpConnection,
+        session: &TakeoverSession,
+        until: &WaitCondition,
+        timeout_secs: u64,
+    ) -> Result<WaitOutcome> {
+        if until.watches_page() {
+            cdp.connect().await?;
+        }
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(timeout_secs);
+        loop {
+            // The human finished (done or cancel) from another terminal
+            if self.get_current().is_none() {
+                let finished = self.get_history()?.into_iter().find(|s| s.id == session.id);
+                // Cancelled takeovers aren't kept in the history
+                let by = if finished.is_some() {
+                    "user"
+                } else {
+                    "cancelled"
+                };
+                return Ok(WaitOutcome {
+                    completed: finished,
+                    by,
+                });
+            }
+
+            if until.watches_page() {
+                if let Some(outcome) = until.detect(cdp).await? {
+                    let notes = format!("Auto-completed: {}", outcome);
+                    return Ok(WaitOutcome {
+                        completed: self.complete(true, Some(notes))?,
+                        by: "page",
+                    });
+                }
+            }
+
+            if std::time::Instant::now() >= deadline {
+                anyhow::bail!(
+                    "Timed out after {}s waiting for takeover {} to finish",
+                    timeout_secs,
+                    session.id
+                );
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+    }
+}
+
+/// Build a takeover request, noting the current page when Chrome is reachable
+pub async fn request(
+    cdp: &mut CdpConnection,
+    reason: &str,
+    message: Option<&str>,
+    instructions: Option<&str>,
+    expected: Option<&str>,
+) -> TakeoverSession {
+    let reason = TakeoverReason::parse(reason);
+    let message = message.map_or_else(|| reason.default_message(), str::to_string);
+    let mut session = TakeoverSession::new(reason, &message);
+
+    if let Some(instructions) = instructions {
+        session = session.with_instructions(instructions);
+    }
+    if let Some(expected) = expected {
+        session = session.with_expected_outcome(expected);
+    }
+    if cdp.connect().await.is_ok() {
+        if let Ok(url) = cdp.current_url().await {
+            session = session.with_url(&url);
+        }
+    }
+    session
+}
+
+/// Start a takeover if the page shows an unsolved CAPTCHA
+///
+/// Returns None when a takeover is already active or there is nothing for a
+/// human to solve. A screenshot of the page goes with the request.
+pub async fn request_for_captcha(cdp: &CdpConnection) -> Result<Option<TakeoverSession>> {
+    let domguard_dir = Config::find_domguard_dir().unwrap_or_else(Config::domguard_dir);
+    let manager = TakeoverManager::new(&domguard_dir);
+    if manager.is_active() {
+        return Ok(None);
+    }
+
+    let Ok(detection) = detect_captcha(cdp).await else {
+        return Ok(None);
+    };
+    if !detection.detected || detection.recommendation != CaptchaRecommendation::PauseForHuman {
+        return Ok(None);
+    }
+
+    let captcha_type = detection
+        .captcha_type
+        .as_ref()
+        .and_then(|t| serde_json::to_value(t).ok())
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_else(|| "unknown".to_string());
+    let message = format!(
+        "CAPTCHA detected ({}): {}",
+        captcha_type,
+        detection
+            .description
+            .as_deref()
+            .unwrap_or("solve it to continue")
+    );
+
+    let mut session = TakeoverSession::new(TakeoverReason::Captcha, &message)
+        .with_instructions("Solve the CAPTCHA in the browser, then run 'domguard takeover done'");
+    if let Ok(url) = cdp.current_url().await {
+        session = session.with_url(&url);
+    }
+    if let Ok(png) = cdp.screenshot(false).await {
+        let path = domguard_dir
+            .join("takeover_history")
+            .join(format!("{}.png", session.id));
+        let saved = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&path, png));
+        if saved.is_ok() {
+            session = session.with_screenshot(path);
+        }
+    }
+
+    manager.start(&session)?;
+    Ok(Some(session))
+}
+
+/// Which channels a takeover request was sent to, and whether each worked
+#[derive(Debug, Default)]
+pub struct Notifications {
+    pub desktop: Option<Result<()>>,
+    pub webhook: Option<Result<()>>,
+}
+
+impl Notifications {
+    /// `{"desktop": true, "webhook": false}`, for JSON output
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut notified = serde_json::Map::new();
+        if let Some(sent) = &self.desktop {
+            notified.insert("desktop".to_string(), sent.is_ok().into());
+        }
+        if let Some(sent) = &self.webhook {
+            notified.insert("webhook".to_string(), sent.is_ok().into());
+        }
+        serde_json::Value::Object(notified)
+    }
+
+    /// Warn about each channel that failed; the request stands either way
+    pub fn warn_failures(&self, formatter: &Formatter) {
+        if let Some(Err(e)) = &self.desktop {
+            formatter.warning(&format!("Desktop notification failed: {}", e));
+        }
+        if let Some(Err(e)) = &self.webhook {
+            formatter.warning(&format!("Takeover webhook failed: {}", e));
+        }
+    }
+}
+
+/// Tell the human about a takeover request on the desktop and by webhook
+pub fn notify(session: &TakeoverSession, desktop: bool, webhook: Option<&str>) -> Notifications {
+    Notifications {
+        desktop: desktop
+            .then(|| send_desktop_notification("DOMGuard takeover", &notification_text(session))),
+        webhook: webhook.map(|url| post_webhook(url, &webhook_payload(session))),
+    }
+}
+
+/// One-line summary of a takeover request for notifications
+pub fn notification_text(session: &TakeoverSession) -> String {
+    let mut text = format!("DOMGuard needs you: {}", session.message);
+    if let Some(url) = &session.url {
+        let _ = write!(text, " ({})", url);
+    }
+    text
+}
+
+/// Slack-compatible webhook payload for a takeover request
+///
+/// Slack reads `text`; the other fields are there for custom receivers.
+pub fn webhook_payload(session: &TakeoverSession) -> serde_json::Value {
+    let mut text = notification_text(session);
+    if let Some(instructions) = &session.instructions {
+        let _ = write!(text, "\n{}", instructions);
+    }
+    serde_json::json!({
+        "text": text,
+        "takeover_id": session.id,
+        "reason": session.reason,
+        "message": session.message,
+        "url": session.url,
+    })
+}
+
+/// Show an OS desktop notification (notify-send on Linux, osascript on macOS)
+pub fn send_desktop_notification(title: &str, body: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut command = std::process::Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification \"{}\" with title \"{}\"",
+            quote(body),
+            quote(title)
+        ));
+        command
+    } else {
+        let binary = which::which("notify-send")
+            .map_err(|_| anyhow::anyhow!("notify-send not found; install libnotify"))?;
+        let mut command = std::process::Command::new(binary);
+        command.args(["--urgency=critical", title, body]);
+        command
+    };
+
+    let status = command.status()?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("Desktop notification failed ({})", status));
+    }
+    Ok(())
+}
+
+/// POST a JSON payload to a webhook URL
+pub fn post_webhook(url: &str, payload: &serde_json::Value) -> Result<()> {
+    crate::http::post_json(
+        url,
+        &payload.to_string(),
+        std::time::Duration::from_secs(10),
+    )
+}
+
 /// Format takeover session for display
 pub fn format_takeover(session: &TakeoverSession) -> String {
     let mut output = String::new();
@@ -302,6 +652,10 @@ pub fn format_takeover(session: &TakeoverSession) -> String {
         let _ = writeln!(output, "  URL: {}", url);
     }
 
+    if let Some(screenshot) = &session.screenshot {
+        let _ = writeln!(output, "  Screenshot: {}", screenshot.display());
+    }
+
     let _ = writeln!(
         output,
         "  Started: {}",
@@ -330,6 +684,18 @@ mod tests {
         assert!(session.id.starts_with("takeover-"));
     }
 
+    #[test]
+    fn test_parse_reason() {
+        assert!(matches!(
+            TakeoverReason::parse("MFA"),
+            TakeoverReason::TwoFactorAuth
+        ));
+        let custom = TakeoverReason::parse("cookie wall");
+        assert_eq!(custom.default_message(), "cookie wall");
+        assert!(WaitCondition::new(None, Some("(")).is_err());
+        assert!(!WaitCondition::default().watches_page());
+    }
+
     #[test]
     fn test_complete_takeover() {
         let mut session =
@@ -348,6 +714,57 @@ mod tests {
         assert_eq!(session.url, Some("https://example.com".to_string()));
     }
 
+    #[test]
+    fn test_with_screenshot() {
+        let session = TakeoverSession::new(TakeoverReason::Captcha, "CAPTCHA detected")
+            .with_screenshot(std::path::PathBuf::from("takeover_history/shot.png"));
+        assert!(format_takeover(&session).contains("Screenshot: takeover_history/shot.png"));
+
+        let legacy: TakeoverSession = serde_json::from_value(serde_json::json!({
+            "id": "takeover-1",
+            "state": "waiting_for_user",
+            "reason": "captcha",
+            "message": "CAPTCHA",
+            "started_at": "2026-01-01T00:00:00Z"
+        }))
+        .unwrap();
+        assert!(legacy.screenshot.is_none());
+    }
+
+    #[test]
+    fn test_manager_complete_moves_to_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = TakeoverManager::new(dir.path());
+        let session = TakeoverSession::new(TakeoverReason::Authentication, "Log in");
+        manager.start(&session).unwrap();
+        assert!(manager.is_active());
+
+        let completed = manager
+            .complete(true, Some("Auto-completed".to_string()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(completed.id, session.id);
+        assert!(!manager.is_active());
+
+        let history = manager.get_history().unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].user_notes.as_deref(), Some("Auto-completed"));
+    }
+
+    #[test]
+    fn test_webhook_payload() {
+        let session = TakeoverSession::new(TakeoverReason::Captcha, "CAPTCHA detected")
+            .with_url("https://example.com/login")
+            .with_instructions("Solve it, then run takeover done");
+        let payload = webhook_payload(&session);
+        let text = payload["text"].as_str().unwrap();
+        assert!(text.contains("CAPTCHA detected"));
+        assert!(text.contains("https://example.com/login"));
+        assert!(text.contains("takeover done"));
+        assert_eq!(payload["reason"], "captcha");
+        assert_eq!(payload["takeover_id"], session.id);
+    }
+
     #[test]
     fn test_with_instructions() {
         let session = TakeoverSession::new(TakeoverReason::Captcha, "CAPTCHA detected")
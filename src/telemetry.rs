@@ -0,0 +1,328 @@
+//! OpenTelemetry spans for DOMGuard invocations
+//!
+//! Off unless `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` or
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` is set. Each invocation is then one span
+//! (with the action, selector and outcome as attributes), each workflow step a
+//! child of it, and all of them are sent as OTLP/HTTP JSON when the command
+//! ends. A W3C `TRACEPARENT` in the environment makes the invocation a child
+//! of the caller's span, so browser actions show up inside an agent
+//! platform's trace. Export errors are ignored: tracing never fails a command.
+
+use serde_json::json;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Service name when `OTEL_SERVICE_NAME` isn't set
+const DEFAULT_SERVICE: &str = "domguard";
+
+/// How long sending the spans may hold up the end of a command
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+static TRACER: OnceLock<Mutex<Tracer>> = OnceLock::new();
+
+/// Caller's span from a `traceparent` header value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceParent {
+    pub trace_id: String,
+    pub span_id: String,
+    pub sampled: bool,
+}
+
+impl std::str::FromStr for TraceParent {
+    type Err = String;
+
+    /// `00-<32 hex trace id>-<16 hex span id>-<2 hex flags>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid traceparent \"{}\"", s);
+        let parts: Vec<&str> = s.trim().split('-').collect();
+        let [version, trace_id, span_id, flags] = parts[..] else {
+            return Err(invalid());
+        };
+        let hex = |part: &str, len: usize| {
+            part.len() == len
+                && part
+                    .bytes()
+                    .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+                && part.bytes().any(|b| b != b'0')
+        };
+        if version != "00" || !hex(trace_id, 32) || !hex(span_id, 16) || flags.len() != 2 {
+            return Err(invalid());
+        }
+        let flags = u8::from_str_radix(flags, 16).map_err(|_| invalid())?;
+        Ok(Self {
+            trace_id: trace_id.to_string(),
+            span_id: span_id.to_string(),
+            sampled: flags & 1 == 1,
+        })
+    }
+}
+
+/// A finished or running span
+#[derive(Debug, Clone)]
+struct SpanData {
+    span_id: String,
+    parent_span_id: Option<String>,
+    name: String,
+    start_ns: u128,
+    end_ns: u128,
+    attributes: Vec<(String, String)>,
+    error: Option<String>,
+}
+
+impl SpanData {
+    fn new(name: &str, parent_span_id: Option<String>) -> Self {
+        Self {
+            span_id: random_hex(8),
+            parent_span_id,
+            name: name.to_string(),
+            start_ns: now_ns(),
+            end_ns: 0,
+            attributes: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        self.attributes.retain(|(k, _)| k != key);
+        self.attributes.push((key.to_string(), value.to_string()));
+    }
+
+    fn end(&mut self, error: Option<&str>) {
+        self.end_ns = now_ns();
+        self.error = error.map(str::to_string);
+        self.set(
+            "domguard.outcome",
+            if error.is_some() { "error" } else { "success" },
+        );
+    }
+
+    fn to_otlp(&self, trace_id: &str) -> serde_json::Value {
+        let attributes: Vec<_> = self
+            .attributes
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } }))
+            .collect();
+        let status = match &self.error {
+            Some(message) => json!({ "code": 2, "message": message }),
+            None => json!({ "code": 1 }),
+        };
+        let mut span = json!({
+            "traceId": trace_id,
+            "spanId": self.span_id,
+            "name": self.name,
+            "kind": 1,
+            "startTimeUnixNano": self.start_ns.to_string(),
+            "endTimeUnixNano": self.end_ns.to_string(),
+            "attributes": attributes,
+            "status": status,
+        });
+        if let Some(parent) = &self.parent_span_id {
+            span["parentSpanId"] = json!(parent);
+        }
+        span
+    }
+}
+
+/// Spans of this invocation, waiting for export
+#[derive(Debug)]
+struct Tracer {
+    endpoint: String,
+    service: String,
+    trace_id: String,
+    root: SpanData,
+    finished: Vec<SpanData>,
+}
+
+impl Tracer {
+    fn otlp_request(&self) -> serde_json::Value {
+        let spans: Vec<_> = std::iter::once(&self.root)
+            .chain(&self.finished)
+            .map(|span| span.to_otlp(&self.trace_id))
+            .collect();
+        json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [
+                        { "key": "service.name", "value": { "stringValue": self.service } }
+                    ]
+                },
+                "scopeSpans": [{
+                    "scope": { "name": "domguard", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans,
+                }]
+            }]
+        })
+    }
+}
+
+/// OTLP traces URL from the standard environment variables
+fn endpoint_from_env() -> Option<String> {
+    let var = |name| {
+        std::env::var(name)
+            .ok()
+            .filter(|v: &String| !v.trim().is_empty())
+    };
+    if var("OTEL_SDK_DISABLED").is_some_and(|v| v.eq_ignore_ascii_case("true")) {
+        return None;
+    }
+    var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT").or_else(|| {
+        var("OTEL_EXPORTER_OTLP_ENDPOINT")
+            .map(|base| format!("{}/v1/traces", base.trim_end_matches('/')))
+    })
+}
+
+/// Whether the environment asks for traces
+pub fn configured() -> bool {
+    endpoint_from_env().is_some()
+}
+
+/// Start the invocation span named `name` (e.g. "domguard interact click")
+///
+/// Does nothing without an OTLP endpoint, or when `TRACEPARENT` says the
+/// caller's trace isn't sampled. An unparseable `TRACEPARENT` starts a new trace.
+pub fn init(name: &str) {
+    let Some(endpoint) = endpoint_from_env() else {
+        return;
+    };
+    let parent = std::env::var("TRACEPARENT")
+        .ok()
+        .and_then(|value| value.parse::<TraceParent>().ok());
+    if parent.as_ref().is_some_and(|p| !p.sampled) {
+        return;
+    }
+    let (trace_id, parent_span_id) = match parent {
+        Some(parent) => (parent.trace_id, Some(parent.span_id)),
+        None => (random_hex(16), None),
+    };
+    let _ = TRACER.set(Mutex::new(Tracer {
+        endpoint,
+        service: std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| DEFAULT_SERVICE.into()),
+        trace_id,
+        root: SpanData::new(name, parent_span_id),
+        finished: Vec::new(),
+    }));
+}
+
+/// Set an attribute on the invocation span
+pub fn set_attribute(key: &str, value: &str) {
+    if let Some(tracer) = TRACER.get() {
+        if let Ok(mut tracer) = tracer.lock() {
+            tracer.root.set(key, value);
+        }
+    }
+}
+
+/// End the invocation span and send every span to the collector
+pub fn finish(error: Option<&str>) {
+    let Some(tracer) = TRACER.get() else {
+        return;
+    };
+    let Ok(mut tracer) = tracer.lock() else {
+        return;
+    };
+    tracer.root.end(error);
+    let body = tracer.otlp_request().to_string();
+    let _ = crate::http::post_json(&tracer.endpoint, &body, EXPORT_TIMEOUT);
+}
+
+/// Child span of the invocation, e.g. one workflow step
+///
+/// Inert when tracing is off, so callers don't need to check.
+pub struct Span(Option<SpanData>);
+
+impl Span {
+    pub fn start(name: &str) -> Self {
+        let parent = TRACER
+            .get()
+            .and_then(|tracer| tracer.lock().ok())
+            .map(|tracer| tracer.root.span_id.clone());
+        Self(parent.map(|parent| SpanData::new(name, Some(parent))))
+    }
+
+    pub fn set_attribute(&mut self, key: &str, value: &str) {
+        if let Some(span) = &mut self.0 {
+            span.set(key, value);
+        }
+    }
+
+    /// Finish the span; it is exported with the invocation span
+    pub fn end(self, error: Option<&str>) {
+        let (Some(mut span), Some(tracer)) = (self.0, TRACER.get()) else {
+            return;
+        };
+        span.end(error);
+        if let Ok(mut tracer) = tracer.lock() {
+            tracer.finished.push(span);
+        }
+    }
+}
+
+fn now_ns() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+/// `bytes` random bytes as lowercase hex
+///
+/// std's `RandomState` is seeded randomly per process, which is enough for
+/// ids that only need to be unique.
+fn random_hex(bytes: usize) -> String {
+    let mut out = String::with_capacity(bytes * 2);
+    while out.len() < bytes * 2 {
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u128(now_ns());
+        hasher.write_usize(out.len());
+        for byte in hasher.finish().to_be_bytes() {
+            if out.len() < bytes * 2 {
+                out.push_str(&format!("{:02x}", byte));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_traceparent() {
+        let parent: TraceParent = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+            .parse()
+            .unwrap();
+        assert_eq!(parent.trace_id, "0af7651916cd43dd8448eb211c80319c");
+        assert_eq!(parent.span_id, "b7ad6b7169203331");
+        assert!(parent.sampled);
+        let unsampled: TraceParent = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00"
+            .parse()
+            .unwrap();
+        assert!(!unsampled.sampled);
+
+        assert!("00-00000000000000000000000000000000-b7ad6b7169203331-01"
+            .parse::<TraceParent>()
+            .is_err());
+        assert!("00-0AF7651916CD43DD8448EB211C80319C-b7ad6b7169203331-01"
+            .parse::<TraceParent>()
+            .is_err());
+        assert!("0af7651916cd43dd8448eb211c80319c"
+            .parse::<TraceParent>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_otlp_span() {
+        let mut span = SpanData::new("workflow step click", Some("b7ad6b7169203331".into()));
+        span.set("domguard.selector", "#buy");
+        span.end(Some("Element not found"));
+        let otlp = span.to_otlp("0af7651916cd43dd8448eb211c80319c");
+        assert_eq!(otlp["parentSpanId"], "b7ad6b7169203331");
+        assert_eq!(otlp["status"]["code"], 2);
+        assert_eq!(otlp["attributes"][1]["key"], "domguard.outcome");
+        assert_eq!(otlp["attributes"][1]["value"]["stringValue"], "error");
+        assert_eq!(span.span_id.len(), 16);
+        assert_eq!(random_hex(16).len(), 32);
+    }
+}
@@ -0,0 +1,228 @@
+//! Traffic captured by `debug xhr`, searched by `debug network grep`
+//!
+//! Every fetch/XHR call `debug xhr` sees is appended, masked like its output,
+//! to `.domguard/traffic.jsonl`. That lets an agent confirm afterwards that a
+//! backend call carried the data it submitted. Chrome can't hand out bodies
+//! of requests made before DevTools started listening, so only calls seen
+//! while `debug xhr` ran can be searched. The log keeps recent calls only:
+//! past [`MAX_LOG_BYTES`] its older half is dropped.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::cdp::XhrCall;
+use crate::config::Config;
+
+/// Size at which the log drops its older half
+pub const MAX_LOG_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Characters of context shown on each side of a match
+const SNIPPET_CONTEXT: usize = 40;
+
+/// A logged call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrafficEntry {
+    pub captured_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub call: XhrCall,
+}
+
+/// Where captured traffic is logged
+pub fn log_path() -> PathBuf {
+    Config::find_domguard_dir()
+        .unwrap_or_else(Config::domguard_dir)
+        .join("traffic.jsonl")
+}
+
+/// Append a call to the log
+pub fn append(path: &Path, call: &XhrCall) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::metadata(path).is_ok_and(|m| m.len() > MAX_LOG_BYTES) {
+        let content = std::fs::read_to_string(path)?;
+        let lines: Vec<&str> = content.lines().collect();
+        let kept = lines[lines.len() / 2..].join("\n");
+        crate::storage::write_atomic(path, &format!("{}\n", kept))?;
+    }
+    let entry = TrafficEntry {
+        captured_at: Utc::now(),
+        call: call.clone(),
+    };
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+    Ok(())
+}
+
+/// The logged calls, oldest first (none if nothing was captured yet)
+pub fn load(path: &Path) -> Result<Vec<TrafficEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(path)?;
+    // A line cut off by a crash is skipped rather than failing the search
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// A call with at least one match
+#[derive(Debug, Clone, Serialize)]
+pub struct TrafficMatch {
+    pub captured_at: DateTime<Utc>,
+    pub method: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<i64>,
+    pub matches: Vec<MatchLocation>,
+}
+
+/// Where in a call the pattern matched
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MatchLocation {
+    /// "url", "request headers", "request body", "response headers" or "response body"
+    pub part: String,
+    /// The match with some context around it
+    pub snippet: String,
+}
+
+/// Calls captured at or after `since` whose URL, headers or bodies match `pattern`
+pub fn grep(
+    entries: &[TrafficEntry],
+    pattern: &regex::Regex,
+    since: Option<DateTime<Utc>>,
+) -> Vec<TrafficMatch> {
+    entries
+        .iter()
+        .filter(|entry| since.is_none_or(|since| entry.captured_at >= since))
+        .filter_map(|entry| {
+            let call = &entry.call;
+            let parts = [
+                ("url", call.url.clone()),
+                ("request headers", header_text(&call.request_headers)),
+                ("request body", body_text(call.request_body.as_ref())),
+                ("response headers", header_text(&call.response_headers)),
+                ("response body", body_text(call.response_body.as_ref())),
+            ];
+            let matches: Vec<MatchLocation> = parts
+                .iter()
+                .filter_map(|(part, text)| {
+                    let found = pattern.find(text)?;
+                    Some(MatchLocation {
+                        part: (*part).to_string(),
+                        snippet: snippet(text, found.start(), found.end()),
+                    })
+                })
+                .collect();
+            (!matches.is_empty()).then(|| TrafficMatch {
+                captured_at: entry.captured_at,
+                method: call.method.clone(),
+                url: call.url.clone(),
+                status: call.status,
+                matches,
+            })
+        })
+        .collect()
+}
+
+/// "name: value" lines
+fn header_text(headers: &serde_json::Value) -> String {
+    headers
+        .as_object()
+        .map(|headers| {
+            headers
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value.as_str().unwrap_or_default()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default()
+}
+
+fn body_text(body: Option<&serde_json::Value>) -> String {
+    match body {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(body) => body.to_string(),
+        None => String::new(),
+    }
+}
+
+/// `text[start..end]` with up to `SNIPPET_CONTEXT` characters either side, on one line
+fn snippet(text: &str, start: usize, end: usize) -> String {
+    let from = text[..start]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(0, |(i, _)| i);
+    let to = text[end..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map_or(text.len(), |(i, _)| end + i);
+    let mut snippet = text[from..to].replace(['\n', '\r'], " ");
+    if from > 0 {
+        snippet.insert(0, '…');
+    }
+    if to < text.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_log_and_grep() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("traffic.jsonl");
+        assert!(load(&path).unwrap().is_empty());
+
+        let order = XhrCall {
+            kind: "fetch".to_string(),
+            method: "POST".to_string(),
+            url: "https://shop.test/api/orders".to_string(),
+            status: Some(201),
+            request_body: Some(json!({ "sku": "A-1", "quantity": 2 })),
+            response_headers: json!({ "x-order-id": "ord_123" }),
+            response_body: Some(json!({ "order_id": "ord_123" })),
+            ..XhrCall::default()
+        };
+        let search = XhrCall {
+            method: "GET".to_string(),
+            url: "https://shop.test/api/search?q=lamp".to_string(),
+            ..XhrCall::default()
+        };
+        append(&path, &order).unwrap();
+        append(&path, &search).unwrap();
+        let entries = load(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let found = grep(&entries, &regex::Regex::new("ord_123").unwrap(), None);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].url, "https://shop.test/api/orders");
+        let parts: Vec<&str> = found[0].matches.iter().map(|m| m.part.as_str()).collect();
+        assert_eq!(parts, vec!["response headers", "response body"]);
+        assert_eq!(found[0].matches[1].snippet, r#"{"order_id":"ord_123"}"#);
+
+        let later = Utc::now() + chrono::Duration::seconds(60);
+        assert!(grep(&entries, &regex::Regex::new("shop").unwrap(), Some(later)).is_empty());
+    }
+
+    #[test]
+    fn test_snippet() {
+        let text = format!("{}order_id=42{}", "a".repeat(50), "b\nc");
+        assert_eq!(
+            snippet(&text, 50, 61),
+            format!("…{}order_id=42b c", "a".repeat(40))
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! SSH tunnels to a remote Chrome
+//!
+//! `domguard tunnel` forwards a local port to Chrome's debugging port on
+//! another machine, so the remote Chrome is reached as localhost and its
+//! port never has to be exposed.
+
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// A port forward through `ssh -L`
+#[derive(Debug, Clone)]
+pub struct Tunnel {
+    ssh: PathBuf,
+    pub destination: String,
+    pub remote_port: u16,
+    pub local_port: u16,
+}
+
+impl Tunnel {
+    /// A tunnel from `localhost:local_port` to `127.0.0.1:remote_port` on `destination`
+    pub fn new(destination: &str, remote_port: u16, local_port: u16) -> Result<Self> {
+        Ok(Self {
+            ssh: which::which("ssh").map_err(|_| anyhow!("ssh not found in PATH"))?,
+            destination: destination.to_string(),
+            remote_port,
+            local_port,
+        })
+    }
+
+    /// Arguments for ssh: forward only, failing when the port can't be forwarded
+    pub fn ssh_args(&self) -> Vec<String> {
+        vec![
+            "-N".to_string(),
+            "-o".to_string(),
+            "ExitOnForwardFailure=yes".to_string(),
+            "-L".to_string(),
+            format!("{}:127.0.0.1:{}", self.local_port, self.remote_port),
+            self.destination.clone(),
+        ]
+    }
+
+    /// Keep the tunnel open until ssh exits
+    pub fn run(&self) -> Result<()> {
+        let status = std::process::Command::new(&self.ssh)
+            .args(self.ssh_args())
+            .status()?;
+        if !status.success() {
+            return Err(anyhow!("ssh exited with {}", status));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_args() {
+        let tunnel = Tunnel {
+            ssh: PathBuf::from("ssh"),
+            destination: "user@build-box".to_string(),
+            remote_port: 9222,
+            local_port: 9333,
+        };
+        assert_eq!(
+            tunnel.ssh_args(),
+            [
+                "-N",
+                "-o",
+                "ExitOnForwardFailure=yes",
+                "-L",
+                "9333:127.0.0.1:9222",
+                "user@build-box"
+            ]
+        );
+    }
+}
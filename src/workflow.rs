@@ -3,11 +3,14 @@
 //! Allows saving, editing, and replaying recorded sessions as reusable workflows.
 //! Workflows can include parameters, conditions, and loops.
 
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use chrono::{Datelike, Timelike};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Write as _;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::storage::{FsWorkflowStorage, WorkflowStorage};
 
 /// A reusable workflow (macro) definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,15 +73,136 @@ pub struct WorkflowParameter {
     #[serde(default)]
     pub required: bool,
 
-    /// Type hint: text, password, url, number, file
+    /// Type: string (or text), number, enum, secret (or password), file, url
     #[serde(default = "default_param_type")]
     pub param_type: String,
+
+    /// Allowed values for enum parameters
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+
+    /// Regex the whole value must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
 }
 
 fn default_param_type() -> String {
     "text".to_string()
 }
 
+impl WorkflowParameter {
+    /// Whether values of this parameter must be kept out of logs and exports
+    pub fn is_secret(&self) -> bool {
+        matches!(
+            self.param_type.to_lowercase().as_str(),
+            "secret" | "password"
+        )
+    }
+
+    /// Check a value against the parameter's type, choices and pattern
+    pub fn validate(&self, value: &str) -> Result<()> {
+        let name = &self.name;
+        match self.param_type.to_lowercase().as_str() {
+            "number" => {
+                value.trim().parse::<f64>().map_err(|_| {
+                    anyhow!("Parameter {} must be a number, got \"{}\"", name, value)
+                })?;
+            }
+            "enum" => {
+                if !self.choices.iter().any(|c| c == value) {
+                    return Err(anyhow!(
+                        "Parameter {} must be one of: {}",
+                        name,
+                        self.choices.join(", ")
+                    ));
+                }
+            }
+            "file" => {
+                if !Path::new(value).exists() {
+                    return Err(anyhow!("Parameter {}: file not found: {}", name, value));
+                }
+            }
+            "url" => {
+                if !value.contains("://") {
+                    return Err(anyhow!(
+                        "Parameter {} must be a URL, got \"{}\"",
+                        name,
+                        value
+                    ));
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(pattern) = &self.pattern {
+            let re = regex::Regex::new(&format!("^(?:{})$", pattern))
+                .with_context(|| format!("Invalid pattern for parameter {}", name))?;
+            if !re.is_match(value) {
+                // Don't echo secret values in the error
+                let shown = if self.is_secret() { "***" } else { value };
+                return Err(anyhow!(
+                    "Parameter {} value \"{}\" does not match {}",
+                    name,
+                    shown,
+                    pattern
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Fill in defaults, ask for missing required parameters and validate all values
+///
+/// `prompt` is called for each required parameter without a value or default;
+/// returning `None` means prompting isn't possible and the parameter is missing.
+pub fn resolve_params(
+    workflow: &Workflow,
+    mut params: HashMap<String, String>,
+    mut prompt: impl FnMut(&WorkflowParameter) -> Result<Option<String>>,
+) -> Result<HashMap<String, String>> {
+    for param in &workflow.parameters {
+        if params.contains_key(&param.name) {
+            continue;
+        }
+        if let Some(default) = &param.default {
+            params.insert(param.name.clone(), default.clone());
+        } else if param.required {
+            let value = prompt(param)?
+                .ok_or_else(|| anyhow!("Missing required parameter: {}", param.name))?;
+            params.insert(param.name.clone(), value);
+        }
+    }
+
+    for param in &workflow.parameters {
+        if let Some(value) = params.get(&param.name) {
+            param.validate(value)?;
+        }
+    }
+
+    Ok(params)
+}
+
+/// Values of secret parameters, for masking
+pub fn secret_values(workflow: &Workflow, params: &HashMap<String, String>) -> Vec<String> {
+    workflow
+        .parameters
+        .iter()
+        .filter(|p| p.is_secret())
+        .filter_map(|p| params.get(&p.name))
+        .filter(|v| !v.is_empty())
+        .cloned()
+        .collect()
+}
+
+/// Replace every secret value in `text` with `***`
+pub fn mask_secrets(text: &str, secrets: &[String]) -> String {
+    secrets.iter().fold(text.to_string(), |acc, secret| {
+        acc.replace(secret.as_str(), "***")
+    })
+}
+
 /// A single step in a workflow
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkflowStep {
@@ -174,6 +298,24 @@ pub struct WorkflowResult {
     pub screenshots: Vec<String>,
 }
 
+/// A `--dry-run --live` check that found steps whose selectors aren't on the page
+///
+/// The readiness report has been printed by then; `main` only sets the exit status.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepsNotReady {
+    /// 1-based step numbers
+    pub steps: Vec<usize>,
+}
+
+impl std::fmt::Display for StepsNotReady {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let steps: Vec<String> = self.steps.iter().map(ToString::to_string).collect();
+        write!(f, "Workflow steps not ready to run: {}", steps.join(", "))
+    }
+}
+
+impl std::error::Error for StepsNotReady {}
+
 /// Result of a single step
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StepResult {
@@ -203,45 +345,123 @@ pub struct StepResult {
     pub error: Option<String>,
 }
 
+/// Readiness of a step's selector on the live page (`workflow run --dry-run --live`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepReadiness {
+    /// Step index
+    pub index: usize,
+
+    /// Step action
+    pub action: String,
+
+    /// Selector that was checked
+    #[serde(default)]
+    pub target: Option<String>,
+
+    /// Number of elements matching the selector
+    pub count: u64,
+
+    /// Whether the first match is visible
+    pub visible: bool,
+
+    /// Whether the step looks ready to run
+    pub ready: bool,
+
+    /// Why the step isn't ready, or caveats about the check
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl StepReadiness {
+    /// Judge a step from its selector's match count and visibility
+    ///
+    /// `after_navigation` marks steps that follow a navigate step, which were
+    /// checked against the page as it is now rather than the one they'll run on.
+    pub fn from_probe(
+        index: usize,
+        action: &str,
+        target: &str,
+        count: u64,
+        visible: bool,
+        after_navigation: bool,
+    ) -> Self {
+        let (ready, note) = match count {
+            0 if action == "wait" => (
+                true,
+                Some("not on the page yet; the step waits for it".to_string()),
+            ),
+            0 => (false, Some("no element matches".to_string())),
+            _ if !visible && action != "wait" => {
+                (false, Some("first match is not visible".to_string()))
+            }
+            1 => (true, None),
+            n => (
+                true,
+                Some(format!("matches {} elements; the first is used", n)),
+            ),
+        };
+
+        let note = if after_navigation {
+            let caveat = "checked against the current page; an earlier step navigates";
+            Some(note.map_or_else(|| caveat.to_string(), |n| format!("{}; {}", n, caveat)))
+        } else {
+            note
+        };
+
+        Self {
+            index,
+            action: action.to_string(),
+            target: Some(target.to_string()),
+            count,
+            visible,
+            // A later page may well have the element, so don't fail those steps
+            ready: ready || after_navigation,
+            note,
+        }
+    }
+}
+
+/// Whether a step action's target is a CSS selector
+pub fn action_targets_selector(action: &str) -> bool {
+    matches!(action, "click" | "type" | "wait" | "scroll" | "hover")
+}
+
 /// Workflow manager for saving and loading workflows
 pub struct WorkflowManager {
-    workflows_dir: PathBuf,
+    storage: Box<dyn WorkflowStorage + Send + Sync>,
     cache: HashMap<String, Workflow>,
 }
 
 impl WorkflowManager {
-    /// Create a new manager
+    /// Create a new manager for the TOML files in `workflows_dir`
     pub fn new(workflows_dir: PathBuf) -> Self {
+        Self::with_storage(Box::new(FsWorkflowStorage::new(workflows_dir)))
+    }
+
+    /// Create a new manager over any workflow storage
+    pub fn with_storage(storage: Box<dyn WorkflowStorage + Send + Sync>) -> Self {
         Self {
-            workflows_dir,
+            storage,
             cache: HashMap::new(),
         }
     }
 
-    /// Load all workflows from disk
+    /// Load all workflows from storage
     pub fn load_all(&mut self) -> Result<()> {
-        self.cache.clear();
-
-        if !self.workflows_dir.exists() {
-            return Ok(());
-        }
-
-        for entry in std::fs::read_dir(&self.workflows_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.extension().is_some_and(|e| e == "toml") {
-                if let Ok(content) = std::fs::read_to_string(&path) {
-                    if let Ok(workflow) = toml::from_str::<Workflow>(&content) {
-                        self.cache.insert(workflow.id.clone(), workflow);
-                    }
-                }
-            }
-        }
-
+        self.cache = self
+            .storage
+            .load_all()?
+            .into_iter()
+            .map(|workflow| (workflow.id.clone(), workflow))
+            .collect();
         Ok(())
     }
 
+    /// The file a workflow is kept in, when its storage has one per workflow
+    pub fn file(&self, id: &str) -> Option<PathBuf> {
+        self.storage.file(id)
+    }
+
     /// Get a workflow by ID
     pub fn get(&self, id: &str) -> Option<&Workflow> {
         self.cache.get(id)
@@ -284,29 +504,17 @@ impl WorkflowManager {
             .collect()
     }
 
-    /// Save a workflow
+    /// Save a workflow, returning the file it was saved to
     pub fn save(&mut self, workflow: Workflow) -> Result<PathBuf> {
-        std::fs::create_dir_all(&self.workflows_dir)?;
-
-        let filename = format!("{}.toml", workflow.id);
-        let path = self.workflows_dir.join(filename);
-
-        let content = toml::to_string_pretty(&workflow)?;
-        std::fs::write(&path, content)?;
-
+        let path = self.storage.save(&workflow)?;
         self.cache.insert(workflow.id.clone(), workflow);
-
         Ok(path)
     }
 
     /// Delete a workflow
     pub fn delete(&mut self, id: &str) -> Result<bool> {
         if self.cache.remove(id).is_some() {
-            let filename = format!("{}.toml", id);
-            let path = self.workflows_dir.join(filename);
-            if path.exists() {
-                std::fs::remove_file(path)?;
-            }
+            self.storage.delete(id)?;
             Ok(true)
         } else {
             Ok(false)
@@ -396,15 +604,277 @@ impl WorkflowManager {
         if let Some(workflow) = self.cache.get_mut(id) {
             workflow.run_count += 1;
             workflow.last_run = Some(chrono::Utc::now());
+            self.storage.save(workflow)?;
+        }
+        Ok(())
+    }
+}
+
+/// A standing trigger that runs a workflow from `workflow daemon`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowSchedule {
+    /// Workflow to run
+    pub workflow_id: String,
+
+    /// Five-field cron expression (minute hour day month weekday), local time
+    #[serde(default)]
+    pub cron: Option<String>,
+
+    /// Page to poll for `when_text`
+    #[serde(default)]
+    pub watch_url: Option<String>,
+
+    /// Text whose appearance on `watch_url` triggers a run
+    #[serde(default)]
+    pub when_text: Option<String>,
+
+    /// Seconds between watch checks
+    #[serde(default = "default_watch_interval")]
+    pub interval_secs: u64,
+
+    /// Parameters passed to each run
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+
+    /// When the schedule was created
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+fn default_watch_interval() -> u64 {
+    300
+}
+
+/// Schedules stored in .domguard/schedules.toml
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleConfig {
+    #[serde(default)]
+    pub schedules: Vec<WorkflowSchedule>,
+}
 
-            // Save to disk
-            let filename = format!("{}.toml", id);
-            let path = self.workflows_dir.join(filename);
-            let content = toml::to_string_pretty(&workflow)?;
-            std::fs::write(&path, content)?;
+impl ScheduleConfig {
+    /// Load schedules from file
+    pub fn load(path: &Path) -> Result<Self> {
+        if path.exists() {
+            let content = std::fs::read_to_string(path)?;
+            Ok(toml::from_str(&content)?)
+        } else {
+            Ok(Self::default())
         }
+    }
+
+    /// Save schedules to file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = toml::to_string_pretty(self)?;
+        std::fs::write(path, content)?;
         Ok(())
     }
+
+    /// Add a schedule, replacing any existing one for the same workflow
+    pub fn upsert(&mut self, schedule: WorkflowSchedule) {
+        self.remove(&schedule.workflow_id);
+        self.schedules.push(schedule);
+    }
+
+    /// Remove the schedule for a workflow, returning whether one existed
+    pub fn remove(&mut self, workflow_id: &str) -> bool {
+        let before = self.schedules.len();
+        self.schedules.retain(|s| s.workflow_id != workflow_id);
+        self.schedules.len() != before
+    }
+}
+
+/// Parsed five-field cron expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: Vec<u32>,
+    hours: Vec<u32>,
+    days: Vec<u32>,
+    months: Vec<u32>,
+    weekdays: Vec<u32>,
+    /// Day-of-month and day-of-week were both restricted, so either may match
+    day_or_weekday: bool,
+}
+
+impl CronSchedule {
+    /// Parse "minute hour day-of-month month day-of-week"
+    /// Fields accept `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps (`*/10`)
+    pub fn parse(expr: &str) -> Result<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(anyhow!(
+                "Cron expression \"{}\" must have 5 fields (minute hour day month weekday)",
+                expr
+            ));
+        }
+
+        let mut weekdays = parse_cron_field(fields[4], 0, 7)?;
+        // 7 is an alias for Sunday
+        for day in &mut weekdays {
+            if *day == 7 {
+                *day = 0;
+            }
+        }
+
+        Ok(Self {
+            minutes: parse_cron_field(fields[0], 0, 59)?,
+            hours: parse_cron_field(fields[1], 0, 23)?,
+            days: parse_cron_field(fields[2], 1, 31)?,
+            months: parse_cron_field(fields[3], 1, 12)?,
+            weekdays,
+            day_or_weekday: fields[2] != "*" && fields[4] != "*",
+        })
+    }
+
+    /// Whether the schedule fires during the minute containing `time`
+    pub fn matches<Tz: chrono::TimeZone>(&self, time: &chrono::DateTime<Tz>) -> bool {
+        let day = self.days.contains(&time.day());
+        let weekday = self
+            .weekdays
+            .contains(&time.weekday().num_days_from_sunday());
+        let day_matches = if self.day_or_weekday {
+            day || weekday
+        } else {
+            day && weekday
+        };
+
+        self.minutes.contains(&time.minute())
+            && self.hours.contains(&time.hour())
+            && self.months.contains(&time.month())
+            && day_matches
+    }
+
+    /// Next minute after `time` when the schedule fires, searching up to a year ahead
+    pub fn next_after(
+        &self,
+        time: chrono::DateTime<chrono::Local>,
+    ) -> Option<chrono::DateTime<chrono::Local>> {
+        let mut candidate = time.with_second(0)?.with_nanosecond(0)?;
+        for _ in 0..(366 * 24 * 60) {
+            candidate += chrono::Duration::minutes(1);
+            if self.matches(&candidate) {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+}
+
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<Vec<u32>> {
+    let mut values = Vec::new();
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| anyhow!("Invalid cron step \"{}\"", step))?,
+            ),
+            None => (part, 1),
+        };
+
+        let parse = |v: &str| {
+            v.parse::<u32>()
+                .ok()
+                .filter(|n| (min..=max).contains(n))
+                .ok_or_else(|| anyhow!("Invalid cron value \"{}\" (expected {}-{})", v, min, max))
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse(a)?, parse(b)?)
+        } else {
+            let n = parse(range)?;
+            // "5/15" means every 15 starting at 5
+            (n, if step > 1 { max } else { n })
+        };
+        if start > end {
+            return Err(anyhow!("Invalid cron range \"{}\"", range));
+        }
+
+        values.extend((start..=end).step_by(step as usize));
+    }
+
+    values.sort_unstable();
+    values.dedup();
+    Ok(values)
+}
+
+/// Record of a scheduled or triggered workflow run, stored under .domguard/runs/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub workflow_id: String,
+
+    /// What started the run: cron, watch or manual
+    pub trigger: String,
+
+    pub started_at: chrono::DateTime<chrono::Utc>,
+
+    pub success: bool,
+
+    pub duration_ms: u64,
+
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl RunRecord {
+    /// Build a record from a finished run
+    pub fn from_result(
+        result: &WorkflowResult,
+        trigger: &str,
+        started_at: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        Self {
+            workflow_id: result.workflow_id.clone(),
+            trigger: trigger.to_string(),
+            started_at,
+            success: result.success,
+            duration_ms: result.duration_ms,
+            error: result
+                .error
+                .clone()
+                .or_else(|| result.step_results.iter().find_map(|r| r.error.clone())),
+        }
+    }
+
+    /// Write this record to the runs directory
+    pub fn save(&self, runs_dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(runs_dir)
+            .with_context(|| format!("Failed to create {}", runs_dir.display()))?;
+        let filename = format!(
+            "{}-{}.json",
+            self.started_at.format("%Y%m%dT%H%M%S%3f"),
+            self.workflow_id
+        );
+        let path = runs_dir.join(filename);
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    /// Load run records, newest first
+    pub fn load_all(runs_dir: &Path, limit: usize) -> Result<Vec<Self>> {
+        if !runs_dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut runs = Vec::new();
+        for entry in std::fs::read_dir(runs_dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                if let Ok(content) = std::fs::read_to_string(&path) {
+                    if let Ok(run) = serde_json::from_str::<RunRecord>(&content) {
+                        runs.push(run);
+                    }
+                }
+            }
+        }
+
+        runs.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        runs.truncate(limit);
+        Ok(runs)
+    }
 }
 
 /// Substitute parameters in a string
@@ -451,13 +921,18 @@ pub fn format_workflow(workflow: &Workflow) -> String {
         output.push_str("\n  Parameters:\n");
         for param in &workflow.parameters {
             let req = if param.required { "*" } else { "" };
+            let kind = if param.choices.is_empty() {
+                param.param_type.clone()
+            } else {
+                format!("{}: {}", param.param_type, param.choices.join("|"))
+            };
             let _ = writeln!(
                 output,
                 "    - {}{}: {} ({})",
                 param.name,
                 req,
                 param.description.as_deref().unwrap_or(""),
-                param.param_type
+                kind
             );
         }
     }
@@ -495,6 +970,12 @@ pub fn format_workflow_list(workflows: &[&Workflow]) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_steps_not_ready_message() {
+        let error = StepsNotReady { steps: vec![2, 5] };
+        assert_eq!(error.to_string(), "Workflow steps not ready to run: 2, 5");
+    }
+
     #[test]
     fn test_substitute_params() {
         let mut params = HashMap::new();
@@ -506,6 +987,95 @@ mod tests {
         assert_eq!(result, "Hello john, your password is secret");
     }
 
+    #[test]
+    fn test_parameter_validation() {
+        let mut param = WorkflowParameter {
+            name: "count".to_string(),
+            description: None,
+            default: None,
+            required: true,
+            param_type: "number".to_string(),
+            choices: vec![],
+            pattern: None,
+        };
+        assert!(param.validate("42").is_ok());
+        assert!(param.validate("many").is_err());
+
+        param.param_type = "enum".to_string();
+        param.choices = vec!["small".to_string(), "large".to_string()];
+        assert!(param.validate("small").is_ok());
+        assert!(param.validate("medium").is_err());
+
+        param.param_type = "secret".to_string();
+        param.pattern = Some("[0-9]{4}".to_string());
+        assert!(param.validate("1234").is_ok());
+        let err = param.validate("12345").unwrap_err().to_string();
+        assert!(!err.contains("12345"));
+    }
+
+    #[test]
+    fn test_resolve_params_and_masking() {
+        let mut workflow = WorkflowManager::create_empty("Login");
+        workflow.parameters = vec![
+            WorkflowParameter {
+                name: "user".to_string(),
+                description: None,
+                default: Some("admin".to_string()),
+                required: true,
+                param_type: "text".to_string(),
+                choices: vec![],
+                pattern: None,
+            },
+            WorkflowParameter {
+                name: "password".to_string(),
+                description: None,
+                default: None,
+                required: true,
+                param_type: "secret".to_string(),
+                choices: vec![],
+                pattern: None,
+            },
+        ];
+
+        let missing = resolve_params(&workflow, HashMap::new(), |_| Ok(None));
+        assert!(missing.is_err());
+
+        let params = resolve_params(&workflow, HashMap::new(), |_| {
+            Ok(Some("hunter2".to_string()))
+        })
+        .unwrap();
+        assert_eq!(params.get("user").map(String::as_str), Some("admin"));
+
+        let secrets = secret_values(&workflow, &params);
+        assert_eq!(
+            mask_secrets("typed hunter2 as admin", &secrets),
+            "typed *** as admin"
+        );
+    }
+
+    #[test]
+    fn test_step_readiness() {
+        let missing = StepReadiness::from_probe(0, "click", "#go", 0, false, false);
+        assert!(!missing.ready);
+
+        let hidden = StepReadiness::from_probe(1, "click", "#go", 1, false, false);
+        assert!(!hidden.ready);
+
+        let many = StepReadiness::from_probe(2, "type", "input", 3, true, false);
+        assert!(many.ready);
+        assert!(many.note.unwrap().contains("3 elements"));
+
+        let pending = StepReadiness::from_probe(3, "wait", "#later", 0, false, false);
+        assert!(pending.ready);
+
+        let later_page = StepReadiness::from_probe(4, "click", "#next", 0, false, true);
+        assert!(later_page.ready);
+        assert!(later_page.note.unwrap().contains("navigates"));
+
+        assert!(action_targets_selector("hover"));
+        assert!(!action_targets_selector("navigate"));
+    }
+
     #[test]
     fn test_create_empty_workflow() {
         let workflow = WorkflowManager::create_empty("Test Workflow");
@@ -520,4 +1090,75 @@ mod tests {
         let parsed: Workflow = toml::from_str(&toml).unwrap();
         assert_eq!(parsed.name, "Test");
     }
+
+    #[test]
+    fn test_parse_cron() {
+        use chrono::TimeZone;
+
+        let cron = CronSchedule::parse("0 9 * * 1-5").unwrap();
+        // Monday 2024-01-08 09:00
+        let monday = chrono::Utc.with_ymd_and_hms(2024, 1, 8, 9, 0, 0).unwrap();
+        assert!(cron.matches(&monday));
+        assert!(!cron.matches(&(monday + chrono::Duration::minutes(1))));
+        // Sunday 2024-01-07 09:00
+        assert!(!cron.matches(&(monday - chrono::Duration::days(1))));
+
+        let every = CronSchedule::parse("*/15 * * * *").unwrap();
+        assert_eq!(every.minutes, vec![0, 15, 30, 45]);
+
+        let sunday = CronSchedule::parse("0 0 * * 7").unwrap();
+        assert_eq!(sunday.weekdays, vec![0]);
+
+        assert!(CronSchedule::parse("0 9 * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        use chrono::TimeZone;
+
+        let cron = CronSchedule::parse("30 * * * *").unwrap();
+        let now = chrono::Local
+            .with_ymd_and_hms(2024, 1, 8, 9, 45, 10)
+            .unwrap();
+        let next = cron.next_after(now).unwrap();
+        assert_eq!((next.hour(), next.minute()), (10, 30));
+    }
+
+    #[test]
+    fn test_schedule_upsert_and_runs() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let schedule = WorkflowSchedule {
+            workflow_id: "workflow-1".to_string(),
+            cron: Some("0 9 * * *".to_string()),
+            watch_url: None,
+            when_text: None,
+            interval_secs: default_watch_interval(),
+            params: HashMap::new(),
+            created_at: chrono::Utc::now(),
+        };
+        let mut config = ScheduleConfig::default();
+        config.upsert(schedule.clone());
+        config.upsert(schedule);
+        assert_eq!(config.schedules.len(), 1);
+        assert!(config.remove("workflow-1"));
+        assert!(!config.remove("workflow-1"));
+
+        let runs_dir = dir.path().join("runs");
+        RunRecord {
+            workflow_id: "workflow-1".to_string(),
+            trigger: "cron".to_string(),
+            started_at: chrono::Utc::now(),
+            success: true,
+            duration_ms: 12,
+            error: None,
+        }
+        .save(&runs_dir)
+        .unwrap();
+        let runs = RunRecord::load_all(&runs_dir, 10).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].trigger, "cron");
+    }
 }
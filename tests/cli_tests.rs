@@ -171,3 +171,859 @@ fn test_inspire_requires_url() {
         .failure()
         .stderr(predicate::str::contains("URL"));
 }
+
+#[test]
+fn test_debug_count_requires_selector() {
+    domguard()
+        .args(["debug", "count"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SELECTOR"));
+}
+
+#[test]
+fn test_interact_back_rejects_zero_steps() {
+    domguard()
+        .args(["interact", "back", "--steps", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--steps"));
+}
+
+#[test]
+fn test_secrets_list_empty() {
+    let dir = tempfile::tempdir().unwrap();
+    domguard()
+        .current_dir(dir.path())
+        .args(["--json", "secrets", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"secrets\": []"));
+}
+
+#[test]
+fn test_workflow_run_live_requires_dry_run() {
+    domguard()
+        .args(["workflow", "run", "login-flow", "--live"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--dry-run"));
+}
+
+#[test]
+fn test_interact_confirm_sensitive_is_enforced() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".domguard")).unwrap();
+    std::fs::write(
+        dir.path().join(".domguard/config.toml"),
+        "[security]\non_sensitive = \"confirm\"\n",
+    )
+    .unwrap();
+
+    // Refused before connecting, so no browser is needed
+    domguard()
+        .current_dir(dir.path())
+        .args([
+            "--port",
+            "1",
+            "interact",
+            "navigate",
+            "https://www.paypal.com/",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "re-run with --confirm-sensitive to proceed",
+        ));
+
+    // Confirmed, it gets as far as connecting (to nothing on port 1)
+    domguard()
+        .current_dir(dir.path())
+        .args([
+            "--port",
+            "1",
+            "interact",
+            "navigate",
+            "https://www.paypal.com/",
+            "--confirm-sensitive",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Sensitive action").not());
+}
+
+#[test]
+fn test_navigate_blocked_site_is_refused_and_audited() {
+    let dir = tempfile::tempdir().unwrap();
+    let domguard_dir = dir.path().join(".domguard");
+    std::fs::create_dir_all(&domguard_dir).unwrap();
+    std::fs::write(
+        domguard_dir.join("blocked_sites.toml"),
+        "blocked = [\"malicious-site.com\"]\n",
+    )
+    .unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .args([
+            "--json",
+            "interact",
+            "navigate",
+            "https://malicious-site.com/login",
+        ])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("blocked_by_policy"));
+
+    domguard()
+        .current_dir(dir.path())
+        .args([
+            "interact",
+            "navigate",
+            "https://malicious-site.com/login",
+            "--override-block",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("allow_block_override"));
+
+    let log = std::fs::read_to_string(domguard_dir.join("audit.log")).unwrap();
+    assert_eq!(log.lines().count(), 2);
+}
+
+#[test]
+fn test_firefox_backend_rejects_unsupported_commands() {
+    domguard()
+        .args(["--browser", "safari", "status"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected chrome or firefox"));
+
+    // Checked before connecting, so no Firefox is needed
+    domguard()
+        .args(["--browser", "firefox", "interact", "hover", "#menu"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Not supported by the firefox backend",
+        ));
+}
+
+#[test]
+fn test_json_error_has_code() {
+    domguard()
+        .args(["--json", "interact", "click"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("\"code\": \"INVALID_ARGUMENT\""))
+        .stdout(predicate::str::contains("\"retryable\": false"));
+}
+
+#[test]
+fn test_output_file_receives_json_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out.json");
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["--quiet", "--output-file", "out.json", "interact", "click"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_empty());
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.contains("INVALID_ARGUMENT"));
+}
+
+#[test]
+fn test_yaml_and_toml_formats() {
+    domguard()
+        .args(["--format", "yaml", "interact", "click"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("code: INVALID_ARGUMENT"))
+        .stdout(predicate::str::contains("success: false"));
+
+    domguard()
+        .args(["--format", "toml", "interact", "click"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("code = \"INVALID_ARGUMENT\""));
+}
+
+#[test]
+fn test_doctor_reports_broken_config() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".domguard")).unwrap();
+    std::fs::write(dir.path().join(".domguard/config.toml"), "[chrome\nport = ").unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["--json", "--port", "1", "doctor"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("\"healthy\": false"))
+        .stdout(predicate::str::contains("\"debugging_port\""))
+        .stdout(predicate::str::contains("\"fix\""));
+}
+
+#[test]
+fn test_unknown_profile() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".domguard")).unwrap();
+    std::fs::write(
+        dir.path().join(".domguard/config.toml"),
+        "[profiles.staging.chrome]\nport = 9333\n",
+    )
+    .unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["--profile", "prod", "status"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown profile \"prod\" (defined: staging)",
+        ));
+}
+
+#[test]
+fn test_config_get_set() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".domguard")).unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["config", "set", "defaults.timeout_ms", "10000"])
+        .assert()
+        .success();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["config", "get", "defaults.timeout_ms"])
+        .assert()
+        .success()
+        .stdout("10000\n");
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["config", "set", "defaults.timeout_ms", "soon"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expects an integer"));
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["config", "set", "chrome.prot", "9333"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown config key"));
+}
+
+#[test]
+fn test_config_follow_popups() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".domguard")).unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["config", "set", "defaults.follow_popups", "yes"])
+        .assert()
+        .success();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["config", "get", "defaults.follow_popups"])
+        .assert()
+        .success()
+        .stdout("true\n");
+}
+
+#[test]
+fn test_config_auto_dismiss_consent() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".domguard")).unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["config", "set", "defaults.auto_dismiss_consent", "true"])
+        .assert()
+        .success();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["config", "get", "defaults.auto_dismiss_consent"])
+        .assert()
+        .success()
+        .stdout("true\n");
+}
+
+#[test]
+fn test_inspire_export_requires_component() {
+    domguard()
+        .args(["inspire", "https://example.com", "--export", "bundle"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--component"));
+}
+
+#[test]
+fn test_inspire_library() {
+    let dir = tempfile::tempdir().unwrap();
+    let saved = dir.path().join(".domguard/inspirations/demo");
+    std::fs::create_dir_all(&saved).unwrap();
+    std::fs::write(
+        saved.join("design.json"),
+        r#"{"url": "https://example.com", "colors": [], "typography": []}"#,
+    )
+    .unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["--json", "inspire", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"name\": \"demo\""));
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["inspire", "show", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No saved inspiration \"nope\""));
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["inspire", "delete", "demo"])
+        .assert()
+        .success();
+    assert!(!saved.exists());
+}
+
+#[test]
+fn test_unknown_format_rejected() {
+    domguard()
+        .args(["--format", "xml", "status"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown format \"xml\""));
+
+    domguard()
+        .args(["interact", "screenshot", "--quality", "101"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--quality"));
+}
+
+#[test]
+fn test_pdf_options_validated() {
+    domguard()
+        .args(["interact", "pdf", "--paper", "b5"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown paper \"b5\""));
+
+    domguard()
+        .args(["interact", "pdf", "--scale", "3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("between 0.1 and 2"));
+}
+
+#[test]
+fn test_console_duration_requires_follow() {
+    domguard()
+        .args(["debug", "console", "--duration", "1000"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow"));
+}
+
+#[test]
+fn test_exception_capture_requires_pause() {
+    domguard()
+        .args(["debug", "exceptions", "--capture"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--pause"));
+}
+
+#[test]
+fn test_eval_requires_script() {
+    domguard()
+        .args(["debug", "eval"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("<EXPRESSION>"));
+
+    domguard()
+        .args(["debug", "eval", "1", "--world", "worker"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown world"));
+
+    domguard()
+        .args(["debug", "eval", "1", "--return-by-ref", "--await"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_find_requires_criteria() {
+    domguard()
+        .args(["debug", "find", "--visible-only"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--text"));
+}
+
+#[test]
+fn test_dom_diff_requires_baseline() {
+    domguard()
+        .args(["debug", "dom-diff"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--before"));
+}
+
+#[test]
+fn test_css_set_requires_declarations() {
+    domguard()
+        .args(["debug", "css", "set", "h1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("<DECLARATIONS>"));
+}
+
+#[test]
+fn test_inject_list_and_remove() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".domguard")).unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["debug", "inject", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No injected scripts"));
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["debug", "inject", "remove", "3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No injected script #3"));
+}
+
+#[test]
+fn test_animations_mode_validated() {
+    domguard()
+        .args(["debug", "animations"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--disable"));
+
+    domguard()
+        .args(["debug", "animations", "--slow", "fast"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid slowdown"));
+}
+
+#[test]
+fn test_tabs_switch_requires_target() {
+    domguard()
+        .args(["debug", "tabs", "switch"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--url-contains"));
+}
+
+#[test]
+fn test_contexts_dispose_requires_target() {
+    domguard()
+        .args(["debug", "contexts", "dispose"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--all"));
+}
+
+#[test]
+fn test_wait_url_conditions_validated() {
+    domguard()
+        .args(["interact", "wait", "--url-matches", "orders/("])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--url-matches"));
+
+    domguard()
+        .args(["interact", "wait", "#app", "--title-contains", "Home"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_click_follow_ups_conflict() {
+    domguard()
+        .args([
+            "interact",
+            "click",
+            "#save",
+            "--then-wait",
+            ".toast",
+            "--then-network-idle",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_hover_hold_options() {
+    domguard()
+        .args(["interact", "hover", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--capture <PATH>"))
+        .stdout(predicate::str::contains("--then-click <SELECTOR>"));
+}
+
+#[test]
+fn test_wait_spa_navigation_conflicts() {
+    domguard()
+        .args(["interact", "wait", "#app", "--spa-navigation"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_type_framework_hint() {
+    domguard()
+        .args(["interact", "type", "#q", "hi", "--framework", "svelte"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown framework"));
+
+    domguard()
+        .args([
+            "interact",
+            "type",
+            "#q",
+            "hi",
+            "--framework",
+            "vue",
+            "--keystrokes",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_table_click_cell_needs_row() {
+    domguard()
+        .args(["interact", "table", "#users", "--click-cell", "3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--find-row <TEXT>"));
+}
+
+#[test]
+fn test_date_value_is_validated() {
+    domguard()
+        .args(["interact", "date", "#checkin", "--value", "2025-13-40"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected YYYY-MM-DD"));
+}
+
+#[test]
+fn test_combobox_needs_choice() {
+    domguard()
+        .args(["interact", "combobox", "#country"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--choose <TEXT>"));
+}
+
+#[test]
+fn test_gesture_path_needs_two_points() {
+    domguard()
+        .args(["interact", "gesture", "--path", "100,100"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("at least two points"));
+}
+
+#[test]
+fn test_swipe_needs_coordinates() {
+    domguard()
+        .args(["interact", "swipe", "--from", "300,600"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--to <TO>"));
+}
+
+#[test]
+fn test_overlays_coverage_is_a_percentage() {
+    domguard()
+        .args(["debug", "overlays", "--min-coverage", "150"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("1..=100"));
+}
+
+#[test]
+fn test_migrate_storage_indexes_sessions() {
+    let dir = tempfile::tempdir().unwrap();
+    let sessions = dir.path().join(".domguard/sessions");
+    std::fs::create_dir_all(&sessions).unwrap();
+    std::fs::write(
+        sessions.join("session_abc.json"),
+        r#"{"id":"abc","started_at":"2026-01-01T00:00:00Z","actions":[],"status":"completed"}"#,
+    )
+    .unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["--json", "migrate-storage"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"sessions_indexed\": 1"));
+    assert!(sessions.join("_index.json").exists());
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["--json", "session", "list", "--status", "failed"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[]"));
+}
+
+#[test]
+fn test_session_id_must_be_a_plain_name() {
+    let dir = tempfile::tempdir().unwrap();
+    domguard()
+        .current_dir(dir.path())
+        .args(["session", "status", "--session", "../other"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid session ID"));
+}
+
+#[test]
+fn test_unknown_coordinate_space() {
+    domguard()
+        .args(["interact", "mouse-move", "10,10", "--space", "screen"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("element:<selector>"));
+}
+
+#[test]
+fn test_tab_walk_max_is_numeric() {
+    domguard()
+        .args(["interact", "tab-walk", "--max", "all"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'all'"));
+}
+
+#[test]
+fn test_auth_needs_saved_state() {
+    let dir = tempfile::tempdir().unwrap();
+    domguard()
+        .current_dir(dir.path())
+        .args(["auth", "restore", "shop.example"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No saved login for shop.example"));
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["auth", "login", "shop.example"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No [login] section"));
+}
+
+#[test]
+fn test_wait_state_requires_selector() {
+    domguard()
+        .args(["interact", "wait", "--enabled"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("<SELECTOR>"));
+
+    domguard()
+        .args(["interact", "wait", "#save", "--attr", "aria-busy"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("key=value"));
+}
+
+#[test]
+fn test_retry_validates_command() {
+    domguard()
+        .args(["retry", "--", "interact", "frobnicate"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid command to retry"));
+
+    domguard()
+        .args(["retry", "--", "retry", "--", "status"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("can't run another retry"));
+
+    domguard()
+        .args(["retry", "--backoff", "0.5x", "--", "status"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Backoff must be"));
+}
+
+#[test]
+fn test_stitch_requires_full() {
+    domguard()
+        .args(["interact", "screenshot", "--stitch"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--full"));
+}
+
+#[test]
+fn test_inspire_responsive_requires_save() {
+    domguard()
+        .args(["inspire", "https://example.com", "--responsive"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--save"));
+}
+
+#[test]
+fn test_events_subscribe_and_list() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".domguard")).unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .args([
+            "events",
+            "subscribe",
+            "--url",
+            "http://localhost:8000/hook",
+            "--on",
+            "navigation,console-error",
+        ])
+        .assert()
+        .success();
+    assert!(dir.path().join(".domguard/webhooks.toml").exists());
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["--json", "events", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"console-error\""));
+
+    domguard()
+        .current_dir(dir.path())
+        .args([
+            "events",
+            "subscribe",
+            "--url",
+            "http://localhost:8000/hook",
+            "--on",
+            "popup",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown event"));
+}
+
+#[test]
+fn test_recover_without_interrupted_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".domguard")).unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .arg("recover")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No interrupted commands"));
+}
+
+#[test]
+fn test_throttle_status_without_throttling() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".domguard")).unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["--json", "debug", "throttle", "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"active\": false"));
+}
+
+#[test]
+fn test_network_grep_searches_captured_traffic() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join(".domguard")).unwrap();
+
+    domguard()
+        .current_dir(dir.path())
+        .args(["debug", "network", "grep", "ord_123"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No captured traffic"));
+
+    std::fs::write(
+        dir.path().join(".domguard/traffic.jsonl"),
+        concat!(
+            r#"{"captured_at":"2026-01-01T00:00:00Z","kind":"fetch","method":"POST","#,
+            r#""url":"https://shop.test/api/orders","response_body":{"order_id":"ord_123"},"#,
+            r#""duration_ms":12}"#,
+            "\n"
+        ),
+    )
+    .unwrap();
+    domguard()
+        .current_dir(dir.path())
+        .args(["--json", "debug", "network", "grep", "ord_\\d+", "--regex"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"part\": \"response body\""));
+}
+
+#[test]
+fn test_perf_assert_rejects_invalid_size() {
+    domguard()
+        .args(["debug", "perf-assert", "--transfer-max", "1.5 parsecs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid size"));
+}
+
+#[test]
+fn test_heap_requires_snapshot_or_compare() {
+    domguard()
+        .args(["debug", "heap"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--snapshot"));
+}
+
+#[test]
+fn test_fps_rejects_unterminated_quote() {
+    domguard()
+        .args(["debug", "fps", "--during", "interact click \"#play"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unterminated quote"));
+}
@@ -0,0 +1,35 @@
+//! Library API tests for DOMGuard
+//!
+//! Uses the crate directly, the way tools embedding DOMGuard do
+
+use domguard::interact::CoordSpace;
+use domguard::session::{ActionBuilder, SessionStatus};
+use domguard::storage::SessionFilter;
+use domguard::SessionRecorder;
+
+#[test]
+fn test_record_session_through_the_api() {
+    let dir = tempfile::tempdir().unwrap();
+    let recorder = SessionRecorder::new(dir.path().to_path_buf());
+
+    let id = recorder
+        .start(Some("embedded".to_string()), None, false)
+        .unwrap();
+    recorder
+        .record_action(ActionBuilder::new("click").success())
+        .unwrap();
+    let session = recorder.stop().unwrap().unwrap();
+    assert_eq!(session.id, id);
+    assert_eq!(session.status, SessionStatus::Completed);
+
+    let listed = recorder.list_sessions(&SessionFilter::default()).unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].total_actions, 1);
+}
+
+#[test]
+fn test_parse_interact_types() {
+    let space: CoordSpace = "element:#map".parse().unwrap();
+    assert_eq!(space.to_string(), "element:#map");
+    assert!("sideways".parse::<CoordSpace>().is_err());
+}